@@ -0,0 +1,101 @@
+//! Auto-source - source nodes for new Prism clients
+//!
+//! Prism のクライアント（アプリ）が新しいチャンネルペアに現れたら、設定
+//! （`prism_auto_source`: auto / ask / off）に従ってそのペアの SourceNode を作るか、
+//! フロントエンドに通知する。
+//!
+//! 起動後に最初に見えたクライアントは既存とみなして対象にしない
+//! （復元したグラフや、ユーザーが削除したソースを勝手に作り直さないため）。
+//! ペア 0（MAIN）は割り当て前のアプリが集まる場所なので対象外。
+
+use super::dto::{GraphEventDto, PrismAppDetectedDto};
+use super::events::emit_graph_event;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::source::{SourceId, SourceNode};
+use crate::prismd::{ClientInfo, ProcessInfo};
+use crate::settings::AutoSourcePolicy;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+
+/// (pid, channel offset) seen in the last client list; None until the first list
+static SEEN: Mutex<Option<HashSet<(i32, u32)>>> = Mutex::new(None);
+
+/// Handle a new client list from the prismd supervisor
+///
+/// Returns the apps that need a source node (with the created handle for "auto").
+pub fn on_clients(clients: &[ClientInfo]) -> Vec<PrismAppDetectedDto> {
+    let current: HashSet<(i32, u32)> = clients.iter().map(|c| (c.pid, c.channel_offset)).collect();
+    let new_clients: Vec<&ClientInfo> = {
+        let mut seen = SEEN.lock();
+        let Some(previous) = seen.as_ref() else {
+            *seen = Some(current);
+            return Vec::new();
+        };
+        let new_clients = clients
+            .iter()
+            .filter(|c| !previous.contains(&(c.pid, c.channel_offset)))
+            .collect();
+        *seen = Some(current);
+        new_clients
+    };
+
+    let policy = crate::settings::prism_auto_source();
+    if policy == AutoSourcePolicy::Off {
+        return Vec::new();
+    }
+
+    let mut pairs: HashSet<u8> = HashSet::new();
+    let mut detected = Vec::new();
+    for client in new_clients {
+        let Ok(pair) = u8::try_from(client.channel_offset / 2) else {
+            continue;
+        };
+        // 同じアプリの複数プロセスが同じペアに来ることがあるので 1 ペア 1 回
+        if pair == 0 || !pairs.insert(pair) || has_prism_source(pair) {
+            continue;
+        }
+
+        let info = ProcessInfo::from(client.clone());
+        let handle = match policy {
+            AutoSourcePolicy::Auto => Some(create_source(pair, &info.name)),
+            AutoSourcePolicy::Ask | AutoSourcePolicy::Off => None,
+        };
+        log_info!(
+            "[AutoSource] {} on Prism pair {} ({})",
+            info.name,
+            pair,
+            if handle.is_some() {
+                "source created"
+            } else {
+                "offered"
+            }
+        );
+        detected.push(PrismAppDetectedDto {
+            pid: info.pid,
+            name: info.name,
+            channel: pair,
+            handle,
+        });
+    }
+    detected
+}
+
+fn has_prism_source(pair: u8) -> bool {
+    get_graph_processor().with_graph(|graph| {
+        graph.node_handles().any(|handle| {
+            graph
+                .get_node(handle)
+                .and_then(|n| n.as_any().downcast_ref::<SourceNode>())
+                .is_some_and(|s| {
+                    matches!(s.source_id(), SourceId::PrismChannel { channel } if *channel == pair)
+                })
+        })
+    })
+}
+
+fn create_source(pair: u8, app_name: &str) -> u32 {
+    let node = SourceNode::new_prism(pair, app_name);
+    let handle = get_graph_processor().add_node(Box::new(node)).raw();
+    emit_graph_event(GraphEventDto::NodeAdded { handle });
+    handle
+}
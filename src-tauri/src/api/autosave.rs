@@ -0,0 +1,207 @@
+//! Autosave - debounced background saves and crash recovery
+//!
+//! graph_state.json は終了時にしか書かれないので、クラッシュすると変更が失われる。
+//! グラフ変更イベントごとに dirty を立て、最後の変更から一定時間たったら
+//! `autosave.json` に保存する（連続した変更中も最大待ち時間で保存、最小間隔で頻度を制限）。
+//!
+//! 正常終了時（`persist_state`）に autosave.json は削除される。起動時に残っていて
+//! graph_state.json より新しければ、前回はクラッシュしたとみなして
+//! `autosave-recovery.json` に退避し、フロントエンドに復元を提案させる。
+
+use super::dto::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::Manager;
+
+/// Quiet time after the last change before saving
+const DEBOUNCE_MS: u64 = 5_000;
+
+/// Save even while changes keep coming once the oldest unsaved change is this old
+const MAX_DELAY_MS: u64 = 60_000;
+
+/// Minimum time between two autosaves
+const MIN_INTERVAL_MS: u64 = 15_000;
+
+/// Scheduler poll interval
+const POLL_MS: u64 = 500;
+
+const AUTOSAVE_FILE: &str = "autosave.json";
+const RECOVERY_FILE: &str = "autosave-recovery.json";
+
+/// Time of the first unsaved change (unix ms, 0 = clean)
+static DIRTY_SINCE_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Time of the last change (unix ms)
+static LAST_CHANGE_MS: AtomicU64 = AtomicU64::new(0);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn data_file(name: &str) -> Result<PathBuf, String> {
+    Ok(super::persistence::app_data_dir()?.join(name))
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+}
+
+/// Set aside a crash autosave and start the scheduler (call once from `setup`)
+pub fn init() {
+    match stash_crash_autosave() {
+        Ok(true) => log_info!("[Autosave] Found unsaved changes from the previous session"),
+        Ok(false) => {}
+        Err(e) => log_error!("[Autosave] Recovery check failed: {}", e),
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-autosave".to_string())
+        .spawn(autosave_thread)
+    {
+        log_error!("[Autosave] Failed to start scheduler: {}", e);
+    }
+}
+
+/// Record a graph change (called for every graph event)
+pub fn mark_dirty() {
+    let now = now_ms();
+    LAST_CHANGE_MS.store(now, Ordering::Relaxed);
+    let _ = DIRTY_SINCE_MS.compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed);
+}
+
+/// The state was saved explicitly; drop the autosave
+pub fn clear() {
+    DIRTY_SINCE_MS.store(0, Ordering::Relaxed);
+    if let Ok(path) = data_file(AUTOSAVE_FILE) {
+        if path.exists() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                log_error!("[Autosave] Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn autosave_thread() {
+    let mut last_save_ms: u64 = 0;
+
+    loop {
+        std::thread::sleep(Duration::from_millis(POLL_MS));
+
+        let dirty_since = DIRTY_SINCE_MS.load(Ordering::Relaxed);
+        if dirty_since == 0 {
+            continue;
+        }
+        let now = now_ms();
+        let quiet = now.saturating_sub(LAST_CHANGE_MS.load(Ordering::Relaxed)) >= DEBOUNCE_MS;
+        let overdue = now.saturating_sub(dirty_since) >= MAX_DELAY_MS;
+        let rate_ok = now.saturating_sub(last_save_ms) >= MIN_INTERVAL_MS;
+        if !(quiet || overdue) || !rate_ok {
+            continue;
+        }
+
+        // 保存中の変更は次回に回す
+        DIRTY_SINCE_MS.store(0, Ordering::Relaxed);
+        last_save_ms = now;
+        if let Err(e) = save_now() {
+            log_error!("[Autosave] Failed: {}", e);
+            let _ = DIRTY_SINCE_MS.compare_exchange(
+                0,
+                dirty_since,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+fn save_now() -> Result<(), String> {
+    let ui_state = super::events::app_handle().and_then(|app| {
+        app.state::<crate::UiStateCache>()
+            .0
+            .lock()
+            .ok()
+            .and_then(|guard| guard.clone())
+    });
+    let state = tauri::async_runtime::block_on(super::save_graph_state(ui_state))?;
+
+    let path = data_file(AUTOSAVE_FILE)?;
+    let json = serde_json::to_string_pretty(&state)
+        .map_err(|e| format!("Failed to serialize state: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write autosave: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write autosave: {}", e))?;
+    log_info!(
+        "[Autosave] Saved (nodes={} edges={})",
+        state.nodes.len(),
+        state.edges.len()
+    );
+    Ok(())
+}
+
+/// Move a leftover autosave that is newer than graph_state.json aside
+fn stash_crash_autosave() -> Result<bool, String> {
+    let autosave = data_file(AUTOSAVE_FILE)?;
+    let Some(autosaved_at) = modified_ms(&autosave) else {
+        return Ok(false);
+    };
+    let saved_at = modified_ms(&super::persistence::state_file()?);
+    if saved_at.is_some_and(|t| t >= autosaved_at) {
+        let _ = std::fs::remove_file(&autosave);
+        return Ok(false);
+    }
+    std::fs::rename(&autosave, data_file(RECOVERY_FILE)?)
+        .map_err(|e| format!("Failed to keep autosave: {}", e))?;
+    Ok(true)
+}
+
+// =============================================================================
+// Recovery
+// =============================================================================
+
+/// Autosave left by a crashed session, if any
+pub fn pending_recovery() -> Result<Option<AutosaveRecoveryDto>, String> {
+    let path = data_file(RECOVERY_FILE)?;
+    let Some(autosaved_at_ms) = modified_ms(&path) else {
+        return Ok(None);
+    };
+    let state = read_recovery_state(&path)?;
+    Ok(Some(AutosaveRecoveryDto {
+        autosaved_at_ms,
+        last_saved_at_ms: modified_ms(&super::persistence::state_file()?),
+        node_count: state.nodes.len(),
+        edge_count: state.edges.len(),
+    }))
+}
+
+/// Take the recovered state (removes it from disk)
+pub fn take_recovery() -> Result<Option<GraphStateDto>, String> {
+    let path = data_file(RECOVERY_FILE)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let state = read_recovery_state(&path)?;
+    discard_recovery()?;
+    Ok(Some(state))
+}
+
+pub fn discard_recovery() -> Result<(), String> {
+    let path = data_file(RECOVERY_FILE)?;
+    if path.exists() {
+        std::fs::remove_file(&path).map_err(|e| format!("Failed to remove autosave: {}", e))?;
+    }
+    Ok(())
+}
+
+fn read_recovery_state(path: &Path) -> Result<GraphStateDto, String> {
+    let json =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read autosave: {}", e))?;
+    super::persistence::parse_state(&json)
+}
@@ -1,16 +1,48 @@
 //! Tauri Commands - API endpoints for frontend
 
 use super::dto::*;
-use crate::audio::bus::BusNode;
+use super::error::SpectrumError;
+use super::events::emit_graph_event;
+use super::history;
+use super::integrity;
+use super::persistence::{self, GRAPH_STATE_VERSION};
+use crate::audio::analyzer::{self, TapConfig};
+use crate::audio::automation::{self, Breakpoint};
+use crate::audio::bus::{BusNode, PluginLayout};
+use crate::audio::crossfade::{self, Crossfade, CrossfadeCurve, MAX_CROSSFADE_MS};
+use crate::audio::dsp::{
+    is_native_plugin_id, CompressorSettings, EqBand, EqBandKind, EqSettings, GateSettings,
+    LimiterSettings, NativeSettings, ProtectionSettings, EQ_BANDS, NATIVE_MANUFACTURER,
+    NATIVE_PLUGINS,
+};
+use crate::audio::ducking::{self, DuckingConfig, DuckingRule};
+use crate::audio::edge_link::{self, EdgeLinkGroup};
+use crate::audio::freeze::FrozenAudio;
+use crate::audio::generator::{GeneratorNode, GeneratorParams, Waveform};
+use crate::audio::loopback::{LoopbackSinkNode, LoopbackSourceNode};
+use crate::audio::matrix::{MatrixNode, MatrixPreset, MAX_MATRIX_CHANNELS};
+use crate::audio::meter_history::{HistoryKey, HISTORY_LEN, HISTORY_RATE_HZ};
+use crate::audio::meter_packet::MeterFilter;
+use crate::audio::net::{NetReceiveSourceNode, NetSendSinkNode};
 use crate::audio::output::start_output_v2;
+use crate::audio::param_mailbox::EdgeParamUpdate;
+use crate::audio::plugin_source::PluginSourceNode;
 use crate::audio::processor::get_graph_processor;
-use crate::audio::sink::SinkNode;
+use crate::audio::reclaim::{self, Retired};
+use crate::audio::record::{RecordFormat, RecordNode, RecordSession, RecordingHandle};
+use crate::audio::sink::{ChannelRoute, SinkNode};
+use crate::audio::solo::SoloMode;
 use crate::audio::source::SourceNode;
-use crate::audio::{AudioNode, EdgeId, NodeHandle, PortId};
+use crate::audio::taper::{db_to_linear, fader_taper, FaderTaper, MAX_DB, MIN_DB};
+use crate::audio::{
+    AudioGraph, AudioNode, BusResize, CueTap, Edge, EdgeId, MeterBallistics, MonitorMode,
+    NodeHandle, PanLaw, PortId, SumLaw, TapPoint,
+};
+use crate::logging::{self, Level};
 use crate::UiStateCache;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
 use tauri::State;
 
@@ -22,6 +54,8 @@ use tauri::State;
 /// - 0: off (default in release)
 /// - 1: summary (default in debug builds)
 /// - 2: verbose (set `SPECTRUM_STATE_LOG=2`)
+///
+/// `SPECTRUM_STATE_LOG` > settings.json `log_level` > build default.
 fn state_log_level() -> u8 {
     if let Ok(v) = std::env::var("SPECTRUM_STATE_LOG") {
         let v = v.trim().to_ascii_lowercase();
@@ -32,6 +66,8 @@ fn state_log_level() -> u8 {
         } else {
             1
         }
+    } else if let Some(level) = crate::settings::log_level() {
+        level.as_u8()
     } else if cfg!(debug_assertions) {
         1
     } else {
@@ -41,13 +77,13 @@ fn state_log_level() -> u8 {
 
 fn state_log_summary(msg: impl AsRef<str>) {
     if state_log_level() >= 1 {
-        println!("[state] {}", msg.as_ref());
+        log_info!("[state] {}", msg.as_ref());
     }
 }
 
 fn state_log_verbose(msg: impl AsRef<str>) {
     if state_log_level() >= 2 {
-        println!("[state] {}", msg.as_ref());
+        log_info!("[state] {}", msg.as_ref());
     }
 }
 
@@ -77,17 +113,56 @@ fn stable_id_for_bus_id(bus_id: &str) -> String {
 }
 
 fn stable_id_for_sink(sink: &OutputSinkDto) -> String {
+    // 既定出力に追従するシンクはデバイスが変わっても同じノード
+    if sink.follow_default {
+        return format!(
+            "sink:default:{}:{}",
+            sink.channel_offset, sink.channel_count
+        );
+    }
     format!(
         "sink:{}:{}:{}",
         sink.device_id, sink.channel_offset, sink.channel_count
     )
 }
 
-fn compute_stable_id_for_node(node: &NodeInfoDto) -> String {
+fn stable_id_for_record_id(record_id: &str) -> String {
+    format!("record:{}", record_id)
+}
+
+fn stable_id_for_generator_id(generator_id: &str) -> String {
+    format!("generator:{}", generator_id)
+}
+
+fn stable_id_for_plugin_source(source_id: &str) -> String {
+    format!("plugin_source:{}", source_id)
+}
+
+fn stable_id_for_matrix_id(matrix_id: &str) -> String {
+    format!("matrix:{}", matrix_id)
+}
+
+fn stable_id_for_loopback(loopback_id: &str, role: &str) -> String {
+    format!("loopback:{}:{}", loopback_id, role)
+}
+
+fn stable_id_for_net_id(net_id: &str) -> String {
+    format!("net:{}", net_id)
+}
+
+pub(super) fn compute_stable_id_for_node(node: &NodeInfoDto) -> String {
     match node {
         NodeInfoDto::Source { source_id, .. } => stable_id_for_source_id(source_id),
         NodeInfoDto::Bus { bus_id, .. } => stable_id_for_bus_id(bus_id),
         NodeInfoDto::Sink { sink, .. } => stable_id_for_sink(sink),
+        NodeInfoDto::Record { record_id, .. } => stable_id_for_record_id(record_id),
+        NodeInfoDto::Generator { generator_id, .. } => stable_id_for_generator_id(generator_id),
+        NodeInfoDto::PluginSource { source_id, .. } => stable_id_for_plugin_source(source_id),
+        NodeInfoDto::Matrix { matrix_id, .. } => stable_id_for_matrix_id(matrix_id),
+        NodeInfoDto::Loopback {
+            loopback_id, role, ..
+        } => stable_id_for_loopback(loopback_id, role),
+        NodeInfoDto::Network { net_id, .. } => stable_id_for_net_id(net_id),
     }
 }
 
@@ -96,7 +171,7 @@ fn compute_stable_id_for_node(node: &NodeInfoDto) -> String {
 // =============================================================================
 
 #[tauri::command]
-pub async fn get_input_devices() -> Result<Vec<InputDeviceDto>, String> {
+pub async fn get_input_devices() -> Result<Vec<InputDeviceDto>, SpectrumError> {
     // Use the capture module to get devices
     let devices = crate::capture::get_input_devices();
     Ok(devices
@@ -127,38 +202,225 @@ pub async fn get_input_devices() -> Result<Vec<InputDeviceDto>, String> {
 }
 
 #[tauri::command]
-pub async fn get_output_devices() -> Result<Vec<OutputDeviceDto>, String> {
+pub async fn get_output_devices() -> Result<Vec<OutputDeviceDto>, SpectrumError> {
     // Use the device module to get output devices
     let devices = crate::device::get_output_devices();
     Ok(devices)
 }
 
 #[tauri::command]
-pub async fn get_prism_status() -> Result<PrismStatusDto, String> {
+pub async fn get_prism_status() -> Result<PrismStatusDto, SpectrumError> {
     // connected should reflect prismd daemon connection, not whether audio capture is active
     let connected = crate::prismd::is_connected();
-    let apps = crate::prismd::get_processes()
-        .into_iter()
-        .map(|p| PrismAppDto {
-            pid: p.pid,
-            name: p.name,
-            channel_offset: (p.channel_offset / 2) as u8, // Convert to stereo pair index
-        })
-        .collect();
+    let clients = if connected {
+        crate::prismd::clients()
+    } else {
+        Vec::new()
+    };
+    Ok(PrismStatusDto::new(connected, clients))
+}
+
+/// Create an aggregate device from output device UIDs
+///
+/// The first UID becomes the clock source; with `drift_correction` the others are
+/// drift-compensated against it.
+#[tauri::command]
+pub async fn create_aggregate_device(
+    sub_device_uids: Vec<String>,
+    drift_correction: Option<bool>,
+    name: Option<String>,
+) -> Result<AggregateDeviceDto, SpectrumError> {
+    let created = crate::device::create_aggregate_device(
+        name.as_deref().unwrap_or(""),
+        &sub_device_uids,
+        drift_correction.unwrap_or(true),
+    )?;
+    Ok(AggregateDeviceDto {
+        device_id: created.device_id,
+        uid: created.uid,
+        name: created.name,
+        channel_count: created.channels,
+    })
+}
+
+/// Destroy an aggregate device previously created by Spectrum
+#[tauri::command]
+pub async fn destroy_aggregate_device(device_id: u32) -> Result<(), SpectrumError> {
+    if crate::audio::output::is_output_device_running(device_id) {
+        return Err(SpectrumError::DeviceBusy {
+            device_id,
+            reason: "Device is an active output; stop it before destroying it".to_string(),
+        });
+    }
+    Ok(crate::device::destroy_aggregate_device(device_id)?)
+}
+
+/// Microphone (input capture) authorization
+#[tauri::command]
+pub async fn get_audio_permission_status() -> Result<AudioPermissionDto, SpectrumError> {
+    Ok(AudioPermissionDto::from(
+        crate::permission::audio_permission_status(),
+    ))
+}
+
+/// Show the system microphone prompt if the user has not been asked yet
+///
+/// Waits up to 60 s for an answer. Denied access can only be changed in System Settings.
+#[tauri::command]
+pub async fn request_audio_permission() -> Result<AudioPermissionDto, SpectrumError> {
+    // Blocks until the user answers; keep it off the async runtime
+    let status = tokio::task::spawn_blocking(|| {
+        crate::permission::request_audio_permission(std::time::Duration::from_secs(60))
+    })
+    .await
+    .map_err(|e| SpectrumError::Other(e.to_string()))?;
+    Ok(AudioPermissionDto::from(status))
+}
+
+// =============================================================================
+// Prism Channel Commands
+// =============================================================================
+
+/// Stereo pairs 0-31 with the apps routed to them and their source nodes
+#[tauri::command]
+pub async fn get_channel_map() -> Result<Vec<PrismChannelDto>, SpectrumError> {
+    let clients = super::prism_channels::fresh_clients().await?;
+    Ok(super::prism_channels::channel_map(&clients))
+}
+
+/// Route an app to a stereo pair
+///
+/// If another app already uses the pair, fails unless `steal` is set, in which case
+/// that app is released to MAIN (pair 0) first. Returns the updated channel map.
+#[tauri::command]
+pub async fn assign_app_to_channel(
+    app: String,
+    channel: u8,
+    steal: Option<bool>,
+) -> Result<Vec<PrismChannelDto>, SpectrumError> {
+    let clients = super::prism_channels::assign(&app, channel, steal.unwrap_or(false)).await?;
+    Ok(super::prism_channels::channel_map(&clients))
+}
+
+/// Release an app back to MAIN (pair 0)
+#[tauri::command]
+pub async fn release_app_channel(app: String) -> Result<Vec<PrismChannelDto>, SpectrumError> {
+    let clients = super::prism_channels::assign(&app, 0, false).await?;
+    Ok(super::prism_channels::channel_map(&clients))
+}
+
+/// Trim a Prism client before capture (linear, 0.0-4.0); returns the applied gain
+///
+/// The gain is remembered per app and applied again when the app reconnects.
+#[tauri::command]
+pub async fn set_client_gain(client_id: u32, gain: f32) -> Result<f32, SpectrumError> {
+    super::prism_channels::set_client_gain(client_id, gain).await
+}
+
+// =============================================================================
+// Prism Driver Commands
+// =============================================================================
+
+/// Driver I/O buffer range accepted by prismd (frames)
+const PRISM_BUFFER_RANGE: std::ops::RangeInclusive<u32> = 32..=2048;
+
+fn prism_driver_dto(info: crate::prismd::DriverInfo) -> PrismDriverDto {
+    PrismDriverDto {
+        connected: true,
+        sample_rate: info.sample_rate,
+        buffer_size: info.buffer_size,
+        channel_count: info.channel_count,
+        negotiated: true,
+    }
+}
 
-    Ok(PrismStatusDto {
-        connected,
-        channels: if connected { 64 } else { 0 },
-        apps,
+/// Actual Prism driver configuration
+///
+/// prismd に問い合わせ、答えられない場合（古いデーモン・未起動）は Prism デバイスの
+/// CoreAudio プロパティから読む。
+#[tauri::command]
+pub async fn get_prism_driver() -> Result<PrismDriverDto, SpectrumError> {
+    match crate::prismd::get_driver_info().await {
+        Ok(info) => return Ok(prism_driver_dto(info)),
+        Err(e) => log_debug!("[Prism] Driver info unavailable from prismd: {}", e),
+    }
+
+    let device = crate::capture::find_prism_device();
+    let sample_rate = device
+        .and_then(crate::device::get_device_nominal_sample_rate)
+        .unwrap_or_else(crate::audio::engine_sample_rate);
+    Ok(PrismDriverDto {
+        connected: crate::prismd::is_connected() || crate::capture::is_capture_running(),
+        sample_rate: sample_rate as u32,
+        buffer_size: device
+            .map(crate::capture::device_io_buffer_size)
+            .unwrap_or_else(|| crate::capture::get_io_buffer_size() as u32),
+        channel_count: device
+            .map(crate::capture::get_device_input_channels)
+            .unwrap_or(0),
+        negotiated: false,
     })
 }
 
+/// Change the Prism driver's I/O buffer size through prismd
+#[tauri::command]
+pub async fn set_prism_buffer_size(frames: u32) -> Result<PrismDriverDto, SpectrumError> {
+    if !PRISM_BUFFER_RANGE.contains(&frames) {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Prism buffer size must be {}-{} frames, got {}",
+            PRISM_BUFFER_RANGE.start(),
+            PRISM_BUFFER_RANGE.end(),
+            frames
+        )));
+    }
+    let info = crate::prismd::set_buffer_size(frames)
+        .await
+        .map_err(super::prism_channels::prism_error)?;
+    if info.buffer_size != frames {
+        log_warn!(
+            "[Prism] Driver buffer is {} frames (requested {})",
+            info.buffer_size,
+            frames
+        );
+    } else {
+        log_info!("[Prism] Driver buffer set to {} frames", frames);
+    }
+    Ok(prism_driver_dto(info))
+}
+
+/// Change the number of Prism driver channels through prismd (even, up to 64)
+///
+/// ドライバーがストリームを作り直すので、Prism のキャプチャが動いていれば再起動する。
+#[tauri::command]
+pub async fn set_prism_channel_count(channels: u32) -> Result<PrismDriverDto, SpectrumError> {
+    let max = crate::audio_capture::PRISM_CHANNELS as u32;
+    if channels < 2 || channels > max || channels % 2 != 0 {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Prism channel count must be an even number from 2 to {}, got {}",
+            max, channels
+        )));
+    }
+    let info = crate::prismd::set_channel_count(channels)
+        .await
+        .map_err(super::prism_channels::prism_error)?;
+    log_info!("[Prism] Driver now has {} channels", info.channel_count);
+
+    let prism_generic = crate::capture::get_active_captures().iter().any(|c| c.3);
+    if crate::capture::is_capture_running() && !prism_generic {
+        crate::audio_capture::restart_capture()?;
+    }
+    Ok(prism_driver_dto(info))
+}
+
 // =============================================================================
 // Graph Commands
 // =============================================================================
 
 #[tauri::command]
-pub async fn add_source_node(source_id: SourceIdDto, label: Option<String>) -> Result<u32, String> {
+pub async fn add_source_node(
+    source_id: SourceIdDto,
+    label: Option<String>,
+) -> Result<u32, SpectrumError> {
     let processor = get_graph_processor();
 
     // De-dup: ensure only one node exists per logical source (Prism channel / device input).
@@ -180,17 +442,19 @@ pub async fn add_source_node(source_id: SourceIdDto, label: Option<String>) -> R
         }
         None
     }) {
-        println!(
+        log_info!(
             "[api] add_source_node de-dup: source_id={:?} -> existing_handle={}",
-            source_id, existing
+            source_id,
+            existing
         );
         return Ok(existing);
     }
 
     // Debug log: indicate frontend requested adding a source
-    println!(
+    log_info!(
         "[api] add_source_node invoked: source_id={:?}, label={:?}",
-        source_id, label
+        source_id,
+        label
     );
     // If this is a physical input device, ensure capture is running for it.
     // Prism capture is handled separately by start_audio/start_capture.
@@ -209,11 +473,16 @@ pub async fn add_source_node(source_id: SourceIdDto, label: Option<String>) -> R
                     device_id
                 ));
             }
+            // Without microphone access the source would only be silent; let the UI ask
+            Err(e @ crate::capture::CaptureError::PermissionDenied { .. }) => {
+                return Err(e.into());
+            }
             Err(e) => {
                 // Not fatal; allow graph operations even if capture can't start.
-                eprintln!(
+                log_error!(
                     "[api] add_source_node: start_input_capture failed for device_id={}: {}",
-                    device_id, e
+                    device_id,
+                    e
                 );
             }
         }
@@ -241,44 +510,24 @@ pub async fn add_source_node(source_id: SourceIdDto, label: Option<String>) -> R
     };
 
     let handle = processor.add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
     Ok(handle.raw())
 }
 
 #[tauri::command]
-pub async fn add_bus_node(label: Option<String>, port_count: Option<u8>) -> Result<u32, String> {
+pub async fn add_bus_node(
+    label: Option<String>,
+    port_count: Option<u8>,
+) -> Result<u32, SpectrumError> {
     let processor = get_graph_processor();
     let port_count = port_count.unwrap_or(2);
 
     // Auto-generate label if not provided by finding the smallest available bus number
-    let label = if let Some(l) = label {
-        l
-    } else {
-        processor.with_graph(|graph| {
-            // Collect all used bus numbers
-            let mut used_numbers = std::collections::HashSet::new();
-            for handle in graph.node_handles() {
-                let Some(node) = graph.get_node(handle) else {
-                    continue;
-                };
-                let Some(bus) = node.as_any().downcast_ref::<BusNode>() else {
-                    continue;
-                };
-                // Parse "Bus N" pattern
-                if let Some(caps) = bus.label().strip_prefix("Bus ") {
-                    if let Ok(num) = caps.parse::<u32>() {
-                        used_numbers.insert(num);
-                    }
-                }
-            }
-
-            // Find the smallest available number
-            let mut bus_number = 1u32;
-            while used_numbers.contains(&bus_number) {
-                bus_number += 1;
-            }
-
-            format!("Bus {}", bus_number)
-        })
+    let label = match label {
+        Some(l) => l,
+        None => processor.with_graph(|graph| BusLabels::new(graph).next_label()),
     };
 
     // De-dup: avoid accidentally creating multiple identical buses (common during UI/dev refreshes).
@@ -297,39 +546,263 @@ pub async fn add_bus_node(label: Option<String>, port_count: Option<u8>) -> Resu
         }
         None
     }) {
-        println!(
+        log_info!(
             "[api] add_bus_node de-dup: label={:?} port_count={} -> existing_handle={} ",
-            label, port_count, existing
+            label,
+            port_count,
+            existing
         );
         return Ok(existing);
     }
 
-    let bus_id = format!(
+    let node = new_bus_node(&label, port_count);
+    let handle = processor.add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Smallest unused "Bus N" labels (numbers used by the graph or handed out earlier)
+struct BusLabels {
+    used: HashSet<u32>,
+}
+
+impl BusLabels {
+    fn new(graph: &AudioGraph) -> Self {
+        let mut labels = Self {
+            used: HashSet::new(),
+        };
+        for handle in graph.node_handles() {
+            if let Some(bus) = graph
+                .get_node(handle)
+                .and_then(|node| node.as_any().downcast_ref::<BusNode>())
+            {
+                labels.claim(bus.label());
+            }
+        }
+        labels
+    }
+
+    /// Mark a label as used (if it has the "Bus N" pattern)
+    fn claim(&mut self, label: &str) {
+        if let Some(num) = label
+            .strip_prefix("Bus ")
+            .and_then(|n| n.parse::<u32>().ok())
+        {
+            self.used.insert(num);
+        }
+    }
+
+    /// Hand out the smallest available number
+    fn next_label(&mut self) -> String {
+        let mut bus_number = 1u32;
+        while self.used.contains(&bus_number) {
+            bus_number += 1;
+        }
+        self.used.insert(bus_number);
+        format!("Bus {}", bus_number)
+    }
+}
+
+/// New random bus id ("bus_xxxxxxxx")
+fn new_bus_id() -> String {
+    format!(
         "bus_{}",
         uuid::Uuid::new_v4()
             .to_string()
             .split('-')
             .next()
             .unwrap_or("0")
-    );
-    let node: Box<dyn AudioNode> = if port_count == 2 {
-        Box::new(crate::audio::bus::BusNode::new_stereo(&bus_id, &label))
+    )
+}
+
+fn new_bus_node(label: &str, port_count: u8) -> Box<dyn AudioNode> {
+    let bus_id = new_bus_id();
+    if port_count == 2 {
+        Box::new(crate::audio::bus::BusNode::new_stereo(&bus_id, label))
     } else {
         Box::new(crate::audio::bus::BusNode::new(
             &bus_id,
-            &label,
+            label,
             port_count as usize,
         ))
-    };
+    }
+}
 
-    let handle = processor.add_node(node);
-    Ok(handle.raw())
+/// Change the number of ports of a bus
+///
+/// Edges on ports that no longer exist are narrowed, moved onto the remaining ports
+/// or removed; input bundles wider than the bus are summed into it (see `set_edge_sum_law`).
+/// The result lists which.
+#[tauri::command]
+pub async fn set_bus_port_count(
+    handle: u32,
+    port_count: u8,
+) -> Result<BusResizeDto, SpectrumError> {
+    if port_count == 0 {
+        return Err(SpectrumError::InvalidArgument(
+            "A bus needs at least one port".to_string(),
+        ));
+    }
+    let processor = get_graph_processor();
+    let node_handle = NodeHandle::from_raw(handle);
+
+    let report = processor.with_graph_mut(|graph| {
+        if graph.get_node(node_handle).is_none() {
+            return Err(SpectrumError::NodeNotFound(handle));
+        }
+        graph
+            .resize_bus(node_handle, port_count as usize)
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "bus",
+            })
+    })?;
+    let plugins_bypassed = !fit_plugin_layouts(&[node_handle]).is_empty();
+
+    for &id in &report.removed {
+        automation::set_lane(id, &[]);
+        crossfade::cancel(id);
+    }
+    log_info!(
+        "[api] set_bus_port_count: bus {} -> {} ports (narrowed={} folded={} rewired={} removed={})",
+        handle,
+        port_count,
+        report.narrowed.len(),
+        report.folded.len(),
+        report.rewired.len(),
+        report.removed.len()
+    );
+    if report == BusResize::default() {
+        emit_graph_event(GraphEventDto::NodeChanged { handle });
+    } else {
+        emit_graph_event(GraphEventDto::GraphReloaded);
+    }
+    let raw = |edges: &[EdgeId]| -> Vec<u32> { edges.iter().map(|id| id.raw()).collect() };
+    Ok(BusResizeDto {
+        port_count,
+        narrowed_edges: raw(&report.narrowed),
+        folded_edges: raw(&report.folded),
+        rewired_edges: raw(&report.rewired),
+        removed_edges: raw(&report.removed),
+        plugins_bypassed,
+    })
+}
+
+/// Fit the AudioUnits on `buses` to each bus width (see `AudioUnitManager::negotiate_layout`)
+///
+/// AU の再設定中はオーディオスレッドが触らないよう、先にバイパスしてから交渉する。
+/// 幅に合っているプラグインは何もしない。
+/// Returns the instance IDs on these buses that are bypassed by their layout.
+fn fit_plugin_layouts(buses: &[NodeHandle]) -> Vec<String> {
+    let processor = get_graph_processor();
+    let pending: Vec<(NodeHandle, usize, Vec<String>)> = processor.with_graph_mut(|graph| {
+        buses
+            .iter()
+            .filter_map(|&handle| {
+                let bus = graph
+                    .get_node_mut(handle)?
+                    .as_any_mut()
+                    .downcast_mut::<BusNode>()?;
+                let ids = bus.unfitted_plugins();
+                for id in &ids {
+                    bus.set_plugin_layout(id, PluginLayout::Bypassed, 0);
+                }
+                (!ids.is_empty()).then(|| (handle, bus.output_port_count(), ids))
+            })
+            .collect()
+    });
+
+    let manager = crate::audio_unit::get_au_manager();
+    let negotiated: Vec<_> = pending
+        .into_iter()
+        .map(|(handle, ports, ids)| {
+            let layouts: Vec<_> = ids
+                .into_iter()
+                .map(|id| {
+                    let layout = manager.negotiate_layout(&id, ports);
+                    (id, layout)
+                })
+                .collect();
+            (handle, ports, layouts)
+        })
+        .collect();
+
+    processor.with_graph_mut(|graph| {
+        for (handle, ports, layouts) in &negotiated {
+            if let Some(bus) = graph
+                .get_node_mut(*handle)
+                .and_then(|n| n.as_any_mut().downcast_mut::<BusNode>())
+            {
+                for (id, layout) in layouts {
+                    bus.set_plugin_layout(id, *layout, *ports);
+                }
+            }
+        }
+    });
+
+    // 読むだけなのでオーディオスレッドに回さない（ログもここで書く）
+    let bypassed: Vec<(String, usize)> = processor.with_graph(|graph| {
+        buses
+            .iter()
+            .filter_map(|&handle| {
+                graph
+                    .get_node(handle)
+                    .and_then(|n| n.as_any().downcast_ref::<BusNode>())
+            })
+            .flat_map(|bus| {
+                bus.plugins()
+                    .iter()
+                    .filter(|plugin| plugin.layout() == PluginLayout::Bypassed)
+                    .map(|plugin| (plugin.instance_id.clone(), bus.output_port_count()))
+            })
+            .collect()
+    });
+    bypassed
+        .into_iter()
+        .map(|(instance_id, ports)| {
+            log_warn!(
+                "[api] Plugin {} can't run on a {}-port bus; bypassed",
+                instance_id,
+                ports
+            );
+            instance_id
+        })
+        .collect()
+}
+
+/// Every bus in the graph
+fn bus_handles() -> Vec<NodeHandle> {
+    get_graph_processor().with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter(|&handle| {
+                graph
+                    .get_node(handle)
+                    .is_some_and(|n| n.as_any().downcast_ref::<BusNode>().is_some())
+            })
+            .collect()
+    })
 }
 
 #[tauri::command]
-pub async fn add_sink_node(sink: OutputSinkDto, label: Option<String>) -> Result<u32, String> {
+pub async fn add_sink_node(
+    mut sink: OutputSinkDto,
+    label: Option<String>,
+) -> Result<u32, SpectrumError> {
     let processor = get_graph_processor();
 
+    // "Follow system default": bind to whatever the default output is right now
+    if sink.follow_default {
+        let device_id = crate::device::default_output_device()
+            .ok_or_else(|| SpectrumError::InvalidState("No default output device".to_string()))?;
+        if device_id != sink.device_id {
+            sink.device_id = device_id;
+            sink.device_uid = None;
+        }
+    }
+
     // De-dup: ensure only one node exists per logical sink (device + offset + count).
     let target_stable_id = stable_id_for_sink(&sink);
     if let Some(existing) = processor.with_graph(|graph| {
@@ -348,17 +821,19 @@ pub async fn add_sink_node(sink: OutputSinkDto, label: Option<String>) -> Result
         }
         None
     }) {
-        println!(
+        log_info!(
             "[api] add_sink_node de-dup: sink={:?} -> existing_handle={}",
-            sink, existing
+            sink,
+            existing
         );
         return Ok(existing);
     }
 
     // Debug log: indicate frontend requested adding a sink
-    println!(
+    log_info!(
         "[api] add_sink_node invoked: sink={:?}, label={:?}",
-        sink, label
+        sink,
+        label
     );
     let label = label.unwrap_or_else(|| format!("Output {}", sink.device_id));
 
@@ -383,20 +858,31 @@ pub async fn add_sink_node(sink: OutputSinkDto, label: Option<String>) -> Result
         }
     });
 
-    let sink_id = crate::audio::sink::SinkId::with_uid(
+    let mut sink_id = crate::audio::sink::SinkId::with_uid(
         sink.device_id,
         sink.channel_offset,
         sink.channel_count,
         device_uid,
     );
-    let node: Box<dyn AudioNode> = Box::new(crate::audio::sink::SinkNode::new(sink_id, &label));
+    sink_id.follow_default = sink.follow_default;
+    let mut node = crate::audio::sink::SinkNode::new(sink_id, &label);
+    node.set_channel_map(sink.channel_map.clone())
+        .map_err(SpectrumError::InvalidArgument)?;
+    node.set_device_latency(crate::device::get_sink_output_latency(
+        sink.device_id,
+        sink.channel_offset,
+    ));
+    let node: Box<dyn AudioNode> = Box::new(node);
 
     let handle = processor.add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
     Ok(handle.raw())
 }
 
 #[tauri::command]
-pub async fn remove_node(handle: u32) -> Result<(), String> {
+pub async fn remove_node(handle: u32) -> Result<(), SpectrumError> {
     let processor = get_graph_processor();
     let node_handle = NodeHandle::from(handle);
 
@@ -406,84 +892,240 @@ pub async fn remove_node(handle: u32) -> Result<(), String> {
     let plugin_instance_ids: Vec<String> = processor.with_graph(|graph| {
         graph
             .get_node(node_handle)
-            .and_then(|node| node.as_any().downcast_ref::<BusNode>())
-            .map(|bus| {
-                bus.plugins()
-                    .iter()
-                    .map(|p| p.instance_id.clone())
-                    .collect()
-            })
+            .map(node_plugin_instances)
             .unwrap_or_default()
     });
 
-    if !plugin_instance_ids.is_empty() {
-        let ids_for_ui = plugin_instance_ids.clone();
-        let (tx, rx) = std::sync::mpsc::channel::<()>();
+    release_plugin_instances(
+        &plugin_instance_ids,
+        &format!("remove_node: node {}", handle),
+    );
 
-        unsafe {
-            use block2::RcBlock;
-            use objc2::class;
-            use objc2::msg_send;
-            use objc2::runtime::AnyObject;
+    if processor.remove_node(node_handle) {
+        emit_graph_event(GraphEventDto::NodeRemoved { handle });
+        Ok(())
+    } else {
+        Err(SpectrumError::NodeNotFound(handle))
+    }
+}
 
-            let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+/// AU instances owned by a node (bus chain or plugin source)
+fn node_plugin_instances(node: &dyn AudioNode) -> Vec<String> {
+    let any = node.as_any();
+    if let Some(bus) = any.downcast_ref::<BusNode>() {
+        bus.plugins()
+            .iter()
+            .map(|p| p.instance_id.clone())
+            .collect()
+    } else if let Some(source) = any.downcast_ref::<PluginSourceNode>() {
+        vec![source.instance_id().to_string()]
+    } else {
+        Vec::new()
+    }
+}
 
-            let block = RcBlock::new(move || {
-                for id in &ids_for_ui {
-                    crate::audio_unit_ui::close_audio_unit_ui(id);
-                }
-                let _ = tx.send(());
-            });
+/// Close the plugin windows of removed nodes and release their AU instances
+///
+/// Best-effort: if closing times out, the instances are released anyway.
+fn release_plugin_instances(instance_ids: &[String], context: &str) {
+    if instance_ids.is_empty() {
+        return;
+    }
+    let ids_for_ui = instance_ids.to_vec();
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
 
-            let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
-        }
+    unsafe {
+        use block2::RcBlock;
+        use objc2::class;
+        use objc2::msg_send;
+        use objc2::runtime::AnyObject;
 
-        if rx.recv_timeout(std::time::Duration::from_secs(2)).is_err() {
-            eprintln!(
-                "[api] remove_node: timeout closing plugin UIs for bus {}",
-                handle
-            );
-        }
+        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
 
-        // Release AU instances (best-effort)
-        let au_manager = crate::audio_unit::get_au_manager();
-        for id in &plugin_instance_ids {
-            let _ = au_manager.remove_instance(id);
-        }
+        let block = RcBlock::new(move || {
+            for id in &ids_for_ui {
+                crate::audio_unit_ui::close_audio_unit_ui(id);
+            }
+            let _ = tx.send(());
+        });
+
+        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
     }
 
-    if processor.remove_node(node_handle) {
-        Ok(())
-    } else {
-        Err(format!("Node {} not found", handle))
+    if rx.recv_timeout(std::time::Duration::from_secs(2)).is_err() {
+        log_error!("[api] {}: timeout closing plugin UIs", context);
+    }
+
+    // Release AU instances (best-effort)
+    let au_manager = crate::audio_unit::get_au_manager();
+    for id in instance_ids {
+        let _ = au_manager.remove_instance(id);
     }
 }
 
+/// Rename a node
+///
+/// Prism sources are labelled after the app routed to them and cannot be renamed.
 #[tauri::command]
-pub async fn add_edge(
-    source: u32,
-    source_port: u8,
-    target: u32,
-    target_port: u8,
-    gain: Option<f32>,
-    muted: Option<bool>,
-) -> Result<u32, String> {
+pub async fn set_node_label(handle: u32, label: String) -> Result<(), SpectrumError> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(SpectrumError::InvalidArgument(
+            "Label must not be empty".to_string(),
+        ));
+    }
     let processor = get_graph_processor();
+    let changed = processor.with_graph_mut(|graph| {
+        let node = graph
+            .get_node_mut(NodeHandle::from_raw(handle))
+            .ok_or(SpectrumError::NodeNotFound(handle))?;
+        let is_prism = node.as_any().downcast_ref::<SourceNode>().is_some_and(|s| {
+            matches!(
+                s.source_id(),
+                crate::audio::source::SourceId::PrismChannel { .. }
+            )
+        });
+        if is_prism {
+            return Err(SpectrumError::InvalidArgument(
+                "Prism sources are labelled after their app".to_string(),
+            ));
+        }
+        Ok(relabel_node(node, label))
+    })?;
+    if changed {
+        emit_graph_event(GraphEventDto::NodeChanged { handle });
+    }
+    Ok(())
+}
 
-    let gain_v = gain.unwrap_or(1.0);
-    let muted_v = muted.unwrap_or(false);
-
-    // Debug log: indicate frontend requested adding an edge (graph mutation)
-    println!(
-        "[graph] add_edge invoked: {}:{} -> {}:{} gain={} muted={}",
-        source, source_port, target, target_port, gain_v, muted_v
-    );
-
-    let edge_id = processor.add_edge(
-        NodeHandle::from(source),
-        PortId::from(source_port),
+/// Set or clear (None / "") the display color of a node
+///
+/// `color` is "#rgb", "#rrggbb" or "#rrggbbaa"; it is stored lowercase.
+#[tauri::command]
+pub async fn set_node_color(handle: u32, color: Option<String>) -> Result<(), SpectrumError> {
+    let color = match color.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(color) => {
+            let digits = color.strip_prefix('#').unwrap_or_default();
+            let valid =
+                matches!(digits.len(), 3 | 6 | 8) && digits.chars().all(|c| c.is_ascii_hexdigit());
+            if !valid {
+                return Err(SpectrumError::InvalidArgument(format!(
+                    "Invalid color {:?} (expected #rrggbb)",
+                    color
+                )));
+            }
+            Some(color.to_ascii_lowercase())
+        }
+    };
+    let processor = get_graph_processor();
+    if !processor.set_node_color(NodeHandle::from_raw(handle), color) {
+        return Err(SpectrumError::NodeNotFound(handle));
+    }
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(())
+}
+
+/// Limits of node metadata (it is saved with the graph)
+const MAX_METADATA_ENTRIES: usize = 64;
+const MAX_METADATA_KEY_LEN: usize = 64;
+const MAX_METADATA_VALUE_LEN: usize = 4096;
+
+/// Set (Some) or remove (None) one metadata entry of a node
+///
+/// The backend does not interpret metadata; it is returned in `NodeInfoDto.metadata`
+/// and saved with the graph.
+#[tauri::command]
+pub async fn set_node_metadata(
+    handle: u32,
+    key: String,
+    value: Option<String>,
+) -> Result<(), SpectrumError> {
+    if key.is_empty() || key.len() > MAX_METADATA_KEY_LEN {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Metadata key must be 1-{} bytes",
+            MAX_METADATA_KEY_LEN
+        )));
+    }
+    if value
+        .as_ref()
+        .is_some_and(|v| v.len() > MAX_METADATA_VALUE_LEN)
+    {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Metadata value must be at most {} bytes",
+            MAX_METADATA_VALUE_LEN
+        )));
+    }
+
+    let processor = get_graph_processor();
+    let node_handle = NodeHandle::from_raw(handle);
+    if value.is_some() {
+        let full = processor.with_graph(|graph| {
+            graph.node_metadata(node_handle).is_some_and(|metadata| {
+                metadata.len() >= MAX_METADATA_ENTRIES && !metadata.contains_key(&key)
+            })
+        });
+        if full {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Node {} already has {} metadata entries",
+                handle, MAX_METADATA_ENTRIES
+            )));
+        }
+    }
+    if !processor.set_node_metadata(node_handle, key, value) {
+        return Err(SpectrumError::NodeNotFound(handle));
+    }
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_edge(
+    source: u32,
+    source_port: u8,
+    target: u32,
+    target_port: u8,
+    gain: Option<f32>,
+    muted: Option<bool>,
+    channels: Option<u8>,
+) -> Result<u32, SpectrumError> {
+    let processor = get_graph_processor();
+
+    let gain_v = gain.unwrap_or(1.0);
+    let muted_v = muted.unwrap_or(false);
+    let channels_v = channels.unwrap_or(1);
+
+    // Debug log: indicate frontend requested adding an edge (graph mutation)
+    log_info!(
+        "[graph] add_edge invoked: {}:{} -> {}:{} gain={} muted={} channels={}",
+        source,
+        source_port,
+        target,
+        target_port,
+        gain_v,
+        muted_v,
+        channels_v
+    );
+
+    // ループがあると topological sort から外れて無音になるので、ここで弾く
+    processor.with_graph(|g| {
+        for handle in [source, target] {
+            if g.get_node(NodeHandle::from(handle)).is_none() {
+                return Err(SpectrumError::NodeNotFound(handle));
+            }
+        }
+        if g.has_path(NodeHandle::from(target), NodeHandle::from(source)) {
+            return Err(SpectrumError::GraphCycle { source, target });
+        }
+        Ok(())
+    })?;
+
+    let edge_id = processor.add_bundle_edge(
+        NodeHandle::from(source),
+        PortId::from(source_port),
         NodeHandle::from(target),
         PortId::from(target_port),
+        channels_v,
         gain_v,
         muted_v,
     );
@@ -492,54 +1134,67 @@ pub async fn add_edge(
         Some(id) => {
             let (node_count, edge_count) =
                 processor.with_graph(|g| (g.node_handles().count(), g.edges().len()));
-            println!(
+            log_info!(
                 "[graph] add_edge ok: edge_id={} nodes={} edges={}",
                 id.raw(),
                 node_count,
                 edge_count
             );
+            emit_graph_event(GraphEventDto::EdgeAdded { id: id.raw() });
             Ok(id.raw())
         }
         None => {
             let (node_count, edge_count) =
                 processor.with_graph(|g| (g.node_handles().count(), g.edges().len()));
-            println!(
+            log_warn!(
                 "[graph] add_edge FAILED: {}:{} -> {}:{} (nodes={} edges={})",
-                source, source_port, target, target_port, node_count, edge_count
+                source,
+                source_port,
+                target,
+                target_port,
+                node_count,
+                edge_count
             );
-            Err("Failed to add edge (nodes may not exist or edge already exists)".to_string())
+            Err(SpectrumError::EdgeConflict { source, target })
         }
     }
 }
 
 #[tauri::command]
-pub async fn remove_edge(id: u32) -> Result<(), String> {
+pub async fn remove_edge(id: u32) -> Result<(), SpectrumError> {
     let processor = get_graph_processor();
 
     // Debug log: indicate frontend requested removing an edge (graph mutation)
-    println!("[graph] remove_edge invoked: edge_id={}", id);
+    log_info!("[graph] remove_edge invoked: edge_id={}", id);
 
     if processor.remove_edge(EdgeId::from(id)) {
         let (node_count, edge_count) =
             processor.with_graph(|g| (g.node_handles().count(), g.edges().len()));
-        println!(
+        log_info!(
             "[graph] remove_edge ok: edge_id={} nodes={} edges={}",
-            id, node_count, edge_count
+            id,
+            node_count,
+            edge_count
         );
+        automation::set_lane(EdgeId::from(id), &[]);
+        crossfade::cancel(EdgeId::from(id));
+        emit_graph_event(GraphEventDto::EdgeRemoved { id });
         Ok(())
     } else {
         let (node_count, edge_count) =
             processor.with_graph(|g| (g.node_handles().count(), g.edges().len()));
-        println!(
+        log_info!(
             "[graph] remove_edge NOT_FOUND: edge_id={} (nodes={} edges={})",
-            id, node_count, edge_count
+            id,
+            node_count,
+            edge_count
         );
-        Err(format!("Edge {} not found", id))
+        Err(SpectrumError::EdgeNotFound(id))
     }
 }
 
 #[tauri::command]
-pub async fn get_graph() -> Result<GraphDto, String> {
+pub async fn get_graph() -> Result<GraphDto, SpectrumError> {
     let processor = get_graph_processor();
 
     processor.with_graph(|graph| {
@@ -549,6 +1204,7 @@ pub async fn get_graph() -> Result<GraphDto, String> {
         // Prism app lookup: channel_offset (stereo pair index) -> first app name.
         // Best-effort and local to this snapshot.
         let mut prism_app_by_offset: Option<std::collections::HashMap<u32, String>> = None;
+        let mut prism_gains_by_pair: Option<HashMap<u8, Vec<PrismClientGainDto>>> = None;
 
         // Optional on-demand lookup for filling missing plugin metadata (old saved state).
         // Built lazily only if we detect missing fields to avoid extra work.
@@ -557,10 +1213,67 @@ pub async fn get_graph() -> Result<GraphDto, String> {
         // Collect nodes with type-specific info
         for handle in graph.node_handles() {
             if let Some(node) = graph.get_node(handle) {
+                let color = graph.node_color(handle).map(str::to_string);
+                let metadata = graph.node_metadata(handle).cloned().unwrap_or_default();
                 let info = match node.node_type() {
                     crate::audio::NodeType::Source => {
+                        if let Some(generator) = node.as_any().downcast_ref::<GeneratorNode>() {
+                            NodeInfoDto::Generator {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_generator_id(generator.generator_id()),
+                                generator_id: generator.generator_id().to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                port_count: node.output_port_count() as u8,
+                                params: GeneratorParamsDto::from(generator.params()),
+                            }
+                        } else if let Some(source) =
+                            node.as_any().downcast_ref::<PluginSourceNode>()
+                        {
+                            NodeInfoDto::PluginSource {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_plugin_source(source.source_id()),
+                                source_id: source.source_id().to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                port_count: node.output_port_count() as u8,
+                                plugin: plugin_source_dto(source),
+                            }
+                        } else if let Some(loopback) =
+                            node.as_any().downcast_ref::<LoopbackSourceNode>()
+                        {
+                            NodeInfoDto::Loopback {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_loopback(loopback.loopback_id(), "source"),
+                                loopback_id: loopback.loopback_id().to_string(),
+                                role: "source".to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                port_count: node.output_port_count() as u8,
+                            }
+                        } else if let Some(net) =
+                            node.as_any().downcast_ref::<NetReceiveSourceNode>()
+                        {
+                            NodeInfoDto::Network {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_net_id(net.net_id()),
+                                net_id: net.net_id().to_string(),
+                                role: "receive".to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                port_count: node.output_port_count() as u8,
+                                address: net.address().to_string(),
+                                port: net.port(),
+                                packet_time_us: 0,
+                                jitter_ms: net.jitter_ms(),
+                            }
                         // Downcast to SourceNode to get source_id
-                        if let Some(source_node) = node.as_any().downcast_ref::<SourceNode>() {
+                        } else if let Some(source_node) = node.as_any().downcast_ref::<SourceNode>()
+                        {
                             // Normalize Prism source label semantics:
                             // - label: app name (or MAIN/Empty)
                             // - sub_label: channel label ("Ch 1-2")
@@ -571,7 +1284,8 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                             std::collections::HashMap::new();
                                         let list = crate::prismd::get_processes();
                                         for p in list {
-                                            map.entry(p.channel_offset).or_insert_with(|| p.name);
+                                            map.entry(p.channel_offset / 2)
+                                                .or_insert_with(|| p.name);
                                         }
                                         prism_app_by_offset = Some(map);
                                     }
@@ -623,6 +1337,21 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 }
                             };
 
+                            let client_gains = match source_node.source_id() {
+                                crate::audio::source::SourceId::PrismChannel { channel } => {
+                                    prism_gains_by_pair
+                                        .get_or_insert_with(|| {
+                                            super::prism_channels::client_gains_by_pair(
+                                                &crate::prismd::clients(),
+                                            )
+                                        })
+                                        .get(channel)
+                                        .cloned()
+                                        .unwrap_or_default()
+                                }
+                                _ => Vec::new(),
+                            };
+
                             NodeInfoDto::Source {
                                 handle: handle.raw(),
                                 stable_id: stable_id_for_source_id(&SourceIdDto::from(
@@ -631,8 +1360,13 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 source_id: SourceIdDto::from(source_node.source_id().clone()),
                                 port_count: node.output_port_count() as u8,
                                 label,
+                                color,
+                                metadata,
                                 sub_label,
                                 available,
+                                trims: source_trims(source_node),
+                                phase_inverted: source_phase(source_node),
+                                client_gains,
                             }
                         } else {
                             // Fallback if downcast fails
@@ -644,14 +1378,31 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 source_id: SourceIdDto::PrismChannel { channel: 0 },
                                 port_count: node.output_port_count() as u8,
                                 label: node.label().to_string(),
+                                color,
+                                metadata,
                                 sub_label: None,
                                 available: None,
+                                trims: Vec::new(),
+                                phase_inverted: Vec::new(),
+                                client_gains: Vec::new(),
                             }
                         }
                     }
                     crate::audio::NodeType::Bus => {
+                        if let Some(matrix) = node.as_any().downcast_ref::<MatrixNode>() {
+                            NodeInfoDto::Matrix {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_matrix_id(matrix.matrix_id()),
+                                matrix_id: matrix.matrix_id().to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                input_count: node.input_port_count() as u8,
+                                output_count: node.output_port_count() as u8,
+                                coefficients: matrix.coefficients(),
+                            }
                         // Downcast to BusNode to get bus_id and plugins
-                        if let Some(bus_node) = node.as_any().downcast_ref::<BusNode>() {
+                        } else if let Some(bus_node) = node.as_any().downcast_ref::<BusNode>() {
                             let plugins = bus_node.plugins();
 
                             let needs_lookup = plugins.iter().any(|p| {
@@ -664,26 +1415,11 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                             });
 
                             if needs_lookup && plugin_lookup.is_none() {
-                                let mut map: HashMap<String, (String, String)> = HashMap::new();
-
-                                for p in crate::audio_unit::get_effect_audio_units() {
-                                    map.insert(
-                                        p.id.clone(),
-                                        (p.name.clone(), p.manufacturer.clone()),
-                                    );
-                                }
-                                for p in crate::audio_unit::get_instrument_audio_units() {
-                                    map.insert(
-                                        p.id.clone(),
-                                        (p.name.clone(), p.manufacturer.clone()),
-                                    );
-                                }
-                                for p in crate::audio_unit::get_generator_audio_units() {
-                                    map.insert(
-                                        p.id.clone(),
-                                        (p.name.clone(), p.manufacturer.clone()),
-                                    );
-                                }
+                                let map: HashMap<String, (String, String)> =
+                                    crate::plugin_registry::all()
+                                        .into_iter()
+                                        .map(|p| (p.id, (p.name, p.manufacturer)))
+                                        .collect();
 
                                 plugin_lookup = Some(map);
                             }
@@ -694,6 +1430,8 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 bus_id: bus_node.bus_id().to_string(),
                                 stable_id: stable_id_for_bus_id(bus_node.bus_id()),
                                 label: node.label().to_string(),
+                                color,
+                                metadata,
                                 port_count: node.input_port_count() as u8,
                                 plugins: plugins
                                     .iter()
@@ -732,10 +1470,20 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                             name,
                                             manufacturer,
                                             enabled: p.enabled,
+                                            mix: p.mix(),
+                                            ring_out: p.ring_out(),
                                             state: None,
+                                            native: p
+                                                .native()
+                                                .map(|n| NativeDspDto::from(n.settings())),
+                                            out_of_process: p.is_out_of_process(),
+                                            crashed: p.is_crashed(),
+                                            overloaded: p.is_overloaded(),
+                                            layout: p.layout().as_str().to_string(),
                                         }
                                     })
                                     .collect(),
+                                frozen: bus_node.is_frozen(),
                             }
                         } else {
                             NodeInfoDto::Bus {
@@ -743,14 +1491,43 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 bus_id: "unknown".to_string(),
                                 stable_id: stable_id_for_bus_id("unknown"),
                                 label: node.label().to_string(),
+                                color,
+                                metadata,
                                 port_count: node.input_port_count() as u8,
                                 plugins: Vec::new(),
+                                frozen: false,
                             }
                         }
                     }
                     crate::audio::NodeType::Sink => {
+                        if let Some(loopback) = node.as_any().downcast_ref::<LoopbackSinkNode>() {
+                            NodeInfoDto::Loopback {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_loopback(loopback.loopback_id(), "sink"),
+                                loopback_id: loopback.loopback_id().to_string(),
+                                role: "sink".to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                port_count: node.input_port_count() as u8,
+                            }
+                        } else if let Some(net) = node.as_any().downcast_ref::<NetSendSinkNode>() {
+                            NodeInfoDto::Network {
+                                handle: handle.raw(),
+                                stable_id: stable_id_for_net_id(net.net_id()),
+                                net_id: net.net_id().to_string(),
+                                role: "send".to_string(),
+                                label: node.label().to_string(),
+                                color,
+                                metadata,
+                                port_count: node.input_port_count() as u8,
+                                address: net.address().to_string(),
+                                port: net.port(),
+                                packet_time_us: net.packet_time_us(),
+                                jitter_ms: 0,
+                            }
                         // Downcast to SinkNode to get sink_id
-                        if let Some(sink_node) = node.as_any().downcast_ref::<SinkNode>() {
+                        } else if let Some(sink_node) = node.as_any().downcast_ref::<SinkNode>() {
                             let sink_dto = OutputSinkDto::from(sink_node.sink_id().clone());
                             // Check if the output device is available (UID-based)
                             let available = if let Some(ref device_uid) = sink_dto.device_uid {
@@ -781,7 +1558,22 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 sink: sink_dto,
                                 port_count: node.input_port_count() as u8,
                                 label: node.label().to_string(),
+                                color,
+                                metadata,
                                 available,
+                                limiter: {
+                                    let settings = sink_node.limiter().settings();
+                                    // 既定値のままなら保存しない
+                                    (settings != LimiterSettings::default())
+                                        .then(|| SinkLimiterDto::from(settings))
+                                },
+                                protection: {
+                                    let protection = sink_node.protection();
+                                    let settings = protection.settings();
+                                    (settings != ProtectionSettings::default()).then(|| {
+                                        OutputProtectionDto::new(settings, protection.is_tripped())
+                                    })
+                                },
                             }
                         } else {
                             let sink_dto = OutputSinkDto {
@@ -789,6 +1581,8 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 channel_offset: 0,
                                 channel_count: node.input_port_count() as u8,
                                 device_uid: None,
+                                channel_map: None,
+                                follow_default: false,
                             };
                             NodeInfoDto::Sink {
                                 handle: handle.raw(),
@@ -796,10 +1590,32 @@ pub async fn get_graph() -> Result<GraphDto, String> {
                                 sink: sink_dto,
                                 port_count: node.input_port_count() as u8,
                                 label: node.label().to_string(),
+                                color,
+                                metadata,
                                 available: None,
+                                limiter: None,
+                                protection: None,
                             }
                         }
                     }
+                    crate::audio::NodeType::Record => {
+                        let (record_id, recording, armed) = node
+                            .as_any()
+                            .downcast_ref::<RecordNode>()
+                            .map(|r| (r.record_id().to_string(), r.is_recording(), r.is_armed()))
+                            .unwrap_or_else(|| ("unknown".to_string(), false, false));
+                        NodeInfoDto::Record {
+                            handle: handle.raw(),
+                            stable_id: stable_id_for_record_id(&record_id),
+                            record_id,
+                            label: node.label().to_string(),
+                            color,
+                            metadata,
+                            port_count: node.input_port_count() as u8,
+                            recording,
+                            armed,
+                        }
+                    }
                 };
                 nodes.push(info);
             }
@@ -810,445 +1626,4412 @@ pub async fn get_graph() -> Result<GraphDto, String> {
             edges.push(EdgeInfoDto::from(edge.clone()));
         }
 
-        Ok(GraphDto { nodes, edges })
+        Ok(GraphDto {
+            nodes,
+            edges,
+            cue: cue_state_dto(graph),
+        })
     })
 }
 
 // =============================================================================
-// Edge Commands (Hot Path - Realtime Parameter Changes)
+// Batch Commands
 // =============================================================================
 
+/// Apply several graph mutations as one transaction
+///
+/// Ops run in order; later ops can refer to nodes, edges and plugins created by earlier
+/// ops through their `alias`. Plugins are instantiated, aliases resolved and buses built
+/// before the graph is locked, and everything else is applied under a single lock, so the
+/// audio thread never renders (and autosave never persists) an intermediate state. If an
+/// op fails, the ops before it are rolled back and its error is returned; an unknown alias
+/// is rejected before anything is applied.
+///
+/// 削除（remove_node / remove_edge）は元に戻せるようにバッチの最後にまとめて行う。
+/// そのため循環チェックでは、同じバッチで削除するエッジもまだ残っているものとして扱う。
+/// 変更通知は最後に `GraphReloaded` を 1 回だけ送る。
 #[tauri::command]
-pub async fn set_edge_gain(id: u32, gain: f32) -> Result<(), String> {
+pub async fn execute_batch(ops: Vec<GraphOpDto>) -> Result<BatchResultDto, SpectrumError> {
     let processor = get_graph_processor();
 
-    if processor.set_edge_gain(EdgeId::from(id), gain) {
-        Ok(())
-    } else {
-        Err(format!("Edge {} not found", id))
+    // プラグインのインスタンス化は遅いので、ロックの外で先に済ませる
+    let mut instances: Vec<(String, String, String)> = Vec::new();
+    for op in &ops {
+        let GraphOpDto::AddPlugin { plugin_id, .. } = op else {
+            continue;
+        };
+        match instantiate_plugin(plugin_id).await {
+            Ok(instance) => instances.push(instance),
+            Err(e) => {
+                let created: Vec<String> = instances.into_iter().map(|(id, ..)| id).collect();
+                release_plugin_instances(&created, "execute_batch");
+                return Err(e);
+            }
+        }
     }
-}
+    let created: Vec<String> = instances.iter().map(|(id, ..)| id.clone()).collect();
+
+    // エイリアスの解決・バスの生成・作業領域の確保も変更の適用前に済ませる
+    let mut batch = match processor.with_graph(|graph| Batch::plan(graph, &ops, instances)) {
+        Ok(batch) => batch,
+        Err(e) => {
+            release_plugin_instances(&created, "execute_batch");
+            return Err(e);
+        }
+    };
+    let outcome = processor.with_graph_mut(|graph| batch.run(graph));
+    if let Err((index, e)) = outcome {
+        log_warn!(
+            "[api] execute_batch: op {} of {} failed, rolled back: {}",
+            index,
+            ops.len(),
+            e
+        );
+        release_plugin_instances(&created, "execute_batch: rollback");
+        return Err(e);
+    }
+    let (result, committed) = batch.finish();
 
-#[tauri::command]
-pub async fn set_edge_muted(id: u32, muted: bool) -> Result<(), String> {
-    let processor = get_graph_processor();
+    // グラフから外してからインスタンスを解放する
+    release_plugin_instances(&committed.removed_plugins, "execute_batch");
+    for id in committed.removed_edges {
+        automation::set_lane(id, &[]);
+        crossfade::cancel(id);
+    }
+    for (id, gain) in committed.gains {
+        crossfade::cancel(id);
+        automation::record_gain(id, gain);
+    }
 
-    if processor.set_edge_muted(EdgeId::from(id), muted) {
-        Ok(())
-    } else {
-        Err(format!("Edge {} not found", id))
+    state_log_summary(format!(
+        "execute_batch: {} ops, nodes +{} edges +{} plugins +{}",
+        result.applied,
+        result.nodes.len(),
+        result.edges.len(),
+        result.plugins.len()
+    ));
+    if result.applied > 0 {
+        fit_plugin_layouts(&bus_handles());
+        emit_graph_event(GraphEventDto::GraphReloaded);
     }
+    Ok(result)
 }
 
-#[tauri::command]
-pub async fn set_edge_gains_batch(updates: Vec<EdgeGainUpdate>) -> Result<(), String> {
-    let processor = get_graph_processor();
-
-    let batch: Vec<_> = updates
-        .into_iter()
-        .map(|u| (EdgeId::from(u.id), u.gain))
-        .collect();
+/// A node or edge of a batch op, resolved before the graph is locked
+#[derive(Debug, Clone, Copy)]
+enum BatchRef {
+    /// Existing node handle / edge id
+    Id(u32),
+    /// Created by an earlier op (index among the nodes / edges the batch creates)
+    Created(usize),
+}
 
-    processor.set_edge_gains_batch(&batch);
-    Ok(())
+/// A batch op with its aliases resolved (nodes and plugin names are held by the batch)
+#[derive(Debug, Clone, Copy)]
+enum BatchOp {
+    AddBus {
+        bus: usize,
+    },
+    AddPlugin {
+        bus: BatchRef,
+        plugin: usize,
+        position: Option<usize>,
+    },
+    RemoveNode {
+        node: BatchRef,
+    },
+    AddEdge {
+        source: BatchRef,
+        source_port: PortId,
+        target: BatchRef,
+        target_port: PortId,
+        channels: u8,
+        gain: f32,
+        muted: bool,
+    },
+    RemoveEdge {
+        edge: BatchRef,
+    },
+    SetEdgeGain {
+        edge: BatchRef,
+        gain: f32,
+    },
+    SetEdgeMuted {
+        edge: BatchRef,
+        muted: bool,
+    },
 }
 
-// =============================================================================
-// Output Commands
-// =============================================================================
+/// What an alias of the batch names (index in creation order)
+enum BatchAlias {
+    Node(usize),
+    Edge(usize),
+    Plugin(usize),
+}
 
-/// Set output (sink/vout) gain (linear). Applied per-sink during summing.
-#[tauri::command]
-pub async fn set_output_gain(output_handle: u32, gain: f32) -> Result<(), String> {
-    let processor = get_graph_processor();
-    let handle = NodeHandle::from_raw(output_handle);
+/// A pre-created plugin waiting to be added to a bus
+struct BatchPlugin {
+    instance_id: String,
+    plugin_id: String,
+    name: String,
+    manufacturer: String,
+}
 
-    let updated = processor.with_graph_mut(|graph| {
-        let Some(node) = graph.get_node_mut(handle) else {
-            return false;
-        };
-        let Some(sink) = node
-            .as_any_mut()
-            .downcast_mut::<crate::audio::sink::SinkNode>()
-        else {
-            return false;
-        };
-        // RT-safe atomic store inside the SinkNode.
-        sink.set_output_gain(gain);
-        true
-    });
+/// How to revert one applied batch op
+enum BatchUndo {
+    Node(NodeHandle),
+    Plugin(NodeHandle, usize),
+    Edge(EdgeId),
+    Gain(EdgeId, f32),
+    Muted(EdgeId, bool),
+}
 
-    if updated {
-        Ok(())
-    } else {
-        Err(format!(
-            "Node {} is not an output (sink) node or was not found",
-            output_handle
-        ))
-    }
+/// Side effects of a committed batch that are handled outside the graph lock
+struct BatchCommitted {
+    removed_plugins: Vec<String>,
+    removed_edges: Vec<EdgeId>,
+    gains: Vec<(EdgeId, f32)>,
 }
 
-/// Set output (sink/vout) gain for a specific channel/port (linear).
+/// An `execute_batch`: planned on the control thread, run at a block boundary
 ///
-/// `channel` is the port index relative to the sink (0..channel_count).
-#[tauri::command]
-pub async fn set_output_channel_gain(
-    output_handle: u32,
-    channel: u32,
-    gain: f32,
-) -> Result<(), String> {
-    let processor = get_graph_processor();
-    let handle = NodeHandle::from_raw(output_handle);
-    let ch = channel as usize;
+/// グラフへの適用（`run`）は確保・ログ・文字列の組み立てをしない。作るノードと
+/// プラグインの名前は事前に用意し、記録用の Vec は op 数ぶん確保しておく。
+/// 外したノードは持ち帰り、プラグインの一覧を読んでから reclaim スレッドに渡す。
+struct Batch {
+    ops: Vec<BatchOp>,
+    aliases: Vec<(String, BatchAlias)>,
+    /// Buses of the AddBus ops (taken when applied; the rest is dropped with the batch)
+    buses: Vec<Option<Box<dyn AudioNode>>>,
+    /// Plugins of the AddPlugin ops (taken when applied)
+    plugins: Vec<Option<BatchPlugin>>,
+    /// Instance ids of `plugins` (kept for undo and the result)
+    plugin_ids: Vec<String>,
+    /// Handles of the created buses / ids of the created edges
+    nodes: Vec<NodeHandle>,
+    edges: Vec<EdgeId>,
+    undo: Vec<BatchUndo>,
+    remove_nodes: Vec<NodeHandle>,
+    remove_edges: Vec<EdgeId>,
+    gains: Vec<(EdgeId, f32)>,
+    removed_nodes: Vec<Box<dyn AudioNode>>,
+    removed_edges: Vec<EdgeId>,
+    applied: usize,
+}
 
-    let updated = processor.with_graph_mut(|graph| {
-        let Some(node) = graph.get_node_mut(handle) else {
-            return Err("not_found".to_string());
+impl Batch {
+    /// Resolve aliases and build the buses (control thread, graph read-locked)
+    fn plan(
+        graph: &AudioGraph,
+        ops: &[GraphOpDto],
+        instances: Vec<(String, String, String)>,
+    ) -> Result<Self, SpectrumError> {
+        let mut node_aliases: HashMap<&str, usize> = HashMap::new();
+        let mut edge_aliases: HashMap<&str, usize> = HashMap::new();
+        let resolve = |aliases: &HashMap<&str, usize>, r: &BatchRefDto, kind: &str| match r {
+            BatchRefDto::Id(raw) => Ok(BatchRef::Id(*raw)),
+            BatchRefDto::Alias(alias) => aliases
+                .get(alias.as_str())
+                .map(|&index| BatchRef::Created(index))
+                .ok_or_else(|| {
+                    SpectrumError::InvalidArgument(format!("Unknown {} alias {:?}", kind, alias))
+                }),
         };
-        let port_count = node.input_port_count();
-        let Some(sink) = node
-            .as_any_mut()
-            .downcast_mut::<crate::audio::sink::SinkNode>()
-        else {
-            return Err("not_sink".to_string());
+
+        let mut added_edges = 0;
+        let mut labels = BusLabels::new(graph);
+        let mut instances = instances.into_iter();
+        let mut batch = Self {
+            ops: Vec::with_capacity(ops.len()),
+            aliases: Vec::new(),
+            buses: Vec::new(),
+            plugins: Vec::new(),
+            plugin_ids: Vec::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            undo: Vec::with_capacity(ops.len()),
+            remove_nodes: Vec::with_capacity(ops.len()),
+            remove_edges: Vec::with_capacity(ops.len()),
+            gains: Vec::with_capacity(ops.len()),
+            removed_nodes: Vec::new(),
+            removed_edges: Vec::new(),
+            applied: 0,
         };
+        for op in ops {
+            let planned = match op {
+                GraphOpDto::AddBus {
+                    alias,
+                    label,
+                    port_count,
+                } => {
+                    let label = match label {
+                        Some(label) => {
+                            labels.claim(label);
+                            label.clone()
+                        }
+                        None => labels.next_label(),
+                    };
+                    let bus = batch.buses.len();
+                    batch
+                        .buses
+                        .push(Some(new_bus_node(&label, port_count.unwrap_or(2))));
+                    if let Some(alias) = alias {
+                        node_aliases.insert(alias, bus);
+                        batch.aliases.push((alias.clone(), BatchAlias::Node(bus)));
+                    }
+                    BatchOp::AddBus { bus }
+                }
+                GraphOpDto::AddPlugin {
+                    alias,
+                    bus,
+                    plugin_id,
+                    position,
+                } => {
+                    let bus = resolve(&node_aliases, bus, "node")?;
+                    let Some((instance_id, name, manufacturer)) = instances.next() else {
+                        return Err(SpectrumError::Other(format!(
+                            "No instance for plugin {}",
+                            plugin_id
+                        )));
+                    };
+                    let plugin = batch.plugins.len();
+                    batch.plugin_ids.push(instance_id.clone());
+                    batch.plugins.push(Some(BatchPlugin {
+                        instance_id,
+                        plugin_id: plugin_id.clone(),
+                        name,
+                        manufacturer,
+                    }));
+                    if let Some(alias) = alias {
+                        batch
+                            .aliases
+                            .push((alias.clone(), BatchAlias::Plugin(plugin)));
+                    }
+                    BatchOp::AddPlugin {
+                        bus,
+                        plugin,
+                        position: *position,
+                    }
+                }
+                GraphOpDto::RemoveNode { node } => BatchOp::RemoveNode {
+                    node: resolve(&node_aliases, node, "node")?,
+                },
+                GraphOpDto::AddEdge {
+                    alias,
+                    source,
+                    source_port,
+                    target,
+                    target_port,
+                    gain,
+                    muted,
+                    channels,
+                } => {
+                    let planned = BatchOp::AddEdge {
+                        source: resolve(&node_aliases, source, "node")?,
+                        source_port: PortId::from(*source_port),
+                        target: resolve(&node_aliases, target, "node")?,
+                        target_port: PortId::from(*target_port),
+                        channels: channels.unwrap_or(1),
+                        gain: gain.unwrap_or(1.0),
+                        muted: muted.unwrap_or(false),
+                    };
+                    let edge = added_edges;
+                    added_edges += 1;
+                    if let Some(alias) = alias {
+                        edge_aliases.insert(alias, edge);
+                        batch.aliases.push((alias.clone(), BatchAlias::Edge(edge)));
+                    }
+                    planned
+                }
+                GraphOpDto::RemoveEdge { edge } => BatchOp::RemoveEdge {
+                    edge: resolve(&edge_aliases, edge, "edge")?,
+                },
+                GraphOpDto::SetEdgeGain { edge, gain } => BatchOp::SetEdgeGain {
+                    edge: resolve(&edge_aliases, edge, "edge")?,
+                    gain: *gain,
+                },
+                GraphOpDto::SetEdgeMuted { edge, muted } => BatchOp::SetEdgeMuted {
+                    edge: resolve(&edge_aliases, edge, "edge")?,
+                    muted: *muted,
+                },
+            };
+            batch.ops.push(planned);
+        }
+        batch.nodes.reserve(batch.buses.len());
+        batch.edges.reserve(added_edges);
+        batch.removed_nodes.reserve(batch.ops.len());
+        batch.removed_edges.reserve(batch.ops.len());
+        Ok(batch)
+    }
 
-        if ch >= port_count {
-            return Err("bad_channel".to_string());
+    /// Apply the ops in order; on failure the applied ones are reverted (audio thread)
+    fn run(&mut self, graph: &mut AudioGraph) -> Result<(), (usize, SpectrumError)> {
+        // 適用中は self を変更するので、op の列は一時的に取り出す（確保しない）
+        let ops = std::mem::take(&mut self.ops);
+        let failed = ops
+            .iter()
+            .enumerate()
+            .find_map(|(index, &op)| self.apply(graph, op).err().map(|e| (index, e)));
+        self.ops = ops;
+        match failed {
+            Some(failure) => {
+                self.rollback(graph);
+                Err(failure)
+            }
+            None => {
+                self.commit(graph);
+                Ok(())
+            }
         }
+    }
 
-        // RT-safe atomic store inside the SinkNode.
-        sink.set_output_gain_for_port(ch, gain);
+    fn apply(&mut self, graph: &mut AudioGraph, op: BatchOp) -> Result<(), SpectrumError> {
+        match op {
+            BatchOp::AddBus { bus } => {
+                if let Some(node) = self.buses[bus].take() {
+                    let handle = graph.add_node(node);
+                    self.undo.push(BatchUndo::Node(handle));
+                    self.nodes.push(handle);
+                }
+            }
+            BatchOp::AddPlugin {
+                bus,
+                plugin,
+                position,
+            } => {
+                let handle = self.node(graph, bus)?;
+                let bus = graph
+                    .get_node_mut(handle)
+                    .and_then(|node| node.as_any_mut().downcast_mut::<BusNode>())
+                    .ok_or(SpectrumError::WrongNodeType {
+                        handle: handle.raw(),
+                        expected: "bus",
+                    })?;
+                if let Some(p) = self.plugins[plugin].take() {
+                    bus.add_plugin(p.instance_id, p.plugin_id, p.name, p.manufacturer);
+                    if let Some(position) = position {
+                        bus.move_plugin(&self.plugin_ids[plugin], position);
+                    }
+                    self.undo.push(BatchUndo::Plugin(handle, plugin));
+                }
+            }
+            BatchOp::RemoveNode { node } => {
+                let handle = self.node(graph, node)?;
+                self.remove_nodes.push(handle);
+            }
+            BatchOp::AddEdge {
+                source,
+                source_port,
+                target,
+                target_port,
+                channels,
+                gain,
+                muted,
+            } => {
+                let source = self.node(graph, source)?;
+                let target = self.node(graph, target)?;
+                if graph.has_path(target, source) {
+                    return Err(SpectrumError::GraphCycle {
+                        source: source.raw(),
+                        target: target.raw(),
+                    });
+                }
+                let id = graph
+                    .add_bundle_edge_with_params(
+                        source,
+                        source_port,
+                        target,
+                        target_port,
+                        channels,
+                        gain,
+                        muted,
+                    )
+                    .ok_or(SpectrumError::EdgeConflict {
+                        source: source.raw(),
+                        target: target.raw(),
+                    })?;
+                self.undo.push(BatchUndo::Edge(id));
+                self.edges.push(id);
+            }
+            BatchOp::RemoveEdge { edge } => {
+                let id = self.edge(graph, edge)?;
+                self.remove_edges.push(id);
+            }
+            BatchOp::SetEdgeGain { edge, gain } => {
+                let id = self.edge(graph, edge)?;
+                if let Some(edge) = graph.get_edge(id) {
+                    self.undo.push(BatchUndo::Gain(id, edge.gain()));
+                    edge.set_gain(gain);
+                    self.gains.push((id, gain));
+                }
+            }
+            BatchOp::SetEdgeMuted { edge, muted } => {
+                let id = self.edge(graph, edge)?;
+                if let Some(edge) = graph.get_edge(id) {
+                    self.undo.push(BatchUndo::Muted(id, edge.muted()));
+                    edge.set_muted(muted);
+                }
+            }
+        }
+        self.applied += 1;
         Ok(())
-    });
+    }
 
-    match updated {
-        Ok(()) => Ok(()),
-        Err(tag) if tag == "not_found" => Err(format!("Node {} was not found", output_handle)),
-        Err(tag) if tag == "not_sink" => Err(format!(
-            "Node {} is not an output (sink) node or was not found",
-            output_handle
-        )),
-        Err(tag) if tag == "bad_channel" => Err(format!(
-            "Channel {} is out of range for sink node {}",
-            channel, output_handle
-        )),
-        Err(e) => Err(e),
+    /// Existing node that is not removed by this batch
+    fn node(&self, graph: &AudioGraph, node: BatchRef) -> Result<NodeHandle, SpectrumError> {
+        let handle = match node {
+            BatchRef::Id(raw) => NodeHandle::from_raw(raw),
+            // 作った op は先に適用済み（失敗していればバッチはそこで止まる）
+            BatchRef::Created(index) => self.nodes[index],
+        };
+        if graph.get_node(handle).is_none() || self.remove_nodes.contains(&handle) {
+            return Err(SpectrumError::NodeNotFound(handle.raw()));
+        }
+        Ok(handle)
     }
-}
 
-// =============================================================================
-// Plugin Commands
-// =============================================================================
+    /// Existing edge that is not removed by this batch (directly or with its nodes)
+    fn edge(&self, graph: &AudioGraph, edge: BatchRef) -> Result<EdgeId, SpectrumError> {
+        let id = match edge {
+            BatchRef::Id(raw) => EdgeId::from(raw),
+            BatchRef::Created(index) => self.edges[index],
+        };
+        let removed = |e: &Edge| {
+            self.remove_edges.contains(&e.id)
+                || self.remove_nodes.contains(&e.source)
+                || self.remove_nodes.contains(&e.target)
+        };
+        match graph.get_edge(id) {
+            Some(e) if !removed(e) => Ok(id),
+            _ => Err(SpectrumError::EdgeNotFound(id.raw())),
+        }
+    }
 
-#[tauri::command]
-pub async fn get_available_plugins() -> Result<Vec<PluginInfoDto>, String> {
-    let plugins = crate::audio_unit::get_effect_audio_units();
-    Ok(plugins
-        .into_iter()
-        .map(|p| PluginInfoDto {
-            plugin_id: p.id.clone(),
-            name: p.name.clone(),
-            manufacturer: p.manufacturer.clone(),
-        })
-        .collect())
-}
+    /// Revert every applied op, newest first
+    fn rollback(&mut self, graph: &mut AudioGraph) {
+        while let Some(undo) = self.undo.pop() {
+            match undo {
+                BatchUndo::Node(handle) => {
+                    graph.remove_node(handle);
+                }
+                BatchUndo::Plugin(handle, plugin) => {
+                    if let Some(bus) = graph
+                        .get_node_mut(handle)
+                        .and_then(|node| node.as_any_mut().downcast_mut::<BusNode>())
+                    {
+                        bus.remove_plugin(&self.plugin_ids[plugin]);
+                    }
+                }
+                BatchUndo::Edge(id) => {
+                    graph.remove_edge(id);
+                }
+                BatchUndo::Gain(id, gain) => {
+                    graph.set_edge_gain(id, gain);
+                }
+                BatchUndo::Muted(id, muted) => {
+                    graph.set_edge_muted(id, muted);
+                }
+            }
+        }
+    }
 
-#[tauri::command]
-pub async fn add_plugin_to_bus(
-    bus_handle: u32,
-    plugin_id: String,
-    position: Option<usize>,
-) -> Result<String, String> {
-    let handle = NodeHandle::from_raw(bus_handle);
-    let processor = get_graph_processor();
-
-    // Get plugin info
-    let plugins = crate::audio_unit::get_effect_audio_units();
-    let plugin = plugins
-        .iter()
-        .find(|p| p.id == plugin_id)
-        .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
-
-    // Create the real AudioUnit instance in the manager (async for better UI responsiveness)
-    let au_manager = crate::audio_unit::get_au_manager();
-
-    // Use oneshot channel to await the async result
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    let plugin_clone = plugin.clone();
-
-    au_manager.create_instance_async(&plugin_clone, move |result| {
-        let _ = tx.send(result);
-    });
-
-    let instance_id = rx
-        .await
-        .map_err(|_| "Failed to receive instance creation result".to_string())??;
-
-    // Add the plugin reference to the bus node
-    let plugin_name = plugin.name.clone();
-    let plugin_manufacturer = plugin.manufacturer.clone();
-    let instance_id_clone = instance_id.clone();
-    processor.with_graph_mut(|graph| {
-        if let Some(node) = graph.get_node_mut(handle) {
-            if let Some(bus) = node.as_any_mut().downcast_mut::<BusNode>() {
-                bus.add_plugin(
-                    instance_id_clone.clone(),
-                    plugin_id.clone(),
-                    plugin_name,
-                    plugin_manufacturer,
-                );
+    /// Apply the deferred removals (removed nodes are kept for `finish`)
+    fn commit(&mut self, graph: &mut AudioGraph) {
+        for &id in &self.remove_edges {
+            if graph.remove_edge(id) {
+                self.removed_edges.push(id);
+            }
+        }
+        for &handle in &self.remove_nodes {
+            if let Some(node) = graph.take_node(handle) {
+                self.removed_nodes.push(node);
+            }
+        }
+    }
 
-                if let Some(pos) = position {
-                    // Reorder if a position was specified
-                    let mut ids: Vec<String> = bus
-                        .plugins()
-                        .iter()
-                        .map(|p| p.instance_id.clone())
-                        .collect();
-                    if let Some(current_idx) = ids.iter().position(|id| id == &instance_id_clone) {
-                        let id = ids.remove(current_idx);
-                        ids.insert(pos.min(ids.len()), id);
-                        bus.reorder_plugins(&ids);
+    /// What the aliases resolved to, and the side effects left for the control thread
+    fn finish(self) -> (BatchResultDto, BatchCommitted) {
+        let mut result = BatchResultDto {
+            applied: self.applied,
+            ..Default::default()
+        };
+        for (alias, target) in self.aliases {
+            match target {
+                BatchAlias::Node(index) => {
+                    if let Some(handle) = self.nodes.get(index) {
+                        result.nodes.insert(alias, handle.raw());
+                    }
+                }
+                BatchAlias::Edge(index) => {
+                    if let Some(id) = self.edges.get(index) {
+                        result.edges.insert(alias, id.raw());
                     }
                 }
+                BatchAlias::Plugin(index) => {
+                    result.plugins.insert(alias, self.plugin_ids[index].clone());
+                }
             }
         }
-    });
 
-    Ok(instance_id)
+        let removed_plugins = self
+            .removed_nodes
+            .iter()
+            .flat_map(|node| node_plugin_instances(node.as_ref()))
+            .collect();
+        for node in self.removed_nodes {
+            reclaim::retire(Retired::Node(node));
+        }
+        let committed = BatchCommitted {
+            removed_plugins,
+            removed_edges: self.removed_edges,
+            gains: self.gains,
+        };
+        (result, committed)
+    }
 }
 
-#[tauri::command]
-pub async fn remove_plugin_from_bus(bus_handle: u32, instance_id: String) -> Result<(), String> {
-    let handle = NodeHandle::from_raw(bus_handle);
-    let processor = get_graph_processor();
-
-    // If the plugin UI is open, close it first (must run on main thread).
-    // Best-effort: if closing times out, we still proceed with removal.
-    {
-        let instance_id_clone = instance_id.clone();
-        let (tx, rx) = std::sync::mpsc::channel::<()>();
+// =============================================================================
+// Edge Commands (Hot Path - Realtime Parameter Changes)
+// =============================================================================
 
-        unsafe {
-            use block2::RcBlock;
-            use objc2::class;
-            use objc2::msg_send;
-            use objc2::runtime::AnyObject;
+#[tauri::command]
+pub async fn set_edge_gain(id: u32, gain: f32) -> Result<(), SpectrumError> {
+    set_edge_params(id, Some(gain), None, None).await
+}
 
-            let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+#[tauri::command]
+pub async fn set_edge_muted(id: u32, muted: bool) -> Result<(), SpectrumError> {
+    set_edge_params(id, None, Some(muted), None).await
+}
 
-            let block = RcBlock::new(move || {
-                crate::audio_unit_ui::close_audio_unit_ui(&instance_id_clone);
-                let _ = tx.send(());
-            });
+/// Set gain, mute and pan of an edge in one call (omitted values are unchanged)
+///
+/// 値はエッジごとの mailbox に入り、オーディオスレッドが次のブロックの頭で最新値だけを反映する。
+/// フェーダー操作中に大量に呼んでもグラフのロックを取らない。
+#[tauri::command]
+pub async fn set_edge_params(
+    id: u32,
+    gain: Option<f32>,
+    muted: Option<bool>,
+    pan: Option<f32>,
+) -> Result<(), SpectrumError> {
+    let update = EdgeParamUpdate { gain, muted, pan };
+    if update.is_empty() {
+        return Ok(());
+    }
+    let edge_id = EdgeId::from(id);
+    if !get_graph_processor().post_edge_params(edge_id, update) {
+        return Err(SpectrumError::EdgeNotFound(id));
+    }
+    if let Some(gain) = gain {
+        crossfade::cancel(edge_id);
+        automation::record_gain(edge_id, gain);
+    }
+    emit_graph_event(GraphEventDto::EdgeChanged { id, gain, muted });
+    apply_edge_links(edge_id, gain, muted, &[]);
+    Ok(())
+}
 
-            let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+/// Carry a gain/mute change over to the edges linked with `edge_id` (see `link_edges`)
+///
+/// `skip` のエッジは呼び出し側が個別に値を設定するので触らない（バッチ更新）。
+pub(crate) fn apply_edge_links(
+    edge_id: EdgeId,
+    gain: Option<f32>,
+    muted: Option<bool>,
+    skip: &[EdgeId],
+) {
+    if gain.is_none() && muted.is_none() {
+        return;
+    }
+    let Some(group) = edge_link::group_of(edge_id) else {
+        return;
+    };
+    let gains = gain
+        .map(|gain| group.follow_gain(edge_id, gain))
+        .unwrap_or_default();
+    let processor = get_graph_processor();
+    for member in group.others(edge_id) {
+        if skip.contains(&member) {
+            continue;
         }
-
-        if rx.recv_timeout(std::time::Duration::from_secs(2)).is_err() {
-            eprintln!(
-                "[api] remove_plugin_from_bus: timeout closing UI for instance {}",
-                instance_id
-            );
+        let member_gain = gains.iter().find(|(e, _)| *e == member).map(|&(_, g)| g);
+        let update = EdgeParamUpdate {
+            gain: member_gain,
+            muted,
+            pan: None,
+        };
+        if !processor.post_edge_params(member, update) {
+            continue;
         }
-    }
-
-    let mut found_in_bus = false;
-    processor.with_graph_mut(|graph| {
-        if let Some(node) = graph.get_node_mut(handle) {
-            if let Some(bus) = node.as_any_mut().downcast_mut::<BusNode>() {
-                if bus.remove_plugin(&instance_id).is_some() {
-                    found_in_bus = true;
-                }
-            }
+        if let Some(gain) = member_gain {
+            crossfade::cancel(member);
+            automation::record_gain(member, gain);
         }
-    });
+        emit_graph_event(GraphEventDto::EdgeChanged {
+            id: member.raw(),
+            gain: member_gain,
+            muted,
+        });
+    }
+}
 
-    // Also remove from the manager to release resources
-    let au_manager = crate::audio_unit::get_au_manager();
-    let removed_from_manager = au_manager.remove_instance(&instance_id);
+/// Set the trim of one channel inside a bundle edge (linear)
+#[tauri::command]
+pub async fn set_edge_channel_trim(id: u32, channel: u8, trim: f32) -> Result<(), SpectrumError> {
+    let processor = get_graph_processor();
 
-    if found_in_bus || removed_from_manager {
+    if processor.set_edge_channel_trim(EdgeId::from(id), channel as usize, trim) {
+        emit_graph_event(GraphEventDto::EdgeChanged {
+            id,
+            gain: None,
+            muted: None,
+        });
         Ok(())
     } else {
-        Err(format!("Plugin instance not found: {}", instance_id))
+        Err(SpectrumError::InvalidArgument(format!(
+            "Edge {} has no channel {}",
+            id, channel
+        )))
     }
 }
 
+/// Set edge gain in dBFS (`null` = -inf)
 #[tauri::command]
-pub async fn reorder_plugins(bus_handle: u32, instance_ids: Vec<String>) -> Result<(), String> {
-    let handle = NodeHandle::from_raw(bus_handle);
-    let processor = get_graph_processor();
+pub async fn set_edge_gain_db(id: u32, db: Option<f32>) -> Result<f32, SpectrumError> {
+    let gain = db_to_linear(db);
+    set_edge_gain(id, gain).await?;
+    Ok(gain)
+}
 
-    processor.with_graph_mut(|graph| {
-        if let Some(node) = graph.get_node_mut(handle) {
-            if let Some(bus) = node.as_any_mut().downcast_mut::<BusNode>() {
-                bus.reorder_plugins(&instance_ids);
-            }
-        }
-    });
+/// Get the fader taper used for position <-> dB conversion
+#[tauri::command]
+pub async fn get_fader_taper() -> Result<FaderTaperDto, SpectrumError> {
+    let taper = fader_taper();
+    Ok(FaderTaperDto {
+        points: taper
+            .points()
+            .iter()
+            .map(|&(position, db)| TaperPointDto { position, db })
+            .collect(),
+        min_db: MIN_DB,
+        max_db: MAX_DB,
+    })
+}
 
-    Ok(())
+/// Replace the fader taper table
+#[tauri::command]
+pub async fn set_fader_taper(points: Vec<TaperPointDto>) -> Result<FaderTaperDto, SpectrumError> {
+    let taper = FaderTaper::new(points.iter().map(|p| (p.position, p.db)).collect())?;
+    crate::audio::taper::set_fader_taper(taper);
+    log_info!("[Taper] Fader taper updated ({} points)", points.len());
+    get_fader_taper().await
 }
 
+/// Set edge pan (-1.0 = L, 1.0 = R) and optionally the pan law ("0dB", "-3dB", "-4.5dB", "-6dB")
 #[tauri::command]
-pub async fn set_plugin_enabled(
-    bus_handle: u32,
-    instance_id: String,
-    enabled: bool,
-) -> Result<(), String> {
-    let handle = NodeHandle::from_raw(bus_handle);
+pub async fn set_edge_pan(id: u32, pan: f32, pan_law: Option<String>) -> Result<(), SpectrumError> {
+    let law =
+        match pan_law {
+            Some(law) => Some(PanLaw::parse(&law).ok_or_else(|| {
+                SpectrumError::InvalidArgument(format!("Unknown pan law: {}", law))
+            })?),
+            None => None,
+        };
     let processor = get_graph_processor();
 
-    let mut found_in_bus = false;
-    processor.with_graph_mut(|graph| {
-        if let Some(node) = graph.get_node_mut(handle) {
-            if let Some(bus) = node.as_any_mut().downcast_mut::<BusNode>() {
-                if bus.set_plugin_enabled(&instance_id, enabled) {
-                    found_in_bus = true;
-                }
-            }
-        }
-    });
-
-    // Best-effort: also set AU manager's enabled flag (lock-free atomic).
-    // Even if the bus doesn't have the instance (stale UI), we keep behavior consistent.
-    let au_manager = crate::audio_unit::get_au_manager();
-    let _ = au_manager.set_enabled(&instance_id, enabled);
-
-    if found_in_bus {
+    // パンローの変更はまれなので直接、パンだけなら mailbox 経由
+    let applied = match law {
+        Some(law) => processor.set_edge_pan(EdgeId::from(id), pan, Some(law)),
+        None => processor.post_edge_params(
+            EdgeId::from(id),
+            EdgeParamUpdate {
+                pan: Some(pan),
+                ..Default::default()
+            },
+        ),
+    };
+    if applied {
+        emit_graph_event(GraphEventDto::EdgeChanged {
+            id,
+            gain: None,
+            muted: None,
+        });
         Ok(())
     } else {
-        Err(format!("Plugin instance not found in bus: {}", instance_id))
+        Err(SpectrumError::EdgeNotFound(id))
     }
 }
 
+/// Set the sum law of an edge ("0dB", "-3dB" or "-6dB")
+///
+/// A bundle wider than its target (e.g. a stereo edge into a mono bus or sink) folds
+/// its channels onto the target ports and is attenuated by this amount.
 #[tauri::command]
-pub async fn open_plugin_ui(instance_id: String) -> Result<(), String> {
-    // Verify the instance exists first
-    let _au_instance = crate::audio_unit::get_au_manager()
-        .get_instance(&instance_id)
-        .ok_or_else(|| format!("Plugin instance not found: {}", instance_id))?;
-
-    // UI operations must run on main thread
-    // We need to dispatch to main thread and wait for completion
-    let instance_id_clone = instance_id.clone();
-
-    let (tx, rx) = std::sync::mpsc::channel::<Result<(), String>>();
-
-    // Dispatch to main thread
-    unsafe {
-        use block2::RcBlock;
-        use objc2::class;
-        use objc2::msg_send;
-        use objc2::runtime::AnyObject;
-
-        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
-
-        let block = RcBlock::new(move || {
-            let result = crate::audio_unit_ui::open_plugin_ui_by_instance_id(&instance_id_clone);
-            let _ = tx.send(result);
+pub async fn set_edge_sum_law(id: u32, sum_law: String) -> Result<(), SpectrumError> {
+    let law = SumLaw::parse(&sum_law)
+        .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown sum law: {}", sum_law)))?;
+
+    if get_graph_processor().set_edge_sum_law(EdgeId::from(id), law) {
+        emit_graph_event(GraphEventDto::EdgeChanged {
+            id,
+            gain: None,
+            muted: None,
         });
-
-        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+        Ok(())
+    } else {
+        Err(SpectrumError::EdgeNotFound(id))
     }
-
-    // Wait for result with timeout
-    rx.recv_timeout(std::time::Duration::from_secs(5))
-        .map_err(|_| "Timeout waiting for UI to open".to_string())?
 }
 
+/// Set an edge's monitoring path ("direct" or "buffered")
+///
+/// Direct monitoring is only available for an input-device source wired to a
+/// sink on the same device. A running output on that device is restarted when
+/// its unit needs to gain or drop the input side.
 #[tauri::command]
-pub async fn close_plugin_ui(instance_id: String) -> Result<(), String> {
-    let instance_id_clone = instance_id.clone();
-    let (tx, rx) = std::sync::mpsc::channel::<()>();
+pub async fn set_monitor_mode(edge_id: u32, mode: String) -> Result<(), SpectrumError> {
+    let mode = MonitorMode::parse(&mode)
+        .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown monitor mode: {}", mode)))?;
+    let processor = get_graph_processor();
 
-    unsafe {
-        use block2::RcBlock;
-        use objc2::class;
-        use objc2::msg_send;
-        use objc2::runtime::AnyObject;
+    let (device_id, was_direct, is_direct) = processor.with_graph(|graph| {
+        let device_id = graph.direct_monitor_device(EdgeId::from(edge_id));
+        let was_direct = device_id.is_some_and(|d| graph.has_direct_monitor(d));
+        let device_id = graph.set_edge_monitor_mode(EdgeId::from(edge_id), mode)?;
+        Ok::<_, SpectrumError>((device_id, was_direct, graph.has_direct_monitor(device_id)))
+    })?;
 
-        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+    if was_direct != is_direct && crate::audio::output::is_output_device_running(device_id) {
+        crate::audio::output::restart_output_device(device_id)?;
+    }
 
-        let block = RcBlock::new(move || {
-            crate::audio_unit_ui::close_audio_unit_ui(&instance_id_clone);
-            let _ = tx.send(());
-        });
+    log_info!(
+        "[Spectrum] Edge {} monitor mode: {}",
+        edge_id,
+        mode.as_str()
+    );
+    emit_graph_event(GraphEventDto::EdgeChanged {
+        id: edge_id,
+        gain: None,
+        muted: None,
+    });
+    Ok(())
+}
 
-        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+/// Pin an edge to the menu bar extra (quick mute toggle while the window is closed)
+#[tauri::command]
+pub async fn set_edge_pinned(edge_id: u32, pinned: bool) -> Result<(), SpectrumError> {
+    let found = get_graph_processor().with_graph(|graph| {
+        graph
+            .get_edge(EdgeId::from(edge_id))
+            .map(|edge| edge.set_pinned(pinned))
+            .is_some()
+    });
+    if !found {
+        return Err(SpectrumError::EdgeNotFound(edge_id));
     }
 
-    rx.recv_timeout(std::time::Duration::from_secs(5))
-        .map_err(|_| "Timeout waiting for UI to close".to_string())?;
-
+    log_info!("[Spectrum] Edge {} pinned: {}", edge_id, pinned);
+    emit_graph_event(GraphEventDto::EdgeChanged {
+        id: edge_id,
+        gain: None,
+        muted: None,
+    });
     Ok(())
 }
 
-// =============================================================================
-// Meter Commands
-// =============================================================================
-
+/// Set where an edge taps its bus source ("pre_plugin" or "post_plugin")
+///
+/// A pre-plugin send carries the bus input, so a monitor send is not affected by
+/// the bus plugin chain. Buses have no fader of their own; "post_gain" is accepted
+/// as the post-plugin point.
 #[tauri::command]
-pub async fn get_meters() -> Result<GraphMetersDto, String> {
+pub async fn set_edge_tap_point(edge_id: u32, tap: String) -> Result<(), SpectrumError> {
+    let tap = TapPoint::parse(&tap)
+        .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown tap point: {}", tap)))?;
     let processor = get_graph_processor();
-    let meters = processor.get_meters();
-    Ok(GraphMetersDto::from((*meters).clone()))
+
+    if processor.with_graph(|graph| graph.get_edge(EdgeId::from(edge_id)).is_none()) {
+        return Err(SpectrumError::EdgeNotFound(edge_id));
+    }
+    processor
+        .with_graph_mut(|graph| graph.set_edge_tap_point(EdgeId::from(edge_id), tap))
+        .map_err(SpectrumError::InvalidArgument)?;
+
+    log_info!("[Spectrum] Edge {} tap point: {}", edge_id, tap.as_str());
+    emit_graph_event(GraphEventDto::EdgeChanged {
+        id: edge_id,
+        gain: None,
+        muted: None,
+    });
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn get_node_meters(handles: Vec<u32>) -> Result<Vec<NodeMeterDto>, String> {
+pub async fn set_edge_gains_batch(updates: Vec<EdgeGainUpdate>) -> Result<(), SpectrumError> {
     let processor = get_graph_processor();
-    let meters = processor.get_meters();
 
-    let filtered: Vec<_> = meters
-        .nodes
-        .iter()
-        .filter(|m| handles.contains(&m.handle.raw()))
-        .map(|m| NodeMeterDto {
-            handle: m.handle.raw(),
-            inputs: m
-                .inputs
-                .iter()
-                .map(|p| PortMeterDto {
-                    peak: p.peak,
-                    rms: p.rms,
-                })
-                .collect(),
-            outputs: m
-                .outputs
-                .iter()
-                .map(|p| PortMeterDto {
-                    peak: p.peak,
-                    rms: p.rms,
-                })
-                .collect(),
-        })
+    let batch: Vec<_> = updates
+        .into_iter()
+        .map(|u| (EdgeId::from(u.id), u.gain))
         .collect();
 
-    Ok(filtered)
+    processor.set_edge_gains_batch(&batch);
+    let updated: Vec<EdgeId> = batch.iter().map(|&(id, _)| id).collect();
+    for &(id, gain) in &batch {
+        crossfade::cancel(id);
+        automation::record_gain(id, gain);
+        emit_graph_event(GraphEventDto::EdgeChanged {
+            id: id.raw(),
+            gain: Some(gain),
+            muted: None,
+        });
+        apply_edge_links(id, Some(gain), None, &updated);
+    }
+    Ok(())
 }
 
+/// Crossfade from `edge_a` to `edge_b` on the audio thread
+///
+/// `edge_a` fades from its current gain to 0; `edge_b` is unmuted and fades in from 0 to
+/// `target_gain` (default: the current gain of `edge_a`). `curve` is "equal_power" (default)
+/// or "linear". Moving either fader manually cancels the crossfade.
 #[tauri::command]
-pub async fn get_edge_meters(ids: Vec<u32>) -> Result<Vec<EdgeMeterDto>, String> {
+pub async fn crossfade_edges(
+    edge_a: u32,
+    edge_b: u32,
+    duration_ms: f32,
+    curve: Option<String>,
+    target_gain: Option<f32>,
+) -> Result<(), SpectrumError> {
+    if edge_a == edge_b {
+        return Err(SpectrumError::InvalidArgument(
+            "Cannot crossfade an edge with itself".to_string(),
+        ));
+    }
+    if !duration_ms.is_finite() || !(0.0..=MAX_CROSSFADE_MS).contains(&duration_ms) {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Crossfade duration must be 0-{} ms",
+            MAX_CROSSFADE_MS
+        )));
+    }
+    let curve = match curve {
+        Some(c) => CrossfadeCurve::parse(&c)
+            .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown curve: {}", c)))?,
+        None => CrossfadeCurve::default(),
+    };
+
     let processor = get_graph_processor();
-    let meters = processor.get_meters();
+    let from_gain = processor
+        .with_graph(|g| g.get_edge(EdgeId::from(edge_a)).map(|e| e.gain()))
+        .ok_or(SpectrumError::EdgeNotFound(edge_a))?;
+    let (id_a, id_b) = (EdgeId::from(edge_a), EdgeId::from(edge_b));
+    let to_gain = target_gain.unwrap_or(from_gain).max(0.0);
+
+    // B starts from silence
+    if !processor.set_edge_gain(id_b, 0.0) {
+        return Err(SpectrumError::EdgeNotFound(edge_b));
+    }
+    processor.set_edge_muted(id_b, false);
+    crossfade::start(Crossfade::new(
+        id_a,
+        id_b,
+        from_gain,
+        to_gain,
+        duration_ms,
+        curve,
+    ));
+    log_info!(
+        "[graph] crossfade edge {} -> {} ({} ms, {})",
+        edge_a,
+        edge_b,
+        duration_ms,
+        curve.as_str()
+    );
 
-    let filtered: Vec<_> = meters
-        .edges
-        .iter()
-        .filter(|m| ids.contains(&m.edge_id.raw()))
-        .map(|m| EdgeMeterDto {
-            edge_id: m.edge_id.raw(),
-            post_gain: PortMeterDto {
-                peak: m.post_gain.peak,
-                rms: m.post_gain.rms,
-            },
+    // Report the gains once the fade is over (or was cancelled by a fader move)
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(duration_ms as u64 + 20));
+        for id in [edge_a, edge_b] {
+            let state = get_graph_processor()
+                .with_graph(|g| g.get_edge(EdgeId::from(id)).map(|e| (e.gain(), e.muted())));
+            if let Some((gain, muted)) = state {
+                emit_graph_event(GraphEventDto::EdgeChanged {
+                    id,
+                    gain: Some(gain),
+                    muted: Some(muted),
+                });
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Set the edge gain smoothing time in milliseconds (0 = instant)
+#[tauri::command]
+pub async fn set_gain_ramp_time(ms: f32) -> Result<(), SpectrumError> {
+    get_graph_processor().set_gain_ramp_ms(ms);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_gain_ramp_time() -> Result<f32, SpectrumError> {
+    Ok(get_graph_processor().gain_ramp_ms())
+}
+
+// =============================================================================
+// Automation Commands
+// =============================================================================
+
+fn automation_status() -> AutomationStatusDto {
+    let state = automation::transport_state();
+    AutomationStatusDto {
+        playing: state.playing,
+        recording: state.recording,
+        position_ms: state.position_ms,
+        duration_ms: state.duration_ms,
+        lane_count: state.lane_count,
+    }
+}
+
+/// Drop lanes of edges that no longer exist (edge ids restart after a restore)
+fn prune_automation_lanes() {
+    let existing = get_graph_processor()
+        .with_graph(|g| g.edges().iter().map(|e| e.id).collect::<HashSet<_>>());
+    automation::retain_lanes(&existing);
+}
+
+/// Set the gain curve of an edge (breakpoints in ms from the transport start)
+///
+/// An empty list removes the curve.
+#[tauri::command]
+pub async fn set_edge_automation(
+    id: u32,
+    points: Vec<AutomationPointDto>,
+) -> Result<AutomationStatusDto, SpectrumError> {
+    let edge_id = EdgeId::from(id);
+    if !get_graph_processor().with_graph(|g| g.get_edge(edge_id).is_some()) {
+        return Err(SpectrumError::EdgeNotFound(id));
+    }
+    let points: Vec<Breakpoint> = points
+        .iter()
+        .map(|p| Breakpoint {
+            time_ms: p.time_ms,
+            gain: p.gain,
+        })
+        .collect();
+    if !automation::set_lane(edge_id, &points) && !points.is_empty() {
+        return Err(SpectrumError::InvalidArgument(
+            "Automation points must have finite time and gain".to_string(),
+        ));
+    }
+    prune_automation_lanes();
+    Ok(automation_status())
+}
+
+/// Current gain curve of an edge (empty if not automated)
+#[tauri::command]
+pub async fn get_edge_automation(id: u32) -> Result<Vec<AutomationPointDto>, SpectrumError> {
+    Ok(automation::lane(EdgeId::from(id))
+        .map(|lane| {
+            lane.points()
+                .iter()
+                .map(|p| AutomationPointDto {
+                    time_ms: p.time_ms,
+                    gain: p.gain,
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Start automation playback at `from_ms` (default 0)
+///
+/// With `record`, gain changes made through `set_edge_gain` are written as breakpoints
+/// at the transport position instead of playing the existing curve back.
+#[tauri::command]
+pub async fn start_automation(
+    from_ms: Option<f64>,
+    record: Option<bool>,
+) -> Result<AutomationStatusDto, SpectrumError> {
+    prune_automation_lanes();
+    let record = record.unwrap_or(false);
+    automation::start(from_ms.unwrap_or(0.0), record);
+    log_info!(
+        "[Automation] Started at {:.0} ms{}",
+        automation::position_ms(),
+        if record { " (recording)" } else { "" }
+    );
+    Ok(automation_status())
+}
+
+/// Stop automation playback (edges keep the gain they reached)
+#[tauri::command]
+pub async fn stop_automation() -> Result<AutomationStatusDto, SpectrumError> {
+    automation::stop();
+    log_info!(
+        "[Automation] Stopped at {:.0} ms",
+        automation::position_ms()
+    );
+
+    // Faders moved on the audio thread; let the frontend pick up the final values
+    let gains: Vec<(u32, f32)> = get_graph_processor().with_graph(|g| {
+        g.edges()
+            .iter()
+            .filter(|e| automation::lane(e.id).is_some())
+            .map(|e| (e.id.raw(), e.gain()))
+            .collect()
+    });
+    for (id, gain) in gains {
+        emit_graph_event(GraphEventDto::EdgeChanged {
+            id,
+            gain: Some(gain),
+            muted: None,
+        });
+    }
+    Ok(automation_status())
+}
+
+#[tauri::command]
+pub async fn get_automation_status() -> Result<AutomationStatusDto, SpectrumError> {
+    Ok(automation_status())
+}
+
+// =============================================================================
+// Solo Commands
+// =============================================================================
+
+fn solo_state_dto(graph: &AudioGraph) -> SoloStateDto {
+    let state = graph.solo_state();
+    let mut soloed_nodes: Vec<u32> = state.nodes.iter().map(|h| h.raw()).collect();
+    soloed_nodes.sort_unstable();
+
+    SoloStateDto {
+        mode: state.mode.as_str().to_string(),
+        monitor_sink: state.monitor.map(|h| h.raw()),
+        soloed_nodes,
+        soloed_edges: graph
+            .edges()
+            .iter()
+            .filter(|e| e.soloed())
+            .map(|e| e.id.raw())
+            .collect(),
+        solo_muted_edges: graph
+            .edges()
+            .iter()
+            .filter(|e| e.solo_muted())
+            .map(|e| e.id.raw())
+            .collect(),
+    }
+}
+
+#[tauri::command]
+pub async fn set_edge_solo(id: u32, solo: bool) -> Result<SoloStateDto, SpectrumError> {
+    let processor = get_graph_processor();
+
+    // ソロフラグは差し替え後に反映されるので、状態は変更が済んでから読む
+    if !processor.with_graph_mut(|graph| graph.set_edge_solo(EdgeId::from(id), solo)) {
+        return Err(SpectrumError::EdgeNotFound(id));
+    }
+    emit_graph_event(GraphEventDto::SoloChanged);
+    Ok(processor.with_graph(solo_state_dto))
+}
+
+#[tauri::command]
+pub async fn set_node_solo(handle: u32, solo: bool) -> Result<SoloStateDto, SpectrumError> {
+    let processor = get_graph_processor();
+
+    // ソロフラグは差し替え後に反映されるので、状態は変更が済んでから読む
+    if !processor.with_graph_mut(|graph| graph.set_node_solo(NodeHandle::from_raw(handle), solo)) {
+        return Err(SpectrumError::NodeNotFound(handle));
+    }
+    emit_graph_event(GraphEventDto::SoloChanged);
+    Ok(processor.with_graph(solo_state_dto))
+}
+
+/// Set solo mode ("sip", "afl", "pfl") and the sink used for AFL/PFL monitoring
+#[tauri::command]
+pub async fn set_solo_mode(
+    mode: String,
+    monitor_sink: Option<u32>,
+) -> Result<SoloStateDto, SpectrumError> {
+    let solo_mode = SoloMode::parse(&mode)
+        .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown solo mode: {}", mode)))?;
+    let processor = get_graph_processor();
+
+    processor.with_graph_mut(|graph| {
+        let monitor = monitor_sink.map(NodeHandle::from_raw);
+        if let Some(handle) = monitor {
+            let is_sink = graph
+                .get_node(handle)
+                .map(|n| n.node_type() == crate::audio::NodeType::Sink)
+                .unwrap_or(false);
+            if !is_sink {
+                return Err(SpectrumError::WrongNodeType {
+                    handle: handle.raw(),
+                    expected: "sink",
+                });
+            }
+        }
+        graph.set_solo_mode(solo_mode, monitor);
+        Ok(())
+    })?;
+
+    emit_graph_event(GraphEventDto::SoloChanged);
+    Ok(processor.with_graph(solo_state_dto))
+}
+
+#[tauri::command]
+pub async fn clear_solo() -> Result<SoloStateDto, SpectrumError> {
+    let processor = get_graph_processor();
+    processor.with_graph_mut(|graph| graph.clear_solo());
+    emit_graph_event(GraphEventDto::SoloChanged);
+    Ok(processor.with_graph(solo_state_dto))
+}
+
+#[tauri::command]
+pub async fn get_solo_state() -> Result<SoloStateDto, SpectrumError> {
+    Ok(get_graph_processor().with_graph(solo_state_dto))
+}
+
+// =============================================================================
+// Cue Commands
+// =============================================================================
+
+fn cue_state_dto(graph: &AudioGraph) -> CueStateDto {
+    CueStateDto {
+        sink: graph.cue_sink().map(|h| h.raw()),
+        edges: graph
+            .edges()
+            .iter()
+            .filter(|e| e.cue() != CueTap::Off)
+            .map(|e| CueEdgeDto {
+                id: e.id.raw(),
+                tap: e.cue().as_str().to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Designate the headphone cue sink (`null` turns cue monitoring off)
+#[tauri::command]
+pub async fn set_cue_sink(handle: Option<u32>) -> Result<CueStateDto, SpectrumError> {
+    let processor = get_graph_processor();
+    processor.with_graph_mut(|graph| {
+        let sink = handle.map(NodeHandle::from_raw);
+        if let Some(h) = sink {
+            if graph.get_node(h).is_none() {
+                return Err(SpectrumError::NodeNotFound(h.raw()));
+            }
+        }
+        if !graph.set_cue_sink(sink) {
+            return Err(SpectrumError::WrongNodeType {
+                handle: handle.unwrap_or(0),
+                expected: "sink",
+            });
+        }
+        Ok(())
+    })?;
+    emit_graph_event(GraphEventDto::CueChanged);
+    Ok(processor.with_graph(cue_state_dto))
+}
+
+/// Send an edge's source to the cue sink (`tap`: "pre_gain" (default) or "post_gain")
+///
+/// Pre-gain cue ignores the edge's fader and mute, so a source can be auditioned
+/// before it is un-muted in the main mix. Cue sends are not saved.
+#[tauri::command]
+pub async fn set_edge_cue(
+    edge_id: u32,
+    on: bool,
+    tap: Option<String>,
+) -> Result<CueStateDto, SpectrumError> {
+    let tap = if on {
+        match tap {
+            Some(t) => match CueTap::parse(&t) {
+                Some(CueTap::Off) | None => {
+                    return Err(SpectrumError::InvalidArgument(format!(
+                        "Unknown cue tap: {}",
+                        t
+                    )))
+                }
+                Some(tap) => tap,
+            },
+            None => CueTap::PreGain,
+        }
+    } else {
+        CueTap::Off
+    };
+
+    let state = get_graph_processor().with_graph(|graph| {
+        let edge = graph
+            .get_edge(EdgeId::from(edge_id))
+            .ok_or(SpectrumError::EdgeNotFound(edge_id))?;
+        edge.set_cue(tap);
+        Ok::<_, SpectrumError>(cue_state_dto(graph))
+    })?;
+    emit_graph_event(GraphEventDto::CueChanged);
+    Ok(state)
+}
+
+#[tauri::command]
+pub async fn get_cue_state() -> Result<CueStateDto, SpectrumError> {
+    Ok(get_graph_processor().with_graph(cue_state_dto))
+}
+
+// =============================================================================
+// Ducking Commands
+// =============================================================================
+
+fn ducking_rule_dto(rule: &DuckingRule) -> DuckingRuleDto {
+    let cfg = &rule.config;
+    DuckingRuleDto {
+        id: Some(rule.id),
+        key: cfg.key.raw(),
+        targets: cfg.targets.iter().map(|e| e.raw()).collect(),
+        threshold_db: cfg.threshold_db,
+        depth_db: cfg.depth_db,
+        attack_ms: cfg.attack_ms,
+        release_ms: cfg.release_ms,
+        enabled: cfg.enabled,
+        reduction_db: rule.reduction_db(),
+    }
+}
+
+/// Create or update a ducking rule (`rule.id` = None creates one)
+///
+/// While the key node's level is above `threshold_db`, the target edges are lowered by
+/// `depth_db` with the given attack/release. Fader values are not changed.
+#[tauri::command]
+pub async fn set_ducking_rule(rule: DuckingRuleDto) -> Result<DuckingRuleDto, SpectrumError> {
+    let config = DuckingConfig {
+        key: NodeHandle::from_raw(rule.key),
+        targets: rule.targets.iter().map(|&id| EdgeId::from(id)).collect(),
+        threshold_db: rule.threshold_db,
+        depth_db: rule.depth_db,
+        attack_ms: rule.attack_ms,
+        release_ms: rule.release_ms,
+        enabled: rule.enabled,
+    };
+
+    let id = get_graph_processor().with_graph(|graph| {
+        if graph.get_node(config.key).is_none() {
+            return Err(SpectrumError::NodeNotFound(rule.key));
+        }
+        if let Some(&missing) = rule
+            .targets
+            .iter()
+            .find(|&&id| graph.get_edge(EdgeId::from(id)).is_none())
+        {
+            return Err(SpectrumError::EdgeNotFound(missing));
+        }
+        ducking::set_rule(graph, rule.id, config).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!("Unknown ducking rule {:?}", rule.id))
+        })
+    })?;
+    log_info!(
+        "[graph] ducking rule {} (key={} targets={})",
+        id,
+        rule.key,
+        rule.targets.len()
+    );
+
+    ducking::rules()
+        .iter()
+        .find(|r| r.id == id)
+        .map(|r| ducking_rule_dto(r))
+        .ok_or_else(|| SpectrumError::InvalidState(format!("Ducking rule {} vanished", id)))
+}
+
+#[tauri::command]
+pub async fn remove_ducking_rule(id: u32) -> Result<(), SpectrumError> {
+    if get_graph_processor().with_graph(|graph| ducking::remove_rule(graph, id)) {
+        Ok(())
+    } else {
+        Err(SpectrumError::InvalidArgument(format!(
+            "Unknown ducking rule {}",
+            id
+        )))
+    }
+}
+
+/// Ducking rules with their current gain reduction
+#[tauri::command]
+pub async fn get_ducking_rules() -> Result<Vec<DuckingRuleDto>, SpectrumError> {
+    Ok(ducking::rules()
+        .iter()
+        .map(|r| ducking_rule_dto(r))
+        .collect())
+}
+
+// =============================================================================
+// Edge Link Commands
+// =============================================================================
+
+fn edge_link_group_dto(group: &EdgeLinkGroup) -> EdgeLinkGroupDto {
+    EdgeLinkGroupDto {
+        id: group.id,
+        edges: group.edges().map(|e| e.raw()).collect(),
+        preserve_offsets: group.preserve_offsets,
+    }
+}
+
+/// Link edges so changing the gain or mute of any member applies to all
+///
+/// With `preserve_offsets` the members keep their current gain differences (dB);
+/// otherwise they share one gain. Mute is always shared. Edges leave any previous
+/// group. Applies to every control path (UI, HTTP, hotkeys, URL scheme, tray).
+#[tauri::command]
+pub async fn link_edges(
+    edge_ids: Vec<u32>,
+    preserve_offsets: Option<bool>,
+) -> Result<EdgeLinkGroupDto, SpectrumError> {
+    let gains = get_graph_processor().with_graph(|graph| {
+        edge_link::retain_edges(|e| graph.get_edge(e).is_some());
+        edge_ids
+            .iter()
+            .map(|&id| {
+                graph
+                    .get_edge(EdgeId::from(id))
+                    .map(|edge| (edge.id, edge.gain()))
+                    .ok_or(SpectrumError::EdgeNotFound(id))
+            })
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    let group = edge_link::link(&gains, preserve_offsets.unwrap_or(false)).ok_or_else(|| {
+        SpectrumError::InvalidArgument("Linking needs at least two different edges".to_string())
+    })?;
+    log_info!(
+        "[graph] edge link group {} ({} edges, offsets={})",
+        group.id,
+        group.members.len(),
+        group.preserve_offsets
+    );
+    Ok(edge_link_group_dto(&group))
+}
+
+/// Remove an edge link group (its edges move independently again)
+#[tauri::command]
+pub async fn unlink_edges(group_id: u32) -> Result<(), SpectrumError> {
+    if edge_link::unlink(group_id) {
+        Ok(())
+    } else {
+        Err(SpectrumError::InvalidArgument(format!(
+            "Unknown edge link group {}",
+            group_id
+        )))
+    }
+}
+
+/// Edge link groups (removed edges are dropped)
+#[tauri::command]
+pub async fn get_edge_link_groups() -> Result<Vec<EdgeLinkGroupDto>, SpectrumError> {
+    get_graph_processor()
+        .with_graph(|graph| edge_link::retain_edges(|e| graph.get_edge(e).is_some()));
+    Ok(edge_link::groups()
+        .iter()
+        .map(|g| edge_link_group_dto(g))
+        .collect())
+}
+
+// =============================================================================
+// Output Commands
+// =============================================================================
+
+/// Set output (sink/vout) gain (linear). Applied per-sink during summing.
+#[tauri::command]
+pub async fn set_output_gain(output_handle: u32, gain: f32) -> Result<(), SpectrumError> {
+    let processor = get_graph_processor();
+    let handle = NodeHandle::from_raw(output_handle);
+
+    let updated = processor.with_graph_mut(|graph| {
+        let Some(node) = graph.get_node_mut(handle) else {
+            return false;
+        };
+        let Some(sink) = node
+            .as_any_mut()
+            .downcast_mut::<crate::audio::sink::SinkNode>()
+        else {
+            return false;
+        };
+        // RT-safe atomic store inside the SinkNode.
+        sink.set_output_gain(gain);
+        true
+    });
+
+    if updated {
+        emit_graph_event(GraphEventDto::NodeChanged {
+            handle: output_handle,
+        });
+        Ok(())
+    } else {
+        Err(SpectrumError::WrongNodeType {
+            handle: output_handle,
+            expected: "sink",
+        })
+    }
+}
+
+/// Set output (sink/vout) master gain in dBFS (`null` = -inf)
+#[tauri::command]
+pub async fn set_output_gain_db(output_handle: u32, db: Option<f32>) -> Result<f32, SpectrumError> {
+    let gain = db_to_linear(db);
+    set_output_gain(output_handle, gain).await?;
+    Ok(gain)
+}
+
+/// Set output (sink/vout) gain for a specific channel/port (linear).
+///
+/// `channel` is the port index relative to the sink (0..channel_count).
+#[tauri::command]
+pub async fn set_output_channel_gain(
+    output_handle: u32,
+    channel: u32,
+    gain: f32,
+) -> Result<(), SpectrumError> {
+    let processor = get_graph_processor();
+    let handle = NodeHandle::from_raw(output_handle);
+    let ch = channel as usize;
+
+    processor.with_graph_mut(|graph| {
+        let Some(node) = graph.get_node_mut(handle) else {
+            return Err(SpectrumError::NodeNotFound(output_handle));
+        };
+        let port_count = node.input_port_count();
+        let Some(sink) = node
+            .as_any_mut()
+            .downcast_mut::<crate::audio::sink::SinkNode>()
+        else {
+            return Err(SpectrumError::WrongNodeType {
+                handle: output_handle,
+                expected: "sink",
+            });
+        };
+
+        if ch >= port_count {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Channel {} is out of range for sink node {}",
+                channel, output_handle
+            )));
+        }
+
+        // RT-safe atomic store inside the SinkNode.
+        sink.set_output_gain_for_port(ch, gain);
+        Ok(())
+    })?;
+
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: output_handle,
+    });
+    Ok(())
+}
+
+/// Route a sink's ports to arbitrary device channels
+///
+/// Each route sends one port to one device channel (absolute, not relative to the
+/// channel offset). Ports without a route are silent; a port may feed several
+/// channels and several ports may share one. `null` or an empty map restores the
+/// contiguous layout from the channel offset.
+#[tauri::command]
+pub async fn set_sink_channel_map(
+    sink_handle: u32,
+    map: Option<Vec<ChannelRoute>>,
+) -> Result<(), SpectrumError> {
+    let processor = get_graph_processor();
+    let handle = NodeHandle::from_raw(sink_handle);
+
+    let device_id = processor.with_graph(|graph| {
+        graph
+            .get_node(handle)
+            .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+            .map(|s| s.device_id())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle: sink_handle,
+                expected: "sink",
+            })
+    })?;
+    let device_channels = crate::device::get_device_output_channels(device_id);
+    if let Some(route) = map
+        .iter()
+        .flatten()
+        .find(|r| device_channels > 0 && r.channel as u32 >= device_channels)
+    {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Device {} has {} output channels, cannot route to channel {}",
+            device_id, device_channels, route.channel
+        )));
+    }
+
+    processor.with_graph_mut(|graph| {
+        let sink = graph
+            .get_node_mut(handle)
+            .and_then(|n| n.as_any_mut().downcast_mut::<SinkNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle: sink_handle,
+                expected: "sink",
+            })?;
+        sink.set_channel_map(map)
+            .map_err(SpectrumError::InvalidArgument)
+    })?;
+
+    log_info!("[Spectrum] Sink {} channel map updated", sink_handle);
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: sink_handle,
+    });
+    Ok(())
+}
+
+/// Configure the lookahead limiter of an output (sink) node
+///
+/// Omitted values keep their current setting. Returns the applied settings.
+#[tauri::command]
+pub async fn set_sink_limiter(
+    sink_handle: u32,
+    enabled: Option<bool>,
+    threshold_db: Option<f32>,
+    release_ms: Option<f32>,
+    lookahead_ms: Option<f32>,
+) -> Result<SinkLimiterDto, SpectrumError> {
+    let settings = get_graph_processor().with_graph(|graph| {
+        let sink = graph
+            .get_node(NodeHandle::from_raw(sink_handle))
+            .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle: sink_handle,
+                expected: "sink",
+            })?;
+
+        let current = sink.limiter().settings();
+        sink.limiter().set_settings(LimiterSettings {
+            enabled: enabled.unwrap_or(current.enabled),
+            threshold_db: threshold_db.unwrap_or(current.threshold_db),
+            release_ms: release_ms.unwrap_or(current.release_ms),
+            lookahead_ms: lookahead_ms.unwrap_or(current.lookahead_ms),
+        });
+        Ok::<_, SpectrumError>(sink.limiter().settings())
+    })?;
+
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: sink_handle,
+    });
+    Ok(SinkLimiterDto::from(settings))
+}
+
+/// Configure the output protection of an output (sink) node
+///
+/// しきい値を `hold_ms` 以上超え続けたら出力を `dim_db` 下げ、`output-protection`
+/// イベントを送る。設定すると発動中の保護も解除される。Returns the applied settings.
+#[tauri::command]
+pub async fn set_output_protection(
+    sink_handle: u32,
+    config: OutputProtectionDto,
+) -> Result<OutputProtectionDto, SpectrumError> {
+    let protection = with_sink_protection(sink_handle, |protection| {
+        protection.set_settings((&config).into())
+    })?;
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: sink_handle,
+    });
+    Ok(protection)
+}
+
+/// Lift the dim of a tripped output protection (keeps the settings)
+#[tauri::command]
+pub async fn reset_output_protection(
+    sink_handle: u32,
+) -> Result<OutputProtectionDto, SpectrumError> {
+    let protection = with_sink_protection(sink_handle, |protection| protection.rearm())?;
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: sink_handle,
+    });
+    Ok(protection)
+}
+
+fn with_sink_protection(
+    sink_handle: u32,
+    f: impl FnOnce(&crate::audio::dsp::OutputProtection),
+) -> Result<OutputProtectionDto, SpectrumError> {
+    get_graph_processor().with_graph(|graph| {
+        let protection = graph
+            .get_node(NodeHandle::from_raw(sink_handle))
+            .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle: sink_handle,
+                expected: "sink",
+            })?
+            .protection();
+        f(protection);
+        Ok(OutputProtectionDto::new(
+            protection.settings(),
+            protection.is_tripped(),
+        ))
+    })
+}
+
+/// Make an output (sink) node follow the system default output device
+///
+/// Enabling moves the sink to the current default output right away (output is
+/// switched without stopping the graph). Disabling keeps it on its current device.
+#[tauri::command]
+pub async fn set_sink_follow_default(
+    sink_handle: u32,
+    enabled: bool,
+) -> Result<OutputSinkDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let handle = NodeHandle::from_raw(sink_handle);
+
+    let default_output =
+        if enabled {
+            Some(crate::device::default_output_device().ok_or_else(|| {
+                SpectrumError::InvalidState("No default output device".to_string())
+            })?)
+        } else {
+            None
+        };
+
+    processor.with_graph_mut(|graph| {
+        let sink = graph
+            .get_node_mut(handle)
+            .and_then(|n| n.as_any_mut().downcast_mut::<SinkNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle: sink_handle,
+                expected: "sink",
+            })?;
+        sink.set_follow_default(enabled);
+        Ok::<_, SpectrumError>(())
+    })?;
+
+    if let Some(device_id) = default_output {
+        crate::audio::output::retarget_default_sinks(device_id);
+    }
+
+    log_info!(
+        "[Spectrum] Sink {} {} the system default output",
+        sink_handle,
+        if enabled {
+            "follows"
+        } else {
+            "no longer follows"
+        }
+    );
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: sink_handle,
+    });
+    processor.with_graph(|graph| {
+        graph
+            .get_node(handle)
+            .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+            .map(|s| OutputSinkDto::from(s.sink_id().clone()))
+            .ok_or(SpectrumError::NodeNotFound(sink_handle))
+    })
+}
+
+// =============================================================================
+// Source Commands
+// =============================================================================
+
+/// Run `f` on a source node (trim/phase are atomics, so a read lock is enough)
+fn with_source_node<R>(
+    source_handle: u32,
+    f: impl FnOnce(&SourceNode) -> Result<R, SpectrumError>,
+) -> Result<R, SpectrumError> {
+    get_graph_processor().with_graph(|graph| {
+        let node = graph
+            .get_node(NodeHandle::from_raw(source_handle))
+            .ok_or(SpectrumError::NodeNotFound(source_handle))?;
+        let source =
+            node.as_any()
+                .downcast_ref::<SourceNode>()
+                .ok_or(SpectrumError::WrongNodeType {
+                    handle: source_handle,
+                    expected: "source",
+                })?;
+        f(source)
+    })
+}
+
+/// Ports addressed by an optional channel (`None` = every port)
+fn source_ports(source: &SourceNode, channel: Option<u32>) -> Result<Vec<usize>, SpectrumError> {
+    let port_count = source.output_port_count();
+    match channel {
+        None => Ok((0..port_count).collect()),
+        Some(ch) if (ch as usize) < port_count => Ok(vec![ch as usize]),
+        Some(ch) => Err(SpectrumError::InvalidArgument(format!(
+            "Channel {} is out of range for a {}-port source",
+            ch, port_count
+        ))),
+    }
+}
+
+/// Per-port trims for the graph DTO (empty when every port is at unity)
+fn source_trims(source: &SourceNode) -> Vec<f32> {
+    let trims: Vec<f32> = (0..source.output_port_count())
+        .map(|port| source.trim_for_port(port))
+        .collect();
+    if trims.iter().all(|&t| t == 1.0) {
+        Vec::new()
+    } else {
+        trims
+    }
+}
+
+/// Per-port polarity for the graph DTO (empty when nothing is inverted)
+fn source_phase(source: &SourceNode) -> Vec<bool> {
+    let inverted: Vec<bool> = (0..source.output_port_count())
+        .map(|port| source.phase_inverted(port))
+        .collect();
+    if inverted.contains(&true) {
+        inverted
+    } else {
+        Vec::new()
+    }
+}
+
+/// Set input trim (linear) on a source node, before it fans out to edges.
+///
+/// `channel` is the port index relative to the source; omit it for all ports.
+#[tauri::command]
+pub async fn set_source_trim(
+    source_handle: u32,
+    channel: Option<u32>,
+    trim: f32,
+) -> Result<(), SpectrumError> {
+    with_source_node(source_handle, |source| {
+        for port in source_ports(source, channel)? {
+            source.set_trim_for_port(port, trim);
+        }
+        Ok(())
+    })?;
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: source_handle,
+    });
+    Ok(())
+}
+
+/// Invert (or restore) the polarity of a source node's ports.
+///
+/// `channel` is the port index relative to the source; omit it for all ports.
+#[tauri::command]
+pub async fn set_source_phase(
+    source_handle: u32,
+    channel: Option<u32>,
+    inverted: bool,
+) -> Result<(), SpectrumError> {
+    with_source_node(source_handle, |source| {
+        for port in source_ports(source, channel)? {
+            source.set_phase_inverted(port, inverted);
+        }
+        Ok(())
+    })?;
+    emit_graph_event(GraphEventDto::NodeChanged {
+        handle: source_handle,
+    });
+    Ok(())
+}
+
+/// Auto-mute a Prism source's outgoing edges while its app is gone or silent
+///
+/// The gate closes when no app is routed to the source's pair, or (with `silence_secs`)
+/// after the source stayed below `threshold_db` that long. Edges it muted are unmuted
+/// when the app is back and playing. `enabled: false` removes the gate.
+#[tauri::command]
+pub async fn set_source_auto_gate(
+    handle: u32,
+    config: SourceAutoGateDto,
+) -> Result<SourceAutoGateDto, SpectrumError> {
+    let gate = super::source_gate::set(handle, config)?;
+    log_info!(
+        "[graph] source {} auto-gate enabled={} silence={:?}",
+        handle,
+        gate.enabled,
+        gate.silence_secs
+    );
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(gate)
+}
+
+#[tauri::command]
+pub async fn get_source_auto_gate(handle: u32) -> Result<SourceAutoGateDto, SpectrumError> {
+    Ok(super::source_gate::get(handle))
+}
+
+// =============================================================================
+// Plugin Commands
+// =============================================================================
+
+fn available_plugins() -> Vec<PluginInfoDto> {
+    crate::plugin_registry::effects()
+        .into_iter()
+        .map(|p| PluginInfoDto {
+            error: crate::plugin_registry::failure(&p.id),
+            plugin_id: p.id,
+            name: p.name,
+            manufacturer: p.manufacturer,
+        })
+        .chain(NATIVE_PLUGINS.iter().map(|(id, name)| PluginInfoDto {
+            plugin_id: id.to_string(),
+            name: name.to_string(),
+            manufacturer: NATIVE_MANUFACTURER.to_string(),
+            error: None,
+        }))
+        .collect()
+}
+
+/// Effect plugins from the registry (scanned once and cached on disk)
+#[tauri::command]
+pub async fn get_available_plugins() -> Result<Vec<PluginInfoDto>, SpectrumError> {
+    Ok(available_plugins())
+}
+
+/// Scan all AudioUnits again (e.g. after installing an AUv3 app)
+#[tauri::command]
+pub async fn rescan_plugins() -> Result<Vec<PluginInfoDto>, SpectrumError> {
+    tauri::async_runtime::spawn_blocking(crate::plugin_registry::rescan)
+        .await
+        .map_err(|e| format!("Plugin scan failed: {}", e))?;
+    Ok(available_plugins())
+}
+
+/// Create a plugin instance (AudioUnit or built-in) -> (instance id, name, manufacturer)
+///
+/// バスにはまだ追加しない。AudioUnit の場合はマネージャーにインスタンスが残るので、
+/// 使わなかったときは `release_plugin_instances` で解放する。
+async fn instantiate_plugin(plugin_id: &str) -> Result<(String, String, String), SpectrumError> {
+    if is_native_plugin_id(plugin_id) {
+        // Built-in processor: no AudioUnit instance needed
+        let (_, name) = NATIVE_PLUGINS
+            .iter()
+            .find(|(id, _)| *id == plugin_id)
+            .ok_or_else(|| SpectrumError::PluginNotFound(plugin_id.to_string()))?;
+        return Ok((
+            new_native_instance_id(),
+            name.to_string(),
+            NATIVE_MANUFACTURER.to_string(),
+        ));
+    }
+
+    // Get plugin info
+    let plugins = crate::plugin_registry::effects();
+    let plugin = plugins
+        .iter()
+        .find(|p| p.id == plugin_id)
+        .ok_or_else(|| SpectrumError::PluginNotFound(plugin_id.to_string()))?;
+
+    let instance_id = create_au_instance(plugin).await?;
+    Ok((
+        instance_id,
+        plugin.name.clone(),
+        plugin.manufacturer.clone(),
+    ))
+}
+
+/// Create the real AudioUnit instance in the manager (async for better UI responsiveness)
+async fn create_au_instance(
+    plugin: &crate::audio_unit::AudioUnitInfo,
+) -> Result<String, SpectrumError> {
+    let au_manager = crate::audio_unit::get_au_manager();
+
+    // Use oneshot channel to await the async result
+    let (tx, rx) = tokio::sync::oneshot::channel();
+
+    au_manager.create_instance_async(plugin, move |result| {
+        let _ = tx.send(result);
+    });
+
+    match rx.await {
+        Ok(Ok(id)) => {
+            crate::plugin_registry::clear_failure(&plugin.id);
+            Ok(id)
+        }
+        Ok(Err(e)) => {
+            crate::plugin_registry::invalidate(&plugin.id, &e);
+            Err(SpectrumError::PluginInstantiationFailed {
+                plugin_id: plugin.id.clone(),
+                reason: e,
+            })
+        }
+        Err(_) => Err(SpectrumError::Other(
+            "Failed to receive instance creation result".to_string(),
+        )),
+    }
+}
+
+#[tauri::command]
+pub async fn add_plugin_to_bus(
+    bus_handle: u32,
+    plugin_id: String,
+    position: Option<usize>,
+) -> Result<String, SpectrumError> {
+    let handle = NodeHandle::from_raw(bus_handle);
+    let processor = get_graph_processor();
+
+    let (instance_id, plugin_name, plugin_manufacturer) = instantiate_plugin(&plugin_id).await?;
+
+    // Add the plugin reference to the bus node
+    let instance_id_clone = instance_id.clone();
+    let history_message = format!(
+        "Added plugin {:?} to bus {}",
+        plugin_name,
+        history::describe_node(bus_handle)
+    );
+    processor.with_graph_mut(|graph| {
+        if let Some(node) = graph.get_node_mut(handle) {
+            if let Some(bus) = node.as_any_mut().downcast_mut::<BusNode>() {
+                bus.add_plugin(
+                    instance_id_clone.clone(),
+                    plugin_id.clone(),
+                    plugin_name,
+                    plugin_manufacturer,
+                );
+
+                if let Some(pos) = position {
+                    // Reorder if a position was specified
+                    bus.move_plugin(&instance_id_clone, pos);
+                }
+            }
+        }
+    });
+    // モノラル / 3ポート以上のバスでは AU を幅に合わせる
+    fit_plugin_layouts(&[handle]);
+
+    history::record(
+        MutationKind::PluginAdded,
+        Some(bus_handle),
+        None,
+        history_message,
+    );
+    emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+    Ok(instance_id)
+}
+
+#[tauri::command]
+pub async fn remove_plugin_from_bus(
+    bus_handle: u32,
+    instance_id: String,
+) -> Result<(), SpectrumError> {
+    let handle = NodeHandle::from_raw(bus_handle);
+    let processor = get_graph_processor();
+
+    // If the plugin UI is open, close it first (must run on main thread).
+    // Best-effort: if closing times out, we still proceed with removal.
+    {
+        let instance_id_clone = instance_id.clone();
+        let (tx, rx) = std::sync::mpsc::channel::<()>();
+
+        unsafe {
+            use block2::RcBlock;
+            use objc2::class;
+            use objc2::msg_send;
+            use objc2::runtime::AnyObject;
+
+            let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+
+            let block = RcBlock::new(move || {
+                crate::audio_unit_ui::close_audio_unit_ui(&instance_id_clone);
+                let _ = tx.send(());
+            });
+
+            let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+        }
+
+        if rx.recv_timeout(std::time::Duration::from_secs(2)).is_err() {
+            log_error!(
+                "[api] remove_plugin_from_bus: timeout closing UI for instance {}",
+                instance_id
+            );
+        }
+    }
+
+    // 名前は先に読んでおく（スロットは外した先で reclaim スレッドに渡る）
+    let plugin_name = processor.with_graph(|graph| {
+        let bus = graph.get_node(handle)?.as_any().downcast_ref::<BusNode>()?;
+        bus.plugins()
+            .iter()
+            .find(|p| p.instance_id == instance_id)
+            .map(|p| p.name.clone())
+    });
+    let removed = processor.with_graph_mut(|graph| {
+        graph
+            .get_node_mut(handle)
+            .and_then(|node| node.as_any_mut().downcast_mut::<BusNode>())
+            .is_some_and(|bus| bus.remove_plugin(&instance_id))
+    });
+    let removed_name = plugin_name.filter(|_| removed);
+
+    // Also remove from the manager to release resources
+    let au_manager = crate::audio_unit::get_au_manager();
+    let removed_from_manager = au_manager.remove_instance(&instance_id);
+
+    if let Some(name) = &removed_name {
+        history::record(
+            MutationKind::PluginRemoved,
+            Some(bus_handle),
+            None,
+            format!(
+                "Removed plugin {:?} from bus {}",
+                name,
+                history::describe_node(bus_handle)
+            ),
+        );
+    }
+    if removed_name.is_some() || removed_from_manager {
+        emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+        Ok(())
+    } else {
+        Err(SpectrumError::PluginInstanceNotFound(instance_id))
+    }
+}
+
+#[tauri::command]
+pub async fn reorder_plugins(
+    bus_handle: u32,
+    instance_ids: Vec<String>,
+) -> Result<(), SpectrumError> {
+    let handle = NodeHandle::from_raw(bus_handle);
+    let processor = get_graph_processor();
+
+    processor.with_graph_mut(|graph| {
+        if let Some(node) = graph.get_node_mut(handle) {
+            if let Some(bus) = node.as_any_mut().downcast_mut::<BusNode>() {
+                bus.reorder_plugins(&instance_ids);
+            }
+        }
+    });
+
+    emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+    Ok(())
+}
+
+/// Mirror a bus slot's enabled state on its AudioUnit
+///
+/// バイパスはバスがクロスフェード/テール処理するので、AU 自体はバイパス時に止めない
+/// （止めるとフェードアウト中に素通しになる）。バスが描画をやめれば AU も呼ばれない。
+pub(crate) fn sync_bus_au_enabled(instance_id: &str, enabled: bool) {
+    if enabled {
+        let _ = crate::audio_unit::get_au_manager().set_enabled(instance_id, true);
+    }
+}
+
+/// Enable or bypass a plugin slot (crossfaded; see `set_plugin_ring_out` for tails)
+#[tauri::command]
+pub async fn set_plugin_enabled(
+    bus_handle: u32,
+    instance_id: String,
+    enabled: bool,
+) -> Result<(), SpectrumError> {
+    let handle = NodeHandle::from_raw(bus_handle);
+    let processor = get_graph_processor();
+
+    let found_in_bus = processor.with_graph_mut(|graph| {
+        graph
+            .get_node_mut(handle)
+            .and_then(|node| node.as_any_mut().downcast_mut::<BusNode>())
+            .map(|bus| bus.set_plugin_enabled(&instance_id, enabled))
+            .unwrap_or(false)
+    });
+
+    if !found_in_bus {
+        // Best-effort: also set AU manager's enabled flag (lock-free atomic).
+        // Even if the bus doesn't have the instance (stale UI), we keep behavior consistent.
+        let _ = crate::audio_unit::get_au_manager().set_enabled(&instance_id, enabled);
+        return Err(SpectrumError::PluginInstanceNotFound(instance_id));
+    }
+
+    sync_bus_au_enabled(&instance_id, enabled);
+    emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+    Ok(())
+}
+
+/// Let a plugin slot's tail (reverb, delay) ring out when it is bypassed
+///
+/// Off: the output crossfades to dry. On: the plugin's input fades out instead and it keeps
+/// running until its output has been silent for a moment.
+#[tauri::command]
+pub async fn set_plugin_ring_out(
+    bus_handle: u32,
+    instance_id: String,
+    ring_out: bool,
+) -> Result<(), SpectrumError> {
+    let handle = NodeHandle::from_raw(bus_handle);
+    let processor = get_graph_processor();
+
+    let found = processor.with_graph_mut(|graph| {
+        graph
+            .get_node_mut(handle)
+            .and_then(|node| node.as_any_mut().downcast_mut::<BusNode>())
+            .map(|bus| bus.set_plugin_ring_out(&instance_id, ring_out))
+            .unwrap_or(false)
+    });
+
+    if found {
+        emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+        Ok(())
+    } else {
+        Err(SpectrumError::PluginInstanceNotFound(instance_id))
+    }
+}
+
+/// Set the wet/dry mix of a plugin slot (0.0 = dry, 1.0 = wet)
+#[tauri::command]
+pub async fn set_plugin_mix(
+    bus_handle: u32,
+    instance_id: String,
+    mix: f32,
+) -> Result<(), SpectrumError> {
+    let handle = NodeHandle::from_raw(bus_handle);
+    let processor = get_graph_processor();
+
+    let found = processor.with_graph_mut(|graph| {
+        graph
+            .get_node_mut(handle)
+            .and_then(|node| node.as_any_mut().downcast_mut::<BusNode>())
+            .map(|bus| bus.set_plugin_mix(&instance_id, mix))
+            .unwrap_or(false)
+    });
+
+    if found {
+        emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+        Ok(())
+    } else {
+        Err(SpectrumError::PluginInstanceNotFound(instance_id))
+    }
+}
+
+/// Open a plugin's UI
+///
+/// カスタムビューを持たない AU（または開けなかった場合）はウィンドウを開かず、
+/// 汎用 UI 用のパラメーター一覧を返す。
+#[tauri::command]
+pub async fn open_plugin_ui(instance_id: String) -> Result<PluginUiDto, SpectrumError> {
+    // Verify the instance exists first
+    let _au_instance = crate::audio_unit::get_au_manager()
+        .get_instance(&instance_id)
+        .ok_or_else(|| SpectrumError::PluginInstanceNotFound(instance_id.to_string()))?;
+
+    // UI operations must run on main thread
+    // We need to dispatch to main thread and wait for completion
+    let instance_id_clone = instance_id.clone();
+
+    let (tx, rx) = std::sync::mpsc::channel::<Result<bool, String>>();
+
+    // Dispatch to main thread
+    unsafe {
+        use block2::RcBlock;
+        use objc2::class;
+        use objc2::msg_send;
+        use objc2::runtime::AnyObject;
+
+        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+
+        let block = RcBlock::new(move || {
+            let result = crate::audio_unit_ui::open_plugin_ui_by_instance_id(&instance_id_clone);
+            let _ = tx.send(result);
+        });
+
+        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+    }
+
+    // Wait for result with timeout
+    let result = rx
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .map_err(|_| SpectrumError::Timeout("Timeout waiting for UI to open".to_string()))?;
+    plugin_ui_result(&instance_id, result, "open_plugin_ui")
+}
+
+/// Custom view shown, or the parameter list for the generic UI
+fn plugin_ui_result(
+    instance_id: &str,
+    opened: Result<bool, String>,
+    context: &str,
+) -> Result<PluginUiDto, SpectrumError> {
+    match opened {
+        Ok(true) => return Ok(PluginUiDto::Custom),
+        Ok(false) => {}
+        Err(e) => log_warn!(
+            "[{}] {}: {} - falling back to generic UI",
+            context,
+            instance_id,
+            e
+        ),
+    }
+
+    let parameters = crate::audio_unit::get_au_manager()
+        .get_parameters(instance_id)
+        .ok_or_else(|| SpectrumError::PluginInstanceNotFound(instance_id.to_string()))?;
+    Ok(PluginUiDto::Generic {
+        parameters: parameters
+            .into_iter()
+            .map(PluginParameterDto::from)
+            .collect(),
+    })
+}
+
+/// Dock a plugin's UI into the main window (instead of a separate window)
+///
+/// 座標はメインウィンドウのコンテンツ左上からのポイント（100% ズーム時の CSS px）。
+/// ドッキング済みのインスタンスで呼ぶと位置・サイズだけ更新する（レイアウト変更時に呼び直す）。
+/// 閉じるときは `close_plugin_ui`。カスタムビューがなければ `open_plugin_ui` と同じく
+/// 汎用 UI 用のパラメーター一覧を返す。
+#[tauri::command]
+pub async fn open_plugin_ui_embedded(
+    window: tauri::WebviewWindow,
+    instance_id: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<PluginUiDto, SpectrumError> {
+    crate::audio_unit::get_au_manager()
+        .get_instance(&instance_id)
+        .ok_or_else(|| SpectrumError::PluginInstanceNotFound(instance_id.to_string()))?;
+    if !(width > 0.0 && height > 0.0) {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Embedded plugin UI size must be positive: {}x{}",
+            width, height
+        )));
+    }
+
+    // NSWindow のポインタはスレッドをまたげないので数値で渡す
+    let ns_window = window
+        .ns_window()
+        .map_err(|e| SpectrumError::Other(format!("Main window is not available: {}", e)))?
+        as usize;
+
+    let instance_id_clone = instance_id.clone();
+    let (tx, rx) = std::sync::mpsc::channel::<Result<bool, String>>();
+
+    unsafe {
+        use block2::RcBlock;
+        use objc2::class;
+        use objc2::msg_send;
+        use objc2::runtime::AnyObject;
+
+        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+
+        let block = RcBlock::new(move || {
+            let result = crate::audio_unit_ui::open_plugin_ui_embedded_by_instance_id(
+                &instance_id_clone,
+                ns_window as *mut AnyObject,
+                x,
+                y,
+                width,
+                height,
+            );
+            let _ = tx.send(result);
+        });
+
+        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+    }
+
+    let result = rx
+        .recv_timeout(std::time::Duration::from_secs(5))
+        .map_err(|_| SpectrumError::Timeout("Timeout waiting for UI to open".to_string()))?;
+    plugin_ui_result(&instance_id, result, "open_plugin_ui_embedded")
+}
+
+#[tauri::command]
+pub async fn close_plugin_ui(instance_id: String) -> Result<(), SpectrumError> {
+    let instance_id_clone = instance_id.clone();
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+
+    unsafe {
+        use block2::RcBlock;
+        use objc2::class;
+        use objc2::msg_send;
+        use objc2::runtime::AnyObject;
+
+        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+
+        let block = RcBlock::new(move || {
+            crate::audio_unit_ui::close_audio_unit_ui(&instance_id_clone);
+            let _ = tx.send(());
+        });
+
+        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+    }
+
+    rx.recv_timeout(std::time::Duration::from_secs(5))
+        .map_err(|_| SpectrumError::Timeout("Timeout waiting for UI to close".to_string()))?;
+
+    Ok(())
+}
+
+/// Load new AudioUnit instances out-of-process (crash isolation)
+///
+/// 既存のインスタンスには影響しない（`restart_plugin` で読み込み直すと反映される）。
+#[tauri::command]
+pub async fn set_plugin_out_of_process(enabled: bool) -> Result<bool, SpectrumError> {
+    crate::audio_unit::set_out_of_process_hosting(enabled);
+    Ok(crate::audio_unit::out_of_process_hosting())
+}
+
+#[tauri::command]
+pub async fn get_plugin_out_of_process() -> Result<bool, SpectrumError> {
+    Ok(crate::audio_unit::out_of_process_hosting())
+}
+
+/// Re-instantiate a (crashed or overloaded) AudioUnit under the same instance ID
+///
+/// 最後に保存できた状態を復元し、そのインスタンスを持つバス（またはプラグインソース）の
+/// 処理を再開する。
+#[tauri::command]
+pub async fn restart_plugin(instance_id: String) -> Result<(), SpectrumError> {
+    crate::audio_unit::get_au_manager().restart_instance(&instance_id)?;
+
+    let processor = get_graph_processor();
+    let mut nodes = Vec::new();
+    processor.with_graph_mut(|graph| {
+        for handle in graph.node_handles().collect::<Vec<_>>() {
+            let Some(any) = graph.get_node_mut(handle).map(|n| n.as_any_mut()) else {
+                continue;
+            };
+            let refreshed = if let Some(bus) = any.downcast_mut::<BusNode>() {
+                bus.refresh_plugin(&instance_id)
+            } else if let Some(source) = any.downcast_mut::<PluginSourceNode>() {
+                source.refresh_plugin(&instance_id)
+            } else {
+                false
+            };
+            if refreshed {
+                nodes.push(handle.raw());
+            }
+        }
+    });
+
+    for handle in nodes {
+        emit_graph_event(GraphEventDto::NodeChanged { handle });
+    }
+    Ok(())
+}
+
+/// List the parameters of an AudioUnit instance
+#[tauri::command]
+pub async fn get_plugin_parameters(
+    instance_id: String,
+) -> Result<Vec<PluginParameterDto>, SpectrumError> {
+    let params = crate::audio_unit::get_au_manager()
+        .get_parameters(&instance_id)
+        .ok_or_else(|| SpectrumError::PluginInstanceNotFound(instance_id.to_string()))?;
+    Ok(params.into_iter().map(PluginParameterDto::from).collect())
+}
+
+/// Set one AudioUnit parameter (value is clamped to the parameter range)
+#[tauri::command]
+pub async fn set_plugin_parameter(
+    instance_id: String,
+    param_id: u64,
+    value: f32,
+) -> Result<PluginParameterDto, SpectrumError> {
+    let latency_changes = crate::audio_unit::plugin_latency_change_count();
+    let param = crate::audio_unit::get_au_manager().set_parameter(&instance_id, param_id, value)?;
+    // ルックアヘッドなどでレイテンシが変わったら補正を合わせ直す
+    if crate::audio_unit::plugin_latency_change_count() != latency_changes {
+        get_graph_processor().refresh_latency_compensation();
+    }
+    Ok(PluginParameterDto::from(param))
+}
+
+// =============================================================================
+// Native DSP Commands
+// =============================================================================
+
+fn new_native_instance_id() -> String {
+    format!(
+        "native_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    )
+}
+
+fn native_settings_from_dto(dto: &NativeDspDto) -> Result<NativeSettings, SpectrumError> {
+    Ok(match dto {
+        NativeDspDto::Eq { bands } => {
+            let mut settings = EqSettings::default();
+            if bands.len() > EQ_BANDS {
+                return Err(SpectrumError::InvalidArgument(format!(
+                    "EQ has {} bands",
+                    EQ_BANDS
+                )));
+            }
+            for (band, dto) in settings.bands.iter_mut().zip(bands) {
+                *band = EqBand {
+                    kind: EqBandKind::parse(&dto.kind).ok_or_else(|| {
+                        SpectrumError::InvalidArgument(format!(
+                            "Unknown EQ band kind: {}",
+                            dto.kind
+                        ))
+                    })?,
+                    frequency: dto.frequency,
+                    gain_db: dto.gain_db,
+                    q: dto.q,
+                    enabled: dto.enabled,
+                };
+            }
+            NativeSettings::Eq(settings)
+        }
+        NativeDspDto::Compressor {
+            threshold_db,
+            ratio,
+            attack_ms,
+            release_ms,
+            makeup_db,
+        } => NativeSettings::Compressor(CompressorSettings {
+            threshold_db: *threshold_db,
+            ratio: *ratio,
+            attack_ms: *attack_ms,
+            release_ms: *release_ms,
+            makeup_db: *makeup_db,
+        }),
+        NativeDspDto::Gate {
+            threshold_db,
+            range_db,
+            attack_ms,
+            hold_ms,
+            release_ms,
+        } => NativeSettings::Gate(GateSettings {
+            threshold_db: *threshold_db,
+            range_db: *range_db,
+            attack_ms: *attack_ms,
+            hold_ms: *hold_ms,
+            release_ms: *release_ms,
+        }),
+    })
+}
+
+/// Restore the saved fullState (plugin parameters) of a recreated AudioUnit
+fn restore_au_state(instance_id: &str, plugin: &PluginInstanceDto) {
+    use base64::Engine;
+
+    let Some(state_b64) = &plugin.state else {
+        return;
+    };
+    if let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(state_b64) {
+        let _ = crate::audio_unit::get_au_manager().set_instance_full_state(instance_id, &bytes);
+    } else {
+        log_error!(
+            "[state] Failed to decode plugin state for {}",
+            plugin.plugin_id
+        );
+    }
+}
+
+/// Re-add a saved native processor to a bus being restored
+fn restore_native_plugin(bus: &mut BusNode, plugin: &PluginInstanceDto) {
+    let name = if plugin.name.trim().is_empty() {
+        NATIVE_PLUGINS
+            .iter()
+            .find(|(id, _)| *id == plugin.plugin_id)
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_else(|| plugin.plugin_id.clone())
+    } else {
+        plugin.name.clone()
+    };
+    let instance_id = if plugin.instance_id.trim().is_empty() {
+        new_native_instance_id()
+    } else {
+        plugin.instance_id.clone()
+    };
+    bus.add_plugin(
+        instance_id.clone(),
+        plugin.plugin_id.clone(),
+        name,
+        NATIVE_MANUFACTURER.to_string(),
+    );
+
+    let Some(added) = bus
+        .plugins()
+        .iter()
+        .find(|p| p.instance_id == instance_id && p.native().is_some())
+    else {
+        log_error!(
+            "[state] Unknown native plugin {} (skipping)",
+            plugin.plugin_id
+        );
+        bus.remove_plugin(&instance_id);
+        return;
+    };
+    if let Some(dto) = &plugin.native {
+        let restored = native_settings_from_dto(dto)
+            .and_then(|s| added.set_native_settings(s).map_err(SpectrumError::from));
+        if let Err(e) = restored {
+            log_error!(
+                "[state] Failed to restore settings for {}: {}",
+                plugin.plugin_id,
+                e
+            );
+        }
+    }
+    let _ = bus.set_plugin_ring_out(&instance_id, plugin.ring_out);
+    let _ = bus.set_plugin_enabled(&instance_id, plugin.enabled);
+    let _ = bus.set_plugin_mix(&instance_id, plugin.mix);
+}
+
+/// Get the settings of a built-in processor on a bus
+#[tauri::command]
+pub async fn get_native_dsp_params(
+    bus_handle: u32,
+    instance_id: String,
+) -> Result<NativeDspDto, SpectrumError> {
+    get_graph_processor().with_graph(|graph| {
+        let native = find_native_processor(graph, bus_handle, &instance_id)?;
+        Ok(NativeDspDto::from(native.settings()))
+    })
+}
+
+/// Replace the settings of a built-in processor on a bus
+///
+/// Values are clamped; the applied settings are returned.
+#[tauri::command]
+pub async fn set_native_dsp_params(
+    bus_handle: u32,
+    instance_id: String,
+    params: NativeDspDto,
+) -> Result<NativeDspDto, SpectrumError> {
+    let settings = native_settings_from_dto(&params)?;
+    let applied = get_graph_processor().with_graph(|graph| {
+        let native = find_native_processor(graph, bus_handle, &instance_id)?;
+        // 3ポート以上のバスではペアごとのプロセッサーにも反映する
+        find_native_plugin(graph, bus_handle, &instance_id)?.set_native_settings(settings)?;
+        Ok::<_, SpectrumError>(native.settings())
+    })?;
+
+    emit_graph_event(GraphEventDto::NodeChanged { handle: bus_handle });
+    Ok(NativeDspDto::from(applied))
+}
+
+fn find_native_processor<'a>(
+    graph: &'a crate::audio::AudioGraph,
+    bus_handle: u32,
+    instance_id: &str,
+) -> Result<&'a crate::audio::dsp::NativeProcessor, SpectrumError> {
+    find_native_plugin(graph, bus_handle, instance_id)?
+        .native()
+        .ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!(
+                "Plugin {} is not a native processor",
+                instance_id
+            ))
+        })
+}
+
+fn find_native_plugin<'a>(
+    graph: &'a crate::audio::AudioGraph,
+    bus_handle: u32,
+    instance_id: &str,
+) -> Result<&'a crate::audio::bus::PluginInstance, SpectrumError> {
+    let bus = graph
+        .get_node(NodeHandle::from_raw(bus_handle))
+        .and_then(|n| n.as_any().downcast_ref::<BusNode>())
+        .ok_or(SpectrumError::WrongNodeType {
+            handle: bus_handle,
+            expected: "bus",
+        })?;
+    bus.plugins()
+        .iter()
+        .find(|p| p.instance_id == instance_id)
+        .ok_or_else(|| SpectrumError::PluginInstanceNotFound(instance_id.to_string()))
+}
+
+// =============================================================================
+// Meter Commands
+// =============================================================================
+
+/// Rate of a binary meter subscription when none is given (Hz)
+const DEFAULT_METER_SUBSCRIPTION_HZ: u32 = 30;
+
+#[tauri::command]
+pub async fn get_meters() -> Result<GraphMetersDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let meters = processor.get_meters();
+    Ok(GraphMetersDto::from((*meters).clone()))
+}
+
+#[tauri::command]
+pub async fn get_node_meters(handles: Vec<u32>) -> Result<Vec<NodeMeterDto>, SpectrumError> {
+    let processor = get_graph_processor();
+    let meters = processor.get_meters();
+
+    let filtered: Vec<_> = meters
+        .nodes
+        .iter()
+        .filter(|m| handles.contains(&m.handle.raw()))
+        .map(|m| NodeMeterDto {
+            handle: m.handle.raw(),
+            inputs: m
+                .inputs
+                .iter()
+                .map(|p| PortMeterDto::from(p.clone()))
+                .collect(),
+            outputs: m
+                .outputs
+                .iter()
+                .map(|p| PortMeterDto::from(p.clone()))
+                .collect(),
+        })
+        .collect();
+
+    Ok(filtered)
+}
+
+#[tauri::command]
+pub async fn get_edge_meters(ids: Vec<u32>) -> Result<Vec<EdgeMeterDto>, SpectrumError> {
+    let processor = get_graph_processor();
+    let meters = processor.get_meters();
+
+    let filtered: Vec<_> = meters
+        .edges
+        .iter()
+        .filter(|m| ids.contains(&m.edge_id.raw()))
+        .map(|m| EdgeMeterDto {
+            edge_id: m.edge_id.raw(),
+            post_gain: PortMeterDto::from(m.post_gain.clone()),
+        })
+        .collect();
+
+    Ok(filtered)
+}
+
+/// All meters (or only `nodes` / `edges`) as a compact binary packet
+///
+/// レイアウトは [`crate::audio::meter_packet`] を参照。フロントエンドには ArrayBuffer で届く。
+#[tauri::command]
+pub async fn get_meters_packed(
+    nodes: Option<Vec<u32>>,
+    edges: Option<Vec<u32>>,
+) -> Result<tauri::ipc::Response, SpectrumError> {
+    let meters = get_graph_processor().get_meters();
+    let filter = MeterFilter::new(nodes.as_deref(), edges.as_deref());
+    let mut packet = Vec::new();
+    crate::audio::meter_packet::encode(&meters, &filter, &mut packet);
+    Ok(tauri::ipc::Response::new(packet))
+}
+
+/// Push binary meter packets to `channel` at `hz` (default 30), optionally only for some
+/// nodes / edges. Returns a subscription id for `unsubscribe_meters`.
+///
+/// Packets are sent only when the meters changed (nothing while the output is stopped).
+#[tauri::command]
+pub async fn subscribe_meters(
+    channel: tauri::ipc::Channel,
+    nodes: Option<Vec<u32>>,
+    edges: Option<Vec<u32>>,
+    hz: Option<u32>,
+) -> Result<u32, SpectrumError> {
+    let filter = MeterFilter::new(nodes.as_deref(), edges.as_deref());
+    Ok(super::events::subscribe_meters(
+        channel,
+        filter,
+        hz.unwrap_or(DEFAULT_METER_SUBSCRIPTION_HZ),
+    ))
+}
+
+/// Stop a binary meter subscription. Returns false if the id is unknown.
+#[tauri::command]
+pub async fn unsubscribe_meters(id: u32) -> Result<bool, SpectrumError> {
+    Ok(super::events::unsubscribe_meters(id))
+}
+
+/// Set the push meter stream rate in Hz (0 disables; returns the applied rate)
+#[tauri::command]
+pub async fn set_meter_stream_rate(hz: u32) -> Result<u32, SpectrumError> {
+    Ok(super::events::set_meter_stream_rate(hz))
+}
+
+#[tauri::command]
+pub async fn get_meter_stream_rate() -> Result<u32, SpectrumError> {
+    Ok(super::events::meter_stream_rate())
+}
+
+/// Configure meter ballistics for the whole app
+///
+/// `preset` (instant / digital / ppm / vu) を基準に、指定された値だけ上書きする。
+/// preset を省略した場合は現在の設定が基準。`rate_divisor` はメーターを計算する
+/// ブロック間隔（1 = 毎ブロック、0 = 停止。ヘッドレス・最小化時の負荷削減用）。
+#[tauri::command]
+pub async fn configure_metering(
+    preset: Option<String>,
+    attack_ms: Option<f32>,
+    release_ms: Option<f32>,
+    peak_hold_ms: Option<f32>,
+    clip_hold: Option<bool>,
+    rate_divisor: Option<u32>,
+) -> Result<MeteringConfigDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let mut ballistics = match preset.as_deref() {
+        Some(name) => MeterBallistics::preset(name).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!("Unknown metering preset: {}", name))
+        })?,
+        None => processor.meter_ballistics(),
+    };
+    if let Some(v) = attack_ms {
+        ballistics.attack_ms = v;
+    }
+    if let Some(v) = release_ms {
+        ballistics.release_ms = v;
+    }
+    if let Some(v) = peak_hold_ms {
+        ballistics.peak_hold_ms = v;
+    }
+    if let Some(v) = clip_hold {
+        ballistics.clip_hold = v;
+    }
+    let applied = processor.set_meter_ballistics(ballistics);
+    log_info!(
+        "[Meter] Ballistics: attack {} ms, release {} ms, hold {} ms, clip hold {}",
+        applied.attack_ms,
+        applied.release_ms,
+        applied.peak_hold_ms,
+        applied.clip_hold
+    );
+    if let Some(divisor) = rate_divisor {
+        let applied = processor.set_meter_rate_divisor(divisor);
+        log_info!("[Meter] Rate divisor set to {}", applied);
+    }
+    Ok(metering_config_dto())
+}
+
+#[tauri::command]
+pub async fn get_metering_config() -> Result<MeteringConfigDto, SpectrumError> {
+    Ok(metering_config_dto())
+}
+
+fn metering_config_dto() -> MeteringConfigDto {
+    let processor = get_graph_processor();
+    let ballistics = processor.meter_ballistics();
+    let (unmetered_nodes, unmetered_edges) = processor.with_graph(|graph| {
+        (
+            graph.unmetered_nodes().iter().map(|h| h.raw()).collect(),
+            graph.unmetered_edges().iter().map(|e| e.raw()).collect(),
+        )
+    });
+    MeteringConfigDto {
+        attack_ms: ballistics.attack_ms,
+        release_ms: ballistics.release_ms,
+        peak_hold_ms: ballistics.peak_hold_ms,
+        clip_hold: ballistics.clip_hold,
+        rate_divisor: processor.meter_rate_divisor(),
+        unmetered_nodes,
+        unmetered_edges,
+    }
+}
+
+/// Turn metering of nodes and/or edges on or off
+///
+/// 止めたノードはピーク/RMS を計算せず、`get_meters` などに出てこない（そこから出る
+/// エッジのメーターは 0）。止めたエッジはポストゲインのレベルを出さない。
+#[tauri::command]
+pub async fn set_metering_enabled(
+    handles: Option<Vec<u32>>,
+    edge_ids: Option<Vec<u32>>,
+    enabled: bool,
+) -> Result<MeteringConfigDto, SpectrumError> {
+    let handles = handles.unwrap_or_default();
+    let edge_ids = edge_ids.unwrap_or_default();
+    get_graph_processor().with_graph_mut(|graph| {
+        // 全部確認してから変える（途中で失敗して半端にならないように）
+        if let Some(&handle) = handles
+            .iter()
+            .find(|&&h| graph.get_node(NodeHandle::from_raw(h)).is_none())
+        {
+            return Err(SpectrumError::NodeNotFound(handle));
+        }
+        if let Some(&id) = edge_ids
+            .iter()
+            .find(|&&id| graph.get_edge(EdgeId::from(id)).is_none())
+        {
+            return Err(SpectrumError::EdgeNotFound(id));
+        }
+        for &handle in &handles {
+            graph.set_node_metered(NodeHandle::from_raw(handle), enabled);
+        }
+        for &id in &edge_ids {
+            graph.set_edge_metered(EdgeId::from(id), enabled);
+        }
+        Ok(())
+    })?;
+    Ok(metering_config_dto())
+}
+
+/// Short peak history (60 Hz, up to 2 s) of nodes and edges
+///
+/// `frames` で末尾から取得するフレーム数を指定（省略時は全部）。
+#[tauri::command]
+pub async fn get_meter_history(
+    handles: Vec<u32>,
+    edge_ids: Option<Vec<u32>>,
+    frames: Option<u32>,
+) -> Result<MeterHistoryDto, SpectrumError> {
+    let max_frames = frames.map(|f| f as usize).unwrap_or(HISTORY_LEN);
+    let keys: Vec<HistoryKey> = handles
+        .iter()
+        .map(|&h| HistoryKey::Node(NodeHandle::from_raw(h)))
+        .chain(
+            edge_ids
+                .unwrap_or_default()
+                .into_iter()
+                .map(|id| HistoryKey::Edge(EdgeId::from(id))),
+        )
+        .collect();
+
+    let (frame_index, entries) = get_graph_processor().meter_history(&keys, max_frames);
+    let mut history = MeterHistoryDto {
+        rate_hz: HISTORY_RATE_HZ,
+        frame_index,
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    for (key, peaks) in entries {
+        match key {
+            HistoryKey::Node(handle) => history.nodes.push(MeterHistoryEntryDto {
+                handle: Some(handle.raw()),
+                edge_id: None,
+                peaks,
+            }),
+            HistoryKey::Edge(edge_id) => history.edges.push(MeterHistoryEntryDto {
+                handle: None,
+                edge_id: Some(edge_id.raw()),
+                peaks,
+            }),
+        }
+    }
+    Ok(history)
+}
+
+/// Clear latched clip indicators on every meter
+#[tauri::command]
+pub async fn reset_meter_clips() -> Result<(), SpectrumError> {
+    get_graph_processor().reset_meter_clips();
+    Ok(())
+}
+
+// =============================================================================
+// Analyzer Commands
+// =============================================================================
+
+fn ensure_node_port(handle: u32, port: u8) -> Result<(), SpectrumError> {
+    let processor = get_graph_processor();
+    processor.with_graph(|graph| {
+        let node = graph
+            .get_node(NodeHandle::from_raw(handle))
+            .ok_or(SpectrumError::NodeNotFound(handle))?;
+        let ports = node.output_port_count().max(node.input_port_count());
+        if (port as usize) < ports {
+            Ok(())
+        } else {
+            Err(SpectrumError::PortNotFound { handle, port })
+        }
+    })
+}
+
+/// Binned spectrum of a node port (attaches an analysis tap on first call)
+///
+/// 最初の呼び出しではタップを付けるだけなので、結果は空のバンド（-120 dB）になる。
+/// 10 秒間呼ばれないとタップは自動で外れる。
+#[tauri::command]
+pub async fn get_spectrum(
+    handle: u32,
+    port: Option<u8>,
+    bands: Option<u32>,
+) -> Result<SpectrumDto, SpectrumError> {
+    let port = port.unwrap_or(0);
+    let bands = bands.unwrap_or(64).clamp(1, 1024) as usize;
+    let node = NodeHandle::from_raw(handle);
+
+    let (config, spectrum) = match analyzer::read_spectrum(node, port) {
+        Some(found) => found,
+        None => {
+            ensure_node_port(handle, port)?;
+            analyzer::attach_tap(node, port, TapConfig::default());
+            analyzer::read_spectrum(node, port)
+                .ok_or_else(|| "Failed to attach spectrum tap".to_string())?
+        }
+    };
+
+    let sample_rate = if spectrum.sample_rate > 0.0 {
+        spectrum.sample_rate
+    } else {
+        crate::audio::engine_sample_rate()
+    };
+    let (frequencies, magnitudes_db) = analyzer::bin_spectrum(&spectrum, bands, 20.0, 20_000.0)
+        .into_iter()
+        .unzip();
+
+    Ok(SpectrumDto {
+        handle,
+        port,
+        sample_rate,
+        fft_size: config.fft_size as u32,
+        frequencies,
+        magnitudes_db,
+    })
+}
+
+/// Configure (or attach) the analysis tap of a node port
+#[tauri::command]
+pub async fn set_spectrum_config(
+    handle: u32,
+    port: Option<u8>,
+    fft_size: Option<u32>,
+    rate_hz: Option<f32>,
+    smoothing: Option<f32>,
+) -> Result<SpectrumTapConfigDto, SpectrumError> {
+    let port = port.unwrap_or(0);
+    ensure_node_port(handle, port)?;
+    let node = NodeHandle::from_raw(handle);
+
+    let current = analyzer::read_spectrum(node, port)
+        .map(|(config, _)| config)
+        .unwrap_or_default();
+    let config = analyzer::attach_tap(
+        node,
+        port,
+        TapConfig {
+            fft_size: fft_size.map(|v| v as usize).unwrap_or(current.fft_size),
+            rate_hz: rate_hz.unwrap_or(current.rate_hz),
+            smoothing: smoothing.unwrap_or(current.smoothing),
+        },
+    );
+
+    Ok(SpectrumTapConfigDto {
+        handle,
+        port,
+        fft_size: config.fft_size as u32,
+        rate_hz: config.rate_hz,
+        smoothing: config.smoothing,
+    })
+}
+
+/// Detach the analysis tap of a node port (returns false if none was attached)
+#[tauri::command]
+pub async fn remove_spectrum_tap(handle: u32, port: Option<u8>) -> Result<bool, SpectrumError> {
+    Ok(analyzer::detach_tap(
+        NodeHandle::from_raw(handle),
+        port.unwrap_or(0),
+    ))
+}
+
+// =============================================================================
+// Record Commands
+// =============================================================================
+
+#[tauri::command]
+pub async fn add_record_node(
+    label: Option<String>,
+    port_count: Option<u8>,
+) -> Result<u32, SpectrumError> {
+    let processor = get_graph_processor();
+    let port_count = port_count.unwrap_or(2).max(1);
+
+    let record_id = format!(
+        "rec_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    );
+    let label = label.unwrap_or_else(|| "Recorder".to_string());
+    let node: Box<dyn AudioNode> =
+        Box::new(RecordNode::new(&record_id, &label, port_count as usize));
+
+    let handle = processor.add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Lead time of a punch without an explicit frame (every node is set up before it passes)
+const PUNCH_LEAD_MS: f64 = 50.0;
+
+/// Extra wait for the render position to reach a punch-out frame
+const PUNCH_OUT_GRACE: std::time::Duration = std::time::Duration::from_secs(1);
+
+fn parse_record_format(format: Option<&str>) -> Result<RecordFormat, SpectrumError> {
+    match format {
+        Some(f) => RecordFormat::parse(f)
+            .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown record format: {}", f))),
+        None => Ok(RecordFormat::Wav),
+    }
+}
+
+/// Stop recordings that are not kept and delete their files
+async fn discard_recordings(recordings: impl IntoIterator<Item = RecordingHandle>) {
+    for recording in recordings {
+        discard_recording(recording).await;
+    }
+}
+
+/// Stop a recording that is not kept and delete its file
+async fn discard_recording(recording: RecordingHandle) {
+    let path = recording.path().to_path_buf();
+    let finished =
+        tokio::task::spawn_blocking(move || crate::audio::record::finish_recording(recording))
+            .await;
+    if let Err(e) = finished {
+        log_warn!("[Record] Failed to finish {}: {}", path.display(), e);
+    }
+    if let Err(e) = std::fs::remove_file(&path) {
+        log_warn!("[Record] Failed to delete {}: {}", path.display(), e);
+    }
+}
+
+/// `~/Music/Spectrum/<name>_<unix time>.<ext>`
+fn default_record_path(
+    name: &str,
+    format: RecordFormat,
+) -> Result<std::path::PathBuf, SpectrumError> {
+    let dir = dirs::audio_dir()
+        .or_else(dirs::home_dir)
+        .ok_or("Could not find a recordings directory")?
+        .join("Spectrum");
+    std::fs::create_dir_all(&dir).map_err(|e| {
+        SpectrumError::Storage(format!("Failed to create recordings directory: {}", e))
+    })?;
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let safe_name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(dir.join(format!("{}_{}.{}", safe_name, secs, format.extension())))
+}
+
+/// Start recording a record node's inputs to disk.
+///
+/// `path` defaults to `~/Music/Spectrum/<label>_<unix time>.<ext>`; `format` is "wav" (default) or "caf".
+#[tauri::command]
+pub async fn start_recording(
+    handle: u32,
+    path: Option<String>,
+    format: Option<String>,
+) -> Result<RecordingStatusDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let node_handle = NodeHandle::from_raw(handle);
+
+    let format = parse_record_format(format.as_deref())?;
+
+    let (record_id, label, channels) = processor
+        .with_graph(|graph| {
+            graph
+                .get_node(node_handle)
+                .and_then(|n| n.as_any().downcast_ref::<RecordNode>())
+                .map(|r| {
+                    (
+                        r.record_id().to_string(),
+                        r.label().to_string(),
+                        r.input_port_count(),
+                    )
+                })
+        })
+        .ok_or(SpectrumError::WrongNodeType {
+            handle,
+            expected: "record",
+        })?;
+
+    let path = match path {
+        Some(p) => std::path::PathBuf::from(shellexpand::tilde(&p).into_owned()),
+        None => default_record_path(&label, format)?,
+    };
+
+    // ファイルとライタースレッドはここで用意し、グラフの変更では取り付けるだけにする
+    let sample_rate = crate::audio::engine_sample_rate();
+    let session = RecordSession::open(&record_id, channels, &path, format, sample_rate, 0)?;
+    let attached = processor.with_graph_mut(move |graph| {
+        let Some(record) = graph
+            .get_node_mut(node_handle)
+            .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+        else {
+            return Err((session, "is no longer a record node"));
+        };
+        match record.attach(session) {
+            Ok(()) => Ok(record.status()),
+            Err(session) => Err((session, "is already recording or changed channels")),
+        }
+    });
+    let status = match attached {
+        Ok(status) => status,
+        Err((session, reason)) => {
+            discard_recording(session.into()).await;
+            return Err(SpectrumError::InvalidState(format!(
+                "Record node {} {}",
+                handle, reason
+            )));
+        }
+    };
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+
+    Ok(RecordingStatusDto::from_status(handle, status, sample_rate))
+}
+
+#[tauri::command]
+pub async fn stop_recording(handle: u32) -> Result<RecordingStatusDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let node_handle = NodeHandle::from_raw(handle);
+
+    // Detach under the graph lock, then join the writer thread outside of it.
+    let detached = processor.with_graph_mut(|graph| {
+        let record = graph
+            .get_node_mut(node_handle)
+            .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "record",
+            })?;
+        Ok::<_, SpectrumError>(record.detach_session())
+    })?;
+
+    let Some(detached) = detached else {
+        return Err(SpectrumError::InvalidState(format!(
+            "Record node {} is not recording",
+            handle
+        )));
+    };
+
+    let status =
+        tokio::task::spawn_blocking(move || crate::audio::record::finish_recording(detached))
+            .await
+            .map_err(|e| format!("Failed to finish recording: {}", e))?;
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+
+    Ok(RecordingStatusDto::from_status(
+        handle,
+        status,
+        crate::audio::engine_sample_rate(),
+    ))
+}
+
+#[tauri::command]
+pub async fn get_recording_status(handle: u32) -> Result<RecordingStatusDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let node_handle = NodeHandle::from_raw(handle);
+
+    let status = processor
+        .with_graph(|graph| {
+            graph
+                .get_node(node_handle)
+                .and_then(|n| n.as_any().downcast_ref::<RecordNode>())
+                .map(|r| r.status())
+        })
+        .ok_or(SpectrumError::WrongNodeType {
+            handle,
+            expected: "record",
+        })?;
+
+    Ok(RecordingStatusDto::from_status(
+        handle,
+        status,
+        crate::audio::engine_sample_rate(),
+    ))
+}
+
+/// Arm (or disarm) a record node for `punch_in`
+#[tauri::command]
+pub async fn set_record_armed(handle: u32, armed: bool) -> Result<(), SpectrumError> {
+    get_graph_processor().with_graph_mut(|graph| {
+        graph
+            .get_node_mut(NodeHandle::from_raw(handle))
+            .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+            .map(|record| record.set_armed(armed))
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "record",
+            })
+    })?;
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(())
+}
+
+/// Record nodes for a punch: `handles`, or every armed node (recording or not per `recording`)
+///
+/// Returns (handle, label, record id).
+fn punch_targets(
+    graph: &AudioGraph,
+    handles: Option<&[u32]>,
+    recording: bool,
+) -> Result<Vec<(u32, String, String)>, SpectrumError> {
+    let record = |handle: u32| {
+        graph
+            .get_node(NodeHandle::from_raw(handle))
+            .and_then(|n| n.as_any().downcast_ref::<RecordNode>())
+    };
+    let targets: Vec<(u32, String, String)> = match handles {
+        Some(handles) => handles
+            .iter()
+            .map(|&handle| {
+                record(handle)
+                    .filter(|r| r.is_recording() == recording)
+                    .map(|r| (handle, r.label().to_string(), r.record_id().to_string()))
+                    .ok_or_else(|| {
+                        SpectrumError::InvalidState(format!(
+                            "Node {} is not a record node that is {}",
+                            handle,
+                            if recording { "recording" } else { "stopped" }
+                        ))
+                    })
+            })
+            .collect::<Result<_, _>>()?,
+        None => graph
+            .node_handles()
+            .filter_map(|handle| {
+                record(handle.raw())
+                    .filter(|r| r.is_armed() && r.is_recording() == recording)
+                    .map(|r| {
+                        (
+                            handle.raw(),
+                            r.label().to_string(),
+                            r.record_id().to_string(),
+                        )
+                    })
+            })
+            .collect(),
+    };
+    if targets.is_empty() {
+        return Err(SpectrumError::InvalidState(
+            "No armed record nodes to punch".to_string(),
+        ));
+    }
+    Ok(targets)
+}
+
+/// Detach the sessions of record nodes and finish their files (off the graph lock)
+async fn finish_record_nodes(handles: &[u32]) -> Result<Vec<RecordingStatusDto>, SpectrumError> {
+    let detached: Vec<(u32, RecordingHandle)> = get_graph_processor().with_graph_mut(|graph| {
+        handles
+            .iter()
+            .filter_map(|&handle| {
+                graph
+                    .get_node_mut(NodeHandle::from_raw(handle))
+                    .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+                    .and_then(|r| r.detach_session())
+                    .map(|session| (handle, session))
+            })
+            .collect()
+    });
+    let statuses = tokio::task::spawn_blocking(move || {
+        detached
+            .into_iter()
+            .map(|(handle, session)| (handle, crate::audio::record::finish_recording(session)))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .map_err(|e| format!("Failed to finish recording: {}", e))?;
+
+    let sample_rate = crate::audio::engine_sample_rate();
+    Ok(statuses
+        .into_iter()
+        .map(|(handle, status)| {
+            emit_graph_event(GraphEventDto::NodeChanged { handle });
+            RecordingStatusDto::from_status(handle, status, sample_rate)
+        })
+        .collect())
+}
+
+/// Start recording several record nodes at one render frame (sample-aligned takes)
+///
+/// `handles` defaults to every armed record node that is not recording. `at_frame` is a
+/// render frame (see the `start_frame` of recording statuses); omitted, the punch happens
+/// shortly after the call. Files go to the default recordings directory. If any node fails
+/// to start, none of them records and the files already created are deleted.
+#[tauri::command]
+pub async fn punch_in(
+    handles: Option<Vec<u32>>,
+    at_frame: Option<u64>,
+    format: Option<String>,
+) -> Result<PunchResultDto, SpectrumError> {
+    let format = parse_record_format(format.as_deref())?;
+    let processor = get_graph_processor();
+    let sample_rate = crate::audio::engine_sample_rate();
+
+    let targets = processor.with_graph(|graph| {
+        punch_targets(graph, handles.as_deref(), false).map(|targets| {
+            targets
+                .into_iter()
+                .map(|(handle, label, record_id)| {
+                    let channels = graph
+                        .get_node(NodeHandle::from_raw(handle))
+                        .map_or(1, |n| n.input_port_count());
+                    (handle, label, record_id, channels)
+                })
+                .collect::<Vec<_>>()
+        })
+    })?;
+
+    // ファイルとライタースレッドは先に用意する（グラフの変更では取り付けるだけ）
+    let mut sessions: Vec<(u32, RecordSession)> = Vec::with_capacity(targets.len());
+    for (handle, label, record_id, channels) in targets {
+        let opened =
+            default_record_path(&format!("{}_{}", label, record_id), format).and_then(|path| {
+                RecordSession::open(&record_id, channels, &path, format, sample_rate, 0)
+                    .map_err(SpectrumError::from)
+            });
+        match opened {
+            Ok(session) => sessions.push((handle, session)),
+            Err(e) => {
+                discard_recordings(sessions.into_iter().map(|(_, s)| s.into())).await;
+                return Err(e);
+            }
+        }
+    }
+
+    let frame = at_frame.unwrap_or_else(|| {
+        crate::audio::record::render_frame() + (PUNCH_LEAD_MS * sample_rate / 1000.0) as u64
+    });
+    for (_, session) in &sessions {
+        session.set_start_frame(frame);
+    }
+
+    // 全ノードを同じブロックの処理前に取り付ける（1 回のグラフ変更で）。
+    // 1 つでも取り付けられなければ、取り付け済みのものも外して全部返す
+    let attached = processor.with_graph_mut(move |graph| {
+        let mut started = Vec::with_capacity(sessions.len());
+        let mut sessions = sessions.into_iter();
+        while let Some((handle, session)) = sessions.next() {
+            let result = match graph
+                .get_node_mut(NodeHandle::from_raw(handle))
+                .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+            {
+                Some(record) => record
+                    .attach(session)
+                    .map(|()| record.status())
+                    .map_err(|session| (session, "is already recording or changed channels")),
+                None => Err((session, "is no longer a record node")),
+            };
+            match result {
+                Ok(status) => started.push((handle, status)),
+                Err((session, reason)) => {
+                    let mut rollback: Vec<RecordingHandle> = started
+                        .iter()
+                        .filter_map(|&(handle, _)| {
+                            graph
+                                .get_node_mut(NodeHandle::from_raw(handle))
+                                .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+                                .and_then(|r| r.detach_session())
+                        })
+                        .collect();
+                    rollback.push(session.into());
+                    rollback.extend(sessions.map(|(_, s)| s.into()));
+                    let error =
+                        SpectrumError::InvalidState(format!("Record node {} {}", handle, reason));
+                    return Err((error, rollback));
+                }
+            }
+        }
+        Ok(started)
+    });
+    let started = match attached {
+        Ok(started) => started,
+        Err((e, rollback)) => {
+            // 揃わないテイクは残さない
+            discard_recordings(rollback).await;
+            return Err(e);
+        }
+    };
+    log_info!(
+        "[graph] punch in {} record nodes at frame {}",
+        started.len(),
+        frame
+    );
+
+    Ok(PunchResultDto {
+        frame,
+        sample_rate,
+        nodes: started
+            .into_iter()
+            .map(|(handle, status)| {
+                emit_graph_event(GraphEventDto::NodeChanged { handle });
+                RecordingStatusDto::from_status(handle, status, sample_rate)
+            })
+            .collect(),
+    })
+}
+
+/// Stop several recordings at one render frame and finish their files
+///
+/// `handles` defaults to every armed record node that is recording. Omitting `at_frame`
+/// punches out shortly after the call; the command returns once the render position has
+/// passed the frame, with the final statuses.
+#[tauri::command]
+pub async fn punch_out(
+    handles: Option<Vec<u32>>,
+    at_frame: Option<u64>,
+) -> Result<PunchResultDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let sample_rate = crate::audio::engine_sample_rate();
+
+    let targets = processor.with_graph(|graph| punch_targets(graph, handles.as_deref(), true))?;
+    let handles: Vec<u32> = targets.into_iter().map(|(handle, ..)| handle).collect();
+    let now = crate::audio::record::render_frame();
+    let frame = at_frame.unwrap_or_else(|| now + (PUNCH_LEAD_MS * sample_rate / 1000.0) as u64);
+
+    processor.with_graph_mut(|graph| {
+        for &handle in &handles {
+            if let Some(record) = graph
+                .get_node_mut(NodeHandle::from_raw(handle))
+                .and_then(|n| n.as_any_mut().downcast_mut::<RecordNode>())
+            {
+                record.punch_out_at(frame);
+            }
+        }
+    });
+
+    // 出力が止まっていても待ち続けないよう、予定時刻 + 猶予で打ち切る
+    let wait = if sample_rate > 0.0 {
+        std::time::Duration::from_secs_f64(frame.saturating_sub(now) as f64 / sample_rate)
+    } else {
+        std::time::Duration::ZERO
+    };
+    let deadline = std::time::Instant::now() + wait + PUNCH_OUT_GRACE;
+    while crate::audio::record::render_frame() < frame && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let nodes = finish_record_nodes(&handles).await?;
+    log_info!(
+        "[graph] punch out {} record nodes at frame {}",
+        nodes.len(),
+        frame
+    );
+    Ok(PunchResultDto {
+        frame,
+        sample_rate,
+        nodes,
+    })
+}
+
+// =============================================================================
+// Marker Commands
+// =============================================================================
+
+/// Drop a time-stamped marker (default label "Marker <id>")
+///
+/// The marker is timed from the engine session start and, for every running recording,
+/// from the start of its file (also written to `<file>.markers.json`).
+#[tauri::command]
+pub async fn add_marker(label: Option<String>) -> Result<MarkerDto, SpectrumError> {
+    let marker = super::markers::add(label)?;
+    log_info!(
+        "[Markers] {} \"{}\" at {:.3}s ({} recordings)",
+        marker.id,
+        marker.label,
+        marker.session_secs,
+        marker.recordings.len()
+    );
+    Ok(marker)
+}
+
+#[tauri::command]
+pub async fn get_markers() -> Result<Vec<MarkerDto>, SpectrumError> {
+    Ok(super::markers::list())
+}
+
+/// Remove every stored marker; returns how many were removed
+#[tauri::command]
+pub async fn clear_markers() -> Result<usize, SpectrumError> {
+    super::markers::clear()
+}
+
+/// Export markers as "csv" or "edl" text (also written to `path` if given)
+///
+/// `recording_path` limits the export to one recording, timed from the start of that file.
+/// `fps` is the EDL timecode rate (default 30).
+#[tauri::command]
+pub async fn export_markers(
+    format: String,
+    recording_path: Option<String>,
+    fps: Option<u32>,
+    path: Option<String>,
+) -> Result<String, SpectrumError> {
+    let format = super::markers::MarkerFormat::parse(&format).ok_or_else(|| {
+        SpectrumError::InvalidArgument(format!("Unknown marker format: {}", format))
+    })?;
+    let text = super::markers::export(
+        format,
+        recording_path.as_deref(),
+        fps.unwrap_or(super::markers::DEFAULT_EDL_FPS),
+    );
+    if let Some(path) = path {
+        let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+        std::fs::write(&path, &text).map_err(|e| {
+            SpectrumError::Storage(format!("Failed to write {}: {}", path.display(), e))
+        })?;
+    }
+    Ok(text)
+}
+
+// =============================================================================
+// Bounce Commands
+// =============================================================================
+
+/// Resolve input paths (`~` expanded) for an offline render
+fn bounce_inputs(inputs: Vec<BounceInputDto>) -> Vec<crate::audio::bounce::BounceInput> {
+    inputs
+        .into_iter()
+        .map(|input| crate::audio::bounce::BounceInput {
+            path: std::path::PathBuf::from(shellexpand::tilde(&input.path).into_owned()),
+            source: NodeHandle::from_raw(input.source_handle),
+        })
+        .collect()
+}
+
+/// Validate an optional tail length (default 0)
+fn tail_seconds_arg(tail_seconds: Option<f32>) -> Result<f32, SpectrumError> {
+    let tail_seconds = tail_seconds.unwrap_or(0.0);
+    if !tail_seconds.is_finite() || tail_seconds < 0.0 {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Invalid tail length: {}",
+            tail_seconds
+        )));
+    }
+    Ok(tail_seconds)
+}
+
+/// Progress callback that emits `bounce-progress` events
+fn emit_render_progress(frames_rendered: u64, total_frames: u64) {
+    crate::api::events::emit_bounce_progress(BounceProgressDto {
+        frames_rendered,
+        total_frames,
+    })
+}
+
+/// Render audio files through the current graph offline and write a sink's output to a file.
+///
+/// Each input replaces a capture source's live audio (other sources are silent); the file
+/// format follows the extension of `out_path` (".caf" or WAV). `tail_seconds` (default 0,
+/// max 60) keeps rendering after the longest input ends. Live output is muted while
+/// rendering; progress is reported with `bounce-progress` events.
+#[tauri::command]
+pub async fn bounce_graph(
+    inputs: Vec<BounceInputDto>,
+    sink_handle: u32,
+    out_path: String,
+    tail_seconds: Option<f32>,
+) -> Result<BounceResultDto, SpectrumError> {
+    let inputs = bounce_inputs(inputs);
+    let out_path = std::path::PathBuf::from(shellexpand::tilde(&out_path).into_owned());
+    let tail_seconds = tail_seconds_arg(tail_seconds)?;
+
+    let report = tokio::task::spawn_blocking(move || {
+        crate::audio::bounce::bounce(
+            get_graph_processor(),
+            &inputs,
+            NodeHandle::from_raw(sink_handle),
+            &out_path,
+            tail_seconds,
+            emit_render_progress,
+        )
+    })
+    .await
+    .map_err(|e| format!("Bounce task failed: {}", e))??;
+
+    Ok(report.into())
+}
+
+/// Stop the running bounce or freeze (a bounce file keeps what was rendered).
+/// Returns false if none is running.
+#[tauri::command]
+pub async fn cancel_bounce() -> Result<bool, SpectrumError> {
+    Ok(crate::audio::bounce::cancel(get_graph_processor()))
+}
+
+// =============================================================================
+// Freeze Commands
+// =============================================================================
+
+/// Replace a bus's frozen cache (None unfreezes) and return the previous one
+fn swap_frozen_audio(
+    handle: u32,
+    audio: Option<Arc<FrozenAudio>>,
+) -> Result<Option<Arc<FrozenAudio>>, SpectrumError> {
+    get_graph_processor().with_graph_mut(|graph| {
+        let bus = graph
+            .get_node_mut(NodeHandle::from_raw(handle))
+            .and_then(|n| n.as_any_mut().downcast_mut::<BusNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "bus",
+            })?;
+        let previous = bus.unfreeze();
+        if let Some(audio) = audio {
+            bus.freeze(audio);
+        }
+        Ok(previous)
+    })
+}
+
+/// Render a bus's plugin chain offline and play the result instead of running the plugins.
+///
+/// Inputs work as in `bounce_graph`; without inputs, `tail_seconds` of generator / plugin
+/// source output is rendered. The frozen bus plays the cache at the transport position and
+/// is silent while the transport is stopped. Freezing a frozen bus renders it again (the old
+/// cache stays if that fails). Progress is reported with `bounce-progress` events.
+#[tauri::command]
+pub async fn freeze_bus(
+    handle: u32,
+    inputs: Option<Vec<BounceInputDto>>,
+    tail_seconds: Option<f32>,
+) -> Result<FrozenBusDto, SpectrumError> {
+    let inputs = bounce_inputs(inputs.unwrap_or_default());
+    let tail_seconds = tail_seconds_arg(tail_seconds)?;
+
+    // 今のキャッシュを外してプラグインチェーンを通した出力を録る
+    let previous = swap_frozen_audio(handle, None)?;
+    let rendered = tokio::task::spawn_blocking(move || {
+        crate::audio::freeze::render_bus(
+            get_graph_processor(),
+            NodeHandle::from_raw(handle),
+            &inputs,
+            tail_seconds,
+            emit_render_progress,
+        )
+    })
+    .await
+    .map_err(|e| format!("Freeze task failed: {}", e))
+    .and_then(|result| result);
+    let audio = match rendered {
+        Ok(audio) => Arc::new(audio),
+        Err(e) => {
+            if previous.is_some() {
+                swap_frozen_audio(handle, previous)?;
+            }
+            return Err(e.into());
+        }
+    };
+
+    let dto = FrozenBusDto {
+        handle,
+        port_count: audio.port_count(),
+        frames: audio.frames(),
+        duration_secs: audio.duration_ms() / 1000.0,
+    };
+    swap_frozen_audio(handle, Some(audio))?;
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(dto)
+}
+
+/// Run a frozen bus's plugins again and drop its cache. Returns false if it was not frozen.
+#[tauri::command]
+pub async fn unfreeze_bus(handle: u32) -> Result<bool, SpectrumError> {
+    let was_frozen = swap_frozen_audio(handle, None)?.is_some();
+    if was_frozen {
+        log_info!("[Freeze] Unfroze bus {}", handle);
+        emit_graph_event(GraphEventDto::NodeChanged { handle });
+    }
+    Ok(was_frozen)
+}
+
+// =============================================================================
+// Generator Commands
+// =============================================================================
+
+/// Apply DTO fields over `base` (unknown waveform names keep the base waveform)
+fn generator_params_from_dto(dto: &GeneratorParamsDto, base: GeneratorParams) -> GeneratorParams {
+    GeneratorParams {
+        waveform: Waveform::parse(&dto.waveform).unwrap_or(base.waveform),
+        frequency: dto.frequency,
+        level: dto.level,
+        sweep_end: dto.sweep_end,
+        sweep_seconds: dto.sweep_seconds,
+    }
+}
+
+/// Add a test signal generator ("sine", "pink", "white" or "sweep") as a source node
+#[tauri::command]
+pub async fn add_generator_node(
+    waveform: Option<String>,
+    label: Option<String>,
+    port_count: Option<u8>,
+) -> Result<u32, SpectrumError> {
+    let waveform = match waveform {
+        Some(w) => Waveform::parse(&w)
+            .ok_or_else(|| SpectrumError::InvalidArgument(format!("Unknown waveform: {}", w)))?,
+        None => Waveform::Sine,
+    };
+    let port_count = port_count.unwrap_or(2).max(1);
+
+    let generator_id = format!(
+        "gen_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    );
+    let label = label.unwrap_or_else(|| "Generator".to_string());
+    let params = GeneratorParams {
+        waveform,
+        ..GeneratorParams::default()
+    };
+    let node: Box<dyn AudioNode> = Box::new(GeneratorNode::new(
+        generator_id,
+        label,
+        port_count as usize,
+        params,
+    ));
+
+    let handle = get_graph_processor().add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Update a generator's parameters; omitted fields keep their current value.
+///
+/// `level_db` is dBFS (`null` keeps the level; use `level` for linear).
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_generator_params(
+    handle: u32,
+    waveform: Option<String>,
+    frequency: Option<f32>,
+    level: Option<f32>,
+    level_db: Option<f32>,
+    sweep_end: Option<f32>,
+    sweep_seconds: Option<f32>,
+) -> Result<GeneratorParamsDto, SpectrumError> {
+    let waveform =
+        match waveform {
+            Some(w) => Some(Waveform::parse(&w).ok_or_else(|| {
+                SpectrumError::InvalidArgument(format!("Unknown waveform: {}", w))
+            })?),
+            None => None,
+        };
+
+    let params = get_graph_processor().with_graph(|graph| {
+        let generator = graph
+            .get_node(NodeHandle::from_raw(handle))
+            .and_then(|n| n.as_any().downcast_ref::<GeneratorNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "generator",
+            })?;
+
+        let current = generator.params();
+        let params = GeneratorParams {
+            waveform: waveform.unwrap_or(current.waveform),
+            frequency: frequency.unwrap_or(current.frequency),
+            level: level
+                .or(level_db.map(|db| db_to_linear(Some(db))))
+                .unwrap_or(current.level),
+            sweep_end: sweep_end.unwrap_or(current.sweep_end),
+            sweep_seconds: sweep_seconds.unwrap_or(current.sweep_seconds),
+        };
+        generator.set_params(params);
+        Ok::<_, SpectrumError>(generator.params())
+    })?;
+
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(GeneratorParamsDto::from(params))
+}
+
+// =============================================================================
+// Plugin Source Commands
+// =============================================================================
+
+/// Describe the plugin of a plugin source (`state` is filled in by save_graph_state)
+fn plugin_source_dto(source: &PluginSourceNode) -> PluginInstanceDto {
+    PluginInstanceDto {
+        instance_id: source.instance_id().to_string(),
+        plugin_id: source.plugin_id().to_string(),
+        name: source.name().to_string(),
+        manufacturer: source.manufacturer().to_string(),
+        enabled: source.enabled(),
+        mix: 1.0,
+        ring_out: false,
+        state: None,
+        native: None,
+        out_of_process: source.is_out_of_process(),
+        crashed: source.is_crashed(),
+        overloaded: source.is_overloaded(),
+        layout: PluginLayout::Native.as_str().to_string(),
+    }
+}
+
+/// Generator and instrument AudioUnits (for `add_plugin_source_node`)
+#[tauri::command]
+pub async fn get_available_source_plugins() -> Result<Vec<PluginInfoDto>, SpectrumError> {
+    Ok(crate::plugin_registry::sources()
+        .into_iter()
+        .map(|p| PluginInfoDto {
+            error: crate::plugin_registry::failure(&p.id),
+            plugin_id: p.id,
+            name: p.name,
+            manufacturer: p.manufacturer,
+        })
+        .collect())
+}
+
+/// Add a generator or instrument AudioUnit as a stereo source node
+///
+/// インスタンスはバスのプラグインと同じくマネージャーに置くので、パラメータや UI は
+/// 返したノードの `instance_id`（`get_graph` の `plugin`）でそのまま操作できる。
+#[tauri::command]
+pub async fn add_plugin_source_node(
+    plugin_id: String,
+    label: Option<String>,
+) -> Result<u32, SpectrumError> {
+    let plugin = crate::plugin_registry::sources()
+        .into_iter()
+        .find(|p| p.id == plugin_id)
+        .ok_or_else(|| SpectrumError::PluginNotFound(plugin_id.clone()))?;
+    let instance_id = create_au_instance(&plugin).await?;
+
+    let source_id = format!(
+        "psrc_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    );
+    let label = label.unwrap_or_else(|| plugin.name.clone());
+    let node: Box<dyn AudioNode> = Box::new(PluginSourceNode::new(
+        source_id,
+        label,
+        instance_id,
+        plugin.id,
+        plugin.name,
+        plugin.manufacturer,
+    ));
+
+    let handle = get_graph_processor().add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Mute or unmute a plugin source (muted sources output silence without rendering)
+#[tauri::command]
+pub async fn set_plugin_source_enabled(handle: u32, enabled: bool) -> Result<(), SpectrumError> {
+    get_graph_processor().with_graph_mut(|graph| {
+        let source = graph
+            .get_node_mut(NodeHandle::from_raw(handle))
+            .and_then(|n| n.as_any_mut().downcast_mut::<PluginSourceNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "plugin_source",
+            })?;
+        source.set_enabled(enabled);
+        Ok::<_, SpectrumError>(())
+    })?;
+
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(())
+}
+
+// =============================================================================
+// Matrix Commands
+// =============================================================================
+
+/// Add a matrix node (N inputs × M outputs gain matrix)
+///
+/// `preset` is "identity" (default), "split" (every input to every output) or
+/// "downmix_5_1" (6 → 2, the default size for that preset).
+#[tauri::command]
+pub async fn add_matrix_node(
+    inputs: Option<u8>,
+    outputs: Option<u8>,
+    preset: Option<String>,
+    label: Option<String>,
+) -> Result<u32, SpectrumError> {
+    let preset = match preset {
+        Some(p) => MatrixPreset::parse(&p).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!("Unknown matrix preset: {}", p))
+        })?,
+        None => MatrixPreset::Identity,
+    };
+    let (default_inputs, default_outputs) = preset.shape().unwrap_or((2, 2));
+    let inputs = inputs.map_or(default_inputs, usize::from);
+    let outputs = outputs.map_or(default_outputs, usize::from);
+    for count in [inputs, outputs] {
+        if !(1..=MAX_MATRIX_CHANNELS).contains(&count) {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Matrix ports must be 1-{}, got {}",
+                MAX_MATRIX_CHANNELS, count
+            )));
+        }
+    }
+    if preset
+        .shape()
+        .is_some_and(|shape| shape != (inputs, outputs))
+    {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Preset {} needs {:?} (inputs, outputs)",
+            preset.as_str(),
+            preset.shape().unwrap_or_default()
+        )));
+    }
+
+    let matrix_id = format!(
+        "matrix_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    );
+    let label = label.unwrap_or_else(|| "Matrix".to_string());
+    let node: Box<dyn AudioNode> =
+        Box::new(MatrixNode::new(matrix_id, label, inputs, outputs, preset));
+
+    let handle = get_graph_processor().add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Replace a matrix node's coefficients (one row per output, one column per input)
+#[tauri::command]
+pub async fn set_matrix_coefficients(
+    handle: u32,
+    coefficients: Vec<Vec<f32>>,
+) -> Result<Vec<Vec<f32>>, SpectrumError> {
+    let applied = get_graph_processor().with_graph(|graph| {
+        let matrix = graph
+            .get_node(NodeHandle::from_raw(handle))
+            .and_then(|n| n.as_any().downcast_ref::<MatrixNode>())
+            .ok_or(SpectrumError::WrongNodeType {
+                handle,
+                expected: "matrix",
+            })?;
+        matrix
+            .set_coefficients(&coefficients)
+            .map_err(SpectrumError::InvalidArgument)?;
+        Ok::<_, SpectrumError>(matrix.coefficients())
+    })?;
+
+    emit_graph_event(GraphEventDto::NodeChanged { handle });
+    Ok(applied)
+}
+
+// =============================================================================
+// Loopback Commands
+// =============================================================================
+
+/// Add a loopback pair: audio sent to the sink comes back out of the source
+#[tauri::command]
+pub async fn add_loopback_pair(
+    label: Option<String>,
+    port_count: Option<u8>,
+) -> Result<LoopbackPairDto, SpectrumError> {
+    let processor = get_graph_processor();
+    let port_count = port_count.unwrap_or(2).max(1);
+
+    let loopback_id = format!(
+        "lb_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    );
+    let label = label.unwrap_or_else(|| "Loopback".to_string());
+    let (sink, source) =
+        crate::audio::loopback::loopback_pair(&loopback_id, &label, port_count as usize);
+
+    let sink_handle = processor.add_node(Box::new(sink));
+    let source_handle = processor.add_node(Box::new(source));
+    for handle in [sink_handle, source_handle] {
+        emit_graph_event(GraphEventDto::NodeAdded {
+            handle: handle.raw(),
+        });
+    }
+
+    Ok(LoopbackPairDto {
+        loopback_id,
+        sink_handle: sink_handle.raw(),
+        source_handle: source_handle.raw(),
+    })
+}
+
+// =============================================================================
+// Network Audio Commands
+// =============================================================================
+
+fn new_net_id() -> String {
+    format!(
+        "net_{}",
+        uuid::Uuid::new_v4()
+            .to_string()
+            .split('-')
+            .next()
+            .unwrap_or("0")
+    )
+}
+
+/// Add a network send node: streams its inputs as RTP (L24) to `address:port`
+///
+/// `address` may be a unicast host or a multicast group (e.g. 239.69.0.1).
+#[tauri::command]
+pub async fn add_net_send_node(
+    address: String,
+    port: u16,
+    port_count: Option<u8>,
+    packet_time_us: Option<u32>,
+    label: Option<String>,
+) -> Result<u32, SpectrumError> {
+    use crate::audio::net::{DEFAULT_PACKET_TIME_US, MAX_NET_CHANNELS};
+
+    let address = address.trim().to_string();
+    if address.is_empty() || port == 0 {
+        return Err(SpectrumError::InvalidArgument(
+            "Destination address and port are required".to_string(),
+        ));
+    }
+    let port_count = port_count.unwrap_or(2);
+    if port_count == 0 || port_count as usize > MAX_NET_CHANNELS {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "port_count must be 1..={}",
+            MAX_NET_CHANNELS
+        )));
+    }
+
+    let label = label.unwrap_or_else(|| format!("Send {}:{}", address, port));
+    let node = NetSendSinkNode::new(
+        new_net_id(),
+        label,
+        port_count as usize,
+        &address,
+        port,
+        packet_time_us.unwrap_or(DEFAULT_PACKET_TIME_US),
+        crate::audio::engine_sample_rate(),
+    )
+    .map_err(SpectrumError::InvalidArgument)?;
+
+    let handle = get_graph_processor().add_node(Box::new(node));
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Add a network receive node: plays an RTP (L24) stream arriving on `port`
+///
+/// `address` is a local address or a multicast group to join (default: any).
+#[tauri::command]
+pub async fn add_net_receive_node(
+    port: u16,
+    address: Option<String>,
+    port_count: Option<u8>,
+    jitter_ms: Option<u32>,
+    label: Option<String>,
+) -> Result<u32, SpectrumError> {
+    use crate::audio::net::{DEFAULT_JITTER_MS, MAX_NET_CHANNELS};
+
+    if port == 0 {
+        return Err(SpectrumError::InvalidArgument(
+            "Listen port is required".to_string(),
+        ));
+    }
+    let port_count = port_count.unwrap_or(2);
+    if port_count == 0 || port_count as usize > MAX_NET_CHANNELS {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "port_count must be 1..={}",
+            MAX_NET_CHANNELS
+        )));
+    }
+
+    let address = address.unwrap_or_default();
+    let label = label.unwrap_or_else(|| format!("Receive :{}", port));
+    let node = NetReceiveSourceNode::new(
+        new_net_id(),
+        label,
+        port_count as usize,
+        &address,
+        port,
+        jitter_ms.unwrap_or(DEFAULT_JITTER_MS),
+        crate::audio::engine_sample_rate(),
+    )
+    .map_err(SpectrumError::InvalidArgument)?;
+
+    let handle = get_graph_processor().add_node(Box::new(node));
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    Ok(handle.raw())
+}
+
+/// Packet / buffer statistics of a network node
+#[tauri::command]
+pub async fn get_net_node_status(handle: u32) -> Result<NetStatusDto, SpectrumError> {
+    let status = get_graph_processor()
+        .with_graph(|graph| {
+            let node = graph.get_node(NodeHandle::from_raw(handle))?;
+            let any = node.as_any();
+            any.downcast_ref::<NetSendSinkNode>()
+                .map(|n| n.status())
+                .or_else(|| {
+                    any.downcast_ref::<NetReceiveSourceNode>()
+                        .map(|n| n.status())
+                })
+        })
+        .ok_or(SpectrumError::WrongNodeType {
+            handle,
+            expected: "network",
+        })?;
+    Ok(NetStatusDto::from_status(handle, status))
+}
+
+// =============================================================================
+// Scene Commands
+// =============================================================================
+
+/// Save the current mix parameters as a named scene (overwrites a scene with the same name)
+#[tauri::command]
+pub async fn save_scene(name: String) -> Result<SceneInfoDto, SpectrumError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(SpectrumError::InvalidArgument(
+            "Scene name must not be empty".to_string(),
+        ));
+    }
+
+    let scene = super::scenes::capture(&name).await?;
+    let info = SceneInfoDto {
+        name: scene.name.clone(),
+        saved_at: scene.saved_at,
+        edge_count: scene.edges.len(),
+    };
+
+    let mut store = super::scenes::load_store()?;
+    match store.scenes.iter_mut().find(|s| s.name == name) {
+        Some(existing) => *existing = scene,
+        None => store.scenes.push(scene),
+    }
+    super::scenes::save_store(&store)?;
+
+    log_info!("[Scenes] Saved '{}' (edges={})", info.name, info.edge_count);
+    Ok(info)
+}
+
+/// Recall a scene. `fade_ms` ramps edge/output gains (default: instant).
+#[tauri::command]
+pub async fn recall_scene(
+    name: String,
+    fade_ms: Option<u32>,
+) -> Result<SceneRecallDto, SpectrumError> {
+    let store = super::scenes::load_store()?;
+    let scene = store
+        .scenes
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| SpectrumError::SceneNotFound(name.clone()))?;
+
+    let result = super::scenes::recall(scene, fade_ms.unwrap_or(0)).await?;
+    emit_graph_event(GraphEventDto::GraphReloaded);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn list_scenes() -> Result<Vec<SceneInfoDto>, SpectrumError> {
+    let store = super::scenes::load_store()?;
+    Ok(store
+        .scenes
+        .iter()
+        .map(|s| SceneInfoDto {
+            name: s.name.clone(),
+            saved_at: s.saved_at,
+            edge_count: s.edges.len(),
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn delete_scene(name: String) -> Result<(), SpectrumError> {
+    let mut store = super::scenes::load_store()?;
+    let before = store.scenes.len();
+    store.scenes.retain(|s| s.name != name);
+    if store.scenes.len() == before {
+        return Err(SpectrumError::SceneNotFound(name));
+    }
+    Ok(super::scenes::save_store(&store)?)
+}
+
+// =============================================================================
+// Channel Template Commands
+// =============================================================================
+
+/// Save a bus (ports, plugin chain with parameters, sends) as a named template
+///
+/// 同じ名前のテンプレートは上書きする。
+#[tauri::command]
+pub async fn save_channel_template(
+    bus_handle: u32,
+    name: String,
+) -> Result<ChannelTemplateInfoDto, SpectrumError> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err(SpectrumError::InvalidArgument(
+            "Template name must not be empty".to_string(),
+        ));
+    }
+
+    let template = super::templates::capture(bus_handle, &name).await?;
+    let info = ChannelTemplateInfoDto::from(&template);
+
+    let mut store = super::templates::load_store()?;
+    match store.templates.iter_mut().find(|t| t.name == name) {
+        Some(existing) => *existing = template,
+        None => store.templates.push(template),
+    }
+    super::templates::save_store(&store)?;
+
+    log_info!(
+        "[Templates] Saved '{}' (plugins={}, sends={})",
+        info.name,
+        info.plugins.len(),
+        info.send_count
+    );
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn list_channel_templates() -> Result<Vec<ChannelTemplateInfoDto>, SpectrumError> {
+    let store = super::templates::load_store()?;
+    Ok(store
+        .templates
+        .iter()
+        .map(ChannelTemplateInfoDto::from)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn delete_channel_template(name: String) -> Result<(), SpectrumError> {
+    let mut store = super::templates::load_store()?;
+    let before = store.templates.len();
+    store.templates.retain(|t| t.name != name);
+    if store.templates.len() == before {
+        return Err(SpectrumError::TemplateNotFound(name));
+    }
+    Ok(super::templates::save_store(&store)?)
+}
+
+/// Create a new bus from a template (label defaults to the template's bus label)
+///
+/// プラグインは新しいインスタンスとして作り、保存したパラメーターを復元する。
+/// センドは送り先のノードがグラフにあれば繋ぐ（無ければ `missing_targets` に入る）。
+#[tauri::command]
+pub async fn create_bus_from_template(
+    name: String,
+    label: Option<String>,
+) -> Result<TemplateInstanceDto, SpectrumError> {
+    let store = super::templates::load_store()?;
+    let template = store
+        .templates
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| SpectrumError::TemplateNotFound(name.clone()))?;
+    let processor = get_graph_processor();
+
+    let label = label
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .unwrap_or_else(|| template.label.clone());
+    let bus_info = super::templates::bus_info(&template, new_bus_id(), label);
+    let node = NodeBuilder::new()
+        .build(&bus_info)
+        .await
+        .ok_or_else(|| SpectrumError::Other(format!("Failed to create bus from {}", name)))?;
+
+    // 作れなかったプラグイン（未インストール・読み込み失敗）は飛ばされている
+    let created: Vec<String> = node
+        .as_any()
+        .downcast_ref::<BusNode>()
+        .map(|bus| bus.plugins().iter().map(|p| p.plugin_id.clone()).collect())
+        .unwrap_or_default();
+    let mut remaining = created.iter().peekable();
+    let skipped_plugins: Vec<String> = template
+        .plugins
+        .iter()
+        .filter(|p| {
+            if remaining.peek() == Some(&&p.plugin_id) {
+                remaining.next();
+                false
+            } else {
+                true
+            }
         })
+        .map(|p| p.plugin_id.clone())
         .collect();
 
-    Ok(filtered)
+    let handle = processor.add_node(node);
+    emit_graph_event(GraphEventDto::NodeAdded {
+        handle: handle.raw(),
+    });
+    fit_plugin_layouts(&[handle]);
+
+    let graph = get_graph().await?;
+    let handle_by_stable: HashMap<String, NodeHandle> = graph
+        .nodes
+        .iter()
+        .map(|n| (node_stable_id(n), NodeHandle::from_raw(n.handle())))
+        .collect();
+
+    let mut edges = Vec::new();
+    let mut missing_targets = Vec::new();
+    for send in &template.sends {
+        let Some(&target) = handle_by_stable.get(&send.target) else {
+            missing_targets.push(send.target.clone());
+            continue;
+        };
+        let edge_info = &send.edge;
+        let mut skipped = Vec::with_capacity(2);
+        let edge_id = processor.with_graph_mut(|graph| {
+            let edge_id = graph.add_bundle_edge_with_params(
+                handle,
+                PortId::from(edge_info.source_port),
+                target,
+                PortId::from(edge_info.target_port),
+                edge_info.channels.max(1),
+                edge_info.gain,
+                edge_info.muted,
+            )?;
+            apply_edge_settings(graph, edge_id, edge_info, &mut skipped);
+            Some(edge_id)
+        });
+        log_skipped_edge_settings("create_bus_from_template", skipped);
+        match edge_id {
+            Some(edge_id) => {
+                emit_graph_event(GraphEventDto::EdgeAdded { id: edge_id.raw() });
+                edges.push(edge_id.raw());
+            }
+            None => log_warn!(
+                "[Templates] '{}': send to {} rejected",
+                template.name,
+                send.target
+            ),
+        }
+    }
+
+    log_info!(
+        "[Templates] Created bus {} from '{}' (sends={}, skipped plugins={}, missing targets={})",
+        handle.raw(),
+        template.name,
+        edges.len(),
+        skipped_plugins.len(),
+        missing_targets.len()
+    );
+    Ok(TemplateInstanceDto {
+        handle: handle.raw(),
+        edges,
+        skipped_plugins,
+        missing_targets,
+    })
 }
 
 // =============================================================================
@@ -1256,30 +6039,39 @@ pub async fn get_edge_meters(ids: Vec<u32>) -> Result<Vec<EdgeMeterDto>, String>
 // =============================================================================
 
 #[tauri::command]
-pub async fn save_graph_state(ui_state: Option<UIStateDto>) -> Result<GraphStateDto, String> {
+pub async fn save_graph_state(
+    ui_state: Option<UIStateDto>,
+) -> Result<GraphStateDto, SpectrumError> {
     use base64::Engine;
 
     let mut graph_dto = get_graph().await?;
 
     // Capture plugin parameter state (AU fullState) for all known instances.
     // This can be large, so we only populate it for persisted GraphState.
-    let au_manager = crate::audio_unit::get_au_manager();
-    let states = au_manager.collect_all_instance_states();
+    // 遅いプラグインを待つ間ランタイムのワーカーを塞がないように（タイムアウト後はキャッシュ）
+    let states = tokio::task::spawn_blocking(|| {
+        crate::audio_unit::get_au_manager().collect_all_instance_states()
+    })
+    .await
+    .map_err(|e| format!("State capture task failed: {}", e))?;
 
     for node in &mut graph_dto.nodes {
-        if let NodeInfoDto::Bus { plugins, .. } = node {
-            for p in plugins.iter_mut() {
-                let state = states
-                    .get(&p.instance_id)
-                    .and_then(|s| s.as_ref())
-                    .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes));
-                p.state = state;
-            }
+        let plugins = match node {
+            NodeInfoDto::Bus { plugins, .. } => plugins.as_mut_slice(),
+            NodeInfoDto::PluginSource { plugin, .. } => std::slice::from_mut(plugin),
+            _ => continue,
+        };
+        for p in plugins.iter_mut() {
+            let state = states
+                .get(&p.instance_id)
+                .and_then(|s| s.as_ref())
+                .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes));
+            p.state = state;
         }
     }
 
     Ok(GraphStateDto {
-        version: 3,
+        version: GRAPH_STATE_VERSION,
         nodes: graph_dto.nodes,
         edges: graph_dto.edges,
         ui_state,
@@ -1287,7 +6079,7 @@ pub async fn save_graph_state(ui_state: Option<UIStateDto>) -> Result<GraphState
 }
 
 #[tauri::command]
-pub async fn load_graph_state(state: GraphStateDto) -> Result<(), String> {
+pub async fn load_graph_state(mut state: GraphStateDto) -> Result<(), SpectrumError> {
     let processor = get_graph_processor();
 
     state_log_summary(format!(
@@ -1302,162 +6094,920 @@ pub async fn load_graph_state(state: GraphStateDto) -> Result<(), String> {
         }
     ));
 
-    // Reset AudioUnit instances (plugin chain state belongs to the graph state).
-    crate::audio_unit::get_au_manager().remove_all_instances();
+    // Older formats (e.g. imported files) are upgraded before anything is touched
+    persistence::migrate(&mut state).map_err(SpectrumError::InvalidArgument)?;
 
-    // Lookup table for plugin info by ID (for recreating AU instances on restore).
-    let mut plugin_lookup: HashMap<String, crate::audio_unit::AudioUnitInfo> = HashMap::new();
-    for p in crate::audio_unit::get_effect_audio_units() {
-        plugin_lookup.insert(p.id.clone(), p);
-    }
-    for p in crate::audio_unit::get_instrument_audio_units() {
-        plugin_lookup.insert(p.id.clone(), p);
-    }
-    for p in crate::audio_unit::get_generator_audio_units() {
-        plugin_lookup.insert(p.id.clone(), p);
+    // Already-corrupted files (duplicate nodes, dangling edges, ...) are fixed up front
+    for issue in integrity::repair(&mut state) {
+        state_log_summary(format!("load_graph_state: repaired: {}", issue.message));
     }
 
+    // Reset AudioUnit instances (plugin chain state belongs to the graph state).
+    crate::audio_unit::get_au_manager().remove_all_instances();
+
     // Clear existing graph and rebuild from state
+    // （ハンドルの置き場はグラフの外で確保しておく）
+    let mut handles: Vec<NodeHandle> =
+        Vec::with_capacity(processor.with_graph(|graph| graph.node_handles().count()) + 16);
     processor.with_graph_mut(|graph| {
         // Clear existing nodes and edges
-        let handles: Vec<_> = graph.node_handles().collect();
-        state_log_verbose(format!(
-            "load_graph_state: clearing existing nodes={}",
-            handles.len()
-        ));
-        for handle in handles {
+        handles.extend(graph.node_handles());
+        for &handle in &handles {
             graph.remove_node(handle);
         }
     });
+    state_log_verbose(format!(
+        "load_graph_state: cleared existing nodes={}",
+        handles.len()
+    ));
+    processor.clear_pending_edge_params();
 
     // Recreate nodes
     let mut handle_mapping: std::collections::HashMap<u32, NodeHandle> =
         std::collections::HashMap::new();
-    let mut stable_to_handle: std::collections::HashMap<String, NodeHandle> =
-        std::collections::HashMap::new();
-
-    // Track physical input devices referenced by the restored graph.
-    // We'll ensure capture is running for them after the graph is rebuilt.
-    let mut restore_input_devices: std::collections::HashSet<u32> =
-        std::collections::HashSet::new();
+    let mut builder = NodeBuilder::new();
+    // Nodes that could not be created (their edges are dropped too)
+    let mut skipped_handles: HashSet<u32> = HashSet::new();
 
     let mut recreated_nodes: usize = 0;
-    let mut deduped_nodes: usize = 0;
+    let mut moved_handles: usize = 0;
 
     for node_info in &state.nodes {
-        // De-dup nodes by stable id to guard against already-corrupted state files.
-        // If duplicates exist, we map all old handles to the first recreated node.
-        let stable_id = match node_info {
-            NodeInfoDto::Source { stable_id, .. }
-            | NodeInfoDto::Bus { stable_id, .. }
-            | NodeInfoDto::Sink { stable_id, .. } => {
-                if stable_id.trim().is_empty() {
-                    compute_stable_id_for_node(node_info)
-                } else {
-                    stable_id.clone()
+        let old_handle_u32 = node_info.handle();
+
+        let Some(node) = builder.build(node_info).await else {
+            skipped_handles.insert(old_handle_u32);
+            continue;
+        };
+        // 保存時のハンドルを保つ（再起動・再読み込みをまたいで外部コントローラーの参照が有効）
+        let new_handle = processor.add_node_at(NodeHandle::from_raw(old_handle_u32), node);
+        if new_handle.raw() != old_handle_u32 {
+            moved_handles += 1;
+        }
+        if let Some(color) = node_info.color() {
+            processor.set_node_color(new_handle, Some(color.to_string()));
+        }
+        if !node_info.metadata().is_empty() {
+            processor.replace_node_metadata(new_handle, node_info.metadata().clone());
+        }
+        handle_mapping.insert(old_handle_u32, new_handle);
+        recreated_nodes += 1;
+    }
+
+    state_log_summary(format!(
+        "load_graph_state: recreated_nodes={} mapped_handles={} moved_handles={}",
+        recreated_nodes,
+        handle_mapping.len(),
+        moved_handles
+    ));
+
+    // Recreate edges with mapped handles
+    let mut recreated_edges: usize = 0;
+    for edge_info in &state.edges {
+        if skipped_handles.contains(&edge_info.source)
+            || skipped_handles.contains(&edge_info.target)
+        {
+            continue;
+        }
+        let source_handle = handle_mapping.get(&edge_info.source).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!(
+                "Source node {} not found in mapping",
+                edge_info.source
+            ))
+        })?;
+        let target_handle = handle_mapping.get(&edge_info.target).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!(
+                "Target node {} not found in mapping",
+                edge_info.target
+            ))
+        })?;
+
+        let mut skipped = Vec::with_capacity(2);
+        let edge_id = processor.with_graph_mut(|graph| {
+            let edge_id = graph.add_bundle_edge_with_params(
+                *source_handle,
+                PortId::from(edge_info.source_port),
+                *target_handle,
+                PortId::from(edge_info.target_port),
+                edge_info.channels.max(1),
+                edge_info.gain,
+                edge_info.muted,
+            )?;
+            apply_edge_settings(graph, edge_id, edge_info, &mut skipped);
+            Some(edge_id)
+        });
+        log_skipped_edge_settings("load_graph_state", skipped);
+        if edge_id.is_none() {
+            state_log_summary(format!(
+                "load_graph_state: edge {} -> {} rejected",
+                edge_info.source, edge_info.target
+            ));
+        }
+        recreated_edges += 1;
+    }
+
+    state_log_summary(format!(
+        "load_graph_state: recreated_edges={}",
+        recreated_edges
+    ));
+
+    builder.start_input_captures("load_graph_state");
+    fit_plugin_layouts(&bus_handles());
+
+    emit_graph_event(GraphEventDto::GraphReloaded);
+    Ok(())
+}
+
+/// Apply a whole graph description by diffing it against the current graph
+///
+/// Nodes are matched by stable_id. A matching node keeps its handle (and its
+/// plugin instances); only its label and settings are updated. A node whose kind,
+/// port count or bus plugin list changed is recreated. Nodes and edges missing
+/// from `graph` are removed and new ones are created.
+///
+/// 新しいノード（プラグインのインスタンス化を含む）は先に作っておき、削除・追加・更新を
+/// グラフの 1 回のロックでまとめて入れ替える。変わらない経路は鳴り続ける。
+/// Handles in `graph` only need to be consistent between its nodes and edges;
+/// the result maps them to the handles in the audio graph and lists the nodes and
+/// edges that could not be created.
+#[tauri::command]
+pub async fn apply_graph(graph: GraphDto) -> Result<ApplyGraphResultDto, SpectrumError> {
+    let processor = get_graph_processor();
+
+    // Handles of the description -> stable_id
+    let mut target_ids: HashMap<u32, String> = HashMap::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    for node_info in &graph.nodes {
+        let stable_id = node_stable_id(node_info);
+        if !seen.insert(stable_id.clone()) {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Duplicate node {}",
+                stable_id
+            )));
+        }
+        target_ids.insert(node_info.handle(), stable_id);
+    }
+    for edge in &graph.edges {
+        for handle in [edge.source, edge.target] {
+            if !target_ids.contains_key(&handle) {
+                return Err(SpectrumError::InvalidArgument(format!(
+                    "Edge {} references unknown node {}",
+                    edge.id, handle
+                )));
+            }
+        }
+    }
+
+    let current = get_graph().await?;
+    let current_nodes: HashMap<String, &NodeInfoDto> = current
+        .nodes
+        .iter()
+        .map(|n| (node_stable_id(n), n))
+        .collect();
+    let current_ids: HashMap<u32, String> = current_nodes
+        .iter()
+        .map(|(stable_id, n)| (n.handle(), stable_id.clone()))
+        .collect();
+
+    let mut result = ApplyGraphResultDto::default();
+
+    // Kept nodes are updated in place; everything else is (re)created
+    let mut kept: HashMap<String, NodeHandle> = HashMap::new();
+    let mut refs: HashMap<&str, ApplyNode> = HashMap::new();
+    let mut builder = NodeBuilder::new();
+    let mut created: Vec<Option<Box<dyn AudioNode>>> = Vec::new();
+    for node_info in &graph.nodes {
+        let stable_id = target_ids[&node_info.handle()].as_str();
+        match current_nodes.get(stable_id) {
+            Some(existing) if !needs_rebuild(existing, node_info) => {
+                let handle = NodeHandle::from_raw(existing.handle());
+                kept.insert(stable_id.to_string(), handle);
+                refs.insert(stable_id, ApplyNode::Kept(handle));
+            }
+            _ => match builder.build(node_info).await {
+                Some(node) => {
+                    refs.insert(stable_id, ApplyNode::Created(created.len()));
+                    created.push(Some(node));
+                }
+                None => result.nodes_failed.push(node_info.handle()),
+            },
+        }
+    }
+    let removed: Vec<&NodeInfoDto> = current_nodes
+        .iter()
+        .filter(|(stable_id, _)| !kept.contains_key(*stable_id))
+        .map(|(_, n)| *n)
+        .collect();
+    let removed_handles: Vec<NodeHandle> = removed
+        .iter()
+        .map(|n| NodeHandle::from_raw(n.handle()))
+        .collect();
+
+    // 色・メタデータは差し替え用の値を先に用意しておく（使わなかった分はグラフの外で drop）
+    let mut updates: Vec<NodeUpdate> = graph
+        .nodes
+        .iter()
+        .filter_map(|node_info| {
+            let node = *refs.get(target_ids[&node_info.handle()].as_str())?;
+            Some(NodeUpdate {
+                node,
+                info: node_info,
+                color: node_info.color().map(str::to_string),
+                metadata: Some(node_info.metadata().clone()),
+            })
+        })
+        .collect();
+    // 有効に戻すバスのプラグインは AU 側もグラフの外で有効にする
+    let au_enables: Vec<String> = updates
+        .iter()
+        .filter(|update| matches!(update.node, ApplyNode::Kept(_)))
+        .filter_map(|update| {
+            let NodeInfoDto::Bus { plugins, .. } = update.info else {
+                return None;
+            };
+            let NodeInfoDto::Bus {
+                plugins: current_plugins,
+                ..
+            } = current_nodes.get(target_ids[&update.info.handle()].as_str())?
+            else {
+                return None;
+            };
+            Some(
+                current_plugins
+                    .iter()
+                    .zip(plugins)
+                    .filter(|(current, target)| target.enabled && !current.enabled)
+                    .map(|(current, _)| current.instance_id.clone()),
+            )
+        })
+        .flatten()
+        .collect();
+
+    let mut current_edges: HashMap<EdgeKey, EdgeId> = current
+        .edges
+        .iter()
+        .filter_map(|e| Some((edge_key(&current_ids, e)?, EdgeId::from(e.id))))
+        .collect();
+
+    // Edges whose nodes were kept stay in place (gain ramps, no interruption)
+    let mut wanted: Vec<(EdgeId, &EdgeInfoDto)> = Vec::new();
+    let mut added: Vec<(ApplyNode, ApplyNode, &EdgeInfoDto)> = Vec::new();
+    for edge in &graph.edges {
+        let Some(key) = edge_key(&target_ids, edge) else {
+            continue;
+        };
+        let both_kept = kept.contains_key(&key.0) && kept.contains_key(&key.2);
+        match current_edges.remove(&key) {
+            Some(edge_id) if both_kept => wanted.push((edge_id, edge)),
+            _ => {
+                // 作れなかったノード（ポート使用中など）のエッジは張らない
+                match (refs.get(key.0.as_str()), refs.get(key.2.as_str())) {
+                    (Some(&source), Some(&target)) => added.push((source, target, edge)),
+                    _ => result.edges_rejected.push(edge.id),
+                }
+            }
+        }
+    }
+    let stale: Vec<EdgeId> = current_edges.into_values().collect();
+
+    let plugin_instances: Vec<String> = removed
+        .iter()
+        .flat_map(|n| match n {
+            NodeInfoDto::Bus { plugins, .. } => plugins.as_slice(),
+            NodeInfoDto::PluginSource { plugin, .. } => std::slice::from_ref(plugin),
+            _ => &[],
+        })
+        .map(|p| p.instance_id.clone())
+        .collect();
+
+    // ダイレクトモニターの有無が変わった出力はユニットを作り直す
+    let outputs = crate::audio::output::get_active_output_devices();
+    let mut direct_before: Vec<bool> = Vec::with_capacity(outputs.len());
+    let mut direct_after: Vec<bool> = Vec::with_capacity(outputs.len());
+    let mut created_handles: Vec<NodeHandle> = Vec::with_capacity(created.len());
+    let mut rejected: Vec<&EdgeInfoDto> = Vec::with_capacity(added.len());
+    let mut skipped = Vec::with_capacity(2 * (wanted.len() + added.len()));
+
+    // 計画はすべて済ませてあり、ここでは適用するだけ
+    processor.with_graph_mut(|audio_graph| {
+        direct_before.extend(outputs.iter().map(|&d| audio_graph.has_direct_monitor(d)));
+
+        for &handle in &removed_handles {
+            if audio_graph.remove_node(handle) {
+                result.nodes_removed += 1;
+            }
+        }
+        for node in created.iter_mut().filter_map(Option::take) {
+            created_handles.push(audio_graph.add_node(node));
+            result.nodes_added += 1;
+        }
+        for update in &mut updates {
+            let Some(handle) = update.node.handle(&created_handles) else {
+                continue;
+            };
+            let recolored = audio_graph.node_color(handle) != update.info.color();
+            if recolored {
+                audio_graph.set_node_color(handle, update.color.take());
+            }
+            let retagged = audio_graph
+                .node_metadata(handle)
+                .unwrap_or(&BTreeMap::new())
+                != update.info.metadata();
+            if retagged {
+                if let Some(metadata) = update.metadata.take() {
+                    audio_graph.replace_node_metadata(handle, metadata);
+                }
+            }
+            if let ApplyNode::Kept(_) = update.node {
+                let updated = audio_graph
+                    .get_node_mut(handle)
+                    .is_some_and(|node| update_node_from_dto(node, update.info));
+                if updated || recolored || retagged {
+                    result.nodes_updated += 1;
+                }
+            }
+        }
+
+        for &edge_id in &stale {
+            if audio_graph.remove_edge(edge_id) {
+                result.edges_removed += 1;
+            }
+        }
+        for &(edge_id, edge) in &wanted {
+            if apply_edge_settings(audio_graph, edge_id, edge, &mut skipped) {
+                result.edges_updated += 1;
+            }
+        }
+        for &(source, target, edge) in &added {
+            let (Some(source), Some(target)) = (
+                source.handle(&created_handles),
+                target.handle(&created_handles),
+            ) else {
+                continue;
+            };
+            let Some(edge_id) = audio_graph.add_bundle_edge_with_params(
+                source,
+                PortId::from(edge.source_port),
+                target,
+                PortId::from(edge.target_port),
+                edge.channels.max(1),
+                edge.gain,
+                edge.muted,
+            ) else {
+                rejected.push(edge);
+                continue;
+            };
+            apply_edge_settings(audio_graph, edge_id, edge, &mut skipped);
+            result.edges_added += 1;
+        }
+
+        direct_after.extend(outputs.iter().map(|&d| audio_graph.has_direct_monitor(d)));
+    });
+
+    for update in &updates {
+        if let Some(handle) = update.node.handle(&created_handles) {
+            result.handles.insert(update.info.handle(), handle.raw());
+        }
+    }
+    for instance_id in &au_enables {
+        sync_bus_au_enabled(instance_id, true);
+    }
+    for edge in &rejected {
+        state_log_summary(format!(
+            "apply_graph: edge {} -> {} rejected",
+            edge.source, edge.target
+        ));
+        result.edges_rejected.push(edge.id);
+    }
+    log_skipped_edge_settings("apply_graph", skipped);
+    let direct_changed: Vec<u32> = outputs
+        .iter()
+        .zip(direct_before.iter().zip(&direct_after))
+        .filter(|(_, (before, after))| before != after)
+        .map(|(&device_id, _)| device_id)
+        .collect();
+
+    // グラフから外してからインスタンスを解放する
+    release_plugin_instances(&plugin_instances, "apply_graph");
+    for device_id in direct_changed {
+        if let Err(e) = crate::audio::output::restart_output_device(device_id) {
+            log_error!(
+                "[api] apply_graph: failed to restart output {}: {}",
+                device_id,
+                e
+            );
+        }
+    }
+    builder.start_input_captures("apply_graph");
+
+    state_log_summary(format!(
+        "apply_graph: nodes +{} -{} ~{} !{} edges +{} -{} ~{} !{}",
+        result.nodes_added,
+        result.nodes_removed,
+        result.nodes_updated,
+        result.nodes_failed.len(),
+        result.edges_added,
+        result.edges_removed,
+        result.edges_updated,
+        result.edges_rejected.len()
+    ));
+    if result.changed() {
+        fit_plugin_layouts(&bus_handles());
+        emit_graph_event(GraphEventDto::GraphReloaded);
+    }
+    Ok(result)
+}
+
+/// A node of an `apply_graph` description: kept in place or created for it
+#[derive(Debug, Clone, Copy)]
+enum ApplyNode {
+    Kept(NodeHandle),
+    /// Index into the created nodes (in the order they are added)
+    Created(usize),
+}
+
+impl ApplyNode {
+    /// Handle in the audio graph (`created` holds the handles of the added nodes)
+    fn handle(self, created: &[NodeHandle]) -> Option<NodeHandle> {
+        match self {
+            ApplyNode::Kept(handle) => Some(handle),
+            ApplyNode::Created(index) => created.get(index).copied(),
+        }
+    }
+}
+
+/// Settings `apply_graph` applies to one node of its description
+///
+/// 色とメタデータは差し替える値を用意しておき、変わったときだけグラフに渡す。
+struct NodeUpdate<'a> {
+    node: ApplyNode,
+    info: &'a NodeInfoDto,
+    color: Option<String>,
+    metadata: Option<BTreeMap<String, String>>,
+}
+
+/// Key matching edges across graph descriptions
+///
+/// (source, source port, target, target port, channels)。ノードはハンドルではなく
+/// stable_id で比べる（説明ごとにハンドルが違ってもよい）。
+type EdgeKey = (String, u8, String, u8, u8);
+
+/// Key of an edge; `ids` maps the description's handles to stable ids (None if unknown)
+fn edge_key(ids: &HashMap<u32, String>, e: &EdgeInfoDto) -> Option<EdgeKey> {
+    Some((
+        ids.get(&e.source)?.clone(),
+        e.source_port,
+        ids.get(&e.target)?.clone(),
+        e.target_port,
+        e.channels.max(1),
+    ))
+}
+
+/// Stable id of a node description (computed when the DTO has none)
+pub(super) fn node_stable_id(node_info: &NodeInfoDto) -> String {
+    let stable_id = node_info.stable_id();
+    if stable_id.trim().is_empty() {
+        compute_stable_id_for_node(node_info)
+    } else {
+        stable_id.to_string()
+    }
+}
+
+/// Whether a node must be recreated to match its new description
+///
+/// ポート数・ネットワーク設定・バスのプラグイン構成が変わったら作り直す。
+fn needs_rebuild(current: &NodeInfoDto, target: &NodeInfoDto) -> bool {
+    match (current, target) {
+        (
+            NodeInfoDto::Bus {
+                port_count: a,
+                plugins: a_plugins,
+                ..
+            },
+            NodeInfoDto::Bus {
+                port_count: b,
+                plugins: b_plugins,
+                ..
+            },
+        ) => {
+            a != b
+                || a_plugins.len() != b_plugins.len()
+                || a_plugins
+                    .iter()
+                    .zip(b_plugins)
+                    .any(|(x, y)| x.plugin_id != y.plugin_id)
+        }
+        (
+            NodeInfoDto::PluginSource { plugin: a, .. },
+            NodeInfoDto::PluginSource { plugin: b, .. },
+        ) => a.plugin_id != b.plugin_id,
+        (
+            NodeInfoDto::Matrix {
+                input_count: a_in,
+                output_count: a_out,
+                ..
+            },
+            NodeInfoDto::Matrix {
+                input_count: b_in,
+                output_count: b_out,
+                ..
+            },
+        ) => (a_in, a_out) != (b_in, b_out),
+        (
+            NodeInfoDto::Network {
+                role: a_role,
+                port_count: a_ports,
+                address: a_address,
+                port: a_port,
+                packet_time_us: a_packet,
+                jitter_ms: a_jitter,
+                ..
+            },
+            NodeInfoDto::Network {
+                role: b_role,
+                port_count: b_ports,
+                address: b_address,
+                port: b_port,
+                packet_time_us: b_packet,
+                jitter_ms: b_jitter,
+                ..
+            },
+        ) => {
+            (a_role, a_ports, a_address, a_port, a_packet, a_jitter)
+                != (b_role, b_ports, b_address, b_port, b_packet, b_jitter)
+        }
+        (NodeInfoDto::Source { port_count: a, .. }, NodeInfoDto::Source { port_count: b, .. })
+        | (NodeInfoDto::Sink { port_count: a, .. }, NodeInfoDto::Sink { port_count: b, .. })
+        | (NodeInfoDto::Record { port_count: a, .. }, NodeInfoDto::Record { port_count: b, .. })
+        | (
+            NodeInfoDto::Generator { port_count: a, .. },
+            NodeInfoDto::Generator { port_count: b, .. },
+        )
+        | (
+            NodeInfoDto::Loopback { port_count: a, .. },
+            NodeInfoDto::Loopback { port_count: b, .. },
+        ) => a != b,
+        _ => true,
+    }
+}
+
+/// Set the label of any node type (returns false if unchanged)
+fn relabel_node(node: &mut dyn AudioNode, label: &str) -> bool {
+    if node.label() == label {
+        return false;
+    }
+    let any = node.as_any_mut();
+    if let Some(n) = any.downcast_mut::<SourceNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<BusNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<SinkNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<RecordNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<GeneratorNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<PluginSourceNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<MatrixNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<LoopbackSinkNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<LoopbackSourceNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<NetSendSinkNode>() {
+        n.set_label(label);
+    } else if let Some(n) = any.downcast_mut::<NetReceiveSourceNode>() {
+        n.set_label(label);
+    } else {
+        return false;
+    }
+    true
+}
+
+/// Update a kept node's label and settings from its description (apply_graph)
+///
+/// Returns whether anything changed.
+fn update_node_from_dto(node: &mut dyn AudioNode, node_info: &NodeInfoDto) -> bool {
+    let label = match node_info {
+        NodeInfoDto::Source { label, .. }
+        | NodeInfoDto::Bus { label, .. }
+        | NodeInfoDto::Sink { label, .. }
+        | NodeInfoDto::Record { label, .. }
+        | NodeInfoDto::Generator { label, .. }
+        | NodeInfoDto::PluginSource { label, .. }
+        | NodeInfoDto::Matrix { label, .. }
+        | NodeInfoDto::Loopback { label, .. }
+        | NodeInfoDto::Network { label, .. } => label,
+    };
+    let mut changed = relabel_node(node, label);
+
+    let any = node.as_any_mut();
+    match node_info {
+        NodeInfoDto::Source {
+            trims,
+            phase_inverted,
+            ..
+        } => {
+            let Some(source) = any.downcast_mut::<SourceNode>() else {
+                return changed;
+            };
+            if source_trims(source) != *trims || source_phase(source) != *phase_inverted {
+                for port in 0..source.output_port_count() {
+                    source.set_trim_for_port(port, trims.get(port).copied().unwrap_or(1.0));
+                    source.set_phase_inverted(
+                        port,
+                        phase_inverted.get(port).copied().unwrap_or(false),
+                    );
+                }
+                changed = true;
+            }
+        }
+        NodeInfoDto::Bus { plugins, .. } => {
+            let Some(bus) = any.downcast_mut::<BusNode>() else {
+                return changed;
+            };
+            // プラグインの並びは needs_rebuild で一致を確認済み。状態は今のインスタンスのまま
+            // （AU 側の有効化はグラフの外で呼び出し側が行う）
+            for (index, dto) in plugins.iter().enumerate() {
+                let differs = bus.plugins().get(index).is_some_and(|p| {
+                    p.enabled != dto.enabled || p.mix() != dto.mix || p.ring_out() != dto.ring_out
+                });
+                if differs {
+                    bus.set_plugin_state_at(index, dto.enabled, dto.mix, dto.ring_out);
+                    changed = true;
                 }
             }
+        }
+        NodeInfoDto::Sink {
+            sink,
+            limiter,
+            protection,
+            ..
+        } => {
+            let Some(sink_node) = any.downcast_mut::<SinkNode>() else {
+                return changed;
+            };
+            if sink_node.channel_map() != sink.channel_map.as_deref()
+                && sink_node.set_channel_map(sink.channel_map.clone()).is_ok()
+            {
+                changed = true;
+            }
+            let settings: LimiterSettings = limiter.as_ref().map(Into::into).unwrap_or_default();
+            if sink_node.limiter().settings() != settings {
+                sink_node.limiter().set_settings(settings);
+                changed = true;
+            }
+            let settings: ProtectionSettings =
+                protection.as_ref().map(Into::into).unwrap_or_default();
+            // 同じ設定なら触らない（発動中の保護を解除しない）
+            if sink_node.protection().settings() != settings {
+                sink_node.protection().set_settings(settings);
+                changed = true;
+            }
+        }
+        NodeInfoDto::Record { armed, .. } => {
+            let Some(record) = any.downcast_mut::<RecordNode>() else {
+                return changed;
+            };
+            if record.is_armed() != *armed {
+                record.set_armed(*armed);
+                changed = true;
+            }
+        }
+        NodeInfoDto::Generator { params, .. } => {
+            let Some(generator) = any.downcast_mut::<GeneratorNode>() else {
+                return changed;
+            };
+            let params = generator_params_from_dto(params, generator.params());
+            if generator.params() != params {
+                generator.set_params(params);
+                changed = true;
+            }
+        }
+        NodeInfoDto::PluginSource { plugin, .. } => {
+            let Some(source) = any.downcast_mut::<PluginSourceNode>() else {
+                return changed;
+            };
+            if source.enabled() != plugin.enabled {
+                source.set_enabled(plugin.enabled);
+                changed = true;
+            }
+        }
+        NodeInfoDto::Matrix { coefficients, .. } => {
+            let Some(matrix) = any.downcast_mut::<MatrixNode>() else {
+                return changed;
+            };
+            if matrix.coefficients() != *coefficients
+                && matrix.set_coefficients(coefficients).is_ok()
+            {
+                changed = true;
+            }
+        }
+        // ラベル以外に変えられる設定がない
+        _ => {}
+    }
+    changed
+}
+
+/// Apply an edge's gain, mute, trims, pan, tap point, monitor mode and pin from its DTO
+///
+/// Returns whether anything changed. ゲインはランプするので既存エッジでも途切れない。
+/// 適用できなかった設定は `skipped` に積む（グラフの変更中に呼ばれるのでログは呼び出し側で
+/// [`log_skipped_edge_settings`] する）。
+fn apply_edge_settings(
+    graph: &mut AudioGraph,
+    edge_id: EdgeId,
+    edge_info: &EdgeInfoDto,
+    skipped: &mut Vec<(&'static str, String)>,
+) -> bool {
+    let Some(edge) = graph.get_edge(edge_id) else {
+        return false;
+    };
+    let mut changed = false;
+    if edge.gain() != edge_info.gain {
+        edge.set_gain(edge_info.gain);
+        changed = true;
+    }
+    if edge.muted() != edge_info.muted {
+        edge.set_muted(edge_info.muted);
+        changed = true;
+    }
+    for ch in 0..edge.channels as usize {
+        let trim = edge_info.trims.get(ch).copied().unwrap_or(1.0);
+        if edge.channel_trim(ch) != trim {
+            graph.set_edge_channel_trim(edge_id, ch, trim);
+            changed = true;
+        }
+    }
+    let law = PanLaw::parse(&edge_info.pan_law).unwrap_or_default();
+    if edge.pan() != edge_info.pan || edge.pan_law() != law {
+        edge.set_pan_law(law);
+        edge.set_pan(edge_info.pan);
+        changed = true;
+    }
+    let sum_law = SumLaw::parse(&edge_info.sum_law).unwrap_or_default();
+    if edge.sum_law() != sum_law {
+        edge.set_sum_law(sum_law);
+        changed = true;
+    }
+    if edge.pinned() != edge_info.pinned {
+        edge.set_pinned(edge_info.pinned);
+        changed = true;
+    }
+    let monitor_mode = MonitorMode::parse(&edge_info.monitor_mode).unwrap_or_default();
+    if edge.monitor_mode() != monitor_mode {
+        match graph.set_edge_monitor_mode(edge_id, monitor_mode) {
+            Ok(_) => changed = true,
+            Err(e) => skipped.push(("monitor mode", e)),
+        }
+    }
+    let tap_point = TapPoint::parse(&edge_info.tap_point).unwrap_or_default();
+    if graph.get_edge(edge_id).map(|e| e.tap_point()) != Some(tap_point) {
+        match graph.set_edge_tap_point(edge_id, tap_point) {
+            Ok(()) => changed = true,
+            Err(e) => skipped.push(("tap point", e)),
+        }
+    }
+    changed
+}
+
+/// Log the edge settings `apply_edge_settings` could not apply
+fn log_skipped_edge_settings(context: &str, skipped: Vec<(&'static str, String)>) {
+    for (setting, e) in skipped {
+        state_log_summary(format!("{}: {} skipped: {}", context, setting, e));
+    }
+}
+
+/// Recreates audio nodes from their DTOs (load_graph_state / apply_graph)
+///
+/// ループバックの片割れやキャプチャが必要な入力デバイスなど、複数ノードにまたがる状態を持つ。
+struct NodeBuilder {
+    /// Plugin info by ID (for recreating AU instances)
+    plugin_lookup: HashMap<String, crate::audio_unit::AudioUnitInfo>,
+    /// Loopback halves waiting for their partner (both share one ring buffer)
+    loopback_halves: HashMap<String, (Option<LoopbackSinkNode>, Option<LoopbackSourceNode>)>,
+    /// Physical input devices referenced by the created sources
+    input_devices: HashSet<u32>,
+}
+
+impl NodeBuilder {
+    fn new() -> Self {
+        Self {
+            plugin_lookup: crate::plugin_registry::all()
+                .into_iter()
+                .map(|p| (p.id.clone(), p))
+                .collect(),
+            loopback_halves: HashMap::new(),
+            input_devices: HashSet::new(),
+        }
+    }
+
+    /// Recreate a saved AudioUnit in the manager -> (instance id, name, manufacturer)
+    ///
+    /// 保存時の名前・メーカーを優先し、空なら今のプラグイン情報で補う。
+    /// None = skipped, already logged.
+    async fn restore_au_instance(
+        &self,
+        plugin: &PluginInstanceDto,
+    ) -> Option<(String, String, String)> {
+        let Some(info) = self.plugin_lookup.get(&plugin.plugin_id) else {
+            log_error!("[state] Missing plugin {} (skipping)", plugin.plugin_id);
+            return None;
         };
 
-        let old_handle_u32 = match node_info {
-            NodeInfoDto::Source { handle, .. }
-            | NodeInfoDto::Bus { handle, .. }
-            | NodeInfoDto::Sink { handle, .. } => *handle,
+        // Use async instantiation for better UI responsiveness
+        let instance_id = match create_au_instance(info).await {
+            Ok(id) => id,
+            Err(e) => {
+                log_error!(
+                    "[state] Failed to create instance for {}: {}",
+                    plugin.plugin_id,
+                    e
+                );
+                return None;
+            }
         };
 
-        if let Some(existing) = stable_to_handle.get(&stable_id) {
-            handle_mapping.insert(old_handle_u32, *existing);
-            deduped_nodes += 1;
-            continue;
-        }
+        let name = if plugin.name.trim().is_empty() {
+            info.name.clone()
+        } else {
+            plugin.name.clone()
+        };
+        let manufacturer = if plugin.manufacturer.trim().is_empty()
+            || plugin.manufacturer.trim().eq_ignore_ascii_case("unknown")
+        {
+            info.manufacturer.clone()
+        } else {
+            plugin.manufacturer.clone()
+        };
+        Some((instance_id, name, manufacturer))
+    }
 
-        let (old_handle, new_handle) = match node_info {
+    /// Create the node described by `node_info` (None = skipped, already logged)
+    async fn build(&mut self, node_info: &NodeInfoDto) -> Option<Box<dyn AudioNode>> {
+        match node_info {
             NodeInfoDto::Source {
-                handle,
+                handle: _,
                 stable_id: _,
                 source_id,
                 port_count,
                 label,
+                color: _,
+                metadata: _,
                 sub_label: _,
                 available: _,
+                trims,
+                phase_inverted,
+                client_gains: _,
             } => {
-                let node: Box<dyn AudioNode> = match source_id {
+                let source = match source_id {
                     SourceIdDto::PrismChannel { channel } => {
-                        Box::new(SourceNode::new_prism(*channel, label.clone()))
+                        SourceNode::new_prism(*channel, label.clone())
                     }
                     SourceIdDto::InputDevice { device_id, channel } => {
-                        restore_input_devices.insert(*device_id);
+                        self.input_devices.insert(*device_id);
                         let port_count = (*port_count).max(1) as usize;
-                        Box::new(SourceNode::new_device_with_channels(
+                        SourceNode::new_device_with_channels(
                             *device_id,
                             *channel,
                             label.clone(),
                             port_count,
-                        ))
+                        )
                     }
                 };
-                (*handle, processor.add_node(node))
+                for (port, &trim) in trims.iter().enumerate() {
+                    source.set_trim_for_port(port, trim);
+                }
+                for (port, &inverted) in phase_inverted.iter().enumerate() {
+                    source.set_phase_inverted(port, inverted);
+                }
+                Some(Box::new(source))
             }
             NodeInfoDto::Bus {
-                handle,
+                handle: _,
                 stable_id: _,
                 bus_id,
                 label,
+                color: _,
+                metadata: _,
                 port_count,
                 plugins,
+                frozen: _,
             } => {
-                use base64::Engine;
-
                 let mut bus = BusNode::new(bus_id.clone(), label.clone(), *port_count as usize);
-                let au_manager = crate::audio_unit::get_au_manager();
 
                 // Recreate plugin instances in the AU manager and rebuild the chain (async).
                 for plugin in plugins {
-                    let Some(info) = plugin_lookup.get(&plugin.plugin_id) else {
-                        eprintln!("[state] Missing plugin {} (skipping)", plugin.plugin_id);
+                    if is_native_plugin_id(&plugin.plugin_id) {
+                        restore_native_plugin(&mut bus, plugin);
+                        continue;
+                    }
+                    let Some((instance_id, name, manufacturer)) =
+                        self.restore_au_instance(plugin).await
+                    else {
                         continue;
                     };
-
-                    // Use async instantiation for better UI responsiveness
-                    let (tx, rx) = tokio::sync::oneshot::channel();
-                    let info_clone = info.clone();
-
-                    au_manager.create_instance_async(&info_clone, move |result| {
-                        let _ = tx.send(result);
-                    });
-
-                    let instance_id = match rx.await {
-                        Ok(Ok(id)) => id,
-                        Ok(Err(e)) => {
-                            eprintln!(
-                                "[state] Failed to create instance for {}: {}",
-                                plugin.plugin_id, e
-                            );
-                            continue;
-                        }
-                        Err(_) => {
-                            eprintln!(
-                                "[state] Failed to receive instance creation result for {}",
-                                plugin.plugin_id
-                            );
-                            continue;
-                        }
-                    };
-
-                    // Prefer saved metadata if present; otherwise use current plugin info.
-                    let name = if plugin.name.trim().is_empty() {
-                        info.name.clone()
-                    } else {
-                        plugin.name.clone()
-                    };
-                    let manufacturer = if plugin.manufacturer.trim().is_empty()
-                        || plugin.manufacturer.trim().eq_ignore_ascii_case("unknown")
-                    {
-                        info.manufacturer.clone()
-                    } else {
-                        plugin.manufacturer.clone()
-                    };
-
                     bus.add_plugin(
                         instance_id.clone(),
                         plugin.plugin_id.clone(),
@@ -1466,82 +7016,227 @@ pub async fn load_graph_state(state: GraphStateDto) -> Result<(), String> {
                     );
 
                     // Enabled state (both bus and AU manager).
+                    let _ = bus.set_plugin_ring_out(&instance_id, plugin.ring_out);
                     let _ = bus.set_plugin_enabled(&instance_id, plugin.enabled);
-                    let _ = au_manager.set_enabled(&instance_id, plugin.enabled);
+                    sync_bus_au_enabled(&instance_id, plugin.enabled);
+                    let _ = bus.set_plugin_mix(&instance_id, plugin.mix);
 
                     // Full state (plugin parameters).
-                    if let Some(state_b64) = &plugin.state {
-                        if let Ok(bytes) =
-                            base64::engine::general_purpose::STANDARD.decode(state_b64)
-                        {
-                            let _ = au_manager.set_instance_full_state(&instance_id, &bytes);
-                        } else {
-                            eprintln!(
-                                "[state] Failed to decode plugin state for {}",
-                                plugin.plugin_id
-                            );
-                        }
-                    }
+                    restore_au_state(&instance_id, plugin);
                 }
 
-                (*handle, processor.add_node(Box::new(bus)))
+                Some(Box::new(bus))
             }
             NodeInfoDto::Sink {
-                handle,
+                handle: _,
                 stable_id: _,
                 sink,
                 label,
+                limiter,
+                protection,
+                ..
+            } => {
+                let mut sink_id = crate::audio::sink::SinkId::from(sink.clone());
+                // 保存時のデバイスではなく、今の既定出力に付ける
+                if sink_id.follow_default {
+                    if let Some(device_id) = crate::device::default_output_device() {
+                        sink_id.device_id = device_id;
+                        sink_id.device_uid = crate::device::get_device_uid(device_id);
+                    }
+                }
+                let (device_id, channel_offset) = (sink_id.device_id, sink_id.channel_offset);
+                let mut node = SinkNode::new(sink_id, label.clone());
+                node.set_device_latency(crate::device::get_sink_output_latency(
+                    device_id,
+                    channel_offset,
+                ));
+                if let Some(limiter) = limiter {
+                    node.limiter().set_settings(limiter.into());
+                }
+                if let Some(protection) = protection {
+                    node.protection().set_settings(protection.into());
+                }
+                Some(Box::new(node))
+            }
+            NodeInfoDto::Record {
+                handle: _,
+                record_id,
+                label,
+                port_count,
+                armed,
                 ..
             } => {
-                let sink_id = crate::audio::sink::SinkId::from(sink.clone());
-                let node = SinkNode::new(sink_id, label.clone());
-                (*handle, processor.add_node(Box::new(node)))
+                // 録音状態は復元しない（常に停止状態で作成）。アームは残す
+                let mut node =
+                    RecordNode::new(record_id.clone(), label.clone(), *port_count as usize);
+                node.set_armed(*armed);
+                Some(Box::new(node))
             }
-        };
-        stable_to_handle.insert(stable_id, new_handle);
-        handle_mapping.insert(old_handle, new_handle);
-        recreated_nodes += 1;
-    }
-
-    state_log_summary(format!(
-        "load_graph_state: recreated_nodes={} dedup_skipped_nodes={} mapped_handles={} ",
-        recreated_nodes,
-        deduped_nodes,
-        handle_mapping.len()
-    ));
-
-    // Recreate edges with mapped handles
-    let mut recreated_edges: usize = 0;
-    for edge_info in &state.edges {
-        let source_handle = handle_mapping
-            .get(&edge_info.source)
-            .ok_or_else(|| format!("Source node {} not found in mapping", edge_info.source))?;
-        let target_handle = handle_mapping
-            .get(&edge_info.target)
-            .ok_or_else(|| format!("Target node {} not found in mapping", edge_info.target))?;
-
-        processor.add_edge(
-            *source_handle,
-            PortId::from(edge_info.source_port),
-            *target_handle,
-            PortId::from(edge_info.target_port),
-            edge_info.gain,
-            edge_info.muted,
-        );
-        recreated_edges += 1;
+            NodeInfoDto::Loopback {
+                handle: _,
+                loopback_id,
+                role,
+                label,
+                port_count,
+                ..
+            } => {
+                // ペアの片方を作ったら、もう片方は相手の復元時まで取っておく
+                let (sink, source) =
+                    self.loopback_halves.remove(loopback_id).unwrap_or_else(|| {
+                        let (sink, source) = crate::audio::loopback::loopback_pair(
+                            loopback_id.clone(),
+                            label.clone(),
+                            *port_count as usize,
+                        );
+                        (Some(sink), Some(source))
+                    });
+                if role == "sink" {
+                    let Some(mut sink) = sink else {
+                        return None;
+                    };
+                    sink.set_label(label.clone());
+                    self.loopback_halves
+                        .insert(loopback_id.clone(), (None, source));
+                    Some(Box::new(sink))
+                } else {
+                    let Some(mut source) = source else {
+                        return None;
+                    };
+                    source.set_label(label.clone());
+                    self.loopback_halves
+                        .insert(loopback_id.clone(), (sink, None));
+                    Some(Box::new(source))
+                }
+            }
+            NodeInfoDto::Network {
+                handle: _,
+                net_id,
+                role,
+                label,
+                port_count,
+                address,
+                port,
+                packet_time_us,
+                jitter_ms,
+                ..
+            } => {
+                use crate::audio::net::{DEFAULT_JITTER_MS, DEFAULT_PACKET_TIME_US};
+
+                let sample_rate = crate::audio::engine_sample_rate();
+                let node: Result<Box<dyn AudioNode>, String> = if role == "send" {
+                    NetSendSinkNode::new(
+                        net_id.clone(),
+                        label.clone(),
+                        *port_count as usize,
+                        address,
+                        *port,
+                        if *packet_time_us == 0 {
+                            DEFAULT_PACKET_TIME_US
+                        } else {
+                            *packet_time_us
+                        },
+                        sample_rate,
+                    )
+                    .map(|n| Box::new(n) as Box<dyn AudioNode>)
+                } else {
+                    NetReceiveSourceNode::new(
+                        net_id.clone(),
+                        label.clone(),
+                        *port_count as usize,
+                        address,
+                        *port,
+                        if *jitter_ms == 0 {
+                            DEFAULT_JITTER_MS
+                        } else {
+                            *jitter_ms
+                        },
+                        sample_rate,
+                    )
+                    .map(|n| Box::new(n) as Box<dyn AudioNode>)
+                };
+                // ポート使用中などで開けなければ、そのノード（とエッジ）だけ落として続ける
+                let node = match node {
+                    Ok(node) => node,
+                    Err(e) => {
+                        log_warn!("[state] Skipping network node {}: {}", net_id, e);
+                        return None;
+                    }
+                };
+                Some(node)
+            }
+            NodeInfoDto::Matrix {
+                handle: _,
+                matrix_id,
+                label,
+                input_count,
+                output_count,
+                coefficients,
+                ..
+            } => {
+                let node = MatrixNode::new(
+                    matrix_id.clone(),
+                    label.clone(),
+                    *input_count as usize,
+                    *output_count as usize,
+                    MatrixPreset::Identity,
+                );
+                if let Err(e) = node.set_coefficients(coefficients) {
+                    state_log_summary(format!(
+                        "load_graph_state: matrix coefficients skipped: {}",
+                        e
+                    ));
+                }
+                Some(Box::new(node))
+            }
+            NodeInfoDto::PluginSource {
+                source_id,
+                label,
+                plugin,
+                ..
+            } => {
+                let (instance_id, name, manufacturer) = self.restore_au_instance(plugin).await?;
+                let mut node = PluginSourceNode::new(
+                    source_id.clone(),
+                    label.clone(),
+                    instance_id.clone(),
+                    plugin.plugin_id.clone(),
+                    name,
+                    manufacturer,
+                );
+                node.set_enabled(plugin.enabled);
+                restore_au_state(&instance_id, plugin);
+                Some(Box::new(node))
+            }
+            NodeInfoDto::Generator {
+                handle: _,
+                generator_id,
+                label,
+                port_count,
+                params,
+                ..
+            } => {
+                let params = generator_params_from_dto(params, GeneratorParams::default());
+                let node = GeneratorNode::new(
+                    generator_id.clone(),
+                    label.clone(),
+                    *port_count as usize,
+                    params,
+                );
+                Some(Box::new(node))
+            }
+        }
     }
 
-    state_log_summary(format!(
-        "load_graph_state: recreated_edges={}",
-        recreated_edges
-    ));
-
-    // Ensure capture is running for any non-Prism input devices referenced by the restored graph.
-    // We intentionally do NOT fail restore if capture cannot start (device missing, permissions, etc.).
-    if !restore_input_devices.is_empty() {
+    /// Ensure capture is running for the non-Prism input devices of the created sources
+    ///
+    /// We intentionally do NOT fail if capture cannot start (device missing, permissions, etc.).
+    fn start_input_captures(&self, context: &str) {
+        if self.input_devices.is_empty() {
+            return;
+        }
         let mut started: usize = 0;
         let mut failed: usize = 0;
-        for device_id in restore_input_devices.iter().copied() {
+        for device_id in self.input_devices.iter().copied() {
             match crate::capture::start_input_capture(device_id) {
                 Ok(true) => started += 1,
                 Ok(false) => {
@@ -1549,41 +7244,31 @@ pub async fn load_graph_state(state: GraphStateDto) -> Result<(), String> {
                 }
                 Err(e) => {
                     failed += 1;
-                    eprintln!(
-                        "[state] load_graph_state: start_input_capture failed for device_id={}: {}",
-                        device_id, e
+                    log_error!(
+                        "[state] {}: start_input_capture failed for device_id={}: {}",
+                        context,
+                        device_id,
+                        e
                     );
                 }
             }
         }
         state_log_summary(format!(
-            "load_graph_state: ensured input captures for devices={} started={} failed={}",
-            restore_input_devices.len(),
+            "{}: ensured input captures for devices={} started={} failed={}",
+            context,
+            self.input_devices.len(),
             started,
             failed
         ));
     }
-
-    Ok(())
 }
 
 #[tauri::command]
-pub async fn persist_state(ui_state: Option<UIStateDto>) -> Result<(), String> {
-    use std::fs;
-
+pub async fn persist_state(ui_state: Option<UIStateDto>) -> Result<(), SpectrumError> {
     let call_id = PERSIST_CALL_SEQ.fetch_add(1, Ordering::Relaxed) + 1;
     let uptime = state_uptime_ms();
 
-    // Get app data directory
-    let app_data = dirs::data_dir()
-        .ok_or("Could not find app data directory")?
-        .join("spectrum");
-
-    // Create directory if it doesn't exist
-    fs::create_dir_all(&app_data)
-        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
-
-    let state_file = app_data.join("graph_state.json");
+    let state_file = persistence::state_file().map_err(SpectrumError::Storage)?;
 
     // Save graph state.
     // Guard against clobbering a non-empty on-disk graph with an empty in-memory graph
@@ -1591,13 +7276,7 @@ pub async fn persist_state(ui_state: Option<UIStateDto>) -> Result<(), String> {
     let mut state = save_graph_state(ui_state).await?;
 
     // Best-effort: read existing on-disk state so we can avoid destructive early writes.
-    let existing_state: Option<GraphStateDto> = if state_file.exists() {
-        fs::read_to_string(&state_file)
-            .ok()
-            .and_then(|s| serde_json::from_str::<GraphStateDto>(&s).ok())
-    } else {
-        None
-    };
+    let existing_state: Option<GraphStateDto> = persistence::read_state().ok().flatten();
 
     // Guard #1: never clobber non-empty with empty.
     if state.nodes.is_empty() && state.edges.is_empty() {
@@ -1644,18 +7323,16 @@ pub async fn persist_state(ui_state: Option<UIStateDto>) -> Result<(), String> {
         }
     ));
 
-    let json = serde_json::to_string_pretty(&state)
-        .map_err(|e| format!("Failed to serialize state: {}", e))?;
-
-    fs::write(&state_file, json).map_err(|e| format!("Failed to write state file: {}", e))?;
-
+    // Previous file goes to backups/, then atomic replace
+    persistence::write_state(&state).map_err(SpectrumError::Storage)?;
+    super::autosave::clear();
     Ok(())
 }
 
 /// Persist state in the background (returns immediately).
 /// Useful if the frontend ever re-enables periodic autosave without blocking the UI.
 #[tauri::command]
-pub async fn persist_state_background(ui_state: Option<UIStateDto>) -> Result<(), String> {
+pub async fn persist_state_background(ui_state: Option<UIStateDto>) -> Result<(), SpectrumError> {
     tauri::async_runtime::spawn(async move {
         if let Err(e) = persist_state(ui_state).await {
             state_log_summary(format!(
@@ -1673,7 +7350,7 @@ pub async fn persist_state_background(ui_state: Option<UIStateDto>) -> Result<()
 pub async fn set_ui_state_cache(
     state: State<'_, UiStateCache>,
     mut ui_state: UIStateDto,
-) -> Result<(), String> {
+) -> Result<(), SpectrumError> {
     fn is_default_canvas_transform(t: &CanvasTransformDto) -> bool {
         t.x.abs() <= 0.001 && t.y.abs() <= 0.001 && (t.scale - 1.0).abs() <= 0.001
     }
@@ -1737,144 +7414,496 @@ pub async fn set_ui_state_cache(
 }
 
 #[tauri::command]
-pub async fn restore_state() -> Result<Option<UIStateDto>, String> {
-    use std::fs;
-
+pub async fn restore_state() -> Result<Option<UIStateDto>, SpectrumError> {
     let call_id = RESTORE_CALL_SEQ.fetch_add(1, Ordering::Relaxed) + 1;
     let uptime = state_uptime_ms();
 
-    // Get app data directory
-    let app_data = dirs::data_dir()
-        .ok_or("Could not find app data directory")?
-        .join("spectrum");
+    // Read + migrate graph_state.json; fall back to the newest readable backup if it is damaged
+    let state = match persistence::read_state() {
+        Ok(Some(state)) => state,
+        Ok(None) => {
+            state_log_summary(format!(
+                "restore_state#{} @{}ms: no graph_state.json found",
+                call_id, uptime
+            ));
+            // 初回起動: 無音の空グラフではなく既定の経路を作っておく
+            if let Err(e) = bootstrap_default_graph().await {
+                log_error!("[state] restore_state: default graph failed: {}", e);
+            }
+            return Ok(None);
+        }
+        Err(e) => {
+            log_error!("[state] restore_state: {}", e);
+            let Some((name, state)) = persistence::latest_readable_backup() else {
+                return Err(SpectrumError::Storage(e));
+            };
+            log_error!("[state] restore_state: recovering from backup {}", name);
+            state
+        }
+    };
 
-    let state_file_new = app_data.join("graph_state.json");
+    state_log_summary(format!(
+        "restore_state#{} @{}ms: selected nodes={} edges={}",
+        call_id,
+        uptime,
+        state.nodes.len(),
+        state.edges.len()
+    ));
 
-    if !state_file_new.exists() {
-        state_log_summary(format!(
-            "restore_state#{} @{}ms: no graph_state.json found",
-            call_id, uptime
-        ));
-        return Ok(None);
+    // Load the state
+    let ui_state = state.ui_state.clone();
+    state_log_summary(format!(
+        "restore_state#{} @{}ms: loading graph into runtime",
+        call_id, uptime
+    ));
+    load_graph_state(state).await?;
+    state_log_summary(format!(
+        "restore_state#{} @{}ms: load_graph_state completed",
+        call_id, uptime
+    ));
+
+    Ok(ui_state)
+}
+
+/// Build the first-launch graph: Prism MAIN -> "Main" bus -> default output (unity edges)
+///
+/// graph_state.json が無いときに `restore_state` から呼ばれる。グラフに既にノードがあれば
+/// 何もしない（false）。既定の出力デバイスが無ければソースとバスだけを作る。
+async fn bootstrap_default_graph() -> Result<bool, SpectrumError> {
+    let processor = get_graph_processor();
+    if processor.with_graph(|graph| graph.node_handles().next().is_some()) {
+        return Ok(false);
+    }
+
+    let source = add_source_node(SourceIdDto::PrismChannel { channel: 0 }, None).await?;
+    let bus = add_bus_node(Some("Main".to_string()), Some(2)).await?;
+    let mut route = vec![(source, bus)];
+
+    match crate::device::default_output_device() {
+        Some(device_id) => {
+            let sink = add_sink_node(
+                OutputSinkDto {
+                    device_id,
+                    channel_offset: 0,
+                    channel_count: 2,
+                    device_uid: None,
+                    channel_map: None,
+                    follow_default: true,
+                },
+                Some("Default Output".to_string()),
+            )
+            .await?;
+            route.push((bus, sink));
+        }
+        None => log_warn!("[state] Default graph: no default output device, sink skipped"),
     }
 
-    let mut state: GraphStateDto;
+    for (source, target) in route {
+        add_edge(source, 0, target, 0, Some(1.0), Some(false), Some(2)).await?;
+    }
 
-    let json = fs::read_to_string(&state_file_new)
-        .map_err(|e| format!("Failed to read state file: {}", e))?;
+    log_info!(
+        "[state] Built the default graph (source={}, bus={})",
+        source,
+        bus
+    );
+    Ok(true)
+}
 
-    state = serde_json::from_str::<GraphStateDto>(&json)
-        .map_err(|e| format!("Failed to parse state file: {}", e))?;
+/// Backups of graph_state.json, newest first
+#[tauri::command]
+pub async fn list_state_backups() -> Result<Vec<StateBackupDto>, SpectrumError> {
+    persistence::list_backups().map_err(SpectrumError::Storage)
+}
 
-    state_log_summary(format!(
-        "restore_state#{} @{}ms: parsed graph_state.json (version={} nodes={} edges={})",
-        call_id,
-        uptime,
-        state.version,
+/// Load a backup (name from `list_state_backups`) and make it the current state
+///
+/// 置き換え前の graph_state.json もバックアップされるので、復元は取り消せる。
+/// Returns the UI state stored in the backup.
+#[tauri::command]
+pub async fn recover_state_from_backup(name: String) -> Result<Option<UIStateDto>, SpectrumError> {
+    let state = persistence::read_backup(&name)
+        .map_err(SpectrumError::Storage)?
+        .ok_or_else(|| SpectrumError::InvalidArgument(format!("Backup not found: {}", name)))?;
+
+    log_info!(
+        "[state] Recovering graph state from backup {} (nodes={} edges={})",
+        name,
         state.nodes.len(),
         state.edges.len()
-    ));
+    );
+    persistence::write_state(&state).map_err(SpectrumError::Storage)?;
 
-    state_log_summary(format!(
-        "restore_state#{} @{}ms: selected nodes={} edges={}",
-        call_id,
-        uptime,
+    let ui_state = state.ui_state.clone();
+    load_graph_state(state).await?;
+    Ok(ui_state)
+}
+
+/// Problems in graph_state.json (nothing is modified)
+///
+/// 重複ノード・存在しないノードへのエッジ・範囲外のポート・未インストールのプラグインを一覧にする。
+#[tauri::command]
+pub async fn check_state_integrity() -> Result<StateIntegrityReportDto, SpectrumError> {
+    let state = persistence::read_state()
+        .map_err(SpectrumError::Storage)?
+        .ok_or_else(|| SpectrumError::InvalidState("No saved graph state".to_string()))?;
+    let issues = integrity::check(&state, &installed_plugin_ids());
+    Ok(StateIntegrityReportDto {
+        version: state.version,
+        node_count: state.nodes.len(),
+        edge_count: state.edges.len(),
+        issues,
+    })
+}
+
+/// Apply the safe fixes to graph_state.json and write it back
+///
+/// 置き換え前のファイルはバックアップされる。実行中のグラフは読み込み時に同じ修正が
+/// 済んでいるので作り直さない。未インストールのプラグインは `remaining` に残る。
+#[tauri::command]
+pub async fn repair_state() -> Result<StateRepairReportDto, SpectrumError> {
+    let mut state = persistence::read_state()
+        .map_err(SpectrumError::Storage)?
+        .ok_or_else(|| SpectrumError::InvalidState("No saved graph state".to_string()))?;
+    let fixed = integrity::repair(&mut state);
+    let remaining = integrity::missing_plugins(&state, &installed_plugin_ids());
+    if !fixed.is_empty() {
+        log_info!(
+            "[state] Repaired graph state: {} issue(s) fixed, {} remaining",
+            fixed.len(),
+            remaining.len()
+        );
+        persistence::write_state(&state).map_err(SpectrumError::Storage)?;
+    }
+    Ok(StateRepairReportDto { fixed, remaining })
+}
+
+fn installed_plugin_ids() -> HashSet<String> {
+    crate::plugin_registry::all()
+        .into_iter()
+        .map(|p| p.id)
+        .collect()
+}
+
+/// Autosave from a session that crashed, newer than graph_state.json (None if there is none)
+#[tauri::command]
+pub async fn get_autosave_recovery() -> Result<Option<AutosaveRecoveryDto>, SpectrumError> {
+    super::autosave::pending_recovery().map_err(SpectrumError::Storage)
+}
+
+/// Load the crash autosave and make it the current state
+///
+/// Returns the UI state stored with it.
+#[tauri::command]
+pub async fn recover_autosave() -> Result<Option<UIStateDto>, SpectrumError> {
+    let state = super::autosave::take_recovery()
+        .map_err(SpectrumError::Storage)?
+        .ok_or_else(|| SpectrumError::InvalidState("No autosave to recover".to_string()))?;
+
+    log_info!(
+        "[state] Recovering autosave (nodes={} edges={})",
         state.nodes.len(),
         state.edges.len()
-    ));
+    );
+    persistence::write_state(&state).map_err(SpectrumError::Storage)?;
 
-    // Normalize node stable IDs (older state files may not have them).
-    let mut filled_stable_ids: usize = 0;
-    for node in &mut state.nodes {
-        let needs_fill = match node {
-            NodeInfoDto::Source { stable_id, .. }
-            | NodeInfoDto::Bus { stable_id, .. }
-            | NodeInfoDto::Sink { stable_id, .. } => stable_id.trim().is_empty(),
-        };
+    let ui_state = state.ui_state.clone();
+    load_graph_state(state).await?;
+    Ok(ui_state)
+}
 
-        if needs_fill {
-            let computed = compute_stable_id_for_node(node);
-            match node {
-                NodeInfoDto::Source { stable_id, .. }
-                | NodeInfoDto::Bus { stable_id, .. }
-                | NodeInfoDto::Sink { stable_id, .. } => {
-                    *stable_id = computed;
-                    filled_stable_ids += 1;
-                }
-            }
+#[tauri::command]
+pub async fn discard_autosave() -> Result<(), SpectrumError> {
+    super::autosave::discard_recovery().map_err(SpectrumError::Storage)
+}
+
+/// Recent graph mutations, newest first (default 100)
+#[tauri::command]
+pub async fn get_mutation_history(
+    limit: Option<usize>,
+) -> Result<Vec<MutationEntryDto>, SpectrumError> {
+    Ok(history::recent(limit.unwrap_or(100)))
+}
+
+// =============================================================================
+// Project Commands
+// =============================================================================
+
+/// Save the current graph as a project file
+///
+/// `name` defaults to the existing project name (when overwriting) or the file name.
+#[tauri::command]
+pub async fn save_project(
+    path: String,
+    name: Option<String>,
+    ui_state: Option<UIStateDto>,
+) -> Result<ProjectInfoDto, SpectrumError> {
+    let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+    let state = save_graph_state(ui_state).await?;
+    let info =
+        super::projects::write_project(&path, name, state).map_err(SpectrumError::Storage)?;
+    super::projects::record_recent(&path, &info.metadata.name);
+    Ok(info)
+}
+
+/// Open a project file and replace the current graph
+///
+/// デバイスは UID で現在の ID に付け替える。接続されていないデバイスのノードは
+/// 残したまま `missing_devices` で返し、接続されたら自動で付け替える。
+#[tauri::command]
+pub async fn open_project(path: String) -> Result<OpenProjectDto, SpectrumError> {
+    let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+    let mut project = super::projects::read_project(&path).map_err(SpectrumError::Storage)?;
+
+    let resolution =
+        super::projects::resolve_devices(&project.metadata.devices, &HashMap::new(), false)?;
+    super::projects::remap_devices(&mut project.state, &resolution.mapping);
+
+    let ui_state = project.state.ui_state.clone();
+    load_graph_state(project.state).await?;
+    resolution.watch_missing();
+
+    super::projects::record_recent(&path, &project.metadata.name);
+    log_info!(
+        "[Project] Opened '{}' ({} missing devices)",
+        project.metadata.name,
+        resolution.missing.len()
+    );
+    Ok(OpenProjectDto {
+        project: ProjectInfoDto {
+            path: path.to_string_lossy().to_string(),
+            metadata: project.metadata,
+        },
+        ui_state,
+        missing_devices: resolution.missing,
+    })
+}
+
+/// Export the graph to a file that can be imported on another Mac
+///
+/// Returns the devices the graph uses.
+#[tauri::command]
+pub async fn export_graph(
+    path: String,
+    ui_state: Option<UIStateDto>,
+) -> Result<Vec<DeviceRequirementDto>, SpectrumError> {
+    let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+    let state = save_graph_state(ui_state).await?;
+    super::projects::write_export(&path, state).map_err(SpectrumError::Storage)
+}
+
+/// Import an exported graph, replacing the current one
+///
+/// `device_mapping` maps a device key (see `DeviceRemapDto::key`) to the UID of a
+/// connected device. Unmapped devices are matched by UID; devices that are not
+/// connected stay as placeholders. The returned report lists candidates so the UI
+/// can ask the user and import again with a mapping.
+#[tauri::command]
+pub async fn import_graph(
+    path: String,
+    device_mapping: Option<HashMap<String, String>>,
+) -> Result<ImportGraphDto, SpectrumError> {
+    let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+    let mut export = super::projects::read_export(&path).map_err(SpectrumError::Storage)?;
+
+    let resolution = super::projects::resolve_devices(
+        &export.devices,
+        &device_mapping.unwrap_or_default(),
+        true,
+    )
+    .map_err(SpectrumError::InvalidArgument)?;
+    super::projects::remap_devices(&mut export.state, &resolution.mapping);
+    super::projects::retarget_sink_uids(&mut export.state, &resolution);
+
+    let ui_state = export.state.ui_state.clone();
+    load_graph_state(export.state).await?;
+    resolution.watch_missing();
+
+    let devices = super::projects::remap_report(&export.devices, &resolution);
+    log_info!(
+        "[Project] Imported {} ({} devices, {} missing)",
+        path.display(),
+        devices.len(),
+        resolution.missing.len()
+    );
+    Ok(ImportGraphDto {
+        ui_state,
+        devices,
+        missing: resolution.missing.len(),
+    })
+}
+
+#[tauri::command]
+pub async fn list_recent_projects() -> Result<Vec<RecentProjectDto>, SpectrumError> {
+    Ok(super::projects::recent_projects())
+}
+
+// =============================================================================
+// Settings Commands
+// =============================================================================
+
+#[tauri::command]
+pub async fn get_settings() -> Result<SettingsDto, SpectrumError> {
+    Ok(SettingsDto::from(crate::settings::get()))
+}
+
+/// Update settings.json; omitted values are kept
+///
+/// Meter rate, rate policy, log level, the Prism auto-source policy and output latency
+/// alignment apply immediately; output device, buffer size and sample rate are used on
+/// the next launch.
+/// An empty `preferred_output_device_uid` clears the preference.
+#[tauri::command]
+pub async fn update_settings(
+    preferred_output_device_uid: Option<String>,
+    buffer_size: Option<u32>,
+    sample_rate: Option<u32>,
+    rate_policy: Option<String>,
+    meter_rate_hz: Option<u32>,
+    log_level: Option<String>,
+    prism_auto_source: Option<String>,
+    align_output_latency: Option<bool>,
+) -> Result<SettingsDto, SpectrumError> {
+    use crate::settings::{AutoSourcePolicy, LogLevel};
+
+    let mut settings = crate::settings::get();
+    if let Some(uid) = preferred_output_device_uid {
+        settings.preferred_output_device_uid = Some(uid).filter(|u| !u.trim().is_empty());
+    }
+    if let Some(size) = buffer_size {
+        settings.buffer_size = size;
+    }
+    if let Some(rate) = sample_rate {
+        if !crate::audio::sample_rate::is_supported_sample_rate(rate as f64) {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Unsupported sample rate: {}",
+                rate
+            )));
+        }
+        settings.sample_rate = Some(rate);
+    }
+    if let Some(policy) = rate_policy {
+        if crate::settings::parse_rate_policy(&policy).is_none() {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Unknown rate policy: {}",
+                policy
+            )));
+        }
+        settings.rate_policy = policy;
+    }
+    if let Some(hz) = meter_rate_hz {
+        settings.meter_rate_hz = hz;
+    }
+    if let Some(level) = log_level {
+        settings.log_level = Some(LogLevel::parse(&level).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!("Unknown log level: {}", level))
+        })?);
+    }
+    if let Some(policy) = prism_auto_source {
+        settings.prism_auto_source = AutoSourcePolicy::parse(&policy).ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!("Unknown auto-source policy: {}", policy))
+        })?;
+    }
+    if let Some(enabled) = align_output_latency {
+        settings.align_output_latency = enabled;
+    }
+
+    let applied = crate::settings::update(settings).map_err(SpectrumError::Storage)?;
+    Ok(SettingsDto::from(applied))
+}
+
+/// Saved global keyboard shortcuts
+#[tauri::command]
+pub async fn get_hotkeys() -> Result<Vec<HotkeyBindingDto>, SpectrumError> {
+    Ok(crate::settings::get().hotkeys)
+}
+
+/// Replace the global keyboard shortcuts, save them and register them
+///
+/// Returns the shortcuts that could not be registered (taken by another app);
+/// they stay saved and are retried on the next launch.
+#[tauri::command]
+pub async fn set_hotkeys(bindings: Vec<HotkeyBindingDto>) -> Result<Vec<String>, SpectrumError> {
+    let mut seen = Vec::with_capacity(bindings.len());
+    for binding in &bindings {
+        let shortcut = crate::hotkeys::parse_shortcut(&binding.shortcut)
+            .map_err(SpectrumError::InvalidArgument)?;
+        if seen.contains(&shortcut) {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Shortcut '{}' is bound more than once",
+                binding.shortcut
+            )));
         }
-    }
-    if filled_stable_ids > 0 {
-        state_log_summary(format!(
-            "restore_state#{} @{}ms: filled missing stable_id count={}",
-            call_id, uptime, filled_stable_ids
-        ));
+        seen.push(shortcut);
     }
 
-    // Normalize UIState: if legacy handle-keyed positions exist, convert them to stable-keyed.
-    if let Some(ui) = state.ui_state.as_mut() {
-        state_log_verbose(format!(
-            "restore_state#{} @{}ms: ui_state positions(stable)={} positions(by_handle)={} ",
-            call_id,
-            uptime,
-            ui.node_positions.len(),
-            ui.node_positions_by_handle.len()
-        ));
-        if ui.node_positions.is_empty() && !ui.node_positions_by_handle.is_empty() {
-            let mut handle_to_stable: HashMap<u32, String> = HashMap::new();
-            for node in &state.nodes {
-                match node {
-                    NodeInfoDto::Source {
-                        handle, stable_id, ..
-                    }
-                    | NodeInfoDto::Bus {
-                        handle, stable_id, ..
-                    }
-                    | NodeInfoDto::Sink {
-                        handle, stable_id, ..
-                    } => {
-                        let sid = if stable_id.trim().is_empty() {
-                            compute_stable_id_for_node(node)
-                        } else {
-                            stable_id.clone()
-                        };
-                        handle_to_stable.insert(*handle, sid);
-                    }
-                }
-            }
+    let mut settings = crate::settings::get();
+    settings.hotkeys = bindings;
+    let applied = crate::settings::update(settings).map_err(SpectrumError::Storage)?;
+    Ok(crate::hotkeys::register(&applied.hotkeys))
+}
 
-            let mut converted: usize = 0;
+fn http_api_status(settings: &crate::settings::Settings) -> HttpApiStatusDto {
+    let address = super::http::address();
+    HttpApiStatusDto {
+        enabled: settings.http_api_enabled,
+        running: address.is_some(),
+        address: address.map(|a| a.to_string()),
+        port: settings.http_api_port,
+        allow_lan: settings.http_api_allow_lan,
+        advertised: super::http::is_advertised(),
+        token: settings.http_api_token.clone(),
+    }
+}
 
-            for (h, pos) in ui.node_positions_by_handle.iter() {
-                if let Some(stable_id) = handle_to_stable.get(h) {
-                    ui.node_positions.insert(stable_id.clone(), pos.clone());
-                    converted += 1;
-                }
-            }
+#[tauri::command]
+pub async fn get_http_api_status() -> Result<HttpApiStatusDto, SpectrumError> {
+    Ok(http_api_status(&crate::settings::get()))
+}
 
-            state_log_summary(format!(
-                "restore_state#{} @{}ms: converted legacy node_positions_by_handle -> node_positions count={}",
-                call_id, uptime, converted
+/// Configure the HTTP control API; omitted values are kept
+///
+/// Enabling generates a token if there is none yet (`regenerate_token` replaces it).
+/// The server is restarted with the new settings. A port that cannot be opened is
+/// an error: nothing is saved and the API stays stopped.
+#[tauri::command]
+pub async fn set_http_api(
+    enabled: Option<bool>,
+    port: Option<u16>,
+    allow_lan: Option<bool>,
+    regenerate_token: Option<bool>,
+) -> Result<HttpApiStatusDto, SpectrumError> {
+    let mut settings = crate::settings::get();
+    if let Some(enabled) = enabled {
+        settings.http_api_enabled = enabled;
+    }
+    if let Some(port) = port {
+        if port == 0 {
+            return Err(SpectrumError::InvalidArgument(
+                "Port must not be 0".to_string(),
             ));
         }
-
-        // Clear legacy map so frontend doesn't accidentally use it.
-        ui.node_positions_by_handle.clear();
+        settings.http_api_port = port;
+    }
+    if let Some(allow_lan) = allow_lan {
+        settings.http_api_allow_lan = allow_lan;
+    }
+    if regenerate_token.unwrap_or(false)
+        || (settings.http_api_enabled && settings.http_api_token.is_none())
+    {
+        settings.http_api_token = Some(super::http::generate_token());
     }
 
-    // Load the state
-    let ui_state = state.ui_state.clone();
-    state_log_summary(format!(
-        "restore_state#{} @{}ms: loading graph into runtime",
-        call_id, uptime
-    ));
-    load_graph_state(state).await?;
-    state_log_summary(format!(
-        "restore_state#{} @{}ms: load_graph_state completed",
-        call_id, uptime
-    ));
+    match settings.http_api_token.as_deref() {
+        Some(token) if settings.http_api_enabled => {
+            super::http::start(settings.http_api_port, settings.http_api_allow_lan, token)
+                .map_err(SpectrumError::InvalidState)?;
+        }
+        _ => super::http::stop(),
+    }
 
-    Ok(ui_state)
+    let applied = crate::settings::update(settings).map_err(SpectrumError::Storage)?;
+    Ok(http_api_status(&applied))
 }
 
 // =============================================================================
@@ -1884,7 +7913,7 @@ pub async fn restore_state() -> Result<Option<UIStateDto>, String> {
 /// Open Prism.app (companion app for channel assignment)
 /// Uses URL scheme prism://popup for popup mode
 #[tauri::command]
-pub async fn open_prism_app() -> Result<bool, String> {
+pub async fn open_prism_app() -> Result<bool, SpectrumError> {
     #[cfg(target_os = "macos")]
     {
         use std::process::Command;
@@ -1897,7 +7926,7 @@ pub async fn open_prism_app() -> Result<bool, String> {
                     .spawn()
                     .is_ok()
                 {
-                    println!("[Spectrum] Prism.app already running, sent popup deep link");
+                    log_info!("[Spectrum] Prism.app already running, sent popup deep link");
                     return Ok(true);
                 }
 
@@ -1905,7 +7934,7 @@ pub async fn open_prism_app() -> Result<bool, String> {
                 let _ = Command::new("osascript")
                     .args(["-e", "tell application \"Prism\" to activate"])
                     .spawn();
-                println!("[Spectrum] Prism.app already running, activated");
+                log_info!("[Spectrum] Prism.app already running, activated");
                 return Ok(true);
             }
         }
@@ -1916,7 +7945,7 @@ pub async fn open_prism_app() -> Result<bool, String> {
             .spawn()
             .is_ok()
         {
-            println!("[Spectrum] Opened Prism.app via URL scheme (popup mode)");
+            log_info!("[Spectrum] Opened Prism.app via URL scheme (popup mode)");
             return Ok(true);
         }
 
@@ -1937,7 +7966,7 @@ pub async fn open_prism_app() -> Result<bool, String> {
                     .spawn()
                     .is_ok()
                 {
-                    println!("[Spectrum] Opened Prism.app from {}", expanded_path);
+                    log_info!("[Spectrum] Opened Prism.app from {}", expanded_path);
                     return Ok(true);
                 }
             }
@@ -1948,20 +7977,22 @@ pub async fn open_prism_app() -> Result<bool, String> {
             .args(["-a", "Prism"])
             .spawn()
             .map(|_| {
-                println!("[Spectrum] Opened Prism.app by name");
+                log_info!("[Spectrum] Opened Prism.app by name");
                 true
             })
-            .map_err(|e| format!("Could not find or open Prism.app: {}", e))
+            .map_err(|e| SpectrumError::Other(format!("Could not find or open Prism.app: {}", e)))
     }
 
     #[cfg(not(target_os = "macos"))]
     {
-        Err("open_prism_app is only supported on macOS".to_string())
+        Err(SpectrumError::Unsupported(
+            "open_prism_app is only supported on macOS".to_string(),
+        ))
     }
 }
 
 #[tauri::command]
-pub async fn start_audio(device_id: u32) -> Result<(), String> {
+pub async fn start_audio(device_id: u32) -> Result<(), SpectrumError> {
     crate::capture::start_capture()?;
 
     // If device_id == 0 treat as "auto": prefer aggregate device, otherwise system default.
@@ -1973,22 +8004,48 @@ pub async fn start_audio(device_id: u32) -> Result<(), String> {
 
     if let Err(e) = start_output_v2(target_device) {
         crate::capture::stop_capture();
-        return Err(e);
+        return Err(e.into());
     }
 
     Ok(())
 }
 
+/// Move every sink of output device `old_device_id` to `new_device_id` without a gap
+///
+/// 新しいデバイスを起動して約 100ms クロスフェードしてから旧デバイスを止める。
+/// Returns the handles of the sinks that moved.
+#[tauri::command]
+pub async fn switch_output_device(
+    old_device_id: u32,
+    new_device_id: u32,
+) -> Result<Vec<u32>, SpectrumError> {
+    if crate::device::get_device_output_channels(new_device_id) == 0 {
+        return Err(SpectrumError::DeviceNotFound(new_device_id));
+    }
+    let moved = tauri::async_runtime::spawn_blocking(move || {
+        crate::audio::output::switch_output_device(old_device_id, new_device_id)
+    })
+    .await
+    .map_err(|e| format!("Output switch failed: {}", e))??;
+
+    for handle in &moved {
+        emit_graph_event(GraphEventDto::NodeChanged {
+            handle: handle.raw(),
+        });
+    }
+    Ok(moved.into_iter().map(|h| h.raw()).collect())
+}
+
 /// Stop only the physical output runtime (keep capture running).
 /// This is used for output switching without resetting capture/ringbuffers.
 #[tauri::command]
-pub async fn stop_output_runtime() -> Result<(), String> {
+pub async fn stop_output_runtime() -> Result<(), SpectrumError> {
     crate::audio::output::stop_output_v2();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_audio() -> Result<(), String> {
+pub async fn stop_audio() -> Result<(), SpectrumError> {
     crate::capture::stop_capture();
 
     // Ensure physical output runtime is stopped as well
@@ -1998,43 +8055,684 @@ pub async fn stop_audio() -> Result<(), String> {
 }
 
 #[tauri::command]
-pub async fn get_output_runtime() -> Result<Option<u32>, String> {
+pub async fn get_output_runtime() -> Result<Option<u32>, SpectrumError> {
     Ok(crate::audio::output::get_active_output_device())
 }
 
+/// Start an additional output device alongside the current ones
+///
+/// The clock master keeps processing the graph; this device plays the sinks
+/// bound to it with drift compensation.
+#[tauri::command]
+pub async fn start_output_device(device_id: u32) -> Result<Vec<OutputRuntimeDto>, SpectrumError> {
+    ensure_device_exists(device_id)?;
+    crate::audio::output::start_output_device(device_id)?;
+    get_output_runtimes().await
+}
+
+/// Stop one output device (another running device takes over as clock master)
+#[tauri::command]
+pub async fn stop_output_device(device_id: u32) -> Result<Vec<OutputRuntimeDto>, SpectrumError> {
+    crate::audio::output::stop_output_device(device_id);
+    get_output_runtimes().await
+}
+
+fn output_runtime_dtos() -> Vec<OutputRuntimeDto> {
+    crate::audio::output::output_runtime_info()
+        .into_iter()
+        .map(|info| OutputRuntimeDto {
+            device_id: info.device_id,
+            device_name: coreaudio::audio_unit::macos_helpers::get_device_name(info.device_id)
+                .unwrap_or_else(|_| format!("Device {}", info.device_id)),
+            is_clock_master: info.is_clock_master,
+            fifo_fill: info.drift.fill as u32,
+            drift_corrections: info.drift.corrections,
+            fifo_underruns: info.drift.underruns,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn get_output_runtimes() -> Result<Vec<OutputRuntimeDto>, SpectrumError> {
+    Ok(output_runtime_dtos())
+}
+
+fn ensure_device_exists(device_id: u32) -> Result<(), SpectrumError> {
+    coreaudio::audio_unit::macos_helpers::get_device_name(device_id)
+        .map(|_| ())
+        .map_err(|_| SpectrumError::DeviceNotFound(device_id))
+}
+
 #[tauri::command]
-pub async fn get_system_status() -> Result<SystemStatusDto, String> {
+pub async fn get_system_status() -> Result<SystemStatusDto, SpectrumError> {
     let audio_running = crate::capture::is_capture_running();
 
     Ok(SystemStatusDto {
         audio_running,
-        sample_rate: 48000,
+        sample_rate: crate::audio::engine_sample_rate() as u32,
         buffer_size: crate::capture::get_io_buffer_size() as u32,
-        cpu_load: 0.0, // TODO: Implement CPU load monitoring
+        cpu_load: crate::audio::dsp_load::overall_load(),
     })
 }
 
+/// Get dropout counters per device
 #[tauri::command]
-pub async fn set_buffer_size(size: u32) -> Result<(), String> {
-    crate::capture::set_io_buffer_size(size as usize);
+pub async fn get_xrun_stats() -> Result<Vec<XrunStatsDto>, SpectrumError> {
+    Ok(super::events::xrun_stats())
+}
+
+#[tauri::command]
+pub async fn reset_xrun_stats() -> Result<(), SpectrumError> {
+    crate::audio::xrun::reset_xrun_stats();
     Ok(())
 }
 
+/// Get capture ring fill levels and xruns per input device and per reader
+///
+/// クラックル報告の切り分け用: キャプチャ側が詰まっているのか、出力側が
+/// 読めていないのかをリーダーごとの充填量と xrun で判断できる。
+/// `reset` clears the per-reader counters and low-water marks after reading.
+#[tauri::command]
+pub async fn get_buffer_diagnostics(
+    reset: Option<bool>,
+) -> Result<BufferDiagnosticsDto, SpectrumError> {
+    let xruns: HashMap<u32, crate::audio::xrun::XrunSnapshot> =
+        crate::audio::xrun::xrun_stats().into_iter().collect();
+    let inputs = crate::capture::buffer_diagnostics(reset.unwrap_or(false))
+        .into_iter()
+        .map(|ring| {
+            let device = xruns.get(&ring.device_id).copied().unwrap_or_default();
+            CaptureRingDto {
+                device_id: ring.device_id,
+                device_name: ring.device_name,
+                legacy: ring.legacy,
+                running: ring.running,
+                capacity_frames: ring.capacity as u32,
+                underruns: device.underruns,
+                overruns: device.overruns,
+                readers: ring
+                    .readers
+                    .into_iter()
+                    .map(|r| RingReaderDto {
+                        output_device_id: (r.reader_id != crate::capture::PROCESSOR_READER_ID)
+                            .then_some(r.reader_id),
+                        fill_frames: r.fill as u32,
+                        min_fill_frames: r.min_fill.map(|f| f as u32),
+                        underruns: r.underruns,
+                        overruns: r.overruns,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    Ok(BufferDiagnosticsDto {
+        inputs,
+        outputs: output_runtime_dtos(),
+    })
+}
+
+/// Get DSP load per output device and per node
+///
+/// `reset_peaks` clears the peak values after reading.
+#[tauri::command]
+pub async fn get_dsp_profile(reset_peaks: Option<bool>) -> Result<DspProfileDto, SpectrumError> {
+    let reset = reset_peaks.unwrap_or(false);
+
+    let devices = crate::audio::dsp_load::device_loads()
+        .into_iter()
+        .map(|(device_id, s)| DeviceLoadDto {
+            device_id,
+            callbacks: s.callbacks,
+            load: s.into(),
+        })
+        .collect();
+
+    let nodes = get_graph_processor().with_graph(|graph| {
+        graph
+            .processing_order()
+            .iter()
+            .filter_map(|&handle| {
+                let node = graph.get_node(handle)?;
+                let meter = graph.node_load(handle)?;
+                let snapshot = meter.snapshot();
+                if reset {
+                    meter.reset_peak();
+                }
+                Some(NodeLoadDto {
+                    handle: handle.raw(),
+                    label: node.label().to_string(),
+                    load: snapshot.into(),
+                })
+            })
+            .collect()
+    });
+
+    if reset {
+        crate::audio::dsp_load::reset_device_peaks();
+    }
+
+    Ok(DspProfileDto { devices, nodes })
+}
+
+/// Get engine statistics since startup (or the last reset)
+///
+/// 稼働時間・デバイスごとのレンダリング済みフレーム数・ドロップアウト数・最大負荷・
+/// プラグインの生成失敗数。`reset` starts a new session after reading.
+#[tauri::command]
+pub async fn get_session_stats(reset: Option<bool>) -> Result<SessionStatsDto, SpectrumError> {
+    let stats = crate::audio::session_stats::snapshot();
+    if reset.unwrap_or(false) {
+        crate::audio::session_stats::reset();
+    }
+
+    let devices: Vec<DeviceSessionStatsDto> = stats
+        .devices
+        .into_iter()
+        .map(|(device_id, s)| DeviceSessionStatsDto {
+            device_id,
+            device_name: coreaudio::audio_unit::macos_helpers::get_device_name(device_id).ok(),
+            frames_rendered: s.frames,
+            callbacks: s.callbacks,
+            peak_load: s.peak_load,
+        })
+        .collect();
+    Ok(SessionStatsDto {
+        started_at_ms: stats.started_at_ms,
+        uptime_secs: stats.uptime.as_secs_f64(),
+        dropouts: stats.dropouts,
+        plugin_failures: stats.plugin_failures,
+        peak_load: devices.iter().map(|d| d.peak_load).fold(0.0, f32::max),
+        devices,
+    })
+}
+
+/// Get the render time of every plugin in every bus chain
+///
+/// 値はバッファ周期に対する割合（`budget_ms` が 100% に相当）。処理順に並ぶ。
+/// `reset_peaks` clears the peak values after reading.
+#[tauri::command]
+pub async fn get_plugin_performance(
+    reset_peaks: Option<bool>,
+) -> Result<Vec<PluginLoadDto>, SpectrumError> {
+    let reset = reset_peaks.unwrap_or(false);
+
+    Ok(get_graph_processor().with_graph(|graph| {
+        let mut loads = Vec::new();
+        for &handle in graph.processing_order() {
+            let Some(node) = graph.get_node(handle) else {
+                continue;
+            };
+            let Some(bus) = node.as_any().downcast_ref::<BusNode>() else {
+                continue;
+            };
+            for (index, plugin) in bus.plugins().iter().enumerate() {
+                let snapshot = plugin.load().snapshot();
+                if reset {
+                    plugin.load().reset_peak();
+                }
+                loads.push(PluginLoadDto {
+                    bus_handle: handle.raw(),
+                    bus_label: node.label().to_string(),
+                    instance_id: plugin.instance_id.clone(),
+                    name: plugin.name.clone(),
+                    index,
+                    budget_ms: snapshot.budget_ms,
+                    load: snapshot.into(),
+                });
+            }
+        }
+        loads
+    }))
+}
+
+/// Change the I/O buffer size of the running devices in place
+///
+/// キャプチャも出力も止めずに適用する（グラフ・リングバッファは作り直さない）。
+#[tauri::command]
+pub async fn set_buffer_size(size: u32) -> Result<BufferSizeDto, SpectrumError> {
+    let devices = crate::capture::apply_io_buffer_size(size as usize)
+        .into_iter()
+        .map(|r| DeviceBufferSizeDto {
+            device_id: r.device_id,
+            device_name: coreaudio::audio_unit::macos_helpers::get_device_name(r.device_id)
+                .unwrap_or_else(|_| format!("Device {}", r.device_id)),
+            actual: r.actual,
+            error: r.error,
+        })
+        .collect();
+    Ok(BufferSizeDto {
+        buffer_size: crate::capture::get_io_buffer_size() as u32,
+        devices,
+    })
+}
+
+/// Override the I/O buffer size of one device (`frames` = None returns it to the global size)
+///
+/// settings.json に保存され、次回以降の起動やデバイスの再接続でも使われる。
+#[tauri::command]
+pub async fn set_device_buffer_size(
+    device_id: u32,
+    frames: Option<u32>,
+) -> Result<DeviceBufferSizeDto, SpectrumError> {
+    ensure_device_exists(device_id)?;
+    let result = crate::capture::set_device_buffer_override(device_id, frames)
+        .map_err(SpectrumError::InvalidArgument)?;
+    Ok(DeviceBufferSizeDto {
+        device_id,
+        device_name: coreaudio::audio_unit::macos_helpers::get_device_name(device_id)
+            .unwrap_or_else(|_| format!("Device {}", device_id)),
+        actual: result.actual,
+        error: result.error,
+    })
+}
+
+// =============================================================================
+// Logging
+// =============================================================================
+
+fn parse_log_level(level: &str) -> Result<Level, SpectrumError> {
+    Level::parse(level).ok_or_else(|| {
+        SpectrumError::InvalidArgument(format!(
+            "Unknown log level '{}' (expected error, warn, info, debug or trace)",
+            level
+        ))
+    })
+}
+
+fn log_levels_dto() -> LogLevelsDto {
+    let (level, modules) = logging::levels();
+    LogLevelsDto {
+        level: level.as_str().to_string(),
+        modules: modules
+            .into_iter()
+            .map(|(target, level)| LogFilterDto {
+                target,
+                level: level.as_str().to_string(),
+            })
+            .collect(),
+        file: logging::log_file_path().map(|p| p.to_string_lossy().into_owned()),
+    }
+}
+
+/// Most recent crash report (panic or fatal engine fault); None if nothing crashed yet
+///
+/// レポートは次回の起動後も残るので、落ちた後に開き直してバグ報告に添付できる。
+#[tauri::command]
+pub async fn get_last_crash_report() -> Result<Option<CrashReportDto>, SpectrumError> {
+    crate::crash::last_report().map_err(SpectrumError::Storage)
+}
+
+/// Get recent log entries (oldest first) for in-app diagnostics
+///
+/// `min_level` はその重要度以上のみ（デフォルト "trace" = 全部）、
+/// `target` はモジュールのプレフィックス（例: "audio::output"）。
+#[tauri::command]
+pub async fn get_recent_logs(
+    limit: Option<usize>,
+    min_level: Option<String>,
+    target: Option<String>,
+) -> Result<Vec<LogEntryDto>, SpectrumError> {
+    let min_level = match min_level.as_deref() {
+        Some(l) => parse_log_level(l)?,
+        None => Level::Trace,
+    };
+    Ok(
+        logging::recent(limit.unwrap_or(200), min_level, target.as_deref())
+            .into_iter()
+            .map(LogEntryDto::from)
+            .collect(),
+    )
+}
+
+#[tauri::command]
+pub async fn get_log_levels() -> Result<LogLevelsDto, SpectrumError> {
+    Ok(log_levels_dto())
+}
+
+/// Set the default log level (`target` = None) or a per-module filter
+#[tauri::command]
+pub async fn set_log_level(
+    level: String,
+    target: Option<String>,
+) -> Result<LogLevelsDto, SpectrumError> {
+    let level = parse_log_level(&level)?;
+    logging::set_level(target.as_deref(), level);
+    Ok(log_levels_dto())
+}
+
+/// Remove a per-module filter (the module falls back to the default level)
+#[tauri::command]
+pub async fn clear_log_level(target: String) -> Result<LogLevelsDto, SpectrumError> {
+    logging::clear_level(&target);
+    Ok(log_levels_dto())
+}
+
+// =============================================================================
+// Sample Rate
+// =============================================================================
+
+#[tauri::command]
+pub async fn get_sample_rate() -> Result<SampleRateDto, SpectrumError> {
+    use crate::audio::sample_rate::SUPPORTED_SAMPLE_RATES;
+
+    let output_device = crate::audio::output::get_active_output_device();
+
+    Ok(SampleRateDto {
+        sample_rate: crate::audio::engine_sample_rate() as u32,
+        supported: SUPPORTED_SAMPLE_RATES.iter().map(|&r| r as u32).collect(),
+        output_device_rate: output_device
+            .and_then(crate::device::get_device_nominal_sample_rate)
+            .map(|r| r as u32),
+        output_device_supported: output_device
+            .map(crate::device::get_device_available_sample_rates)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| r as u32)
+            .collect(),
+    })
+}
+
+/// Change the engine sample rate.
+/// Output and input captures are restarted and AudioUnits are reconfigured.
+#[tauri::command]
+pub async fn set_sample_rate(sample_rate: u32) -> Result<SampleRateDto, SpectrumError> {
+    use crate::audio::sample_rate::{is_supported_sample_rate, rates_match};
+
+    let rate = sample_rate as f64;
+    if !is_supported_sample_rate(rate) {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Unsupported sample rate: {}",
+            sample_rate
+        )));
+    }
+    if rates_match(rate, crate::audio::engine_sample_rate()) {
+        return get_sample_rate().await;
+    }
+
+    // 録音中のファイルはヘッダーのレートが固定されるため変更不可
+    let recording = get_graph_processor().with_graph(|graph| {
+        graph.record_nodes().any(|h| {
+            graph
+                .get_node(h)
+                .and_then(|n| n.as_any().downcast_ref::<RecordNode>())
+                .map(|r| r.is_recording())
+                .unwrap_or(false)
+        })
+    });
+    if recording {
+        return Err(SpectrumError::InvalidState(
+            "Stop all recordings before changing the sample rate".to_string(),
+        ));
+    }
+
+    log_info!("[Spectrum] Changing sample rate to {}Hz", sample_rate);
+
+    // Stop the output runtimes first so nothing renders while AUs are reconfigured
+    let output_device = crate::audio::output::get_active_output_device();
+    let other_outputs: Vec<u32> = crate::audio::output::get_active_output_devices()
+        .into_iter()
+        .filter(|&id| Some(id) != output_device)
+        .collect();
+    crate::audio::output::stop_output_v2();
+
+    crate::audio::sample_rate::store_engine_sample_rate(rate);
+
+    // AudioUnit configure must run on the main thread
+    let (tx, rx) = std::sync::mpsc::channel::<()>();
+    unsafe {
+        use block2::RcBlock;
+        use objc2::class;
+        use objc2::msg_send;
+        use objc2::runtime::AnyObject;
+
+        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+
+        let block = RcBlock::new(move || {
+            crate::audio_unit::get_au_manager().configure_all(rate, 1024);
+            let _ = tx.send(());
+        });
+
+        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+    }
+    if rx.recv_timeout(std::time::Duration::from_secs(5)).is_err() {
+        log_error!("[api] set_sample_rate: timeout reconfiguring AudioUnits");
+    }
+
+    // Restart captures (ring buffers are recreated at the new rate)
+    let prism_generic = crate::capture::get_active_captures().iter().any(|c| c.3);
+    if crate::capture::is_capture_running() && !prism_generic {
+        crate::audio_capture::restart_capture()?;
+    }
+    crate::capture::restart_input_captures()?;
+
+    if let Some(device_id) = output_device {
+        start_output_v2(device_id)?;
+    }
+    for device_id in other_outputs {
+        crate::audio::output::start_output_device(device_id)?;
+    }
+
+    get_sample_rate().await
+}
+
+/// Query an output device's nominal rate, rate policy and clock sources
+#[tauri::command]
+pub async fn get_device_clock_info(device_id: u32) -> Result<DeviceClockInfoDto, SpectrumError> {
+    use crate::device::RatePolicy;
+
+    ensure_device_exists(device_id)?;
+
+    let policy = crate::device::rate_policy(device_id);
+    Ok(DeviceClockInfoDto {
+        device_id,
+        nominal_sample_rate: crate::device::get_device_nominal_sample_rate(device_id),
+        available_sample_rates: crate::device::get_device_available_sample_rates(device_id),
+        rate_policy: policy.as_str().to_string(),
+        fixed_sample_rate: match policy {
+            RatePolicy::Fixed(rate) => Some(rate),
+            _ => None,
+        },
+        clock_source: crate::device::get_clock_source(device_id),
+        clock_sources: crate::device::get_clock_sources(device_id)
+            .into_iter()
+            .map(|s| ClockSourceDto {
+                id: s.id,
+                name: s.name,
+            })
+            .collect(),
+    })
+}
+
+/// Pin an output device to a nominal sample rate
+///
+/// The device keeps this rate across output restarts (AUHAL converts if it
+/// differs from the engine rate).
+#[tauri::command]
+pub async fn set_device_nominal_sample_rate(
+    device_id: u32,
+    sample_rate: f64,
+) -> Result<DeviceClockInfoDto, SpectrumError> {
+    crate::device::set_device_nominal_sample_rate(device_id, sample_rate)?;
+    get_device_clock_info(device_id).await
+}
+
+/// Choose how output start treats a device's rate: "engine" (switch it to the
+/// engine rate) or "follow" (leave it alone)
+#[tauri::command]
+pub async fn set_device_rate_policy(
+    device_id: u32,
+    policy: String,
+) -> Result<DeviceClockInfoDto, SpectrumError> {
+    use crate::device::RatePolicy;
+
+    let policy = match policy.as_str() {
+        "engine" => RatePolicy::Engine,
+        "follow" => RatePolicy::Follow,
+        other => {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "Unknown rate policy: {}",
+                other
+            )))
+        }
+    };
+    crate::device::set_rate_policy(device_id, policy);
+
+    if crate::audio::output::is_output_device_running(device_id) {
+        crate::device::apply_output_rate_policy(device_id, crate::audio::engine_sample_rate());
+    }
+    get_device_clock_info(device_id).await
+}
+
+/// Select an output device's clock source
+#[tauri::command]
+pub async fn set_device_clock_source(
+    device_id: u32,
+    source_id: u32,
+) -> Result<DeviceClockInfoDto, SpectrumError> {
+    crate::device::set_clock_source(device_id, source_id)?;
+    get_device_clock_info(device_id).await
+}
+
 // =============================================================================
 // App Icon (macOS)
 // =============================================================================
 
+/// App icon of a process as PNG (`size` pixels square, default 64)
+///
+/// Helper processes of a Prism client resolve to their host app's icon.
 #[tauri::command]
-pub async fn get_app_icon_by_pid(_pid: u32) -> Result<Vec<u8>, String> {
-    #[cfg(target_os = "macos")]
-    {
-        // Icon retrieval by PID is disabled for now.
-        // The UI should continue using type/category icons (same as V1).
-        Err("get_app_icon_by_pid is temporarily disabled; use type icons".to_string())
+pub async fn get_app_icon_by_pid(pid: u32, size: Option<u32>) -> Result<Vec<u8>, SpectrumError> {
+    let size = size.unwrap_or(crate::app_icon::DEFAULT_ICON_SIZE);
+    let png = tokio::task::spawn_blocking(move || crate::app_icon::icon_png(pid, size))
+        .await
+        .map_err(|e| SpectrumError::Other(e.to_string()))?
+        .map_err(SpectrumError::InvalidArgument)?;
+    Ok(png.as_ref().clone())
+}
+
+/// App icons for several processes in one call (base64 PNG, None when unavailable)
+#[tauri::command]
+pub async fn get_app_icons(
+    pids: Vec<u32>,
+    size: Option<u32>,
+) -> Result<Vec<AppIconDto>, SpectrumError> {
+    use base64::Engine;
+
+    let size = size.unwrap_or(crate::app_icon::DEFAULT_ICON_SIZE);
+    tokio::task::spawn_blocking(move || {
+        pids.into_iter()
+            .map(|pid| AppIconDto {
+                pid,
+                png_base64: crate::app_icon::icon_png(pid, size)
+                    .map(|png| base64::engine::general_purpose::STANDARD.encode(png.as_slice()))
+                    .ok(),
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| SpectrumError::Other(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bus_info(port_count: u8, plugin_ids: &[&str]) -> NodeInfoDto {
+        let plugins: Vec<_> = plugin_ids
+            .iter()
+            .enumerate()
+            .map(|(i, plugin_id)| {
+                json!({
+                    "instance_id": format!("instance-{}", i),
+                    "plugin_id": plugin_id,
+                    "name": plugin_id,
+                    "enabled": true,
+                })
+            })
+            .collect();
+        serde_json::from_value(json!({
+            "type": "bus",
+            "handle": 1,
+            "bus_id": "bus_1",
+            "label": "Bus 1",
+            "port_count": port_count,
+            "plugins": plugins,
+        }))
+        .unwrap()
     }
 
-    #[cfg(not(target_os = "macos"))]
-    {
-        Err("get_app_icon_by_pid is only supported on macOS".to_string())
+    fn edge_info(source: u32, source_port: u8, target: u32, channels: u8) -> EdgeInfoDto {
+        serde_json::from_value(json!({
+            "id": 1,
+            "source": source,
+            "source_port": source_port,
+            "target": target,
+            "target_port": 0,
+            "gain": 1.0,
+            "muted": false,
+            "channels": channels,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_needs_rebuild_keeps_a_bus_with_the_same_plugins() {
+        let current = bus_info(2, &["native:eq", "aufx:comp"]);
+        let mut target = bus_info(2, &["native:eq", "aufx:comp"]);
+        assert!(!needs_rebuild(&current, &target));
+
+        // ラベルやプラグインの状態はその場で更新できる
+        if let NodeInfoDto::Bus { label, plugins, .. } = &mut target {
+            *label = "Renamed".to_string();
+            plugins[1].enabled = false;
+            plugins[1].mix = 0.5;
+            plugins[1].ring_out = true;
+        }
+        assert!(!needs_rebuild(&current, &target));
+    }
+
+    #[test]
+    fn test_needs_rebuild_on_ports_plugins_or_kind() {
+        let current = bus_info(2, &["native:eq", "aufx:comp"]);
+        for target in [
+            bus_info(4, &["native:eq", "aufx:comp"]),
+            bus_info(2, &["native:eq", "aufx:verb"]),
+            bus_info(2, &["aufx:comp", "native:eq"]),
+            bus_info(2, &["native:eq"]),
+            bus_info(2, &["native:eq", "aufx:comp", "native:gate"]),
+        ] {
+            assert!(needs_rebuild(&current, &target), "{:?}", target);
+        }
+
+        let record: NodeInfoDto = serde_json::from_value(json!({
+            "type": "record",
+            "handle": 1,
+            "record_id": "rec_1",
+            "label": "Bus 1",
+            "port_count": 2,
+        }))
+        .unwrap();
+        assert!(needs_rebuild(&current, &record));
+    }
+
+    #[test]
+    fn test_edge_key_matches_by_stable_id() {
+        let current: HashMap<u32, String> =
+            HashMap::from([(1, "source:a".to_string()), (2, "bus:b".to_string())]);
+        let target: HashMap<u32, String> =
+            HashMap::from([(7, "source:a".to_string()), (9, "bus:b".to_string())]);
+
+        // ハンドルが違っても同じノード・ポートなら同じエッジ
+        let key = edge_key(&current, &edge_info(1, 0, 2, 1));
+        assert!(key.is_some());
+        assert_eq!(key, edge_key(&target, &edge_info(7, 0, 9, 1)));
+        // channels = 0 は 1 として扱う
+        assert_eq!(key, edge_key(&target, &edge_info(7, 0, 9, 0)));
+
+        assert_ne!(key, edge_key(&target, &edge_info(7, 1, 9, 1)));
+        assert_ne!(key, edge_key(&target, &edge_info(7, 0, 9, 2)));
+        assert_ne!(key, edge_key(&target, &edge_info(9, 0, 7, 1)));
+        assert_eq!(edge_key(&target, &edge_info(1, 0, 9, 1)), None);
     }
 }
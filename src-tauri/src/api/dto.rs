@@ -1,7 +1,7 @@
 //! Data Transfer Objects for API
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // =============================================================================
 // 基本型
@@ -36,6 +36,12 @@ pub struct OutputSinkDto {
     pub channel_count: u8,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_uid: Option<String>,
+    /// Port → device channel routes; None = contiguous from `channel_offset`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_map: Option<Vec<crate::audio::sink::ChannelRoute>>,
+    /// Follow the system default output (`device_id` is the current default)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub follow_default: bool,
 }
 
 // =============================================================================
@@ -50,9 +56,118 @@ pub struct PluginInstanceDto {
     #[serde(default)]
     pub manufacturer: String,
     pub enabled: bool,
+    /// Wet/dry mix (0.0 = dry, 1.0 = wet)
+    #[serde(default = "default_mix")]
+    pub mix: f32,
+    /// Bypass lets the tail ring out instead of crossfading the output
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ring_out: bool,
     /// Optional plugin fullState serialized as base64(plist binary)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub state: Option<String>,
+    /// Settings of a built-in processor (`native:*` plugin_id)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub native: Option<NativeDspDto>,
+    /// Runs in a separate process (runtime info, not restored)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub out_of_process: bool,
+    /// The plugin process crashed; it is bypassed until `restart_plugin`
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub crashed: bool,
+    /// Renders kept exceeding the time budget; it is bypassed until `restart_plugin`
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub overloaded: bool,
+    /// Channel layout on the bus: "native", "stereo_pairs" (one instance per port pair)
+    /// or "bypassed" (can't run at the bus width). Runtime info, not restored
+    #[serde(default = "default_plugin_layout")]
+    pub layout: String,
+}
+
+fn is_false(v: &bool) -> bool {
+    !*v
+}
+
+fn default_mix() -> f32 {
+    1.0
+}
+
+fn default_plugin_layout() -> String {
+    crate::audio::bus::PluginLayout::default()
+        .as_str()
+        .to_string()
+}
+
+/// Settings of a built-in bus processor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NativeDspDto {
+    Eq {
+        bands: Vec<EqBandDto>,
+    },
+    Compressor {
+        threshold_db: f32,
+        ratio: f32,
+        attack_ms: f32,
+        release_ms: f32,
+        makeup_db: f32,
+    },
+    Gate {
+        threshold_db: f32,
+        range_db: f32,
+        attack_ms: f32,
+        hold_ms: f32,
+        release_ms: f32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EqBandDto {
+    /// "low_shelf", "peak", "high_shelf", "low_cut" or "high_cut"
+    pub kind: String,
+    pub frequency: f32,
+    pub gain_db: f32,
+    pub q: f32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl From<crate::audio::dsp::NativeSettings> for NativeDspDto {
+    fn from(settings: crate::audio::dsp::NativeSettings) -> Self {
+        use crate::audio::dsp::NativeSettings;
+        match settings {
+            NativeSettings::Eq(eq) => NativeDspDto::Eq {
+                bands: eq
+                    .bands
+                    .iter()
+                    .map(|b| EqBandDto {
+                        kind: b.kind.as_str().to_string(),
+                        frequency: b.frequency,
+                        gain_db: b.gain_db,
+                        q: b.q,
+                        enabled: b.enabled,
+                    })
+                    .collect(),
+            },
+            NativeSettings::Compressor(c) => NativeDspDto::Compressor {
+                threshold_db: c.threshold_db,
+                ratio: c.ratio,
+                attack_ms: c.attack_ms,
+                release_ms: c.release_ms,
+                makeup_db: c.makeup_db,
+            },
+            NativeSettings::Gate(g) => NativeDspDto::Gate {
+                threshold_db: g.threshold_db,
+                range_db: g.range_db,
+                attack_ms: g.attack_ms,
+                hold_ms: g.hold_ms,
+                release_ms: g.release_ms,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,10 +181,25 @@ pub enum NodeInfoDto {
         source_id: SourceIdDto,
         port_count: u8,
         label: String,
+        /// Display color ("#rrggbb"); None uses the default for the node kind
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        /// Free-form key/value data for the frontend and controllers (notes, tags, ...)
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         sub_label: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         available: Option<bool>,
+        /// Per-port input trim (linear); empty means unity on every port
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        trims: Vec<f32>,
+        /// Per-port polarity invert; empty means none inverted
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        phase_inverted: Vec<bool>,
+        /// Prism clients routed to this pair and their pre-capture gain (Prism sources only)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        client_gains: Vec<PrismClientGainDto>,
     },
     #[serde(rename = "bus")]
     Bus {
@@ -78,8 +208,15 @@ pub enum NodeInfoDto {
         stable_id: String,
         bus_id: String,
         label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
         port_count: u8,
         plugins: Vec<PluginInstanceDto>,
+        /// Playing a frozen render instead of the plugins (runtime state; not restored)
+        #[serde(default, skip_serializing_if = "is_false")]
+        frozen: bool,
     },
     #[serde(rename = "sink")]
     Sink {
@@ -89,11 +226,332 @@ pub enum NodeInfoDto {
         sink: OutputSinkDto,
         port_count: u8,
         label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         available: Option<bool>,
+        /// Output limiter; None when never configured
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limiter: Option<SinkLimiterDto>,
+        /// Output protection; None when never configured
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        protection: Option<OutputProtectionDto>,
+    },
+    #[serde(rename = "record")]
+    Record {
+        handle: NodeHandle,
+        #[serde(default)]
+        stable_id: String,
+        record_id: String,
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        port_count: u8,
+        #[serde(default)]
+        recording: bool,
+        /// Included in the next `punch_in` without an explicit node list
+        #[serde(default)]
+        armed: bool,
+    },
+    #[serde(rename = "generator")]
+    Generator {
+        handle: NodeHandle,
+        #[serde(default)]
+        stable_id: String,
+        generator_id: String,
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        port_count: u8,
+        params: GeneratorParamsDto,
+    },
+    /// Generator / instrument AudioUnit rendering into the graph
+    #[serde(rename = "plugin_source")]
+    PluginSource {
+        handle: NodeHandle,
+        #[serde(default)]
+        stable_id: String,
+        source_id: String,
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        port_count: u8,
+        /// The plugin (state is filled in for persisted graphs, like bus plugins)
+        plugin: PluginInstanceDto,
+    },
+    /// N×M gain matrix (downmix / upmix)
+    #[serde(rename = "matrix")]
+    Matrix {
+        handle: NodeHandle,
+        #[serde(default)]
+        stable_id: String,
+        matrix_id: String,
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        input_count: u8,
+        output_count: u8,
+        /// One row per output, one column per input (linear)
+        coefficients: Vec<Vec<f32>>,
+    },
+    /// One half of a loopback pair (`role` is "sink" or "source")
+    #[serde(rename = "loopback")]
+    Loopback {
+        handle: NodeHandle,
+        #[serde(default)]
+        stable_id: String,
+        loopback_id: String,
+        role: String,
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        port_count: u8,
+    },
+    /// RTP network stream (`role` is "send" or "receive")
+    #[serde(rename = "network")]
+    Network {
+        handle: NodeHandle,
+        #[serde(default)]
+        stable_id: String,
+        net_id: String,
+        role: String,
+        label: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        color: Option<String>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        port_count: u8,
+        /// Destination host (send) or listen address / multicast group (receive, "" = any)
+        address: String,
+        port: u16,
+        /// Send only
+        #[serde(default)]
+        packet_time_us: u32,
+        /// Receive only
+        #[serde(default)]
+        jitter_ms: u32,
     },
 }
 
+impl NodeInfoDto {
+    pub fn handle(&self) -> NodeHandle {
+        match self {
+            Self::Source { handle, .. }
+            | Self::Bus { handle, .. }
+            | Self::Sink { handle, .. }
+            | Self::Record { handle, .. }
+            | Self::Generator { handle, .. }
+            | Self::PluginSource { handle, .. }
+            | Self::Matrix { handle, .. }
+            | Self::Loopback { handle, .. }
+            | Self::Network { handle, .. } => *handle,
+        }
+    }
+
+    pub fn set_handle(&mut self, new_handle: NodeHandle) {
+        match self {
+            Self::Source { handle, .. }
+            | Self::Bus { handle, .. }
+            | Self::Sink { handle, .. }
+            | Self::Record { handle, .. }
+            | Self::Generator { handle, .. }
+            | Self::PluginSource { handle, .. }
+            | Self::Matrix { handle, .. }
+            | Self::Loopback { handle, .. }
+            | Self::Network { handle, .. } => *handle = new_handle,
+        }
+    }
+
+    pub fn stable_id(&self) -> &str {
+        match self {
+            Self::Source { stable_id, .. }
+            | Self::Bus { stable_id, .. }
+            | Self::Sink { stable_id, .. }
+            | Self::Record { stable_id, .. }
+            | Self::Generator { stable_id, .. }
+            | Self::PluginSource { stable_id, .. }
+            | Self::Matrix { stable_id, .. }
+            | Self::Loopback { stable_id, .. }
+            | Self::Network { stable_id, .. } => stable_id,
+        }
+    }
+
+    /// (input ports, output ports)
+    pub fn port_counts(&self) -> (usize, usize) {
+        match self {
+            Self::Source { port_count, .. }
+            | Self::Generator { port_count, .. }
+            | Self::PluginSource { port_count, .. } => (0, *port_count as usize),
+            Self::Sink { port_count, .. } | Self::Record { port_count, .. } => {
+                (*port_count as usize, 0)
+            }
+            Self::Bus { port_count, .. } => (*port_count as usize, *port_count as usize),
+            Self::Matrix {
+                input_count,
+                output_count,
+                ..
+            } => (*input_count as usize, *output_count as usize),
+            Self::Loopback {
+                role, port_count, ..
+            } => match role.as_str() {
+                "sink" => (*port_count as usize, 0),
+                _ => (0, *port_count as usize),
+            },
+            Self::Network {
+                role, port_count, ..
+            } => match role.as_str() {
+                "send" => (*port_count as usize, 0),
+                _ => (0, *port_count as usize),
+            },
+        }
+    }
+
+    pub fn color(&self) -> Option<&str> {
+        match self {
+            Self::Source { color, .. }
+            | Self::Bus { color, .. }
+            | Self::Sink { color, .. }
+            | Self::Record { color, .. }
+            | Self::Generator { color, .. }
+            | Self::PluginSource { color, .. }
+            | Self::Matrix { color, .. }
+            | Self::Loopback { color, .. }
+            | Self::Network { color, .. } => color.as_deref(),
+        }
+    }
+
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        match self {
+            Self::Source { metadata, .. }
+            | Self::Bus { metadata, .. }
+            | Self::Sink { metadata, .. }
+            | Self::Record { metadata, .. }
+            | Self::Generator { metadata, .. }
+            | Self::PluginSource { metadata, .. }
+            | Self::Matrix { metadata, .. }
+            | Self::Loopback { metadata, .. }
+            | Self::Network { metadata, .. } => metadata,
+        }
+    }
+}
+
+/// Handles of a newly created loopback pair
+#[derive(Debug, Clone, Serialize)]
+pub struct LoopbackPairDto {
+    pub loopback_id: String,
+    pub sink_handle: NodeHandle,
+    pub source_handle: NodeHandle,
+}
+
+/// Lookahead limiter of a sink node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SinkLimiterDto {
+    pub enabled: bool,
+    /// Ceiling (dBFS)
+    pub threshold_db: f32,
+    pub release_ms: f32,
+    pub lookahead_ms: f32,
+}
+
+impl From<crate::audio::dsp::LimiterSettings> for SinkLimiterDto {
+    fn from(s: crate::audio::dsp::LimiterSettings) -> Self {
+        SinkLimiterDto {
+            enabled: s.enabled,
+            threshold_db: s.threshold_db,
+            release_ms: s.release_ms,
+            lookahead_ms: s.lookahead_ms,
+        }
+    }
+}
+
+impl From<&SinkLimiterDto> for crate::audio::dsp::LimiterSettings {
+    fn from(dto: &SinkLimiterDto) -> Self {
+        crate::audio::dsp::LimiterSettings {
+            enabled: dto.enabled,
+            threshold_db: dto.threshold_db,
+            release_ms: dto.release_ms,
+            lookahead_ms: dto.lookahead_ms,
+        }
+    }
+}
+
+/// Output protection of a sink node (dims the output after sustained spikes)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputProtectionDto {
+    pub enabled: bool,
+    /// Spike level (dBFS, after the sink gain)
+    pub threshold_db: f32,
+    /// Time above the threshold before dimming (ms)
+    pub hold_ms: f32,
+    /// Dim applied once tripped (dB)
+    pub dim_db: f32,
+    /// Currently dimmed (read-only; ignored when applied)
+    #[serde(default)]
+    pub tripped: bool,
+}
+
+impl OutputProtectionDto {
+    pub fn new(s: crate::audio::dsp::ProtectionSettings, tripped: bool) -> Self {
+        OutputProtectionDto {
+            enabled: s.enabled,
+            threshold_db: s.threshold_db,
+            hold_ms: s.hold_ms,
+            dim_db: s.dim_db,
+            tripped,
+        }
+    }
+}
+
+impl From<&OutputProtectionDto> for crate::audio::dsp::ProtectionSettings {
+    fn from(dto: &OutputProtectionDto) -> Self {
+        crate::audio::dsp::ProtectionSettings {
+            enabled: dto.enabled,
+            threshold_db: dto.threshold_db,
+            hold_ms: dto.hold_ms,
+            dim_db: dto.dim_db,
+        }
+    }
+}
+
+/// Test signal generator parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeneratorParamsDto {
+    /// "sine", "pink", "white" or "sweep"
+    pub waveform: String,
+    /// Sine frequency / sweep start (Hz)
+    pub frequency: f32,
+    /// Output level (linear)
+    pub level: f32,
+    /// Sweep end frequency (Hz)
+    pub sweep_end: f32,
+    /// Sweep duration (seconds)
+    pub sweep_seconds: f32,
+}
+
+impl From<crate::audio::generator::GeneratorParams> for GeneratorParamsDto {
+    fn from(p: crate::audio::generator::GeneratorParams) -> Self {
+        GeneratorParamsDto {
+            waveform: p.waveform.as_str().to_string(),
+            frequency: p.frequency,
+            level: p.level,
+            sweep_end: p.sweep_end,
+            sweep_seconds: p.sweep_seconds,
+        }
+    }
+}
+
 // =============================================================================
 // Edge DTOs
 // =============================================================================
@@ -107,6 +565,50 @@ pub struct EdgeInfoDto {
     pub target_port: PortId,
     pub gain: f32,
     pub muted: bool,
+    /// Number of adjacent ports carried by this edge (1 = mono edge)
+    #[serde(default = "default_edge_channels")]
+    pub channels: u8,
+    /// Per-channel trim (linear); empty means unity on every channel
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trims: Vec<f32>,
+    /// Pan position -1.0 (L) ~ 1.0 (R)
+    #[serde(default)]
+    pub pan: f32,
+    /// "0dB", "-3dB", "-4.5dB" or "-6dB"
+    #[serde(default = "default_pan_law")]
+    pub pan_law: String,
+    /// Attenuation when the bundle is wider than the target and is summed: "0dB", "-3dB" or "-6dB"
+    #[serde(default = "default_sum_law")]
+    pub sum_law: String,
+    /// "buffered" or "direct"
+    #[serde(default = "default_monitor_mode")]
+    pub monitor_mode: String,
+    /// "pre_plugin" or "post_plugin" (bus sources)
+    #[serde(default = "default_tap_point")]
+    pub tap_point: String,
+    /// Mute toggle shown in the menu bar extra
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+fn default_edge_channels() -> u8 {
+    1
+}
+
+fn default_pan_law() -> String {
+    crate::audio::PanLaw::default().as_str().to_string()
+}
+
+fn default_sum_law() -> String {
+    crate::audio::SumLaw::default().as_str().to_string()
+}
+
+fn default_monitor_mode() -> String {
+    crate::audio::MonitorMode::default().as_str().to_string()
+}
+
+fn default_tap_point() -> String {
+    crate::audio::TapPoint::default().as_str().to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -115,6 +617,105 @@ pub struct EdgeGainUpdate {
     pub gain: f32,
 }
 
+/// Automation breakpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationPointDto {
+    /// Milliseconds from the transport start
+    pub time_ms: f64,
+    /// Linear gain
+    pub gain: f32,
+}
+
+/// Automation transport state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutomationStatusDto {
+    pub playing: bool,
+    pub recording: bool,
+    pub position_ms: f64,
+    /// Time of the last breakpoint over all lanes
+    pub duration_ms: f64,
+    pub lane_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaperPointDto {
+    /// Fader position 0.0 ~ 1.0
+    pub position: f32,
+    pub db: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FaderTaperDto {
+    /// Piecewise-linear (position, dB) points; position 0.0 is always -inf
+    pub points: Vec<TaperPointDto>,
+    /// dB values at or below this are treated as -inf
+    pub min_db: f32,
+    /// Maximum gain accepted by the dB API
+    pub max_db: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoloStateDto {
+    /// "sip" (solo in place), "afl" or "pfl"
+    pub mode: String,
+    /// Monitor sink for AFL/PFL
+    pub monitor_sink: Option<NodeHandle>,
+    pub soloed_nodes: Vec<NodeHandle>,
+    pub soloed_edges: Vec<EdgeId>,
+    /// Edges currently silenced by solo
+    pub solo_muted_edges: Vec<EdgeId>,
+}
+
+/// Ducking rule (talkback): lower `targets` while `key` is above the threshold
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuckingRuleDto {
+    /// None when creating a rule
+    #[serde(default)]
+    pub id: Option<u32>,
+    /// Key node (e.g. a mic source)
+    pub key: NodeHandle,
+    /// Edges that are lowered
+    pub targets: Vec<EdgeId>,
+    pub threshold_db: f32,
+    /// Gain reduction while ducked (positive dB)
+    pub depth_db: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Current gain reduction in dB (ignored on input)
+    #[serde(default)]
+    pub reduction_db: f32,
+}
+
+/// Edges whose gain and mute move together (`link_edges`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EdgeLinkGroupDto {
+    pub id: u32,
+    pub edges: Vec<EdgeId>,
+    /// Members keep their gain differences (dB) instead of sharing one gain
+    pub preserve_offsets: bool,
+}
+
+/// Auto-mute of a Prism source while its app is away (`set_source_auto_gate`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceAutoGateDto {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Also gate after this many seconds below `threshold_db` (None = only when the app leaves)
+    #[serde(default)]
+    pub silence_secs: Option<f32>,
+    #[serde(default = "default_gate_threshold_db")]
+    pub threshold_db: f32,
+    /// Outgoing edges are currently muted by the gate (ignored on input)
+    #[serde(default)]
+    pub gated: bool,
+}
+
+fn default_gate_threshold_db() -> f32 {
+    -60.0
+}
+
 // =============================================================================
 // Graph DTOs
 // =============================================================================
@@ -123,6 +724,169 @@ pub struct EdgeGainUpdate {
 pub struct GraphDto {
     pub nodes: Vec<NodeInfoDto>,
     pub edges: Vec<EdgeInfoDto>,
+    /// Cue sends (not part of the saved graph state)
+    #[serde(default)]
+    pub cue: CueStateDto,
+}
+
+impl GraphDto {
+    /// Node by stable_id
+    pub fn node_by_stable_id(&self, stable_id: &str) -> Option<&NodeInfoDto> {
+        self.nodes.iter().find(|n| n.stable_id() == stable_id)
+    }
+
+    /// Edge connecting two ports
+    pub fn edge_between(
+        &self,
+        source: NodeHandle,
+        source_port: PortId,
+        target: NodeHandle,
+        target_port: PortId,
+    ) -> Option<&EdgeInfoDto> {
+        self.edges.iter().find(|e| {
+            e.source == source
+                && e.source_port == source_port
+                && e.target == target
+                && e.target_port == target_port
+        })
+    }
+}
+
+/// Result of `apply_graph`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplyGraphResultDto {
+    /// Handle in the applied description -> handle in the audio graph
+    pub handles: HashMap<NodeHandle, NodeHandle>,
+    pub nodes_added: usize,
+    pub nodes_removed: usize,
+    /// Kept nodes whose label or settings changed
+    pub nodes_updated: usize,
+    pub edges_added: usize,
+    pub edges_removed: usize,
+    /// Kept edges whose gain or settings changed
+    pub edges_updated: usize,
+    /// Handles (in the applied description) of nodes that could not be created
+    pub nodes_failed: Vec<NodeHandle>,
+    /// Ids (in the applied description) of edges that could not be created, including
+    /// those whose nodes failed
+    pub edges_rejected: Vec<EdgeId>,
+}
+
+impl ApplyGraphResultDto {
+    /// Whether the graph was modified at all
+    pub fn changed(&self) -> bool {
+        self.nodes_added
+            + self.nodes_removed
+            + self.nodes_updated
+            + self.edges_added
+            + self.edges_removed
+            + self.edges_updated
+            > 0
+    }
+}
+
+/// A node or edge in an `execute_batch` op: an existing handle/id, or the alias of
+/// a node/edge created by an earlier op of the same batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BatchRefDto {
+    Id(u32),
+    Alias(String),
+}
+
+/// One mutation of an `execute_batch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum GraphOpDto {
+    /// Label defaults to the smallest unused "Bus N"
+    AddBus {
+        #[serde(default)]
+        alias: Option<String>,
+        #[serde(default)]
+        label: Option<String>,
+        #[serde(default)]
+        port_count: Option<u8>,
+    },
+    /// Append a plugin (AudioUnit or built-in) to a bus
+    AddPlugin {
+        #[serde(default)]
+        alias: Option<String>,
+        bus: BatchRefDto,
+        plugin_id: String,
+        #[serde(default)]
+        position: Option<usize>,
+    },
+    RemoveNode {
+        node: BatchRefDto,
+    },
+    AddEdge {
+        #[serde(default)]
+        alias: Option<String>,
+        source: BatchRefDto,
+        source_port: PortId,
+        target: BatchRefDto,
+        target_port: PortId,
+        #[serde(default)]
+        gain: Option<f32>,
+        #[serde(default)]
+        muted: Option<bool>,
+        #[serde(default)]
+        channels: Option<u8>,
+    },
+    RemoveEdge {
+        edge: BatchRefDto,
+    },
+    SetEdgeGain {
+        edge: BatchRefDto,
+        gain: f32,
+    },
+    SetEdgeMuted {
+        edge: BatchRefDto,
+        muted: bool,
+    },
+}
+
+/// Result of `execute_batch`: what the aliases resolved to
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchResultDto {
+    /// Alias -> node handle
+    pub nodes: HashMap<String, NodeHandle>,
+    /// Alias -> edge id
+    pub edges: HashMap<String, EdgeId>,
+    /// Alias -> plugin instance id
+    pub plugins: HashMap<String, String>,
+    /// Number of ops applied
+    pub applied: usize,
+}
+
+/// Result of `set_bus_port_count`
+#[derive(Debug, Clone, Serialize)]
+pub struct BusResizeDto {
+    pub port_count: u8,
+    /// Bundles narrowed to the remaining ports
+    pub narrowed_edges: Vec<EdgeId>,
+    /// Input bundles now wider than the bus, summed with their sum law
+    pub folded_edges: Vec<EdgeId>,
+    /// Edges moved from removed ports onto remaining ones
+    pub rewired_edges: Vec<EdgeId>,
+    /// Edges dropped because their new ports were already connected
+    pub removed_edges: Vec<EdgeId>,
+    /// Some plugins can't run at the new width and are bypassed (see `PluginInstanceDto::layout`)
+    pub plugins_bypassed: bool,
+}
+
+/// Headphone cue: the cue sink and the edges sent to it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CueStateDto {
+    pub sink: Option<NodeHandle>,
+    pub edges: Vec<CueEdgeDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CueEdgeDto {
+    pub id: EdgeId,
+    /// "pre_gain" | "post_gain"
+    pub tap: String,
 }
 
 // =============================================================================
@@ -158,6 +922,10 @@ pub struct OutputDeviceDto {
     pub transport_type: String,
     pub icon_hint: String,
     pub is_aggregate_sub: bool,
+    /// Output latency (device latency + safety offset) in frames
+    pub latency_frames: u32,
+    /// `latency_frames` at the device's nominal rate
+    pub latency_ms: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -167,6 +935,35 @@ pub struct PrismAppDto {
     pub channel_offset: u8,
 }
 
+/// Pre-capture gain of one Prism client (applied by the driver before capture)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrismClientGainDto {
+    pub client_id: u32,
+    pub pid: u32,
+    pub name: String,
+    /// Linear (1.0 = unity)
+    pub gain: f32,
+}
+
+/// App icon for one PID (`get_app_icons`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppIconDto {
+    pub pid: u32,
+    /// Base64 PNG; None if the process has no icon (or has exited)
+    pub png_base64: Option<String>,
+}
+
+/// Microphone authorization
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioPermissionDto {
+    /// "not_determined" | "restricted" | "denied" | "authorized"
+    pub status: String,
+    /// Input capture can start (not determined: the system asks on first capture)
+    pub can_capture: bool,
+    /// `request_audio_permission` can still show the system prompt
+    pub can_request: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrismStatusDto {
     pub connected: bool,
@@ -174,6 +971,38 @@ pub struct PrismStatusDto {
     pub apps: Vec<PrismAppDto>,
 }
 
+/// One Prism stereo pair in the channel map
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrismChannelDto {
+    /// Stereo pair index (same as `SourceIdDto::PrismChannel`); 0 = MAIN
+    pub channel: u8,
+    /// Apps currently routed to this pair
+    pub apps: Vec<PrismAppDto>,
+    /// Source nodes reading this pair
+    pub source_handles: Vec<NodeHandle>,
+}
+
+/// Prism virtual driver configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrismDriverDto {
+    pub connected: bool,
+    pub sample_rate: u32,
+    /// Driver I/O buffer (frames)
+    pub buffer_size: u32,
+    pub channel_count: u32,
+    /// true = reported by prismd; false = read from the Prism device (older daemon / not running)
+    pub negotiated: bool,
+}
+
+/// Aggregate device created by Spectrum
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateDeviceDto {
+    pub device_id: u32,
+    pub uid: String,
+    pub name: String,
+    pub channel_count: u32,
+}
+
 // =============================================================================
 // Plugin DTOs
 // =============================================================================
@@ -183,6 +1012,65 @@ pub struct PluginInfoDto {
     pub plugin_id: String,
     pub name: String,
     pub manufacturer: String,
+    /// Last instantiation error (the plugin failed to load)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One AudioUnit parameter (for generic inline controls)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginParameterDto {
+    /// Parameter address (pass to `set_plugin_parameter`)
+    pub param_id: u64,
+    pub identifier: String,
+    pub name: String,
+    /// Parameter group path (e.g. "Band 1 / Filter")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub unit: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_label: Option<String>,
+    pub min_value: f32,
+    pub max_value: f32,
+    pub value: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_value: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_strings: Vec<String>,
+    pub writable: bool,
+    pub can_ramp: bool,
+    pub logarithmic: bool,
+}
+
+impl From<crate::audio_unit::AudioUnitParameter> for PluginParameterDto {
+    fn from(p: crate::audio_unit::AudioUnitParameter) -> Self {
+        PluginParameterDto {
+            param_id: p.address,
+            identifier: p.identifier,
+            name: p.name,
+            group: p.group,
+            unit: p.unit,
+            unit_label: p.unit_label,
+            min_value: p.min_value,
+            max_value: p.max_value,
+            value: p.value,
+            display_value: p.display_value,
+            value_strings: p.value_strings,
+            writable: p.writable,
+            can_ramp: p.can_ramp,
+            logarithmic: p.logarithmic,
+        }
+    }
+}
+
+/// How `open_plugin_ui` presented the plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PluginUiDto {
+    /// The plugin's own view opened in a window
+    Custom,
+    /// No custom view: render generic controls for these parameters
+    Generic { parameters: Vec<PluginParameterDto> },
 }
 
 // =============================================================================
@@ -194,6 +1082,11 @@ pub struct PortMeterDto {
     pub peak: f32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rms: Option<f32>,
+    /// Held peak (only when peak hold is configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hold: Option<f32>,
+    #[serde(default)]
+    pub clip: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -216,21 +1109,378 @@ pub struct GraphMetersDto {
     pub timestamp: u64,
 }
 
-// =============================================================================
-// State DTOs (永続化用)
-// =============================================================================
+/// Peak history of one node or edge (oldest first)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MeterHistoryEntryDto {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handle: Option<NodeHandle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge_id: Option<EdgeId>,
+    pub peaks: Vec<f32>,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct NodePosition {
-    pub x: f32,
-    pub y: f32,
+pub struct MeterHistoryDto {
+    pub rate_hz: f32,
+    /// Index of the newest frame + 1 (frames since the engine started)
+    pub frame_index: u64,
+    pub nodes: Vec<MeterHistoryEntryDto>,
+    pub edges: Vec<MeterHistoryEntryDto>,
 }
 
+/// Meter ballistics applied to every meter, plus what gets metered
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CanvasTransformDto {
-    pub x: f32,
-    pub y: f32,
-    pub scale: f32,
+pub struct MeteringConfigDto {
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub peak_hold_ms: f32,
+    pub clip_hold: bool,
+    /// Meters are computed every this many audio blocks (1 = every block, 0 = off)
+    pub rate_divisor: u32,
+    /// Nodes whose metering is turned off
+    pub unmetered_nodes: Vec<NodeHandle>,
+    /// Edges whose metering is turned off
+    pub unmetered_edges: Vec<u32>,
+}
+
+/// Binned spectrum of one node port (log-spaced bands)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectrumDto {
+    pub handle: NodeHandle,
+    pub port: u8,
+    pub sample_rate: f64,
+    pub fft_size: u32,
+    /// Band center frequencies (Hz)
+    pub frequencies: Vec<f32>,
+    /// Band levels (dBFS, floor -120)
+    pub magnitudes_db: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpectrumTapConfigDto {
+    pub handle: NodeHandle,
+    pub port: u8,
+    pub fft_size: u32,
+    pub rate_hz: f32,
+    pub smoothing: f32,
+}
+
+// =============================================================================
+// Record DTOs
+// =============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingStatusDto {
+    pub handle: NodeHandle,
+    pub recording: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub frames_written: u64,
+    pub dropped_frames: u64,
+    pub duration_secs: f64,
+    /// Render frame of the first sample in the file (files with the same value line up)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<u64>,
+    /// Scheduled punch-out frame
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_frame: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Result of `punch_in` / `punch_out`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PunchResultDto {
+    /// Render frame shared by every node
+    pub frame: u64,
+    pub sample_rate: f64,
+    pub nodes: Vec<RecordingStatusDto>,
+}
+
+// =============================================================================
+// Marker DTOs
+// =============================================================================
+
+/// A time-stamped marker (`add_marker`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkerDto {
+    pub id: u32,
+    pub label: String,
+    /// Unix ms
+    pub timestamp_ms: u64,
+    /// Seconds since the engine session started (see `get_session_stats`)
+    pub session_secs: f64,
+    /// Unix ms of that session start (tells markers of different sessions apart)
+    pub session_started_at_ms: u64,
+    /// Recordings that were running, with the marker position inside each file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recordings: Vec<MarkerRecordingDto>,
+}
+
+/// Position of a marker inside a recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkerRecordingDto {
+    pub handle: NodeHandle,
+    pub path: String,
+    /// Render frame the recording started at (with the session, tells apart takes
+    /// written to the same path)
+    #[serde(default)]
+    pub start_frame: u64,
+    pub offset_frames: u64,
+    pub offset_secs: f64,
+}
+
+// =============================================================================
+// Bounce DTOs
+// =============================================================================
+
+/// An audio file (WAV / CAF) played by a capture source during a bounce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BounceInputDto {
+    pub path: String,
+    /// Source node whose live input the file replaces
+    pub source_handle: NodeHandle,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BounceResultDto {
+    pub path: String,
+    pub channels: usize,
+    pub frames: u64,
+    pub duration_secs: f64,
+    /// Sink path latency trimmed from the start of the file
+    pub latency_frames: u32,
+    pub cancelled: bool,
+}
+
+/// Payload of the `bounce-progress` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BounceProgressDto {
+    pub frames_rendered: u64,
+    pub total_frames: u64,
+}
+
+/// A bus playing a frozen render
+#[derive(Debug, Clone, Serialize)]
+pub struct FrozenBusDto {
+    pub handle: NodeHandle,
+    pub port_count: usize,
+    /// Cache length including the path latency at the start
+    pub frames: usize,
+    pub duration_secs: f64,
+}
+
+/// Statistics of a network send/receive node
+#[derive(Debug, Clone, Serialize)]
+pub struct NetStatusDto {
+    pub handle: NodeHandle,
+    /// Packets sent (send) or played (receive)
+    pub packets: u64,
+    pub lost_packets: u64,
+    pub late_packets: u64,
+    /// Not RTP, or a channel count that does not match the node
+    pub bad_packets: u64,
+    pub dropped_frames: u64,
+    pub underruns: u64,
+    /// Frames waiting in the send queue / jitter buffer
+    pub buffered_frames: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// =============================================================================
+// Scene DTOs
+// =============================================================================
+
+/// Edge parameters in a scene (nodes referenced by stable_id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneEdgeDto {
+    pub source: String,
+    pub source_port: PortId,
+    pub target: String,
+    pub target_port: PortId,
+    pub gain: f32,
+    pub muted: bool,
+}
+
+/// Output (sink) gains in a scene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneOutputDto {
+    pub node: String,
+    /// Per-port linear gain
+    pub gains: Vec<f32>,
+}
+
+/// Plugin enable state in a scene (matched by bus + chain position + plugin_id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenePluginDto {
+    pub bus: String,
+    pub index: usize,
+    pub plugin_id: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneDto {
+    pub name: String,
+    /// Unix time (seconds)
+    pub saved_at: u64,
+    pub edges: Vec<SceneEdgeDto>,
+    #[serde(default)]
+    pub outputs: Vec<SceneOutputDto>,
+    #[serde(default)]
+    pub plugins: Vec<ScenePluginDto>,
+}
+
+/// Scene list entry (without parameters)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneInfoDto {
+    pub name: String,
+    pub saved_at: u64,
+    pub edge_count: usize,
+}
+
+/// On-disk scene store (`scenes.json`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SceneStoreDto {
+    pub version: u32,
+    pub scenes: Vec<SceneDto>,
+}
+
+/// Result of recalling a scene
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneRecallDto {
+    pub name: String,
+    pub applied_edges: usize,
+    /// Scene edges whose nodes/edge no longer exist in the graph
+    pub missing_edges: usize,
+    pub fade_ms: u32,
+}
+
+// =============================================================================
+// Channel Template DTOs
+// =============================================================================
+
+/// Default send of a channel-strip template (target referenced by stable_id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateSendDto {
+    pub target: String,
+    /// Ports and parameters of the send (`id`, `source` and `target` are not used)
+    pub edge: EdgeInfoDto,
+}
+
+/// Saved bus configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelTemplateDto {
+    pub name: String,
+    /// Unix time (seconds)
+    pub saved_at: u64,
+    /// Label of the captured bus (default label of new buses)
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    pub port_count: u8,
+    /// Plugin chain including parameter states
+    #[serde(default)]
+    pub plugins: Vec<PluginInstanceDto>,
+    #[serde(default)]
+    pub sends: Vec<TemplateSendDto>,
+}
+
+/// Template list entry (without the chain)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelTemplateInfoDto {
+    pub name: String,
+    pub saved_at: u64,
+    pub port_count: u8,
+    /// Plugin names in chain order
+    pub plugins: Vec<String>,
+    pub send_count: usize,
+}
+
+impl From<&ChannelTemplateDto> for ChannelTemplateInfoDto {
+    fn from(template: &ChannelTemplateDto) -> Self {
+        Self {
+            name: template.name.clone(),
+            saved_at: template.saved_at,
+            port_count: template.port_count,
+            plugins: template.plugins.iter().map(|p| p.name.clone()).collect(),
+            send_count: template.sends.len(),
+        }
+    }
+}
+
+/// On-disk template store (`templates.json`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChannelTemplateStoreDto {
+    pub version: u32,
+    pub templates: Vec<ChannelTemplateDto>,
+}
+
+/// Result of `create_bus_from_template`
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInstanceDto {
+    pub handle: NodeHandle,
+    /// Sends that were connected
+    pub edges: Vec<EdgeId>,
+    /// Plugins that could not be created (not installed or failed to load)
+    pub skipped_plugins: Vec<String>,
+    /// Send targets (stable_id) that are not in the graph
+    pub missing_targets: Vec<String>,
+}
+
+// =============================================================================
+// Event DTOs (push 通知)
+// =============================================================================
+
+/// Graph change notification (emitted as `graph-changed`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GraphEventDto {
+    #[serde(rename = "node_added")]
+    NodeAdded { handle: NodeHandle },
+    #[serde(rename = "node_removed")]
+    NodeRemoved { handle: NodeHandle },
+    #[serde(rename = "node_changed")]
+    NodeChanged { handle: NodeHandle },
+    #[serde(rename = "edge_added")]
+    EdgeAdded { id: EdgeId },
+    #[serde(rename = "edge_removed")]
+    EdgeRemoved { id: EdgeId },
+    #[serde(rename = "edge_changed")]
+    EdgeChanged {
+        id: EdgeId,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        gain: Option<f32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        muted: Option<bool>,
+    },
+    /// Solo state or mode changed
+    #[serde(rename = "solo_changed")]
+    SoloChanged,
+    /// Cue sink or cue sends changed
+    #[serde(rename = "cue_changed")]
+    CueChanged,
+    /// The whole graph was replaced (load / restore)
+    #[serde(rename = "graph_reloaded")]
+    GraphReloaded,
+}
+
+// =============================================================================
+// State DTOs (永続化用)
+// =============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodePosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanvasTransformDto {
+    pub x: f32,
+    pub y: f32,
+    pub scale: f32,
 }
 
 fn is_empty_map_kv<K, V>(m: &HashMap<K, V>) -> bool {
@@ -270,6 +1520,227 @@ pub struct GraphStateDto {
     pub ui_state: Option<UIStateDto>,
 }
 
+/// Backup of graph_state.json (written before each save)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateBackupDto {
+    /// File name (pass to `recover_state_from_backup`)
+    pub name: String,
+    /// When the backup was taken (unix ms)
+    pub created_at_ms: u64,
+    pub size_bytes: u64,
+    /// False if the file can no longer be parsed
+    pub readable: bool,
+    pub node_count: usize,
+    pub edge_count: usize,
+}
+
+/// Autosave left behind by a session that did not exit cleanly
+#[derive(Debug, Clone, Serialize)]
+pub struct AutosaveRecoveryDto {
+    /// Unix ms
+    pub autosaved_at_ms: u64,
+    /// When graph_state.json was last written (unix ms; None if never)
+    pub last_saved_at_ms: Option<u64>,
+    pub node_count: usize,
+    pub edge_count: usize,
+}
+
+/// Kind of problem found in a graph state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StateIssueKind {
+    /// Two nodes with the same stable_id (the later one is merged into the first)
+    DuplicateStableId,
+    /// Two different nodes with the same handle (the later one gets a new handle)
+    DuplicateHandle,
+    /// Edge whose source or target node does not exist (dropped)
+    DanglingEdge,
+    /// Second edge between the same ports (dropped)
+    DuplicateEdge,
+    /// Edge port past the node's port count (narrowed or dropped)
+    PortOutOfRange,
+    /// Plugin that is not installed (left in place; skipped on load)
+    MissingPlugin,
+}
+
+/// One problem found in a graph state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateIssueDto {
+    pub kind: StateIssueKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<NodeHandle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge: Option<EdgeId>,
+    /// `repair_state` fixes it
+    pub repairable: bool,
+}
+
+/// Result of `check_state_integrity`
+#[derive(Debug, Clone, Serialize)]
+pub struct StateIntegrityReportDto {
+    pub version: u32,
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub issues: Vec<StateIssueDto>,
+}
+
+/// Result of `repair_state`
+#[derive(Debug, Clone, Serialize)]
+pub struct StateRepairReportDto {
+    /// Issues fixed (and written back to graph_state.json)
+    pub fixed: Vec<StateIssueDto>,
+    /// Issues left alone (not safe to fix automatically)
+    pub remaining: Vec<StateIssueDto>,
+}
+
+/// Kind of recorded graph mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationKind {
+    NodeAdded,
+    NodeRemoved,
+    EdgeAdded,
+    EdgeRemoved,
+    /// Edge gain moved by more than the logging threshold
+    EdgeGain,
+    EdgeMute,
+    PluginAdded,
+    PluginRemoved,
+    /// The whole graph was replaced (load / restore / apply)
+    GraphReloaded,
+}
+
+/// One entry of the mutation history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MutationEntryDto {
+    /// Unix ms
+    pub timestamp_ms: u64,
+    /// Where the change came from: "ui", "http", "hotkey", "url", "tray" or "engine"
+    pub actor: String,
+    pub kind: MutationKind,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node: Option<NodeHandle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edge: Option<EdgeId>,
+}
+
+// =============================================================================
+// Project DTOs
+// =============================================================================
+
+/// Audio device a project was saved with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceRequirementDto {
+    /// Device ID at save time (node device ids in the project refer to this)
+    pub device_id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    pub name: String,
+    /// Input channels used by source nodes (0 = not used for input)
+    #[serde(default)]
+    pub input_channels: u32,
+    /// Output channels used by sink nodes (0 = not used for output)
+    #[serde(default)]
+    pub output_channels: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMetadataDto {
+    pub name: String,
+    /// Unix ms
+    pub created_at_ms: u64,
+    /// Unix ms
+    pub modified_at_ms: u64,
+    #[serde(default)]
+    pub devices: Vec<DeviceRequirementDto>,
+}
+
+/// Project file contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectFileDto {
+    /// Always "spectrum-project"
+    pub format: String,
+    /// Project file format version (the graph has its own `state.version`)
+    pub version: u32,
+    pub metadata: ProjectMetadataDto,
+    pub state: GraphStateDto,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectInfoDto {
+    pub path: String,
+    #[serde(flatten)]
+    pub metadata: ProjectMetadataDto,
+}
+
+/// Result of `open_project`
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenProjectDto {
+    pub project: ProjectInfoDto,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ui_state: Option<UIStateDto>,
+    /// Devices that are not connected; their nodes stay in the graph as
+    /// placeholders and are rebound when the device appears
+    pub missing_devices: Vec<DeviceRequirementDto>,
+}
+
+/// Exported graph (portable between machines)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedGraphDto {
+    /// Always "spectrum-graph"
+    pub format: String,
+    pub version: u32,
+    /// Unix ms
+    pub exported_at_ms: u64,
+    /// `device_id` here is a 1-based index that nodes in `state` refer to
+    pub devices: Vec<DeviceRequirementDto>,
+    pub state: GraphStateDto,
+}
+
+/// Connected device that can stand in for a saved one
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceCandidateDto {
+    pub uid: String,
+    pub name: String,
+    pub input_channels: u32,
+    pub output_channels: u32,
+}
+
+/// How a device of an imported graph was matched
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceRemapDto {
+    /// Key for `device_mapping` (saved UID, or name if it had none)
+    pub key: String,
+    pub saved: DeviceRequirementDto,
+    /// Device now used; None = not connected (nodes are placeholders)
+    pub device_id: Option<u32>,
+    pub device_name: Option<String>,
+    /// Connected devices with enough channels to replace it
+    pub candidates: Vec<DeviceCandidateDto>,
+}
+
+/// Result of `import_graph`
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportGraphDto {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ui_state: Option<UIStateDto>,
+    pub devices: Vec<DeviceRemapDto>,
+    /// Number of devices that are not connected
+    pub missing: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecentProjectDto {
+    pub path: String,
+    pub name: String,
+    /// Unix ms
+    pub opened_at_ms: u64,
+    /// False if the file was moved or deleted
+    pub exists: bool,
+}
+
 // =============================================================================
 // System DTOs
 // =============================================================================
@@ -279,9 +1750,482 @@ pub struct SystemStatusDto {
     pub audio_running: bool,
     pub sample_rate: u32,
     pub buffer_size: u32,
+    /// Highest average render load across output devices (1.0 = full buffer period)
     pub cpu_load: f32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadStatsDto {
+    /// Last callback (ratio of the buffer period)
+    pub last: f32,
+    /// Rolling average
+    pub average: f32,
+    /// Peak since start or last reset
+    pub peak: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceLoadDto {
+    pub device_id: u32,
+    pub callbacks: u64,
+    #[serde(flatten)]
+    pub load: LoadStatsDto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeLoadDto {
+    pub handle: NodeHandle,
+    pub label: String,
+    #[serde(flatten)]
+    pub load: LoadStatsDto,
+}
+
+/// Render time of one plugin in a bus chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginLoadDto {
+    pub bus_handle: NodeHandle,
+    pub bus_label: String,
+    pub instance_id: String,
+    pub name: String,
+    /// Position in the chain (0 = first)
+    pub index: usize,
+    #[serde(flatten)]
+    pub load: LoadStatsDto,
+    /// Buffer period the ratios refer to (ms)
+    pub budget_ms: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XrunStatsDto {
+    pub device_id: u32,
+    pub device_name: String,
+    /// Capture ring buffer had fewer frames than requested
+    pub underruns: u64,
+    /// Reader lagged far enough that unread frames were overwritten
+    pub overruns: u64,
+    /// Output callback exceeded the buffer period
+    pub late_callbacks: u64,
+}
+
+/// Payload of the `xrun` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XrunEventDto {
+    /// Dropouts since the previous event
+    pub new_xruns: u64,
+    pub devices: Vec<XrunStatsDto>,
+}
+
+/// An out-of-process plugin died (it is bypassed until restarted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginCrashEventDto {
+    pub instance_id: String,
+    pub name: String,
+    /// Buses with the plugin in their chain, or the plugin source node running it
+    pub bus_handles: Vec<NodeHandle>,
+}
+
+/// A plugin kept overrunning its render budget (it is bypassed until restarted)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginOverloadEventDto {
+    pub instance_id: String,
+    pub name: String,
+    /// Buses with the plugin in their chain, or the plugin source node running it
+    pub bus_handles: Vec<NodeHandle>,
+    /// Duration of the last slow render (ms)
+    pub render_ms: f32,
+}
+
+/// A sink's output protection tripped (the output stays dimmed until re-armed)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputProtectionEventDto {
+    pub handle: NodeHandle,
+    pub label: String,
+    /// Highest level while the spike lasted (dBFS)
+    pub peak_db: f32,
+    /// Dim applied (dB)
+    pub dim_db: f32,
+}
+
+/// A source auto-gate closed or opened (`set_source_auto_gate`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceGateEventDto {
+    pub handle: NodeHandle,
+    pub label: String,
+    /// true = outgoing edges muted, false = restored
+    pub gated: bool,
+    /// "app_gone" | "silent" | "returned"
+    pub reason: String,
+    /// Edges that were muted or unmuted
+    pub edges: Vec<EdgeId>,
+}
+
+/// Payload of the `engine-watchdog` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogEventDto {
+    /// "output" | "capture" | "processor"
+    pub component: String,
+    /// Device of the runtime (None for the processor)
+    pub device_id: Option<u32>,
+    pub device_name: Option<String>,
+    /// How long the runtime had been silent (or the lock unavailable)
+    pub stalled_ms: u64,
+    /// "restarted" | "restart_failed" | "gave_up" | "reported"
+    pub action: String,
+    pub error: Option<String>,
+}
+
+/// A Prism client landed on a channel pair that has no source node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrismAppDetectedDto {
+    pub pid: u32,
+    pub name: String,
+    /// Stereo pair index (same as `SourceIdDto::PrismChannel`)
+    pub channel: u8,
+    /// Source node created for it ("auto" policy); None = the frontend may offer one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub handle: Option<NodeHandle>,
+}
+
+/// Payload of the `device-changed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceChangeEventDto {
+    /// "connected" | "disconnected" | "default-output" | "default-input" | "channels-changed"
+    pub kind: String,
+    pub device_id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_uid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub input_channels: u32,
+    pub output_channels: u32,
+    /// "none" | "paused" | "resumed" | "resized" | "retargeted"
+    pub action: String,
+    /// Graph nodes moved to the device's new ID on resume, resized source nodes,
+    /// or sinks that followed the new default output
+    pub rebound_nodes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DspProfileDto {
+    /// Render callback load per output device
+    pub devices: Vec<DeviceLoadDto>,
+    /// Processing load per node (buses include their plugin chain)
+    pub nodes: Vec<NodeLoadDto>,
+}
+
+/// Engine statistics since startup or the last reset (`get_session_stats`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatsDto {
+    /// Unix time of the session start (ms)
+    pub started_at_ms: u64,
+    pub uptime_secs: f64,
+    /// Dropouts of every device (underruns, overruns, late callbacks)
+    pub dropouts: u64,
+    /// Plugins that failed to instantiate or configure
+    pub plugin_failures: u64,
+    /// Highest render callback load of any device (1.0 = deadline)
+    pub peak_load: f32,
+    /// Output devices that rendered in this session (including stopped ones)
+    pub devices: Vec<DeviceSessionStatsDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceSessionStatsDto {
+    pub device_id: u32,
+    /// None when the device is no longer present
+    pub device_name: Option<String>,
+    pub frames_rendered: u64,
+    pub callbacks: u64,
+    pub peak_load: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntryDto {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    /// "error" | "warn" | "info" | "debug" | "trace"
+    pub level: String,
+    /// Module path (e.g. "audio::output")
+    pub target: String,
+    pub message: String,
+}
+
+impl From<crate::logging::LogEntry> for LogEntryDto {
+    fn from(e: crate::logging::LogEntry) -> Self {
+        Self {
+            timestamp_ms: e.timestamp_ms,
+            level: e.level.as_str().to_string(),
+            target: e.target,
+            message: e.message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFilterDto {
+    /// Module prefix
+    pub target: String,
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogLevelsDto {
+    /// Level for modules without a filter
+    pub level: String,
+    /// Per-module filters (longest prefix wins)
+    pub modules: Vec<LogFilterDto>,
+    /// Current log file, if file output is active
+    pub file: Option<String>,
+}
+
+/// Saved report of a panic or fatal engine fault (see `get_last_crash_report`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReportDto {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    /// "panic" | "processor_stall" | "output_stall" | "capture_stall"
+    pub kind: String,
+    pub app_version: String,
+    /// OS and architecture (e.g. "macos aarch64")
+    pub platform: String,
+    /// Name of the thread that failed (None for unnamed threads, e.g. CoreAudio IO threads)
+    pub thread: Option<String>,
+    pub message: String,
+    /// Source location of a panic
+    pub location: Option<String>,
+    pub backtrace: Option<String>,
+    /// None when the engine state could not be read in time
+    pub engine: Option<EngineSnapshotDto>,
+    /// Last log lines (oldest first)
+    pub logs: Vec<LogEntryDto>,
+}
+
+/// Engine state at the time of a crash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineSnapshotDto {
+    pub sample_rate: f64,
+    /// Global I/O buffer size (frames)
+    pub io_buffer_size: u32,
+    /// None when the graph lock was held
+    pub graph: Option<GraphSummaryDto>,
+    pub outputs: Vec<CrashDeviceDto>,
+    pub inputs: Vec<CrashDeviceDto>,
+}
+
+/// Shape of the graph without labels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphSummaryDto {
+    pub node_count: usize,
+    pub edge_count: usize,
+    /// "source" | "bus" | "sink" | "record" -> count
+    pub nodes_by_type: BTreeMap<String, usize>,
+    /// Plugins in bus chains
+    pub plugin_count: usize,
+}
+
+/// A running device in a crash report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashDeviceDto {
+    pub device_id: u32,
+    pub name: String,
+    pub channels: u32,
+    /// I/O buffer size (frames)
+    pub buffer_size: u32,
+    pub sample_rate: Option<f64>,
+}
+
+/// Result of a live buffer size change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferSizeDto {
+    /// Requested size after clamping (frames)
+    pub buffer_size: u32,
+    /// Running devices that were reconfigured
+    pub devices: Vec<DeviceBufferSizeDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceBufferSizeDto {
+    pub device_id: u32,
+    pub device_name: String,
+    /// Size the device reports now (may differ if it rejected the request)
+    pub actual: Option<u32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleRateDto {
+    /// Engine sample rate (Hz)
+    pub sample_rate: u32,
+    /// Rates the engine can run at
+    pub supported: Vec<u32>,
+    /// Active output device nominal rate (None if no output is running)
+    pub output_device_rate: Option<u32>,
+    /// Rates the active output device supports
+    pub output_device_supported: Vec<u32>,
+}
+
+/// One running output device
+/// Ring buffer health (`get_buffer_diagnostics`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferDiagnosticsDto {
+    /// Capture rings (one per input device, plus the legacy Prism ring)
+    pub inputs: Vec<CaptureRingDto>,
+    /// Output runtimes (secondary devices read from a drift FIFO)
+    pub outputs: Vec<OutputRuntimeDto>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRingDto {
+    pub device_id: u32,
+    pub device_name: String,
+    /// Legacy Prism ring shared by `start_capture`
+    pub legacy: bool,
+    pub running: bool,
+    /// Ring size per channel (frames)
+    pub capacity_frames: u32,
+    /// Device totals (same as `get_xrun_stats`)
+    pub underruns: u64,
+    pub overruns: u64,
+    pub readers: Vec<RingReaderDto>,
+}
+
+/// One consumer of a capture ring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingReaderDto {
+    /// Output device reading the ring (None = the graph processor)
+    pub output_device_id: Option<u32>,
+    /// Frames available at the last read
+    pub fill_frames: u32,
+    /// Lowest fill since the last reset (None = not read yet)
+    pub min_fill_frames: Option<u32>,
+    pub underruns: u64,
+    pub overruns: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputRuntimeDto {
+    pub device_id: u32,
+    pub device_name: String,
+    /// This device's callback processes the graph
+    pub is_clock_master: bool,
+    /// Frames buffered from the clock master (secondary devices)
+    pub fifo_fill: u32,
+    /// Frames dropped/repeated to follow the master clock
+    pub drift_corrections: u64,
+    pub fifo_underruns: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockSourceDto {
+    pub id: u32,
+    pub name: String,
+}
+
+/// Output device clock: nominal rate, rate policy and clock source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceClockInfoDto {
+    pub device_id: u32,
+    pub nominal_sample_rate: Option<f64>,
+    pub available_sample_rates: Vec<f64>,
+    /// "engine" | "follow" | "fixed"
+    pub rate_policy: String,
+    /// Pinned rate when `rate_policy` is "fixed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixed_sample_rate: Option<f64>,
+    /// Current clock source (None if the device has no selectable source)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_source: Option<u32>,
+    pub clock_sources: Vec<ClockSourceDto>,
+}
+
+/// Persistent engine settings (settings.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsDto {
+    /// Output device started on launch (None = aggregate device / system default)
+    pub preferred_output_device_uid: Option<String>,
+    /// I/O buffer size on launch (frames)
+    pub buffer_size: u32,
+    /// Engine sample rate on launch (None = 48 kHz)
+    pub sample_rate: Option<u32>,
+    /// "engine" | "follow" (devices without their own policy)
+    pub rate_policy: String,
+    /// Push meter stream rate in Hz (0 = off)
+    pub meter_rate_hz: u32,
+    /// "off" | "summary" | "verbose" (None = build default)
+    pub log_level: Option<String>,
+    /// "auto" | "ask" | "off" (new Prism clients)
+    pub prism_auto_source: String,
+    /// Per-device buffer size overrides (device UID -> frames)
+    #[serde(default)]
+    pub device_buffer_sizes: BTreeMap<String, u32>,
+    /// Delay faster outputs to match the slowest device's latency
+    #[serde(default)]
+    pub align_output_latency: bool,
+}
+
+/// Global shortcut binding (stored in settings.json)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyBindingDto {
+    /// Accelerator, e.g. "CmdOrCtrl+Shift+M"
+    pub shortcut: String,
+    pub action: HotkeyActionDto,
+}
+
+/// What a global shortcut does
+///
+/// ノードは stable_id で参照する（再起動でハンドルが変わってもバインドが有効）。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HotkeyActionDto {
+    /// Toggle mute on one edge
+    ToggleEdgeMute {
+        source: String,
+        source_port: PortId,
+        target: String,
+        target_port: PortId,
+    },
+    /// Toggle mute on every send leaving a node
+    ToggleNodeMute { node: String },
+    /// Nudge an edge gain by `db` (e.g. +1.0 / -1.0)
+    NudgeEdgeGain {
+        source: String,
+        source_port: PortId,
+        target: String,
+        target_port: PortId,
+        db: f32,
+    },
+    /// Nudge an output (sink) master gain by `db`
+    NudgeOutputGain { node: String, db: f32 },
+    /// Recall a saved scene
+    RecallScene {
+        name: String,
+        #[serde(default)]
+        fade_ms: u32,
+    },
+    /// Drop a time-stamped marker (highlight)
+    AddMarker {
+        #[serde(default)]
+        label: Option<String>,
+    },
+}
+
+/// HTTP control API configuration and state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiStatusDto {
+    pub enabled: bool,
+    /// Listening right now
+    pub running: bool,
+    /// Listen address while running ("127.0.0.1:17380")
+    pub address: Option<String>,
+    pub port: u16,
+    pub allow_lan: bool,
+    /// Advertised over Bonjour (`_spectrum._tcp`) while listening on the LAN
+    #[serde(default)]
+    pub advertised: bool,
+    /// Bearer token for `Authorization: Bearer <token>`
+    pub token: Option<String>,
+}
+
 // =============================================================================
 // Conversions
 // =============================================================================
@@ -312,6 +2256,30 @@ impl From<SourceIdDto> for crate::audio::source::SourceId {
     }
 }
 
+impl From<crate::prismd::ProcessInfo> for PrismAppDto {
+    fn from(p: crate::prismd::ProcessInfo) -> Self {
+        PrismAppDto {
+            pid: p.pid,
+            name: p.name,
+            channel_offset: (p.channel_offset / 2) as u8, // Convert to stereo pair index
+        }
+    }
+}
+
+impl PrismStatusDto {
+    /// Status for a connection state and client list
+    pub fn new(connected: bool, clients: Vec<crate::prismd::ClientInfo>) -> Self {
+        PrismStatusDto {
+            connected,
+            channels: if connected { 64 } else { 0 },
+            apps: clients
+                .into_iter()
+                .map(|c| PrismAppDto::from(crate::prismd::ProcessInfo::from(c)))
+                .collect(),
+        }
+    }
+}
+
 impl From<crate::audio::sink::SinkId> for OutputSinkDto {
     fn from(sink: crate::audio::sink::SinkId) -> Self {
         OutputSinkDto {
@@ -319,6 +2287,8 @@ impl From<crate::audio::sink::SinkId> for OutputSinkDto {
             channel_offset: sink.channel_offset,
             channel_count: sink.channel_count,
             device_uid: sink.device_uid,
+            channel_map: sink.channel_map,
+            follow_default: sink.follow_default,
         }
     }
 }
@@ -330,6 +2300,8 @@ impl From<OutputSinkDto> for crate::audio::sink::SinkId {
             channel_offset: dto.channel_offset,
             channel_count: dto.channel_count,
             device_uid: dto.device_uid,
+            channel_map: dto.channel_map,
+            follow_default: dto.follow_default,
         }
     }
 }
@@ -344,6 +2316,42 @@ impl From<crate::audio::Edge> for EdgeInfoDto {
             target_port: edge.target_port.into(),
             gain: edge.gain(),
             muted: edge.muted(),
+            channels: edge.channels,
+            trims: {
+                let trims = edge.channel_trims();
+                if trims.iter().all(|&t| t == 1.0) {
+                    Vec::new()
+                } else {
+                    trims
+                }
+            },
+            pan: edge.pan(),
+            pan_law: edge.pan_law().as_str().to_string(),
+            sum_law: edge.sum_law().as_str().to_string(),
+            monitor_mode: edge.monitor_mode().as_str().to_string(),
+            tap_point: edge.tap_point().as_str().to_string(),
+            pinned: edge.pinned(),
+        }
+    }
+}
+
+impl From<crate::audio::dsp_load::LoadSnapshot> for LoadStatsDto {
+    fn from(s: crate::audio::dsp_load::LoadSnapshot) -> Self {
+        LoadStatsDto {
+            last: s.last,
+            average: s.average,
+            peak: s.peak,
+        }
+    }
+}
+
+impl From<crate::audio::PortMeter> for PortMeterDto {
+    fn from(p: crate::audio::PortMeter) -> Self {
+        PortMeterDto {
+            peak: p.peak,
+            rms: p.rms,
+            hold: p.hold,
+            clip: p.clip,
         }
     }
 }
@@ -356,22 +2364,8 @@ impl From<crate::audio::GraphMeters> for GraphMetersDto {
                 .into_iter()
                 .map(|m| NodeMeterDto {
                     handle: m.handle.raw(),
-                    inputs: m
-                        .inputs
-                        .into_iter()
-                        .map(|p| PortMeterDto {
-                            peak: p.peak,
-                            rms: p.rms,
-                        })
-                        .collect(),
-                    outputs: m
-                        .outputs
-                        .into_iter()
-                        .map(|p| PortMeterDto {
-                            peak: p.peak,
-                            rms: p.rms,
-                        })
-                        .collect(),
+                    inputs: m.inputs.into_iter().map(PortMeterDto::from).collect(),
+                    outputs: m.outputs.into_iter().map(PortMeterDto::from).collect(),
                 })
                 .collect(),
             edges: meters
@@ -379,13 +2373,93 @@ impl From<crate::audio::GraphMeters> for GraphMetersDto {
                 .into_iter()
                 .map(|m| EdgeMeterDto {
                     edge_id: m.edge_id.raw(),
-                    post_gain: PortMeterDto {
-                        peak: m.post_gain.peak,
-                        rms: m.post_gain.rms,
-                    },
+                    post_gain: PortMeterDto::from(m.post_gain),
                 })
                 .collect(),
             timestamp: meters.timestamp,
         }
     }
 }
+
+impl RecordingStatusDto {
+    pub fn from_status(
+        handle: NodeHandle,
+        status: crate::audio::record::RecordingStatus,
+        sample_rate: f64,
+    ) -> Self {
+        RecordingStatusDto {
+            handle,
+            recording: status.recording,
+            path: status.path.map(|p| p.display().to_string()),
+            frames_written: status.frames_written,
+            dropped_frames: status.dropped_frames,
+            duration_secs: if sample_rate > 0.0 {
+                status.frames_written as f64 / sample_rate
+            } else {
+                0.0
+            },
+            start_frame: status.start_frame,
+            stop_frame: status.stop_frame,
+            error: status.error,
+        }
+    }
+}
+
+impl From<crate::audio::bounce::BounceReport> for BounceResultDto {
+    fn from(report: crate::audio::bounce::BounceReport) -> Self {
+        BounceResultDto {
+            path: report.path.display().to_string(),
+            channels: report.channels,
+            frames: report.frames,
+            duration_secs: if report.sample_rate > 0.0 {
+                report.frames as f64 / report.sample_rate
+            } else {
+                0.0
+            },
+            latency_frames: report.latency_frames,
+            cancelled: report.cancelled,
+        }
+    }
+}
+
+impl NetStatusDto {
+    pub fn from_status(handle: NodeHandle, status: crate::audio::net::NetStatus) -> Self {
+        NetStatusDto {
+            handle,
+            packets: status.packets,
+            lost_packets: status.lost_packets,
+            late_packets: status.late_packets,
+            bad_packets: status.bad_packets,
+            dropped_frames: status.dropped_frames,
+            underruns: status.underruns,
+            buffered_frames: status.buffered_frames as u32,
+            error: status.error,
+        }
+    }
+}
+
+impl From<crate::permission::PermissionStatus> for AudioPermissionDto {
+    fn from(status: crate::permission::PermissionStatus) -> Self {
+        Self {
+            status: status.as_str().to_string(),
+            can_capture: status.allows_capture(),
+            can_request: status.can_request(),
+        }
+    }
+}
+
+impl From<crate::settings::Settings> for SettingsDto {
+    fn from(s: crate::settings::Settings) -> Self {
+        Self {
+            preferred_output_device_uid: s.preferred_output_device_uid,
+            buffer_size: s.buffer_size,
+            sample_rate: s.sample_rate,
+            rate_policy: s.rate_policy,
+            meter_rate_hz: s.meter_rate_hz,
+            log_level: s.log_level.map(|l| l.as_str().to_string()),
+            prism_auto_source: s.prism_auto_source.as_str().to_string(),
+            device_buffer_sizes: s.device_buffer_sizes,
+            align_output_latency: s.align_output_latency,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Structured command errors
+//!
+//! Tauri コマンドのエラーはフロントエンドに `{ code, message, context }` として届く。
+//! UI は `code` で分岐し、`message` はそのまま表示できる。
+//! 下位モジュールの `String` エラーは `?` で `Other` に変換される。
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::{json, Value};
+
+/// Error returned by every `api` command
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpectrumError {
+    /// Audio device id is unknown or the device disappeared
+    DeviceNotFound(u32),
+    /// Device cannot be used right now (e.g. it is an active output)
+    DeviceBusy { device_id: u32, reason: String },
+    /// Microphone access is denied or restricted ("denied" / "restricted")
+    PermissionDenied {
+        device_id: u32,
+        status: &'static str,
+    },
+    /// Graph node handle does not exist
+    NodeNotFound(u32),
+    /// Node exists but is not of the expected kind ("bus", "sink", ...)
+    WrongNodeType { handle: u32, expected: &'static str },
+    /// Node has no such port
+    PortNotFound { handle: u32, port: u8 },
+    /// Edge id does not exist
+    EdgeNotFound(u32),
+    /// The edge would close a loop in the graph
+    GraphCycle { source: u32, target: u32 },
+    /// The edge overlaps an existing edge (or has an invalid channel count)
+    EdgeConflict { source: u32, target: u32 },
+    /// Plugin id is not in the registry
+    PluginNotFound(String),
+    /// Plugin instance id is unknown (or not in the given bus)
+    PluginInstanceNotFound(String),
+    /// The AudioUnit could not be instantiated
+    PluginInstantiationFailed { plugin_id: String, reason: String },
+    /// Scene name is unknown
+    SceneNotFound(String),
+    /// Channel template name is unknown
+    TemplateNotFound(String),
+    /// A parameter is out of range or cannot be parsed
+    InvalidArgument(String),
+    /// The operation is not possible in the current state
+    InvalidState(String),
+    /// Reading/writing a file in the app data directory failed
+    Storage(String),
+    /// Waiting for the main thread or a worker timed out
+    Timeout(String),
+    /// Not available on this platform / build
+    Unsupported(String),
+    /// Error from a lower layer without a more specific kind
+    Other(String),
+}
+
+impl SpectrumError {
+    /// Stable identifier the frontend can branch on
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DeviceNotFound(_) => "device_not_found",
+            Self::DeviceBusy { .. } => "device_busy",
+            Self::PermissionDenied { .. } => "permission_denied",
+            Self::NodeNotFound(_) => "node_not_found",
+            Self::WrongNodeType { .. } => "wrong_node_type",
+            Self::PortNotFound { .. } => "port_not_found",
+            Self::EdgeNotFound(_) => "edge_not_found",
+            Self::GraphCycle { .. } => "graph_cycle",
+            Self::EdgeConflict { .. } => "edge_conflict",
+            Self::PluginNotFound(_) => "plugin_not_found",
+            Self::PluginInstanceNotFound(_) => "plugin_instance_not_found",
+            Self::PluginInstantiationFailed { .. } => "plugin_instantiation_failed",
+            Self::SceneNotFound(_) => "scene_not_found",
+            Self::TemplateNotFound(_) => "template_not_found",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::InvalidState(_) => "invalid_state",
+            Self::Storage(_) => "storage",
+            Self::Timeout(_) => "timeout",
+            Self::Unsupported(_) => "unsupported",
+            Self::Other(_) => "other",
+        }
+    }
+
+    /// Machine-readable details (ids etc.), Null if there are none
+    pub fn context(&self) -> Value {
+        match self {
+            Self::DeviceNotFound(device_id) => json!({ "device_id": device_id }),
+            Self::DeviceBusy { device_id, .. } => json!({ "device_id": device_id }),
+            Self::PermissionDenied { device_id, status } => {
+                json!({ "device_id": device_id, "status": status })
+            }
+            Self::NodeNotFound(handle) => json!({ "handle": handle }),
+            Self::WrongNodeType { handle, expected } => {
+                json!({ "handle": handle, "expected": expected })
+            }
+            Self::PortNotFound { handle, port } => json!({ "handle": handle, "port": port }),
+            Self::EdgeNotFound(id) => json!({ "edge_id": id }),
+            Self::GraphCycle { source, target } | Self::EdgeConflict { source, target } => {
+                json!({ "source": source, "target": target })
+            }
+            Self::PluginNotFound(plugin_id) => json!({ "plugin_id": plugin_id }),
+            Self::PluginInstanceNotFound(instance_id) => json!({ "instance_id": instance_id }),
+            Self::PluginInstantiationFailed { plugin_id, .. } => {
+                json!({ "plugin_id": plugin_id })
+            }
+            Self::SceneNotFound(name) | Self::TemplateNotFound(name) => json!({ "name": name }),
+            _ => Value::Null,
+        }
+    }
+}
+
+impl std::fmt::Display for SpectrumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DeviceNotFound(id) => write!(f, "Device {} not found", id),
+            Self::DeviceBusy { reason, .. } => write!(f, "{}", reason),
+            Self::PermissionDenied { .. } => write!(
+                f,
+                "Microphone access is not allowed; enable Spectrum in System Settings > Privacy & Security > Microphone"
+            ),
+            Self::NodeNotFound(handle) => write!(f, "Node {} not found", handle),
+            Self::WrongNodeType { handle, expected } => {
+                write!(f, "Node {} is not a {} node", handle, expected)
+            }
+            Self::PortNotFound { handle, port } => {
+                write!(f, "Node {} has no port {}", handle, port)
+            }
+            Self::EdgeNotFound(id) => write!(f, "Edge {} not found", id),
+            Self::GraphCycle { source, target } => write!(
+                f,
+                "Connecting node {} to node {} would create a feedback loop",
+                source, target
+            ),
+            Self::EdgeConflict { source, target } => write!(
+                f,
+                "Edge {} -> {} overlaps an existing edge or has an invalid channel count",
+                source, target
+            ),
+            Self::PluginNotFound(id) => write!(f, "Plugin not found: {}", id),
+            Self::PluginInstanceNotFound(id) => write!(f, "Plugin instance not found: {}", id),
+            Self::PluginInstantiationFailed { plugin_id, reason } => {
+                write!(f, "Failed to load {}: {}", plugin_id, reason)
+            }
+            Self::SceneNotFound(name) => write!(f, "Scene not found: {}", name),
+            Self::TemplateNotFound(name) => write!(f, "Template not found: {}", name),
+            Self::InvalidArgument(msg)
+            | Self::InvalidState(msg)
+            | Self::Storage(msg)
+            | Self::Timeout(msg)
+            | Self::Unsupported(msg)
+            | Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for SpectrumError {}
+
+impl Serialize for SpectrumError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let context = self.context();
+        let mut s = serializer.serialize_struct("SpectrumError", 3)?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        if context.is_null() {
+            s.skip_field("context")?;
+        } else {
+            s.serialize_field("context", &context)?;
+        }
+        s.end()
+    }
+}
+
+impl From<String> for SpectrumError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<crate::capture::CaptureError> for SpectrumError {
+    fn from(error: crate::capture::CaptureError) -> Self {
+        match error {
+            crate::capture::CaptureError::PermissionDenied { device_id, status } => {
+                Self::PermissionDenied {
+                    device_id,
+                    status: status.as_str(),
+                }
+            }
+            crate::capture::CaptureError::Other(message) => Self::Other(message),
+        }
+    }
+}
+
+impl From<&str> for SpectrumError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+/// For helpers that still return `Result<_, String>` (e.g. scenes)
+impl From<SpectrumError> for String {
+    fn from(error: SpectrumError) -> Self {
+        error.to_string()
+    }
+}
@@ -0,0 +1,859 @@
+//! Push event stream for meters and graph changes
+//!
+//! フロントエンド（およびリモート UI）がポーリングせずに同期できるよう、
+//! Tauri のイベントとしてメーターとグラフ変更を配信する。
+//!
+//! - `graph-changed`: [`GraphEventDto`]（ノード/エッジの追加・削除・変更）
+//! - `meters`: [`GraphMetersDto`]（設定したレートで送信、0 で停止）
+//!
+//! メーターはこのほか [`subscribe_meters`] で購読ごとのレート・ノード/エッジのフィルタ付きの
+//! バイナリパケット（[`meter_packet`](crate::audio::meter_packet)）として Tauri チャンネルに送れる。
+//!
+//! - `xrun`: [`XrunEventDto`]（ドロップアウト発生時、最大 4 回/秒）
+//! - `device-changed`: [`DeviceChangeEventDto`]（デバイスの抜き差し・既定デバイス変更）
+//! - `plugin-crashed`: [`PluginCrashEventDto`]（別プロセスの AU が落ちたとき）
+//! - `plugin-overloaded`: [`PluginOverloadEventDto`]（レンダリングが予算を超え続けた AU を
+//!   バイパスしたとき）
+//! - `prism-connected` / `prism-disconnected` / `prism-clients-changed`: [`PrismStatusDto`]
+//!   （prismd の接続状態とクライアント一覧）
+//! - `prism-app-detected`: [`PrismAppDetectedDto`]（ソースのないペアに来たアプリ）
+//! - `engine-watchdog`: [`WatchdogEventDto`]（止まった出力/キャプチャの自動再起動、
+//!   グラフのロックが取れないとき）
+//! - `bounce-progress`: [`BounceProgressDto`]（オフラインレンダリングの進捗）
+//! - `output-protection`: [`OutputProtectionEventDto`]（出力保護が過大レベルで出力を下げたとき）
+//! - `source-gate`: [`SourceGateEventDto`](super::dto::SourceGateEventDto)
+//!   （アプリが居なくなった/無音の Prism ソースを自動ミュートしたとき、戻して解除したとき）
+
+use super::dto::{
+    BounceProgressDto, DeviceChangeEventDto, GraphEventDto, GraphMetersDto,
+    OutputProtectionEventDto, PluginCrashEventDto, PluginOverloadEventDto, PrismAppDetectedDto,
+    PrismStatusDto, WatchdogEventDto, XrunEventDto, XrunStatsDto,
+};
+use crate::audio::bus::BusNode;
+use crate::audio::meter_packet::{self, MeterFilter};
+use crate::audio::plugin_source::PluginSourceNode;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::sink::SinkNode;
+use crate::audio::source::{SourceId, SourceNode};
+use crate::audio::watchdog::{self, Component, StallDetector};
+use crate::device::{DeviceChange, RerouteAction};
+use crate::prismd::PrismEvent;
+use coreaudio::audio_unit::macos_helpers::get_device_name;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter};
+
+/// Event name for graph change notifications
+pub const GRAPH_CHANGED_EVENT: &str = "graph-changed";
+
+/// Event name for meter frames
+pub const METERS_EVENT: &str = "meters";
+
+/// Event name for dropout notifications
+pub const XRUN_EVENT: &str = "xrun";
+
+/// Event name for device hot-plug / default device notifications
+pub const DEVICE_CHANGED_EVENT: &str = "device-changed";
+
+/// Event name for crashed out-of-process plugins
+pub const PLUGIN_CRASHED_EVENT: &str = "plugin-crashed";
+
+/// Event name for plugins bypassed after repeated slow renders
+pub const PLUGIN_OVERLOADED_EVENT: &str = "plugin-overloaded";
+
+/// Event name for prismd becoming reachable (startup or daemon restart)
+pub const PRISM_CONNECTED_EVENT: &str = "prism-connected";
+
+/// Event name for prismd going away
+pub const PRISM_DISCONNECTED_EVENT: &str = "prism-disconnected";
+
+/// Event name for Prism client list changes (apps appearing/leaving, re-routing)
+pub const PRISM_CLIENTS_EVENT: &str = "prism-clients-changed";
+
+/// Event name for a Prism app on a channel pair without a source node
+pub const PRISM_APP_DETECTED_EVENT: &str = "prism-app-detected";
+
+/// Event name for watchdog recoveries
+pub const WATCHDOG_EVENT: &str = "engine-watchdog";
+
+/// Event name for offline render progress
+pub const BOUNCE_PROGRESS_EVENT: &str = "bounce-progress";
+
+/// Event name for sinks dimmed by their output protection
+pub const OUTPUT_PROTECTION_EVENT: &str = "output-protection";
+
+/// Event name for Prism sources muted or restored by their auto-gate
+pub const SOURCE_GATE_EVENT: &str = "source-gate";
+
+/// Xrun watcher poll interval
+const XRUN_POLL_MS: u64 = 250;
+
+/// Plugin crash/overload watcher poll interval
+const PLUGIN_WATCH_MS: u64 = 500;
+
+/// Output protection watcher poll interval
+const PROTECTION_POLL_MS: u64 = 100;
+
+/// Source auto-gate poll interval
+const SOURCE_GATE_POLL_MS: u64 = 250;
+
+/// Watchdog poll interval
+const WATCHDOG_POLL_MS: u64 = 500;
+
+/// Maximum meter stream rate (Hz)
+pub const MAX_METER_STREAM_RATE: u32 = 120;
+
+/// Meter stream rate in Hz (0 = disabled; frontend polls instead)
+static METER_STREAM_RATE: AtomicU32 = AtomicU32::new(0);
+
+/// Binary meter subscriptions kept at once (the oldest is dropped beyond this)
+const MAX_METER_SUBSCRIPTIONS: usize = 16;
+
+/// How often the meter stream re-checks its settings while idle
+const METER_IDLE_POLL: Duration = Duration::from_millis(100);
+
+/// A binary meter feed to a Tauri channel
+struct MeterSubscription {
+    id: u32,
+    channel: Channel,
+    filter: MeterFilter,
+    interval: Duration,
+    next_due: Instant,
+    last_timestamp: u64,
+}
+
+static METER_SUBSCRIPTIONS: Mutex<Vec<MeterSubscription>> = Mutex::new(Vec::new());
+
+static NEXT_SUBSCRIPTION_ID: AtomicU32 = AtomicU32::new(1);
+
+/// App handle used for emitting (set once in setup)
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// Initialize the event stream (call once from `setup`)
+pub fn init(app: AppHandle) {
+    if APP_HANDLE.set(app).is_err() {
+        return;
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-meter-stream".to_string())
+        .spawn(meter_stream_thread)
+    {
+        log_error!("[Events] Failed to start meter stream: {}", e);
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-xrun-watch".to_string())
+        .spawn(xrun_watch_thread)
+    {
+        log_error!("[Events] Failed to start xrun watcher: {}", e);
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-plugin-watch".to_string())
+        .spawn(plugin_watch_thread)
+    {
+        log_error!("[Events] Failed to start plugin watcher: {}", e);
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-protection-watch".to_string())
+        .spawn(protection_watch_thread)
+    {
+        log_error!("[Events] Failed to start output protection watcher: {}", e);
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-source-gate".to_string())
+        .spawn(source_gate_thread)
+    {
+        log_error!("[Events] Failed to start source gate watcher: {}", e);
+    }
+
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-watchdog".to_string())
+        .spawn(watchdog_thread)
+    {
+        log_error!("[Events] Failed to start watchdog: {}", e);
+    }
+
+    if let Err(e) = crate::device::start_device_monitor(emit_device_change) {
+        log_error!("[Events] Failed to start device monitor: {}", e);
+    }
+
+    if let Err(e) = crate::prismd::start_supervisor(emit_prism_event) {
+        log_error!("[Events] Failed to start prismd supervisor: {}", e);
+    }
+
+    log_info!("[Events] Event stream initialized");
+}
+
+/// App handle (None before `init`)
+pub(crate) fn app_handle() -> Option<&'static AppHandle> {
+    APP_HANDLE.get()
+}
+
+/// Emit a graph change event (no-op before `init`)
+///
+/// Every graph change also schedules an autosave and goes to the mutation history.
+pub fn emit_graph_event(event: GraphEventDto) {
+    super::autosave::mark_dirty();
+    super::history::record_event(&event);
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    if let Err(e) = app.emit(GRAPH_CHANGED_EVENT, event) {
+        log_error!("[Events] Failed to emit graph event: {}", e);
+    }
+}
+
+/// Set meter stream rate in Hz (0 disables streaming)
+pub fn set_meter_stream_rate(hz: u32) -> u32 {
+    let hz = hz.min(MAX_METER_STREAM_RATE);
+    METER_STREAM_RATE.store(hz, Ordering::Relaxed);
+    log_info!("[Events] Meter stream rate set to {} Hz", hz);
+    hz
+}
+
+/// Get meter stream rate in Hz
+pub fn meter_stream_rate() -> u32 {
+    METER_STREAM_RATE.load(Ordering::Relaxed)
+}
+
+/// Stream binary meter packets to `channel` at `hz` (clamped to 1..=MAX); returns the id
+pub fn subscribe_meters(channel: Channel, filter: MeterFilter, hz: u32) -> u32 {
+    let hz = hz.clamp(1, MAX_METER_STREAM_RATE);
+    let id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+    let mut subscriptions = METER_SUBSCRIPTIONS.lock();
+    if subscriptions.len() >= MAX_METER_SUBSCRIPTIONS {
+        // 再読み込みした UI が解除せずに残した購読を想定して古いものから捨てる
+        let dropped = subscriptions.remove(0);
+        log_warn!(
+            "[Events] Too many meter subscriptions; dropped {}",
+            dropped.id
+        );
+    }
+    subscriptions.push(MeterSubscription {
+        id,
+        channel,
+        filter,
+        interval: Duration::from_micros(1_000_000 / hz as u64),
+        next_due: Instant::now(),
+        last_timestamp: u64::MAX,
+    });
+    log_info!("[Events] Meter subscription {} at {} Hz", id, hz);
+    id
+}
+
+/// Stop a binary meter subscription (false if unknown)
+pub fn unsubscribe_meters(id: u32) -> bool {
+    let mut subscriptions = METER_SUBSCRIPTIONS.lock();
+    let before = subscriptions.len();
+    subscriptions.retain(|s| s.id != id);
+    subscriptions.len() != before
+}
+
+/// Send due binary packets (subscriptions whose channel fails are dropped)
+fn send_meter_packets(meters: &crate::audio::GraphMeters, now: Instant) {
+    METER_SUBSCRIPTIONS.lock().retain_mut(|sub| {
+        if now < sub.next_due {
+            return true;
+        }
+        sub.next_due = now + sub.interval;
+        if meters.timestamp == sub.last_timestamp {
+            return true;
+        }
+        sub.last_timestamp = meters.timestamp;
+
+        let mut packet = Vec::new();
+        meter_packet::encode(meters, &sub.filter, &mut packet);
+        match sub.channel.send(InvokeResponseBody::Raw(packet)) {
+            Ok(()) => true,
+            Err(e) => {
+                log_warn!("[Events] Dropping meter subscription {}: {}", sub.id, e);
+                false
+            }
+        }
+    });
+}
+
+/// Meter stream loop: snapshots meters at the configured rates and emits them
+///
+/// JSON の `meters` イベントとバイナリ購読はそれぞれのレートで送る。
+fn meter_stream_thread() {
+    let mut last_timestamp = u64::MAX;
+    let mut next_event = Instant::now();
+
+    loop {
+        let hz = METER_STREAM_RATE.load(Ordering::Relaxed);
+        let now = Instant::now();
+        let mut due = now + METER_IDLE_POLL;
+        if hz > 0 {
+            due = due.min(next_event);
+        }
+        if let Some(next) = METER_SUBSCRIPTIONS.lock().iter().map(|s| s.next_due).min() {
+            due = due.min(next);
+        }
+        std::thread::sleep(due.saturating_duration_since(now));
+
+        let Some(app) = APP_HANDLE.get() else {
+            continue;
+        };
+
+        let now = Instant::now();
+        let meters = get_graph_processor().get_meters();
+        send_meter_packets(&meters, now);
+
+        if hz == 0 || now < next_event {
+            continue;
+        }
+        next_event = now + Duration::from_micros(1_000_000 / hz as u64);
+        // 出力が止まっている間は同じフレームを送り続けない
+        if meters.timestamp == last_timestamp {
+            continue;
+        }
+        last_timestamp = meters.timestamp;
+
+        let dto = GraphMetersDto::from((*meters).clone());
+        if let Err(e) = app.emit(METERS_EVENT, dto) {
+            log_error!("[Events] Failed to emit meters: {}", e);
+        }
+    }
+}
+
+/// Snapshot every device's dropout counters
+pub fn xrun_stats() -> Vec<XrunStatsDto> {
+    crate::audio::xrun::xrun_stats()
+        .into_iter()
+        .map(|(device_id, s)| XrunStatsDto {
+            device_id,
+            device_name: get_device_name(device_id)
+                .unwrap_or_else(|_| format!("Device {}", device_id)),
+            underruns: s.underruns,
+            overruns: s.overruns,
+            late_callbacks: s.late_callbacks,
+        })
+        .collect()
+}
+
+/// Xrun watcher: emits an event whenever the dropout total increases
+fn xrun_watch_thread() {
+    let mut last_total = crate::audio::xrun::total_xruns();
+
+    loop {
+        std::thread::sleep(Duration::from_millis(XRUN_POLL_MS));
+
+        let total = crate::audio::xrun::total_xruns();
+        if total == last_total {
+            continue;
+        }
+        let new_xruns = total - last_total;
+        last_total = total;
+
+        let Some(app) = APP_HANDLE.get() else {
+            continue;
+        };
+        let event = XrunEventDto {
+            new_xruns,
+            devices: xrun_stats(),
+        };
+        if let Err(e) = app.emit(XRUN_EVENT, event) {
+            log_error!("[Events] Failed to emit xrun event: {}", e);
+        }
+    }
+}
+
+/// Plugin watcher: reports AudioUnit instances whose process died or that got overloaded,
+/// and follows latency changes made in the plugins' own UIs
+fn plugin_watch_thread() {
+    let mut last_count = crate::audio_unit::plugin_crash_count();
+    let mut last_overloads = crate::audio_unit::plugin_overload_count();
+    let mut last_latency_changes = crate::audio_unit::plugin_latency_change_count();
+    let mut reported: HashSet<String> = HashSet::new();
+    let mut reported_overloads: HashSet<String> = HashSet::new();
+
+    loop {
+        std::thread::sleep(Duration::from_millis(PLUGIN_WATCH_MS));
+
+        let overloads = crate::audio_unit::plugin_overload_count();
+        if overloads != last_overloads {
+            last_overloads = overloads;
+            report_overloaded_plugins(&mut reported_overloads);
+        }
+
+        // パラメーター変更など他の経路で増えた分もここで拾う
+        crate::audio_unit::get_au_manager().refresh_latencies();
+        let latency_changes = crate::audio_unit::plugin_latency_change_count();
+        if latency_changes != last_latency_changes {
+            last_latency_changes = latency_changes;
+            get_graph_processor().refresh_latency_compensation();
+        }
+
+        let count = crate::audio_unit::plugin_crash_count();
+        if count == last_count {
+            continue;
+        }
+        last_count = count;
+
+        let manager = crate::audio_unit::get_au_manager();
+        let crashed = manager.crashed_instances();
+        // 再起動されたものは次のクラッシュで再度通知する
+        reported.retain(|id| crashed.contains(id));
+
+        for instance_id in crashed {
+            if !reported.insert(instance_id.clone()) {
+                continue;
+            }
+            let name = manager
+                .get_instance(&instance_id)
+                .map(|inst| inst.info.name.clone())
+                .unwrap_or_default();
+            let bus_handles = nodes_with_plugin(&instance_id);
+            log_error!(
+                "[Events] Plugin {} ({}) crashed; bypassing",
+                name,
+                instance_id
+            );
+
+            for &handle in &bus_handles {
+                emit_graph_event(GraphEventDto::NodeChanged { handle });
+            }
+            let Some(app) = APP_HANDLE.get() else {
+                continue;
+            };
+            let event = PluginCrashEventDto {
+                instance_id,
+                name,
+                bus_handles,
+            };
+            if let Err(e) = app.emit(PLUGIN_CRASHED_EVENT, event) {
+                log_error!("[Events] Failed to emit plugin crash: {}", e);
+            }
+        }
+    }
+}
+
+/// Bypass and report instances newly tripped by the render time guard
+fn report_overloaded_plugins(reported: &mut HashSet<String>) {
+    let manager = crate::audio_unit::get_au_manager();
+    let overloaded = manager.overloaded_instances();
+    // 再起動されたものは次の過負荷で再度通知する
+    reported.retain(|id| overloaded.contains(id));
+
+    for instance_id in overloaded {
+        if !reported.insert(instance_id.clone()) {
+            continue;
+        }
+        // ステレオペアの片側だけが止まらないよう、まとめてバイパスする
+        manager.bypass_overloaded(&instance_id);
+        let (name, render_ms) = manager
+            .get_instance(&instance_id)
+            .map(|inst| {
+                let slowest = manager
+                    .pair_instances(&instance_id)
+                    .iter()
+                    .map(|p| p.slow_render_ms())
+                    .fold(inst.slow_render_ms(), f32::max);
+                (inst.info.name.clone(), slowest)
+            })
+            .unwrap_or_default();
+        let bus_handles = nodes_with_plugin(&instance_id);
+        log_warn!(
+            "[Events] Plugin {} ({}) overran its render budget ({:.1} ms); bypassing",
+            name,
+            instance_id,
+            render_ms
+        );
+
+        for &handle in &bus_handles {
+            emit_graph_event(GraphEventDto::NodeChanged { handle });
+        }
+        let Some(app) = APP_HANDLE.get() else {
+            continue;
+        };
+        let event = PluginOverloadEventDto {
+            instance_id,
+            name,
+            bus_handles,
+            render_ms,
+        };
+        if let Err(e) = app.emit(PLUGIN_OVERLOADED_EVENT, event) {
+            log_error!("[Events] Failed to emit plugin overload: {}", e);
+        }
+    }
+}
+
+/// Output protection watcher: reports sinks that dimmed their output
+fn protection_watch_thread() {
+    let mut last_count = crate::audio::dsp::protection::trip_count();
+
+    loop {
+        std::thread::sleep(Duration::from_millis(PROTECTION_POLL_MS));
+
+        let count = crate::audio::dsp::protection::trip_count();
+        if count == last_count {
+            continue;
+        }
+        last_count = count;
+
+        for event in tripped_sinks() {
+            log_warn!(
+                "[Events] Output protection of {:?} ({}) tripped at {:.1} dBFS; dimmed by {:.0} dB",
+                event.label,
+                event.handle,
+                event.peak_db,
+                event.dim_db
+            );
+            emit_graph_event(GraphEventDto::NodeChanged {
+                handle: event.handle,
+            });
+            let Some(app) = APP_HANDLE.get() else {
+                continue;
+            };
+            if let Err(e) = app.emit(OUTPUT_PROTECTION_EVENT, event) {
+                log_error!("[Events] Failed to emit output protection: {}", e);
+            }
+        }
+    }
+}
+
+/// Sinks whose protection tripped and was not reported yet
+fn tripped_sinks() -> Vec<OutputProtectionEventDto> {
+    static REPORTED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    let tripped: Vec<OutputProtectionEventDto> = get_graph_processor().with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter_map(|handle| {
+                let node = graph.get_node(handle)?;
+                let protection = node.as_any().downcast_ref::<SinkNode>()?.protection();
+                protection.is_tripped().then(|| OutputProtectionEventDto {
+                    handle: handle.raw(),
+                    label: node.label().to_string(),
+                    peak_db: protection.trip_peak_db(),
+                    dim_db: protection.settings().dim_db,
+                })
+            })
+            .collect()
+    });
+
+    let mut reported = REPORTED.lock();
+    // 解除されたものは次の発動で再度通知する
+    reported.retain(|handle| tripped.iter().any(|e| e.handle == *handle));
+    tripped
+        .into_iter()
+        .filter(|e| {
+            if reported.contains(&e.handle) {
+                return false;
+            }
+            reported.push(e.handle);
+            true
+        })
+        .collect()
+}
+
+/// Source auto-gate: mutes Prism sources whose app left or went silent
+fn source_gate_thread() {
+    loop {
+        std::thread::sleep(Duration::from_millis(SOURCE_GATE_POLL_MS));
+
+        for event in super::source_gate::poll() {
+            log_info!(
+                "[Events] Source {:?} ({}) {} ({}, {} edges)",
+                event.label,
+                event.handle,
+                if event.gated { "gated" } else { "restored" },
+                event.reason,
+                event.edges.len()
+            );
+            let Some(app) = APP_HANDLE.get() else {
+                continue;
+            };
+            if let Err(e) = app.emit(SOURCE_GATE_EVENT, event) {
+                log_error!("[Events] Failed to emit source gate: {}", e);
+            }
+        }
+    }
+}
+
+/// Watchdog: restarts output/capture runtimes whose callbacks stopped
+///
+/// グラフのロックが取れない（デッドロック）場合は再起動しても直らないので
+/// 通知のみ行い、その周期の再起動は見送る。
+fn watchdog_thread() {
+    let stall = Duration::from_millis(watchdog::DEFAULT_STALL_MS);
+    let mut detector = StallDetector::new();
+    let mut processor_stuck = false;
+
+    loop {
+        std::thread::sleep(Duration::from_millis(WATCHDOG_POLL_MS));
+
+        let probe_started = Instant::now();
+        if !get_graph_processor().is_graph_responsive(stall) {
+            if !processor_stuck {
+                processor_stuck = true;
+                log_error!(
+                    "[Watchdog] Graph lock unavailable for {} ms (processor deadlock?)",
+                    stall.as_millis()
+                );
+                crate::crash::report_fault(
+                    "processor_stall",
+                    format!(
+                        "Graph lock unavailable for {} ms (processor deadlock?)",
+                        stall.as_millis()
+                    ),
+                );
+                emit_watchdog_event(WatchdogEventDto {
+                    component: "processor".to_string(),
+                    device_id: None,
+                    device_name: None,
+                    stalled_ms: probe_started.elapsed().as_millis() as u64,
+                    action: "reported".to_string(),
+                    error: None,
+                });
+            }
+            continue;
+        }
+        if processor_stuck {
+            processor_stuck = false;
+            log_info!("[Watchdog] Graph lock available again");
+            // ロック待ちで止まっていたコールバックを停止扱いにしない
+            let now = Instant::now();
+            for (component, _) in watchdog::beats() {
+                detector.reset(component, now);
+            }
+            continue;
+        }
+
+        let now = Instant::now();
+        for (component, silent) in detector.observe(now, &watchdog::beats(), stall) {
+            let device_id = component.device_id();
+            let device_name = get_device_name(device_id).ok();
+            let mut event = WatchdogEventDto {
+                component: component.kind().to_string(),
+                device_id: Some(device_id),
+                device_name: device_name.clone(),
+                stalled_ms: silent.as_millis() as u64,
+                action: "restarted".to_string(),
+                error: None,
+            };
+
+            if !detector.allow_restart(component, now) {
+                // 再起動しても止まり続けるので諦め、ユーザーの操作を待つ
+                log_error!(
+                    "[Watchdog] {} {} keeps stalling; giving up after {} restarts",
+                    component.kind(),
+                    device_id,
+                    watchdog::MAX_RESTARTS
+                );
+                watchdog::disarm(component);
+                crate::crash::report_fault(
+                    &format!("{}_stall", component.kind()),
+                    format!(
+                        "{} {} kept stalling after {} restarts",
+                        component.kind(),
+                        device_id,
+                        watchdog::MAX_RESTARTS
+                    ),
+                );
+                event.action = "gave_up".to_string();
+                emit_watchdog_event(event);
+                continue;
+            }
+
+            log_warn!(
+                "[Watchdog] {} {} ({}) silent for {} ms; restarting",
+                component.kind(),
+                device_id,
+                device_name.as_deref().unwrap_or("unknown"),
+                silent.as_millis()
+            );
+            let result = match component {
+                Component::Output(id) => crate::audio::output::restart_output_device(id),
+                Component::Capture(id) => crate::capture::restart_device_capture(id),
+            };
+            detector.reset(component, Instant::now());
+            if let Err(e) = result {
+                log_error!("[Watchdog] Restart failed: {}", e);
+                event.action = "restart_failed".to_string();
+                event.error = Some(e);
+            }
+            emit_watchdog_event(event);
+        }
+    }
+}
+
+fn emit_watchdog_event(event: WatchdogEventDto) {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    if let Err(e) = app.emit(WATCHDOG_EVENT, event) {
+        log_error!("[Events] Failed to emit watchdog event: {}", e);
+    }
+}
+
+/// Emit offline render progress (no-op before `init`)
+pub fn emit_bounce_progress(event: BounceProgressDto) {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    if let Err(e) = app.emit(BOUNCE_PROGRESS_EVENT, event) {
+        log_error!("[Events] Failed to emit bounce progress: {}", e);
+    }
+}
+
+/// Handles of the buses whose chain contains `instance_id` (or the plugin source running it)
+fn nodes_with_plugin(instance_id: &str) -> Vec<u32> {
+    get_graph_processor().with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter(|&handle| {
+                let Some(node) = graph.get_node(handle) else {
+                    return false;
+                };
+                if let Some(source) = node.as_any().downcast_ref::<PluginSourceNode>() {
+                    return source.instance_id() == instance_id;
+                }
+                node.as_any()
+                    .downcast_ref::<BusNode>()
+                    .map(|bus| bus.plugins().iter().any(|p| p.instance_id == instance_id))
+                    .unwrap_or(false)
+            })
+            .map(|handle| handle.raw())
+            .collect()
+    })
+}
+
+/// Source nodes bound to an input device
+fn device_sources(device_id: u32) -> Vec<u32> {
+    get_graph_processor().with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter(|&handle| {
+                graph
+                    .get_node(handle)
+                    .and_then(|n| n.as_any().downcast_ref::<SourceNode>())
+                    .map(|source| {
+                        matches!(
+                            source.source_id(),
+                            SourceId::InputDevice { device_id: id, .. } if *id == device_id
+                        )
+                    })
+                    .unwrap_or(false)
+            })
+            .map(|handle| handle.raw())
+            .collect()
+    })
+}
+
+/// Sinks following the system default output that are bound to a device
+fn device_sinks(device_id: u32) -> Vec<u32> {
+    get_graph_processor().with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter(|&handle| {
+                graph
+                    .get_node(handle)
+                    .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+                    .map(|sink| sink.follows_default() && sink.device_id() == device_id)
+                    .unwrap_or(false)
+            })
+            .map(|handle| handle.raw())
+            .collect()
+    })
+}
+
+/// Forward a prismd connection change from the supervisor to the frontend
+fn emit_prism_event(event: &PrismEvent) {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    let (name, status) = match event {
+        PrismEvent::Connected { clients } => (
+            PRISM_CONNECTED_EVENT,
+            PrismStatusDto::new(true, clients.clone()),
+        ),
+        PrismEvent::Disconnected => (PRISM_DISCONNECTED_EVENT, PrismStatusDto::new(false, vec![])),
+        PrismEvent::ClientsChanged { clients } => (
+            PRISM_CLIENTS_EVENT,
+            PrismStatusDto::new(true, clients.clone()),
+        ),
+    };
+    if let Err(e) = app.emit(name, status) {
+        log_error!("[Events] Failed to emit {}: {}", name, e);
+    }
+
+    if let PrismEvent::Connected { clients } | PrismEvent::ClientsChanged { clients } = event {
+        super::prism_channels::sync_source_labels(clients);
+        super::prism_channels::apply_saved_gains(clients);
+        for detected in super::auto_source::on_clients(clients) {
+            emit_prism_app_detected(app, detected);
+        }
+    }
+}
+
+fn emit_prism_app_detected(app: &AppHandle, detected: PrismAppDetectedDto) {
+    if let Err(e) = app.emit(PRISM_APP_DETECTED_EVENT, detected) {
+        log_error!("[Events] Failed to emit prism app event: {}", e);
+    }
+}
+
+/// Forward a device change from the hot-plug monitor to the frontend
+fn emit_device_change(change: &DeviceChange, action: RerouteAction) {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+
+    let (kind, info) = match change {
+        DeviceChange::Connected(info) => ("connected", Some(info)),
+        DeviceChange::Disconnected(info) => ("disconnected", Some(info)),
+        DeviceChange::DefaultOutputChanged(_) => ("default-output", None),
+        DeviceChange::DefaultInputChanged(_) => ("default-input", None),
+        DeviceChange::ChannelsChanged(info) => ("channels-changed", Some(info)),
+    };
+    let device_id = match change {
+        DeviceChange::Connected(info)
+        | DeviceChange::Disconnected(info)
+        | DeviceChange::ChannelsChanged(info) => info.device_id,
+        DeviceChange::DefaultOutputChanged(id) | DeviceChange::DefaultInputChanged(id) => *id,
+    };
+    if let RerouteAction::Resized { resized } = action {
+        if resized > 0 {
+            for handle in device_sources(device_id) {
+                emit_graph_event(GraphEventDto::NodeChanged { handle });
+            }
+        }
+    }
+    if let RerouteAction::Retargeted { .. } = action {
+        for handle in device_sinks(device_id) {
+            emit_graph_event(GraphEventDto::NodeChanged { handle });
+        }
+    }
+    let (action, rebound_nodes) = match action {
+        RerouteAction::None => ("none", 0),
+        RerouteAction::Paused => ("paused", 0),
+        RerouteAction::Resumed { rebound } => ("resumed", rebound),
+        RerouteAction::Resized { resized } => ("resized", resized),
+        RerouteAction::Retargeted { retargeted } => ("retargeted", retargeted),
+    };
+
+    let event = DeviceChangeEventDto {
+        kind: kind.to_string(),
+        device_id,
+        device_uid: info.and_then(|i| i.uid.clone()),
+        name: info
+            .map(|i| i.name.clone())
+            .or_else(|| get_device_name(device_id).ok()),
+        input_channels: info.map(|i| i.input_channels).unwrap_or(0),
+        output_channels: info.map(|i| i.output_channels).unwrap_or(0),
+        action: action.to_string(),
+        rebound_nodes,
+    };
+    if let Err(e) = app.emit(DEVICE_CHANGED_EVENT, event) {
+        log_error!("[Events] Failed to emit device change: {}", e);
+    }
+}
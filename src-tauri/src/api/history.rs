@@ -0,0 +1,381 @@
+//! Mutation history - rolling audit log of graph changes
+//!
+//! 「配信中にいつの間にかルーティングが変わった」ときに原因を追えるよう、
+//! ノード・エッジの追加/削除、しきい値を超えるゲイン変更、ミュート、プラグインの追加/削除、
+//! グラフの読み込みを、いつ・どこから（actor）行われたかと一緒に記録する。
+//!
+//! actor は入口（HTTP API・ホットキー・URL スキーム・トレイ）で [`as_actor`] / [`with_actor`]
+//! により設定する。設定がなければ async ランタイム上（Tauri コマンド）は "ui"、
+//! それ以外のスレッド（監視スレッドなど）は "engine" とみなす。
+//!
+//! 保存先: `<data_dir>/spectrum/mutation_log.jsonl`（1 行 1 件、古いものから捨てる）
+
+use super::dto::{GraphEventDto, MutationEntryDto, MutationKind};
+use crate::audio::processor::get_graph_processor;
+use crate::audio::taper::{linear_to_db, MIN_DB};
+use crate::audio::{AudioGraph, EdgeId, NodeHandle};
+use parking_lot::Mutex;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Entries kept (in memory and on disk)
+const MAX_ENTRIES: usize = 1000;
+
+/// Gain changes smaller than this (from the last logged gain) are not recorded
+const GAIN_THRESHOLD_DB: f32 = 3.0;
+
+const LOG_FILE_NAME: &str = "mutation_log.jsonl";
+
+tokio::task_local! {
+    static ACTOR: &'static str;
+}
+
+thread_local! {
+    static SYNC_ACTOR: Cell<Option<&'static str>> = const { Cell::new(None) };
+}
+
+/// Last logged state of an edge (gain changes are compared against it)
+struct EdgeSeen {
+    route: String,
+    gain: f32,
+    muted: bool,
+}
+
+struct History {
+    entries: VecDeque<MutationEntryDto>,
+    edges: HashMap<u32, EdgeSeen>,
+    /// Lines in the log file (rewritten from `entries` when it grows past 2× the limit)
+    file_lines: usize,
+}
+
+static HISTORY: LazyLock<Mutex<History>> = LazyLock::new(|| Mutex::new(History::load()));
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn log_file() -> Result<PathBuf, String> {
+    Ok(super::persistence::app_data_dir()?.join(LOG_FILE_NAME))
+}
+
+impl History {
+    fn load() -> Self {
+        let mut entries = VecDeque::new();
+        let mut file_lines = 0;
+        if let Ok(text) =
+            log_file().and_then(|p| std::fs::read_to_string(p).map_err(|e| e.to_string()))
+        {
+            for line in text.lines().filter(|l| !l.trim().is_empty()) {
+                file_lines += 1;
+                // 壊れた行（書き込み途中で落ちたなど）は読み飛ばす
+                if let Ok(entry) = serde_json::from_str::<MutationEntryDto>(line) {
+                    entries.push_back(entry);
+                }
+            }
+        }
+        while entries.len() > MAX_ENTRIES {
+            entries.pop_front();
+        }
+        Self {
+            entries,
+            edges: HashMap::new(),
+            file_lines,
+        }
+    }
+
+    fn push(&mut self, entry: MutationEntryDto) {
+        if let Err(e) = self.append_to_file(&entry) {
+            log_warn!("[History] Failed to write mutation log: {}", e);
+        }
+        self.entries.push_back(entry);
+        while self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    fn append_to_file(&mut self, entry: &MutationEntryDto) -> Result<(), String> {
+        let path = log_file()?;
+        let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+        if self.file_lines >= MAX_ENTRIES * 2 {
+            // 古い行を捨てて、残っている分だけで書き直す
+            let mut text = String::new();
+            for kept in &self.entries {
+                text.push_str(&serde_json::to_string(kept).map_err(|e| e.to_string())?);
+                text.push('\n');
+            }
+            text.push_str(&line);
+            text.push('\n');
+            let tmp = path.with_extension("jsonl.tmp");
+            std::fs::write(&tmp, text).map_err(|e| e.to_string())?;
+            std::fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+            self.file_lines = self.entries.len() + 1;
+            return Ok(());
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+        self.file_lines += 1;
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Actor
+// =============================================================================
+
+/// Run `fut` with mutations attributed to `actor` ("http", "hotkey", ...)
+pub async fn as_actor<F: Future>(actor: &'static str, fut: F) -> F::Output {
+    ACTOR.scope(actor, fut).await
+}
+
+/// Run `f` with mutations attributed to `actor` (synchronous callers)
+pub fn with_actor<R>(actor: &'static str, f: impl FnOnce() -> R) -> R {
+    let previous = SYNC_ACTOR.with(|a| a.replace(Some(actor)));
+    let result = f();
+    SYNC_ACTOR.with(|a| a.set(previous));
+    result
+}
+
+fn current_actor() -> &'static str {
+    if let Ok(actor) = ACTOR.try_with(|a| *a) {
+        return actor;
+    }
+    if let Some(actor) = SYNC_ACTOR.with(Cell::get) {
+        return actor;
+    }
+    if tokio::runtime::Handle::try_current().is_ok() {
+        "ui"
+    } else {
+        "engine"
+    }
+}
+
+// =============================================================================
+// Recording
+// =============================================================================
+
+/// Record one mutation
+pub fn record(kind: MutationKind, node: Option<u32>, edge: Option<u32>, message: String) {
+    HISTORY.lock().push(MutationEntryDto {
+        timestamp_ms: now_ms(),
+        actor: current_actor().to_string(),
+        kind,
+        message,
+        node,
+        edge,
+    });
+}
+
+/// Record a graph change event (called for every emitted event)
+///
+/// ノードの設定変更（NodeChanged）やソロ・キューの変更は記録しない。
+pub fn record_event(event: &GraphEventDto) {
+    match *event {
+        GraphEventDto::NodeAdded { handle } => record(
+            MutationKind::NodeAdded,
+            Some(handle),
+            None,
+            format!("Added node {}", describe_node(handle)),
+        ),
+        GraphEventDto::NodeRemoved { handle } => record(
+            MutationKind::NodeRemoved,
+            Some(handle),
+            None,
+            format!("Removed node {}", handle),
+        ),
+        GraphEventDto::EdgeAdded { id } => {
+            let seen = read_edge(id);
+            let route = seen
+                .as_ref()
+                .map(|s| s.route.clone())
+                .unwrap_or_else(|| format!("edge {}", id));
+            let mut history = HISTORY.lock();
+            if let Some(seen) = seen {
+                history.edges.insert(id, seen);
+            }
+            history.push(entry(
+                MutationKind::EdgeAdded,
+                id,
+                format!("Connected {}", route),
+            ));
+        }
+        GraphEventDto::EdgeRemoved { id } => {
+            let mut history = HISTORY.lock();
+            let route = history
+                .edges
+                .remove(&id)
+                .map(|s| s.route)
+                .unwrap_or_else(|| format!("edge {}", id));
+            history.push(entry(
+                MutationKind::EdgeRemoved,
+                id,
+                format!("Disconnected {}", route),
+            ));
+        }
+        GraphEventDto::EdgeChanged { id, gain, muted } => record_edge_change(id, gain, muted),
+        GraphEventDto::GraphReloaded => {
+            let snapshot = get_graph_processor().try_with_graph(|graph| {
+                let edges: HashMap<u32, EdgeSeen> = graph
+                    .edges()
+                    .iter()
+                    .map(|e| (e.id.raw(), edge_seen(graph, e)))
+                    .collect();
+                (graph.node_handles().count(), edges)
+            });
+            let mut history = HISTORY.lock();
+            let message = match snapshot {
+                Some((node_count, edges)) => {
+                    let message = format!(
+                        "Graph reloaded ({} nodes, {} edges)",
+                        node_count,
+                        edges.len()
+                    );
+                    history.edges = edges;
+                    message
+                }
+                None => "Graph reloaded".to_string(),
+            };
+            history.push(MutationEntryDto {
+                timestamp_ms: now_ms(),
+                actor: current_actor().to_string(),
+                kind: MutationKind::GraphReloaded,
+                message,
+                node: None,
+                edge: None,
+            });
+        }
+        GraphEventDto::NodeChanged { .. }
+        | GraphEventDto::SoloChanged
+        | GraphEventDto::CueChanged => {}
+    }
+}
+
+fn record_edge_change(id: u32, gain: Option<f32>, muted: Option<bool>) {
+    let mut history = HISTORY.lock();
+    if !history.edges.contains_key(&id) {
+        // Not seen yet: take the current state as the baseline
+        drop(history);
+        if let Some(seen) = read_edge(id) {
+            HISTORY.lock().edges.insert(id, seen);
+        }
+        return;
+    }
+    let Some(seen) = history.edges.get_mut(&id) else {
+        return;
+    };
+
+    let mut entries = Vec::new();
+    if let Some(gain) = gain {
+        let db = |g: f32| linear_to_db(g).unwrap_or(MIN_DB);
+        if (db(gain) - db(seen.gain)).abs() >= GAIN_THRESHOLD_DB {
+            entries.push(entry(
+                MutationKind::EdgeGain,
+                id,
+                format!(
+                    "Gain of {} {} -> {}",
+                    seen.route,
+                    format_db(seen.gain),
+                    format_db(gain)
+                ),
+            ));
+            seen.gain = gain;
+        }
+    }
+    if let Some(muted) = muted {
+        if muted != seen.muted {
+            entries.push(entry(
+                MutationKind::EdgeMute,
+                id,
+                format!("{} {}", if muted { "Muted" } else { "Unmuted" }, seen.route),
+            ));
+            seen.muted = muted;
+        }
+    }
+    for new_entry in entries {
+        history.push(new_entry);
+    }
+}
+
+fn entry(kind: MutationKind, edge: u32, message: String) -> MutationEntryDto {
+    MutationEntryDto {
+        timestamp_ms: now_ms(),
+        actor: current_actor().to_string(),
+        kind,
+        message,
+        node: None,
+        edge: Some(edge),
+    }
+}
+
+/// Newest entries first (at most `limit`)
+pub fn recent(limit: usize) -> Vec<MutationEntryDto> {
+    HISTORY
+        .lock()
+        .entries
+        .iter()
+        .rev()
+        .take(limit)
+        .cloned()
+        .collect()
+}
+
+// =============================================================================
+// Descriptions
+// =============================================================================
+
+/// `"Label" (handle)`; the graph is only read if it is not locked
+pub fn describe_node(handle: u32) -> String {
+    get_graph_processor()
+        .try_with_graph(|graph| node_label(graph, NodeHandle::from_raw(handle)))
+        .flatten()
+        .map(|label| format!("{:?} ({})", label, handle))
+        .unwrap_or_else(|| handle.to_string())
+}
+
+fn node_label(graph: &AudioGraph, handle: NodeHandle) -> Option<String> {
+    graph.get_node(handle).map(|n| n.label().to_string())
+}
+
+fn read_edge(id: u32) -> Option<EdgeSeen> {
+    get_graph_processor()
+        .try_with_graph(|graph| {
+            graph
+                .get_edge(EdgeId::from(id))
+                .map(|edge| edge_seen(graph, edge))
+        })
+        .flatten()
+}
+
+fn edge_seen(graph: &AudioGraph, edge: &crate::audio::Edge) -> EdgeSeen {
+    let label =
+        |handle: NodeHandle| node_label(graph, handle).unwrap_or_else(|| handle.raw().to_string());
+    EdgeSeen {
+        route: format!(
+            "{}:{} -> {}:{}",
+            label(edge.source),
+            edge.source_port.index(),
+            label(edge.target),
+            edge.target_port.index()
+        ),
+        gain: edge.gain(),
+        muted: edge.muted(),
+    }
+}
+
+fn format_db(gain: f32) -> String {
+    match linear_to_db(gain) {
+        Some(db) => format!("{:.1} dB", db),
+        None => "-inf dB".to_string(),
+    }
+}
@@ -0,0 +1,359 @@
+//! HTTP control API - companion apps and scripts
+//!
+//! 設定で有効にしたときだけ起動する。既定では 127.0.0.1 だけで待ち受け、
+//! `http_api_allow_lan` で全インターフェイスにする。全リクエストに
+//! `Authorization: Bearer <token>`（または `X-Spectrum-Token`）が必要。
+//! ハンドラは Tauri コマンドをそのまま呼ぶので、UI と同じ検証・イベント通知を通る。
+//!
+//! - `GET  /graph`
+//! - `GET  /scenes`
+//! - `POST /edges/{id}/gain` - `{"gain": 0.5}` or `{"db": -6.0}`
+//! - `POST /edges/{id}/mute` - `{"muted": true}`
+//! - `POST /scenes/{name}/recall` - `{"fade_ms": 500}` (body optional)
+//!
+//! HTTP/1.1 の最小限だけ実装する（1 接続 1 リクエスト、`Connection: close`）。
+//! LAN に公開しているときは Bonjour（`_spectrum._tcp`, proto=http）で広告する。
+
+use super::error::SpectrumError;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+/// Protocol name in the Bonjour TXT record
+const BONJOUR_PROTOCOL: &str = "http";
+
+/// Default listen port
+pub const DEFAULT_PORT: u16 = 17380;
+
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// A client must send its whole request within this time
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Server {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+static SERVER: Mutex<Option<Server>> = Mutex::new(None);
+
+/// Start listening (restarts a running server)
+pub fn start(port: u16, allow_lan: bool, token: &str) -> Result<SocketAddr, String> {
+    stop();
+
+    let ip = if allow_lan {
+        Ipv4Addr::UNSPECIFIED
+    } else {
+        Ipv4Addr::LOCALHOST
+    };
+    // 同期的に bind して、ポート使用中などのエラーを呼び出し元に返す
+    let listener = std::net::TcpListener::bind((ip, port))
+        .map_err(|e| format!("Failed to listen on {}:{}: {}", ip, port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure listener: {}", e))?;
+    let addr = listener
+        .local_addr()
+        .map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+    let (shutdown, mut stopped) = oneshot::channel();
+    let token: Arc<str> = Arc::from(token);
+    tauri::async_runtime::spawn(async move {
+        let listener = match TcpListener::from_std(listener) {
+            Ok(listener) => listener,
+            Err(e) => {
+                log_error!("[HTTP] Failed to start listener: {}", e);
+                return;
+            }
+        };
+        loop {
+            tokio::select! {
+                _ = &mut stopped => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let token = token.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = serve(stream, &token).await {
+                                log_debug!("[HTTP] {}: {}", peer, e);
+                            }
+                        });
+                    }
+                    Err(e) => log_warn!("[HTTP] Accept failed: {}", e),
+                },
+            }
+        }
+        log_info!("[HTTP] Stopped listening on {}", addr);
+    });
+
+    *SERVER.lock() = Some(Server { addr, shutdown });
+    log_info!("[HTTP] Listening on {}", addr);
+    if allow_lan {
+        crate::bonjour::advertise(BONJOUR_PROTOCOL, addr.port(), &["graph", "edges", "scenes"]);
+    }
+    Ok(addr)
+}
+
+/// Stop the server (no-op when it is not running)
+pub fn stop() {
+    if let Some(server) = SERVER.lock().take() {
+        let _ = server.shutdown.send(());
+        crate::bonjour::withdraw(BONJOUR_PROTOCOL);
+    }
+}
+
+/// Address of the running server
+pub fn address() -> Option<SocketAddr> {
+    SERVER.lock().as_ref().map(|s| s.addr)
+}
+
+/// Start or stop according to settings.json (call once from `setup`)
+pub fn apply_startup() {
+    let settings = crate::settings::get();
+    if !settings.http_api_enabled {
+        return;
+    }
+    let Some(token) = settings.http_api_token.as_deref() else {
+        log_warn!("[HTTP] Enabled without a token; not starting");
+        return;
+    };
+    if let Err(e) = start(settings.http_api_port, settings.http_api_allow_lan, token) {
+        log_error!("[HTTP] {}", e);
+    }
+}
+
+/// Whether the running server is advertised over Bonjour
+pub fn is_advertised() -> bool {
+    crate::bonjour::is_advertised(BONJOUR_PROTOCOL)
+}
+
+/// New random bearer token
+pub fn generate_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+// =============================================================================
+// Request handling
+// =============================================================================
+
+struct Request {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+async fn serve(mut stream: TcpStream, token: &str) -> Result<(), String> {
+    let request = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream))
+        .await
+        .map_err(|_| "Timed out reading request".to_string())??;
+
+    let (status, body) = if authorized(&request, token) {
+        match super::history::as_actor("http", route(&request)).await {
+            Ok(value) => (200, value),
+            Err(e) => (status_for(&e), json!(e)),
+        }
+    } else {
+        (
+            401,
+            json!({ "code": "unauthorized", "message": "Missing or invalid token", "context": null }),
+        )
+    };
+
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<Request, String> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buf.len() > MAX_HEADER_BYTES {
+            return Err("Request header too large".to_string());
+        }
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Connection closed".to_string());
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = std::str::from_utf8(&buf[..header_end]).map_err(|_| "Header is not UTF-8")?;
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or_default().to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(n, v)| (n.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    let mut request = Request {
+        method,
+        path,
+        headers,
+        body: buf[header_end + 4..].to_vec(),
+    };
+    let length: usize = request
+        .header("content-length")
+        .map(|v| v.parse().map_err(|_| "Bad Content-Length"))
+        .transpose()?
+        .unwrap_or(0);
+    if length > MAX_BODY_BYTES {
+        return Err("Request body too large".to_string());
+    }
+    while request.body.len() < length {
+        let n = stream.read(&mut chunk).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Err("Connection closed".to_string());
+        }
+        request.body.extend_from_slice(&chunk[..n]);
+    }
+    request.body.truncate(length);
+    Ok(request)
+}
+
+/// Bearer token check (constant time for tokens of the right length)
+fn authorized(request: &Request, token: &str) -> bool {
+    let presented = request
+        .header("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| request.header("x-spectrum-token"));
+    presented.is_some_and(|p| {
+        p.len() == token.len()
+            && p.bytes()
+                .zip(token.bytes())
+                .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    })
+}
+
+#[derive(Deserialize)]
+struct GainBody {
+    gain: Option<f32>,
+    db: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct MuteBody {
+    muted: bool,
+}
+
+#[derive(Deserialize, Default)]
+struct RecallBody {
+    #[serde(default)]
+    fade_ms: u32,
+}
+
+async fn route(request: &Request) -> Result<Value, SpectrumError> {
+    let path = request.path.split('?').next().unwrap_or_default();
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(crate::url_scheme::percent_decode)
+        .collect();
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["graph"]) => to_json(super::get_graph().await?),
+        ("GET", ["scenes"]) => to_json(super::list_scenes().await?),
+        ("POST", ["edges", id, "gain"]) => {
+            let id = parse_id(id)?;
+            let body: GainBody = parse_body(&request.body)?;
+            let gain = match (body.gain, body.db) {
+                (_, Some(db)) => super::set_edge_gain_db(id, Some(db)).await?,
+                (Some(gain), None) => {
+                    super::set_edge_gain(id, gain).await?;
+                    gain
+                }
+                (None, None) => {
+                    return Err(SpectrumError::InvalidArgument(
+                        "Expected \"gain\" or \"db\"".to_string(),
+                    ))
+                }
+            };
+            Ok(json!({ "id": id, "gain": gain }))
+        }
+        ("POST", ["edges", id, "mute"]) => {
+            let id = parse_id(id)?;
+            let body: MuteBody = parse_body(&request.body)?;
+            super::set_edge_muted(id, body.muted).await?;
+            Ok(json!({ "id": id, "muted": body.muted }))
+        }
+        ("POST", ["scenes", name, "recall"]) => {
+            let body: RecallBody = if request.body.is_empty() {
+                RecallBody::default()
+            } else {
+                parse_body(&request.body)?
+            };
+            to_json(super::recall_scene(name.to_string(), Some(body.fade_ms)).await?)
+        }
+        (method, _) => Err(SpectrumError::Unsupported(format!(
+            "No route for {} {}",
+            method, path
+        ))),
+    }
+}
+
+fn parse_id(id: &str) -> Result<u32, SpectrumError> {
+    id.parse()
+        .map_err(|_| SpectrumError::InvalidArgument(format!("Bad id: {}", id)))
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, SpectrumError> {
+    serde_json::from_slice(body)
+        .map_err(|e| SpectrumError::InvalidArgument(format!("Bad request body: {}", e)))
+}
+
+fn to_json<T: serde::Serialize>(value: T) -> Result<Value, SpectrumError> {
+    serde_json::to_value(value).map_err(|e| SpectrumError::Other(e.to_string()))
+}
+
+fn status_for(error: &SpectrumError) -> u16 {
+    match error.code() {
+        "unsupported" => 404,
+        code if code.ends_with("not_found") => 404,
+        "invalid_argument" | "wrong_node_type" => 400,
+        "invalid_state" | "device_busy" => 409,
+        _ => 500,
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        409 => "Conflict",
+        _ => "Internal Server Error",
+    }
+}
@@ -0,0 +1,223 @@
+//! Graph state integrity - check and repair saved graph states
+//!
+//! 壊れた graph_state.json（重複ノード、存在しないノードへのエッジ、範囲外のポートなど）を
+//! 読み込み時に黙って直すのではなく、問題を一覧にして報告する。
+//! `repair` は安全に直せるものだけを直す（未インストールのプラグインはそのまま残す）。
+
+use super::commands::node_stable_id;
+use super::dto::*;
+use crate::audio::MAX_BUNDLE_CHANNELS;
+use std::collections::{HashMap, HashSet};
+
+/// Every issue in `state` (nothing is modified)
+pub fn check(state: &GraphStateDto, installed_plugins: &HashSet<String>) -> Vec<StateIssueDto> {
+    let mut repaired = state.clone();
+    let mut issues = repair(&mut repaired);
+    issues.extend(missing_plugins(state, installed_plugins));
+    issues
+}
+
+/// Apply the safe fixes to `state` -> issues that were fixed
+///
+/// - 同じ stable_id のノードは最初の 1 つにまとめ、後のノードへのエッジは付け替える
+/// - 同じハンドルの別ノードには空いているハンドルを振る（エッジは最初のノードのまま）
+/// - 存在しないノードへのエッジ・重複エッジは削除する
+/// - ソースのポート数を超えるバンドルは狭め、範囲外から始まるエッジは削除する
+pub fn repair(state: &mut GraphStateDto) -> Vec<StateIssueDto> {
+    let mut issues = Vec::new();
+
+    // Duplicate stable ids: keep the first node
+    let mut first_by_stable: HashMap<String, NodeHandle> = HashMap::new();
+    let mut merged: Vec<(NodeHandle, NodeHandle)> = Vec::new();
+    state.nodes.retain(|node| {
+        let stable_id = node_stable_id(node);
+        match first_by_stable.get(&stable_id) {
+            Some(&first) => {
+                issues.push(fixed(
+                    StateIssueKind::DuplicateStableId,
+                    format!(
+                        "Node {} duplicates stable_id {:?} of node {} (merged)",
+                        node.handle(),
+                        stable_id,
+                        first
+                    ),
+                    Some(node.handle()),
+                    None,
+                ));
+                merged.push((node.handle(), first));
+                false
+            }
+            None => {
+                first_by_stable.insert(stable_id, node.handle());
+                true
+            }
+        }
+    });
+
+    // Duplicate handles: later nodes move to a free handle
+    let mut next_free = state.nodes.iter().map(|n| n.handle()).max().unwrap_or(0);
+    let mut owned: HashSet<NodeHandle> = HashSet::new();
+    for node in &mut state.nodes {
+        if owned.insert(node.handle()) {
+            continue;
+        }
+        next_free += 1;
+        issues.push(fixed(
+            StateIssueKind::DuplicateHandle,
+            format!(
+                "Node {:?} reuses handle {} (moved to {})",
+                node_stable_id(node),
+                node.handle(),
+                next_free
+            ),
+            Some(node.handle()),
+            None,
+        ));
+        node.set_handle(next_free);
+        owned.insert(next_free);
+    }
+
+    // Edges of merged nodes follow the kept node (unless a kept node owns the handle)
+    let remap: HashMap<NodeHandle, NodeHandle> = merged
+        .into_iter()
+        .filter(|(old, _)| !owned.contains(old))
+        .collect();
+
+    let port_counts: HashMap<NodeHandle, (usize, usize)> = state
+        .nodes
+        .iter()
+        .map(|n| (n.handle(), n.port_counts()))
+        .collect();
+
+    let mut kept: Vec<EdgeInfoDto> = Vec::with_capacity(state.edges.len());
+    for mut edge in std::mem::take(&mut state.edges) {
+        edge.source = remap.get(&edge.source).copied().unwrap_or(edge.source);
+        edge.target = remap.get(&edge.target).copied().unwrap_or(edge.target);
+
+        let (Some(&(_, source_outputs)), Some(&(target_inputs, _))) =
+            (port_counts.get(&edge.source), port_counts.get(&edge.target))
+        else {
+            issues.push(fixed(
+                StateIssueKind::DanglingEdge,
+                format!(
+                    "Edge {} connects missing node {} -> {} (dropped)",
+                    edge.id, edge.source, edge.target
+                ),
+                None,
+                Some(edge.id),
+            ));
+            continue;
+        };
+
+        let source_port = edge.source_port as usize;
+        if source_port >= source_outputs || edge.target_port as usize >= target_inputs {
+            issues.push(fixed(
+                StateIssueKind::PortOutOfRange,
+                format!(
+                    "Edge {} uses port {}:{} -> {}:{} outside the nodes' ports (dropped)",
+                    edge.id, edge.source, edge.source_port, edge.target, edge.target_port
+                ),
+                None,
+                Some(edge.id),
+            ));
+            continue;
+        }
+        // Wider than the target is fine (summed); wider than the source is not
+        let channels = (edge.channels as usize)
+            .min(source_outputs - source_port)
+            .clamp(1, MAX_BUNDLE_CHANNELS) as u8;
+        if channels != edge.channels {
+            issues.push(fixed(
+                StateIssueKind::PortOutOfRange,
+                format!(
+                    "Edge {} carries {} channels from port {} of node {} with {} outputs (narrowed to {})",
+                    edge.id, edge.channels, edge.source_port, edge.source, source_outputs, channels
+                ),
+                None,
+                Some(edge.id),
+            ));
+            edge.channels = channels;
+            edge.trims.truncate(channels as usize);
+        }
+
+        if kept.iter().any(|e| overlaps(e, &edge)) {
+            issues.push(fixed(
+                StateIssueKind::DuplicateEdge,
+                format!(
+                    "Edge {} repeats ports {}:{} -> {}:{} of an earlier edge (dropped)",
+                    edge.id, edge.source, edge.source_port, edge.target, edge.target_port
+                ),
+                None,
+                Some(edge.id),
+            ));
+            continue;
+        }
+        kept.push(edge);
+    }
+    state.edges = kept;
+
+    issues
+}
+
+/// Plugins in `state` that are not installed (built-in processors always are)
+///
+/// 読み込み時はスキップされるだけなので、インストールし直せば次回の読み込みで戻る。
+pub fn missing_plugins(
+    state: &GraphStateDto,
+    installed_plugins: &HashSet<String>,
+) -> Vec<StateIssueDto> {
+    let mut issues = Vec::new();
+    for node in &state.nodes {
+        let plugins = match node {
+            NodeInfoDto::Bus { plugins, .. } => plugins.as_slice(),
+            NodeInfoDto::PluginSource { plugin, .. } => std::slice::from_ref(plugin),
+            _ => continue,
+        };
+        for plugin in plugins {
+            if crate::audio::dsp::is_native_plugin_id(&plugin.plugin_id)
+                || installed_plugins.contains(&plugin.plugin_id)
+            {
+                continue;
+            }
+            issues.push(StateIssueDto {
+                kind: StateIssueKind::MissingPlugin,
+                message: format!(
+                    "Node {} uses plugin {} ({}) which is not installed",
+                    node.handle(),
+                    plugin.plugin_id,
+                    plugin.name
+                ),
+                node: Some(node.handle()),
+                edge: None,
+                repairable: false,
+            });
+        }
+    }
+    issues
+}
+
+fn fixed(
+    kind: StateIssueKind,
+    message: String,
+    node: Option<NodeHandle>,
+    edge: Option<EdgeId>,
+) -> StateIssueDto {
+    StateIssueDto {
+        kind,
+        message,
+        node,
+        edge,
+        repairable: true,
+    }
+}
+
+/// Same rule as `Edge::overlaps`: same nodes, same port offset, overlapping source range
+fn overlaps(a: &EdgeInfoDto, b: &EdgeInfoDto) -> bool {
+    let offset = |e: &EdgeInfoDto| e.target_port as isize - e.source_port as isize;
+    let (sa, sb) = (a.source_port as usize, b.source_port as usize);
+    a.source == b.source
+        && a.target == b.target
+        && offset(a) == offset(b)
+        && sa < sb + b.channels as usize
+        && sb < sa + a.channels as usize
+}
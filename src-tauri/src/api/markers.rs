@@ -0,0 +1,476 @@
+//! Markers - time-stamped highlights during recording/streaming
+//!
+//! `add_marker` の時点を、エンジンのセッション開始（`get_session_stats` の uptime）からの
+//! 秒数と、動いている録音ごとのファイル内の位置（フレーム）で記録する。
+//! 配信中に「ここ」と印を付けておき、あとで CSV / EDL に書き出して編集に使う。
+//!
+//! 保存先: `<data_dir>/spectrum/markers.json`（全セッション分、古いものから捨てる）
+//! 録音のサイドカー: `<録音ファイル>.markers.json`（そのファイルのマーカーだけ）
+
+use super::dto::{MarkerDto, MarkerRecordingDto};
+use super::error::SpectrumError;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::record::{render_frame, RecordNode};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Marker store format version
+const MARKER_STORE_VERSION: u32 = 1;
+
+/// Markers kept (oldest are dropped first)
+const MAX_MARKERS: usize = 5000;
+
+const MARKER_FILE_NAME: &str = "markers.json";
+
+/// Timecode rate of EDL exports when none is given
+pub const DEFAULT_EDL_FPS: u32 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MarkerStore {
+    version: u32,
+    /// Id of the next marker (ids are not reused after `clear`)
+    #[serde(default)]
+    next_id: u32,
+    markers: Vec<MarkerDto>,
+}
+
+static STORE: LazyLock<Mutex<MarkerStore>> = LazyLock::new(|| Mutex::new(load_store()));
+
+// =============================================================================
+// Storage
+// =============================================================================
+
+fn markers_file() -> Result<PathBuf, String> {
+    Ok(super::persistence::app_data_dir()?.join(MARKER_FILE_NAME))
+}
+
+fn load_store() -> MarkerStore {
+    let loaded = markers_file()
+        .and_then(|path| std::fs::read_to_string(path).map_err(|e| e.to_string()))
+        .and_then(|json| serde_json::from_str::<MarkerStore>(&json).map_err(|e| e.to_string()));
+    match loaded {
+        Ok(store) => store,
+        Err(_) => MarkerStore {
+            version: MARKER_STORE_VERSION,
+            next_id: 1,
+            markers: Vec::new(),
+        },
+    }
+}
+
+/// Write JSON to `path` (atomic replace)
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize markers: {}", e))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write markers: {}", e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to write markers: {}", e))
+}
+
+/// `<recording>.markers.json`
+fn sidecar_path(recording: &str) -> PathBuf {
+    let mut path = PathBuf::from(recording).into_os_string();
+    path.push(".markers.json");
+    PathBuf::from(path)
+}
+
+// =============================================================================
+// Markers
+// =============================================================================
+
+/// Recordings that are writing right now, with the current position in each file
+fn active_recordings() -> Vec<MarkerRecordingDto> {
+    let now = render_frame();
+    let sample_rate = crate::audio::engine_sample_rate();
+    get_graph_processor().with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter_map(|handle| {
+                let status = graph
+                    .get_node(handle)?
+                    .as_any()
+                    .downcast_ref::<RecordNode>()?
+                    .status();
+                let start = status.start_frame.filter(|_| status.recording)?;
+                // パンチアウト済み（セッションが残っているだけ）のものは除く
+                if status.stop_frame.is_some_and(|stop| stop <= now) {
+                    return None;
+                }
+                let offset_frames = now.saturating_sub(start);
+                Some(MarkerRecordingDto {
+                    handle: handle.raw(),
+                    path: status.path?.display().to_string(),
+                    start_frame: start,
+                    offset_frames,
+                    offset_secs: if sample_rate > 0.0 {
+                        offset_frames as f64 / sample_rate
+                    } else {
+                        0.0
+                    },
+                })
+            })
+            .collect()
+    })
+}
+
+/// Record a marker now (also written to the sidecar of every running recording)
+pub fn add(label: Option<String>) -> Result<MarkerDto, SpectrumError> {
+    let session = crate::audio::session_stats::snapshot();
+    let recordings = active_recordings();
+
+    let mut store = STORE.lock();
+    let id = store
+        .next_id
+        .max(store.markers.last().map_or(1, |m| m.id + 1));
+    store.next_id = id + 1;
+    let marker = MarkerDto {
+        id,
+        label: label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .unwrap_or_else(|| format!("Marker {}", id)),
+        timestamp_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        session_secs: session.uptime.as_secs_f64(),
+        session_started_at_ms: session.started_at_ms,
+        recordings,
+    };
+    store.markers.push(marker.clone());
+    let excess = store.markers.len().saturating_sub(MAX_MARKERS);
+    store.markers.drain(..excess);
+
+    write_json(&markers_file()?, &*store)?;
+    for recording in &marker.recordings {
+        let markers: Vec<&MarkerDto> = take_markers(&store.markers, &marker, recording)
+            .map(|(m, _)| m)
+            .collect();
+        // 録音先に書けなくてもマーカー自体は残す
+        if let Err(e) = write_json(&sidecar_path(&recording.path), &markers) {
+            log_warn!("[Markers] {}: {}", recording.path, e);
+        }
+    }
+    Ok(marker)
+}
+
+/// Markers of the take `recording` of `marker` belongs to, with their position in it
+///
+/// 同じパスに録り直したファイルの古いテイクのマーカーを拾わないよう、セッションと
+/// 開始フレームも一致するものだけ。
+fn take_markers<'a>(
+    markers: &'a [MarkerDto],
+    marker: &MarkerDto,
+    recording: &MarkerRecordingDto,
+) -> impl Iterator<Item = (&'a MarkerDto, &'a MarkerRecordingDto)> {
+    let session = marker.session_started_at_ms;
+    let (path, start_frame) = (recording.path.clone(), recording.start_frame);
+    markers
+        .iter()
+        .filter(move |m| m.session_started_at_ms == session)
+        .filter_map(move |m| {
+            m.recordings
+                .iter()
+                .find(|r| r.path == path && r.start_frame == start_frame)
+                .map(|r| (m, r))
+        })
+}
+
+/// Every stored marker (oldest first)
+pub fn list() -> Vec<MarkerDto> {
+    STORE.lock().markers.clone()
+}
+
+/// Remove every stored marker (recording sidecars are kept); returns how many were removed
+pub fn clear() -> Result<usize, SpectrumError> {
+    let mut store = STORE.lock();
+    let removed = store.markers.len();
+    store.markers.clear();
+    write_json(&markers_file()?, &*store)?;
+    Ok(removed)
+}
+
+// =============================================================================
+// Export
+// =============================================================================
+
+/// Export format of [`export`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerFormat {
+    Csv,
+    /// CMX 3600 with one locator event per marker
+    Edl,
+}
+
+impl MarkerFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "edl" => Some(Self::Edl),
+            _ => None,
+        }
+    }
+}
+
+/// Stored markers as CSV or EDL text
+///
+/// With `recording` (a recording's path) only the markers of its latest take are
+/// exported, timed from the start of that file; otherwise every marker is timed from
+/// its session start.
+pub fn export(format: MarkerFormat, recording: Option<&str>, fps: u32) -> String {
+    format_markers(&list(), format, recording, fps)
+}
+
+/// [`export`] of the given markers
+fn format_markers(
+    markers: &[MarkerDto],
+    format: MarkerFormat,
+    recording: Option<&str>,
+    fps: u32,
+) -> String {
+    let timed: Vec<(&MarkerDto, f64)> = match recording {
+        Some(path) => {
+            // 最後のテイク（同じパスに録り直していれば新しい方）
+            let latest = markers
+                .iter()
+                .rev()
+                .find_map(|m| m.recordings.iter().find(|r| r.path == path).map(|r| (m, r)));
+            match latest {
+                Some((marker, recording)) => take_markers(markers, marker, recording)
+                    .map(|(m, r)| (m, r.offset_secs))
+                    .collect(),
+                None => Vec::new(),
+            }
+        }
+        None => markers.iter().map(|m| (m, m.session_secs)).collect(),
+    };
+
+    let mut out = String::new();
+    match format {
+        MarkerFormat::Csv => {
+            out.push_str("id,label,time,seconds,timestamp_ms\n");
+            for (marker, secs) in timed {
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{:.3},{}",
+                    marker.id,
+                    csv_field(&marker.label),
+                    clock_time(secs),
+                    secs,
+                    marker.timestamp_ms
+                );
+            }
+        }
+        MarkerFormat::Edl => {
+            let fps = fps.max(1);
+            let title = recording
+                .and_then(|p| Path::new(p).file_stem())
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "Spectrum markers".to_string());
+            let _ = writeln!(out, "TITLE: {}", title);
+            out.push_str("FCM: NON-DROP FRAME\n\n");
+            for (event, (marker, secs)) in timed.into_iter().enumerate() {
+                let start = timecode(secs, fps);
+                let end = timecode(secs + 1.0 / fps as f64, fps);
+                let _ = writeln!(
+                    out,
+                    "{:03}  AX       V     C        {} {} {} {}",
+                    event + 1,
+                    start,
+                    end,
+                    start,
+                    end
+                );
+                let _ = writeln!(out, "* LOC: {} RED    {}", start, marker.label);
+                out.push('\n');
+            }
+        }
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// "HH:MM:SS.mmm"
+fn clock_time(secs: f64) -> String {
+    let ms = (secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+/// "HH:MM:SS:FF" (non-drop)
+fn timecode(secs: f64, fps: u32) -> String {
+    let frames = (secs.max(0.0) * fps as f64).floor() as u64;
+    let fps = fps as u64;
+    let total_secs = frames / fps;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        total_secs / 3600 % 24,
+        total_secs / 60 % 60,
+        total_secs % 60,
+        frames % fps
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recording(path: &str, start_frame: u64, offset_secs: f64) -> MarkerRecordingDto {
+        MarkerRecordingDto {
+            handle: 1,
+            path: path.to_string(),
+            start_frame,
+            offset_frames: (offset_secs * 48_000.0) as u64,
+            offset_secs,
+        }
+    }
+
+    fn marker(
+        id: u32,
+        label: &str,
+        session_started_at_ms: u64,
+        session_secs: f64,
+        recordings: Vec<MarkerRecordingDto>,
+    ) -> MarkerDto {
+        MarkerDto {
+            id,
+            label: label.to_string(),
+            timestamp_ms: 1_000 * id as u64,
+            session_secs,
+            session_started_at_ms,
+            recordings,
+        }
+    }
+
+    /// Two takes written to the same path in different sessions, plus an unrelated marker
+    fn retaken() -> Vec<MarkerDto> {
+        vec![
+            marker(
+                1,
+                "Old take",
+                100,
+                10.0,
+                vec![recording("/rec/a.wav", 0, 2.0)],
+            ),
+            marker(2, "Between takes", 100, 20.0, Vec::new()),
+            marker(
+                3,
+                "Intro",
+                200,
+                5.0,
+                vec![recording("/rec/a.wav", 96_000, 1.5)],
+            ),
+            marker(
+                4,
+                "Outro",
+                200,
+                65.0,
+                vec![recording("/rec/a.wav", 96_000, 61.5)],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("Intro"), "Intro");
+        assert_eq!(csv_field("Intro, part 1"), "\"Intro, part 1\"");
+        assert_eq!(csv_field("The \"drop\""), "\"The \"\"drop\"\"\"");
+        assert_eq!(csv_field("line\nbreak"), "\"line\nbreak\"");
+        assert_eq!(csv_field(""), "");
+    }
+
+    #[test]
+    fn test_clock_time() {
+        assert_eq!(clock_time(0.0), "00:00:00.000");
+        assert_eq!(clock_time(-1.0), "00:00:00.000");
+        assert_eq!(clock_time(3661.5), "01:01:01.500");
+        // 丸めで繰り上がる
+        assert_eq!(clock_time(59.9999), "00:01:00.000");
+        assert_eq!(clock_time(3599.9995), "01:00:00.000");
+    }
+
+    #[test]
+    fn test_timecode() {
+        assert_eq!(timecode(0.0, 30), "00:00:00:00");
+        assert_eq!(timecode(59.999, 30), "00:00:59:29");
+        assert_eq!(timecode(60.0, 30), "00:01:00:00");
+        assert_eq!(timecode(3600.0, 25), "01:00:00:00");
+        assert_eq!(timecode(3599.99, 25), "00:59:59:24");
+        // 24 時間で一周する
+        assert_eq!(timecode(86_401.0, 30), "00:00:01:00");
+    }
+
+    #[test]
+    fn test_export_zero_fps() {
+        let markers = vec![marker(1, "Intro", 100, 2.5, Vec::new())];
+        let edl = format_markers(&markers, MarkerFormat::Edl, None, 0);
+        assert!(edl.starts_with("TITLE: Spectrum markers\n"));
+        assert!(edl.contains("001  AX       V     C        00:00:02:00 00:00:03:00"));
+        assert!(edl.contains("* LOC: 00:00:02:00 RED    Intro"));
+    }
+
+    #[test]
+    fn test_export_all_sessions() {
+        let csv = format_markers(&retaken(), MarkerFormat::Csv, None, DEFAULT_EDL_FPS);
+        assert_eq!(
+            csv,
+            "id,label,time,seconds,timestamp_ms\n\
+             1,Old take,00:00:10.000,10.000,1000\n\
+             2,Between takes,00:00:20.000,20.000,2000\n\
+             3,Intro,00:00:05.000,5.000,3000\n\
+             4,Outro,00:01:05.000,65.000,4000\n"
+        );
+    }
+
+    #[test]
+    fn test_export_recording_latest_take() {
+        let markers = retaken();
+        let csv = format_markers(&markers, MarkerFormat::Csv, Some("/rec/a.wav"), 30);
+        assert_eq!(
+            csv,
+            "id,label,time,seconds,timestamp_ms\n\
+             3,Intro,00:00:01.500,1.500,3000\n\
+             4,Outro,00:01:01.500,61.500,4000\n"
+        );
+
+        let edl = format_markers(&markers, MarkerFormat::Edl, Some("/rec/a.wav"), 30);
+        assert!(edl.starts_with("TITLE: a\n"));
+        assert!(edl.contains("001  AX       V     C        00:00:01:15 00:00:01:16"));
+        assert!(edl.contains("* LOC: 00:01:01:15 RED    Outro"));
+        assert!(!edl.contains("Old take"));
+
+        let none = format_markers(&markers, MarkerFormat::Csv, Some("/rec/b.wav"), 30);
+        assert_eq!(none, "id,label,time,seconds,timestamp_ms\n");
+    }
+
+    #[test]
+    fn test_take_markers() {
+        let markers = retaken();
+        let take: Vec<u32> = take_markers(&markers, &markers[3], &markers[3].recordings[0])
+            .map(|(m, _)| m.id)
+            .collect();
+        assert_eq!(take, vec![3, 4]);
+
+        let old: Vec<u32> = take_markers(&markers, &markers[0], &markers[0].recordings[0])
+            .map(|(m, _)| m.id)
+            .collect();
+        assert_eq!(old, vec![1]);
+    }
+}
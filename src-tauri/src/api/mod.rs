@@ -1,7 +1,22 @@
 //! API Module - Tauri commands and DTOs
 
+mod auto_source;
+pub mod autosave;
 mod commands;
 pub mod dto;
+pub mod error;
+pub mod events;
+pub mod history;
+pub mod http;
+mod integrity;
+mod markers;
+mod persistence;
+mod prism_channels;
+mod projects;
+mod scenes;
+mod source_gate;
+mod templates;
 
 pub use commands::*;
 pub use dto::*;
+pub use error::SpectrumError;
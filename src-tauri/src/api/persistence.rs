@@ -0,0 +1,346 @@
+//! State persistence - graph_state.json
+//!
+//! 読み込み時はファイルのバージョンから最新形式までマイグレーションを順に適用する。
+//! 書き込みは一時ファイル + rename で置き換え、直前の内容は `backups/` に
+//! タイムスタンプ付きで残す（内容が変わらない書き込みではバックアップしない）。
+//!
+//! 保存先: `<data_dir>/spectrum/graph_state.json`
+//! バックアップ: `<data_dir>/spectrum/backups/graph_state-<unix_ms>.json`
+
+use super::dto::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current graph_state.json format version
+///
+/// - 1: nodes without `stable_id`, node positions keyed by handle
+/// - 2: `stable_id` on every node
+/// - 3: node positions keyed by stable_id (`ui_state.node_positions`)
+/// - 4: channel bundle edges (`channels` / `trims`)
+pub(crate) const GRAPH_STATE_VERSION: u32 = 4;
+
+/// Number of backups kept (oldest are deleted first)
+const MAX_BACKUPS: usize = 20;
+
+const STATE_FILE_NAME: &str = "graph_state.json";
+const BACKUP_PREFIX: &str = "graph_state-";
+
+// =============================================================================
+// Paths
+// =============================================================================
+
+pub fn app_data_dir() -> Result<PathBuf, String> {
+    let app_data = dirs::data_dir()
+        .ok_or("Could not find app data directory")?
+        .join("spectrum");
+    std::fs::create_dir_all(&app_data)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data)
+}
+
+pub fn state_file() -> Result<PathBuf, String> {
+    Ok(app_data_dir()?.join(STATE_FILE_NAME))
+}
+
+fn backups_dir() -> Result<PathBuf, String> {
+    let dir = app_data_dir()?.join("backups");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Backup file names are generated by us; reject anything else (no path traversal)
+fn backup_timestamp(name: &str) -> Option<u64> {
+    name.strip_prefix(BACKUP_PREFIX)?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// =============================================================================
+// Read / Write
+// =============================================================================
+
+/// Parse a state file and migrate it to the current version
+///
+/// `version` が無いファイルは v1 として扱う。
+pub fn parse_state(json: &str) -> Result<GraphStateDto, String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse state file: {}", e))?;
+    if let Some(obj) = value.as_object_mut() {
+        obj.entry("version").or_insert(serde_json::json!(1));
+    }
+    let mut state: GraphStateDto =
+        serde_json::from_value(value).map_err(|e| format!("Failed to parse state file: {}", e))?;
+    migrate(&mut state)?;
+    Ok(state)
+}
+
+/// Read graph_state.json (None if it does not exist)
+pub fn read_state() -> Result<Option<GraphStateDto>, String> {
+    let path = state_file()?;
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read state file: {}", e))?;
+    parse_state(&json).map(Some)
+}
+
+/// Replace graph_state.json atomically, backing up the previous file first
+pub fn write_state(state: &GraphStateDto) -> Result<(), String> {
+    let path = state_file()?;
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize state: {}", e))?;
+
+    if let Ok(previous) = std::fs::read_to_string(&path) {
+        if previous == json {
+            return Ok(());
+        }
+        // バックアップに失敗しても保存自体は続ける
+        if let Err(e) = write_backup(&previous) {
+            log_error!("[state] Failed to back up {}: {}", path.display(), e);
+        }
+    }
+
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write state file: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write state file: {}", e))
+}
+
+// =============================================================================
+// Backups
+// =============================================================================
+
+fn write_backup(json: &str) -> Result<(), String> {
+    let dir = backups_dir()?;
+    let path = dir.join(format!("{}{}.json", BACKUP_PREFIX, now_ms()));
+    std::fs::write(&path, json).map_err(|e| e.to_string())?;
+    prune_backups(&dir);
+    Ok(())
+}
+
+/// Backup file names, newest first
+fn backup_names(dir: &Path) -> Vec<(u64, String)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<(u64, String)> = entries
+        .flatten()
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            backup_timestamp(&name).map(|ts| (ts, name))
+        })
+        .collect();
+    names.sort_by(|a, b| b.0.cmp(&a.0));
+    names
+}
+
+fn prune_backups(dir: &Path) {
+    for (_, name) in backup_names(dir).into_iter().skip(MAX_BACKUPS) {
+        if let Err(e) = std::fs::remove_file(dir.join(&name)) {
+            log_error!("[state] Failed to remove old backup {}: {}", name, e);
+        }
+    }
+}
+
+/// Available backups, newest first
+///
+/// 読めないバックアップも `readable: false` で一覧に含める。
+pub fn list_backups() -> Result<Vec<StateBackupDto>, String> {
+    let dir = backups_dir()?;
+    Ok(backup_names(&dir)
+        .into_iter()
+        .map(|(created_at_ms, name)| {
+            let path = dir.join(&name);
+            let size_bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            let parsed = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|json| parse_state(&json).ok());
+            StateBackupDto {
+                name,
+                created_at_ms,
+                size_bytes,
+                readable: parsed.is_some(),
+                node_count: parsed.as_ref().map(|s| s.nodes.len()).unwrap_or(0),
+                edge_count: parsed.as_ref().map(|s| s.edges.len()).unwrap_or(0),
+            }
+        })
+        .collect())
+}
+
+/// Read one backup (migrated to the current version)
+pub fn read_backup(name: &str) -> Result<Option<GraphStateDto>, String> {
+    if backup_timestamp(name).is_none() {
+        return Ok(None);
+    }
+    let path = backups_dir()?.join(name);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read backup: {}", e))?;
+    parse_state(&json).map(Some)
+}
+
+/// Newest backup that can still be parsed
+pub fn latest_readable_backup() -> Option<(String, GraphStateDto)> {
+    let dir = backups_dir().ok()?;
+    backup_names(&dir).into_iter().find_map(|(_, name)| {
+        let json = std::fs::read_to_string(dir.join(&name)).ok()?;
+        parse_state(&json).ok().map(|state| (name, state))
+    })
+}
+
+// =============================================================================
+// Migrations
+// =============================================================================
+
+/// Bring a state to `GRAPH_STATE_VERSION` (no-op for current files)
+pub fn migrate(state: &mut GraphStateDto) -> Result<(), String> {
+    if state.version > GRAPH_STATE_VERSION {
+        return Err(format!(
+            "State file version {} is newer than supported version {}",
+            state.version, GRAPH_STATE_VERSION
+        ));
+    }
+    let from = state.version;
+    if state.version < 2 {
+        migrate_v1_to_v2(state);
+    }
+    if state.version < 3 {
+        migrate_v2_to_v3(state);
+    }
+    if state.version < 4 {
+        migrate_v3_to_v4(state);
+    }
+    if from != state.version {
+        log_info!(
+            "[state] Migrated graph state v{} -> v{} (nodes={} edges={})",
+            from,
+            state.version,
+            state.nodes.len(),
+            state.edges.len()
+        );
+    }
+    Ok(())
+}
+
+/// v1 -> v2: fill missing stable ids
+fn migrate_v1_to_v2(state: &mut GraphStateDto) {
+    for node in &mut state.nodes {
+        let computed = super::commands::compute_stable_id_for_node(node);
+        match node {
+            NodeInfoDto::Source { stable_id, .. }
+            | NodeInfoDto::Bus { stable_id, .. }
+            | NodeInfoDto::Sink { stable_id, .. }
+            | NodeInfoDto::Record { stable_id, .. }
+            | NodeInfoDto::Generator { stable_id, .. }
+            | NodeInfoDto::PluginSource { stable_id, .. }
+            | NodeInfoDto::Matrix { stable_id, .. }
+            | NodeInfoDto::Loopback { stable_id, .. }
+            | NodeInfoDto::Network { stable_id, .. } => {
+                if stable_id.trim().is_empty() {
+                    *stable_id = computed;
+                }
+            }
+        }
+    }
+    state.version = 2;
+}
+
+/// v2 -> v3: handle-keyed node positions -> stable-keyed
+fn migrate_v2_to_v3(state: &mut GraphStateDto) {
+    if let Some(ui) = state.ui_state.as_mut() {
+        if ui.node_positions.is_empty() && !ui.node_positions_by_handle.is_empty() {
+            let handle_to_stable: HashMap<u32, String> = state
+                .nodes
+                .iter()
+                .map(|node| match node {
+                    NodeInfoDto::Source {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Bus {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Sink {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Record {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Generator {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::PluginSource {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Matrix {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Loopback {
+                        handle, stable_id, ..
+                    }
+                    | NodeInfoDto::Network {
+                        handle, stable_id, ..
+                    } => (*handle, stable_id.clone()),
+                })
+                .collect();
+
+            for (h, pos) in ui.node_positions_by_handle.iter() {
+                if let Some(stable_id) = handle_to_stable.get(h) {
+                    ui.node_positions.insert(stable_id.clone(), pos.clone());
+                }
+            }
+        }
+        // Clear legacy map so frontend doesn't accidentally use it.
+        ui.node_positions_by_handle.clear();
+    }
+    state.version = 3;
+}
+
+/// v3 -> v4: mono edges -> channel bundle edges
+fn migrate_v3_to_v4(state: &mut GraphStateDto) {
+    state.edges = merge_mono_edges_into_bundles(&state.edges);
+    state.version = 4;
+}
+
+/// 旧形式のモノラルエッジを、隣接ポートの連続した組ごとにバンドルエッジへまとめる
+///
+/// 同じソース/ターゲット間で、ポートが 1 つずつ増え、ゲインとミュートが一致する
+/// エッジの並びを 1 本にする（ステレオの L/R ペアなど）。
+fn merge_mono_edges_into_bundles(edges: &[EdgeInfoDto]) -> Vec<EdgeInfoDto> {
+    let mut sorted: Vec<&EdgeInfoDto> = edges.iter().collect();
+    sorted.sort_by_key(|e| (e.source, e.target, e.source_port, e.target_port));
+
+    let mut merged: Vec<EdgeInfoDto> = Vec::new();
+    for edge in sorted {
+        if let Some(last) = merged.last_mut() {
+            let next_port = |port: u8| port.checked_add(last.channels);
+            let extends = edge.channels == 1
+                && last.trims.is_empty()
+                && edge.trims.is_empty()
+                && last.source == edge.source
+                && last.target == edge.target
+                && next_port(last.source_port) == Some(edge.source_port)
+                && next_port(last.target_port) == Some(edge.target_port)
+                && last.gain == edge.gain
+                && last.muted == edge.muted
+                && (last.channels as usize) < crate::audio::MAX_BUNDLE_CHANNELS;
+            if extends {
+                last.channels += 1;
+                continue;
+            }
+        }
+        merged.push(edge.clone());
+    }
+    merged
+}
@@ -0,0 +1,285 @@
+//! Prism channel assignment - prismd routing through the graph API
+//!
+//! アプリをどのステレオペアに流すかを v2 API から操作する（レガシーの
+//! `set_app_routing` などを直接使わない）。ペア 0（MAIN）は未割り当てのアプリが
+//! 集まる場所で、アプリの解放 = ペア 0 に戻すこと。
+//!
+//! 使用中のペアへ割り当てるときは、`steal` 指定が無ければエラーにし、
+//! 指定があれば先に居たアプリをペア 0 に戻してから割り当てる。
+//!
+//! SourceNode のラベル（アプリ名）は `get_graph` がその時点の割り当てから作るので、
+//! 割り当てが変わったペアのソースに NodeChanged を送ってフロントエンドに再取得させる。
+//!
+//! クライアントごとのゲイン（キャプチャ前にドライバーが掛ける）もここで扱う。
+//! 設定した値はアプリ名ごとに settings.json に保存し、そのアプリの新しいクライアントに適用する。
+
+use super::dto::{GraphEventDto, NodeHandle, PrismAppDto, PrismChannelDto, PrismClientGainDto};
+use super::error::SpectrumError;
+use super::events::emit_graph_event;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::source::{SourceId, SourceNode};
+use crate::prismd::{ClientInfo, ProcessInfo};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Stereo pairs provided by the Prism driver (64 channels)
+pub const PRISM_PAIR_COUNT: u8 = 32;
+
+/// Apps per pair from the last client list; None until the first list
+static LAST_OCCUPANCY: Mutex<Option<BTreeMap<u8, BTreeSet<String>>>> = Mutex::new(None);
+
+/// Client ids already checked against the saved app gains
+static GAIN_CHECKED: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+pub(super) fn prism_error(e: Box<dyn std::error::Error + Send + Sync>) -> SpectrumError {
+    SpectrumError::Other(format!("prismd: {}", e))
+}
+
+fn pair_of(client: &ClientInfo) -> Option<u8> {
+    u8::try_from(client.channel_offset / 2)
+        .ok()
+        .filter(|pair| *pair < PRISM_PAIR_COUNT)
+}
+
+fn app_name(client: &ClientInfo) -> String {
+    ProcessInfo::from(client.clone()).name
+}
+
+fn occupancy(clients: &[ClientInfo]) -> BTreeMap<u8, BTreeSet<String>> {
+    let mut map: BTreeMap<u8, BTreeSet<String>> = BTreeMap::new();
+    for client in clients {
+        if let Some(pair) = pair_of(client) {
+            map.entry(pair).or_default().insert(app_name(client));
+        }
+    }
+    map
+}
+
+/// Whether any app is routed to `pair` (None before the first client list)
+pub fn pair_occupied(pair: u8) -> Option<bool> {
+    LAST_OCCUPANCY
+        .lock()
+        .as_ref()
+        .map(|map| map.get(&pair).is_some_and(|apps| !apps.is_empty()))
+}
+
+/// Prism source nodes in the graph by stereo pair
+fn prism_sources() -> HashMap<u8, Vec<NodeHandle>> {
+    get_graph_processor().with_graph(|graph| {
+        let mut sources: HashMap<u8, Vec<NodeHandle>> = HashMap::new();
+        for handle in graph.node_handles() {
+            if let Some(SourceId::PrismChannel { channel }) = graph
+                .get_node(handle)
+                .and_then(|n| n.as_any().downcast_ref::<SourceNode>())
+                .map(|s| s.source_id())
+            {
+                sources.entry(*channel).or_default().push(handle.raw());
+            }
+        }
+        sources
+    })
+}
+
+/// Every stereo pair with its apps and source nodes
+pub fn channel_map(clients: &[ClientInfo]) -> Vec<PrismChannelDto> {
+    let sources = prism_sources();
+    (0..PRISM_PAIR_COUNT)
+        .map(|pair| PrismChannelDto {
+            channel: pair,
+            apps: clients
+                .iter()
+                .filter(|c| pair_of(c) == Some(pair))
+                .map(|c| PrismAppDto::from(ProcessInfo::from(c.clone())))
+                .collect(),
+            source_handles: sources.get(&pair).cloned().unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// Current client list straight from prismd (not the supervisor cache)
+pub async fn fresh_clients() -> Result<Vec<ClientInfo>, SpectrumError> {
+    crate::prismd::get_clients().await.map_err(prism_error)
+}
+
+/// Route every process of `app` to `pair`
+///
+/// ペア 0 以外で他のアプリが居る場合、`steal` ならそのアプリをペア 0 に戻し、
+/// そうでなければ `InvalidState` を返す。戻り値は割り当て後のクライアント一覧。
+pub async fn assign(app: &str, pair: u8, steal: bool) -> Result<Vec<ClientInfo>, SpectrumError> {
+    if pair >= PRISM_PAIR_COUNT {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Prism channel pair must be 0-{}, got {}",
+            PRISM_PAIR_COUNT - 1,
+            pair
+        )));
+    }
+
+    let clients = fresh_clients().await?;
+    if !clients.iter().any(|c| app_name(c) == app) {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "No Prism client named {}",
+            app
+        )));
+    }
+
+    if pair != 0 {
+        let occupants: BTreeSet<String> = clients
+            .iter()
+            .filter(|c| pair_of(c) == Some(pair))
+            .map(app_name)
+            .filter(|name| name != app)
+            .collect();
+        if !occupants.is_empty() {
+            if !steal {
+                return Err(SpectrumError::InvalidState(format!(
+                    "Prism pair {} is used by {}",
+                    pair,
+                    occupants.into_iter().collect::<Vec<_>>().join(", ")
+                )));
+            }
+            for occupant in occupants {
+                log_info!("[Prism] Releasing {} from pair {}", occupant, pair);
+                crate::prismd::set_app_routing(occupant, 0)
+                    .await
+                    .map_err(prism_error)?;
+            }
+        }
+    }
+
+    crate::prismd::set_app_routing(app.to_string(), pair as u32 * 2)
+        .await
+        .map_err(prism_error)?;
+    log_info!("[Prism] Assigned {} to pair {}", app, pair);
+
+    let clients = fresh_clients().await?;
+    sync_source_labels(&clients);
+    Ok(clients)
+}
+
+/// Notify the frontend about Prism sources whose app assignment changed
+///
+/// スーパーバイザーのクライアント一覧更新と、API からの割り当て変更の両方から呼ばれる。
+/// 最初の一覧は比較対象が無いので記録だけする。
+pub fn sync_source_labels(clients: &[ClientInfo]) {
+    let current = occupancy(clients);
+    let changed: BTreeSet<u8> = {
+        let mut last = LAST_OCCUPANCY.lock();
+        let Some(previous) = last.as_ref() else {
+            *last = Some(current);
+            return;
+        };
+        let changed = previous
+            .keys()
+            .chain(current.keys())
+            .filter(|pair| previous.get(pair) != current.get(pair))
+            .copied()
+            .collect();
+        *last = Some(current);
+        changed
+    };
+    if changed.is_empty() {
+        return;
+    }
+
+    let sources = prism_sources();
+    for pair in changed {
+        for &handle in sources.get(&pair).into_iter().flatten() {
+            emit_graph_event(GraphEventDto::NodeChanged { handle });
+        }
+    }
+}
+
+// =============================================================================
+// Per-client gain
+// =============================================================================
+
+/// Clients with their pre-capture gain, by stereo pair
+pub fn client_gains_by_pair(clients: &[ClientInfo]) -> HashMap<u8, Vec<PrismClientGainDto>> {
+    let mut map: HashMap<u8, Vec<PrismClientGainDto>> = HashMap::new();
+    for client in clients {
+        if let Some(pair) = pair_of(client) {
+            map.entry(pair).or_default().push(PrismClientGainDto {
+                client_id: client.client_id,
+                pid: client.pid as u32,
+                name: app_name(client),
+                gain: client.gain,
+            });
+        }
+    }
+    map
+}
+
+/// Set one client's pre-capture gain and remember it for the app
+pub async fn set_client_gain(client_id: u32, gain: f32) -> Result<f32, SpectrumError> {
+    let max = crate::prismd::MAX_CLIENT_GAIN;
+    if !gain.is_finite() || !(0.0..=max).contains(&gain) {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Client gain must be 0.0-{}, got {}",
+            max, gain
+        )));
+    }
+
+    let clients = fresh_clients().await?;
+    let client = clients
+        .iter()
+        .find(|c| c.client_id == client_id)
+        .ok_or_else(|| {
+            SpectrumError::InvalidArgument(format!("No Prism client with id {}", client_id))
+        })?;
+
+    let applied = crate::prismd::set_client_gain(client_id, gain)
+        .await
+        .map_err(prism_error)?
+        .gain;
+    let app = app_name(client);
+    log_info!(
+        "[Prism] Client {} ({}) gain set to {:.3}",
+        client_id,
+        app,
+        applied
+    );
+    if let Err(e) = crate::settings::set_prism_client_gain(&app, applied) {
+        log_warn!("[Prism] Failed to save gain for {}: {}", app, e);
+    }
+
+    if let Some(pair) = pair_of(client) {
+        for &handle in prism_sources().get(&pair).into_iter().flatten() {
+            emit_graph_event(GraphEventDto::NodeChanged { handle });
+        }
+    }
+    Ok(applied)
+}
+
+/// Apply saved app gains to clients that have not been checked yet
+///
+/// スーパーバイザーのクライアント一覧ごとに呼ぶ。起動時の最初の一覧も対象にする
+/// （アプリを起動しなおしても、Spectrum を起動しなおしても同じゲインになる）。
+pub fn apply_saved_gains(clients: &[ClientInfo]) {
+    let new_clients: Vec<&ClientInfo> = {
+        let mut checked = GAIN_CHECKED.lock();
+        checked.retain(|id| clients.iter().any(|c| c.client_id == *id));
+        clients
+            .iter()
+            .filter(|c| checked.insert(c.client_id))
+            .collect()
+    };
+
+    for client in new_clients {
+        let Some(gain) = crate::settings::prism_client_gain(&app_name(client)) else {
+            continue;
+        };
+        if (gain - client.gain).abs() < 1e-4 {
+            continue;
+        }
+        let client_id = client.client_id;
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::prismd::set_client_gain(client_id, gain).await {
+                log_warn!(
+                    "[Prism] Failed to restore gain of client {}: {}",
+                    client_id,
+                    e
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,498 @@
+//! Projects - named routing setups saved as files
+//!
+//! graph_state.json（終了時に自動保存される現在の状態）とは別に、
+//! ルーティング一式を任意のパスに保存/読み込みする。
+//! プロジェクトには保存時のデバイス（UID・名前・使用チャンネル数）を記録し、
+//! 開くときに UID から現在の AudioObjectID へノードを付け替える。
+//! 接続されていないデバイスのノードはプレースホルダー ID のまま残し、
+//! デバイスが接続されたら hotplug 監視が付け替える。
+//!
+//! エクスポート（`export_graph`）は別の Mac と共有するための形式で、
+//! CoreAudio の ID を含まず、読み込み側でデバイスを割り当て直せる。
+//!
+//! 最近使ったプロジェクト: `<data_dir>/spectrum/recent_projects.json`
+
+use super::dto::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PROJECT_FORMAT: &str = "spectrum-project";
+
+/// Project file format version
+const PROJECT_FORMAT_VERSION: u32 = 1;
+
+const MAX_RECENT_PROJECTS: usize = 10;
+
+const EXPORT_FORMAT: &str = "spectrum-graph";
+
+/// Exported graph format version
+const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Placeholder IDs for devices that are not connected
+///
+/// CoreAudio の AudioObjectID は小さな整数なので衝突しない範囲から払い出す。
+/// 開くたびに新しい ID を使い、前のプロジェクトの待機登録と混ざらないようにする。
+static NEXT_PLACEHOLDER_ID: AtomicU32 = AtomicU32::new(0xFFFF_0000);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// =============================================================================
+// Project files
+// =============================================================================
+
+/// Devices referenced by source and sink nodes
+pub fn device_requirements(state: &GraphStateDto) -> Vec<DeviceRequirementDto> {
+    // device_id -> (input channels, output channels, uid from sink)
+    let mut used: BTreeMap<u32, (u32, u32, Option<String>)> = BTreeMap::new();
+    for node in &state.nodes {
+        match node {
+            NodeInfoDto::Source {
+                source_id: SourceIdDto::InputDevice { device_id, channel },
+                port_count,
+                ..
+            } => {
+                let entry = used.entry(*device_id).or_default();
+                entry.0 = entry.0.max(*channel as u32 + *port_count as u32);
+            }
+            // 既定出力に追従するシンクは読み込み時の既定デバイスに付くので要件にしない
+            NodeInfoDto::Sink { sink, .. } if !sink.follow_default => {
+                let entry = used.entry(sink.device_id).or_default();
+                let channels = match &sink.channel_map {
+                    Some(map) => map.iter().map(|r| r.channel as u32 + 1).max().unwrap_or(0),
+                    None => sink.channel_offset as u32 + sink.channel_count as u32,
+                };
+                entry.1 = entry.1.max(channels);
+                if entry.2.is_none() {
+                    entry.2 = sink.device_uid.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    used.into_iter()
+        .map(
+            |(device_id, (input_channels, output_channels, sink_uid))| DeviceRequirementDto {
+                device_id,
+                uid: sink_uid.or_else(|| crate::device::get_device_uid(device_id)),
+                name: coreaudio::audio_unit::macos_helpers::get_device_name(device_id)
+                    .unwrap_or_else(|_| format!("Device {}", device_id)),
+                input_channels,
+                output_channels,
+            },
+        )
+        .collect()
+}
+
+/// Read a project file (graph state migrated to the current version)
+pub fn read_project(path: &Path) -> Result<ProjectFileDto, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read project {}: {}", path.display(), e))?;
+    let mut project: ProjectFileDto = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse project {}: {}", path.display(), e))?;
+    if project.format != PROJECT_FORMAT {
+        return Err(format!("{} is not a Spectrum project", path.display()));
+    }
+    if project.version > PROJECT_FORMAT_VERSION {
+        return Err(format!(
+            "Project version {} is newer than supported version {}",
+            project.version, PROJECT_FORMAT_VERSION
+        ));
+    }
+    super::persistence::migrate(&mut project.state)?;
+    Ok(project)
+}
+
+/// Save the graph as a project (atomic replace)
+///
+/// 上書き保存では作成日時と名前を引き継ぐ。名前の既定値はファイル名。
+pub fn write_project(
+    path: &Path,
+    name: Option<String>,
+    state: GraphStateDto,
+) -> Result<ProjectInfoDto, String> {
+    let previous = read_project(path).ok().map(|p| p.metadata);
+    let name = name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty())
+        .or_else(|| previous.as_ref().map(|m| m.name.clone()))
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "Untitled".to_string());
+    let now = now_ms();
+
+    let project = ProjectFileDto {
+        format: PROJECT_FORMAT.to_string(),
+        version: PROJECT_FORMAT_VERSION,
+        metadata: ProjectMetadataDto {
+            name,
+            created_at_ms: previous.map(|m| m.created_at_ms).unwrap_or(now),
+            modified_at_ms: now,
+            devices: device_requirements(&state),
+        },
+        state,
+    };
+
+    write_json(path, &project)?;
+
+    log_info!(
+        "[Project] Saved '{}' to {} ({} devices)",
+        project.metadata.name,
+        path.display(),
+        project.metadata.devices.len()
+    );
+    Ok(ProjectInfoDto {
+        path: path.to_string_lossy().to_string(),
+        metadata: project.metadata,
+    })
+}
+
+/// Write a file next to its destination, then rename over it
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize: {}", e))?;
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// =============================================================================
+// Export / Import
+// =============================================================================
+
+/// Write a portable copy of the graph
+///
+/// CoreAudio の ID はマシンごとに違うので、ファイル内のノードは `devices` の番号
+/// （1 始まり）でデバイスを参照し、デバイス自体は UID・名前・チャンネル数で記録する。
+pub fn write_export(
+    path: &Path,
+    mut state: GraphStateDto,
+) -> Result<Vec<DeviceRequirementDto>, String> {
+    let mut devices = device_requirements(&state);
+    let local: HashMap<u32, u32> = devices
+        .iter()
+        .enumerate()
+        .map(|(i, d)| (d.device_id, i as u32 + 1))
+        .collect();
+    remap_devices(&mut state, &local);
+    for (i, device) in devices.iter_mut().enumerate() {
+        device.device_id = i as u32 + 1;
+    }
+
+    let export = ExportedGraphDto {
+        format: EXPORT_FORMAT.to_string(),
+        version: EXPORT_FORMAT_VERSION,
+        exported_at_ms: now_ms(),
+        devices,
+        state,
+    };
+    write_json(path, &export)?;
+    log_info!(
+        "[Project] Exported graph to {} ({} nodes, {} devices)",
+        path.display(),
+        export.state.nodes.len(),
+        export.devices.len()
+    );
+    Ok(export.devices)
+}
+
+/// Read an exported graph (graph state migrated to the current version)
+pub fn read_export(path: &Path) -> Result<ExportedGraphDto, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut export: ExportedGraphDto = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if export.format != EXPORT_FORMAT {
+        return Err(format!(
+            "{} is not an exported Spectrum graph",
+            path.display()
+        ));
+    }
+    if export.version > EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Export version {} is newer than supported version {}",
+            export.version, EXPORT_FORMAT_VERSION
+        ));
+    }
+    super::persistence::migrate(&mut export.state)?;
+    Ok(export)
+}
+
+/// Point sink UIDs at the devices they were remapped to
+///
+/// 別のデバイスに割り当てた出力が、元のデバイスの UID で可用性を判定されないようにする。
+pub fn retarget_sink_uids(state: &mut GraphStateDto, resolution: &DeviceResolution) {
+    for node in &mut state.nodes {
+        if let NodeInfoDto::Sink { sink, .. } = node {
+            if sink.follow_default || resolution.is_placeholder(sink.device_id) {
+                continue;
+            }
+            if let Some(uid) = crate::device::get_device_uid(sink.device_id) {
+                sink.device_uid = Some(uid);
+            }
+        }
+    }
+}
+
+/// How each saved device was matched, with connected devices that could replace it
+pub fn remap_report(
+    devices: &[DeviceRequirementDto],
+    resolution: &DeviceResolution,
+) -> Vec<DeviceRemapDto> {
+    let connected = crate::device::snapshot_devices();
+    devices
+        .iter()
+        .map(|saved| {
+            let device_id = resolution
+                .mapping
+                .get(&saved.device_id)
+                .copied()
+                .filter(|id| !resolution.is_placeholder(*id));
+            let mut candidates: Vec<DeviceCandidateDto> = connected
+                .values()
+                .filter(|d| {
+                    d.input_channels >= saved.input_channels
+                        && d.output_channels >= saved.output_channels
+                })
+                .filter_map(|d| {
+                    Some(DeviceCandidateDto {
+                        uid: d.uid.clone()?,
+                        name: d.name.clone(),
+                        input_channels: d.input_channels,
+                        output_channels: d.output_channels,
+                    })
+                })
+                .collect();
+            candidates.sort_by(|a, b| a.name.cmp(&b.name));
+
+            DeviceRemapDto {
+                key: device_key(saved).to_string(),
+                device_name: device_id.and_then(|id| connected.get(&id).map(|d| d.name.clone())),
+                device_id,
+                saved: saved.clone(),
+                candidates,
+            }
+        })
+        .collect()
+}
+
+// =============================================================================
+// Device resolution
+// =============================================================================
+
+/// Saved device ids matched to the devices on this machine
+#[derive(Debug, Default)]
+pub struct DeviceResolution {
+    /// Saved device id -> current (or placeholder) device id
+    pub mapping: HashMap<u32, u32>,
+    /// Devices that are not connected
+    pub missing: Vec<DeviceRequirementDto>,
+    /// (uid, placeholder id, output, input) to register with the hotplug monitor
+    placeholders: Vec<(String, u32, bool, bool)>,
+}
+
+impl DeviceResolution {
+    /// Whether `device_id` is a placeholder for a device that is not connected
+    pub fn is_placeholder(&self, device_id: u32) -> bool {
+        self.missing
+            .iter()
+            .any(|m| self.mapping.get(&m.device_id) == Some(&device_id))
+    }
+
+    /// Rebind placeholder nodes automatically once their device is connected
+    pub fn watch_missing(&self) {
+        for (uid, placeholder, output, input) in &self.placeholders {
+            crate::device::await_device(uid, *placeholder, *output, *input);
+        }
+    }
+}
+
+/// Key used in `device_mapping` (UID, or the name for devices without one)
+pub fn device_key(device: &DeviceRequirementDto) -> &str {
+    device.uid.as_deref().unwrap_or(&device.name)
+}
+
+/// Match saved devices to the devices on this machine
+///
+/// `overrides` maps a device key to the UID of the device to use instead.
+/// Devices without a UID are matched by their saved id, or by name when
+/// `ids_are_local` (exported files, where ids only reference `devices`).
+pub fn resolve_devices(
+    devices: &[DeviceRequirementDto],
+    overrides: &HashMap<String, String>,
+    ids_are_local: bool,
+) -> Result<DeviceResolution, String> {
+    let mut resolution = DeviceResolution::default();
+    let connected = if ids_are_local {
+        crate::device::snapshot_devices()
+    } else {
+        HashMap::new()
+    };
+    for device in devices {
+        let current = if let Some(target) = overrides.get(device_key(device)) {
+            let id = crate::device::find_device_by_uid(target)
+                .ok_or_else(|| format!("Mapped device {} is not connected", target))?;
+            Some(id)
+        } else {
+            match &device.uid {
+                Some(uid) => crate::device::find_device_by_uid(uid),
+                None if ids_are_local => connected
+                    .values()
+                    .find(|d| d.name == device.name)
+                    .map(|d| d.device_id),
+                None => coreaudio::audio_unit::macos_helpers::get_device_name(device.device_id)
+                    .ok()
+                    .map(|_| device.device_id),
+            }
+        };
+        match current {
+            Some(id) => {
+                resolution.mapping.insert(device.device_id, id);
+            }
+            None => {
+                let placeholder = NEXT_PLACEHOLDER_ID.fetch_add(1, Ordering::Relaxed);
+                log_info!(
+                    "[Project] Device '{}' is not connected; using placeholder {}",
+                    device.name,
+                    placeholder
+                );
+                resolution.mapping.insert(device.device_id, placeholder);
+                if let Some(uid) = &device.uid {
+                    resolution.placeholders.push((
+                        uid.clone(),
+                        placeholder,
+                        device.output_channels > 0,
+                        device.input_channels > 0,
+                    ));
+                }
+                resolution.missing.push(device.clone());
+            }
+        }
+    }
+    Ok(resolution)
+}
+
+/// Rewrite device ids of source/sink nodes
+///
+/// stable_id にデバイス ID が含まれるので、stable_id とノード位置のキーも付け替える。
+pub fn remap_devices(state: &mut GraphStateDto, mapping: &HashMap<u32, u32>) {
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    for node in &mut state.nodes {
+        let changed = match node {
+            NodeInfoDto::Source {
+                source_id: SourceIdDto::InputDevice { device_id, .. },
+                ..
+            } => match mapping.get(&*device_id) {
+                Some(&new_id) if new_id != *device_id => {
+                    *device_id = new_id;
+                    true
+                }
+                _ => false,
+            },
+            NodeInfoDto::Sink { sink, .. } if !sink.follow_default => {
+                match mapping.get(&sink.device_id) {
+                    Some(&new_id) if new_id != sink.device_id => {
+                        sink.device_id = new_id;
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => false,
+        };
+        if !changed {
+            continue;
+        }
+        let new_stable = super::commands::compute_stable_id_for_node(node);
+        if let NodeInfoDto::Source { stable_id, .. } | NodeInfoDto::Sink { stable_id, .. } = node {
+            renamed.insert(std::mem::replace(stable_id, new_stable.clone()), new_stable);
+        }
+    }
+
+    if let Some(ui) = state.ui_state.as_mut() {
+        // 入れ替え（A->B, B->A）でも取りこぼさないよう、先に全部取り出してから入れ直す
+        let moved: Vec<(String, NodePosition)> = renamed
+            .iter()
+            .filter_map(|(old, new)| ui.node_positions.remove(old).map(|p| (new.clone(), p)))
+            .collect();
+        ui.node_positions.extend(moved);
+    }
+}
+
+// =============================================================================
+// Recent projects
+// =============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecentEntry {
+    path: String,
+    name: String,
+    opened_at_ms: u64,
+}
+
+fn recent_file() -> Result<PathBuf, String> {
+    let app_data = dirs::data_dir()
+        .ok_or("Could not find app data directory")?
+        .join("spectrum");
+    std::fs::create_dir_all(&app_data)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data.join("recent_projects.json"))
+}
+
+fn load_recent() -> Vec<RecentEntry> {
+    recent_file()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Move a project to the top of the recent list
+pub fn record_recent(path: &Path, name: &str) {
+    let path = path.to_string_lossy().to_string();
+    let mut entries = load_recent();
+    entries.retain(|e| e.path != path);
+    entries.insert(
+        0,
+        RecentEntry {
+            path,
+            name: name.to_string(),
+            opened_at_ms: now_ms(),
+        },
+    );
+    entries.truncate(MAX_RECENT_PROJECTS);
+
+    let result = recent_file().and_then(|file| {
+        let json = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+        std::fs::rename(&tmp, &file).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        log_error!("[Project] Failed to update recent projects: {}", e);
+    }
+}
+
+/// Recently saved/opened projects, newest first
+pub fn recent_projects() -> Vec<RecentProjectDto> {
+    load_recent()
+        .into_iter()
+        .map(|e| RecentProjectDto {
+            exists: Path::new(&e.path).exists(),
+            path: e.path,
+            name: e.name,
+            opened_at_ms: e.opened_at_ms,
+        })
+        .collect()
+}
@@ -0,0 +1,398 @@
+//! Scenes - named snapshots of mix parameters
+//!
+//! エッジのゲイン/ミュート、出力ゲイン、プラグインの有効状態を保存し、
+//! 任意のクロスフェード時間でリコールする。ノードは stable_id で参照するため
+//! 再起動後（ハンドルが変わっても）リコールできる。
+//!
+//! 保存先: `<data_dir>/spectrum/scenes.json`（graph_state.json と同じ場所）
+
+use super::dto::*;
+use crate::audio::bus::BusNode;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::sink::SinkNode;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Scene store format version
+const SCENE_STORE_VERSION: u32 = 1;
+
+/// Crossfade step interval
+const FADE_STEP_MS: u32 = 10;
+
+/// Maximum crossfade time
+pub const MAX_FADE_MS: u32 = 30_000;
+
+/// Incremented on every recall; a running fade stops when it no longer matches
+static FADE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+// =============================================================================
+// Storage
+// =============================================================================
+
+fn scenes_file() -> Result<PathBuf, String> {
+    let app_data = dirs::data_dir()
+        .ok_or("Could not find app data directory")?
+        .join("spectrum");
+    std::fs::create_dir_all(&app_data)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(app_data.join("scenes.json"))
+}
+
+/// Load all scenes from disk (empty store if the file does not exist)
+pub fn load_store() -> Result<SceneStoreDto, String> {
+    let path = scenes_file()?;
+    if !path.exists() {
+        return Ok(SceneStoreDto {
+            version: SCENE_STORE_VERSION,
+            scenes: Vec::new(),
+        });
+    }
+
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read scenes: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse scenes: {}", e))
+}
+
+/// Write all scenes to disk (atomic replace)
+pub fn save_store(store: &SceneStoreDto) -> Result<(), String> {
+    let path = scenes_file()?;
+    let tmp = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("Failed to serialize scenes: {}", e))?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write scenes: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write scenes: {}", e))
+}
+
+// =============================================================================
+// Capture
+// =============================================================================
+
+/// Snapshot the current mix parameters as a scene
+pub async fn capture(name: &str) -> Result<SceneDto, String> {
+    let graph = super::get_graph().await?;
+    let processor = get_graph_processor();
+
+    let mut stable_by_handle: HashMap<NodeHandle, String> = HashMap::new();
+    let mut outputs = Vec::new();
+    let mut plugins = Vec::new();
+
+    for node in &graph.nodes {
+        let (handle, stable_id) = match node {
+            NodeInfoDto::Source {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Bus {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Sink {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Record {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Generator {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::PluginSource {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Matrix {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Loopback {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Network {
+                handle, stable_id, ..
+            } => (*handle, stable_id.clone()),
+        };
+        stable_by_handle.insert(handle, stable_id.clone());
+
+        match node {
+            NodeInfoDto::Sink { port_count, .. } => {
+                let gains = processor.with_graph(|g| {
+                    g.get_node(crate::audio::NodeHandle::from_raw(handle))
+                        .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+                        .map(|sink| {
+                            (0..*port_count as usize)
+                                .map(|p| sink.output_gain_for_port(p))
+                                .collect::<Vec<_>>()
+                        })
+                });
+                if let Some(gains) = gains {
+                    outputs.push(SceneOutputDto {
+                        node: stable_id,
+                        gains,
+                    });
+                }
+            }
+            NodeInfoDto::Bus { plugins: chain, .. } => {
+                for (index, p) in chain.iter().enumerate() {
+                    plugins.push(ScenePluginDto {
+                        bus: stable_id.clone(),
+                        index,
+                        plugin_id: p.plugin_id.clone(),
+                        enabled: p.enabled,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let edges = graph
+        .edges
+        .iter()
+        .filter_map(|e| {
+            Some(SceneEdgeDto {
+                source: stable_by_handle.get(&e.source)?.clone(),
+                source_port: e.source_port,
+                target: stable_by_handle.get(&e.target)?.clone(),
+                target_port: e.target_port,
+                gain: e.gain,
+                muted: e.muted,
+            })
+        })
+        .collect();
+
+    let saved_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    Ok(SceneDto {
+        name: name.to_string(),
+        saved_at,
+        edges,
+        outputs,
+        plugins,
+    })
+}
+
+// =============================================================================
+// Recall
+// =============================================================================
+
+struct EdgeRamp {
+    id: crate::audio::EdgeId,
+    from: f32,
+    to: f32,
+    gain: f32,
+    muted: bool,
+}
+
+struct OutputRamp {
+    handle: crate::audio::NodeHandle,
+    port: usize,
+    from: f32,
+    to: f32,
+}
+
+/// Recall a scene, ramping gains over `fade_ms` (0 = instant)
+pub async fn recall(scene: &SceneDto, fade_ms: u32) -> Result<SceneRecallDto, String> {
+    let fade_ms = fade_ms.min(MAX_FADE_MS);
+    let graph = super::get_graph().await?;
+    let processor = get_graph_processor();
+
+    let mut handle_by_stable: HashMap<String, NodeHandle> = HashMap::new();
+    for node in &graph.nodes {
+        match node {
+            NodeInfoDto::Source {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Bus {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Sink {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Record {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Generator {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::PluginSource {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Matrix {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Loopback {
+                handle, stable_id, ..
+            }
+            | NodeInfoDto::Network {
+                handle, stable_id, ..
+            } => {
+                handle_by_stable.insert(stable_id.clone(), *handle);
+            }
+        }
+    }
+
+    let edge_by_ports: HashMap<(NodeHandle, PortId, NodeHandle, PortId), &EdgeInfoDto> = graph
+        .edges
+        .iter()
+        .map(|e| ((e.source, e.source_port, e.target, e.target_port), e))
+        .collect();
+
+    // Edges
+    let mut edge_ramps = Vec::new();
+    let mut missing_edges = 0;
+    for se in &scene.edges {
+        let current = handle_by_stable
+            .get(&se.source)
+            .zip(handle_by_stable.get(&se.target))
+            .and_then(|(&s, &t)| edge_by_ports.get(&(s, se.source_port, t, se.target_port)));
+        let Some(current) = current else {
+            missing_edges += 1;
+            continue;
+        };
+        edge_ramps.push(EdgeRamp {
+            id: crate::audio::EdgeId::from(current.id),
+            from: if current.muted { 0.0 } else { current.gain },
+            to: if se.muted { 0.0 } else { se.gain },
+            gain: se.gain,
+            muted: se.muted,
+        });
+    }
+
+    // Outputs
+    let mut output_ramps = Vec::new();
+    processor.with_graph(|g| {
+        for so in &scene.outputs {
+            let Some(&handle) = handle_by_stable.get(&so.node) else {
+                continue;
+            };
+            let handle = crate::audio::NodeHandle::from_raw(handle);
+            let Some(sink) = g
+                .get_node(handle)
+                .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+            else {
+                continue;
+            };
+            for (port, &to) in so.gains.iter().enumerate() {
+                output_ramps.push(OutputRamp {
+                    handle,
+                    port,
+                    from: sink.output_gain_for_port(port),
+                    to,
+                });
+            }
+        }
+    });
+
+    // Plugin enable states switch with the bus bypass crossfade
+    let mut plugin_updates: Vec<(String, bool)> = Vec::new();
+    if !scene.plugins.is_empty() {
+        processor.with_graph_mut(|g| {
+            for sp in &scene.plugins {
+                let Some(&handle) = handle_by_stable.get(&sp.bus) else {
+                    continue;
+                };
+                let Some(bus) = g
+                    .get_node_mut(crate::audio::NodeHandle::from_raw(handle))
+                    .and_then(|n| n.as_any_mut().downcast_mut::<BusNode>())
+                else {
+                    continue;
+                };
+                let Some(instance_id) = bus
+                    .plugins()
+                    .get(sp.index)
+                    .filter(|p| p.plugin_id == sp.plugin_id)
+                    .map(|p| p.instance_id.clone())
+                else {
+                    continue;
+                };
+                if bus.set_plugin_enabled(&instance_id, sp.enabled) {
+                    plugin_updates.push((instance_id, sp.enabled));
+                }
+            }
+        });
+    }
+    for (instance_id, enabled) in &plugin_updates {
+        super::commands::sync_bus_au_enabled(instance_id, *enabled);
+    }
+
+    let applied_edges = edge_ramps.len();
+    let generation = FADE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    if fade_ms < FADE_STEP_MS {
+        apply_final(&edge_ramps, &output_ramps);
+    } else {
+        // ミュート解除されるエッジは 0 から立ち上げる
+        processor.with_graph(|g| {
+            for r in edge_ramps.iter().filter(|r| !r.muted) {
+                g.set_edge_gain_atomic(r.id, r.from);
+                g.set_edge_muted_atomic(r.id, false);
+            }
+        });
+        std::thread::spawn(move || fade_thread(generation, fade_ms, edge_ramps, output_ramps));
+    }
+
+    log_info!(
+        "[Scenes] Recalled '{}' (edges={}, missing={}, fade={}ms)",
+        scene.name,
+        applied_edges,
+        missing_edges,
+        fade_ms
+    );
+
+    Ok(SceneRecallDto {
+        name: scene.name.clone(),
+        applied_edges,
+        missing_edges,
+        fade_ms,
+    })
+}
+
+fn fade_thread(generation: u64, fade_ms: u32, edges: Vec<EdgeRamp>, outputs: Vec<OutputRamp>) {
+    let processor = get_graph_processor();
+    let steps = (fade_ms / FADE_STEP_MS).max(1);
+
+    for step in 1..steps {
+        std::thread::sleep(Duration::from_millis(FADE_STEP_MS as u64));
+        // 新しいリコールが始まったらそちらに任せる
+        if FADE_GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+
+        let t = step as f32 / steps as f32;
+        processor.with_graph(|g| {
+            for r in &edges {
+                g.set_edge_gain_atomic(r.id, r.from + (r.to - r.from) * t);
+            }
+            for r in &outputs {
+                if let Some(sink) = g
+                    .get_node(r.handle)
+                    .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+                {
+                    sink.set_output_gain_for_port(r.port, r.from + (r.to - r.from) * t);
+                }
+            }
+        });
+    }
+
+    std::thread::sleep(Duration::from_millis(FADE_STEP_MS as u64));
+    if FADE_GENERATION.load(Ordering::SeqCst) == generation {
+        apply_final(&edges, &outputs);
+    }
+}
+
+fn apply_final(edges: &[EdgeRamp], outputs: &[OutputRamp]) {
+    get_graph_processor().with_graph(|g| {
+        for r in edges {
+            g.set_edge_gain_atomic(r.id, r.gain);
+            g.set_edge_muted_atomic(r.id, r.muted);
+        }
+        for r in outputs {
+            if let Some(sink) = g
+                .get_node(r.handle)
+                .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+            {
+                sink.set_output_gain_for_port(r.port, r.to);
+            }
+        }
+    });
+}
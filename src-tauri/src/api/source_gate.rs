@@ -0,0 +1,247 @@
+//! Source auto-gate - mute Prism sources whose app is gone or silent
+//!
+//! Prism のペアからアプリが居なくなった（または `silence_secs` 以上しきい値を下回った）
+//! ソースの出力エッジをミュートし、アプリが戻って音が出たら元に戻す。
+//! 死んだチャンネルのノイズやハムがミックスに残らないようにするため。
+//!
+//! ミュートするのはその時点でミュートされていないエッジだけで、戻すときも
+//! ゲートがミュートしたエッジのうち、まだ存在してミュートのままのものだけを戻す
+//! （ゲート中にユーザーが操作したエッジの状態は尊重する）。
+//!
+//! 設定はランタイムのみ（ダッキングと同じく保存しない）。判定は
+//! [`events`](super::events) の監視スレッドから [`poll`] で行う。
+
+use super::dto::{GraphEventDto, SourceAutoGateDto, SourceGateEventDto};
+use super::error::SpectrumError;
+use super::events::emit_graph_event;
+use crate::audio::param_mailbox::EdgeParamUpdate;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::source::{SourceId, SourceNode};
+use crate::audio::taper::db_to_linear;
+use crate::audio::{AudioGraph, EdgeId, GraphMeters, NodeHandle};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Shortest silence that can close the gate (meters update a few times per second)
+const MIN_SILENCE_SECS: f32 = 0.5;
+
+struct Gate {
+    silence: Option<Duration>,
+    threshold_db: f32,
+    /// Last time the app was present and above the threshold
+    last_active: Instant,
+    /// Edges muted by the gate; None while open
+    muted: Option<Vec<EdgeId>>,
+}
+
+impl Gate {
+    fn dto(&self) -> SourceAutoGateDto {
+        SourceAutoGateDto {
+            enabled: true,
+            silence_secs: self.silence.map(|d| d.as_secs_f32()),
+            threshold_db: self.threshold_db,
+            gated: self.muted.is_some(),
+        }
+    }
+}
+
+/// Gates by source node handle
+static GATES: Mutex<BTreeMap<u32, Gate>> = Mutex::new(BTreeMap::new());
+
+/// Stereo pair of a Prism source node
+fn prism_pair(graph: &AudioGraph, handle: NodeHandle) -> Option<u8> {
+    match graph
+        .get_node(handle)?
+        .as_any()
+        .downcast_ref::<SourceNode>()?
+        .source_id()
+    {
+        SourceId::PrismChannel { channel } => Some(*channel),
+        _ => None,
+    }
+}
+
+/// Loudest output port of a node from the last meter frame (linear)
+fn output_peak(meters: &GraphMeters, handle: NodeHandle) -> f32 {
+    meters
+        .nodes
+        .iter()
+        .find(|m| m.handle == handle)
+        .map(|m| m.outputs.iter().map(|p| p.peak).fold(0.0, f32::max))
+        .unwrap_or(0.0)
+}
+
+/// Configure the auto-gate of a Prism source (`enabled: false` removes it)
+///
+/// Disabling a closed gate restores the edges it muted.
+pub fn set(handle: u32, config: SourceAutoGateDto) -> Result<SourceAutoGateDto, SpectrumError> {
+    let node = NodeHandle::from_raw(handle);
+    if get_graph_processor()
+        .with_graph(|graph| prism_pair(graph, node))
+        .is_none()
+    {
+        return Err(SpectrumError::InvalidArgument(format!(
+            "Node {} is not a Prism source",
+            handle
+        )));
+    }
+    let silence = match config.silence_secs {
+        Some(secs) if !secs.is_finite() || secs < MIN_SILENCE_SECS => {
+            return Err(SpectrumError::InvalidArgument(format!(
+                "silence_secs must be at least {}",
+                MIN_SILENCE_SECS
+            )));
+        }
+        secs => secs.map(Duration::from_secs_f32),
+    };
+
+    let mut gates = GATES.lock();
+    if !config.enabled {
+        let restore = gates.remove(&handle).and_then(|gate| gate.muted);
+        drop(gates);
+        if let Some(edges) = restore {
+            set_edges_muted(&restore_candidates(&edges), false);
+        }
+        return Ok(get(handle));
+    }
+
+    let gate = gates.entry(handle).or_insert_with(|| Gate {
+        silence: None,
+        threshold_db: 0.0,
+        last_active: Instant::now(),
+        muted: None,
+    });
+    gate.silence = silence;
+    gate.threshold_db = config.threshold_db.min(0.0);
+    Ok(gate.dto())
+}
+
+/// Auto-gate of a source (disabled if none is set)
+pub fn get(handle: u32) -> SourceAutoGateDto {
+    GATES
+        .lock()
+        .get(&handle)
+        .map(Gate::dto)
+        .unwrap_or(SourceAutoGateDto {
+            enabled: false,
+            silence_secs: None,
+            threshold_db: -60.0,
+            gated: false,
+        })
+}
+
+enum Change {
+    Close(u32, Vec<EdgeId>, &'static str),
+    Open(u32, Vec<EdgeId>),
+}
+
+/// Check every gate against the app presence and source level (called periodically)
+///
+/// Returns the gates that closed or opened (for the `source-gate` event).
+pub fn poll() -> Vec<SourceGateEventDto> {
+    let mut gates = GATES.lock();
+    if gates.is_empty() {
+        return Vec::new();
+    }
+    let processor = get_graph_processor();
+    let meters = processor.get_meters();
+    let now = Instant::now();
+
+    let changes: Vec<Change> = processor.with_graph(|graph| {
+        // 削除された（または別の種類になった）ノードのゲートは捨てる
+        gates.retain(|&raw, _| prism_pair(graph, NodeHandle::from_raw(raw)).is_some());
+
+        let mut changes = Vec::new();
+        for (&raw, gate) in gates.iter_mut() {
+            let handle = NodeHandle::from_raw(raw);
+            let Some(pair) = prism_pair(graph, handle) else {
+                continue;
+            };
+            // クライアント一覧をまだ受け取っていなければ居るものとして扱う
+            let present = super::prism_channels::pair_occupied(pair).unwrap_or(true);
+            let loud = gate.silence.is_none()
+                || output_peak(&meters, handle) >= db_to_linear(Some(gate.threshold_db));
+            if present && loud {
+                gate.last_active = now;
+            }
+            let silent = gate
+                .silence
+                .is_some_and(|silence| now.duration_since(gate.last_active) >= silence);
+
+            match gate.muted.take() {
+                None if !present || silent => {
+                    let edges: Vec<EdgeId> = graph
+                        .edges_from(handle)
+                        .filter(|e| !e.muted())
+                        .map(|e| e.id)
+                        .collect();
+                    gate.muted = Some(edges.clone());
+                    let reason = if present { "silent" } else { "app_gone" };
+                    changes.push(Change::Close(raw, edges, reason));
+                }
+                // 戻ったら、アプリが居て音が出ていること（silence 指定時）を待って開く
+                Some(edges) if present && loud => changes.push(Change::Open(raw, edges)),
+                muted => gate.muted = muted,
+            }
+        }
+        changes
+    });
+    drop(gates);
+
+    changes
+        .into_iter()
+        .map(|change| {
+            let (handle, edges, gated, reason) = match change {
+                Change::Close(handle, edges, reason) => (handle, edges, true, reason),
+                Change::Open(handle, edges) => {
+                    (handle, restore_candidates(&edges), false, "returned")
+                }
+            };
+            set_edges_muted(&edges, gated);
+            emit_graph_event(GraphEventDto::NodeChanged { handle });
+            SourceGateEventDto {
+                handle,
+                label: processor
+                    .with_graph(|graph| {
+                        graph
+                            .get_node(NodeHandle::from_raw(handle))
+                            .map(|n| n.label().to_string())
+                    })
+                    .unwrap_or_default(),
+                gated,
+                reason: reason.to_string(),
+                edges: edges.iter().map(|e| e.raw()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Edges muted by a gate that still exist and are still muted
+fn restore_candidates(edges: &[EdgeId]) -> Vec<EdgeId> {
+    get_graph_processor().with_graph(|graph| {
+        edges
+            .iter()
+            .copied()
+            .filter(|&id| graph.get_edge(id).is_some_and(|e| e.muted()))
+            .collect()
+    })
+}
+
+fn set_edges_muted(edges: &[EdgeId], muted: bool) {
+    let processor = get_graph_processor();
+    for &edge in edges {
+        let update = EdgeParamUpdate {
+            gain: None,
+            muted: Some(muted),
+            pan: None,
+        };
+        if processor.post_edge_params(edge, update) {
+            emit_graph_event(GraphEventDto::EdgeChanged {
+                id: edge.raw(),
+                gain: None,
+                muted: Some(muted),
+            });
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Channel-strip templates - reusable bus configurations
+//!
+//! バスのポート数・プラグインチェーン（パラメーターの状態を含む）・既定のセンドを
+//! 名前を付けて保存し、`create_bus_from_template` で同じ構成のバスを何度でも作れるようにする。
+//! センドの送り先は stable_id で参照するため、再起動後（ハンドルが変わっても）同じノードに繋がる。
+//!
+//! 保存先: `<data_dir>/spectrum/templates.json`（scenes.json と同じ場所）
+
+use super::commands::node_stable_id;
+use super::dto::*;
+use super::error::SpectrumError;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Template store format version
+const TEMPLATE_STORE_VERSION: u32 = 1;
+
+// =============================================================================
+// Storage
+// =============================================================================
+
+fn templates_file() -> Result<PathBuf, String> {
+    Ok(super::persistence::app_data_dir()?.join("templates.json"))
+}
+
+/// Load all templates from disk (empty store if the file does not exist)
+pub fn load_store() -> Result<ChannelTemplateStoreDto, String> {
+    let path = templates_file()?;
+    if !path.exists() {
+        return Ok(ChannelTemplateStoreDto {
+            version: TEMPLATE_STORE_VERSION,
+            templates: Vec::new(),
+        });
+    }
+
+    let json =
+        std::fs::read_to_string(&path).map_err(|e| format!("Failed to read templates: {}", e))?;
+    serde_json::from_str(&json).map_err(|e| format!("Failed to parse templates: {}", e))
+}
+
+/// Write all templates to disk (atomic replace)
+pub fn save_store(store: &ChannelTemplateStoreDto) -> Result<(), String> {
+    let path = templates_file()?;
+    let tmp = path.with_extension("json.tmp");
+    let json = serde_json::to_string_pretty(store)
+        .map_err(|e| format!("Failed to serialize templates: {}", e))?;
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write templates: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write templates: {}", e))
+}
+
+// =============================================================================
+// Capture
+// =============================================================================
+
+/// Snapshot a bus (ports, plugin chain with states, outgoing sends) as a template
+pub async fn capture(bus_handle: u32, name: &str) -> Result<ChannelTemplateDto, SpectrumError> {
+    use base64::Engine;
+
+    let graph = super::get_graph().await?;
+    let stable_by_handle: HashMap<NodeHandle, String> = graph
+        .nodes
+        .iter()
+        .map(|n| (n.handle(), node_stable_id(n)))
+        .collect();
+
+    let node = graph
+        .nodes
+        .iter()
+        .find(|n| n.handle() == bus_handle)
+        .ok_or(SpectrumError::NodeNotFound(bus_handle))?;
+    let NodeInfoDto::Bus {
+        label,
+        color,
+        port_count,
+        plugins,
+        ..
+    } = node
+    else {
+        return Err(SpectrumError::WrongNodeType {
+            handle: bus_handle,
+            expected: "bus",
+        });
+    };
+
+    // AudioUnit のパラメーターは fullState として持つ（ネイティブは `native` に入っている）
+    let states = crate::audio_unit::get_au_manager().collect_all_instance_states();
+    let plugins = plugins
+        .iter()
+        .map(|p| {
+            let mut plugin = p.clone();
+            plugin.state = states
+                .get(&p.instance_id)
+                .and_then(|s| s.as_ref())
+                .map(|bytes| base64::engine::general_purpose::STANDARD.encode(bytes));
+            plugin.out_of_process = false;
+            plugin.crashed = false;
+            plugin.overloaded = false;
+            plugin
+        })
+        .collect();
+
+    let sends = graph
+        .edges
+        .iter()
+        .filter(|e| e.source == bus_handle)
+        .filter_map(|e| {
+            Some(TemplateSendDto {
+                target: stable_by_handle.get(&e.target)?.clone(),
+                edge: e.clone(),
+            })
+        })
+        .collect();
+
+    let saved_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    Ok(ChannelTemplateDto {
+        name: name.to_string(),
+        saved_at,
+        label: label.clone(),
+        color: color.clone(),
+        port_count: *port_count,
+        plugins,
+        sends,
+    })
+}
+
+/// Bus description to build from a template (new bus id, fresh plugin instances)
+pub fn bus_info(template: &ChannelTemplateDto, bus_id: String, label: String) -> NodeInfoDto {
+    let plugins = template
+        .plugins
+        .iter()
+        .map(|p| PluginInstanceDto {
+            // 同じテンプレートから作ったバス同士でインスタンス ID が重ならないように
+            instance_id: String::new(),
+            ..p.clone()
+        })
+        .collect();
+    NodeInfoDto::Bus {
+        handle: 0,
+        stable_id: String::new(),
+        bus_id,
+        label,
+        color: template.color.clone(),
+        metadata: Default::default(),
+        port_count: template.port_count,
+        plugins,
+        frozen: false,
+    }
+}
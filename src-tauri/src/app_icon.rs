@@ -0,0 +1,155 @@
+//! App icons for Prism clients - NSRunningApplication -> PNG
+//!
+//! PID から NSRunningApplication を引き、アイコン（NSImage）を指定サイズの
+//! オフスクリーン NSBitmapImageRep に描いて PNG にする。メインスレッドは使わないので、
+//! 呼び出し側は spawn_blocking から呼ぶ。
+//!
+//! PID は再利用されるため、キャッシュはバンドル（なければ実行ファイル）のパス + サイズで引く。
+//! Prism クライアントがヘルパープロセス（"Google Chrome Helper" など）の場合は、
+//! prismd が報告する responsible_pid（本体アプリ）のアイコンを使う。
+
+use objc2::rc::autoreleasepool;
+use objc2::runtime::{AnyObject, Bool};
+use objc2::{class, msg_send};
+use objc2_foundation::{NSPoint, NSRect, NSSize, NSString};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::sync::Arc;
+
+/// Default edge length (pixels)
+pub const DEFAULT_ICON_SIZE: u32 = 64;
+pub const MIN_ICON_SIZE: u32 = 16;
+pub const MAX_ICON_SIZE: u32 = 512;
+
+/// The cache is flushed when it grows past this many PNGs
+const MAX_CACHED_ICONS: usize = 256;
+
+/// NSCompositingOperationCopy
+const COMPOSITING_OPERATION_COPY: usize = 1;
+
+/// NSBitmapImageFileTypePNG
+const BITMAP_FILE_TYPE_PNG: usize = 4;
+
+/// (bundle path, size) -> PNG
+static CACHE: Mutex<BTreeMap<(String, u32), Arc<Vec<u8>>>> = Mutex::new(BTreeMap::new());
+
+/// PNG of a process's app icon, `size` x `size` pixels
+pub fn icon_png(pid: u32, size: u32) -> Result<Arc<Vec<u8>>, String> {
+    let size = size.clamp(MIN_ICON_SIZE, MAX_ICON_SIZE);
+    let pid = responsible_pid(pid);
+
+    autoreleasepool(|_| unsafe {
+        let app: *mut AnyObject = msg_send![
+            class!(NSRunningApplication),
+            runningApplicationWithProcessIdentifier: pid as i32
+        ];
+        if app.is_null() {
+            return Err(format!("No running application with PID {}", pid));
+        }
+
+        let key = bundle_path(app).map(|path| (path, size));
+        if let Some(png) = key.as_ref().and_then(|key| CACHE.lock().get(key).cloned()) {
+            return Ok(png);
+        }
+
+        let icon: *mut AnyObject = msg_send![app, icon];
+        if icon.is_null() {
+            return Err(format!("PID {} has no icon", pid));
+        }
+        let png = Arc::new(rasterize(icon, size)?);
+
+        if let Some(key) = key {
+            let mut cache = CACHE.lock();
+            if cache.len() >= MAX_CACHED_ICONS {
+                cache.clear();
+            }
+            cache.insert(key, png.clone());
+        }
+        Ok(png)
+    })
+}
+
+/// Helper processes show their host app's icon
+fn responsible_pid(pid: u32) -> u32 {
+    crate::prismd::clients()
+        .into_iter()
+        .find(|c| c.pid as u32 == pid)
+        .and_then(|c| c.responsible_pid)
+        .filter(|p| *p > 0)
+        .map(|p| p as u32)
+        .unwrap_or(pid)
+}
+
+unsafe fn bundle_path(app: *mut AnyObject) -> Option<String> {
+    let mut url: *mut AnyObject = msg_send![app, bundleURL];
+    if url.is_null() {
+        url = msg_send![app, executableURL];
+    }
+    if url.is_null() {
+        return None;
+    }
+    let path: *mut NSString = msg_send![url, path];
+    path.as_ref().map(|p| p.to_string())
+}
+
+/// Draw an NSImage into a `size` x `size` RGBA bitmap and encode it as PNG
+unsafe fn rasterize(image: *mut AnyObject, size: u32) -> Result<Vec<u8>, String> {
+    let color_space = NSString::from_str("NSDeviceRGBColorSpace");
+    let rep: *mut AnyObject = msg_send![class!(NSBitmapImageRep), alloc];
+    let rep: *mut AnyObject = msg_send![
+        rep,
+        initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>(),
+        pixelsWide: size as isize,
+        pixelsHigh: size as isize,
+        bitsPerSample: 8isize,
+        samplesPerPixel: 4isize,
+        hasAlpha: Bool::YES,
+        isPlanar: Bool::NO,
+        colorSpaceName: &*color_space,
+        bytesPerRow: 0isize,
+        bitsPerPixel: 0isize
+    ];
+    if rep.is_null() {
+        return Err("Failed to create bitmap".to_string());
+    }
+
+    let context: *mut AnyObject = msg_send![
+        class!(NSGraphicsContext),
+        graphicsContextWithBitmapImageRep: rep
+    ];
+    if !context.is_null() {
+        // このスレッドのグラフィックスコンテキストだけを差し替える
+        let _: () = msg_send![class!(NSGraphicsContext), saveGraphicsState];
+        let _: () = msg_send![class!(NSGraphicsContext), setCurrentContext: context];
+        let rect = NSRect::new(
+            NSPoint::new(0.0, 0.0),
+            NSSize::new(size as f64, size as f64),
+        );
+        let _: () = msg_send![
+            image,
+            drawInRect: rect,
+            fromRect: NSRect::ZERO,
+            operation: COMPOSITING_OPERATION_COPY,
+            fraction: 1.0f64
+        ];
+        let _: () = msg_send![context, flushGraphics];
+        let _: () = msg_send![class!(NSGraphicsContext), restoreGraphicsState];
+    }
+
+    let properties: *mut AnyObject = msg_send![class!(NSDictionary), dictionary];
+    let data: *mut AnyObject = msg_send![
+        rep,
+        representationUsingType: BITMAP_FILE_TYPE_PNG,
+        properties: properties
+    ];
+    let png = if context.is_null() || data.is_null() {
+        None
+    } else {
+        let length: usize = msg_send![data, length];
+        let bytes: *const c_void = msg_send![data, bytes];
+        Some(std::slice::from_raw_parts(bytes as *const u8, length).to_vec())
+    };
+    let _: () = msg_send![rep, release];
+    png.ok_or_else(|| "Failed to encode icon as PNG".to_string())
+}
@@ -0,0 +1,446 @@
+//! Spectrum Analyzer - FFT analysis taps on node ports
+//!
+//! オーディオスレッドはタップ対象ポートのサンプルをリングに書くだけで、
+//! FFT は解析スレッド（`spectrum-analyzer`）がタップごとのレートで行う。
+//! フロントエンドは [`read_spectrum`] の結果をバンドにまとめたものだけを受け取る。
+//!
+//! 一定時間読まれなかったタップは自動で外す（UI を閉じたまま解析し続けないため）。
+
+use super::node::{AudioNode, NodeHandle, PortId};
+use crate::vdsp::RealFft;
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use std::sync::{Arc, LazyLock, OnceLock};
+use std::time::{Duration, Instant};
+
+/// FFT サイズの範囲
+pub const MIN_FFT_SIZE: usize = 256;
+pub const MAX_FFT_SIZE: usize = 8192;
+pub const DEFAULT_FFT_SIZE: usize = 2048;
+
+/// 解析レートの範囲 (Hz)
+pub const DEFAULT_RATE_HZ: f32 = 30.0;
+const MAX_RATE_HZ: f32 = 120.0;
+
+/// 読まれないタップを外すまでの時間
+const TAP_IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 表示レンジの下限 (dBFS)
+pub const SPECTRUM_FLOOR_DB: f32 = -120.0;
+
+/// Analysis settings of one tap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TapConfig {
+    pub fft_size: usize,
+    /// Analyses per second
+    pub rate_hz: f32,
+    /// Averaging between analyses (0 = none, 0.95 = very slow)
+    pub smoothing: f32,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        Self {
+            fft_size: DEFAULT_FFT_SIZE,
+            rate_hz: DEFAULT_RATE_HZ,
+            smoothing: 0.5,
+        }
+    }
+}
+
+impl TapConfig {
+    pub fn clamped(self) -> Self {
+        let fft_size = self
+            .fft_size
+            .clamp(MIN_FFT_SIZE, MAX_FFT_SIZE)
+            .next_power_of_two()
+            .min(MAX_FFT_SIZE);
+        let rate_hz = if self.rate_hz.is_finite() {
+            self.rate_hz.clamp(1.0, MAX_RATE_HZ)
+        } else {
+            DEFAULT_RATE_HZ
+        };
+        let smoothing = if self.smoothing.is_finite() {
+            self.smoothing.clamp(0.0, 0.95)
+        } else {
+            0.5
+        };
+        Self {
+            fft_size,
+            rate_hz,
+            smoothing,
+        }
+    }
+}
+
+/// Result of one analysis
+#[derive(Debug, Clone, Default)]
+pub struct Spectrum {
+    pub sample_rate: f64,
+    pub fft_size: usize,
+    /// Amplitude per FFT bin (size / 2 bins, full-scale sine = 1.0)
+    pub magnitudes: Vec<f32>,
+}
+
+/// 書き込み側の状態（オーディオスレッド）
+struct TapRing {
+    samples: Box<[f32]>,
+    pos: usize,
+    /// 書き込まれた総サンプル数（解析の要否判定用）
+    written: u64,
+}
+
+/// An analysis tap on one node port
+pub struct SpectrumTap {
+    handle: NodeHandle,
+    port: u8,
+    ring: Mutex<TapRing>,
+    config: Mutex<TapConfig>,
+    spectrum: ArcSwap<Spectrum>,
+    last_read: Mutex<Instant>,
+}
+
+impl SpectrumTap {
+    fn new(handle: NodeHandle, port: u8, config: TapConfig) -> Self {
+        Self {
+            handle,
+            port,
+            ring: Mutex::new(TapRing {
+                samples: vec![0.0; MAX_FFT_SIZE].into_boxed_slice(),
+                pos: 0,
+                written: 0,
+            }),
+            config: Mutex::new(config.clamped()),
+            spectrum: ArcSwap::from_pointee(Spectrum::default()),
+            last_read: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn config(&self) -> TapConfig {
+        *self.config.lock()
+    }
+
+    /// Append samples (audio thread; drops the block if the analyzer holds the ring)
+    fn push(&self, samples: &[f32]) {
+        let Some(mut ring) = self.ring.try_lock() else {
+            return;
+        };
+        for &s in samples {
+            let pos = ring.pos;
+            ring.samples[pos] = s;
+            ring.pos = (pos + 1) % MAX_FFT_SIZE;
+        }
+        ring.written += samples.len() as u64;
+    }
+
+    /// Copy the newest `out.len()` samples; returns the total written count
+    fn snapshot(&self, out: &mut [f32]) -> u64 {
+        let ring = self.ring.lock();
+        let n = out.len().min(MAX_FFT_SIZE);
+        let start = (ring.pos + MAX_FFT_SIZE - n) % MAX_FFT_SIZE;
+        for (i, o) in out[..n].iter_mut().enumerate() {
+            *o = ring.samples[(start + i) % MAX_FFT_SIZE];
+        }
+        ring.written
+    }
+}
+
+/// Active taps (audio thread reads, control thread swaps)
+static TAPS: LazyLock<ArcSwap<Vec<Arc<SpectrumTap>>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+static ANALYZER_THREAD: OnceLock<()> = OnceLock::new();
+
+/// Taps snapshot for one processing cycle (audio thread)
+pub fn active_taps() -> arc_swap::Guard<Arc<Vec<Arc<SpectrumTap>>>> {
+    TAPS.load()
+}
+
+/// Feed every tap on `handle` from the node's buffers (audio thread)
+///
+/// 出力ポートを持つノードは出力、シンクなどは入力ポートを読む。
+pub fn feed_taps(taps: &[Arc<SpectrumTap>], handle: NodeHandle, node: &dyn AudioNode) {
+    for tap in taps.iter().filter(|t| t.handle == handle) {
+        let port = PortId::new(tap.port);
+        let buf = if node.output_port_count() > 0 {
+            node.output_buffer(port)
+        } else {
+            node.input_buffer(port)
+        };
+        if let Some(buf) = buf {
+            tap.push(buf.samples());
+        }
+    }
+}
+
+/// Attach (or reconfigure) a tap
+pub fn attach_tap(handle: NodeHandle, port: u8, config: TapConfig) -> TapConfig {
+    ensure_analyzer_thread();
+    let config = config.clamped();
+    let taps = TAPS.load();
+    if let Some(tap) = taps.iter().find(|t| t.handle == handle && t.port == port) {
+        *tap.config.lock() = config;
+        *tap.last_read.lock() = Instant::now();
+        return config;
+    }
+    let mut next: Vec<Arc<SpectrumTap>> = taps.iter().cloned().collect();
+    next.push(Arc::new(SpectrumTap::new(handle, port, config)));
+    TAPS.store(Arc::new(next));
+    log_debug!(
+        "[Analyzer] Tap attached: node {} port {} ({} pt @ {} Hz)",
+        handle.raw(),
+        port,
+        config.fft_size,
+        config.rate_hz
+    );
+    config
+}
+
+/// Remove a tap; returns false if none was attached
+pub fn detach_tap(handle: NodeHandle, port: u8) -> bool {
+    let taps = TAPS.load();
+    if !taps.iter().any(|t| t.handle == handle && t.port == port) {
+        return false;
+    }
+    let next: Vec<Arc<SpectrumTap>> = taps
+        .iter()
+        .filter(|t| !(t.handle == handle && t.port == port))
+        .cloned()
+        .collect();
+    TAPS.store(Arc::new(next));
+    true
+}
+
+/// Latest spectrum of a tap (None if no tap is attached)
+pub fn read_spectrum(handle: NodeHandle, port: u8) -> Option<(TapConfig, Arc<Spectrum>)> {
+    let taps = TAPS.load();
+    let tap = taps.iter().find(|t| t.handle == handle && t.port == port)?;
+    *tap.last_read.lock() = Instant::now();
+    Some((tap.config(), tap.spectrum.load_full()))
+}
+
+fn ensure_analyzer_thread() {
+    ANALYZER_THREAD.get_or_init(|| {
+        if let Err(e) = std::thread::Builder::new()
+            .name("spectrum-analyzer".to_string())
+            .spawn(analyzer_thread)
+        {
+            log_error!("[Analyzer] Failed to start analyzer thread: {}", e);
+        }
+    });
+}
+
+/// タップごとの解析状態（解析スレッドのみ）
+struct TapAnalysis {
+    tap: Arc<SpectrumTap>,
+    fft: Option<RealFft>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    magnitudes: Vec<f32>,
+    next_due: Instant,
+    last_written: u64,
+}
+
+fn analyzer_thread() {
+    let mut states: Vec<TapAnalysis> = Vec::new();
+    loop {
+        let taps = TAPS.load_full();
+
+        // タップの増減に追従
+        states.retain(|s| taps.iter().any(|t| Arc::ptr_eq(t, &s.tap)));
+        for tap in taps.iter() {
+            if !states.iter().any(|s| Arc::ptr_eq(&s.tap, tap)) {
+                states.push(TapAnalysis {
+                    tap: tap.clone(),
+                    fft: None,
+                    window: Vec::new(),
+                    input: Vec::new(),
+                    magnitudes: Vec::new(),
+                    next_due: Instant::now(),
+                    last_written: 0,
+                });
+            }
+        }
+
+        // 読まれなくなったタップを外す
+        for s in &states {
+            if s.tap.last_read.lock().elapsed() > TAP_IDLE_TIMEOUT {
+                detach_tap(s.tap.handle, s.tap.port);
+            }
+        }
+
+        let now = Instant::now();
+        let mut sleep = Duration::from_millis(100);
+        for state in &mut states {
+            let config = state.tap.config();
+            if state.next_due <= now {
+                analyze_tap(state, config);
+                state.next_due = now + Duration::from_secs_f32(1.0 / config.rate_hz);
+            }
+            sleep = sleep.min(state.next_due.saturating_duration_since(now));
+        }
+        std::thread::sleep(sleep.max(Duration::from_millis(2)));
+    }
+}
+
+fn analyze_tap(state: &mut TapAnalysis, config: TapConfig) {
+    let size = config.fft_size;
+    if state.fft.as_ref().map(|f| f.size()) != Some(size) {
+        state.fft = RealFft::new(size);
+        state.window = hann_window(size);
+        state.input = vec![0.0; size];
+        state.magnitudes = vec![0.0; size / 2];
+    }
+    let Some(fft) = state.fft.as_mut() else {
+        return;
+    };
+
+    let written = state.tap.snapshot(&mut state.input);
+    if written == state.last_written {
+        // 新しいサンプルがない（ノードが処理されていない）: 前回の結果を維持
+        return;
+    }
+    state.last_written = written;
+
+    let previous = state.tap.spectrum.load();
+    let smoothing = if previous.magnitudes.len() == size / 2 {
+        config.smoothing
+    } else {
+        0.0
+    };
+    analyze_block(fft, &state.window, &mut state.input, &mut state.magnitudes);
+    let magnitudes = state
+        .magnitudes
+        .iter()
+        .enumerate()
+        .map(|(k, &m)| {
+            let prev = previous.magnitudes.get(k).copied().unwrap_or(0.0);
+            prev * smoothing + m * (1.0 - smoothing)
+        })
+        .collect();
+
+    state.tap.spectrum.store(Arc::new(Spectrum {
+        sample_rate: super::engine_sample_rate(),
+        fft_size: size,
+        magnitudes,
+    }));
+}
+
+/// Hann window normalized to unity coherent gain
+fn hann_window(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| {
+            let x = std::f32::consts::TAU * i as f32 / size as f32;
+            // 0.5 * (1 - cos) を平均値 0.5 で割り、正弦波の振幅がそのまま読めるようにする
+            1.0 - x.cos()
+        })
+        .collect()
+}
+
+/// Window `input` in-place and write the amplitude spectrum
+fn analyze_block(fft: &mut RealFft, window: &[f32], input: &mut [f32], out: &mut [f32]) {
+    for (s, w) in input.iter_mut().zip(window) {
+        *s *= w;
+    }
+    fft.magnitudes(input, out);
+}
+
+/// Group FFT bins into `bands` log-spaced bands between `min_freq` and `max_freq`
+///
+/// Returns (center frequency, level dBFS) per band. Each band takes the
+/// loudest bin it covers, or the nearest bin when it is narrower than one bin.
+pub fn bin_spectrum(
+    spectrum: &Spectrum,
+    bands: usize,
+    min_freq: f32,
+    max_freq: f32,
+) -> Vec<(f32, f32)> {
+    let bands = bands.max(1);
+    let nyquist = (spectrum.sample_rate / 2.0) as f32;
+    let max_freq = max_freq.min(nyquist).max(min_freq * 2.0);
+    let ratio = (max_freq / min_freq).powf(1.0 / bands as f32);
+    let bin_hz = if spectrum.fft_size > 0 {
+        spectrum.sample_rate as f32 / spectrum.fft_size as f32
+    } else {
+        1.0
+    };
+    let bins = spectrum.magnitudes.len();
+
+    (0..bands)
+        .map(|b| {
+            let lo = min_freq * ratio.powi(b as i32);
+            let hi = lo * ratio;
+            let center = (lo * hi).sqrt();
+            if bins == 0 {
+                return (center, SPECTRUM_FLOOR_DB);
+            }
+            let first = (lo / bin_hz).ceil() as usize;
+            let last = ((hi / bin_hz).floor() as usize).min(bins - 1);
+            let level = if first <= last {
+                spectrum.magnitudes[first..=last]
+                    .iter()
+                    .fold(0.0f32, |a, &m| a.max(m))
+            } else {
+                spectrum.magnitudes[((center / bin_hz).round() as usize).min(bins - 1)]
+            };
+            let db = if level > 0.0 {
+                (20.0 * level.log10()).max(SPECTRUM_FLOOR_DB)
+            } else {
+                SPECTRUM_FLOOR_DB
+            };
+            (center, db)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_sine_peaks_in_right_band() {
+        let size = 1024;
+        let sr = 48_000.0;
+        // 3 kHz = bin 64 exactly, amplitude 0.5 (-6 dBFS)
+        let mut input: Vec<f32> = (0..size)
+            .map(|i| 0.5 * (std::f32::consts::TAU * 3000.0 * i as f32 / sr as f32).sin())
+            .collect();
+        let mut fft = RealFft::new(size).unwrap();
+        let mut magnitudes = vec![0.0; size / 2];
+        analyze_block(&mut fft, &hann_window(size), &mut input, &mut magnitudes);
+        assert!((magnitudes[64] - 0.5).abs() < 0.01, "{}", magnitudes[64]);
+
+        let spectrum = Spectrum {
+            sample_rate: sr,
+            fft_size: size,
+            magnitudes,
+        };
+        let bands = bin_spectrum(&spectrum, 32, 20.0, 20_000.0);
+        assert_eq!(bands.len(), 32);
+        let (loudest, (center, db)) = bands
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1 .1.total_cmp(&b.1 .1))
+            .unwrap();
+        assert!((db + 6.02).abs() < 0.2, "{}", db);
+        assert!(
+            *center > 2500.0 && *center < 3600.0,
+            "band {} at {}",
+            loudest,
+            center
+        );
+    }
+
+    #[test]
+    fn test_tap_config_is_clamped_to_power_of_two() {
+        let config = TapConfig {
+            fft_size: 3000,
+            rate_hz: 1000.0,
+            smoothing: 2.0,
+        }
+        .clamped();
+        assert_eq!(config.fft_size, 4096);
+        assert_eq!(config.rate_hz, MAX_RATE_HZ);
+        assert_eq!(config.smoothing, 0.95);
+    }
+}
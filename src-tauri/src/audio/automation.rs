@@ -0,0 +1,417 @@
+//! Edge automation - time-based gain curves
+//!
+//! エッジごとにブレークポイント（時刻 ms, リニアゲイン）の列を持ち、トランスポートの
+//! 再生位置に合わせてオーディオスレッドがゲインを適用する。ブレークポイント間は直線補間、
+//! 最初の点より前と最後の点より後はその値を保持する。
+//!
+//! ブロック内にブレークポイントがある場合はブロックを区間に分けてランプを引くので、
+//! 折れ点はサンプル単位で正確に再生される。
+//!
+//! 書き込み（record）中はレーンを再生せず、制御スレッドからのフェーダー操作を
+//! 再生位置のブレークポイントとして追記する。
+
+use super::edge::EdgeId;
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+/// Maximum gain segments per processing block
+///
+/// これより多くのブレークポイントが 1 ブロックに入る場合、残りは最後の区間に
+/// まとめて直線で近似する。
+pub const MAX_BLOCK_SEGMENTS: usize = 16;
+
+/// Upper limit for automated gain (+12 dB)
+const MAX_AUTOMATION_GAIN: f32 = 3.981;
+
+/// One point of an automation curve
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoint {
+    /// Time from the transport start in milliseconds
+    pub time_ms: f64,
+    /// Linear gain
+    pub gain: f32,
+}
+
+/// Gain ramp over part of a block (`start` at `offset`, `end` at `offset + len`)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GainSegment {
+    pub offset: usize,
+    pub len: usize,
+    pub start: f32,
+    pub end: f32,
+}
+
+/// Automation curve for one edge
+#[derive(Debug, Clone)]
+pub struct AutomationLane {
+    pub edge: EdgeId,
+    /// Sorted by time, never empty
+    points: Vec<Breakpoint>,
+    /// Being written in the current record pass (not played back)
+    writing: bool,
+}
+
+impl AutomationLane {
+    /// Build a lane (drops non-finite points, sorts by time; None if nothing is left)
+    pub fn new(edge: EdgeId, points: &[Breakpoint]) -> Option<Self> {
+        let mut points: Vec<Breakpoint> = points
+            .iter()
+            .filter(|p| p.time_ms.is_finite() && p.gain.is_finite())
+            .map(|p| Breakpoint {
+                time_ms: p.time_ms.max(0.0),
+                gain: p.gain.clamp(0.0, MAX_AUTOMATION_GAIN),
+            })
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        points.sort_by(|a, b| a.time_ms.total_cmp(&b.time_ms));
+        Some(Self {
+            edge,
+            points,
+            writing: false,
+        })
+    }
+
+    pub fn points(&self) -> &[Breakpoint] {
+        &self.points
+    }
+
+    /// Time of the last breakpoint
+    pub fn duration_ms(&self) -> f64 {
+        self.points.last().map(|p| p.time_ms).unwrap_or(0.0)
+    }
+
+    /// Gain at a point in time
+    pub fn value_at(&self, time_ms: f64) -> f32 {
+        let idx = self.points.partition_point(|p| p.time_ms <= time_ms);
+        if idx == 0 {
+            return self.points[0].gain;
+        }
+        let a = self.points[idx - 1];
+        let Some(b) = self.points.get(idx) else {
+            return a.gain;
+        };
+        let t = ((time_ms - a.time_ms) / (b.time_ms - a.time_ms)) as f32;
+        a.gain + (b.gain - a.gain) * t
+    }
+
+    /// Split one block into linear gain segments at the breakpoints inside it
+    ///
+    /// `start_ms` はブロック先頭サンプルの時刻。戻り値は書き込んだ区間数（1 以上）。
+    pub fn block_segments(
+        &self,
+        start_ms: f64,
+        frames: usize,
+        sample_rate: f64,
+        out: &mut [GainSegment; MAX_BLOCK_SEGMENTS],
+    ) -> usize {
+        let ms_per_sample = 1000.0 / sample_rate;
+        let end_ms = start_ms + frames as f64 * ms_per_sample;
+        let mut count = 0;
+        let mut offset = 0;
+        let mut gain = self.value_at(start_ms);
+
+        let first = self.points.partition_point(|p| p.time_ms <= start_ms);
+        for point in self.points[first..]
+            .iter()
+            .take_while(|p| p.time_ms < end_ms)
+        {
+            if count + 1 == MAX_BLOCK_SEGMENTS {
+                break;
+            }
+            let at = (((point.time_ms - start_ms) / ms_per_sample).ceil() as usize).min(frames);
+            if at <= offset {
+                continue;
+            }
+            let at_gain = self.value_at(start_ms + at as f64 * ms_per_sample);
+            out[count] = GainSegment {
+                offset,
+                len: at - offset,
+                start: gain,
+                end: at_gain,
+            };
+            count += 1;
+            offset = at;
+            gain = at_gain;
+        }
+
+        out[count] = GainSegment {
+            offset,
+            len: frames - offset,
+            start: gain,
+            end: self.value_at(end_ms),
+        };
+        count + 1
+    }
+
+    /// Record a value while writing (the old curve from `time_ms` on is dropped)
+    fn write(&mut self, time_ms: f64, gain: f32) {
+        self.writing = true;
+        self.points.retain(|p| p.time_ms < time_ms);
+        self.points.push(Breakpoint {
+            time_ms,
+            gain: gain.clamp(0.0, MAX_AUTOMATION_GAIN),
+        });
+    }
+}
+
+// =============================================================================
+// Transport
+// =============================================================================
+
+static LANES: LazyLock<ArcSwap<Vec<AutomationLane>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+/// Serializes lane edits from control threads (the audio thread only loads)
+static EDIT_LOCK: Mutex<()> = Mutex::new(());
+
+static PLAYING: AtomicBool = AtomicBool::new(false);
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Transport position in ms (f64 bits, advanced by the audio thread)
+static POSITION_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Start of the block being processed in ms (f64 bits, [`NO_POSITION`] while stopped)
+static BLOCK_POSITION_MS: AtomicU64 = AtomicU64::new(NO_POSITION);
+
+const NO_POSITION: u64 = u64::MAX;
+
+/// Transport state snapshot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransportState {
+    pub playing: bool,
+    pub recording: bool,
+    pub position_ms: f64,
+    /// End of the longest lane
+    pub duration_ms: f64,
+    pub lane_count: usize,
+}
+
+pub fn transport_state() -> TransportState {
+    let lanes = LANES.load();
+    TransportState {
+        playing: PLAYING.load(Ordering::Relaxed),
+        recording: RECORDING.load(Ordering::Relaxed),
+        position_ms: position_ms(),
+        duration_ms: lanes.iter().map(|l| l.duration_ms()).fold(0.0, f64::max),
+        lane_count: lanes.len(),
+    }
+}
+
+pub fn position_ms() -> f64 {
+    f64::from_bits(POSITION_MS.load(Ordering::Relaxed))
+}
+
+/// Transport position at the start of the current block (audio thread; None while stopped)
+///
+/// 凍結したバスなど、再生位置に合わせて素材を読むノードが使う。
+pub fn block_position_ms() -> Option<f64> {
+    match BLOCK_POSITION_MS.load(Ordering::Relaxed) {
+        NO_POSITION => None,
+        bits => Some(f64::from_bits(bits)),
+    }
+}
+
+/// Set the block position directly (offline rendering drives it instead of the transport)
+pub fn set_block_position(position_ms: Option<f64>) {
+    let bits = position_ms.map_or(NO_POSITION, f64::to_bits);
+    BLOCK_POSITION_MS.store(bits, Ordering::Relaxed);
+}
+
+/// Current curve of an edge
+pub fn lane(edge: EdgeId) -> Option<AutomationLane> {
+    LANES.load().iter().find(|l| l.edge == edge).cloned()
+}
+
+fn edit_lanes(f: impl FnOnce(&mut Vec<AutomationLane>)) {
+    let _guard = EDIT_LOCK.lock();
+    let mut lanes = LANES.load().as_ref().clone();
+    f(&mut lanes);
+    LANES.store(Arc::new(lanes));
+}
+
+/// Replace the curve of an edge (empty `points` removes it)
+///
+/// Returns false if no valid point is left (the lane is removed).
+pub fn set_lane(edge: EdgeId, points: &[Breakpoint]) -> bool {
+    let lane = AutomationLane::new(edge, points);
+    let created = lane.is_some();
+    edit_lanes(|lanes| {
+        lanes.retain(|l| l.edge != edge);
+        lanes.extend(lane);
+    });
+    created
+}
+
+/// Drop curves whose edge no longer exists
+pub fn retain_lanes(existing: &HashSet<EdgeId>) {
+    edit_lanes(|lanes| lanes.retain(|l| existing.contains(&l.edge)));
+}
+
+/// Start the transport at `from_ms` (`record` writes fader moves instead of playing back)
+pub fn start(from_ms: f64, record: bool) {
+    let from_ms = if from_ms.is_finite() {
+        from_ms.max(0.0)
+    } else {
+        0.0
+    };
+    edit_lanes(|lanes| lanes.iter_mut().for_each(|l| l.writing = false));
+    POSITION_MS.store(from_ms.to_bits(), Ordering::Relaxed);
+    RECORDING.store(record, Ordering::Relaxed);
+    PLAYING.store(true, Ordering::Relaxed);
+}
+
+/// Stop the transport (the edges keep their last automated gain)
+pub fn stop() {
+    PLAYING.store(false, Ordering::Relaxed);
+    RECORDING.store(false, Ordering::Relaxed);
+    edit_lanes(|lanes| lanes.iter_mut().for_each(|l| l.writing = false));
+}
+
+/// Record a fader move at the current position (no-op unless recording)
+pub fn record_gain(edge: EdgeId, gain: f32) {
+    if !PLAYING.load(Ordering::Relaxed) || !RECORDING.load(Ordering::Relaxed) {
+        return;
+    }
+    let time_ms = position_ms();
+    edit_lanes(|lanes| match lanes.iter_mut().find(|l| l.edge == edge) {
+        Some(lane) => lane.write(time_ms, gain),
+        None => lanes.extend(
+            AutomationLane::new(edge, &[Breakpoint { time_ms, gain }]).map(|mut lane| {
+                lane.writing = true;
+                lane
+            }),
+        ),
+    });
+}
+
+// =============================================================================
+// Audio thread
+// =============================================================================
+
+/// Automation for one processing block (audio thread)
+pub struct AutomationCycle {
+    lanes: arc_swap::Guard<Arc<Vec<AutomationLane>>>,
+    start_ms: f64,
+    frames: usize,
+    sample_rate: f64,
+}
+
+impl AutomationCycle {
+    /// Begin a block; None while the transport is stopped
+    ///
+    /// 再生位置はここで 1 ブロック分進める。録音中でなく全レーンの終端を過ぎたら停止する。
+    pub fn begin(frames: usize, sample_rate: f64) -> Option<Self> {
+        if !PLAYING.load(Ordering::Relaxed) {
+            set_block_position(None);
+            return None;
+        }
+        let lanes = LANES.load();
+        let start_ms = position_ms();
+        set_block_position(Some(start_ms));
+        let block_ms = frames as f64 * 1000.0 / sample_rate;
+        POSITION_MS.store((start_ms + block_ms).to_bits(), Ordering::Relaxed);
+
+        if !RECORDING.load(Ordering::Relaxed) && lanes.iter().all(|l| l.duration_ms() < start_ms) {
+            PLAYING.store(false, Ordering::Relaxed);
+        }
+        Some(Self {
+            lanes,
+            start_ms,
+            frames,
+            sample_rate,
+        })
+    }
+
+    /// Gain segments of an edge for this block (0 = not automated)
+    pub fn segments(&self, edge: EdgeId, out: &mut [GainSegment; MAX_BLOCK_SEGMENTS]) -> usize {
+        match self.lanes.iter().find(|l| l.edge == edge && !l.writing) {
+            Some(lane) => lane.block_segments(self.start_ms, self.frames, self.sample_rate, out),
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lane(points: &[(f64, f32)]) -> AutomationLane {
+        let points: Vec<Breakpoint> = points
+            .iter()
+            .map(|&(time_ms, gain)| Breakpoint { time_ms, gain })
+            .collect();
+        AutomationLane::new(EdgeId::from(1), &points).unwrap()
+    }
+
+    #[test]
+    fn test_value_interpolates_and_holds() {
+        let l = lane(&[(100.0, 1.0), (0.0, 0.0)]);
+        assert_eq!(l.value_at(-5.0), 0.0);
+        assert!((l.value_at(50.0) - 0.5).abs() < 1e-6);
+        assert_eq!(l.value_at(500.0), 1.0);
+        assert_eq!(l.duration_ms(), 100.0);
+    }
+
+    #[test]
+    fn test_empty_lane_is_rejected() {
+        assert!(AutomationLane::new(EdgeId::from(1), &[]).is_none());
+        let nan = Breakpoint {
+            time_ms: f64::NAN,
+            gain: 1.0,
+        };
+        assert!(AutomationLane::new(EdgeId::from(1), &[nan]).is_none());
+    }
+
+    #[test]
+    fn test_block_without_breakpoints_is_one_segment() {
+        // 1 sample = 1 ms
+        let l = lane(&[(0.0, 0.0), (1000.0, 1.0)]);
+        let mut segs = [GainSegment::default(); MAX_BLOCK_SEGMENTS];
+        let n = l.block_segments(100.0, 100, 1000.0, &mut segs);
+        assert_eq!(n, 1);
+        assert_eq!(segs[0].offset, 0);
+        assert_eq!(segs[0].len, 100);
+        assert!((segs[0].start - 0.1).abs() < 1e-6);
+        assert!((segs[0].end - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_block_splits_at_breakpoint_sample() {
+        let l = lane(&[(0.0, 0.0), (30.0, 1.0), (60.0, 0.5)]);
+        let mut segs = [GainSegment::default(); MAX_BLOCK_SEGMENTS];
+        let n = l.block_segments(20.0, 64, 1000.0, &mut segs);
+        assert_eq!(n, 3);
+        assert_eq!((segs[0].offset, segs[0].len), (0, 10));
+        assert!((segs[0].end - 1.0).abs() < 1e-6);
+        assert_eq!((segs[1].offset, segs[1].len), (10, 30));
+        assert!((segs[1].end - 0.5).abs() < 1e-6);
+        assert_eq!((segs[2].offset, segs[2].len), (40, 24));
+        assert_eq!(segs[2].end, 0.5);
+        assert_eq!(segs[..n].iter().map(|s| s.len).sum::<usize>(), 64);
+    }
+
+    #[test]
+    fn test_dense_breakpoints_fit_segment_limit() {
+        let points: Vec<(f64, f32)> = (0..100).map(|i| (i as f64, (i % 2) as f32)).collect();
+        let l = lane(&points);
+        let mut segs = [GainSegment::default(); MAX_BLOCK_SEGMENTS];
+        let n = l.block_segments(0.0, 100, 1000.0, &mut segs);
+        assert_eq!(n, MAX_BLOCK_SEGMENTS);
+        assert_eq!(segs[..n].iter().map(|s| s.len).sum::<usize>(), 100);
+    }
+
+    #[test]
+    fn test_write_replaces_curve_after_position() {
+        let mut l = lane(&[(0.0, 0.0), (100.0, 1.0), (200.0, 0.0)]);
+        l.write(150.0, 0.3);
+        l.write(160.0, 0.4);
+        let times: Vec<f64> = l.points().iter().map(|p| p.time_ms).collect();
+        assert_eq!(times, vec![0.0, 100.0, 150.0, 160.0]);
+        assert_eq!(l.value_at(300.0), 0.4);
+    }
+}
@@ -0,0 +1,705 @@
+//! Offline Bounce - Render the graph to a file faster than realtime
+//!
+//! 音声ファイル（ソースノードに割り当てたステム）をグラフに流し、指定したシンクの
+//! 出力を WAV / CAF (32-bit float) に書き出す。レンダリング中はライブ処理を止め、
+//! グラフのロックはブロックごとに取り直す（UI と watchdog を止めないため）。
+//!
+//! 出力はシンクまでの経路レイテンシ分だけ先頭を詰め、入力と時間軸を揃える。
+//!
+//! レンダリングはライブのグラフで行うので、ライブの状態は動かさない: 開始時にゲインランプ・
+//! 補正ディレイ・ノードの DSP 状態を脇に置いて終了時に戻し、開始時のスケジュールで処理する。
+//! グラフの変更とフェーダーの値はレンダリングに混ぜない。録音・ネットワーク・ループバックの
+//! ノードは処理せず、ダッキングとレンダリング位置も進めない。複製できない AudioUnit の状態は
+//! 開始時と終了時にリセットする。
+
+use super::node::{AudioNode, NodeHandle};
+use super::processor::GraphProcessor;
+use super::record::{write_header, RecordFormat};
+use super::sink::SinkNode;
+use super::source::{SourceId, SourceNode};
+use super::AudioGraph;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// オフライン処理のブロック長（フレーム数）
+pub(super) const BOUNCE_BLOCK_FRAMES: usize = 1024;
+
+/// 進捗を通知する間隔（ブロック数）
+pub(super) const PROGRESS_INTERVAL_BLOCKS: usize = 64;
+
+/// 最大テール長（秒）
+pub const MAX_TAIL_SECONDS: f32 = 60.0;
+
+/// Set by [`cancel`]; checked between blocks
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+// =============================================================================
+// Audio file reader
+// =============================================================================
+
+/// サンプルのエンコーディング
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SampleFormat {
+    bits: u16,
+    float: bool,
+    big_endian: bool,
+}
+
+impl SampleFormat {
+    fn new(bits: u16, float: bool, big_endian: bool) -> Result<Self, String> {
+        match (float, bits) {
+            (false, 16 | 24 | 32) | (true, 32 | 64) => Ok(Self {
+                bits,
+                float,
+                big_endian,
+            }),
+            _ => Err(format!(
+                "Unsupported sample format ({}-bit {})",
+                bits,
+                if float { "float" } else { "integer" }
+            )),
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        self.bits as usize / 8
+    }
+
+    fn decode(&self, b: &[u8]) -> f32 {
+        // リトルエンディアンに揃えてからデコード
+        let mut le = [0u8; 8];
+        let n = self.bytes();
+        le[..n].copy_from_slice(&b[..n]);
+        if self.big_endian {
+            le[..n].reverse();
+        }
+        match (self.float, self.bits) {
+            (true, 64) => f64::from_le_bytes(le) as f32,
+            (true, _) => f32::from_le_bytes([le[0], le[1], le[2], le[3]]),
+            (false, 16) => i16::from_le_bytes([le[0], le[1]]) as f32 / 32768.0,
+            (false, 24) => {
+                // 上位バイトに詰めて符号拡張
+                i32::from_le_bytes([0, le[0], le[1], le[2]]) as f32 / 2_147_483_648.0
+            }
+            (false, _) => i32::from_le_bytes([le[0], le[1], le[2], le[3]]) as f32 / 2_147_483_648.0,
+        }
+    }
+}
+
+/// WAV / CAF (LPCM) のストリーミングリーダー
+pub struct AudioFileReader {
+    reader: BufReader<File>,
+    channels: usize,
+    sample_rate: f64,
+    format: SampleFormat,
+    frames: u64,
+    frames_left: u64,
+    /// 読み込み用スクラッチ（インターリーブ）
+    bytes: Vec<u8>,
+}
+
+impl AudioFileReader {
+    /// Open a WAV or CAF file and position it at the first sample
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file =
+            File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let file_len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0u8; 4];
+        read_bytes(&mut reader, &mut magic)?;
+        let (channels, sample_rate, format, data_len) = match &magic {
+            b"RIFF" => parse_wav(&mut reader)?,
+            b"caff" => parse_caf(&mut reader)?,
+            _ => return Err(format!("{} is not a WAV or CAF file", path.display())),
+        };
+        if channels == 0 {
+            return Err(format!("{} has no channels", path.display()));
+        }
+
+        // サイズ未確定（書きかけ）のデータチャンクはファイル末尾まで読む
+        let position = reader.stream_position().map_err(|e| e.to_string())?;
+        let available = file_len.saturating_sub(position);
+        let data_len = data_len.map_or(available, |len| len.min(available));
+        let frames = data_len / (channels * format.bytes()) as u64;
+
+        Ok(Self {
+            reader,
+            channels,
+            sample_rate,
+            format,
+            frames,
+            frames_left: frames,
+            bytes: Vec::new(),
+        })
+    }
+
+    /// Channel count
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Sample rate (Hz)
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Length in frames
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// 最大 `frames` フレームを読み、チャンネルごとに `out` へ書く（読んだフレーム数を返す）
+    ///
+    /// `out` の各チャンネルは `frames` 以上の長さが必要。足りない分は 0 で埋める。
+    pub fn read(&mut self, out: &mut [Vec<f32>], frames: usize) -> Result<usize, String> {
+        let wanted = (frames as u64).min(self.frames_left) as usize;
+        let frame_bytes = self.channels * self.format.bytes();
+        self.bytes.resize(wanted * frame_bytes, 0);
+
+        let mut filled = 0;
+        while filled < self.bytes.len() {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(format!("Failed to read audio: {}", e)),
+            }
+        }
+        let read = filled / frame_bytes;
+        // 途中で切れたファイルはそこで終わり
+        self.frames_left = if read < wanted {
+            0
+        } else {
+            self.frames_left - read as u64
+        };
+
+        let sample_bytes = self.format.bytes();
+        for (ch, buf) in out.iter_mut().enumerate() {
+            let buf = &mut buf[..frames];
+            if ch >= self.channels {
+                buf.fill(0.0);
+                continue;
+            }
+            for (i, sample) in buf[..read].iter_mut().enumerate() {
+                let at = i * frame_bytes + ch * sample_bytes;
+                *sample = self.format.decode(&self.bytes[at..at + sample_bytes]);
+            }
+            buf[read..].fill(0.0);
+        }
+        Ok(read)
+    }
+}
+
+fn read_bytes(r: &mut impl Read, buf: &mut [u8]) -> Result<(), String> {
+    r.read_exact(buf)
+        .map_err(|e| format!("Truncated audio file header: {}", e))
+}
+
+fn skip_bytes(r: &mut (impl Read + Seek), len: u64) -> Result<(), String> {
+    r.seek(SeekFrom::Current(len as i64))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// RIFF WAVE（"RIFF" の直後から）: (channels, sample rate, format, data bytes)
+fn parse_wav(
+    r: &mut (impl Read + Seek),
+) -> Result<(usize, f64, SampleFormat, Option<u64>), String> {
+    let mut header = [0u8; 8];
+    read_bytes(r, &mut header)?;
+    if &header[4..8] != b"WAVE" {
+        return Err("Not a WAVE file".to_string());
+    }
+
+    let mut fmt: Option<(usize, f64, SampleFormat)> = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        read_bytes(r, &mut chunk)?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut body = vec![0u8; size as usize];
+                read_bytes(r, &mut body)?;
+                if body.len() < 16 {
+                    return Err("Invalid fmt chunk".to_string());
+                }
+                let u16_at = |i: usize| u16::from_le_bytes([body[i], body[i + 1]]);
+                let mut tag = u16_at(0);
+                // WAVE_FORMAT_EXTENSIBLE: サブフォーマット GUID の先頭 2 バイトが実際の形式
+                if tag == 0xFFFE && body.len() >= 26 {
+                    tag = u16_at(24);
+                }
+                let float = match tag {
+                    1 => false,
+                    3 => true,
+                    _ => return Err(format!("Unsupported WAV format tag {}", tag)),
+                };
+                let channels = u16_at(2) as usize;
+                let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]) as f64;
+                let format = SampleFormat::new(u16_at(14), float, false)?;
+                fmt = Some((channels, sample_rate, format));
+                if size % 2 == 1 {
+                    skip_bytes(r, 1)?;
+                }
+            }
+            b"data" => {
+                let (channels, sample_rate, format) =
+                    fmt.ok_or("WAV data chunk before fmt chunk")?;
+                // 0 = 録音中に落ちたファイル（ヘッダー未確定）
+                let len = (size > 0).then_some(size);
+                return Ok((channels, sample_rate, format, len));
+            }
+            _ => skip_bytes(r, size + size % 2)?,
+        }
+    }
+}
+
+/// Core Audio Format（"caff" の直後から）: (channels, sample rate, format, data bytes)
+fn parse_caf(
+    r: &mut (impl Read + Seek),
+) -> Result<(usize, f64, SampleFormat, Option<u64>), String> {
+    // version + flags
+    skip_bytes(r, 4)?;
+
+    let mut desc: Option<(usize, f64, SampleFormat)> = None;
+    loop {
+        let mut chunk = [0u8; 12];
+        read_bytes(r, &mut chunk)?;
+        let size = i64::from_be_bytes([
+            chunk[4], chunk[5], chunk[6], chunk[7], chunk[8], chunk[9], chunk[10], chunk[11],
+        ]);
+        match &chunk[0..4] {
+            b"desc" => {
+                let mut body = [0u8; 32];
+                read_bytes(r, &mut body)?;
+                let u32_at =
+                    |i: usize| u32::from_be_bytes([body[i], body[i + 1], body[i + 2], body[i + 3]]);
+                if &body[8..12] != b"lpcm" {
+                    return Err("Only linear PCM CAF files are supported".to_string());
+                }
+                let sample_rate = f64::from_be_bytes(body[0..8].try_into().unwrap_or_default());
+                // kCAFLinearPCMFormatFlagIsFloat = 1, kCAFLinearPCMFormatFlagIsLittleEndian = 2
+                let flags = u32_at(12);
+                let format = SampleFormat::new(u32_at(28) as u16, flags & 1 != 0, flags & 2 == 0)?;
+                desc = Some((u32_at(24) as usize, sample_rate, format));
+                skip_bytes(r, (size - 32).max(0) as u64)?;
+            }
+            b"data" => {
+                let (channels, sample_rate, format) =
+                    desc.ok_or("CAF data chunk before desc chunk")?;
+                // edit count
+                skip_bytes(r, 4)?;
+                // -1 = サイズ未確定（ファイル末尾まで）
+                let len = (size >= 4).then(|| size as u64 - 4);
+                return Ok((channels, sample_rate, format, len));
+            }
+            _ => skip_bytes(r, size.max(0) as u64)?,
+        }
+    }
+}
+
+// =============================================================================
+// Bounce
+// =============================================================================
+
+/// ソースノードに流す音声ファイル
+#[derive(Debug, Clone)]
+pub struct BounceInput {
+    pub path: PathBuf,
+    /// Capture source node that plays the file (its live input is replaced)
+    pub source: NodeHandle,
+}
+
+/// バウンス結果
+#[derive(Debug, Clone)]
+pub struct BounceReport {
+    pub path: PathBuf,
+    pub channels: usize,
+    pub frames: u64,
+    pub sample_rate: f64,
+    /// Path latency trimmed from the start of the file
+    pub latency_frames: u32,
+    pub cancelled: bool,
+}
+
+/// 入力ファイルとソースチャンネルの対応
+struct Binding {
+    source_id: SourceId,
+    input: usize,
+    channel: usize,
+}
+
+/// ライブ処理への復帰を保証する
+pub(super) struct OfflineGuard<'a>(&'a GraphProcessor);
+
+impl<'a> OfflineGuard<'a> {
+    /// Pause live processing for an offline render (fails if one is already running)
+    pub(super) fn begin(processor: &'a GraphProcessor) -> Result<Self, String> {
+        if !processor.begin_offline_render() {
+            return Err("An offline render is already running".to_string());
+        }
+        // ライブのテールがオフラインの頭に混ざらないように
+        crate::audio_unit::get_au_manager().reset_all();
+        CANCEL.store(false, Ordering::Release);
+        Ok(Self(processor))
+    }
+
+    /// Whether [`cancel`] was called since the last check
+    pub(super) fn cancelled(&self) -> bool {
+        CANCEL.swap(false, Ordering::AcqRel)
+    }
+
+    /// Render one block at `position` frames from the start of the inputs
+    ///
+    /// 凍結したバスがキャッシュを同じ位置から読めるよう、トランスポート位置も合わせる。
+    pub(super) fn render_block<R>(
+        &self,
+        inputs: &OfflineInputs,
+        position: u64,
+        frames: usize,
+        read: impl FnOnce(&AudioGraph) -> R,
+    ) -> R {
+        let sample_rate = super::engine_sample_rate();
+        super::automation::set_block_position(Some(position as f64 * 1000.0 / sample_rate));
+        self.0
+            .render_offline_block(frames, |id, samples| inputs.read_source(id, samples), read)
+    }
+}
+
+impl Drop for OfflineGuard<'_> {
+    fn drop(&mut self) {
+        super::automation::set_block_position(None);
+        // オフラインの音がプラグインのテール（リバーブなど）としてライブに漏れないように
+        crate::audio_unit::get_au_manager().reset_all();
+        self.0.end_offline_render();
+    }
+}
+
+/// オフライン処理に流す入力ファイル（ソースノードへの割り当て済み）
+pub(super) struct OfflineInputs {
+    readers: Vec<AudioFileReader>,
+    bindings: Vec<Binding>,
+    /// 今のブロック（入力ごと、チャンネルごと）
+    blocks: Vec<Vec<Vec<f32>>>,
+}
+
+impl OfflineInputs {
+    /// Check the graph (nothing recording, every input on a capture source) and open the files
+    pub(super) fn open(
+        processor: &GraphProcessor,
+        inputs: &[BounceInput],
+        sample_rate: f64,
+    ) -> Result<Self, String> {
+        let sources = processor.with_graph(|graph| {
+            if let Some(handle) = graph.record_nodes().find(|&h| {
+                graph
+                    .get_node(h)
+                    .and_then(|n| n.as_any().downcast_ref::<super::record::RecordNode>())
+                    .is_some_and(|r| r.is_recording())
+            }) {
+                return Err(format!(
+                    "Record node {} is recording; stop it before rendering offline",
+                    handle.raw()
+                ));
+            }
+            inputs
+                .iter()
+                .map(|input| {
+                    graph
+                        .get_node(input.source)
+                        .and_then(|n| n.as_any().downcast_ref::<SourceNode>())
+                        .map(port_source_ids)
+                        .ok_or_else(|| {
+                            format!("Node {} is not a capture source", input.source.raw())
+                        })
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })?;
+
+        let mut readers = Vec::with_capacity(inputs.len());
+        let mut bindings = Vec::new();
+        for (index, (input, port_ids)) in inputs.iter().zip(sources).enumerate() {
+            let reader = AudioFileReader::open(&input.path)?;
+            if (reader.sample_rate() - sample_rate).abs() > 0.5 {
+                return Err(format!(
+                    "{} is {} Hz but the engine runs at {} Hz",
+                    input.path.display(),
+                    reader.sample_rate(),
+                    sample_rate
+                ));
+            }
+            // モノラルのファイルはステレオソースの両ポートに流す
+            for (port, source_id) in port_ids.into_iter().enumerate() {
+                bindings.push(Binding {
+                    source_id,
+                    input: index,
+                    channel: port.min(reader.channels() - 1),
+                });
+            }
+            readers.push(reader);
+        }
+        let blocks = readers
+            .iter()
+            .map(|r| vec![vec![0.0; BOUNCE_BLOCK_FRAMES]; r.channels()])
+            .collect();
+        Ok(Self {
+            readers,
+            bindings,
+            blocks,
+        })
+    }
+
+    /// Length of the longest input (frames)
+    pub(super) fn frames(&self) -> u64 {
+        self.readers.iter().map(|r| r.frames()).max().unwrap_or(0)
+    }
+
+    /// Read the next `frames` (at most [`BOUNCE_BLOCK_FRAMES`]) of every file
+    pub(super) fn read_block(&mut self, frames: usize) -> Result<(), String> {
+        for (reader, block) in self.readers.iter_mut().zip(self.blocks.iter_mut()) {
+            reader.read(block, frames)?;
+        }
+        Ok(())
+    }
+
+    /// Samples of a source channel for the current block (silence if no file feeds it)
+    fn read_source(&self, id: &SourceId, samples: &mut [f32]) {
+        let n = samples.len().min(BOUNCE_BLOCK_FRAMES);
+        match self.bindings.iter().find(|b| &b.source_id == id) {
+            Some(b) => samples[..n].copy_from_slice(&self.blocks[b.input][b.channel][..n]),
+            None => samples[..n].fill(0.0),
+        }
+    }
+}
+
+/// Cancel the running bounce or freeze (a partial bounce file is kept)
+pub fn cancel(processor: &GraphProcessor) -> bool {
+    if !processor.is_rendering_offline() {
+        return false;
+    }
+    CANCEL.store(true, Ordering::Release);
+    true
+}
+
+/// The source IDs a source node's ports read (channel offset per port)
+fn port_source_ids(source: &SourceNode) -> Vec<SourceId> {
+    (0..source.output_port_count())
+        .map(|port| match source.source_id() {
+            SourceId::PrismChannel { channel } => SourceId::PrismChannel {
+                channel: channel.saturating_add(port as u8),
+            },
+            SourceId::InputDevice { device_id, channel } => SourceId::InputDevice {
+                device_id: *device_id,
+                channel: channel.saturating_add(port as u8),
+            },
+        })
+        .collect()
+}
+
+/// シンクの出力を検証し、(チャンネル数, 経路レイテンシ) を返す
+fn sink_layout(graph: &AudioGraph, sink: NodeHandle) -> Result<(usize, u32), String> {
+    let node = graph
+        .get_node(sink)
+        .filter(|n| n.as_any().is::<SinkNode>())
+        .ok_or_else(|| format!("Node {} is not a sink", sink.raw()))?;
+    let latency = graph
+        .path_latency_to(sink)
+        .saturating_add(node.latency_samples());
+    Ok((node.input_port_count(), latency))
+}
+
+/// 現在のグラフで入力ファイルをオフライン処理し、`sink` の出力を `out_path` に書き出す
+///
+/// 書き出し形式は拡張子で決まる（.caf 以外は WAV）。`progress` には
+/// (レンダリング済みフレーム数, 総フレーム数) が渡される。
+pub fn bounce(
+    processor: &GraphProcessor,
+    inputs: &[BounceInput],
+    sink: NodeHandle,
+    out_path: &Path,
+    tail_seconds: f32,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<BounceReport, String> {
+    if inputs.is_empty() {
+        return Err("Bounce needs at least one input file".to_string());
+    }
+    let sample_rate = super::engine_sample_rate();
+
+    // グラフの検証とソースの割り当て
+    let (channels, latency) = processor.with_graph(|graph| sink_layout(graph, sink))?;
+    let mut files = OfflineInputs::open(processor, inputs, sample_rate)?;
+
+    let tail = tail_seconds.clamp(0.0, MAX_TAIL_SECONDS) as f64;
+    let total = files.frames() + (tail * sample_rate) as u64;
+
+    let format = match out_path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => RecordFormat::parse(ext).unwrap_or(RecordFormat::Wav),
+        None => RecordFormat::Wav,
+    };
+    let file = File::create(out_path)
+        .map_err(|e| format!("Failed to create {}: {}", out_path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    write_header(&mut writer, format, sample_rate, channels as u16, 0)
+        .map_err(|e| format!("Failed to write header: {}", e))?;
+
+    let render = OfflineGuard::begin(processor)?;
+
+    log_info!(
+        "[Bounce] Rendering {} input(s) through sink {} ({} ch, {} frames + {} latency) -> {}",
+        inputs.len(),
+        sink.raw(),
+        channels,
+        total,
+        latency,
+        out_path.display()
+    );
+
+    let mut interleaved = vec![0.0f32; BOUNCE_BLOCK_FRAMES * channels];
+    let mut bytes = Vec::with_capacity(interleaved.len() * 4);
+
+    // レイテンシ分を余分に処理し、先頭の同じ長さを捨てる
+    let mut to_skip = latency as u64;
+    let mut remaining = total + latency as u64;
+    let mut rendered: u64 = 0;
+    let mut written: u64 = 0;
+    let mut blocks = 0usize;
+    let mut cancelled = false;
+
+    while remaining > 0 {
+        if render.cancelled() {
+            cancelled = true;
+            break;
+        }
+        let frames = (remaining as usize).min(BOUNCE_BLOCK_FRAMES);
+        files.read_block(frames)?;
+        render.render_block(&files, rendered, frames, |graph| {
+            interleave_sink(graph, sink, &mut interleaved[..frames * channels], channels)
+        })?;
+        rendered += frames as u64;
+
+        let skip = (to_skip as usize).min(frames);
+        to_skip -= skip as u64;
+        bytes.clear();
+        for s in &interleaved[skip * channels..frames * channels] {
+            bytes.extend_from_slice(&s.to_le_bytes());
+        }
+        writer
+            .write_all(&bytes)
+            .map_err(|e| format!("Failed to write {}: {}", out_path.display(), e))?;
+        written += (frames - skip) as u64;
+        remaining -= frames as u64;
+
+        blocks += 1;
+        if blocks.is_multiple_of(PROGRESS_INTERVAL_BLOCKS) {
+            progress(written, total);
+        }
+    }
+
+    // ヘッダーを実際の長さで書き直す
+    writer
+        .flush()
+        .and_then(|_| writer.seek(SeekFrom::Start(0)).map(|_| ()))
+        .and_then(|_| write_header(&mut writer, format, sample_rate, channels as u16, written))
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("Failed to finalize {}: {}", out_path.display(), e))?;
+    progress(written, total);
+
+    log_info!(
+        "[Bounce] {} {} ({} frames)",
+        if cancelled { "Cancelled" } else { "Finished" },
+        out_path.display(),
+        written
+    );
+
+    Ok(BounceReport {
+        path: out_path.to_path_buf(),
+        channels,
+        frames: written,
+        sample_rate,
+        latency_frames: latency,
+        cancelled,
+    })
+}
+
+/// シンクの入力（出力ゲイン適用後）をインターリーブして `out` に書く
+fn interleave_sink(
+    graph: &AudioGraph,
+    sink: NodeHandle,
+    out: &mut [f32],
+    channels: usize,
+) -> Result<(), String> {
+    let sink = graph
+        .get_node(sink)
+        .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+        .ok_or("Sink was removed during the bounce")?;
+    out.fill(0.0);
+    let frames = out.len() / channels.max(1);
+    for port in 0..channels {
+        let Some(samples) = sink.get_output_samples(port) else {
+            continue;
+        };
+        let gain = sink.output_gain_for_port(port);
+        for (i, s) in samples.iter().take(frames).enumerate() {
+            out[i * channels + port] = s * gain;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("spectrum_bounce_{}_{}", std::process::id(), name))
+    }
+
+    fn write_float_file(path: &Path, format: RecordFormat, channels: u16, samples: &[f32]) {
+        let mut file = File::create(path).unwrap();
+        let frames = samples.len() as u64 / channels as u64;
+        write_header(&mut file, format, 48000.0, channels, frames).unwrap();
+        for s in samples {
+            file.write_all(&s.to_le_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_recorded_wav_and_caf() {
+        let samples: Vec<f32> = (0..200).map(|i| i as f32 / 200.0).collect();
+        for format in [RecordFormat::Wav, RecordFormat::Caf] {
+            let path = temp_path(&format!("in.{}", format.extension()));
+            write_float_file(&path, format, 2, &samples);
+
+            let mut reader = AudioFileReader::open(&path).unwrap();
+            assert_eq!(reader.channels(), 2);
+            assert_eq!(reader.sample_rate(), 48000.0);
+            assert_eq!(reader.frames(), 100);
+
+            let mut out = vec![vec![0.0; 64]; 2];
+            assert_eq!(reader.read(&mut out, 64).unwrap(), 64);
+            assert_eq!(out[0][1], samples[2]);
+            assert_eq!(out[1][1], samples[3]);
+            // 残り 36 フレーム、後ろは 0 埋め
+            assert_eq!(reader.read(&mut out, 64).unwrap(), 36);
+            assert_eq!(out[1][35], samples[199]);
+            assert_eq!(out[0][36], 0.0);
+            assert_eq!(reader.read(&mut out, 64).unwrap(), 0);
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_decode_integer_pcm() {
+        let pcm16 = SampleFormat::new(16, false, false).unwrap();
+        assert_eq!(pcm16.decode(&(-16384i16).to_le_bytes()), -0.5);
+        let pcm24 = SampleFormat::new(24, false, false).unwrap();
+        assert_eq!(pcm24.decode(&[0x00, 0x00, 0x40]), 0.5);
+        assert_eq!(pcm24.decode(&[0x00, 0x00, 0xC0]), -0.5);
+        let be32 = SampleFormat::new(32, true, true).unwrap();
+        assert_eq!(be32.decode(&0.25f32.to_be_bytes()), 0.25);
+        assert!(SampleFormat::new(8, false, false).is_err());
+    }
+}
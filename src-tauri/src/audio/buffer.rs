@@ -2,6 +2,25 @@
 
 use super::MAX_FRAMES;
 use crate::vdsp::VDsp;
+use std::cell::Cell;
+
+thread_local! {
+    /// このスレッドで処理するバッファのピーク/RMS を計算するか
+    static METERING: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Turn peak/RMS caching on or off for buffers updated on this thread
+///
+/// オーディオスレッドがメーターを止めたノード・間引いたブロックで切り替える。
+/// 無効の間 `update_meters` / `update_peak` は計算せずレベルを 0 にする。
+pub fn set_metering(enabled: bool) {
+    METERING.set(enabled);
+}
+
+/// Whether peak/RMS caching is on for this thread
+pub fn metering() -> bool {
+    METERING.get()
+}
 
 /// モノラルオーディオバッファ
 pub struct AudioBuffer {
@@ -60,6 +79,54 @@ impl AudioBuffer {
         }
     }
 
+    /// Mix from a raw slice with gain: self += source * gain
+    pub fn mix_from_slice(&mut self, source: &[f32], gain: f32) {
+        let frames = self.valid_frames.min(source.len());
+        if frames > 0 && gain.abs() > 0.0001 {
+            VDsp::mix_add(&source[..frames], gain, &mut self.data[..frames]);
+        }
+    }
+
+    /// Mix from a raw slice with a linear gain ramp: self += source * ramp(start -> end)
+    pub fn mix_from_slice_ramp(&mut self, source: &[f32], start: f32, end: f32) {
+        let frames = self.valid_frames.min(source.len());
+        if frames == 0 {
+            return;
+        }
+        if (end - start).abs() <= f32::EPSILON {
+            self.mix_from_slice(&source[..frames], end);
+        } else {
+            VDsp::mix_add_ramp(&source[..frames], start, end, &mut self.data[..frames]);
+        }
+    }
+
+    /// Mix `len` samples with a gain ramp starting at `offset`: self[offset..] += source * ramp
+    pub fn mix_from_slice_ramp_at(
+        &mut self,
+        offset: usize,
+        source: &[f32],
+        len: usize,
+        start: f32,
+        end: f32,
+    ) {
+        let frames = len
+            .min(source.len())
+            .min(self.valid_frames.saturating_sub(offset));
+        if frames == 0 {
+            return;
+        }
+        let target = &mut self.data[offset..offset + frames];
+        if (end - start).abs() <= f32::EPSILON {
+            if start.abs() > 0.0001 {
+                VDsp::mix_add(&source[..frames], start, target);
+            }
+        } else {
+            // ランプは len サンプルで end に達する（途中で切れても傾きは変えない）
+            let end = start + (end - start) * frames as f32 / len as f32;
+            VDsp::mix_add_ramp(&source[..frames], start, end, target);
+        }
+    }
+
     /// Copy from another buffer
     pub fn copy_from(&mut self, source: &AudioBuffer) {
         let frames = self.valid_frames.min(source.valid_frames);
@@ -84,6 +151,10 @@ impl AudioBuffer {
 
     /// Update peak cache
     pub fn update_peak(&mut self) {
+        if !metering() {
+            self.peak = 0.0;
+            return;
+        }
         self.peak = VDsp::peak(&self.data[..self.valid_frames]);
     }
 
@@ -100,6 +171,11 @@ impl AudioBuffer {
 
     /// Update both peak and RMS caches
     pub fn update_meters(&mut self) {
+        if !metering() {
+            self.peak = 0.0;
+            self.rms = 0.0;
+            return;
+        }
         let samples = &self.data[..self.valid_frames];
         self.peak = VDsp::peak(samples);
         self.rms = VDsp::rms(samples);
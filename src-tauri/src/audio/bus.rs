@@ -1,10 +1,79 @@
 //! Bus Node - Effects bus with plugin chain
 
 use super::buffer::AudioBuffer;
+use super::dsp::{is_native_plugin_id, NativeProcessor, NativeSettings};
+use super::dsp_load::LoadMeter;
+use super::edge::DelayLine;
+use super::freeze::FrozenAudio;
 use super::node::{AudioNode, NodeType, PortId};
+use super::reclaim::{self, Retired};
 use crate::audio_unit::{get_au_manager, AudioUnitInstance};
+use crate::logging::Level;
 use std::any::Any;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Bypass crossfade length (ms)
+pub const BYPASS_FADE_MS: f32 = 10.0;
+
+/// Tail output below this level counts as silence (-80 dBFS)
+const TAIL_SILENCE: f32 = 1e-4;
+
+/// How long a tail must stay silent before the plugin stops (ms)
+const TAIL_HOLD_MS: f64 = 200.0;
+
+/// Upper bound for ringing out a tail (seconds)
+const MAX_TAIL_SECONDS: f64 = 30.0;
+
+/// How a plugin's channels map onto the bus ports
+///
+/// AU はバスの幅に合わせて設定する（`AudioUnitManager::negotiate_layout`）。
+/// 1ポートのバスはモノラル、3ポート以上はステレオペアごとに別インスタンスで処理する
+/// （奇数ポートの最後の1本は素通し）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PluginLayout {
+    /// Runs at the bus width (mono or stereo)
+    #[default]
+    Native,
+    /// One stereo instance per port pair
+    StereoPairs,
+    /// The plugin can't run at the bus width; audio passes through
+    Bypassed,
+}
+
+impl PluginLayout {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::StereoPairs => "stereo_pairs",
+            Self::Bypassed => "bypassed",
+        }
+    }
+}
+
+/// A tail being rung out after bypass
+#[derive(Debug, Clone, Copy, Default)]
+struct TailState {
+    /// Frames rendered since the input was muted
+    rendered: usize,
+    /// Consecutive frames below [`TAIL_SILENCE`]
+    quiet: usize,
+}
+
+/// Processing state of a plugin slot set aside during an offline render
+///
+/// AudioUnit の内部状態は複製できないので含めない（レンダリングの前後でリセットする）。
+struct PluginState {
+    instance_id: String,
+    /// Whether the plugin was enabled when the state was taken
+    enabled: bool,
+    applied_mix: f32,
+    bypass_gain: f32,
+    tail: Option<TailState>,
+    native: Option<NativeProcessor>,
+    native_pairs: Vec<NativeProcessor>,
+    dry_delays: Vec<DelayLine>,
+}
 
 /// Plugin instance info with AudioUnit integration
 pub struct PluginInstance {
@@ -13,8 +82,38 @@ pub struct PluginInstance {
     pub name: String,
     pub manufacturer: String,
     pub enabled: bool,
+    /// Wet/dry mix (0.0 = dry only, 1.0 = wet only)
+    mix: f32,
+    /// Mix applied in the last block (ramped toward `mix` to avoid zipper noise)
+    applied_mix: f32,
     /// Cached AudioUnit instance for lock-free audio processing
     au_instance: Option<Arc<AudioUnitInstance>>,
+    /// Built-in processor (`native:*` plugin_id); AU の代わりに使う
+    native: Option<NativeProcessor>,
+    /// Channel layout on the bus
+    layout: PluginLayout,
+    /// Bus width `layout` was chosen for (0 = negotiation pending)
+    layout_ports: usize,
+    /// Extra AudioUnit instances for ports 2/3, 4/5, ... (`StereoPairs`)
+    pair_instances: Vec<Arc<AudioUnitInstance>>,
+    /// Extra native processors for ports 2/3, 4/5, ...
+    native_pairs: Vec<NativeProcessor>,
+    /// Bypass crossfade position (1.0 = processing, 0.0 = bypassed)
+    bypass_gain: f32,
+    /// On bypass, fade the plugin's input instead of its output and let the tail ring out
+    ring_out: bool,
+    /// Tail still ringing after a `ring_out` bypass
+    tail: Option<TailState>,
+    /// Render time of this slot in the chain (including its dry/wet mix)
+    load: LoadMeter,
+    /// Delay of the dry copy matching the plugin's latency (one line per port)
+    ///
+    /// トポロジーのコンパイル時に制御スレッドで作り、`install` で差し替える
+    /// （[`BusNode::swap_dry_delays`]）。レイテンシが無ければ空。
+    dry_delays: Vec<DelayLine>,
+    /// Test stand-in for a latent AudioUnit: delays the wet path by its lines' delay
+    #[cfg(test)]
+    latent_stub: Option<Vec<DelayLine>>,
 }
 
 impl std::fmt::Debug for PluginInstance {
@@ -25,10 +124,14 @@ impl std::fmt::Debug for PluginInstance {
             .field("name", &self.name)
             .field("manufacturer", &self.manufacturer)
             .field("enabled", &self.enabled)
+            .field("mix", &self.mix)
             .field(
                 "au_instance",
                 &self.au_instance.as_ref().map(|_| "AudioUnitInstance"),
             )
+            .field("native", &self.native.as_ref().map(|n| n.settings()))
+            .field("layout", &self.layout)
+            .field("ring_out", &self.ring_out)
             .finish()
     }
 }
@@ -41,8 +144,23 @@ impl Clone for PluginInstance {
             name: self.name.clone(),
             manufacturer: self.manufacturer.clone(),
             enabled: self.enabled,
+            mix: self.mix,
+            applied_mix: self.applied_mix,
             // Re-fetch from manager to get Arc clone
             au_instance: get_au_manager().get_instance(&self.instance_id),
+            native: self.native.clone(),
+            layout: self.layout,
+            layout_ports: self.layout_ports,
+            pair_instances: self.pair_instances.clone(),
+            native_pairs: self.native_pairs.clone(),
+            bypass_gain: self.bypass_gain,
+            ring_out: self.ring_out,
+            tail: self.tail,
+            load: LoadMeter::new(),
+            // 次のコンパイルで作り直される
+            dry_delays: Vec::new(),
+            #[cfg(test)]
+            latent_stub: None,
         }
     }
 }
@@ -50,31 +168,218 @@ impl Clone for PluginInstance {
 impl PluginInstance {
     /// Create a new plugin instance
     pub fn new(instance_id: String, plugin_id: String, name: String, manufacturer: String) -> Self {
+        // Native processors don't live in the AU manager
+        let native = if is_native_plugin_id(&plugin_id) {
+            NativeProcessor::from_plugin_id(&plugin_id)
+        } else {
+            None
+        };
         // Try to get the AudioUnit instance from the manager
-        let au_instance = get_au_manager().get_instance(&instance_id);
+        let au_instance = if native.is_none() {
+            get_au_manager().get_instance(&instance_id)
+        } else {
+            None
+        };
         Self {
             instance_id,
             plugin_id,
             name,
             manufacturer,
             enabled: true,
+            mix: 1.0,
+            applied_mix: 1.0,
             au_instance,
+            native,
+            // 生成時の AU はステレオ設定
+            layout: PluginLayout::Native,
+            layout_ports: 2,
+            pair_instances: Vec::new(),
+            native_pairs: Vec::new(),
+            bypass_gain: 1.0,
+            ring_out: false,
+            tail: None,
+            load: LoadMeter::new(),
+            dry_delays: Vec::new(),
+            #[cfg(test)]
+            latent_stub: None,
+        }
+    }
+
+    /// Built-in processor, if this is a `native:*` plugin
+    pub fn native(&self) -> Option<&NativeProcessor> {
+        self.native.as_ref()
+    }
+
+    /// Replace the settings of a built-in processor (every port pair)
+    pub fn set_native_settings(&self, settings: NativeSettings) -> Result<(), String> {
+        let native = self
+            .native
+            .as_ref()
+            .ok_or_else(|| format!("Plugin {} is not a native processor", self.instance_id))?;
+        native.set_settings(settings)?;
+        for pair in &self.native_pairs {
+            pair.set_settings(settings)?;
+        }
+        Ok(())
+    }
+
+    /// Channel layout on the bus
+    pub fn layout(&self) -> PluginLayout {
+        self.layout
+    }
+
+    /// Whether the layout was chosen for a `ports`-wide bus
+    pub fn fits_ports(&self, ports: usize) -> bool {
+        self.native.is_some() || self.layout_ports == ports
+    }
+
+    /// Record the layout negotiated for a `ports`-wide bus (AudioUnits)
+    ///
+    /// `StereoPairs` picks up the pair instances from the AU manager.
+    pub fn set_layout(&mut self, layout: PluginLayout, ports: usize) {
+        if self.native.is_some() {
+            return;
         }
+        self.pair_instances = match layout {
+            PluginLayout::StereoPairs => get_au_manager().pair_instances(&self.instance_id),
+            _ => Vec::new(),
+        };
+        self.layout = layout;
+        self.layout_ports = ports;
+    }
+
+    /// Fit a built-in processor to a `ports`-wide bus (one processor per port pair)
+    fn fit_native(&mut self, ports: usize) {
+        let Some(native) = self.native.as_mut() else {
+            return;
+        };
+        native.reset();
+        let extra = (ports / 2).saturating_sub(1);
+        self.native_pairs = (0..extra).map(|_| native.clone()).collect();
+        self.layout = if extra > 0 {
+            PluginLayout::StereoPairs
+        } else {
+            PluginLayout::Native
+        };
+        self.layout_ports = ports;
+    }
+
+    /// Number of port pairs this plugin processes
+    fn pair_count(&self) -> usize {
+        1 + self.native_pairs.len().max(self.pair_instances.len())
+    }
+
+    /// Whether the plugin processes audio at all
+    fn is_active(&self) -> bool {
+        self.enabled && self.layout != PluginLayout::Bypassed
+    }
+
+    /// Whether the plugin still renders (enabled, fading out, or ringing out a tail)
+    fn is_running(&self) -> bool {
+        self.layout != PluginLayout::Bypassed
+            && (self.enabled || self.bypass_gain > 0.0 || self.tail.is_some())
+    }
+
+    /// Whether bypass lets the tail ring out
+    pub fn ring_out(&self) -> bool {
+        self.ring_out
+    }
+
+    /// Enable or bypass (see [`BusNode::set_plugin_enabled`])
+    fn set_enabled(&mut self, enabled: bool) {
+        if enabled && !self.enabled {
+            // 完全にバイパスされていたなら古いフィルタ状態を持ち越さない
+            // （フェード中/テール中の再有効化はそのまま続ける）
+            if !self.is_running() {
+                for native in self.native.iter_mut().chain(self.native_pairs.iter_mut()) {
+                    native.reset();
+                }
+            }
+            self.tail = None;
+        } else if !enabled && self.enabled && self.ring_out {
+            self.tail = Some(TailState::default());
+        }
+        self.enabled = enabled;
+    }
+
+    /// Set the wet/dry mix (clamped to 0.0..=1.0; NaN means fully wet)
+    fn set_mix(&mut self, mix: f32) {
+        self.mix = if mix.is_finite() {
+            mix.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+    }
+
+    /// Render time of this plugin (ratio of the buffer period)
+    pub fn load(&self) -> &LoadMeter {
+        &self.load
+    }
+
+    /// Move the bypass crossfade toward the enabled state; returns (start, end) for this block
+    fn advance_bypass(&mut self, frames: usize, sample_rate: f64) -> (f32, f32) {
+        let start = self.bypass_gain;
+        let fade_frames = (BYPASS_FADE_MS as f64 * sample_rate / 1000.0).max(1.0);
+        let step = (frames as f64 / fade_frames) as f32;
+        self.bypass_gain = if self.enabled {
+            (start + step).min(1.0)
+        } else {
+            (start - step).max(0.0)
+        };
+        (start, self.bypass_gain)
+    }
+
+    /// Track a ringing tail (`peak` = plugin output with a silent input)
+    fn follow_tail(&mut self, peak: f32, frames: usize, sample_rate: f64) {
+        let Some(tail) = self.tail.as_mut() else {
+            return;
+        };
+        tail.rendered += frames;
+        tail.quiet = if peak < TAIL_SILENCE {
+            tail.quiet + frames
+        } else {
+            0
+        };
+        if tail.quiet as f64 >= TAIL_HOLD_MS * sample_rate / 1000.0
+            || tail.rendered as f64 >= MAX_TAIL_SECONDS * sample_rate
+        {
+            self.tail = None;
+        }
+    }
+
+    /// Wet/dry mix (0.0..=1.0)
+    pub fn mix(&self) -> f32 {
+        self.mix
+    }
+
+    /// Whether the dry signal is needed for this block
+    fn needs_dry(&self) -> bool {
+        self.mix < 1.0 || self.applied_mix < 1.0
     }
 
     /// Process audio through this plugin
     ///
     /// Returns true if processing was applied, false if bypassed/disabled
-    pub fn process(&self, left: &mut [f32], right: &mut [f32]) -> bool {
-        if !self.enabled {
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) -> bool {
+        if !self.is_running() {
             return false;
         }
 
-        if let Some(ref au) = self.au_instance {
+        #[cfg(test)]
+        if let Some([l, r]) = self.latent_stub.as_deref_mut() {
+            left.copy_from_slice(l.process(left));
+            right.copy_from_slice(r.process(right));
+            return true;
+        }
+
+        if let Some(native) = self.native.as_mut() {
+            native.process(left, right);
+            true
+        } else if let Some(ref au) = self.au_instance {
             // Process through AudioUnit
-            if let Err(e) = au.process(left, right, 0.0) {
-                // Log but don't fail - just bypass
-                eprintln!("[BusNode] Plugin {} process error: {}", self.instance_id, e);
+            if au.process(left, right, 0.0).is_err() {
+                // Log but don't fail - just bypass (audio thread: no formatting here)
+                rt_log!(Level::Error, "[BusNode] Plugin process error");
                 return false;
             }
             true
@@ -83,9 +388,160 @@ impl PluginInstance {
         }
     }
 
+    /// Process port pair `pair` (0 is the first two ports)
+    ///
+    /// Returns true if processing was applied, false if bypassed/disabled
+    pub fn process_pair(&mut self, pair: usize, left: &mut [f32], right: &mut [f32]) -> bool {
+        if pair == 0 {
+            return self.process(left, right);
+        }
+        if !self.is_running() {
+            return false;
+        }
+
+        if let Some(native) = self.native_pairs.get_mut(pair - 1) {
+            native.process(left, right);
+            true
+        } else if let Some(au) = self.pair_instances.get(pair - 1) {
+            if au.process(left, right, 0.0).is_err() {
+                rt_log!(Level::Error, "[BusNode] Plugin process error");
+                return false;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Processing latency of this plugin in samples (0 when bypassed)
+    pub fn latency_samples(&self) -> u32 {
+        if !self.is_active() {
+            return 0;
+        }
+        self.wet_latency()
+    }
+
+    /// Latency of the wet path whenever the plugin renders (also while fading out)
+    fn wet_latency(&self) -> u32 {
+        #[cfg(test)]
+        if let Some(stub) = &self.latent_stub {
+            return stub.first().map_or(0, |l| l.delay() as u32);
+        }
+        self.au_instance
+            .as_ref()
+            .map(|au| au.latency_samples())
+            .unwrap_or(0)
+    }
+
+    /// Dry delay wanted for a `ports`-wide bus: (delay, line count); (0, 0) for none
+    fn wanted_dry_delay(&self, ports: usize) -> (usize, usize) {
+        let latency = DelayLine::clamp_delay(self.wet_latency() as usize);
+        if latency == 0 || self.layout == PluginLayout::Bypassed {
+            return (0, 0);
+        }
+        (latency, ports)
+    }
+
+    /// Current dry delay: (delay, line count)
+    fn dry_delay_state(&self) -> (usize, usize) {
+        (
+            self.dry_delays.first().map_or(0, |l| l.delay()),
+            self.dry_delays.len(),
+        )
+    }
+
+    /// Swap the processing state for a settled, fresh one (offline render)
+    ///
+    /// ミックスとバイパスのフェードは目標値に揃え、テールは止め、ネイティブプロセッサーと
+    /// ドライのディレイは同じ設定の新しいものにする。
+    fn take_live_state(&mut self) -> PluginState {
+        let settled = if self.enabled { 1.0 } else { 0.0 };
+        let fresh_pairs = self.native_pairs.clone();
+        let fresh_dry = self
+            .dry_delays
+            .iter()
+            .map(|l| DelayLine::with_delay(l.delay()))
+            .collect();
+        PluginState {
+            instance_id: self.instance_id.clone(),
+            enabled: self.enabled,
+            applied_mix: std::mem::replace(&mut self.applied_mix, self.mix),
+            bypass_gain: std::mem::replace(&mut self.bypass_gain, settled),
+            tail: self.tail.take(),
+            native: self.native.as_mut().map(|native| {
+                let fresh = native.clone();
+                std::mem::replace(native, fresh)
+            }),
+            native_pairs: std::mem::replace(&mut self.native_pairs, fresh_pairs),
+            dry_delays: std::mem::replace(&mut self.dry_delays, fresh_dry),
+        }
+    }
+
+    /// Put back the state set aside by [`Self::take_live_state`]
+    ///
+    /// レンダリング中の設定変更は引き継ぐ。幅やレイテンシが変わって合わなくなった部分は
+    /// 新しい方を使う。
+    fn restore_live_state(&mut self, state: PluginState) {
+        self.applied_mix = state.applied_mix;
+        self.bypass_gain = state.bypass_gain;
+        if state.enabled == self.enabled {
+            self.tail = state.tail;
+        }
+        if let (Some(live), Some(settings)) = (state.native, self.native().map(|n| n.settings())) {
+            if live.set_settings(settings).is_ok() {
+                self.native = Some(live);
+            }
+            if state.native_pairs.len() == self.native_pairs.len()
+                && state
+                    .native_pairs
+                    .iter()
+                    .all(|pair| pair.set_settings(settings).is_ok())
+            {
+                self.native_pairs = state.native_pairs;
+            }
+        }
+        let live_delay = (
+            state.dry_delays.first().map_or(0, |l| l.delay()),
+            state.dry_delays.len(),
+        );
+        if live_delay == self.dry_delay_state() {
+            self.dry_delays = state.dry_delays;
+        }
+    }
+
+    /// Whether the AudioUnit's process died (the plugin is bypassed)
+    pub fn is_crashed(&self) -> bool {
+        self.au_instance
+            .as_ref()
+            .map(|au| au.is_crashed())
+            .unwrap_or(false)
+    }
+
+    /// Whether the AudioUnit kept overrunning its render budget (the plugin is bypassed)
+    pub fn is_overloaded(&self) -> bool {
+        self.au_instance
+            .iter()
+            .chain(self.pair_instances.iter())
+            .any(|au| au.is_overloaded())
+    }
+
+    /// Whether the AudioUnit runs out-of-process
+    pub fn is_out_of_process(&self) -> bool {
+        self.au_instance
+            .as_ref()
+            .map(|au| au.is_out_of_process())
+            .unwrap_or(false)
+    }
+
     /// Refresh the AudioUnit instance reference
     pub fn refresh_au_instance(&mut self) {
+        if self.native.is_some() {
+            return;
+        }
         self.au_instance = get_au_manager().get_instance(&self.instance_id);
+        if self.layout == PluginLayout::StereoPairs {
+            self.pair_instances = get_au_manager().pair_instances(&self.instance_id);
+        }
     }
 }
 
@@ -104,6 +560,11 @@ pub struct BusNode {
     output_buffers: Vec<AudioBuffer>,
     /// プラグインチェーン (TODO: AudioUnit integration)
     plugin_chain: Vec<PluginInstance>,
+    /// Dry copy (one per port, at least 2) for plugins with mix < 1.0; preallocated
+    /// for the audio thread. 1ポートのバスでは 2 本目をモノラル処理の R に使う
+    dry_buffers: Vec<AudioBuffer>,
+    /// 凍結中のキャッシュ（プラグインを呼ばずにトランスポート位置から再生する）
+    frozen: Option<Arc<FrozenAudio>>,
 }
 
 impl BusNode {
@@ -116,6 +577,8 @@ impl BusNode {
             input_buffers: (0..port_count).map(|_| AudioBuffer::new()).collect(),
             output_buffers: (0..port_count).map(|_| AudioBuffer::new()).collect(),
             plugin_chain: Vec::new(),
+            dry_buffers: (0..port_count.max(2)).map(|_| AudioBuffer::new()).collect(),
+            frozen: None,
         }
     }
 
@@ -134,6 +597,100 @@ impl BusNode {
         self.label = label.into();
     }
 
+    /// Grow or shrink the bus to `port_count` ports (at least 1)
+    ///
+    /// 残るポートのバッファはそのまま使う。ネイティブプロセッサーは新しい幅に合わせて
+    /// 作り直す。AudioUnit は呼び出し側で `negotiate_layout` し直す（[`Self::unfitted_plugins`]）。
+    /// Returns false if the port count did not change.
+    pub fn set_port_count(&mut self, port_count: usize) -> bool {
+        let port_count = port_count.max(1);
+        if port_count == self.input_buffers.len() {
+            return false;
+        }
+        self.input_buffers.resize_with(port_count, AudioBuffer::new);
+        self.output_buffers
+            .resize_with(port_count, AudioBuffer::new);
+        self.dry_buffers
+            .resize_with(port_count.max(2), AudioBuffer::new);
+        for plugin in &mut self.plugin_chain {
+            plugin.fit_native(port_count);
+        }
+        true
+    }
+
+    /// Play `audio` instead of running the plugin chain
+    pub fn freeze(&mut self, audio: Arc<FrozenAudio>) {
+        self.frozen = Some(audio);
+    }
+
+    /// Run the plugin chain again; returns the cache that was playing
+    pub fn unfreeze(&mut self) -> Option<Arc<FrozenAudio>> {
+        self.frozen.take()
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    pub fn frozen_audio(&self) -> Option<&Arc<FrozenAudio>> {
+        self.frozen.as_ref()
+    }
+
+    /// Output the frozen cache at `position_ms` (silence while the transport is stopped)
+    ///
+    /// 入力は使わない（送りのメーター用にピークだけ更新する）。
+    fn play_frozen(&mut self, position_ms: Option<f64>, frames: usize) {
+        let Some(audio) = &self.frozen else {
+            return;
+        };
+        let start = position_ms.map(|ms| (ms * audio.sample_rate() / 1000.0).round() as usize);
+        for (port, out) in self.output_buffers.iter_mut().enumerate() {
+            match start {
+                Some(start) => {
+                    out.set_valid_frames(frames);
+                    audio.read(port, start, out.samples_mut());
+                }
+                None => out.clear(frames),
+            }
+            out.update_meters();
+        }
+        for buf in &mut self.input_buffers {
+            buf.update_peak();
+        }
+    }
+
+    /// AudioUnits whose layout was not negotiated for the current port count
+    pub fn unfitted_plugins(&self) -> Vec<String> {
+        let ports = self.output_buffers.len();
+        self.plugin_chain
+            .iter()
+            .filter(|p| !p.fits_ports(ports))
+            .map(|p| p.instance_id.clone())
+            .collect()
+    }
+
+    /// Record the negotiated layout of a plugin (see [`PluginInstance::set_layout`])
+    ///
+    /// Returns true if the instance was found.
+    pub fn set_plugin_layout(
+        &mut self,
+        instance_id: &str,
+        layout: PluginLayout,
+        ports: usize,
+    ) -> bool {
+        match self
+            .plugin_chain
+            .iter_mut()
+            .find(|p| p.instance_id == instance_id)
+        {
+            Some(p) => {
+                p.set_layout(layout, ports);
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get plugin chain
     pub fn plugins(&self) -> &[PluginInstance] {
         &self.plugin_chain
@@ -147,21 +704,83 @@ impl BusNode {
         name: String,
         manufacturer: String,
     ) {
-        self.plugin_chain.push(PluginInstance::new(
-            instance_id,
-            plugin_id,
-            name,
-            manufacturer,
-        ));
+        let mut plugin = PluginInstance::new(instance_id, plugin_id, name, manufacturer);
+        if self.output_buffers.len() != 2 {
+            plugin.fit_native(self.output_buffers.len());
+        }
+        self.plugin_chain.push(plugin);
+    }
+
+    /// Remove a plugin from the chain (false if it is not in this bus)
+    ///
+    /// 外したスロットは [`reclaim`](super::reclaim) スレッドで drop する
+    /// （AudioUnit インスタンスの最後の参照を持っていることがある）。
+    pub fn remove_plugin(&mut self, instance_id: &str) -> bool {
+        let Some(pos) = self
+            .plugin_chain
+            .iter()
+            .position(|p| p.instance_id == instance_id)
+        else {
+            return false;
+        };
+        reclaim::retire(Retired::Plugin(self.plugin_chain.remove(pos)));
+        true
     }
 
-    /// Remove a plugin from the chain
-    pub fn remove_plugin(&mut self, instance_id: &str) -> Option<PluginInstance> {
-        let pos = self
+    /// Move a plugin to `position` in the chain (clamped to the end; no allocation)
+    pub fn move_plugin(&mut self, instance_id: &str, position: usize) -> bool {
+        let Some(from) = self
             .plugin_chain
             .iter()
-            .position(|p| p.instance_id == instance_id)?;
-        Some(self.plugin_chain.remove(pos))
+            .position(|p| p.instance_id == instance_id)
+        else {
+            return false;
+        };
+        let to = position.min(self.plugin_chain.len() - 1);
+        if from < to {
+            self.plugin_chain[from..=to].rotate_left(1);
+        } else {
+            self.plugin_chain[to..=from].rotate_right(1);
+        }
+        true
+    }
+
+    /// Slots whose dry delay does not match their latency: (slot, (delay, line count))
+    ///
+    /// オーディオスレッドから呼ばれる（確保しない）。
+    pub(super) fn dry_delay_changes(&self) -> impl Iterator<Item = (usize, (usize, usize))> + '_ {
+        let ports = self.output_buffers.len();
+        self.plugin_chain
+            .iter()
+            .enumerate()
+            .filter_map(move |(slot, plugin)| {
+                let wanted = plugin.wanted_dry_delay(ports);
+                (wanted != plugin.dry_delay_state()).then_some((slot, wanted))
+            })
+    }
+
+    /// Swap in the dry delay lines of a slot (the old ones end up in `lines`)
+    pub(super) fn swap_dry_delays(&mut self, slot: usize, lines: &mut Vec<DelayLine>) {
+        if let Some(plugin) = self.plugin_chain.get_mut(slot) {
+            std::mem::swap(&mut plugin.dry_delays, lines);
+        }
+    }
+
+    /// Re-resolve a plugin's AudioUnit instance (after it was restarted)
+    ///
+    /// Returns true if the instance is in this bus.
+    pub fn refresh_plugin(&mut self, instance_id: &str) -> bool {
+        match self
+            .plugin_chain
+            .iter_mut()
+            .find(|p| p.instance_id == instance_id)
+        {
+            Some(p) => {
+                p.refresh_au_instance();
+                true
+            }
+            None => false,
+        }
     }
 
     /// Reorder plugins
@@ -179,19 +798,106 @@ impl BusNode {
 
     /// Enable/disable (bypass) a plugin instance in this bus.
     ///
+    /// 切り替えは [`BYPASS_FADE_MS`] のクロスフェードで行う。`ring_out` のプラグインは
+    /// 入力側をフェードし、テールが消えるまで処理を続ける。
     /// Returns true if the instance was found.
     pub fn set_plugin_enabled(&mut self, instance_id: &str, enabled: bool) -> bool {
-        if let Some(p) = self
+        match self
             .plugin_chain
             .iter_mut()
             .find(|p| p.instance_id == instance_id)
         {
-            p.enabled = enabled;
-            true
-        } else {
-            false
+            Some(p) => {
+                p.set_enabled(enabled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Let a plugin's tail ring out when it is bypassed (instead of fading its output)
+    ///
+    /// Returns true if the instance was found.
+    pub fn set_plugin_ring_out(&mut self, instance_id: &str, ring_out: bool) -> bool {
+        match self
+            .plugin_chain
+            .iter_mut()
+            .find(|p| p.instance_id == instance_id)
+        {
+            Some(p) => {
+                p.ring_out = ring_out;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the wet/dry mix of a plugin instance (clamped to 0.0..=1.0)
+    ///
+    /// ドライ側はプラグインのレイテンシ分だけ遅らせて wet と揃える。
+    /// Returns true if the instance was found.
+    pub fn set_plugin_mix(&mut self, instance_id: &str, mix: f32) -> bool {
+        match self
+            .plugin_chain
+            .iter_mut()
+            .find(|p| p.instance_id == instance_id)
+        {
+            Some(p) => {
+                p.set_mix(mix);
+                true
+            }
+            None => false,
         }
     }
+
+    /// Set ring-out, enabled and mix of the slot at `index` (false if there is none)
+    ///
+    /// 並び順で対応付ける呼び出し側（apply_graph）が id を複製せずに済むように。
+    pub fn set_plugin_state_at(
+        &mut self,
+        index: usize,
+        enabled: bool,
+        mix: f32,
+        ring_out: bool,
+    ) -> bool {
+        let Some(p) = self.plugin_chain.get_mut(index) else {
+            return false;
+        };
+        p.ring_out = ring_out;
+        p.set_enabled(enabled);
+        p.set_mix(mix);
+        true
+    }
+}
+
+/// Blend `wet` (in place) with `dry`, ramping the wet amount from `start` to `end`
+fn blend_dry_wet(wet: &mut [f32], dry: &[f32], start: f32, end: f32) {
+    blend_ramped(wet, dry, (start, end), (1.0 - start, 1.0 - end));
+}
+
+/// `wet = wet * w + dry * d`, ramping both gains across the block
+fn blend_ramped(wet: &mut [f32], dry: &[f32], wet_gain: (f32, f32), dry_gain: (f32, f32)) {
+    let frames = wet.len().min(dry.len());
+    if frames == 0 {
+        return;
+    }
+    let wet_step = (wet_gain.1 - wet_gain.0) / frames as f32;
+    let dry_step = (dry_gain.1 - dry_gain.0) / frames as f32;
+    for (i, (w, d)) in wet.iter_mut().zip(dry).take(frames).enumerate() {
+        let n = (i + 1) as f32;
+        *w = *w * (wet_gain.0 + wet_step * n) + *d * (dry_gain.0 + dry_step * n);
+    }
+}
+
+/// Ramp the level of `samples` from `start` to `end` (in place)
+fn ramp_gain(samples: &mut [f32], start: f32, end: f32) {
+    if samples.is_empty() {
+        return;
+    }
+    let step = (end - start) / samples.len() as f32;
+    for (i, s) in samples.iter_mut().enumerate() {
+        *s *= start + step * (i + 1) as f32;
+    }
 }
 
 impl AudioNode for BusNode {
@@ -228,6 +934,11 @@ impl AudioNode for BusNode {
     }
 
     fn process(&mut self, frames: usize) {
+        if self.frozen.is_some() {
+            self.play_frozen(super::automation::block_position_ms(), frames);
+            return;
+        }
+
         // 入力 → 出力にコピー
         for i in 0..self.output_buffers.len() {
             if let Some(in_buf) = self.input_buffers.get(i) {
@@ -236,31 +947,139 @@ impl AudioNode for BusNode {
             self.output_buffers[i].set_valid_frames(frames);
         }
 
-        // プラグインチェーンを通す（ステレオ処理）
-        if self.output_buffers.len() >= 2 && !self.plugin_chain.is_empty() {
-            // Get raw pointers for left and right channels
-            // We need to process both channels together for stereo plugins
-            let left_ptr = self.output_buffers[0].samples_mut().as_mut_ptr();
-            let right_ptr = self.output_buffers[1].samples_mut().as_mut_ptr();
-
-            // Process through each enabled plugin in the chain
-            for plugin in &self.plugin_chain {
-                if plugin.enabled {
-                    // Create slices from pointers for this iteration
-                    // SAFETY: We have mutable access to output_buffers and frames is valid
-                    unsafe {
-                        let left = std::slice::from_raw_parts_mut(left_ptr, frames);
-                        let right = std::slice::from_raw_parts_mut(right_ptr, frames);
-                        plugin.process(left, right);
+        // プラグインチェーンを通す（ステレオペアごと。1ポートのバスはモノラル）
+        let ports = self.output_buffers.len();
+        let sample_rate = super::engine_sample_rate();
+        for plugin in &mut self.plugin_chain {
+            if !plugin.is_running() {
+                // バイパス中は負荷 0 として平均を下げていく
+                plugin.load.record(Duration::ZERO, frames, sample_rate);
+                continue;
+            }
+            let started = Instant::now();
+            // バイパスの切り替え中はドライとクロスフェードする
+            let (fade_start, fade_end) = plugin.advance_bypass(frames, sample_rate);
+            let fading = fade_start < 1.0 || fade_end < 1.0;
+            // 無効化の向きは切り替えた時点のモード（テールの有無）に従う
+            let ring_out = fading
+                && if plugin.enabled {
+                    plugin.ring_out
+                } else {
+                    plugin.tail.is_some()
+                };
+            let needs_dry = plugin.needs_dry() || fading;
+            // レイテンシのあるプラグインのドライは wet と同じだけ遅らせる。ミックスを
+            // 下げたときに古い音が出ないよう、ディレイラインには常に通しておく
+            if needs_dry || !plugin.dry_delays.is_empty() {
+                let dry_ports = self.dry_buffers.iter_mut().zip(&self.output_buffers);
+                for (port, (dry, out)) in dry_ports.enumerate() {
+                    match plugin.dry_delays.get_mut(port) {
+                        Some(line) => dry.write_samples(line.process(out.samples())),
+                        None => dry.write_samples(out.samples()),
+                    }
+                }
+            }
+            if ring_out {
+                // テールを残す: 出力ではなくプラグインへの入力を絞る
+                for out in &mut self.output_buffers {
+                    ramp_gain(out.samples_mut(), fade_start, fade_end);
+                }
+            }
+
+            if ports == 1 {
+                // R は捨てる（モノラル設定の AU は 1ch しか読まない）
+                let left = self.output_buffers[0].samples_mut();
+                let right = &mut self.dry_buffers[1];
+                right.write_samples(left);
+                plugin.process_pair(0, left, right.samples_mut());
+            } else {
+                let pairs = self
+                    .output_buffers
+                    .chunks_exact_mut(2)
+                    .take(plugin.pair_count());
+                for (pair, buffers) in pairs.enumerate() {
+                    if let [left, right] = buffers {
+                        plugin.process_pair(pair, left.samples_mut(), right.samples_mut());
                     }
                 }
             }
+
+            if fade_start == 0.0 && plugin.tail.is_some() {
+                // 入力は無音なので出力はテールのみ
+                let peak = self
+                    .output_buffers
+                    .iter()
+                    .flat_map(|b| b.samples())
+                    .fold(0.0f32, |m, s| m.max(s.abs()));
+                plugin.follow_tail(peak, frames, sample_rate);
+            }
+
+            if needs_dry {
+                let (mix_start, mix_end) = (plugin.applied_mix, plugin.mix);
+                for (out, dry) in self.output_buffers.iter_mut().zip(&self.dry_buffers) {
+                    if ring_out {
+                        // wet はそのまま、ドライは絞った入力の分だけ戻す
+                        blend_ramped(
+                            out.samples_mut(),
+                            dry.samples(),
+                            (mix_start, mix_end),
+                            (1.0 - mix_start * fade_start, 1.0 - mix_end * fade_end),
+                        );
+                    } else {
+                        blend_dry_wet(
+                            out.samples_mut(),
+                            dry.samples(),
+                            mix_start * fade_start,
+                            mix_end * fade_end,
+                        );
+                    }
+                }
+                plugin.applied_mix = plugin.mix;
+            }
+            plugin.load.record(started.elapsed(), frames, sample_rate);
         }
 
         // Update peak levels and RMS
         for buf in &mut self.output_buffers {
             buf.update_meters();
         }
+        // 入力のピーク（プリプラグインの送りのメーター用）
+        for buf in &mut self.input_buffers {
+            buf.update_peak();
+        }
+    }
+
+    fn latency_samples(&self) -> u32 {
+        // プラグインは直列なのでレイテンシは単純加算
+        self.plugin_chain.iter().map(|p| p.latency_samples()).sum()
+    }
+
+    fn take_live_state(&mut self) -> Option<Box<dyn Any + Send>> {
+        if self.plugin_chain.is_empty() {
+            return None;
+        }
+        let plugins: Vec<PluginState> = self
+            .plugin_chain
+            .iter_mut()
+            .map(PluginInstance::take_live_state)
+            .collect();
+        Some(Box::new(plugins))
+    }
+
+    fn restore_live_state(&mut self, state: Box<dyn Any + Send>) {
+        let Ok(plugins) = state.downcast::<Vec<PluginState>>() else {
+            return;
+        };
+        // 並べ替え・削除されていてもインスタンス ID で対応を取る
+        for state in *plugins {
+            if let Some(plugin) = self
+                .plugin_chain
+                .iter_mut()
+                .find(|p| p.instance_id == state.instance_id)
+            {
+                plugin.restore_live_state(state);
+            }
+        }
     }
 
     fn clear_buffers(&mut self, frames: usize) {
@@ -291,3 +1110,313 @@ impl AudioNode for BusNode {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_dry_wet_ramps_to_target_mix() {
+        let mut wet = [1.0f32; 4];
+        let dry = [0.0f32; 4];
+        blend_dry_wet(&mut wet, &dry, 0.0, 1.0);
+        assert_eq!(wet, [0.25, 0.5, 0.75, 1.0]);
+
+        let mut wet = [1.0f32; 4];
+        let dry = [-1.0f32; 4];
+        blend_dry_wet(&mut wet, &dry, 0.5, 0.5);
+        assert!(wet.iter().all(|s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn plugin_mix_is_clamped() {
+        let mut bus = BusNode::new_stereo("bus_1", "Bus 1");
+        bus.add_plugin(
+            "native-1".to_string(),
+            "native:eq".to_string(),
+            "EQ".to_string(),
+            "Spectrum".to_string(),
+        );
+        assert!(bus.set_plugin_mix("native-1", 1.5));
+        assert_eq!(bus.plugins()[0].mix(), 1.0);
+        assert!(bus.set_plugin_mix("native-1", -0.2));
+        assert_eq!(bus.plugins()[0].mix(), 0.0);
+        assert!(bus.set_plugin_mix("native-1", f32::NAN));
+        assert_eq!(bus.plugins()[0].mix(), 1.0);
+        assert!(!bus.set_plugin_mix("missing", 0.5));
+    }
+
+    #[test]
+    fn move_plugin_keeps_the_order_of_the_rest() {
+        let mut bus = BusNode::new_stereo("bus_1", "Bus 1");
+        for id in ["a", "b", "c", "d"] {
+            bus.add_plugin(
+                id.to_string(),
+                "native:eq".to_string(),
+                "EQ".to_string(),
+                "Spectrum".to_string(),
+            );
+        }
+        let order = |bus: &BusNode| -> Vec<String> {
+            bus.plugins()
+                .iter()
+                .map(|p| p.instance_id.clone())
+                .collect()
+        };
+
+        assert!(bus.move_plugin("a", 2));
+        assert_eq!(order(&bus), ["b", "c", "a", "d"]);
+        assert!(bus.move_plugin("d", 0));
+        assert_eq!(order(&bus), ["d", "b", "c", "a"]);
+        // 末尾を越える位置は末尾に丸める
+        assert!(bus.move_plugin("b", 99));
+        assert_eq!(order(&bus), ["d", "c", "a", "b"]);
+        assert!(!bus.move_plugin("missing", 0));
+
+        assert!(bus.remove_plugin("c"));
+        assert!(!bus.remove_plugin("c"));
+        assert_eq!(order(&bus), ["d", "a", "b"]);
+    }
+
+    #[test]
+    fn dry_path_is_delayed_by_the_plugin_latency() {
+        let mut bus = BusNode::new_stereo("bus_1", "Bus 1");
+        bus.add_plugin(
+            "latent-1".to_string(),
+            "test:latent".to_string(),
+            "Latent".to_string(),
+            "Spectrum".to_string(),
+        );
+        bus.plugin_chain[0].latent_stub = Some((0..2).map(|_| DelayLine::with_delay(32)).collect());
+        assert!(bus.set_plugin_mix("latent-1", 0.5));
+
+        // コンパイル時と同じくディレイラインを作って差し替える
+        let changes: Vec<_> = bus.dry_delay_changes().collect();
+        assert_eq!(changes, [(0, (32, 2))]);
+        for (slot, (delay, count)) in changes {
+            let mut lines = (0..count).map(|_| DelayLine::with_delay(delay)).collect();
+            bus.swap_dry_delays(slot, &mut lines);
+        }
+        assert_eq!(bus.dry_delay_changes().count(), 0);
+
+        // 1 ブロック目でミックスのランプを終わらせる
+        run_block(&mut bus, 0.0);
+
+        let mut impulse = [0.0f32; 64];
+        impulse[0] = 1.0;
+        bus.clear_buffers(64);
+        for port in 0..2 {
+            bus.input_buffer_mut(PortId::new(port))
+                .unwrap()
+                .write_samples(&impulse);
+        }
+        bus.process(64);
+        let out = bus.output_buffer(PortId::new(0)).unwrap().samples();
+        // ドライと wet が同じ位置に揃い、半分ずつで元の大きさになる
+        assert!((out[32] - 1.0).abs() < 1e-6, "{}", out[32]);
+        assert!(out
+            .iter()
+            .enumerate()
+            .all(|(i, &s)| i == 32 || s.abs() < 1e-6));
+    }
+
+    /// Stereo bus with a +12 dB low shelf, settled on a constant input
+    fn boosted_bus(input: f32) -> BusNode {
+        use super::super::dsp::{EqBandKind, EqSettings};
+
+        let mut bus = BusNode::new_stereo("bus_1", "Bus 1");
+        bus.add_plugin(
+            "native-1".to_string(),
+            "native:eq".to_string(),
+            "EQ".to_string(),
+            "Spectrum".to_string(),
+        );
+        let mut settings = EqSettings::default();
+        settings.bands[0].kind = EqBandKind::LowShelf;
+        settings.bands[0].gain_db = 12.0;
+        bus.plugins()[0]
+            .set_native_settings(NativeSettings::Eq(settings))
+            .unwrap();
+        for _ in 0..200 {
+            run_block(&mut bus, input);
+        }
+        bus
+    }
+
+    fn run_block(bus: &mut BusNode, input: f32) -> Vec<f32> {
+        bus.clear_buffers(64);
+        for port in 0..2 {
+            bus.input_buffer_mut(PortId::new(port))
+                .unwrap()
+                .write_samples(&[input; 64]);
+        }
+        bus.process(64);
+        bus.output_buffer(PortId::new(0))
+            .unwrap()
+            .samples()
+            .to_vec()
+    }
+
+    #[test]
+    fn plugin_load_is_recorded_per_slot() {
+        let mut bus = boosted_bus(0.5);
+        let load = bus.plugins()[0].load().snapshot();
+        assert_eq!(load.callbacks, 200);
+        assert!(load.budget_ms > 0.0);
+        assert!(load.peak >= load.average);
+
+        // バイパス中も 0 として記録し続ける
+        assert!(bus.set_plugin_enabled("native-1", false));
+        for _ in 0..200 {
+            run_block(&mut bus, 0.5);
+        }
+        assert_eq!(bus.plugins()[0].load().snapshot().last, 0.0);
+    }
+
+    #[test]
+    fn bypass_crossfades_to_dry() {
+        let mut bus = boosted_bus(0.5);
+        let wet = *run_block(&mut bus, 0.5).last().unwrap();
+        assert!(wet > 1.0);
+
+        assert!(bus.set_plugin_enabled("native-1", false));
+        // 1 ブロック目はまだ途中
+        let fading = *run_block(&mut bus, 0.5).last().unwrap();
+        assert!(fading > 0.5 && fading < wet, "{}", fading);
+
+        for _ in 0..200 {
+            run_block(&mut bus, 0.5);
+        }
+        assert!(run_block(&mut bus, 0.5).iter().all(|&s| s == 0.5));
+        assert!(!bus.plugins()[0].is_running());
+    }
+
+    #[test]
+    fn ring_out_keeps_processing_until_the_tail_is_silent() {
+        let mut bus = boosted_bus(0.5);
+        assert!(bus.set_plugin_ring_out("native-1", true));
+        assert!(bus.plugins()[0].ring_out());
+
+        assert!(bus.set_plugin_enabled("native-1", false));
+        for _ in 0..20 {
+            run_block(&mut bus, 0.0);
+        }
+        // フェードは終わっているがテールの無音待ち
+        assert_eq!(bus.plugins()[0].bypass_gain, 0.0);
+        assert!(bus.plugins()[0].is_running());
+
+        for _ in 0..1000 {
+            run_block(&mut bus, 0.0);
+        }
+        assert!(!bus.plugins()[0].is_running());
+
+        assert!(bus.set_plugin_enabled("native-1", true));
+        assert!(bus.plugins()[0].tail.is_none());
+        assert!(bus.plugins()[0].is_running());
+    }
+
+    #[test]
+    fn port_count_can_change() {
+        let mut bus = BusNode::new_stereo("bus_1", "Bus 1");
+        assert!(!bus.set_port_count(2));
+        assert!(bus.set_port_count(6));
+        assert_eq!(bus.input_port_count(), 6);
+        assert_eq!(bus.output_port_count(), 6);
+        assert!(bus.set_port_count(0));
+        assert_eq!(bus.input_port_count(), 1);
+    }
+
+    #[test]
+    fn frozen_bus_plays_the_cache_at_the_transport_position() {
+        let mut bus = BusNode::new_stereo("bus_1", "Bus 1");
+        let left: Vec<f32> = (0..96).map(|i| i as f32).collect();
+        bus.freeze(Arc::new(FrozenAudio::new(
+            vec![left, vec![0.5; 96]],
+            48000.0,
+        )));
+        bus.input_buffer_mut(PortId::new(0))
+            .unwrap()
+            .write_samples(&[1.0; 4]);
+
+        // 1 ms = 48 フレーム目から
+        bus.play_frozen(Some(1.0), 4);
+        let out = bus.output_buffer(PortId::new(0)).unwrap().samples();
+        assert_eq!(out, &[48.0, 49.0, 50.0, 51.0]);
+        assert_eq!(
+            bus.output_buffer(PortId::new(1)).unwrap().samples(),
+            &[0.5; 4]
+        );
+
+        // 停止中は無音
+        bus.play_frozen(None, 4);
+        assert_eq!(
+            bus.output_buffer(PortId::new(0)).unwrap().samples(),
+            &[0.0; 4]
+        );
+
+        assert!(bus.unfreeze().is_some());
+        assert!(!bus.is_frozen());
+        bus.process(4);
+        assert_eq!(
+            bus.output_buffer(PortId::new(0)).unwrap().samples(),
+            &[1.0; 4]
+        );
+    }
+
+    #[test]
+    fn native_plugins_fit_the_bus_width() {
+        let mut bus = BusNode::new("bus_1", "Bus 1", 5);
+        bus.add_plugin(
+            "native-1".to_string(),
+            "native:eq".to_string(),
+            "EQ".to_string(),
+            "Spectrum".to_string(),
+        );
+        assert_eq!(bus.plugins()[0].layout(), PluginLayout::StereoPairs);
+        assert_eq!(bus.plugins()[0].pair_count(), 2);
+        assert!(bus.unfitted_plugins().is_empty());
+
+        assert!(bus.set_port_count(1));
+        assert_eq!(bus.plugins()[0].layout(), PluginLayout::Native);
+        assert_eq!(bus.plugins()[0].pair_count(), 1);
+    }
+
+    #[test]
+    fn native_settings_reach_every_pair() {
+        use super::super::dsp::{EqBandKind, EqSettings};
+
+        let mut bus = BusNode::new("bus_1", "Bus 1", 4);
+        bus.add_plugin(
+            "native-1".to_string(),
+            "native:eq".to_string(),
+            "EQ".to_string(),
+            "Spectrum".to_string(),
+        );
+        let mut settings = EqSettings::default();
+        settings.bands[0].kind = EqBandKind::LowShelf;
+        settings.bands[0].gain_db = 12.0;
+        bus.plugins()[0]
+            .set_native_settings(NativeSettings::Eq(settings))
+            .unwrap();
+
+        for port in 0..4 {
+            bus.input_buffer_mut(PortId::new(port))
+                .unwrap()
+                .write_samples(&[0.5; 64]);
+        }
+        bus.process(64);
+        // 4 ポートすべてに同じカーブがかかる
+        let first = bus
+            .output_buffer(PortId::new(0))
+            .unwrap()
+            .samples()
+            .to_vec();
+        assert!(first.iter().any(|&s| (s - 0.5).abs() > 1e-4));
+        for port in 1..4 {
+            assert_eq!(
+                bus.output_buffer(PortId::new(port)).unwrap().samples(),
+                &first[..]
+            );
+        }
+    }
+}
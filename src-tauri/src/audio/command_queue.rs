@@ -0,0 +1,328 @@
+//! Graph command queue - mutations applied at block boundaries
+//!
+//! API スレッドがグラフの書き込みロックを取ると、オーディオスレッドの `try_write` が
+//! 失敗してブロックを落とす。代わりに変更をクロージャとしてリング（SPSC）に積み、
+//! オーディオスレッドが次のブロックの頭で順に実行する。呼び出し側は実行完了まで待つので、
+//! 戻り値もそのまま返せ、変更の順序も積んだ順に揃う。
+//!
+//! - 書き込み側: 複数の API スレッドから呼ばれるので `producer` の Mutex で 1 本にする
+//!   （オーディオスレッドはこのロックに触れない）
+//! - 読み出し側: グラフの書き込みロックを持っているスレッド（オーディオコールバック
+//!   またはオフラインレンダリング）だけ
+//!
+//! 待ち時間内に拾われなかったジョブは呼び出し側が取り戻して、ロックを取って自分で実行する
+//! （出力が止まった直後など）。オーディオスレッドが既に実行中なら完了を待つ。
+//!
+//! 読み出し側ではメモリを解放しない: タスクは実行の間だけジョブから取り出して戻し、
+//! ジョブ自体も呼び出し側が最後の参照を持って drop する。取り戻したジョブはリングの
+//! スロットからも外し、外せなければ（読み出し側が既に取り出していれば）読み出し側が
+//! 参照を手放すまで待つ。
+
+use parking_lot::{Condvar, Mutex};
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Jobs that can wait in the ring at once (beyond this callers run directly)
+pub const QUEUE_CAPACITY: usize = 64;
+
+/// Boxed job body (runs its closure on the first call, later calls do nothing)
+type Task<'a, T> = Box<dyn FnMut(&mut T) + Send + 'a>;
+
+/// One queued mutation (run exactly once, by the consumer or back by the caller)
+struct Job<T> {
+    task: Mutex<Option<Task<'static, T>>>,
+    done: Mutex<bool>,
+    finished: Condvar,
+}
+
+impl<T> Job<T> {
+    /// Take the task back unless the consumer already ran it (or is running it)
+    fn take(&self) -> Option<Task<'static, T>> {
+        let mut task = self.task.lock();
+        if *self.done.lock() {
+            return None;
+        }
+        task.take()
+    }
+
+    fn finish(&self) {
+        *self.done.lock() = true;
+        self.finished.notify_all();
+    }
+
+    /// Wait until the consumer ran the task (false on timeout)
+    fn wait(&self, timeout: Option<Duration>) -> bool {
+        let mut done = self.done.lock();
+        while !*done {
+            match timeout {
+                Some(timeout) => {
+                    if self.finished.wait_for(&mut done, timeout).timed_out() {
+                        return *done;
+                    }
+                }
+                None => self.finished.wait(&mut done),
+            }
+        }
+        true
+    }
+}
+
+/// Single-producer single-consumer ring of graph mutations
+pub struct CommandQueue<T> {
+    slots: Box<[AtomicPtr<Job<T>>]>,
+    /// Next slot to read (consumer only)
+    head: AtomicUsize,
+    /// Next slot to write (producer only)
+    tail: AtomicUsize,
+    /// Serializes the API threads into one producer
+    producer: Mutex<()>,
+}
+
+impl<T> Default for CommandQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> CommandQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: (0..QUEUE_CAPACITY)
+                .map(|_| AtomicPtr::new(std::ptr::null_mut()))
+                .collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            producer: Mutex::new(()),
+        }
+    }
+
+    /// Put a job into the ring; returns its slot (None when the ring is full)
+    fn push(&self, job: Arc<Job<T>>) -> Option<usize> {
+        let _producer = self.producer.lock();
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail.wrapping_sub(self.head.load(Ordering::Acquire)) >= self.slots.len() {
+            return None;
+        }
+        let index = tail % self.slots.len();
+        self.slots[index].store(Arc::into_raw(job).cast_mut(), Ordering::Relaxed);
+        self.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Some(index)
+    }
+
+    /// Take a job's reference back out of its slot (no-op once the consumer took it)
+    ///
+    /// ジョブは呼び出し側も参照を持っている間は解放されないので、別のジョブが同じアドレスで
+    /// スロットに入ることはない（比較は取り違えない）。
+    fn unqueue(&self, index: usize, job: &Arc<Job<T>>) {
+        let ptr = Arc::as_ptr(job).cast_mut();
+        let taken = self.slots[index].compare_exchange(
+            ptr,
+            std::ptr::null_mut(),
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        );
+        if taken.is_ok() {
+            // SAFETY: the slot held the reference created by `Arc::into_raw` in `push`
+            drop(unsafe { Arc::from_raw(ptr.cast_const()) });
+        }
+    }
+
+    /// Whether any job is waiting
+    pub fn has_pending(&self) -> bool {
+        self.head.load(Ordering::Relaxed) != self.tail.load(Ordering::Acquire)
+    }
+
+    /// Run every queued job in order (consumer side, with exclusive access to `target`)
+    pub fn run_pending(&self, target: &mut T) {
+        let tail = self.tail.load(Ordering::Acquire);
+        let mut head = self.head.load(Ordering::Relaxed);
+        while head != tail {
+            let slot = &self.slots[head % self.slots.len()];
+            let ptr = slot.swap(std::ptr::null_mut(), Ordering::AcqRel);
+            head = head.wrapping_add(1);
+            self.head.store(head, Ordering::Release);
+            if ptr.is_null() {
+                continue;
+            }
+            // SAFETY: the pointer came from `Arc::into_raw` in `push` and is taken once
+            let job = unsafe { Arc::from_raw(ptr.cast_const()) };
+            // 呼び出し側が取り戻したジョブは空。実行中はロックを外し（呼び出し側の `take` を
+            // 止めない）、Box はジョブに戻して呼び出し側で解放させる
+            let task = job.task.lock().take();
+            if let Some(mut task) = task {
+                task(target);
+                *job.task.lock() = Some(task);
+                job.finish();
+            }
+        }
+    }
+
+    /// Queue `f`, wait for the consumer to run it and return its result
+    ///
+    /// `run_directly` is called with the task instead when the ring is full or the
+    /// consumer did not pick the job up within `wait`.
+    pub fn run<'a, R: Send + 'a>(
+        &self,
+        f: impl FnOnce(&mut T) -> R + Send + 'a,
+        wait: Duration,
+        run_directly: impl FnOnce(Box<dyn FnMut(&mut T) + '_>),
+    ) -> R {
+        let mut result: Option<R> = None;
+        {
+            let slot = &mut result;
+            let mut f = Some(f);
+            let task: Task<'_, T> = Box::new(move |target: &mut T| {
+                if let Some(f) = f.take() {
+                    *slot = Some(f(target));
+                }
+            });
+            // SAFETY: the borrowed task never outlives this block: it is either run here
+            // (taken back), or run by the consumer and we wait for `finish` below
+            let task: Task<'static, T> =
+                unsafe { std::mem::transmute::<Task<'_, T>, Task<'static, T>>(task) };
+            let job = Arc::new(Job {
+                task: Mutex::new(Some(task)),
+                done: Mutex::new(false),
+                finished: Condvar::new(),
+            });
+
+            let slot = self.push(job.clone());
+            if slot.is_none() || !job.wait(Some(wait)) {
+                match job.take() {
+                    Some(task) => {
+                        // リングに残った参照はこちらで外す（外せなければ下で手放されるのを待つ）
+                        if let Some(index) = slot {
+                            self.unqueue(index, &job);
+                        }
+                        run_directly(task);
+                    }
+                    // 実行中か実行済みなので終わるまで待つ
+                    None => {
+                        job.wait(None);
+                    }
+                }
+            }
+            // 読み出し側が参照を手放すのを待ち、ジョブ（とタスクの Box）をこちらで解放する
+            while Arc::strong_count(&job) > 1 {
+                std::thread::yield_now();
+            }
+        }
+        result.expect("graph command did not run")
+    }
+}
+
+impl<T> Drop for CommandQueue<T> {
+    fn drop(&mut self) {
+        for slot in self.slots.iter() {
+            let ptr = slot.swap(std::ptr::null_mut(), Ordering::Relaxed);
+            if !ptr.is_null() {
+                // SAFETY: pointers in the ring are owned `Arc`s from `push`
+                drop(unsafe { Arc::from_raw(ptr.cast_const()) });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    fn test_consumer_runs_jobs_in_order() {
+        let queue = Arc::new(CommandQueue::<Vec<u32>>::new());
+        let target = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let consumer = {
+            let (queue, target, stop) = (queue.clone(), target.clone(), stop.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    queue.run_pending(&mut target.lock());
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        for i in 0..10 {
+            let len = queue.run(
+                |v: &mut Vec<u32>| {
+                    v.push(i);
+                    v.len()
+                },
+                Duration::from_secs(5),
+                |_| panic!("consumer is running"),
+            );
+            assert_eq!(len, i as usize + 1);
+        }
+        stop.store(true, Ordering::Relaxed);
+        consumer.join().unwrap();
+        assert_eq!(*target.lock(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_take_back_races_consumer() {
+        let queue = Arc::new(CommandQueue::<Vec<u32>>::new());
+        let target = Arc::new(Mutex::new(Vec::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let consumer = {
+            let (queue, target, stop) = (queue.clone(), target.clone(), stop.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    queue.run_pending(&mut target.lock());
+                    thread::yield_now();
+                }
+            })
+        };
+
+        // 待ち時間 0 なので、取り戻しと読み出し側の実行が入り混じる
+        for i in 0..500 {
+            queue.run(
+                |v: &mut Vec<u32>| v.push(i),
+                Duration::ZERO,
+                |mut task| task(&mut target.lock()),
+            );
+        }
+        stop.store(true, Ordering::Relaxed);
+        consumer.join().unwrap();
+        assert_eq!(*target.lock(), (0..500).collect::<Vec<_>>());
+        assert!(queue
+            .slots
+            .iter()
+            .all(|slot| slot.load(Ordering::Relaxed).is_null()));
+    }
+
+    #[test]
+    fn test_take_back_without_consumer() {
+        let queue = CommandQueue::<Vec<u32>>::new();
+        let mut target = Vec::new();
+        let mut borrowed = 0;
+
+        let result = queue.run(
+            |v: &mut Vec<u32>| {
+                borrowed += 1;
+                v.push(7);
+                v.len()
+            },
+            Duration::from_millis(5),
+            |mut task| task(&mut target),
+        );
+        assert_eq!(result, 1);
+        assert_eq!(borrowed, 1);
+        assert_eq!(target, vec![7]);
+
+        // The job was taken out of its slot; the consumer only skips the empty entry
+        assert!(queue
+            .slots
+            .iter()
+            .all(|slot| slot.load(Ordering::Relaxed).is_null()));
+        assert!(queue.has_pending());
+        let mut other = Vec::new();
+        queue.run_pending(&mut other);
+        assert!(other.is_empty());
+        assert!(!queue.has_pending());
+    }
+}
@@ -0,0 +1,221 @@
+//! Edge crossfade - swap two sends on the audio thread
+//!
+//! 一方のエッジを下げながらもう一方を上げる。ゲインはオーディオスレッドが
+//! ブロックごとにカーブから計算して書き込み、ブロック内はエッジのゲインランプで
+//! 直線補間される（UI から大量のゲイン更新を送る必要がない）。
+//!
+//! 同じエッジを含むクロスフェードを新しく始めると、古い方はその場で置き換えられる。
+
+use super::edge::EdgeId;
+use super::graph::AudioGraph;
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+/// Maximum crossfade time
+pub const MAX_CROSSFADE_MS: f32 = 60_000.0;
+
+/// Crossfade curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrossfadeCurve {
+    /// Gains sum to a constant (dips ~3 dB in the middle for uncorrelated sources)
+    Linear,
+    /// Powers sum to a constant (sin/cos)
+    #[default]
+    EqualPower,
+}
+
+impl CrossfadeCurve {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "linear" => Some(Self::Linear),
+            "equal_power" | "equal-power" | "power" => Some(Self::EqualPower),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::EqualPower => "equal_power",
+        }
+    }
+
+    /// (fade-out factor, fade-in factor) at progress `t` (0.0 ~ 1.0)
+    pub fn factors(self, t: f32) -> (f32, f32) {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => (1.0 - t, t),
+            Self::EqualPower => {
+                let theta = t * std::f32::consts::FRAC_PI_2;
+                (theta.cos(), theta.sin())
+            }
+        }
+    }
+}
+
+/// One running crossfade
+#[derive(Debug)]
+pub struct Crossfade {
+    /// Edge faded out (ends at 0)
+    pub from_edge: EdgeId,
+    /// Edge faded in (ends at `to_gain`)
+    pub to_edge: EdgeId,
+    pub from_gain: f32,
+    pub to_gain: f32,
+    pub curve: CrossfadeCurve,
+    pub duration_ms: f32,
+    /// Elapsed time in ms (f64 bits, audio thread)
+    elapsed_bits: AtomicU64,
+    done: AtomicBool,
+}
+
+impl Crossfade {
+    pub fn new(
+        from_edge: EdgeId,
+        to_edge: EdgeId,
+        from_gain: f32,
+        to_gain: f32,
+        duration_ms: f32,
+        curve: CrossfadeCurve,
+    ) -> Self {
+        Self {
+            from_edge,
+            to_edge,
+            from_gain: from_gain.max(0.0),
+            to_gain: to_gain.max(0.0),
+            curve,
+            duration_ms: duration_ms.clamp(0.0, MAX_CROSSFADE_MS),
+            elapsed_bits: AtomicU64::new(0.0f64.to_bits()),
+            done: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    fn involves(&self, edge: EdgeId) -> bool {
+        self.from_edge == edge || self.to_edge == edge
+    }
+
+    /// Advance by `block_ms` and return the (from, to) gains at the end of the block
+    fn advance(&self, block_ms: f64) -> (f32, f32) {
+        let elapsed = f64::from_bits(self.elapsed_bits.load(Ordering::Relaxed)) + block_ms;
+        self.elapsed_bits
+            .store(elapsed.to_bits(), Ordering::Relaxed);
+        let t = if self.duration_ms <= 0.0 {
+            1.0
+        } else {
+            (elapsed / self.duration_ms as f64) as f32
+        };
+        if t >= 1.0 {
+            self.done.store(true, Ordering::Relaxed);
+        }
+        let (out, inn) = self.curve.factors(t);
+        (self.from_gain * out, self.to_gain * inn)
+    }
+}
+
+static CROSSFADES: LazyLock<ArcSwap<Vec<Arc<Crossfade>>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+/// Serializes list edits from control threads
+static EDIT_LOCK: Mutex<()> = Mutex::new(());
+
+/// Start a crossfade (replaces running ones on the same edges, drops finished ones)
+pub fn start(fade: Crossfade) -> Arc<Crossfade> {
+    let fade = Arc::new(fade);
+    let _guard = EDIT_LOCK.lock();
+    let mut list: Vec<Arc<Crossfade>> = CROSSFADES
+        .load()
+        .iter()
+        .filter(|f| !f.is_done() && !f.involves(fade.from_edge) && !f.involves(fade.to_edge))
+        .cloned()
+        .collect();
+    list.push(fade.clone());
+    CROSSFADES.store(Arc::new(list));
+    fade
+}
+
+/// Stop every crossfade on `edge` where it is (gains stay at their current values)
+pub fn cancel(edge: EdgeId) {
+    let _guard = EDIT_LOCK.lock();
+    let list: Vec<Arc<Crossfade>> = CROSSFADES
+        .load()
+        .iter()
+        .filter(|f| !f.is_done() && !f.involves(edge))
+        .cloned()
+        .collect();
+    CROSSFADES.store(Arc::new(list));
+}
+
+/// Write the gains of running crossfades for one block (audio thread)
+pub fn process(graph: &AudioGraph, frames: usize, sample_rate: f64) {
+    let fades = CROSSFADES.load();
+    if fades.is_empty() {
+        return;
+    }
+    let block_ms = frames as f64 * 1000.0 / sample_rate;
+    for fade in fades.iter().filter(|f| !f.is_done()) {
+        let (from, to) = fade.advance(block_ms);
+        graph.set_edge_gain_atomic(fade.from_edge, from);
+        graph.set_edge_gain_atomic(fade.to_edge, to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equal_power_keeps_power_constant() {
+        for i in 0..=10 {
+            let (a, b) = CrossfadeCurve::EqualPower.factors(i as f32 / 10.0);
+            assert!((a * a + b * b - 1.0).abs() < 1e-5);
+        }
+        assert_eq!(CrossfadeCurve::Linear.factors(0.25), (0.75, 0.25));
+    }
+
+    #[test]
+    fn test_curve_parse_round_trip() {
+        for curve in [CrossfadeCurve::Linear, CrossfadeCurve::EqualPower] {
+            assert_eq!(CrossfadeCurve::parse(curve.as_str()), Some(curve));
+        }
+        assert_eq!(CrossfadeCurve::parse("bogus"), None);
+    }
+
+    #[test]
+    fn test_fade_finishes_at_target_gains() {
+        let fade = Crossfade::new(
+            EdgeId::from(1),
+            EdgeId::from(2),
+            0.8,
+            0.5,
+            100.0,
+            CrossfadeCurve::Linear,
+        );
+        let (a, b) = fade.advance(50.0);
+        assert!((a - 0.4).abs() < 1e-6 && (b - 0.25).abs() < 1e-6);
+        assert!(!fade.is_done());
+        let (a, b) = fade.advance(60.0);
+        assert_eq!((a, b), (0.0, 0.5));
+        assert!(fade.is_done());
+    }
+
+    #[test]
+    fn test_zero_duration_is_instant() {
+        let fade = Crossfade::new(
+            EdgeId::from(1),
+            EdgeId::from(2),
+            1.0,
+            1.0,
+            0.0,
+            CrossfadeCurve::EqualPower,
+        );
+        let (a, b) = fade.advance(1.0);
+        assert!(a.abs() < 1e-6 && (b - 1.0).abs() < 1e-6);
+        assert!(fade.is_done());
+    }
+}
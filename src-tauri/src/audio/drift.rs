@@ -0,0 +1,234 @@
+//! Drift FIFO - hand-off between output devices running on different clocks
+//!
+//! 複数の出力デバイスを同時に鳴らすとき、グラフはクロックマスターのコールバックで
+//! 1 回だけ処理し、他のデバイス向けのシンク出力はこの FIFO 経由で渡す。
+//!
+//! デバイス間のクロックずれは FIFO の充填量の移動平均で検出し、
+//! 目標値から外れたら 1 フレーム捨てる／繰り返すことで吸収する。
+//! 書き込み（マスター）と読み出し（セカンダリ）はそれぞれ 1 スレッドのみ（SPSC, ロックなし）。
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+/// 充填量の移動平均係数（1 コールバックあたり）
+const FILL_AVERAGE_COEFF: f32 = 0.01;
+
+/// Interleaved SPSC FIFO with clock-drift compensation
+pub struct DriftFifo {
+    channels: usize,
+    capacity: usize,
+    data: UnsafeCell<Box<[f32]>>,
+    /// Monotonic frame positions
+    write_pos: AtomicU64,
+    read_pos: AtomicU64,
+    /// Frames of the last push (the master's block size)
+    last_push: AtomicUsize,
+    primed: AtomicBool,
+    fill_average_bits: AtomicU32,
+    corrections: AtomicU64,
+    underruns: AtomicU64,
+    overflows: AtomicU64,
+}
+
+// Safety: 書き込みは push（1 スレッド）、読み出しは pop（1 スレッド）のみ。
+// 領域の受け渡しは write_pos / read_pos の Acquire/Release で同期する。
+unsafe impl Sync for DriftFifo {}
+
+/// [`DriftFifo`] の状態
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DriftStats {
+    /// Current fill (frames)
+    pub fill: usize,
+    /// Smoothed fill (frames)
+    pub fill_average: f32,
+    /// Frames dropped or repeated to follow the master clock
+    pub corrections: u64,
+    pub underruns: u64,
+    pub overflows: u64,
+}
+
+impl DriftFifo {
+    /// Create a FIFO holding up to `capacity_frames` frames of `channels` channels
+    pub fn new(channels: usize, capacity_frames: usize) -> Self {
+        let channels = channels.max(1);
+        let capacity = capacity_frames.max(2);
+        Self {
+            channels,
+            capacity,
+            data: UnsafeCell::new(vec![0.0f32; capacity * channels].into_boxed_slice()),
+            write_pos: AtomicU64::new(0),
+            read_pos: AtomicU64::new(0),
+            last_push: AtomicUsize::new(0),
+            primed: AtomicBool::new(false),
+            fill_average_bits: AtomicU32::new(0),
+            corrections: AtomicU64::new(0),
+            underruns: AtomicU64::new(0),
+            overflows: AtomicU64::new(0),
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Frames currently buffered
+    pub fn fill(&self) -> usize {
+        let w = self.write_pos.load(Ordering::Acquire);
+        let r = self.read_pos.load(Ordering::Acquire);
+        w.saturating_sub(r) as usize
+    }
+
+    /// Push interleaved frames (producer / master callback)
+    ///
+    /// 空きが足りない分は捨てる（overflow として数える）。
+    pub fn push(&self, interleaved: &[f32]) {
+        let frames = interleaved.len() / self.channels;
+        self.last_push.store(frames, Ordering::Relaxed);
+
+        let w = self.write_pos.load(Ordering::Relaxed);
+        let r = self.read_pos.load(Ordering::Acquire);
+        let space = self.capacity - (w - r) as usize;
+        let n = frames.min(space);
+        if n < frames {
+            self.overflows.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let data = unsafe { &mut *self.data.get() };
+        for i in 0..n {
+            let dst = ((w as usize + i) % self.capacity) * self.channels;
+            let src = i * self.channels;
+            data[dst..dst + self.channels].copy_from_slice(&interleaved[src..src + self.channels]);
+        }
+
+        self.write_pos.store(w + n as u64, Ordering::Release);
+    }
+
+    /// Pop interleaved frames into `out` (consumer / secondary device callback)
+    ///
+    /// 充填量が目標（自分のブロック + マスターのブロック）から外れ続けたら
+    /// 1 フレーム捨てる／繰り返して追従する。
+    pub fn pop(&self, out: &mut [f32]) {
+        let ch = self.channels;
+        let frames = out.len() / ch;
+        if frames == 0 {
+            return;
+        }
+
+        let fill = self.fill();
+        let target = frames + self.last_push.load(Ordering::Relaxed);
+
+        if !self.primed.load(Ordering::Relaxed) {
+            if fill < target {
+                out.fill(0.0);
+                return;
+            }
+            self.primed.store(true, Ordering::Relaxed);
+            self.fill_average_bits
+                .store((fill as f32).to_bits(), Ordering::Relaxed);
+        }
+
+        if fill < frames {
+            // アンダーラン: あるだけ出して再プライミング
+            self.copy_out(&mut out[..fill * ch], fill);
+            out[fill * ch..].fill(0.0);
+            self.advance(fill);
+            self.primed.store(false, Ordering::Relaxed);
+            self.underruns.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let mut average = f32::from_bits(self.fill_average_bits.load(Ordering::Relaxed));
+        average += (fill as f32 - average) * FILL_AVERAGE_COEFF;
+
+        let band = (frames / 2).max(1) as f32;
+        let mut take = frames;
+        if average > target as f32 + band && fill > frames {
+            // マスターが速い: 1 フレーム捨てる
+            take = frames + 1;
+            average -= 1.0;
+        } else if average < target as f32 - band && frames > 1 {
+            // マスターが遅い: 最後のフレームを繰り返す
+            take = frames - 1;
+            average += 1.0;
+        }
+        if take != frames {
+            self.corrections.fetch_add(1, Ordering::Relaxed);
+        }
+        self.fill_average_bits
+            .store(average.to_bits(), Ordering::Relaxed);
+
+        let copied = take.min(frames);
+        self.copy_out(&mut out[..copied * ch], copied);
+        if copied < frames {
+            out.copy_within((copied - 1) * ch..copied * ch, copied * ch);
+        }
+        self.advance(take);
+    }
+
+    fn copy_out(&self, out: &mut [f32], frames: usize) {
+        let ch = self.channels;
+        let r = self.read_pos.load(Ordering::Relaxed);
+        let data = unsafe { &*self.data.get() };
+        for i in 0..frames {
+            let src = ((r as usize + i) % self.capacity) * ch;
+            out[i * ch..(i + 1) * ch].copy_from_slice(&data[src..src + ch]);
+        }
+    }
+
+    fn advance(&self, frames: usize) {
+        self.read_pos.fetch_add(frames as u64, Ordering::Release);
+    }
+
+    pub fn stats(&self) -> DriftStats {
+        DriftStats {
+            fill: self.fill(),
+            fill_average: f32::from_bits(self.fill_average_bits.load(Ordering::Relaxed)),
+            corrections: self.corrections.load(Ordering::Relaxed),
+            underruns: self.underruns.load(Ordering::Relaxed),
+            overflows: self.overflows.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_fifo_primes_and_preserves_order() {
+        let fifo = DriftFifo::new(2, 64);
+        let block: Vec<f32> = (0..16).map(|i| i as f32).collect(); // 8 frames
+
+        // Not primed until one block of each side is buffered
+        fifo.push(&block);
+        let mut out = vec![1.0f32; 16];
+        fifo.pop(&mut out);
+        assert!(out.iter().all(|&s| s == 0.0));
+
+        fifo.push(&block);
+        fifo.pop(&mut out);
+        assert_eq!(out, block);
+        assert_eq!(fifo.fill(), 8);
+    }
+
+    #[test]
+    fn test_drift_fifo_drops_frames_when_master_is_fast() {
+        let fifo = DriftFifo::new(1, 4096);
+        // Master delivers 65 frames for every 64 the secondary consumes
+        let block = vec![0.5f32; 65];
+        let mut out = vec![0.0f32; 64];
+
+        fifo.push(&block);
+        fifo.push(&block);
+        for _ in 0..2000 {
+            fifo.push(&block);
+            fifo.pop(&mut out);
+        }
+
+        let stats = fifo.stats();
+        assert!(stats.corrections > 0);
+        assert_eq!(stats.overflows, 0);
+        // Fill stays bounded instead of growing by ~2000 frames
+        assert!(stats.fill < 512, "fill grew to {}", stats.fill);
+    }
+}
@@ -0,0 +1,156 @@
+//! Biquad coefficient design (RBJ Audio EQ Cookbook)
+//!
+//! 係数は a0 で正規化し、vDSP_biquad の並び `[b0, b1, b2, a1, a2]` で返す。
+
+use std::f64::consts::PI;
+
+/// Normalized biquad coefficients
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiquadCoeffs {
+    pub b0: f64,
+    pub b1: f64,
+    pub b2: f64,
+    pub a1: f64,
+    pub a2: f64,
+}
+
+impl BiquadCoeffs {
+    /// Pass-through section
+    pub const IDENTITY: Self = Self {
+        b0: 1.0,
+        b1: 0.0,
+        b2: 0.0,
+        a1: 0.0,
+        a2: 0.0,
+    };
+
+    /// Peaking EQ
+    pub fn peak(sample_rate: f64, freq: f64, q: f64, gain_db: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let (cos_w, alpha) = Self::omega(sample_rate, freq, q);
+        Self::normalize(
+            1.0 + alpha * a,
+            -2.0 * cos_w,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_w,
+            1.0 - alpha / a,
+        )
+    }
+
+    /// Low shelf (`q` = shelf slope as Q)
+    pub fn low_shelf(sample_rate: f64, freq: f64, q: f64, gain_db: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let (cos_w, alpha) = Self::omega(sample_rate, freq, q);
+        let k = 2.0 * a.sqrt() * alpha;
+        Self::normalize(
+            a * ((a + 1.0) - (a - 1.0) * cos_w + k),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w),
+            a * ((a + 1.0) - (a - 1.0) * cos_w - k),
+            (a + 1.0) + (a - 1.0) * cos_w + k,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos_w),
+            (a + 1.0) + (a - 1.0) * cos_w - k,
+        )
+    }
+
+    /// High shelf (`q` = shelf slope as Q)
+    pub fn high_shelf(sample_rate: f64, freq: f64, q: f64, gain_db: f64) -> Self {
+        let a = 10f64.powf(gain_db / 40.0);
+        let (cos_w, alpha) = Self::omega(sample_rate, freq, q);
+        let k = 2.0 * a.sqrt() * alpha;
+        Self::normalize(
+            a * ((a + 1.0) + (a - 1.0) * cos_w + k),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w),
+            a * ((a + 1.0) + (a - 1.0) * cos_w - k),
+            (a + 1.0) - (a - 1.0) * cos_w + k,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos_w),
+            (a + 1.0) - (a - 1.0) * cos_w - k,
+        )
+    }
+
+    /// 12 dB/oct high-pass
+    pub fn high_pass(sample_rate: f64, freq: f64, q: f64) -> Self {
+        let (cos_w, alpha) = Self::omega(sample_rate, freq, q);
+        Self::normalize(
+            (1.0 + cos_w) / 2.0,
+            -(1.0 + cos_w),
+            (1.0 + cos_w) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w,
+            1.0 - alpha,
+        )
+    }
+
+    /// 12 dB/oct low-pass
+    pub fn low_pass(sample_rate: f64, freq: f64, q: f64) -> Self {
+        let (cos_w, alpha) = Self::omega(sample_rate, freq, q);
+        Self::normalize(
+            (1.0 - cos_w) / 2.0,
+            1.0 - cos_w,
+            (1.0 - cos_w) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_w,
+            1.0 - alpha,
+        )
+    }
+
+    /// Magnitude response at `freq` (linear)
+    pub fn magnitude_at(&self, sample_rate: f64, freq: f64) -> f64 {
+        let w = 2.0 * PI * freq / sample_rate;
+        let (c1, s1) = (w.cos(), w.sin());
+        let (c2, s2) = ((2.0 * w).cos(), (2.0 * w).sin());
+        let num_re = self.b0 + self.b1 * c1 + self.b2 * c2;
+        let num_im = -(self.b1 * s1 + self.b2 * s2);
+        let den_re = 1.0 + self.a1 * c1 + self.a2 * c2;
+        let den_im = -(self.a1 * s1 + self.a2 * s2);
+        ((num_re * num_re + num_im * num_im) / (den_re * den_re + den_im * den_im)).sqrt()
+    }
+
+    /// vDSP の係数並び
+    pub fn as_array(&self) -> [f64; 5] {
+        [self.b0, self.b1, self.b2, self.a1, self.a2]
+    }
+
+    fn omega(sample_rate: f64, freq: f64, q: f64) -> (f64, f64) {
+        // ナイキスト直前で頭打ちにして不安定な係数を避ける
+        let freq = freq.clamp(1.0, sample_rate * 0.49);
+        let w = 2.0 * PI * freq / sample_rate;
+        (w.cos(), w.sin() / (2.0 * q.max(0.01)))
+    }
+
+    fn normalize(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(linear: f64) -> f64 {
+        20.0 * linear.log10()
+    }
+
+    #[test]
+    fn test_biquad_design_responses() {
+        let sr = 48_000.0;
+
+        let peak = BiquadCoeffs::peak(sr, 1000.0, 1.0, 6.0);
+        assert!((db(peak.magnitude_at(sr, 1000.0)) - 6.0).abs() < 0.01);
+        assert!(db(peak.magnitude_at(sr, 20.0)).abs() < 0.1);
+
+        let shelf = BiquadCoeffs::low_shelf(sr, 200.0, 0.707, -4.0);
+        assert!((db(shelf.magnitude_at(sr, 5.0)) + 4.0).abs() < 0.05);
+        assert!(db(shelf.magnitude_at(sr, 15_000.0)).abs() < 0.05);
+
+        let hp = BiquadCoeffs::high_pass(sr, 100.0, 0.707);
+        assert!(hp.magnitude_at(sr, 5.0) < 0.01);
+        assert!((hp.magnitude_at(sr, 10_000.0) - 1.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,322 @@
+//! Dynamics - compressor and gate
+//!
+//! どちらもステレオリンク（L/R の大きい方で検出し、同じゲインを両チャンネルに掛ける）。
+//! 設定の差し替え方法は EQ と同じ（ArcSwap + バージョン）。
+
+use crate::audio::engine_sample_rate;
+use arc_swap::ArcSwap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// 時定数 (ms) から 1 サンプルあたりの平滑化係数へ
+fn time_coeff(ms: f32, sample_rate: f64) -> f32 {
+    if ms <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (ms as f64 * 0.001 * sample_rate)).exp() as f32
+}
+
+#[inline]
+fn lin_to_db(v: f32) -> f32 {
+    20.0 * v.max(1e-9).log10()
+}
+
+#[inline]
+fn db_to_lin(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+fn finite_or(v: f32, default: f32) -> f32 {
+    if v.is_finite() {
+        v
+    } else {
+        default
+    }
+}
+
+/// Compressor settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompressorSettings {
+    pub threshold_db: f32,
+    /// N:1
+    pub ratio: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub makeup_db: f32,
+}
+
+impl Default for CompressorSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -18.0,
+            ratio: 4.0,
+            attack_ms: 10.0,
+            release_ms: 120.0,
+            makeup_db: 0.0,
+        }
+    }
+}
+
+impl CompressorSettings {
+    pub fn clamped(self) -> Self {
+        Self {
+            threshold_db: finite_or(self.threshold_db, -18.0).clamp(-60.0, 0.0),
+            ratio: finite_or(self.ratio, 4.0).clamp(1.0, 50.0),
+            attack_ms: finite_or(self.attack_ms, 10.0).clamp(0.1, 500.0),
+            release_ms: finite_or(self.release_ms, 120.0).clamp(5.0, 5000.0),
+            makeup_db: finite_or(self.makeup_db, 0.0).clamp(-24.0, 24.0),
+        }
+    }
+}
+
+/// Feed-forward peak compressor
+pub struct Compressor {
+    settings: ArcSwap<CompressorSettings>,
+    version: AtomicU64,
+    /// 直近ブロックの最大ゲインリダクション (dB, f32 bits)
+    gain_reduction_bits: AtomicU32,
+    /// 以下はオーディオスレッドのみ
+    applied_version: u64,
+    applied_rate: f64,
+    attack: f32,
+    release: f32,
+    envelope: f32,
+}
+
+impl Compressor {
+    pub fn new(settings: CompressorSettings) -> Self {
+        Self {
+            settings: ArcSwap::from_pointee(settings.clamped()),
+            version: AtomicU64::new(1),
+            gain_reduction_bits: AtomicU32::new(0),
+            applied_version: 0,
+            applied_rate: 0.0,
+            attack: 0.0,
+            release: 0.0,
+            envelope: 0.0,
+        }
+    }
+
+    pub fn settings(&self) -> CompressorSettings {
+        **self.settings.load()
+    }
+
+    /// Replace the settings (control thread)
+    pub fn set_settings(&self, settings: CompressorSettings) {
+        self.settings.store(Arc::new(settings.clamped()));
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Largest gain reduction of the last block (dB, positive)
+    pub fn gain_reduction_db(&self) -> f32 {
+        f32::from_bits(self.gain_reduction_bits.load(Ordering::Relaxed))
+    }
+
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let version = self.version.load(Ordering::Acquire);
+        let sample_rate = engine_sample_rate();
+        let s = self.settings();
+        if version != self.applied_version || sample_rate != self.applied_rate {
+            self.attack = time_coeff(s.attack_ms, sample_rate);
+            self.release = time_coeff(s.release_ms, sample_rate);
+            self.applied_version = version;
+            self.applied_rate = sample_rate;
+        }
+        let slope = 1.0 - 1.0 / s.ratio;
+        let makeup = db_to_lin(s.makeup_db);
+
+        let mut max_reduction = 0.0f32;
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            let level = l.abs().max(r.abs());
+            let coeff = if level > self.envelope {
+                self.attack
+            } else {
+                self.release
+            };
+            self.envelope = level + coeff * (self.envelope - level);
+
+            let over = lin_to_db(self.envelope) - s.threshold_db;
+            let reduction = if over > 0.0 { over * slope } else { 0.0 };
+            max_reduction = max_reduction.max(reduction);
+
+            let gain = db_to_lin(-reduction) * makeup;
+            *l *= gain;
+            *r *= gain;
+        }
+        self.gain_reduction_bits
+            .store(max_reduction.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+}
+
+/// Gate settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GateSettings {
+    pub threshold_db: f32,
+    /// Attenuation while closed (dB, negative)
+    pub range_db: f32,
+    pub attack_ms: f32,
+    pub hold_ms: f32,
+    pub release_ms: f32,
+}
+
+impl Default for GateSettings {
+    fn default() -> Self {
+        Self {
+            threshold_db: -50.0,
+            range_db: -80.0,
+            attack_ms: 1.0,
+            hold_ms: 50.0,
+            release_ms: 150.0,
+        }
+    }
+}
+
+impl GateSettings {
+    pub fn clamped(self) -> Self {
+        Self {
+            threshold_db: finite_or(self.threshold_db, -50.0).clamp(-96.0, 0.0),
+            range_db: finite_or(self.range_db, -80.0).clamp(-96.0, 0.0),
+            attack_ms: finite_or(self.attack_ms, 1.0).clamp(0.05, 200.0),
+            hold_ms: finite_or(self.hold_ms, 50.0).clamp(0.0, 2000.0),
+            release_ms: finite_or(self.release_ms, 150.0).clamp(5.0, 5000.0),
+        }
+    }
+}
+
+/// Noise gate with hold
+pub struct Gate {
+    settings: ArcSwap<GateSettings>,
+    version: AtomicU64,
+    /// 以下はオーディオスレッドのみ
+    applied_version: u64,
+    applied_rate: f64,
+    attack: f32,
+    release: f32,
+    hold_samples: u32,
+    hold_left: u32,
+    gain: f32,
+}
+
+impl Gate {
+    pub fn new(settings: GateSettings) -> Self {
+        Self {
+            settings: ArcSwap::from_pointee(settings.clamped()),
+            version: AtomicU64::new(1),
+            applied_version: 0,
+            applied_rate: 0.0,
+            attack: 0.0,
+            release: 0.0,
+            hold_samples: 0,
+            hold_left: 0,
+            gain: 0.0,
+        }
+    }
+
+    pub fn settings(&self) -> GateSettings {
+        **self.settings.load()
+    }
+
+    /// Replace the settings (control thread)
+    pub fn set_settings(&self, settings: GateSettings) {
+        self.settings.store(Arc::new(settings.clamped()));
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Whether the gate is currently (mostly) open
+    pub fn is_open(&self) -> bool {
+        self.gain > 0.5
+    }
+
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        let version = self.version.load(Ordering::Acquire);
+        let sample_rate = engine_sample_rate();
+        let s = self.settings();
+        if version != self.applied_version || sample_rate != self.applied_rate {
+            self.attack = time_coeff(s.attack_ms, sample_rate);
+            self.release = time_coeff(s.release_ms, sample_rate);
+            self.hold_samples = (s.hold_ms as f64 * 0.001 * sample_rate) as u32;
+            self.applied_version = version;
+            self.applied_rate = sample_rate;
+        }
+        let threshold = db_to_lin(s.threshold_db);
+        let floor = db_to_lin(s.range_db);
+
+        for (l, r) in left.iter_mut().zip(right.iter_mut()) {
+            if l.abs().max(r.abs()) >= threshold {
+                self.hold_left = self.hold_samples;
+            } else if self.hold_left > 0 {
+                self.hold_left -= 1;
+            }
+            let open = self.hold_left > 0;
+            let (target, coeff) = if open {
+                (1.0, self.attack)
+            } else {
+                (floor, self.release)
+            };
+            self.gain = target + coeff * (self.gain - target);
+            *l *= self.gain;
+            *r *= self.gain;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.hold_left = 0;
+        self.gain = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compressor_reduces_above_threshold() {
+        let mut comp = Compressor::new(CompressorSettings {
+            threshold_db: -20.0,
+            ratio: 4.0,
+            attack_ms: 0.1,
+            release_ms: 50.0,
+            makeup_db: 0.0,
+        });
+        // Steady 0 dBFS: 20 dB over -> 15 dB reduction -> -15 dBFS
+        let mut left = vec![1.0f32; 4800];
+        let mut right = vec![1.0f32; 4800];
+        comp.process(&mut left, &mut right);
+        assert!((lin_to_db(left[4799]) + 15.0).abs() < 0.1, "{}", left[4799]);
+        assert!((comp.gain_reduction_db() - 15.0).abs() < 0.1);
+
+        // Below threshold: untouched once the envelope has released
+        let mut quiet_l = vec![0.01f32; 48_000];
+        let mut quiet_r = vec![0.01f32; 48_000];
+        comp.process(&mut quiet_l, &mut quiet_r);
+        assert!((quiet_l[47_999] - 0.01).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_gate_closes_below_threshold_after_hold() {
+        let mut gate = Gate::new(GateSettings {
+            threshold_db: -40.0,
+            range_db: -60.0,
+            attack_ms: 0.1,
+            hold_ms: 10.0,
+            release_ms: 5.0,
+        });
+        let mut left = vec![0.5f32; 480];
+        let mut right = vec![0.5f32; 480];
+        gate.process(&mut left, &mut right);
+        assert!(gate.is_open());
+        assert!((left[479] - 0.5).abs() < 1e-3);
+
+        // -60 dBFS noise: hold (480 samples) then ~-60 dB
+        let mut left = vec![0.001f32; 9600];
+        let mut right = vec![0.001f32; 9600];
+        gate.process(&mut left, &mut right);
+        assert!(!gate.is_open());
+        assert!((left[100] - 0.001).abs() < 1e-5);
+        assert!(left[9599] < 0.001 * 0.01);
+    }
+}
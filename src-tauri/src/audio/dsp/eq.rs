@@ -0,0 +1,244 @@
+//! 4-band parametric EQ (vDSP biquad cascade)
+//!
+//! 設定は制御スレッドから [`ParametricEq::set_settings`] で差し替え、
+//! オーディオスレッドは次のブロックの先頭で係数を計算し直す。
+
+use super::biquad::BiquadCoeffs;
+use crate::audio::engine_sample_rate;
+use crate::vdsp::Biquad;
+use arc_swap::ArcSwap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// バンド数
+pub const EQ_BANDS: usize = 4;
+
+const MAX_GAIN_DB: f32 = 24.0;
+
+/// Filter shape of one band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqBandKind {
+    LowShelf,
+    Peak,
+    HighShelf,
+    /// High-pass
+    LowCut,
+    /// Low-pass
+    HighCut,
+}
+
+impl EqBandKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low_shelf" | "lowshelf" => Some(Self::LowShelf),
+            "peak" | "bell" => Some(Self::Peak),
+            "high_shelf" | "highshelf" => Some(Self::HighShelf),
+            "low_cut" | "highpass" | "high_pass" => Some(Self::LowCut),
+            "high_cut" | "lowpass" | "low_pass" => Some(Self::HighCut),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::LowShelf => "low_shelf",
+            Self::Peak => "peak",
+            Self::HighShelf => "high_shelf",
+            Self::LowCut => "low_cut",
+            Self::HighCut => "high_cut",
+        }
+    }
+}
+
+/// One EQ band
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    pub kind: EqBandKind,
+    pub frequency: f32,
+    pub gain_db: f32,
+    pub q: f32,
+    pub enabled: bool,
+}
+
+impl EqBand {
+    fn new(kind: EqBandKind, frequency: f32) -> Self {
+        Self {
+            kind,
+            frequency,
+            gain_db: 0.0,
+            q: 0.707,
+            enabled: true,
+        }
+    }
+
+    /// Whether this band changes the signal at all
+    fn is_active(&self) -> bool {
+        self.enabled
+            && (matches!(self.kind, EqBandKind::LowCut | EqBandKind::HighCut)
+                || self.gain_db.abs() > 0.01)
+    }
+
+    fn coeffs(&self, sample_rate: f64) -> BiquadCoeffs {
+        if !self.is_active() {
+            return BiquadCoeffs::IDENTITY;
+        }
+        let (f, q, g) = (self.frequency as f64, self.q as f64, self.gain_db as f64);
+        match self.kind {
+            EqBandKind::LowShelf => BiquadCoeffs::low_shelf(sample_rate, f, q, g),
+            EqBandKind::Peak => BiquadCoeffs::peak(sample_rate, f, q, g),
+            EqBandKind::HighShelf => BiquadCoeffs::high_shelf(sample_rate, f, q, g),
+            EqBandKind::LowCut => BiquadCoeffs::high_pass(sample_rate, f, q),
+            EqBandKind::HighCut => BiquadCoeffs::low_pass(sample_rate, f, q),
+        }
+    }
+}
+
+/// EQ settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqSettings {
+    pub bands: [EqBand; EQ_BANDS],
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        // Low shelf / 2 x peak / high shelf, all flat
+        Self {
+            bands: [
+                EqBand::new(EqBandKind::LowShelf, 100.0),
+                EqBand::new(EqBandKind::Peak, 500.0),
+                EqBand::new(EqBandKind::Peak, 2000.0),
+                EqBand::new(EqBandKind::HighShelf, 8000.0),
+            ],
+        }
+    }
+}
+
+impl EqSettings {
+    /// Clamp every value into a usable range
+    pub fn clamped(mut self) -> Self {
+        for band in &mut self.bands {
+            band.frequency = finite_or(band.frequency, 1000.0).clamp(10.0, 22_000.0);
+            band.gain_db = finite_or(band.gain_db, 0.0).clamp(-MAX_GAIN_DB, MAX_GAIN_DB);
+            band.q = finite_or(band.q, 0.707).clamp(0.1, 18.0);
+        }
+        self
+    }
+}
+
+fn finite_or(v: f32, default: f32) -> f32 {
+    if v.is_finite() {
+        v
+    } else {
+        default
+    }
+}
+
+/// 4-band parametric EQ (stereo, same curve on both channels)
+pub struct ParametricEq {
+    settings: ArcSwap<EqSettings>,
+    version: AtomicU64,
+    /// 以下はオーディオスレッドのみ
+    applied_version: u64,
+    applied_rate: f64,
+    active: bool,
+    biquad: Option<Biquad>,
+    delays: [Box<[f32]>; 2],
+}
+
+impl ParametricEq {
+    pub fn new(settings: EqSettings) -> Self {
+        let biquad = Biquad::new(&[BiquadCoeffs::IDENTITY.as_array(); EQ_BANDS].concat());
+        let delay_len = biquad.as_ref().map(|b| b.delay_len()).unwrap_or(0);
+        Self {
+            settings: ArcSwap::from_pointee(settings.clamped()),
+            version: AtomicU64::new(1),
+            applied_version: 0,
+            applied_rate: 0.0,
+            active: false,
+            biquad,
+            delays: [
+                vec![0.0; delay_len].into_boxed_slice(),
+                vec![0.0; delay_len].into_boxed_slice(),
+            ],
+        }
+    }
+
+    pub fn settings(&self) -> EqSettings {
+        **self.settings.load()
+    }
+
+    /// Replace the settings (control thread)
+    pub fn set_settings(&self, settings: EqSettings) {
+        self.settings.store(Arc::new(settings.clamped()));
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    fn update_coefficients(&mut self) {
+        let version = self.version.load(Ordering::Acquire);
+        let sample_rate = engine_sample_rate();
+        if version == self.applied_version && sample_rate == self.applied_rate {
+            return;
+        }
+        let settings = self.settings();
+        let mut coeffs = [0.0f64; EQ_BANDS * 5];
+        for (band, chunk) in settings.bands.iter().zip(coeffs.chunks_exact_mut(5)) {
+            chunk.copy_from_slice(&band.coeffs(sample_rate).as_array());
+        }
+        if let Some(biquad) = self.biquad.as_mut() {
+            biquad.set_coefficients(&coeffs);
+        }
+        let active = settings.bands.iter().any(|b| b.is_active());
+        if active && !self.active {
+            // バイパスから戻るときは古い状態でクリックしないよう遅延を消す
+            self.reset();
+        }
+        self.active = active;
+        self.applied_version = version;
+        self.applied_rate = sample_rate;
+    }
+
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.update_coefficients();
+        if !self.active {
+            return;
+        }
+        let Some(biquad) = self.biquad.as_ref() else {
+            return;
+        };
+        let [delay_l, delay_r] = &mut self.delays;
+        biquad.process(delay_l, left);
+        biquad.process(delay_r, right);
+    }
+
+    pub fn reset(&mut self) {
+        for delay in &mut self.delays {
+            delay.fill(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eq_flat_is_bypass_and_boost_applies() {
+        let mut eq = ParametricEq::new(EqSettings::default());
+        let mut left = vec![0.5f32; 256];
+        let mut right = vec![0.5f32; 256];
+        eq.process(&mut left, &mut right);
+        assert!(left.iter().chain(&right).all(|&s| s == 0.5));
+
+        // +6 dB low shelf: DC settles at ~2x
+        let mut settings = eq.settings();
+        settings.bands[0].gain_db = 6.0;
+        eq.set_settings(settings);
+        for _ in 0..20 {
+            left.fill(0.5);
+            right.fill(0.5);
+            eq.process(&mut left, &mut right);
+        }
+        assert!((left[255] - 0.5 * 1.995).abs() < 0.01, "{}", left[255]);
+        assert_eq!(left[255], right[255]);
+    }
+}
@@ -0,0 +1,305 @@
+//! Lookahead brickwall limiter (for sink nodes)
+//!
+//! 全チャンネルをリンクして処理する。必要ゲインの区間最小値 → リリース →
+//! 先読み長の移動平均、の順で平滑化し、信号を同じ長さだけ遅らせることで
+//! ピーク到達前にゲインを下げ切る（オーバーシュートしない）。
+//!
+//! 出力段の `VDsp::clip` は最後の安全策として残る。
+
+use crate::audio::engine_sample_rate;
+use crate::audio::AudioBuffer;
+use arc_swap::ArcSwap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// 先読みの上限 (ms)
+pub const MAX_LOOKAHEAD_MS: f32 = 5.0;
+
+/// 上限レート (192 kHz) での最大先読みサンプル数
+const MAX_WINDOW: usize = (MAX_LOOKAHEAD_MS as usize) * 192 + 1;
+
+/// Limiter settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimiterSettings {
+    pub enabled: bool,
+    /// Ceiling (dBFS)
+    pub threshold_db: f32,
+    pub release_ms: f32,
+    pub lookahead_ms: f32,
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: -0.3,
+            release_ms: 80.0,
+            lookahead_ms: 1.5,
+        }
+    }
+}
+
+impl LimiterSettings {
+    pub fn clamped(self) -> Self {
+        let or = |v: f32, d: f32| if v.is_finite() { v } else { d };
+        Self {
+            enabled: self.enabled,
+            threshold_db: or(self.threshold_db, -0.3).clamp(-24.0, 0.0),
+            release_ms: or(self.release_ms, 80.0).clamp(1.0, 2000.0),
+            lookahead_ms: or(self.lookahead_ms, 1.5).clamp(0.1, MAX_LOOKAHEAD_MS),
+        }
+    }
+}
+
+/// Multichannel lookahead limiter
+pub struct LookaheadLimiter {
+    settings: ArcSwap<LimiterSettings>,
+    version: AtomicU64,
+    /// 直近ブロックの最大ゲインリダクション (dB, f32 bits)
+    gain_reduction_bits: AtomicU32,
+    /// 以下はオーディオスレッドのみ
+    applied_version: u64,
+    applied_rate: f64,
+    active: bool,
+    ceiling: f32,
+    release: f32,
+    /// 区間長（= 遅延 + 1）
+    window: usize,
+    /// チャンネルごとの遅延線
+    delay: Vec<Box<[f32]>>,
+    delay_pos: usize,
+    /// 区間最小値用の単調キュー（(サンプル番号, 必要ゲイン) のリング）
+    min_queue: Box<[(u64, f32)]>,
+    min_head: usize,
+    min_len: usize,
+    /// 移動平均用
+    avg_ring: Box<[f32]>,
+    avg_sum: f64,
+    avg_pos: usize,
+    envelope: f32,
+    sample_index: u64,
+}
+
+impl LookaheadLimiter {
+    pub fn new(channels: usize, settings: LimiterSettings) -> Self {
+        Self {
+            settings: ArcSwap::from_pointee(settings.clamped()),
+            version: AtomicU64::new(1),
+            gain_reduction_bits: AtomicU32::new(0),
+            applied_version: 0,
+            applied_rate: 0.0,
+            active: false,
+            ceiling: 1.0,
+            release: 0.0,
+            window: 1,
+            delay: (0..channels.max(1))
+                .map(|_| vec![0.0f32; MAX_WINDOW].into_boxed_slice())
+                .collect(),
+            delay_pos: 0,
+            min_queue: vec![(0, 1.0); MAX_WINDOW].into_boxed_slice(),
+            min_head: 0,
+            min_len: 0,
+            avg_ring: vec![1.0f32; MAX_WINDOW].into_boxed_slice(),
+            avg_sum: 0.0,
+            avg_pos: 0,
+            envelope: 1.0,
+            sample_index: 0,
+        }
+    }
+
+    pub fn settings(&self) -> LimiterSettings {
+        **self.settings.load()
+    }
+
+    /// Replace the settings (control thread)
+    pub fn set_settings(&self, settings: LimiterSettings) {
+        self.settings.store(Arc::new(settings.clamped()));
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Largest gain reduction of the last block (dB, positive)
+    pub fn gain_reduction_db(&self) -> f32 {
+        f32::from_bits(self.gain_reduction_bits.load(Ordering::Relaxed))
+    }
+
+    /// Added latency in samples (0 when disabled)
+    pub fn latency_samples(&self) -> u32 {
+        let s = self.settings();
+        if !s.enabled {
+            return 0;
+        }
+        (s.lookahead_ms as f64 * 0.001 * engine_sample_rate()).round() as u32
+    }
+
+    fn update(&mut self) {
+        let version = self.version.load(Ordering::Acquire);
+        let sample_rate = engine_sample_rate();
+        if version == self.applied_version && sample_rate == self.applied_rate {
+            return;
+        }
+        let s = self.settings();
+        let lookahead = (s.lookahead_ms as f64 * 0.001 * sample_rate).round() as usize;
+        let window = (lookahead + 1).clamp(1, MAX_WINDOW);
+        if s.enabled != self.active || window != self.window {
+            self.window = window;
+            self.reset();
+        }
+        self.active = s.enabled;
+        self.ceiling = 10f32.powf(s.threshold_db / 20.0);
+        self.release = (-1.0 / (s.release_ms as f64 * 0.001 * sample_rate)).exp() as f32;
+        self.applied_version = version;
+        self.applied_rate = sample_rate;
+    }
+
+    /// Clear the delay lines and gain state
+    pub fn reset(&mut self) {
+        for line in &mut self.delay {
+            line.fill(0.0);
+        }
+        self.delay_pos = 0;
+        self.min_head = 0;
+        self.min_len = 0;
+        self.avg_ring[..self.window].fill(1.0);
+        self.avg_sum = self.window as f64;
+        self.avg_pos = 0;
+        self.envelope = 1.0;
+    }
+
+    /// Limit `buffers` in-place (audio thread)
+    ///
+    /// `gains` はバッファの後段で掛かるチャンネルゲイン。検出はゲイン適用後の
+    /// レベルで行い、リダクションだけをバッファに掛ける。
+    pub fn process(&mut self, buffers: &mut [AudioBuffer], gains: &[f32], frames: usize) {
+        self.update();
+        if !self.active {
+            return;
+        }
+        let channels = buffers.len().min(self.delay.len());
+        let window = self.window;
+        let mut min_gain = 1.0f32;
+
+        for i in 0..frames {
+            // 1. このサンプルに必要なゲイン
+            let mut peak = 0.0f32;
+            for (ch, buf) in buffers.iter().enumerate().take(channels) {
+                let g = gains.get(ch).copied().unwrap_or(1.0);
+                if let Some(&s) = buf.samples().get(i) {
+                    peak = peak.max((s * g).abs());
+                }
+            }
+            let required = if peak > self.ceiling {
+                self.ceiling / peak
+            } else {
+                1.0
+            };
+
+            // 2. 直近 window サンプルの最小値（単調キュー）
+            let n = self.sample_index;
+            while self.min_len > 0 {
+                let tail = (self.min_head + self.min_len - 1) % MAX_WINDOW;
+                if self.min_queue[tail].1 >= required {
+                    self.min_len -= 1;
+                } else {
+                    break;
+                }
+            }
+            let tail = (self.min_head + self.min_len) % MAX_WINDOW;
+            self.min_queue[tail] = (n, required);
+            self.min_len += 1;
+            if self.min_queue[self.min_head].0 + window as u64 <= n {
+                self.min_head = (self.min_head + 1) % MAX_WINDOW;
+                self.min_len -= 1;
+            }
+            let window_min = self.min_queue[self.min_head].1;
+
+            // 3. 下げは即時、戻りはリリース
+            self.envelope = if window_min < self.envelope {
+                window_min
+            } else {
+                window_min + self.release * (self.envelope - window_min)
+            };
+
+            // 4. 先読み長の移動平均でなめらかにする
+            self.avg_sum += (self.envelope - self.avg_ring[self.avg_pos]) as f64;
+            self.avg_ring[self.avg_pos] = self.envelope;
+            self.avg_pos = (self.avg_pos + 1) % window;
+            let gain = ((self.avg_sum / window as f64) as f32).min(1.0);
+            min_gain = min_gain.min(gain);
+
+            // 5. window - 1 サンプル遅らせた信号にゲインを掛ける
+            let read = (self.delay_pos + MAX_WINDOW + 1 - window) % MAX_WINDOW;
+            for (ch, buf) in buffers.iter_mut().enumerate().take(channels) {
+                let line = &mut self.delay[ch];
+                if let Some(s) = buf.samples_mut().get_mut(i) {
+                    line[self.delay_pos] = *s;
+                    *s = line[read] * gain;
+                }
+            }
+            self.delay_pos = (self.delay_pos + 1) % MAX_WINDOW;
+            self.sample_index += 1;
+        }
+
+        let reduction = if min_gain < 1.0 {
+            -20.0 * min_gain.max(1e-9).log10()
+        } else {
+            0.0
+        };
+        self.gain_reduction_bits
+            .store(reduction.to_bits(), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limiter_never_exceeds_ceiling() {
+        let settings = LimiterSettings {
+            enabled: true,
+            threshold_db: -6.0,
+            release_ms: 20.0,
+            lookahead_ms: 1.0,
+        };
+        let mut limiter = LookaheadLimiter::new(2, settings);
+        let ceiling = 10f32.powf(-6.0 / 20.0);
+        let gains = [1.0f32, 2.0];
+
+        let mut buffers = vec![AudioBuffer::new(), AudioBuffer::new()];
+        let mut max_out = 0.0f32;
+        for block in 0..20 {
+            for (ch, buf) in buffers.iter_mut().enumerate() {
+                buf.clear(256);
+                for (i, s) in buf.samples_mut().iter_mut().enumerate() {
+                    // Quiet sine with a full-scale burst in block 5
+                    let amp = if block == 5 && (64..96).contains(&i) {
+                        1.0
+                    } else {
+                        0.1
+                    };
+                    *s = amp * ((i as f32) * 0.3 + ch as f32).sin();
+                }
+            }
+            limiter.process(&mut buffers, &gains, 256);
+            for (ch, buf) in buffers.iter().enumerate() {
+                for &s in buf.samples() {
+                    max_out = max_out.max((s * gains[ch]).abs());
+                }
+            }
+        }
+        assert!(max_out <= ceiling + 1e-4, "{} > {}", max_out, ceiling);
+        assert!(max_out > ceiling * 0.9);
+        assert_eq!(limiter.latency_samples(), 48);
+    }
+
+    #[test]
+    fn test_limiter_disabled_is_passthrough() {
+        let mut limiter = LookaheadLimiter::new(1, LimiterSettings::default());
+        let mut buffers = vec![AudioBuffer::new()];
+        buffers[0].clear(64);
+        buffers[0].samples_mut().fill(1.5);
+        limiter.process(&mut buffers, &[1.0], 64);
+        assert!(buffers[0].samples().iter().all(|&s| s == 1.5));
+        assert_eq!(limiter.latency_samples(), 0);
+    }
+}
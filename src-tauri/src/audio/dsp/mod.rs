@@ -0,0 +1,117 @@
+//! Native DSP - Built-in bus processors (EQ / compressor / gate)
+//!
+//! AudioUnit なしで使える基本処理。バスのプラグインチェーンに AU と同じ扱いで並び、
+//! 並べ替え・バイパスもそのまま使える。設定はグラフ状態に保存されるので、
+//! 外部プラグインがない環境でも復元できる。
+//!
+//! plugin_id は `native:eq` / `native:compressor` / `native:gate`。
+//! シンク用のリミッター（[`limiter`]）と出力保護（[`protection`]）もここに置く。
+
+pub mod biquad;
+pub mod dynamics;
+pub mod eq;
+pub mod limiter;
+pub mod protection;
+
+pub use dynamics::{Compressor, CompressorSettings, Gate, GateSettings};
+pub use eq::{EqBand, EqBandKind, EqSettings, ParametricEq, EQ_BANDS};
+pub use limiter::{LimiterSettings, LookaheadLimiter};
+pub use protection::{OutputProtection, ProtectionSettings};
+
+/// plugin_id prefix of native processors
+pub const NATIVE_PLUGIN_PREFIX: &str = "native:";
+
+/// Manufacturer shown for native processors
+pub const NATIVE_MANUFACTURER: &str = "Spectrum";
+
+/// (plugin_id, display name) of every native processor
+pub const NATIVE_PLUGINS: [(&str, &str); 3] = [
+    ("native:eq", "Spectrum EQ"),
+    ("native:compressor", "Spectrum Compressor"),
+    ("native:gate", "Spectrum Gate"),
+];
+
+/// Whether a plugin_id refers to a native processor
+pub fn is_native_plugin_id(plugin_id: &str) -> bool {
+    plugin_id.starts_with(NATIVE_PLUGIN_PREFIX)
+}
+
+/// Settings of any native processor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NativeSettings {
+    Eq(EqSettings),
+    Compressor(CompressorSettings),
+    Gate(GateSettings),
+}
+
+/// A native processor in a bus plugin chain
+pub enum NativeProcessor {
+    Eq(ParametricEq),
+    Compressor(Compressor),
+    Gate(Gate),
+}
+
+impl NativeProcessor {
+    /// Create a processor with default settings (None for unknown ids)
+    pub fn from_plugin_id(plugin_id: &str) -> Option<Self> {
+        let settings = match plugin_id.strip_prefix(NATIVE_PLUGIN_PREFIX)? {
+            "eq" => NativeSettings::Eq(EqSettings::default()),
+            "compressor" => NativeSettings::Compressor(CompressorSettings::default()),
+            "gate" => NativeSettings::Gate(GateSettings::default()),
+            _ => return None,
+        };
+        Some(Self::with_settings(settings))
+    }
+
+    pub fn with_settings(settings: NativeSettings) -> Self {
+        match settings {
+            NativeSettings::Eq(s) => Self::Eq(ParametricEq::new(s)),
+            NativeSettings::Compressor(s) => Self::Compressor(Compressor::new(s)),
+            NativeSettings::Gate(s) => Self::Gate(Gate::new(s)),
+        }
+    }
+
+    pub fn settings(&self) -> NativeSettings {
+        match self {
+            Self::Eq(p) => NativeSettings::Eq(p.settings()),
+            Self::Compressor(p) => NativeSettings::Compressor(p.settings()),
+            Self::Gate(p) => NativeSettings::Gate(p.settings()),
+        }
+    }
+
+    /// Replace the settings (control thread); the kind must match
+    pub fn set_settings(&self, settings: NativeSettings) -> Result<(), String> {
+        match (self, settings) {
+            (Self::Eq(p), NativeSettings::Eq(s)) => p.set_settings(s),
+            (Self::Compressor(p), NativeSettings::Compressor(s)) => p.set_settings(s),
+            (Self::Gate(p), NativeSettings::Gate(s)) => p.set_settings(s),
+            _ => return Err("Settings do not match the processor kind".to_string()),
+        }
+        Ok(())
+    }
+
+    /// Process a stereo pair in-place (audio thread)
+    pub fn process(&mut self, left: &mut [f32], right: &mut [f32]) {
+        match self {
+            Self::Eq(p) => p.process(left, right),
+            Self::Compressor(p) => p.process(left, right),
+            Self::Gate(p) => p.process(left, right),
+        }
+    }
+
+    /// Clear filter/envelope state
+    pub fn reset(&mut self) {
+        match self {
+            Self::Eq(p) => p.reset(),
+            Self::Compressor(p) => p.reset(),
+            Self::Gate(p) => p.reset(),
+        }
+    }
+}
+
+impl Clone for NativeProcessor {
+    fn clone(&self) -> Self {
+        // 設定のみ複製し、内部状態は初期化する
+        Self::with_settings(self.settings())
+    }
+}
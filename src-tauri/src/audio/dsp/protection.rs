@@ -0,0 +1,297 @@
+//! Output protection - dims a sink after sustained over-threshold levels
+//!
+//! フィードバックの暴走やプラグインの発振でヘッドホンに大音量が出続けるのを防ぐ。
+//! ポートゲイン適用後のブロックピークがしきい値を `hold_ms` 以上超え続けたら
+//! 出力を `dim_db` だけ下げ（短いランプ付き）、解除されるまで下げたままにする。
+//! 一瞬のピークでは反応しない（それはリミッターの仕事）。
+//!
+//! 解除は設定の変更または [`OutputProtection::rearm`]。発動回数は [`trip_count`] で
+//! 監視スレッドが拾い、イベントとして通知する。
+
+use crate::audio::engine_sample_rate;
+use crate::audio::AudioBuffer;
+use arc_swap::ArcSwap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Dim ramp time constant (ms)
+const RAMP_MS: f64 = 10.0;
+
+/// Trips of every sink since startup (polled by the event watcher)
+static TRIPS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of times any sink's protection tripped
+pub fn trip_count() -> u64 {
+    TRIPS.load(Ordering::Relaxed)
+}
+
+/// Output protection settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtectionSettings {
+    pub enabled: bool,
+    /// Level that counts as a spike (dBFS, after the port gain)
+    pub threshold_db: f32,
+    /// How long the level must stay above the threshold before dimming (ms)
+    pub hold_ms: f32,
+    /// Gain applied once tripped (dB, negative)
+    pub dim_db: f32,
+}
+
+impl Default for ProtectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: 0.0,
+            hold_ms: 200.0,
+            dim_db: -20.0,
+        }
+    }
+}
+
+impl ProtectionSettings {
+    pub fn clamped(self) -> Self {
+        let or = |v: f32, d: f32| if v.is_finite() { v } else { d };
+        Self {
+            enabled: self.enabled,
+            threshold_db: or(self.threshold_db, 0.0).clamp(-24.0, 12.0),
+            hold_ms: or(self.hold_ms, 200.0).clamp(10.0, 5000.0),
+            dim_db: or(self.dim_db, -20.0).clamp(-60.0, -1.0),
+        }
+    }
+}
+
+/// Spike detector and dimmer of one sink
+pub struct OutputProtection {
+    settings: ArcSwap<ProtectionSettings>,
+    version: AtomicU64,
+    /// 発動中（rearm まで）
+    tripped: AtomicBool,
+    /// 発動までの区間の最大ピーク (dBFS, f32 bits)
+    trip_peak_bits: AtomicU32,
+    /// 以下はオーディオスレッドのみ
+    applied_version: u64,
+    applied_rate: f64,
+    active: bool,
+    latched: bool,
+    threshold: f32,
+    hold_frames: u64,
+    dim: f32,
+    ramp: f32,
+    over_frames: u64,
+    over_peak: f32,
+    gain: f32,
+}
+
+impl OutputProtection {
+    pub fn new(settings: ProtectionSettings) -> Self {
+        Self {
+            settings: ArcSwap::from_pointee(settings.clamped()),
+            version: AtomicU64::new(1),
+            tripped: AtomicBool::new(false),
+            trip_peak_bits: AtomicU32::new(0),
+            applied_version: 0,
+            applied_rate: 0.0,
+            active: false,
+            latched: false,
+            threshold: 1.0,
+            hold_frames: 0,
+            dim: 1.0,
+            ramp: 0.0,
+            over_frames: 0,
+            over_peak: 0.0,
+            gain: 1.0,
+        }
+    }
+
+    pub fn settings(&self) -> ProtectionSettings {
+        **self.settings.load()
+    }
+
+    /// Replace the settings and re-arm (control thread)
+    pub fn set_settings(&self, settings: ProtectionSettings) {
+        self.settings.store(Arc::new(settings.clamped()));
+        self.rearm();
+    }
+
+    /// Lift the dim and start detecting again (control thread)
+    pub fn rearm(&self) {
+        self.tripped.store(false, Ordering::Relaxed);
+        self.version.fetch_add(1, Ordering::Release);
+    }
+
+    /// Whether the output is currently dimmed
+    pub fn is_tripped(&self) -> bool {
+        self.tripped.load(Ordering::Relaxed)
+    }
+
+    /// Highest level seen before the last trip (dBFS)
+    pub fn trip_peak_db(&self) -> f32 {
+        f32::from_bits(self.trip_peak_bits.load(Ordering::Relaxed))
+    }
+
+    fn update(&mut self) {
+        let version = self.version.load(Ordering::Acquire);
+        let sample_rate = engine_sample_rate();
+        if version == self.applied_version && sample_rate == self.applied_rate {
+            return;
+        }
+        let s = self.settings();
+        if version != self.applied_version {
+            // 設定変更・rearm で検出をやり直す（ゲインはランプで戻す）
+            self.latched = false;
+            self.over_frames = 0;
+            self.over_peak = 0.0;
+            self.tripped.store(false, Ordering::Relaxed);
+        }
+        self.active = s.enabled;
+        self.threshold = 10f32.powf(s.threshold_db / 20.0);
+        self.hold_frames = (s.hold_ms as f64 * 0.001 * sample_rate).round() as u64;
+        self.dim = 10f32.powf(s.dim_db / 20.0);
+        self.ramp = (-1.0 / (RAMP_MS * 0.001 * sample_rate)).exp() as f32;
+        self.applied_version = version;
+        self.applied_rate = sample_rate;
+    }
+
+    /// Detect spikes and dim `buffers` in-place (audio thread)
+    ///
+    /// `gains` はバッファの後段で掛かるチャンネルゲイン（リミッターと同じ）。
+    pub fn process(&mut self, buffers: &mut [AudioBuffer], gains: &[f32], frames: usize) {
+        self.update();
+        if !self.active && self.gain >= 1.0 {
+            return;
+        }
+
+        if self.active && !self.latched {
+            let mut peak = 0.0f32;
+            for (ch, buf) in buffers.iter().enumerate() {
+                let g = gains.get(ch).copied().unwrap_or(1.0);
+                for &s in buf.samples().iter().take(frames) {
+                    peak = peak.max((s * g).abs());
+                }
+            }
+            // NaN / inf も暴走とみなす
+            if peak > self.threshold || !peak.is_finite() {
+                self.over_frames += frames as u64;
+                self.over_peak = self.over_peak.max(peak);
+            } else {
+                self.over_frames = 0;
+                self.over_peak = 0.0;
+            }
+            if self.over_frames >= self.hold_frames {
+                self.latched = true;
+                let peak_db = if self.over_peak.is_finite() {
+                    20.0 * self.over_peak.max(1e-9).log10()
+                } else {
+                    f32::MAX
+                };
+                self.trip_peak_bits
+                    .store(peak_db.to_bits(), Ordering::Relaxed);
+                self.tripped.store(true, Ordering::Relaxed);
+                TRIPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let target = if self.active && self.latched {
+            self.dim
+        } else {
+            1.0
+        };
+        if self.gain == target && target >= 1.0 {
+            return;
+        }
+        for i in 0..frames {
+            self.gain = target + self.ramp * (self.gain - target);
+            if (self.gain - target).abs() < 1e-5 {
+                self.gain = target;
+            }
+            for buf in buffers.iter_mut() {
+                if let Some(s) = buf.samples_mut().get_mut(i) {
+                    *s *= self.gain;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> ProtectionSettings {
+        ProtectionSettings {
+            enabled: true,
+            threshold_db: -6.0,
+            hold_ms: 20.0,
+            dim_db: -20.0,
+        }
+    }
+
+    fn run_block(protection: &mut OutputProtection, amp: f32) -> f32 {
+        let mut buffers = vec![AudioBuffer::new(), AudioBuffer::new()];
+        for buf in &mut buffers {
+            buf.clear(256);
+            for (i, s) in buf.samples_mut().iter_mut().enumerate() {
+                *s = amp * ((i as f32) * 0.3).sin();
+            }
+        }
+        protection.process(&mut buffers, &[1.0, 1.0], 256);
+        buffers
+            .iter()
+            .flat_map(|b| b.samples().iter())
+            .fold(0.0f32, |m, &s| m.max(s.abs()))
+    }
+
+    #[test]
+    fn test_sustained_spike_trips_and_dims() {
+        let mut protection = OutputProtection::new(settings());
+        let before = trip_count();
+
+        // A single loud block is not sustained
+        run_block(&mut protection, 1.0);
+        run_block(&mut protection, 0.1);
+        assert!(!protection.is_tripped());
+
+        // 20 ms at 48 kHz = 960 frames -> trips on the 4th block
+        for _ in 0..4 {
+            run_block(&mut protection, 1.0);
+        }
+        assert!(protection.is_tripped());
+        assert!(trip_count() > before);
+        assert!(protection.trip_peak_db() > -1.0);
+
+        // Stays dimmed (-20 dB) after the ramp, even once the level drops
+        for _ in 0..20 {
+            run_block(&mut protection, 1.0);
+        }
+        let out = run_block(&mut protection, 0.5);
+        assert!(out < 0.5 * 0.11, "{}", out);
+        assert!(protection.is_tripped());
+    }
+
+    #[test]
+    fn test_rearm_restores_gain() {
+        let mut protection = OutputProtection::new(settings());
+        for _ in 0..30 {
+            run_block(&mut protection, 1.0);
+        }
+        assert!(protection.is_tripped());
+
+        protection.rearm();
+        assert!(!protection.is_tripped());
+        for _ in 0..20 {
+            run_block(&mut protection, 0.1);
+        }
+        let out = run_block(&mut protection, 0.1);
+        assert!((out - 0.1).abs() < 0.01, "{}", out);
+        assert!(!protection.is_tripped());
+    }
+
+    #[test]
+    fn test_disabled_is_passthrough() {
+        let mut protection = OutputProtection::new(ProtectionSettings::default());
+        for _ in 0..30 {
+            assert!(run_block(&mut protection, 2.0) > 1.9);
+        }
+        assert!(!protection.is_tripped());
+    }
+}
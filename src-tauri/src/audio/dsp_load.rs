@@ -0,0 +1,159 @@
+//! DSP Load - render time vs. buffer deadline
+//!
+//! オーディオコールバック（出力デバイス単位）とノード処理（バス/プラグインチェーン単位）の
+//! 処理時間を計測し、バッファ周期に対する割合として記録する。
+//! 記録は Atomic のみで行うため、オーディオスレッドから安全に呼べる。
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+/// 移動平均の係数（1 コールバックあたり）
+const AVERAGE_COEFF: f32 = 0.05;
+
+/// 処理負荷メーター
+///
+/// 値はいずれも「処理時間 / バッファ周期」（1.0 = デッドラインちょうど）。
+#[derive(Debug, Default)]
+pub struct LoadMeter {
+    last_bits: AtomicU32,
+    average_bits: AtomicU32,
+    peak_bits: AtomicU32,
+    /// Buffer period of the last callback (µs)
+    deadline_us: AtomicU32,
+    callbacks: AtomicU64,
+}
+
+/// [`LoadMeter`] の読み出し値
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LoadSnapshot {
+    pub last: f32,
+    pub average: f32,
+    pub peak: f32,
+    /// Buffer period of the last callback (ms)
+    pub budget_ms: f32,
+    pub callbacks: u64,
+}
+
+impl LoadMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 1 回分の処理時間を記録（オーディオスレッド）
+    #[inline]
+    pub fn record(&self, elapsed: Duration, frames: usize, sample_rate: f64) {
+        if frames == 0 || sample_rate <= 0.0 {
+            return;
+        }
+        let deadline = frames as f64 / sample_rate;
+        let load = (elapsed.as_secs_f64() / deadline) as f32;
+
+        let count = self.callbacks.fetch_add(1, Ordering::Relaxed);
+        let average = if count == 0 {
+            load
+        } else {
+            let prev = f32::from_bits(self.average_bits.load(Ordering::Relaxed));
+            prev + (load - prev) * AVERAGE_COEFF
+        };
+
+        self.last_bits.store(load.to_bits(), Ordering::Relaxed);
+        self.deadline_us
+            .store((deadline * 1e6) as u32, Ordering::Relaxed);
+        self.average_bits
+            .store(average.to_bits(), Ordering::Relaxed);
+        if load > f32::from_bits(self.peak_bits.load(Ordering::Relaxed)) {
+            self.peak_bits.store(load.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> LoadSnapshot {
+        LoadSnapshot {
+            last: f32::from_bits(self.last_bits.load(Ordering::Relaxed)),
+            average: f32::from_bits(self.average_bits.load(Ordering::Relaxed)),
+            peak: f32::from_bits(self.peak_bits.load(Ordering::Relaxed)),
+            budget_ms: self.deadline_us.load(Ordering::Relaxed) as f32 / 1000.0,
+            callbacks: self.callbacks.load(Ordering::Relaxed),
+        }
+    }
+
+    /// ピークをリセット
+    pub fn reset_peak(&self) {
+        self.peak_bits.store(0.0f32.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// 出力デバイスごとのコールバック負荷
+static DEVICE_LOADS: LazyLock<RwLock<HashMap<u32, Arc<LoadMeter>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Get (or create) the load meter for an output device
+///
+/// Call from the control thread and move the `Arc` into the render callback.
+pub fn device_load_meter(device_id: u32) -> Arc<LoadMeter> {
+    DEVICE_LOADS
+        .write()
+        .entry(device_id)
+        .or_insert_with(|| Arc::new(LoadMeter::new()))
+        .clone()
+}
+
+/// Forget a device's load meter (output stopped)
+pub fn remove_device_load_meter(device_id: u32) {
+    DEVICE_LOADS.write().remove(&device_id);
+}
+
+/// Snapshot all output device loads
+pub fn device_loads() -> Vec<(u32, LoadSnapshot)> {
+    let mut loads: Vec<(u32, LoadSnapshot)> = DEVICE_LOADS
+        .read()
+        .iter()
+        .map(|(&id, meter)| (id, meter.snapshot()))
+        .collect();
+    loads.sort_by_key(|(id, _)| *id);
+    loads
+}
+
+/// Reset peaks of all device meters
+pub fn reset_device_peaks() {
+    for meter in DEVICE_LOADS.read().values() {
+        meter.reset_peak();
+    }
+}
+
+/// 全出力デバイス中で最大の平均負荷
+pub fn overall_load() -> f32 {
+    DEVICE_LOADS
+        .read()
+        .values()
+        .map(|m| m.snapshot().average)
+        .fold(0.0, f32::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_meter_ratio_and_peak() {
+        let meter = LoadMeter::new();
+        // 480 frames @ 48kHz = 10ms deadline
+        meter.record(Duration::from_millis(5), 480, 48000.0);
+        let s = meter.snapshot();
+        assert!((s.last - 0.5).abs() < 1e-4);
+        assert!((s.average - 0.5).abs() < 1e-4);
+        assert!((s.budget_ms - 10.0).abs() < 1e-3);
+
+        meter.record(Duration::from_millis(1), 480, 48000.0);
+        let s = meter.snapshot();
+        assert!((s.last - 0.1).abs() < 1e-4);
+        assert!(s.average < 0.5 && s.average > 0.4);
+        assert!((s.peak - 0.5).abs() < 1e-4);
+        assert_eq!(s.callbacks, 2);
+
+        meter.reset_peak();
+        assert_eq!(meter.snapshot().peak, 0.0);
+    }
+}
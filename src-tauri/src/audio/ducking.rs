@@ -0,0 +1,295 @@
+//! Ducking - lower sends while a key source is active (talkback)
+//!
+//! ルールごとにキーノード（マイクのソースなど）のピークを包絡線追従し、
+//! スレッショルドを超えている間、対象エッジのゲインを `depth_db` だけ下げる。
+//! 減衰量はエッジのダック係数（フェーダーとは別）に書き込むので、ユーザーの
+//! フェーダー値は変わらない。
+//!
+//! キーはブロック処理の最後に測り、次のブロックから反映する（1 ブロック遅れ）。
+//! こうするとバスなどソース以外のノードもキーにできる。
+//! オーディオスレッドではアロケーションしない（ルールは ArcSwap のスナップショット）。
+
+use super::edge::EdgeId;
+use super::graph::AudioGraph;
+use super::node::{NodeHandle, PortId};
+use crate::vdsp::VDsp;
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, LazyLock};
+
+/// Ducking rule parameters
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuckingConfig {
+    /// Key node (its output ports, or input ports for sinks)
+    pub key: NodeHandle,
+    /// Edges that are lowered
+    pub targets: Vec<EdgeId>,
+    /// Key level that starts ducking (dBFS)
+    pub threshold_db: f32,
+    /// Gain reduction while ducked (positive dB)
+    pub depth_db: f32,
+    pub attack_ms: f32,
+    pub release_ms: f32,
+    pub enabled: bool,
+}
+
+impl DuckingConfig {
+    /// Clamp parameters to usable ranges
+    pub fn clamped(mut self) -> Self {
+        let finite = |v: f32, default: f32| if v.is_finite() { v } else { default };
+        self.threshold_db = finite(self.threshold_db, -30.0).clamp(-96.0, 0.0);
+        self.depth_db = finite(self.depth_db, 12.0).clamp(0.0, 96.0);
+        self.attack_ms = finite(self.attack_ms, 10.0).clamp(0.1, 1_000.0);
+        self.release_ms = finite(self.release_ms, 300.0).clamp(1.0, 10_000.0);
+        self.targets.sort_by_key(|e| e.raw());
+        self.targets.dedup();
+        self
+    }
+}
+
+/// A rule with its envelope state
+#[derive(Debug)]
+pub struct DuckingRule {
+    pub id: u32,
+    pub config: DuckingConfig,
+    /// Current gain reduction in dB (f32 bits, audio thread)
+    reduction_bits: AtomicU32,
+}
+
+impl DuckingRule {
+    /// Current gain reduction in dB (0 = not ducking)
+    pub fn reduction_db(&self) -> f32 {
+        f32::from_bits(self.reduction_bits.load(Ordering::Relaxed))
+    }
+
+    fn gain(&self) -> f32 {
+        10f32.powf(-self.reduction_db() / 20.0)
+    }
+
+    /// Follow the key level for one block and return the new reduction
+    fn follow(&self, key_peak: f32, block_ms: f32) -> f32 {
+        let cfg = &self.config;
+        let key_db = if key_peak > 0.0 {
+            20.0 * key_peak.log10()
+        } else {
+            f32::NEG_INFINITY
+        };
+        let target = if cfg.enabled && key_db >= cfg.threshold_db {
+            cfg.depth_db
+        } else {
+            0.0
+        };
+        let current = self.reduction_db();
+        let time_ms = if target > current {
+            cfg.attack_ms
+        } else {
+            cfg.release_ms
+        };
+        let coeff = (-block_ms / time_ms).exp();
+        let mut next = target + (current - target) * coeff;
+        if (next - target).abs() < 0.01 {
+            next = target;
+        }
+        self.reduction_bits.store(next.to_bits(), Ordering::Relaxed);
+        next
+    }
+}
+
+static RULES: LazyLock<ArcSwap<Vec<Arc<DuckingRule>>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+/// Serializes rule edits and id allocation
+static EDIT_LOCK: Mutex<u32> = Mutex::new(1);
+
+/// Current rules
+pub fn rules() -> Vec<Arc<DuckingRule>> {
+    RULES.load().as_ref().clone()
+}
+
+/// Create (`id` = None) or replace a rule; returns its id
+///
+/// 置き換え時は現在の減衰量を引き継ぐ（パラメータ変更で跳ねないように）。
+/// 対象から外れたエッジのダック係数は戻す。
+pub fn set_rule(graph: &AudioGraph, id: Option<u32>, config: DuckingConfig) -> Option<u32> {
+    let config = config.clamped();
+    let mut next_id = EDIT_LOCK.lock();
+    let mut list = rules();
+    let (id, reduction) = match id {
+        Some(id) => {
+            let pos = list.iter().position(|r| r.id == id)?;
+            let old = list.remove(pos);
+            release_edges(graph, &old.config.targets, &list, Some(&config.targets));
+            (id, old.reduction_db())
+        }
+        None => {
+            let id = *next_id;
+            *next_id += 1;
+            (id, 0.0)
+        }
+    };
+    list.push(Arc::new(DuckingRule {
+        id,
+        config,
+        reduction_bits: AtomicU32::new(reduction.to_bits()),
+    }));
+    RULES.store(Arc::new(list));
+    Some(id)
+}
+
+/// Remove a rule (its target edges return to full level)
+pub fn remove_rule(graph: &AudioGraph, id: u32) -> bool {
+    let _guard = EDIT_LOCK.lock();
+    let mut list = rules();
+    let Some(pos) = list.iter().position(|r| r.id == id) else {
+        return false;
+    };
+    let old = list.remove(pos);
+    release_edges(graph, &old.config.targets, &list, None);
+    RULES.store(Arc::new(list));
+    true
+}
+
+/// Reset the duck gain of edges no other rule (or the replacement) still controls
+fn release_edges(
+    graph: &AudioGraph,
+    edges: &[EdgeId],
+    remaining: &[Arc<DuckingRule>],
+    replacement: Option<&[EdgeId]>,
+) {
+    for &edge in edges {
+        let still_ducked = replacement.is_some_and(|t| t.contains(&edge))
+            || remaining.iter().any(|r| r.config.targets.contains(&edge));
+        if !still_ducked {
+            if let Some(e) = graph.get_edge(edge) {
+                e.set_duck_gain(1.0);
+            }
+        }
+    }
+}
+
+fn key_peak(graph: &AudioGraph, key: NodeHandle) -> f32 {
+    let Some(node) = graph.get_node(key) else {
+        return 0.0;
+    };
+    let use_outputs = node.output_port_count() > 0;
+    let ports = if use_outputs {
+        node.output_port_count()
+    } else {
+        node.input_port_count()
+    };
+    (0..ports)
+        .filter_map(|i| {
+            let port = PortId::new(i as u8);
+            if use_outputs {
+                node.output_buffer(port)
+            } else {
+                node.input_buffer(port)
+            }
+        })
+        .map(|buf| VDsp::peak(buf.samples()))
+        .fold(0.0, f32::max)
+}
+
+/// Update envelopes from this block's key levels and write the edge duck gains (audio thread)
+pub fn process(graph: &AudioGraph, frames: usize, sample_rate: f64) {
+    let rules = RULES.load();
+    if rules.is_empty() {
+        return;
+    }
+    let block_ms = (frames as f64 * 1000.0 / sample_rate) as f32;
+    for rule in rules.iter() {
+        rule.follow(key_peak(graph, rule.config.key), block_ms);
+    }
+    // 複数のルールが同じエッジを対象にする場合は一番深い減衰を使う
+    for rule in rules.iter() {
+        for &edge in &rule.config.targets {
+            let gain = rules
+                .iter()
+                .filter(|r| r.config.targets.contains(&edge))
+                .map(|r| r.gain())
+                .fold(1.0, f32::min);
+            if let Some(e) = graph.get_edge(edge) {
+                e.set_duck_gain(gain);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(attack_ms: f32, release_ms: f32) -> DuckingRule {
+        DuckingRule {
+            id: 1,
+            config: DuckingConfig {
+                key: NodeHandle::new(1),
+                targets: vec![EdgeId::from(1)],
+                threshold_db: -30.0,
+                depth_db: 12.0,
+                attack_ms,
+                release_ms,
+                enabled: true,
+            }
+            .clamped(),
+            reduction_bits: AtomicU32::new(0.0f32.to_bits()),
+        }
+    }
+
+    #[test]
+    fn test_attack_then_release() {
+        let r = rule(10.0, 100.0);
+        // -6 dBFS key: ducks toward 12 dB within a few attack times
+        for _ in 0..20 {
+            r.follow(0.5, 5.0);
+        }
+        assert_eq!(r.reduction_db(), 12.0);
+        assert!((r.gain() - 10f32.powf(-12.0 / 20.0)).abs() < 1e-6);
+
+        // Silence: release is slower than attack
+        r.follow(0.0, 5.0);
+        let after_one = r.reduction_db();
+        assert!(after_one < 12.0 && after_one > 11.0);
+        for _ in 0..400 {
+            r.follow(0.0, 5.0);
+        }
+        assert_eq!(r.reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_below_threshold_does_not_duck() {
+        let r = rule(10.0, 100.0);
+        r.follow(0.01, 5.0); // -40 dBFS
+        assert_eq!(r.reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_disabled_rule_releases() {
+        let mut r = rule(1.0, 1.0);
+        r.follow(1.0, 50.0);
+        assert_eq!(r.reduction_db(), 12.0);
+        r.config.enabled = false;
+        r.follow(1.0, 50.0);
+        assert_eq!(r.reduction_db(), 0.0);
+    }
+
+    #[test]
+    fn test_config_is_clamped() {
+        let cfg = DuckingConfig {
+            key: NodeHandle::new(1),
+            targets: vec![EdgeId::from(2), EdgeId::from(2)],
+            threshold_db: f32::NAN,
+            depth_db: -5.0,
+            attack_ms: 0.0,
+            release_ms: 1e9,
+            enabled: true,
+        }
+        .clamped();
+        assert_eq!(cfg.threshold_db, -30.0);
+        assert_eq!(cfg.depth_db, 0.0);
+        assert_eq!(cfg.attack_ms, 0.1);
+        assert_eq!(cfg.release_ms, 10_000.0);
+        assert_eq!(cfg.targets.len(), 1);
+    }
+}
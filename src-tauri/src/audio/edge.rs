@@ -1,9 +1,260 @@
 //! Edge (Send) - All level control happens here
 
+use super::automation::GainSegment;
+use super::buffer::AudioBuffer;
 use super::node::{NodeHandle, PortId};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use super::MAX_FRAMES;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
 use std::sync::Arc;
 
+/// レイテンシ補正で挿入できる最大ディレイ（サンプル数）
+pub const MAX_COMPENSATION_SAMPLES: usize = 48000;
+
+/// 1 本のバンドルエッジで運べる最大チャンネル数（7.1.4 + 余裕）
+pub const MAX_BUNDLE_CHANNELS: usize = 16;
+
+/// デフォルトのゲインランプ時間（ミリ秒）
+pub const DEFAULT_GAIN_RAMP_MS: f32 = 10.0;
+
+/// ゲインがこれ以下なら無音として扱う
+const SILENT_GAIN: f32 = 0.0001;
+
+/// パンロー（センター定位時の減衰量）
+///
+/// `ZeroDb` はバランス動作（センターで両チャンネル 0 dB）。既存エッジのデフォルト。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanLaw {
+    #[default]
+    ZeroDb,
+    /// Constant power (sin/cos)
+    Minus3Db,
+    /// Compromise between constant power and linear
+    Minus4_5Db,
+    /// Linear (constant amplitude)
+    Minus6Db,
+}
+
+impl PanLaw {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().trim_end_matches("dB").trim_end_matches("db") {
+            "0" => Some(Self::ZeroDb),
+            "-3" => Some(Self::Minus3Db),
+            "-4.5" => Some(Self::Minus4_5Db),
+            "-6" => Some(Self::Minus6Db),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ZeroDb => "0dB",
+            Self::Minus3Db => "-3dB",
+            Self::Minus4_5Db => "-4.5dB",
+            Self::Minus6Db => "-6dB",
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Self::Minus3Db,
+            2 => Self::Minus4_5Db,
+            3 => Self::Minus6Db,
+            _ => Self::ZeroDb,
+        }
+    }
+
+    /// パン位置 (-1.0 = L, 1.0 = R) に対する (L ゲイン, R ゲイン)
+    pub fn gains(self, pan: f32) -> (f32, f32) {
+        let pan = pan.clamp(-1.0, 1.0);
+        let linear = ((1.0 - pan) * 0.5, (1.0 + pan) * 0.5);
+        let theta = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+        let power = (theta.cos(), theta.sin());
+        match self {
+            Self::ZeroDb => ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0)),
+            Self::Minus3Db => power,
+            Self::Minus4_5Db => ((power.0 * linear.0).sqrt(), (power.1 * linear.1).sqrt()),
+            Self::Minus6Db => linear,
+        }
+    }
+}
+
+/// サムロー（ターゲットより広いバンドルを畳み込むときの減衰量）
+///
+/// ステレオをモノラルのバス/シンクへ送るときなど、はみ出したチャンネルを加算する。
+/// `Minus3Db` は無相関な L/R のパワーを、`Minus6Db` はセンター成分の振幅を保つ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SumLaw {
+    /// Plain sum (may clip correlated material)
+    ZeroDb,
+    /// Constant power
+    #[default]
+    Minus3Db,
+    /// Constant amplitude for centered material
+    Minus6Db,
+}
+
+impl SumLaw {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().trim_end_matches("dB").trim_end_matches("db") {
+            "0" => Some(Self::ZeroDb),
+            "-3" => Some(Self::Minus3Db),
+            "-6" => Some(Self::Minus6Db),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ZeroDb => "0dB",
+            Self::Minus3Db => "-3dB",
+            Self::Minus6Db => "-6dB",
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Self::ZeroDb,
+            2 => Self::Minus6Db,
+            _ => Self::Minus3Db,
+        }
+    }
+
+    /// 畳み込むチャンネルに掛けるゲイン（リニア）
+    pub fn gain(self) -> f32 {
+        match self {
+            Self::ZeroDb => 1.0,
+            Self::Minus3Db => std::f32::consts::FRAC_1_SQRT_2,
+            Self::Minus6Db => 0.5,
+        }
+    }
+}
+
+/// 入力モニタリングの経路
+///
+/// `Direct` は入力デバイスとシンクが同じデバイスのとき、入出力を 1 つの HAL ユニットで
+/// 処理してリングバッファを経由しない（1 バッファ分レイテンシが減る）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonitorMode {
+    /// Through the capture ring buffers and the graph (default)
+    #[default]
+    Buffered,
+    /// Rendered inside the output device's combined I/O callback
+    Direct,
+}
+
+/// ヘッドフォンキュー（試聴）への送り
+///
+/// キューはグラフのエッジではなく、キューシンクへの追加の送り。保存されない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CueTap {
+    #[default]
+    Off,
+    /// Before the edge gain and mute (audition a muted source)
+    PreGain,
+    /// After the edge gain (what the main mix hears)
+    PostGain,
+}
+
+impl CueTap {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "off" => Some(Self::Off),
+            "pre" | "pre_gain" => Some(Self::PreGain),
+            "post" | "post_gain" => Some(Self::PostGain),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::PreGain => "pre_gain",
+            Self::PostGain => "post_gain",
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Self::PreGain,
+            2 => Self::PostGain,
+            _ => Self::Off,
+        }
+    }
+}
+
+/// バスから出るエッジの取り出し位置
+///
+/// `PrePlugin` はバスの入力（プラグインチェーンの前）を送るので、モニター用の送りを
+/// バスのプラグイン処理から独立させられる。バス自体にはフェーダーが無い
+/// （レベルはエッジで決まる）ため、`post_gain` はプラグイン後と同じ位置。
+/// バス以外のソースは常にノードの出力を送る。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TapPoint {
+    /// Bus input, before the plugin chain
+    PrePlugin,
+    /// Bus output, after the plugin chain (default)
+    #[default]
+    PostPlugin,
+}
+
+impl TapPoint {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "pre_plugin" | "pre" => Some(Self::PrePlugin),
+            "post_plugin" | "post_gain" | "post" => Some(Self::PostPlugin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PrePlugin => "pre_plugin",
+            Self::PostPlugin => "post_plugin",
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Self::PrePlugin,
+            _ => Self::PostPlugin,
+        }
+    }
+}
+
+impl MonitorMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "buffered" => Some(Self::Buffered),
+            "direct" => Some(Self::Direct),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Buffered => "buffered",
+            Self::Direct => "direct",
+        }
+    }
+}
+
 /// Edge の一意識別子
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct EdgeId(u32);
@@ -34,10 +285,41 @@ impl From<EdgeId> for u32 {
 ///
 /// ソースノードの出力ポートからターゲットノードの入力ポートへの接続。
 /// すべてのレベル制御はここで行う（Sends-on-Fader の核心）。
+///
+/// `gain`/`muted` は制御スレッドが書く目標値。`current` はオーディオスレッドだけが
+/// 更新する実際の適用ゲインで、ブロックごとに目標値へランプする（ジッパーノイズ防止）。
 #[derive(Debug)]
 pub struct EdgeParams {
     gain_bits: AtomicU32,
     muted: AtomicBool,
+    /// 実際に適用中のゲイン（ミュート込み）。新規エッジは 0 からフェードイン
+    current_bits: AtomicU32,
+    /// ユーザーがソロにしている
+    soloed: AtomicBool,
+    /// ソロ評価によりミュート中（グラフが書き込む）
+    solo_muted: AtomicBool,
+    /// PFL 試聴中: ゲイン/ミュートを無視してユニティで通す
+    solo_unity: AtomicBool,
+    /// バンドルエッジのチャンネルごとのトリム（リニア、デフォルト 1.0）
+    trim_bits: [AtomicU32; MAX_BUNDLE_CHANNELS],
+    /// パン位置 -1.0 (L) ~ 1.0 (R)
+    pan_bits: AtomicU32,
+    /// パンロー（PanLaw as u8）
+    pan_law: AtomicU8,
+    /// サムロー（SumLaw as u8）
+    sum_law: AtomicU8,
+    /// ダイレクトモニター（グラフでは処理せず、出力コールバックで直接ミックス）
+    direct: AtomicBool,
+    /// ダッキングによる係数（リニア、1.0 = 減衰なし。オーディオスレッドが書く）
+    duck_bits: AtomicU32,
+    /// キューシンクへの送り（CueTap as u8）
+    cue: AtomicU8,
+    /// バスから取り出す位置（TapPoint as u8）
+    tap: AtomicU8,
+    /// メニューバーのクイックミュートに表示する（処理には影響しない）
+    pinned: AtomicBool,
+    /// グラフから外された（差し替え前の古いスケジュールに残っていても鳴らさない）
+    detached: AtomicBool,
 }
 
 impl EdgeParams {
@@ -45,7 +327,123 @@ impl EdgeParams {
         Self {
             gain_bits: AtomicU32::new(gain.max(0.0).to_bits()),
             muted: AtomicBool::new(muted),
+            current_bits: AtomicU32::new(0.0f32.to_bits()),
+            soloed: AtomicBool::new(false),
+            solo_muted: AtomicBool::new(false),
+            solo_unity: AtomicBool::new(false),
+            trim_bits: std::array::from_fn(|_| AtomicU32::new(1.0f32.to_bits())),
+            pan_bits: AtomicU32::new(0.0f32.to_bits()),
+            pan_law: AtomicU8::new(PanLaw::ZeroDb.to_u8()),
+            sum_law: AtomicU8::new(SumLaw::default().to_u8()),
+            direct: AtomicBool::new(false),
+            duck_bits: AtomicU32::new(1.0f32.to_bits()),
+            cue: AtomicU8::new(CueTap::Off.to_u8()),
+            tap: AtomicU8::new(TapPoint::PostPlugin.to_u8()),
+            pinned: AtomicBool::new(false),
+            detached: AtomicBool::new(false),
+        }
+    }
+
+    /// 目標ゲイン（ミュート/ソロミュート時は 0、PFL 試聴中は 1、ダッキング中は減衰込み）
+    #[inline(always)]
+    pub fn target(&self) -> f32 {
+        if self.solo_unity.load(Ordering::Relaxed) {
+            1.0
+        } else if self.muted() || self.solo_muted.load(Ordering::Relaxed) {
+            0.0
+        } else {
+            self.gain() * self.duck_gain()
+        }
+    }
+
+    #[inline(always)]
+    pub fn cue(&self) -> CueTap {
+        CueTap::from_u8(self.cue.load(Ordering::Relaxed))
+    }
+
+    #[inline(always)]
+    pub fn set_cue(&self, tap: CueTap) {
+        self.cue.store(tap.to_u8(), Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn tap_point(&self) -> TapPoint {
+        TapPoint::from_u8(self.tap.load(Ordering::Relaxed))
+    }
+
+    #[inline(always)]
+    pub fn set_tap_point(&self, tap: TapPoint) {
+        self.tap.store(tap.to_u8(), Ordering::Relaxed);
+    }
+
+    pub fn pinned(&self) -> bool {
+        self.pinned.load(Ordering::Relaxed)
+    }
+
+    pub fn set_pinned(&self, pinned: bool) {
+        self.pinned.store(pinned, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn detached(&self) -> bool {
+        self.detached.load(Ordering::Relaxed)
+    }
+
+    pub fn detach(&self) {
+        self.detached.store(true, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn duck_gain(&self) -> f32 {
+        f32::from_bits(self.duck_bits.load(Ordering::Relaxed))
+    }
+
+    #[inline(always)]
+    pub fn set_duck_gain(&self, gain: f32) {
+        self.duck_bits
+            .store(gain.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// 現在適用中のゲイン
+    #[inline(always)]
+    pub fn current(&self) -> f32 {
+        f32::from_bits(self.current_bits.load(Ordering::Relaxed))
+    }
+
+    /// 適用中のゲインをランプなしで書き換える
+    pub fn set_current(&self, gain: f32) {
+        self.current_bits.store(gain.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 1ブロック分ランプを進め、(開始ゲイン, 終了ゲイン) を返す（オーディオスレッド専用）
+    ///
+    /// `max_step` は 1 サンプルあたりの最大変化量（∞ なら即時に目標値）。
+    #[inline]
+    pub fn advance(&self, frames: usize, max_step: f32) -> (f32, f32) {
+        let start = self.current();
+        let target = self.target();
+        let delta = target - start;
+        let max_delta = max_step * frames as f32;
+        let end = if delta.abs() <= max_delta {
+            target
+        } else {
+            start + max_delta.copysign(delta)
+        };
+        self.current_bits.store(end.to_bits(), Ordering::Relaxed);
+        (start, end)
+    }
+
+    /// オートメーションの値を 1 ブロック分そのまま適用する（オーディオスレッド専用）
+    ///
+    /// ミュート/ソロ中、または適用中のゲインが `start` から `tolerance` 以上離れている
+    /// ときは false（通常のランプで追いつかせる）。
+    #[inline]
+    pub fn follow_automation(&self, start: f32, end: f32, tolerance: f32) -> bool {
+        if self.target() != self.gain() || (self.current() - start).abs() > tolerance {
+            return false;
         }
+        self.current_bits.store(end.to_bits(), Ordering::Relaxed);
+        true
     }
 
     #[inline(always)]
@@ -68,8 +466,190 @@ impl EdgeParams {
     pub fn set_muted(&self, muted: bool) {
         self.muted.store(muted, Ordering::Relaxed);
     }
+
+    #[inline(always)]
+    pub fn soloed(&self) -> bool {
+        self.soloed.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub fn set_soloed(&self, soloed: bool) {
+        self.soloed.store(soloed, Ordering::Relaxed);
+    }
+
+    /// ソロ評価結果を書き込む（制御スレッド）
+    #[inline(always)]
+    pub fn set_solo_flags(&self, muted: bool, unity: bool) {
+        self.solo_muted.store(muted, Ordering::Relaxed);
+        self.solo_unity.store(unity, Ordering::Relaxed);
+    }
+
+    /// ソロによりミュートされているか
+    #[inline(always)]
+    pub fn solo_muted(&self) -> bool {
+        self.solo_muted.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub fn direct(&self) -> bool {
+        self.direct.load(Ordering::Relaxed)
+    }
+
+    #[inline(always)]
+    pub fn set_direct(&self, direct: bool) {
+        self.direct.store(direct, Ordering::Relaxed);
+    }
+
+    /// チャンネルトリム（範囲外は 1.0）
+    #[inline(always)]
+    pub fn trim(&self, channel: usize) -> f32 {
+        self.trim_bits
+            .get(channel)
+            .map(|t| f32::from_bits(t.load(Ordering::Relaxed)))
+            .unwrap_or(1.0)
+    }
+
+    #[inline(always)]
+    pub fn set_trim(&self, channel: usize, trim: f32) -> bool {
+        match self.trim_bits.get(channel) {
+            Some(t) => {
+                t.store(trim.max(0.0).to_bits(), Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[inline(always)]
+    pub fn pan(&self) -> f32 {
+        f32::from_bits(self.pan_bits.load(Ordering::Relaxed))
+    }
+
+    #[inline(always)]
+    pub fn set_pan(&self, pan: f32) {
+        let pan = if pan.is_finite() {
+            pan.clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        self.pan_bits.store(pan.to_bits(), Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn pan_law(&self) -> PanLaw {
+        PanLaw::from_u8(self.pan_law.load(Ordering::Relaxed))
+    }
+
+    #[inline(always)]
+    pub fn set_pan_law(&self, law: PanLaw) {
+        self.pan_law.store(law.to_u8(), Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    pub fn sum_law(&self) -> SumLaw {
+        SumLaw::from_u8(self.sum_law.load(Ordering::Relaxed))
+    }
+
+    #[inline(always)]
+    pub fn set_sum_law(&self, law: SumLaw) {
+        self.sum_law.store(law.to_u8(), Ordering::Relaxed);
+    }
+}
+
+/// レイテンシ補正用ディレイライン
+///
+/// 並列パス間の位相を揃えるため、遅延の少ないパス側のエッジに挿入する。
+/// リングバッファは制御スレッドで確保し、オーディオスレッドではアロケーションしない。
+#[derive(Debug)]
+pub struct DelayLine {
+    /// リングバッファ（delay + MAX_FRAMES）
+    line: Vec<f32>,
+    /// 書き込み位置
+    write_pos: usize,
+    /// 遅延量（サンプル数）
+    delay: usize,
+    /// 遅延後の出力（1ブロック分）
+    output: Vec<f32>,
 }
 
+impl DelayLine {
+    pub fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            write_pos: 0,
+            delay: 0,
+            output: vec![0.0; MAX_FRAMES],
+        }
+    }
+
+    /// 遅延量（サンプル数）
+    pub fn delay(&self) -> usize {
+        self.delay
+    }
+
+    /// Delay line already allocated for `delay` samples (control thread)
+    pub fn with_delay(delay: usize) -> Self {
+        let mut line = Self::new();
+        line.set_delay(delay);
+        line
+    }
+
+    /// Delay actually applied for a requested delay (capped at [`MAX_COMPENSATION_SAMPLES`])
+    pub fn clamp_delay(delay: usize) -> usize {
+        delay.min(MAX_COMPENSATION_SAMPLES)
+    }
+
+    /// 遅延量を設定（制御スレッドから呼ぶ。変更時はバッファをリセット）
+    pub fn set_delay(&mut self, delay: usize) {
+        let delay = Self::clamp_delay(delay);
+        if delay == self.delay {
+            return;
+        }
+        self.delay = delay;
+        self.write_pos = 0;
+        self.line = if delay > 0 {
+            vec![0.0; delay + MAX_FRAMES]
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// 入力を書き込み、遅延後のサンプルを返す
+    #[inline]
+    pub fn process(&mut self, input: &[f32]) -> &[f32] {
+        let frames = input.len().min(MAX_FRAMES);
+        if self.delay == 0 {
+            self.output[..frames].copy_from_slice(&input[..frames]);
+            return &self.output[..frames];
+        }
+
+        // ブロック単位でコピーする（折り返しで最大 2 回ずつ）。リングは delay + MAX_FRAMES
+        // あるので、先に全部書き込んでも読み出す前の古いサンプルは上書きされない
+        let len = self.line.len();
+        let first = frames.min(len - self.write_pos);
+        self.line[self.write_pos..self.write_pos + first].copy_from_slice(&input[..first]);
+        self.line[..frames - first].copy_from_slice(&input[first..frames]);
+
+        let read_pos = (self.write_pos + len - self.delay) % len;
+        let first = frames.min(len - read_pos);
+        self.output[..first].copy_from_slice(&self.line[read_pos..read_pos + first]);
+        self.output[first..frames].copy_from_slice(&self.line[..frames - first]);
+
+        self.write_pos = (self.write_pos + frames) % len;
+        &self.output[..frames]
+    }
+}
+
+impl Default for DelayLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// エッジ
+///
+/// `channels` > 1 の場合はバンドルエッジ: `source_port..source_port+channels` を
+/// `target_port..target_port+channels` へ 1 本のゲイン/ミュートでまとめて送る。
 #[derive(Debug, Clone)]
 pub struct Edge {
     /// 一意な識別子
@@ -82,8 +662,16 @@ pub struct Edge {
     pub target: NodeHandle,
     /// ターゲットポート（チャンネル）
     pub target_port: PortId,
+    /// チャンネル数（1 = 従来のモノラルエッジ）
+    pub channels: u8,
     /// 送りレベル/ミュート（共有 & Atomic）
     params: Arc<EdgeParams>,
+    /// レイテンシ補正ディレイ（チャンネルごと、クローン間で共有）
+    ///
+    /// 制御スレッドとオーディオスレッドはグラフのロックで排他されるため競合しない。
+    /// 中身は [`AudioGraph::install`](super::AudioGraph::install) が制御スレッドで確保した
+    /// ものと差し替える（チャンネル数の変更もそこで揃う）。
+    delay: Arc<Mutex<Vec<DelayLine>>>,
 }
 
 impl Edge {
@@ -95,16 +683,72 @@ impl Edge {
         target: NodeHandle,
         target_port: PortId,
     ) -> Self {
+        Self::new_bundle(id, source, source_port, target, target_port, 1)
+    }
+
+    /// Create a channel bundle edge carrying `channels` adjacent ports
+    pub fn new_bundle(
+        id: EdgeId,
+        source: NodeHandle,
+        source_port: PortId,
+        target: NodeHandle,
+        target_port: PortId,
+        channels: u8,
+    ) -> Self {
+        let channels = channels.clamp(1, MAX_BUNDLE_CHANNELS as u8);
         Self {
             id,
             source,
             source_port,
             target,
             target_port,
+            channels,
             params: Arc::new(EdgeParams::new(1.0, false)),
+            delay: Arc::new(Mutex::new(
+                (0..channels).map(|_| DelayLine::new()).collect(),
+            )),
         }
     }
 
+    /// Change the bundle width (the graph moves ports of resized nodes)
+    ///
+    /// 補正ディレイのライン数は次の再コンパイルで揃う（それまで足りないチャンネルは遅延なし）。
+    pub(crate) fn set_channels(&mut self, channels: u8) {
+        self.channels = channels.clamp(1, MAX_BUNDLE_CHANNELS as u8);
+    }
+
+    /// バンドル内チャンネルのソースポート
+    #[inline(always)]
+    pub fn source_port_for(&self, channel: usize) -> PortId {
+        PortId::new(self.source_port.index().saturating_add(channel).min(255) as u8)
+    }
+
+    /// バンドル内チャンネルのターゲットポート
+    #[inline(always)]
+    pub fn target_port_for(&self, channel: usize) -> PortId {
+        PortId::new(self.target_port.index().saturating_add(channel).min(255) as u8)
+    }
+
+    /// 同じポート対（source_port+i → target_port+i）を運ぶか
+    ///
+    /// 同じソース/ターゲット間で、ポートのオフセットが一致しソース範囲が重なる場合に true。
+    pub fn overlaps(
+        &self,
+        source: NodeHandle,
+        source_port: PortId,
+        target: NodeHandle,
+        target_port: PortId,
+        channels: u8,
+    ) -> bool {
+        let offset = |s: PortId, t: PortId| t.index() as isize - s.index() as isize;
+        let (a, b) = (self.source_port.index(), source_port.index());
+        self.source == source
+            && self.target == target
+            && offset(self.source_port, self.target_port) == offset(source_port, target_port)
+            && a < b + channels as usize
+            && b < a + self.channels as usize
+    }
+
     /// 送りレベル（リニアゲイン 0.0 ~ 2.0+）
     #[inline(always)]
     pub fn gain(&self) -> f32 {
@@ -117,9 +761,116 @@ impl Edge {
         self.params.muted()
     }
 
-    /// このエッジが有効か（ミュートされておらず、ゲインがある）
+    /// このエッジが有効か（ミュート/ソロミュートされておらず、ゲインがある）
     pub fn is_active(&self) -> bool {
-        !self.muted() && self.gain() > 0.0001
+        self.params.target() > SILENT_GAIN
+    }
+
+    /// 処理が必要か（有効、またはフェードアウト中。グラフから外されていれば false）
+    #[inline]
+    pub fn is_audible(&self) -> bool {
+        !self.params.detached() && (self.is_active() || self.params.current() > SILENT_GAIN)
+    }
+
+    /// Whether the edge was removed from the graph (only an old schedule still holds it)
+    #[inline(always)]
+    pub fn is_detached(&self) -> bool {
+        self.params.detached()
+    }
+
+    /// Mark the edge removed so a schedule that still holds it skips it
+    pub(crate) fn detach(&self) {
+        self.params.detach();
+    }
+
+    /// ダイレクトモニター経路か（グラフの処理対象外）
+    #[inline(always)]
+    pub fn is_direct(&self) -> bool {
+        self.params.direct()
+    }
+
+    /// Monitoring path of this edge
+    pub fn monitor_mode(&self) -> MonitorMode {
+        if self.is_direct() {
+            MonitorMode::Direct
+        } else {
+            MonitorMode::Buffered
+        }
+    }
+
+    /// Set the monitoring path (validate with `AudioGraph::direct_monitor_device` first)
+    pub(crate) fn set_monitor_mode(&self, mode: MonitorMode) {
+        self.params.set_direct(mode == MonitorMode::Direct);
+    }
+
+    /// Cue send of this edge
+    pub fn cue(&self) -> CueTap {
+        self.params.cue()
+    }
+
+    /// Set the cue send (takes effect once a cue sink is set on the graph)
+    pub fn set_cue(&self, tap: CueTap) {
+        self.params.set_cue(tap);
+    }
+
+    /// Where this edge taps a bus source
+    #[inline(always)]
+    pub fn tap_point(&self) -> TapPoint {
+        self.params.tap_point()
+    }
+
+    /// Set the bus tap point (use `AudioGraph::set_edge_tap_point`, which also
+    /// updates latency compensation)
+    pub(crate) fn set_tap_point(&self, tap: TapPoint) {
+        self.params.set_tap_point(tap);
+    }
+
+    /// Whether the edge has a mute toggle in the menu bar extra
+    pub fn pinned(&self) -> bool {
+        self.params.pinned()
+    }
+
+    /// Show (or hide) the edge's mute toggle in the menu bar extra
+    pub fn set_pinned(&self, pinned: bool) {
+        self.params.set_pinned(pinned);
+    }
+
+    /// 現在適用中のゲイン（ランプ途中の値、ミュート込み）
+    #[inline]
+    pub fn applied_gain(&self) -> f32 {
+        self.params.current()
+    }
+
+    /// ランプの行き先のゲイン（ミュート/ソロ/ダッキング込み）
+    pub(crate) fn target_gain(&self) -> f32 {
+        self.params.target()
+    }
+
+    /// Jump the applied gain without a ramp (offline render set-aside and restore)
+    pub(crate) fn set_applied_gain(&self, gain: f32) {
+        self.params.set_current(gain);
+    }
+
+    /// ゲインランプを 1 ブロック進める（オーディオスレッド専用）
+    #[inline]
+    pub fn advance_gain(&self, frames: usize, max_step: f32) -> (f32, f32) {
+        self.params.advance(frames, max_step)
+    }
+
+    /// Apply an automation block instead of the gain ramp (see `EdgeParams::follow_automation`)
+    #[inline]
+    pub fn follow_automation(&self, start: f32, end: f32, tolerance: f32) -> bool {
+        self.params.follow_automation(start, end, tolerance)
+    }
+
+    /// Ducking factor (1.0 = not ducked)
+    pub fn duck_gain(&self) -> f32 {
+        self.params.duck_gain()
+    }
+
+    /// Set the ducking factor (written by the ducking processor)
+    pub fn set_duck_gain(&self, gain: f32) {
+        self.params.set_duck_gain(gain);
     }
 
     /// Set gain (clamped to reasonable range)
@@ -131,4 +882,316 @@ impl Edge {
     pub fn set_muted(&self, muted: bool) {
         self.params.set_muted(muted);
     }
+
+    /// ソロ
+    #[inline(always)]
+    pub fn soloed(&self) -> bool {
+        self.params.soloed()
+    }
+
+    /// Set solo state (takes effect after the graph re-evaluates solo)
+    pub fn set_soloed(&self, soloed: bool) {
+        self.params.set_soloed(soloed);
+    }
+
+    /// ソロによりミュートされているか
+    pub fn solo_muted(&self) -> bool {
+        self.params.solo_muted()
+    }
+
+    /// Apply the solo evaluation result
+    pub(crate) fn set_solo_flags(&self, muted: bool, unity: bool) {
+        self.params.set_solo_flags(muted, unity);
+    }
+
+    /// チャンネルトリム（リニア）
+    #[inline(always)]
+    pub fn channel_trim(&self, channel: usize) -> f32 {
+        self.params.trim(channel)
+    }
+
+    /// Set per-channel trim (false if the channel is outside the bundle)
+    pub fn set_channel_trim(&self, channel: usize, trim: f32) -> bool {
+        channel < self.channels as usize && self.params.set_trim(channel, trim)
+    }
+
+    /// バンドル全チャンネルのトリム
+    pub fn channel_trims(&self) -> Vec<f32> {
+        (0..self.channels as usize)
+            .map(|ch| self.channel_trim(ch))
+            .collect()
+    }
+
+    /// パン位置 -1.0 (L) ~ 1.0 (R)
+    #[inline(always)]
+    pub fn pan(&self) -> f32 {
+        self.params.pan()
+    }
+
+    /// Set pan position (clamped to -1.0 ~ 1.0)
+    pub fn set_pan(&self, pan: f32) {
+        self.params.set_pan(pan);
+    }
+
+    /// パンロー
+    #[inline(always)]
+    pub fn pan_law(&self) -> PanLaw {
+        self.params.pan_law()
+    }
+
+    /// Set pan law
+    pub fn set_pan_law(&self, law: PanLaw) {
+        self.params.set_pan_law(law);
+    }
+
+    /// サムロー
+    #[inline(always)]
+    pub fn sum_law(&self) -> SumLaw {
+        self.params.sum_law()
+    }
+
+    /// Set sum law
+    pub fn set_sum_law(&self, law: SumLaw) {
+        self.params.set_sum_law(law);
+    }
+
+    /// バンドル内チャンネルの行き先と加算ゲイン（ターゲットの入力が `target_ports` 本のとき）
+    ///
+    /// バンドルがターゲットの残りポートより広ければ、チャンネルを `ch % 残りポート数` へ
+    /// 畳み込み、全チャンネルにサムローを掛ける（ステレオ → モノラルで片側を失わない）。
+    /// ターゲットポート自体が範囲外なら None。
+    #[inline]
+    pub fn fold_target(&self, channel: usize, target_ports: usize) -> Option<(PortId, f32)> {
+        let first = self.target_port.index();
+        let remaining = target_ports.saturating_sub(first);
+        if remaining == 0 {
+            return None;
+        }
+        if self.channels as usize <= remaining {
+            return Some((self.target_port_for(channel), 1.0));
+        }
+        let port = PortId::new((first + channel % remaining) as u8);
+        Some((port, self.sum_law().gain()))
+    }
+
+    /// チャンネルごとの固定ゲイン（トリム × パン）
+    ///
+    /// パンはターゲットポートの偶奇で L/R を判定する（偶数 = L, 奇数 = R）。
+    #[inline]
+    pub fn channel_gain(&self, channel: usize) -> f32 {
+        let (left, right) = self.pan_law().gains(self.pan());
+        let side = if self.target_port_for(channel).index().is_multiple_of(2) {
+            left
+        } else {
+            right
+        };
+        self.channel_trim(channel) * side
+    }
+
+    /// レイテンシ補正量（サンプル数）
+    pub fn compensation_samples(&self) -> usize {
+        self.delay.lock().first().map(|d| d.delay()).unwrap_or(0)
+    }
+
+    /// (Compensation delay, delay line count) as last installed
+    pub(crate) fn delay_state(&self) -> (usize, usize) {
+        let delays = self.delay.lock();
+        (delays.first().map_or(0, |d| d.delay()), delays.len())
+    }
+
+    /// Swap in delay lines prepared by the compile step (the old ones end up in `lines`)
+    pub(crate) fn swap_delay_lines(&self, lines: &mut Vec<DelayLine>) {
+        std::mem::swap(&mut *self.delay.lock(), lines);
+    }
+
+    /// バンドル内 1 チャンネル分をゲインランプ付きでターゲットへミックス（補正ディレイを通す）
+    ///
+    /// `start`/`end` にはトリム/パンを掛けない値を渡す。
+    #[inline]
+    pub fn mix_into(
+        &self,
+        channel: usize,
+        source: &AudioBuffer,
+        target: &mut AudioBuffer,
+        start: f32,
+        end: f32,
+    ) {
+        let gain = self.channel_gain(channel);
+        let (start, end) = (start * gain, end * gain);
+        // グラフのロック下で呼ばれるため try_lock は常に成功する想定
+        match self.delay.try_lock() {
+            Some(mut delays) => match delays.get_mut(channel) {
+                Some(delay) if delay.delay() > 0 => {
+                    let delayed = delay.process(source.samples());
+                    target.mix_from_slice_ramp(delayed, start, end);
+                }
+                _ => target.mix_from_slice_ramp(source.samples(), start, end),
+            },
+            None => target.mix_from_slice_ramp(source.samples(), start, end),
+        }
+    }
+
+    /// `mix_into` with several gain ramps over one block (automation)
+    ///
+    /// `scale` multiplies every segment (the sum law of a folded channel).
+    #[inline]
+    pub fn mix_segments_into(
+        &self,
+        channel: usize,
+        source: &AudioBuffer,
+        target: &mut AudioBuffer,
+        segments: &[GainSegment],
+        scale: f32,
+    ) {
+        let gain = self.channel_gain(channel) * scale;
+        let mix = |samples: &[f32], target: &mut AudioBuffer| {
+            for seg in segments {
+                target.mix_from_slice_ramp_at(
+                    seg.offset,
+                    &samples[seg.offset.min(samples.len())..],
+                    seg.len,
+                    seg.start * gain,
+                    seg.end * gain,
+                );
+            }
+        };
+        match self.delay.try_lock() {
+            Some(mut delays) => match delays.get_mut(channel) {
+                Some(delay) if delay.delay() > 0 => mix(delay.process(source.samples()), target),
+                _ => mix(source.samples(), target),
+            },
+            None => mix(source.samples(), target),
+        }
+    }
+}
+
+/// ランプ時間から 1 サンプルあたりの最大ゲイン変化量を求める
+///
+/// フルスケール（0 → 1）の変化に `ramp_ms` かかる速度。0 なら即時。
+#[inline]
+pub fn gain_ramp_step(ramp_ms: f32, sample_rate: f64) -> f32 {
+    let samples = ramp_ms * sample_rate as f32 / 1000.0;
+    if samples < 1.0 {
+        f32::INFINITY
+    } else {
+        1.0 / samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_line_shifts_samples() {
+        let mut delay = DelayLine::new();
+        delay.set_delay(3);
+
+        let input: Vec<f32> = (1..=8).map(|v| v as f32).collect();
+        let out = delay.process(&input).to_vec();
+        assert_eq!(out, vec![0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        // 次のブロックに持ち越される
+        let out = delay.process(&[9.0, 10.0]).to_vec();
+        assert_eq!(out, vec![6.0, 7.0]);
+    }
+
+    #[test]
+    fn test_gain_ramp_reaches_target() {
+        let params = EdgeParams::new(1.0, false);
+        // 10 samples for a full-scale change
+        let step = 0.1;
+
+        let (start, end) = params.advance(4, step);
+        assert_eq!(start, 0.0);
+        assert!((end - 0.4).abs() < 1e-6);
+
+        let (_, end) = params.advance(8, step);
+        assert_eq!(end, 1.0);
+
+        // Mute ramps back down instead of cutting
+        params.set_muted(true);
+        let (start, end) = params.advance(5, step);
+        assert_eq!(start, 1.0);
+        assert!((end - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pan_law_center_attenuation() {
+        let db = |g: f32| 20.0 * g.log10();
+
+        let (l, r) = PanLaw::ZeroDb.gains(0.0);
+        assert_eq!((l, r), (1.0, 1.0));
+        assert!((db(PanLaw::Minus3Db.gains(0.0).0) + 3.01).abs() < 0.01);
+        assert!((db(PanLaw::Minus4_5Db.gains(0.0).0) + 4.52).abs() < 0.01);
+        assert!((db(PanLaw::Minus6Db.gains(0.0).1) + 6.02).abs() < 0.01);
+
+        // Hard left is unity on L and silent on R for every law
+        for law in [PanLaw::Minus3Db, PanLaw::Minus4_5Db, PanLaw::Minus6Db] {
+            let (l, r) = law.gains(-1.0);
+            assert!((l - 1.0).abs() < 1e-6 && r.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_stereo_bundle_folds_into_mono_target() {
+        let src = NodeHandle::from_raw(1);
+        let dst = NodeHandle::from_raw(2);
+        let edge = Edge::new_bundle(EdgeId::new(1), src, PortId::new(0), dst, PortId::new(0), 2);
+
+        // 幅が足りていれば畳み込まない
+        assert_eq!(edge.fold_target(1, 2), Some((PortId::new(1), 1.0)));
+
+        let (port, gain) = edge.fold_target(1, 1).unwrap();
+        assert_eq!(port, PortId::new(0));
+        assert!((gain - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        edge.set_sum_law(SumLaw::Minus6Db);
+        assert_eq!(edge.fold_target(0, 1), Some((PortId::new(0), 0.5)));
+        assert_eq!(edge.fold_target(0, 0), None);
+
+        for law in [SumLaw::ZeroDb, SumLaw::Minus3Db, SumLaw::Minus6Db] {
+            assert_eq!(SumLaw::parse(law.as_str()), Some(law));
+        }
+    }
+
+    #[test]
+    fn test_delay_line_wraps_across_blocks() {
+        let mut delay = DelayLine::new();
+        delay.set_delay(100);
+
+        // リングを何周もする長さを、長さの違うブロックで流す
+        let input: Vec<f32> = (1..=(MAX_FRAMES * 5) as u32).map(|v| v as f32).collect();
+        let mut output = Vec::new();
+        for block in input.chunks(MAX_FRAMES - 7).chain(input.chunks(33)) {
+            output.extend_from_slice(delay.process(block));
+        }
+        let fed: Vec<f32> = input.iter().chain(&input).copied().collect();
+        assert_eq!(output.len(), fed.len());
+        for (i, &sample) in output.iter().enumerate() {
+            let expected = if i < 100 { 0.0 } else { fed[i - 100] };
+            assert_eq!(sample, expected, "sample {}", i);
+        }
+    }
+
+    #[test]
+    fn test_delay_line_zero_is_passthrough() {
+        let mut delay = DelayLine::new();
+        let out = delay.process(&[0.5, -0.5]).to_vec();
+        assert_eq!(out, vec![0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_tap_point_round_trip() {
+        let params = EdgeParams::new(1.0, false);
+        assert_eq!(params.tap_point(), TapPoint::PostPlugin);
+        params.set_tap_point(TapPoint::PrePlugin);
+        assert_eq!(params.tap_point(), TapPoint::PrePlugin);
+
+        for tap in [TapPoint::PrePlugin, TapPoint::PostPlugin] {
+            assert_eq!(TapPoint::parse(tap.as_str()), Some(tap));
+        }
+        // A bus has no fader of its own, so post-gain is the post-plugin point
+        assert_eq!(TapPoint::parse("post_gain"), Some(TapPoint::PostPlugin));
+        assert_eq!(TapPoint::parse("bogus"), None);
+    }
 }
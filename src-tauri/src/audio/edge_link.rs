@@ -0,0 +1,237 @@
+//! Edge link groups - faders that move together (stereo link)
+//!
+//! グループ内のどのエッジのゲイン/ミュートを変えても、他のメンバーに同じ変更を反映する。
+//! UI だけでなく HTTP・ホットキー・URL スキーム・トレイのどの経路でも効くように、
+//! 展開は API 側（`set_edge_params` など）で行う。
+//!
+//! `preserve_offsets` のグループはリンクした時点のゲイン差（dB）を保ったまま動き、
+//! そうでなければ全メンバーが同じゲインになる。ミュートは常に全メンバーで揃える。
+//! 1 つのエッジは 1 つのグループにしか入らない（リンクし直すと前のグループから抜ける）。
+
+use super::edge::EdgeId;
+use super::taper::{db_to_linear, linear_to_db, MAX_DB};
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use std::sync::{Arc, LazyLock};
+
+/// Lowest offset kept for a member (a silent member would otherwise pull the others to +max)
+const OFFSET_FLOOR_DB: f32 = -60.0;
+
+/// Edges whose gain and mute move together
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeLinkGroup {
+    pub id: u32,
+    /// Members with their offset from the loudest member at link time (dB, <= 0)
+    pub members: Vec<(EdgeId, f32)>,
+    pub preserve_offsets: bool,
+}
+
+impl EdgeLinkGroup {
+    fn new(id: u32, gains: &[(EdgeId, f32)], preserve_offsets: bool) -> Self {
+        let db = |gain: f32| linear_to_db(gain).unwrap_or(OFFSET_FLOOR_DB);
+        let loudest = gains
+            .iter()
+            .map(|&(_, g)| db(g))
+            .fold(OFFSET_FLOOR_DB, f32::max);
+        let members = gains
+            .iter()
+            .map(|&(edge, gain)| {
+                let offset = if preserve_offsets {
+                    (db(gain) - loudest).max(OFFSET_FLOOR_DB)
+                } else {
+                    0.0
+                };
+                (edge, offset)
+            })
+            .collect();
+        Self {
+            id,
+            members,
+            preserve_offsets,
+        }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = EdgeId> + '_ {
+        self.members.iter().map(|&(edge, _)| edge)
+    }
+
+    pub fn contains(&self, edge: EdgeId) -> bool {
+        self.members.iter().any(|&(e, _)| e == edge)
+    }
+
+    /// Gains of the other members after `edge` was set to `gain` (linear)
+    pub fn follow_gain(&self, edge: EdgeId, gain: f32) -> Vec<(EdgeId, f32)> {
+        let Some(&(_, offset)) = self.members.iter().find(|&&(e, _)| e == edge) else {
+            return Vec::new();
+        };
+        if !self.preserve_offsets {
+            return self.others(edge).into_iter().map(|e| (e, gain)).collect();
+        }
+        // 0 (-inf) は全員 0、それ以外は基準（一番大きいメンバー）の dB を求めて各オフセットを足す
+        let reference = linear_to_db(gain).map(|db| (db - offset).min(MAX_DB));
+        self.members
+            .iter()
+            .filter(|&&(e, _)| e != edge)
+            .map(|&(e, member_offset)| {
+                let gain = match reference {
+                    Some(reference) => db_to_linear(Some(reference + member_offset)),
+                    None => 0.0,
+                };
+                (e, gain)
+            })
+            .collect()
+    }
+
+    /// Other members (for mute, which is always absolute)
+    pub fn others(&self, edge: EdgeId) -> Vec<EdgeId> {
+        self.edges().filter(|&e| e != edge).collect()
+    }
+}
+
+static GROUPS: LazyLock<ArcSwap<Vec<Arc<EdgeLinkGroup>>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+/// Serializes group edits and id allocation
+static EDIT_LOCK: Mutex<u32> = Mutex::new(1);
+
+/// Current link groups
+pub fn groups() -> Vec<Arc<EdgeLinkGroup>> {
+    GROUPS.load().as_ref().clone()
+}
+
+/// Group that `edge` belongs to
+pub fn group_of(edge: EdgeId) -> Option<Arc<EdgeLinkGroup>> {
+    GROUPS.load().iter().find(|g| g.contains(edge)).cloned()
+}
+
+/// Link edges (`gains` = current gain of each member); returns the new group
+///
+/// Members leave their previous groups; groups left with fewer than two edges are
+/// dropped. None if fewer than two distinct edges are given.
+pub fn link(gains: &[(EdgeId, f32)], preserve_offsets: bool) -> Option<Arc<EdgeLinkGroup>> {
+    let mut members: Vec<(EdgeId, f32)> = Vec::with_capacity(gains.len());
+    for &(edge, gain) in gains {
+        if !members.iter().any(|&(e, _)| e == edge) {
+            members.push((edge, gain));
+        }
+    }
+    if members.len() < 2 {
+        return None;
+    }
+
+    let mut next_id = EDIT_LOCK.lock();
+    let id = *next_id;
+    *next_id += 1;
+    let group = Arc::new(EdgeLinkGroup::new(id, &members, preserve_offsets));
+    let mut list: Vec<Arc<EdgeLinkGroup>> = groups()
+        .into_iter()
+        .filter_map(|g| without(&g, |e| group.contains(e)))
+        .collect();
+    list.push(group.clone());
+    GROUPS.store(Arc::new(list));
+    Some(group)
+}
+
+/// Remove a group (its edges move independently again)
+pub fn unlink(id: u32) -> bool {
+    let _guard = EDIT_LOCK.lock();
+    let mut list = groups();
+    let Some(pos) = list.iter().position(|g| g.id == id) else {
+        return false;
+    };
+    list.remove(pos);
+    GROUPS.store(Arc::new(list));
+    true
+}
+
+/// Drop members that no longer exist (removed edges)
+pub fn retain_edges(exists: impl Fn(EdgeId) -> bool) {
+    let _guard = EDIT_LOCK.lock();
+    let list = groups();
+    if list.iter().all(|g| g.edges().all(&exists)) {
+        return;
+    }
+    let list = list
+        .into_iter()
+        .filter_map(|g| without(&g, |e| !exists(e)))
+        .collect();
+    GROUPS.store(Arc::new(list));
+}
+
+/// `group` minus the matching members (None if fewer than two remain)
+fn without(
+    group: &Arc<EdgeLinkGroup>,
+    remove: impl Fn(EdgeId) -> bool,
+) -> Option<Arc<EdgeLinkGroup>> {
+    if !group.edges().any(&remove) {
+        return Some(group.clone());
+    }
+    let members: Vec<(EdgeId, f32)> = group
+        .members
+        .iter()
+        .copied()
+        .filter(|&(e, _)| !remove(e))
+        .collect();
+    (members.len() >= 2).then(|| {
+        Arc::new(EdgeLinkGroup {
+            id: group.id,
+            members,
+            preserve_offsets: group.preserve_offsets,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn e(id: u32) -> EdgeId {
+        EdgeId::from(id)
+    }
+
+    fn db(gain: f32) -> f32 {
+        linear_to_db(gain).unwrap()
+    }
+
+    #[test]
+    fn test_follow_gain_with_and_without_offsets() {
+        let half = db_to_linear(Some(-6.0));
+        let gains = [(e(1), 1.0), (e(2), half)];
+
+        let absolute = EdgeLinkGroup::new(1, &gains, false);
+        assert_eq!(absolute.follow_gain(e(1), 0.5), vec![(e(2), 0.5)]);
+
+        let relative = EdgeLinkGroup::new(2, &gains, true);
+        // Moving the louder fader to -10 dB puts the other at -16 dB
+        let follow = relative.follow_gain(e(1), db_to_linear(Some(-10.0)));
+        assert!((db(follow[0].1) + 16.0).abs() < 1e-3);
+        // And the other way round
+        let follow = relative.follow_gain(e(2), db_to_linear(Some(-20.0)));
+        assert!((db(follow[0].1) + 14.0).abs() < 1e-3);
+        // Silence is shared, and the top is capped
+        assert_eq!(relative.follow_gain(e(1), 0.0), vec![(e(2), 0.0)]);
+        let follow = relative.follow_gain(e(2), db_to_linear(Some(MAX_DB)));
+        assert!(db(follow[0].1) <= MAX_DB + 1e-3);
+        // Non-members do nothing
+        assert!(relative.follow_gain(e(9), 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_relinking_moves_edges_between_groups() {
+        let first = link(&[(e(101), 1.0), (e(102), 1.0), (e(103), 1.0)], false).unwrap();
+        let second = link(&[(e(103), 1.0), (e(104), 1.0)], false).unwrap();
+        assert_eq!(group_of(e(103)).unwrap().id, second.id);
+        assert_eq!(group_of(e(101)).unwrap().others(e(101)), vec![e(102)]);
+
+        // A group left with one edge disappears
+        link(&[(e(102), 1.0), (e(105), 1.0)], false).unwrap();
+        assert!(group_of(e(101)).is_none());
+        assert!(!groups().iter().any(|g| g.id == first.id));
+
+        retain_edges(|edge| edge != e(104));
+        assert!(group_of(e(103)).is_none());
+        assert!(unlink(group_of(e(105)).unwrap().id));
+        assert!(group_of(e(102)).is_none());
+        assert!(link(&[(e(106), 1.0), (e(106), 0.5)], true).is_none());
+    }
+}
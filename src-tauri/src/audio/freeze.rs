@@ -0,0 +1,172 @@
+//! Bus Freeze - Play a cached render instead of running the plugin chain
+//!
+//! 重いプラグインチェーンを持つバスの出力を一度オフラインでレンダリングしてメモリに
+//! 保持し、凍結中はプラグインを呼ばずにトランスポート位置のキャッシュを出力する。
+//! 入力はバウンスと同じく音声ファイルをソースノードに割り当てて流す（入力がなければ
+//! ジェネレーター/プラグインソースの出力を `tail_seconds` 分だけ録る）。
+//!
+//! キャッシュはバスの出力そのもの（経路レイテンシを含む）なので、凍結中もバスは同じ
+//! レイテンシを報告し、下流の遅延補償は変わらない。
+
+use super::bounce::{
+    BounceInput, OfflineGuard, OfflineInputs, BOUNCE_BLOCK_FRAMES, MAX_TAIL_SECONDS,
+    PROGRESS_INTERVAL_BLOCKS,
+};
+use super::bus::BusNode;
+use super::node::{AudioNode, NodeHandle, PortId};
+use super::processor::GraphProcessor;
+
+/// 凍結できる最大長（秒）。キャッシュはメモリに置くので上限を設ける
+pub const MAX_FREEZE_SECONDS: f64 = 20.0 * 60.0;
+
+/// レンダリング済みのバス出力（ポートごと）
+#[derive(Debug, Clone)]
+pub struct FrozenAudio {
+    ports: Vec<Vec<f32>>,
+    sample_rate: f64,
+}
+
+impl FrozenAudio {
+    pub fn new(ports: Vec<Vec<f32>>, sample_rate: f64) -> Self {
+        Self { ports, sample_rate }
+    }
+
+    /// Length in frames (the longest port)
+    pub fn frames(&self) -> usize {
+        self.ports.iter().map(|p| p.len()).max().unwrap_or(0)
+    }
+
+    pub fn port_count(&self) -> usize {
+        self.ports.len()
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    pub fn duration_ms(&self) -> f64 {
+        self.frames() as f64 * 1000.0 / self.sample_rate
+    }
+
+    /// Copy `out.len()` frames of `port` from `start` (silence past the end or for missing ports)
+    pub fn read(&self, port: usize, start: usize, out: &mut [f32]) {
+        let data = self.ports.get(port).map(Vec::as_slice).unwrap_or(&[]);
+        let available = data.len().saturating_sub(start).min(out.len());
+        if available > 0 {
+            out[..available].copy_from_slice(&data[start..start + available]);
+        }
+        out[available..].fill(0.0);
+    }
+}
+
+/// `bus` の現在の出力をオフラインでレンダリングする
+///
+/// `inputs` の最長のファイル + `tail_seconds` を処理する。`progress` には
+/// (レンダリング済みフレーム数, 総フレーム数) が渡される。キャンセルされたらエラー。
+pub fn render_bus(
+    processor: &GraphProcessor,
+    bus: NodeHandle,
+    inputs: &[BounceInput],
+    tail_seconds: f32,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<FrozenAudio, String> {
+    let sample_rate = super::engine_sample_rate();
+
+    let (ports, latency) = processor.with_graph(|graph| {
+        let node = graph
+            .get_node(bus)
+            .and_then(|n| n.as_any().downcast_ref::<BusNode>())
+            .ok_or_else(|| format!("Node {} is not a bus", bus.raw()))?;
+        if node.is_frozen() {
+            return Err(format!("Bus {} is already frozen", bus.raw()));
+        }
+        let latency = graph
+            .path_latency_to(bus)
+            .saturating_add(node.latency_samples());
+        Ok((node.output_port_count(), latency))
+    })?;
+    let mut files = OfflineInputs::open(processor, inputs, sample_rate)?;
+
+    let tail = tail_seconds.clamp(0.0, MAX_TAIL_SECONDS) as f64;
+    let content = files.frames() + (tail * sample_rate) as u64;
+    if content == 0 {
+        return Err("Nothing to freeze: pass input files or a tail length".to_string());
+    }
+    let total = content + latency as u64;
+    if total as f64 > MAX_FREEZE_SECONDS * sample_rate {
+        return Err(format!(
+            "Freeze is limited to {} seconds",
+            MAX_FREEZE_SECONDS as u32
+        ));
+    }
+
+    let render = OfflineGuard::begin(processor)?;
+
+    log_info!(
+        "[Freeze] Rendering bus {} ({} ports, {} frames incl. {} latency)",
+        bus.raw(),
+        ports,
+        total,
+        latency
+    );
+
+    let mut cache: Vec<Vec<f32>> = (0..ports)
+        .map(|_| Vec::with_capacity(total as usize))
+        .collect();
+    let mut rendered: u64 = 0;
+    let mut blocks = 0usize;
+
+    while rendered < total {
+        if render.cancelled() {
+            log_info!("[Freeze] Cancelled bus {}", bus.raw());
+            return Err("Freeze cancelled".to_string());
+        }
+        let frames = ((total - rendered) as usize).min(BOUNCE_BLOCK_FRAMES);
+        files.read_block(frames)?;
+        render.render_block(&files, rendered, frames, |graph| {
+            let node = graph
+                .get_node(bus)
+                .ok_or_else(|| format!("Bus {} was removed", bus.raw()))?;
+            for (port, data) in cache.iter_mut().enumerate() {
+                match node.output_buffer(PortId::new(port as u8)) {
+                    Some(buf) => data.extend_from_slice(&buf.samples()[..frames]),
+                    None => data.resize(data.len() + frames, 0.0),
+                }
+            }
+            Ok::<_, String>(())
+        })?;
+        rendered += frames as u64;
+
+        blocks += 1;
+        if blocks.is_multiple_of(PROGRESS_INTERVAL_BLOCKS) {
+            progress(rendered, total);
+        }
+    }
+    progress(rendered, total);
+
+    log_info!("[Freeze] Finished bus {} ({} frames)", bus.raw(), rendered);
+    Ok(FrozenAudio::new(cache, sample_rate))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_pads_with_silence() {
+        let audio = FrozenAudio::new(vec![vec![1.0, 2.0, 3.0]], 48000.0);
+        assert_eq!(audio.frames(), 3);
+
+        let mut out = [9.0f32; 4];
+        audio.read(0, 1, &mut out);
+        assert_eq!(out, [2.0, 3.0, 0.0, 0.0]);
+
+        audio.read(0, 10, &mut out);
+        assert_eq!(out, [0.0; 4]);
+
+        // ないポートは無音
+        let mut out = [9.0f32; 2];
+        audio.read(1, 0, &mut out);
+        assert_eq!(out, [0.0; 2]);
+    }
+}
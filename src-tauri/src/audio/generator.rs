@@ -0,0 +1,430 @@
+//! Generator Node - Built-in test signals (sine, pink/white noise, sweep)
+//!
+//! 外部信号なしでルーティング確認や出力レベルの校正ができるよう、
+//! グラフ内で信号を生成するソースノード。
+//!
+//! パラメータは Atomic で保持し、制御スレッドからロックなしで変更できる。
+//! 全ポートに同じ信号を出力する。
+
+use super::buffer::AudioBuffer;
+use super::node::{AudioNode, NodeType, PortId};
+use std::any::Any;
+use std::f64::consts::TAU;
+use std::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+
+/// 周波数の範囲 (Hz)
+const MIN_FREQUENCY: f32 = 1.0;
+const MAX_FREQUENCY: f32 = 24_000.0;
+
+/// 生成する波形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    PinkNoise,
+    WhiteNoise,
+    /// Logarithmic sweep from `frequency` to `sweep_end`, repeating
+    Sweep,
+}
+
+impl Waveform {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "sine" => Some(Self::Sine),
+            "pink" | "pink_noise" => Some(Self::PinkNoise),
+            "white" | "white_noise" => Some(Self::WhiteNoise),
+            "sweep" => Some(Self::Sweep),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sine => "sine",
+            Self::PinkNoise => "pink",
+            Self::WhiteNoise => "white",
+            Self::Sweep => "sweep",
+        }
+    }
+
+    fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Self::PinkNoise,
+            2 => Self::WhiteNoise,
+            3 => Self::Sweep,
+            _ => Self::Sine,
+        }
+    }
+}
+
+/// [`GeneratorNode`] のパラメータ（読み出し用スナップショット）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeneratorParams {
+    pub waveform: Waveform,
+    /// Sine frequency / sweep start (Hz)
+    pub frequency: f32,
+    /// Output level (linear)
+    pub level: f32,
+    /// Sweep end frequency (Hz)
+    pub sweep_end: f32,
+    /// Sweep duration (seconds)
+    pub sweep_seconds: f32,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Sine,
+            frequency: 1000.0,
+            // -20 dBFS（校正用の一般的なリファレンスレベル）
+            level: 0.1,
+            sweep_end: 20_000.0,
+            sweep_seconds: 10.0,
+        }
+    }
+}
+
+/// テスト信号ジェネレーターノード
+pub struct GeneratorNode {
+    /// ジェネレーターの識別子
+    generator_id: String,
+    /// 表示ラベル
+    label: String,
+    /// 出力バッファ（全ポート同じ信号）
+    output_buffers: Vec<AudioBuffer>,
+    waveform: AtomicU8,
+    frequency_bits: AtomicU32,
+    level_bits: AtomicU32,
+    sweep_end_bits: AtomicU32,
+    sweep_seconds_bits: AtomicU32,
+    /// 生成器の内部状態（オーディオスレッドのみ）
+    osc: Oscillator,
+}
+
+/// オシレーター/ノイズの内部状態
+struct Oscillator {
+    /// 位相 (0.0 ~ 1.0)
+    phase: f64,
+    /// スイープ内の経過時間（秒）
+    sweep_time: f64,
+    /// xorshift32 の状態
+    rng: u32,
+    /// Paul Kellet のピンクノイズフィルター状態
+    pink: [f32; 7],
+}
+
+impl GeneratorNode {
+    /// Create a new generator
+    pub fn new(
+        generator_id: impl Into<String>,
+        label: impl Into<String>,
+        port_count: usize,
+        params: GeneratorParams,
+    ) -> Self {
+        let port_count = port_count.max(1);
+        let node = Self {
+            generator_id: generator_id.into(),
+            label: label.into(),
+            output_buffers: (0..port_count).map(|_| AudioBuffer::new()).collect(),
+            waveform: AtomicU8::new(0),
+            frequency_bits: AtomicU32::new(0),
+            level_bits: AtomicU32::new(0),
+            sweep_end_bits: AtomicU32::new(0),
+            sweep_seconds_bits: AtomicU32::new(0),
+            osc: Oscillator::new(),
+        };
+        node.set_params(params);
+        node
+    }
+
+    /// Get the generator ID
+    pub fn generator_id(&self) -> &str {
+        &self.generator_id
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Current parameters
+    pub fn params(&self) -> GeneratorParams {
+        GeneratorParams {
+            waveform: Waveform::from_u8(self.waveform.load(Ordering::Relaxed)),
+            frequency: load_f32(&self.frequency_bits),
+            level: load_f32(&self.level_bits),
+            sweep_end: load_f32(&self.sweep_end_bits),
+            sweep_seconds: load_f32(&self.sweep_seconds_bits),
+        }
+    }
+
+    /// Update parameters (RT-safe atomic stores, values are clamped)
+    pub fn set_params(&self, params: GeneratorParams) {
+        let frequency = |f: f32| {
+            if f.is_finite() {
+                f.clamp(MIN_FREQUENCY, MAX_FREQUENCY)
+            } else {
+                1000.0
+            }
+        };
+        let level = if params.level.is_finite() {
+            params.level.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let sweep_seconds = if params.sweep_seconds.is_finite() {
+            params.sweep_seconds.clamp(0.1, 600.0)
+        } else {
+            10.0
+        };
+
+        self.waveform
+            .store(params.waveform.to_u8(), Ordering::Relaxed);
+        store_f32(&self.frequency_bits, frequency(params.frequency));
+        store_f32(&self.level_bits, level);
+        store_f32(&self.sweep_end_bits, frequency(params.sweep_end));
+        store_f32(&self.sweep_seconds_bits, sweep_seconds);
+    }
+}
+
+impl Oscillator {
+    fn new() -> Self {
+        Self {
+            phase: 0.0,
+            sweep_time: 0.0,
+            rng: 0x9E37_79B9,
+            pink: [0.0; 7],
+        }
+    }
+
+    fn white(&mut self) -> f32 {
+        // xorshift32 -> [-1.0, 1.0)
+        let mut x = self.rng;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.rng = x;
+        (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+    }
+
+    fn pink(&mut self) -> f32 {
+        let white = self.white();
+        let b = &mut self.pink;
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.153852;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+        // ピークがおおよそ ±1 に収まるようにスケール
+        pink * 0.11
+    }
+
+    /// Render one block
+    fn render(&mut self, params: &GeneratorParams, samples: &mut [f32], sample_rate: f64) {
+        match params.waveform {
+            Waveform::Sine => {
+                let step = params.frequency as f64 / sample_rate;
+                for s in samples.iter_mut() {
+                    *s = (self.phase * TAU).sin() as f32 * params.level;
+                    self.phase = (self.phase + step).fract();
+                }
+            }
+            Waveform::Sweep => {
+                let start = params.frequency as f64;
+                let ratio = params.sweep_end as f64 / start;
+                let duration = params.sweep_seconds as f64;
+                for s in samples.iter_mut() {
+                    *s = (self.phase * TAU).sin() as f32 * params.level;
+                    let freq = start * ratio.powf(self.sweep_time / duration);
+                    self.phase = (self.phase + freq / sample_rate).fract();
+                    self.sweep_time += 1.0 / sample_rate;
+                    if self.sweep_time >= duration {
+                        self.sweep_time = 0.0;
+                    }
+                }
+            }
+            Waveform::WhiteNoise => {
+                for s in samples.iter_mut() {
+                    *s = self.white() * params.level;
+                }
+            }
+            Waveform::PinkNoise => {
+                for s in samples.iter_mut() {
+                    *s = self.pink() * params.level;
+                }
+            }
+        }
+    }
+}
+
+fn load_f32(bits: &AtomicU32) -> f32 {
+    f32::from_bits(bits.load(Ordering::Relaxed))
+}
+
+fn store_f32(bits: &AtomicU32, value: f32) {
+    bits.store(value.to_bits(), Ordering::Relaxed);
+}
+
+impl AudioNode for GeneratorNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Source
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        0
+    }
+
+    fn output_port_count(&self) -> usize {
+        self.output_buffers.len()
+    }
+
+    fn input_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn input_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn output_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.output_buffers.get(port.index())
+    }
+
+    fn output_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.output_buffers.get_mut(port.index())
+    }
+
+    fn process(&mut self, frames: usize) {
+        let frames = frames.min(super::MAX_FRAMES);
+        for buf in &mut self.output_buffers {
+            buf.set_valid_frames(frames);
+        }
+
+        let params = self.params();
+        let (first, rest) = self.output_buffers.split_at_mut(1);
+        self.osc.render(
+            &params,
+            &mut first[0].samples_mut()[..frames],
+            super::engine_sample_rate(),
+        );
+
+        // 同じ信号を残りのポートへコピー
+        for buf in rest {
+            buf.copy_from(&first[0]);
+        }
+        for buf in &mut self.output_buffers {
+            buf.update_meters();
+        }
+    }
+
+    fn take_live_state(&mut self) -> Option<Box<dyn Any + Send>> {
+        // オフラインはスイープ・ノイズを頭から生成する
+        let live = std::mem::replace(&mut self.osc, Oscillator::new());
+        Some(Box::new(live))
+    }
+
+    fn restore_live_state(&mut self, state: Box<dyn Any + Send>) {
+        if let Ok(osc) = state.downcast::<Oscillator>() {
+            self.osc = *osc;
+        }
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.output_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        self.output_buffers
+            .iter()
+            .map(|b| b.cached_peak())
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(node: &GeneratorNode, port: u8, frames: usize) -> f32 {
+        node.output_buffer(PortId::new(port)).unwrap().samples()[..frames]
+            .iter()
+            .fold(0.0f32, |m, s| m.max(s.abs()))
+    }
+
+    #[test]
+    fn test_generator_sine_level_on_every_port() {
+        let params = GeneratorParams {
+            level: 0.5,
+            ..GeneratorParams::default()
+        };
+        let mut node = GeneratorNode::new("gen", "Tone", 2, params);
+        node.process(512);
+
+        let left = peak(&node, 0, 512);
+        assert!((left - 0.5).abs() < 0.01, "peak {}", left);
+        assert_eq!(
+            node.output_buffer(PortId::new(0)).unwrap().samples()[..512],
+            node.output_buffer(PortId::new(1)).unwrap().samples()[..512]
+        );
+    }
+
+    #[test]
+    fn test_generator_noise_stays_in_range() {
+        for waveform in [Waveform::WhiteNoise, Waveform::PinkNoise, Waveform::Sweep] {
+            let params = GeneratorParams {
+                waveform,
+                level: 1.0,
+                ..GeneratorParams::default()
+            };
+            let mut node = GeneratorNode::new("gen", "Noise", 1, params);
+            for _ in 0..8 {
+                node.process(512);
+                let p = peak(&node, 0, 512);
+                assert!(p > 0.0 && p <= 1.5, "{:?} peak {}", waveform, p);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generator_params_are_clamped() {
+        let node = GeneratorNode::new("gen", "Tone", 2, GeneratorParams::default());
+        node.set_params(GeneratorParams {
+            waveform: Waveform::parse("sweep").unwrap(),
+            frequency: 0.0,
+            level: 3.0,
+            sweep_end: f32::NAN,
+            sweep_seconds: 0.0,
+        });
+        let p = node.params();
+        assert_eq!(p.waveform, Waveform::Sweep);
+        assert_eq!(p.frequency, MIN_FREQUENCY);
+        assert_eq!(p.level, 1.0);
+        assert_eq!(p.sweep_end, 1000.0);
+        assert_eq!(p.sweep_seconds, 0.1);
+    }
+}
@@ -1,8 +1,50 @@
 //! Audio Graph - DAG-based routing with topological sort
 
-use super::edge::{Edge, EdgeId};
+use super::bus::BusNode;
+use super::dsp_load::LoadMeter;
+use super::edge::{DelayLine, Edge, EdgeId, MonitorMode, TapPoint, MAX_BUNDLE_CHANNELS};
 use super::node::{AudioNode, NodeHandle, NodeType, PortId};
-use std::collections::{HashMap, HashSet, VecDeque};
+use super::reclaim::{self, Retired};
+use super::schedule::RenderSchedule;
+use super::sink::SinkNode;
+use super::solo::{SoloMode, SoloState};
+use super::source::{SourceId, SourceNode};
+use super::topology::{CompiledGraph, Topology, TopologyNode};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Edges changed by `AudioGraph::resize_bus`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BusResize {
+    /// Bundles narrowed to the ports that still exist
+    pub narrowed: Vec<EdgeId>,
+    /// Input bundles now wider than the bus; they are summed with their sum law
+    pub folded: Vec<EdgeId>,
+    /// Edges moved from removed ports onto remaining ones
+    pub rewired: Vec<EdgeId>,
+    /// Edges dropped because their new ports were already connected
+    pub removed: Vec<EdgeId>,
+}
+
+/// Live processing state set aside while an offline render uses the graph
+///
+/// [`AudioGraph::take_live_state`] で取り出し、[`AudioGraph::restore_live_state`] で戻す。
+pub struct LiveState {
+    /// Schedule when the render started (the render keeps using it)
+    schedule: Arc<RenderSchedule>,
+    /// Applied gain and compensation delay lines of each edge
+    edges: Vec<(Edge, f32, Vec<DelayLine>)>,
+    /// State returned by [`AudioNode::take_live_state`]
+    nodes: Vec<(NodeHandle, Box<dyn Any + Send>)>,
+}
+
+impl LiveState {
+    /// Schedule of the graph when the state was taken
+    pub fn schedule(&self) -> &RenderSchedule {
+        &self.schedule
+    }
+}
 
 /// オーディオグラフ
 ///
@@ -18,8 +60,26 @@ pub struct AudioGraph {
     next_handle: u32,
     /// 次のエッジID
     next_edge_id: u32,
-    /// グラフが変更されたかどうか (rebuild needed)
+    /// 構造が変わり、処理順・スケジュール・補正・ソロの再コンパイルが必要か
     dirty: bool,
+    /// ソロ状態（ノードソロ・モード・モニター先）
+    solo: SoloState,
+    /// ノードごとの処理負荷
+    node_loads: HashMap<NodeHandle, LoadMeter>,
+    /// キュー（ヘッドフォン試聴）シンク。処理順の最後に置く
+    cue_sink: Option<NodeHandle>,
+    /// シンクのデバイスレイテンシ差も補正するか（有線と Bluetooth を揃える）
+    align_output_latency: bool,
+    /// ノードの表示色（UI 用。処理には使わない）
+    node_colors: HashMap<NodeHandle, String>,
+    /// ノードごとの任意のメタデータ（フロントエンド・外部コントローラー用）
+    node_metadata: HashMap<NodeHandle, BTreeMap<String, String>>,
+    /// メーターを止めたノード（既定ではすべて計測する）
+    unmetered_nodes: HashSet<NodeHandle>,
+    /// メーターを止めたエッジ
+    unmetered_edges: HashSet<EdgeId>,
+    /// コンパイル済みの処理スケジュール（install で差し替える）
+    schedule: Arc<RenderSchedule>,
 }
 
 impl AudioGraph {
@@ -32,6 +92,15 @@ impl AudioGraph {
             next_handle: 1, // Start from 1 (0 is reserved)
             next_edge_id: 1,
             dirty: false,
+            solo: SoloState::default(),
+            node_loads: HashMap::new(),
+            cue_sink: None,
+            align_output_latency: false,
+            node_colors: HashMap::new(),
+            node_metadata: HashMap::new(),
+            unmetered_nodes: HashSet::new(),
+            unmetered_edges: HashSet::new(),
+            schedule: Arc::default(),
         }
     }
 
@@ -39,22 +108,131 @@ impl AudioGraph {
     pub fn add_node(&mut self, node: Box<dyn AudioNode>) -> NodeHandle {
         let handle = NodeHandle::new(self.next_handle);
         self.next_handle += 1;
+        self.insert_node(handle, node);
+        handle
+    }
+
+    /// 指定したハンドルでノードを追加（0 や使用中なら新しいハンドルを割り当てる）
+    ///
+    /// 保存したグラフの復元で元のハンドルを保つため。以降の自動割り当てはこのハンドルより
+    /// 後から始まるので、外部コントローラーが覚えているハンドルと衝突しない。
+    pub fn add_node_at(&mut self, handle: NodeHandle, node: Box<dyn AudioNode>) -> NodeHandle {
+        if handle.raw() == 0 || self.nodes.contains_key(&handle) {
+            return self.add_node(node);
+        }
+        self.next_handle = self.next_handle.max(handle.raw().saturating_add(1));
+        self.insert_node(handle, node);
+        handle
+    }
+
+    fn insert_node(&mut self, handle: NodeHandle, node: Box<dyn AudioNode>) {
         self.nodes.insert(handle, node);
+        self.node_loads.insert(handle, LoadMeter::new());
         self.dirty = true;
-        handle
     }
 
     /// ノードを削除（関連エッジも自動削除）
+    ///
+    /// 削除したノードは [`reclaim`](super::reclaim) スレッドで drop される
+    /// （オーディオスレッドで適用されても解放処理を走らせない）。
     pub fn remove_node(&mut self, handle: NodeHandle) -> bool {
-        if self.nodes.remove(&handle).is_some() {
-            // 関連するエッジも削除
-            self.edges
-                .retain(|e| e.source != handle && e.target != handle);
-            self.dirty = true;
-            true
+        match self.take_node(handle) {
+            Some(node) => {
+                reclaim::retire(Retired::Node(node));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a node (and its edges) and hand it back to the caller
+    ///
+    /// 呼び出し側が制御スレッドで中身（プラグインのインスタンスなど）を見てから手放すため。
+    pub fn take_node(&mut self, handle: NodeHandle) -> Option<Box<dyn AudioNode>> {
+        let node = self.nodes.remove(&handle)?;
+        // 関連するエッジも削除
+        self.remove_edges_where(|e| e.source == handle || e.target == handle);
+        self.solo.nodes.remove(&handle);
+        self.node_loads.remove(&handle);
+        self.node_colors.remove(&handle);
+        self.node_metadata.remove(&handle);
+        self.unmetered_nodes.remove(&handle);
+        if self.solo.monitor == Some(handle) {
+            self.solo.monitor = None;
+        }
+        if self.cue_sink == Some(handle) {
+            self.cue_sink = None;
+        }
+        self.dirty = true;
+        Some(node)
+    }
+
+    /// Display color of a node (None = default)
+    pub fn node_color(&self, handle: NodeHandle) -> Option<&str> {
+        self.node_colors.get(&handle).map(String::as_str)
+    }
+
+    /// Set or clear the display color of a node (false if the node does not exist)
+    pub fn set_node_color(&mut self, handle: NodeHandle, color: Option<String>) -> bool {
+        if !self.nodes.contains_key(&handle) {
+            return false;
+        }
+        match color {
+            Some(color) => self.node_colors.insert(handle, color),
+            None => self.node_colors.remove(&handle),
+        };
+        true
+    }
+
+    /// Metadata of a node (None when it has none)
+    pub fn node_metadata(&self, handle: NodeHandle) -> Option<&BTreeMap<String, String>> {
+        self.node_metadata.get(&handle)
+    }
+
+    /// Set (Some) or remove (None) one metadata entry (false if the node does not exist)
+    pub fn set_node_metadata(
+        &mut self,
+        handle: NodeHandle,
+        key: String,
+        value: Option<String>,
+    ) -> bool {
+        if !self.nodes.contains_key(&handle) {
+            return false;
+        }
+        match value {
+            Some(value) => {
+                self.node_metadata
+                    .entry(handle)
+                    .or_default()
+                    .insert(key, value);
+            }
+            None => {
+                if let Some(metadata) = self.node_metadata.get_mut(&handle) {
+                    metadata.remove(&key);
+                    if metadata.is_empty() {
+                        self.node_metadata.remove(&handle);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Replace all metadata of a node (restore / apply_graph)
+    pub fn replace_node_metadata(
+        &mut self,
+        handle: NodeHandle,
+        metadata: BTreeMap<String, String>,
+    ) -> bool {
+        if !self.nodes.contains_key(&handle) {
+            return false;
+        }
+        if metadata.is_empty() {
+            self.node_metadata.remove(&handle);
         } else {
-            false
+            self.node_metadata.insert(handle, metadata);
         }
+        true
     }
 
     /// ノードを取得
@@ -97,6 +275,254 @@ impl AudioGraph {
         unsafe { Some((&mut **a_ptr, &mut **b_ptr)) }
     }
 
+    /// Whether a node's peak/RMS is computed and published
+    pub fn is_node_metered(&self, handle: NodeHandle) -> bool {
+        !self.unmetered_nodes.contains(&handle)
+    }
+
+    /// Turn a node's metering on or off (false if the node does not exist)
+    ///
+    /// 止めたノードはレベルを 0 として扱うので、そこから出るエッジのメーターも 0 になる。
+    pub fn set_node_metered(&mut self, handle: NodeHandle, metered: bool) -> bool {
+        if !self.nodes.contains_key(&handle) {
+            return false;
+        }
+        if metered {
+            self.unmetered_nodes.remove(&handle);
+        } else {
+            self.unmetered_nodes.insert(handle);
+        }
+        true
+    }
+
+    /// Whether an edge's post-gain level is published
+    pub fn is_edge_metered(&self, id: EdgeId) -> bool {
+        !self.unmetered_edges.contains(&id)
+    }
+
+    /// Turn an edge's metering on or off (false if the edge does not exist)
+    pub fn set_edge_metered(&mut self, id: EdgeId, metered: bool) -> bool {
+        if self.get_edge(id).is_none() {
+            return false;
+        }
+        if metered {
+            self.unmetered_edges.remove(&id);
+        } else {
+            self.unmetered_edges.insert(id);
+        }
+        true
+    }
+
+    /// Nodes with metering turned off (sorted)
+    pub fn unmetered_nodes(&self) -> Vec<NodeHandle> {
+        let mut handles: Vec<_> = self.unmetered_nodes.iter().copied().collect();
+        handles.sort_by_key(|h| h.raw());
+        handles
+    }
+
+    /// Existing edges with metering turned off (sorted)
+    pub fn unmetered_edges(&self) -> Vec<EdgeId> {
+        let mut ids: Vec<_> = self
+            .edges
+            .iter()
+            .map(|e| e.id)
+            .filter(|id| self.unmetered_edges.contains(id))
+            .collect();
+        ids.sort_by_key(|id| id.raw());
+        ids
+    }
+
+    /// ノードの処理負荷メーター
+    pub fn node_load(&self, handle: NodeHandle) -> Option<&LoadMeter> {
+        self.node_loads.get(&handle)
+    }
+
+    /// デバイス ID を付け替える（ホットプラグで再列挙されたデバイス向け）
+    ///
+    /// `old` を参照しているシンク/デバイスソースをすべて `new` に向け直し、その数を返す。
+    pub fn rebind_device(&mut self, old: u32, new: u32) -> usize {
+        if old == new {
+            return 0;
+        }
+        let mut count = 0;
+        for node in self.nodes.values_mut() {
+            let any = node.as_any_mut();
+            if let Some(sink) = any.downcast_mut::<SinkNode>() {
+                if sink.device_id() == old {
+                    sink.set_device_id(new);
+                    count += 1;
+                }
+            } else if let Some(source) = any.downcast_mut::<SourceNode>() {
+                let bound = matches!(
+                    source.source_id(),
+                    SourceId::InputDevice { device_id, .. } if *device_id == old
+                );
+                if bound && source.set_device_id(new) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Point every "follow system default" sink at a new default output device
+    ///
+    /// Returns (handle, previous device ID) of each sink that moved.
+    pub fn retarget_default_sinks(
+        &mut self,
+        device_id: u32,
+        device_uid: Option<&str>,
+    ) -> Vec<(NodeHandle, u32)> {
+        let mut moved = Vec::new();
+        for (&handle, node) in self.nodes.iter_mut() {
+            let Some(sink) = node.as_any_mut().downcast_mut::<SinkNode>() else {
+                continue;
+            };
+            if !sink.follows_default() || sink.device_id() == device_id {
+                continue;
+            }
+            moved.push((handle, sink.device_id()));
+            sink.retarget(device_id, device_uid.map(str::to_string));
+        }
+        moved
+    }
+
+    /// Point every sink on output device `from` at device `to` (output device switch)
+    ///
+    /// Returns the handles of the sinks that moved.
+    pub fn retarget_device_sinks(
+        &mut self,
+        from: u32,
+        to: u32,
+        device_uid: Option<&str>,
+    ) -> Vec<NodeHandle> {
+        let mut moved = Vec::new();
+        for (&handle, node) in self.nodes.iter_mut() {
+            let Some(sink) = node.as_any_mut().downcast_mut::<SinkNode>() else {
+                continue;
+            };
+            if sink.device_id() != from || from == to {
+                continue;
+            }
+            moved.push(handle);
+            sink.retarget(to, device_uid.map(str::to_string));
+        }
+        moved
+    }
+
+    /// Fit the sources of an input device to its new channel count
+    ///
+    /// チャンネル数が変わったデバイスのソースノードのポート数を合わせる。
+    /// 消えたポートからのエッジは残す（無音になり、チャンネルが戻れば再び鳴る）。
+    /// Returns the handles of resized nodes.
+    pub fn resize_device_sources(
+        &mut self,
+        device_id: u32,
+        input_channels: usize,
+    ) -> Vec<NodeHandle> {
+        let mut resized = Vec::new();
+        for (&handle, node) in self.nodes.iter_mut() {
+            let Some(source) = node.as_any_mut().downcast_mut::<SourceNode>() else {
+                continue;
+            };
+            let bound = matches!(
+                source.source_id(),
+                SourceId::InputDevice { device_id: id, .. } if *id == device_id
+            );
+            if bound && source.fit_to_device_channels(input_channels) {
+                resized.push(handle);
+            }
+        }
+        if !resized.is_empty() {
+            resized.sort_by_key(|h| h.raw());
+            self.dirty = true;
+        }
+        resized
+    }
+
+    /// Change the port count of a bus and fit its edges to the new ports
+    ///
+    /// 縮小時、範囲からはみ出した出力側のバンドルは残るポートまで幅を詰める。
+    /// 入力側のバンドルは幅を保ち、処理時にサムローで残るポートへ畳み込む
+    /// （ステレオ → モノラルで片側を失わない）。
+    /// 範囲外のポートにつながるエッジは、ポート番号をポート数で折り返した位置へ
+    /// つなぎ替える（同じポート対が既にあればエッジを削除する）。
+    /// Returns None if `handle` is not a bus.
+    pub fn resize_bus(&mut self, handle: NodeHandle, port_count: usize) -> Option<BusResize> {
+        let bus = self
+            .nodes
+            .get_mut(&handle)?
+            .as_any_mut()
+            .downcast_mut::<BusNode>()?;
+        let mut report = BusResize::default();
+        if !bus.set_port_count(port_count) {
+            return Some(report);
+        }
+        let port_count = bus.input_port_count();
+
+        let mut i = 0;
+        while i < self.edges.len() {
+            let edge = &self.edges[i];
+            // バスの入力側（ターゲット）か出力側（ソース）か
+            let bus_port = if edge.target == handle {
+                edge.target_port.index()
+            } else if edge.source == handle {
+                edge.source_port.index()
+            } else {
+                i += 1;
+                continue;
+            };
+            let channels = edge.channels as usize;
+            if bus_port + channels <= port_count {
+                i += 1;
+                continue;
+            }
+            let into_bus = edge.target == handle;
+            if into_bus && bus_port < port_count {
+                report.folded.push(edge.id);
+                i += 1;
+                continue;
+            }
+
+            let new_port = bus_port % port_count;
+            let new_channels = if into_bus {
+                channels as u8
+            } else {
+                channels.min(port_count - new_port) as u8
+            };
+            let (mut source_port, mut target_port) = (edge.source_port, edge.target_port);
+            if into_bus {
+                target_port = PortId::new(new_port as u8);
+            } else {
+                source_port = PortId::new(new_port as u8);
+            }
+            let (id, source, target) = (edge.id, edge.source, edge.target);
+            let taken = self.edges.iter().any(|e| {
+                e.id != id && e.overlaps(source, source_port, target, target_port, new_channels)
+            });
+            if taken {
+                let edge = self.edges.remove(i);
+                edge.detach();
+                reclaim::retire(Retired::Edge(edge));
+                report.removed.push(id);
+                continue;
+            }
+
+            let edge = &mut self.edges[i];
+            edge.source_port = source_port;
+            edge.target_port = target_port;
+            edge.set_channels(new_channels);
+            if new_port == bus_port {
+                report.narrowed.push(id);
+            } else {
+                report.rewired.push(id);
+            }
+            i += 1;
+        }
+        self.dirty = true;
+        Some(report)
+    }
+
     /// すべてのノードハンドルを取得
     pub fn node_handles(&self) -> impl Iterator<Item = NodeHandle> + '_ {
         self.nodes.keys().copied()
@@ -114,26 +540,41 @@ impl AudioGraph {
         source_port: PortId,
         target: NodeHandle,
         target_port: PortId,
+    ) -> Option<EdgeId> {
+        self.add_bundle_edge(source, source_port, target, target_port, 1)
+    }
+
+    /// チャンネルバンドルエッジを追加
+    ///
+    /// 既存エッジとポート範囲が重なる場合は追加しない。
+    pub fn add_bundle_edge(
+        &mut self,
+        source: NodeHandle,
+        source_port: PortId,
+        target: NodeHandle,
+        target_port: PortId,
+        channels: u8,
     ) -> Option<EdgeId> {
         // Validate nodes exist
         if !self.nodes.contains_key(&source) || !self.nodes.contains_key(&target) {
             return None;
         }
+        if channels == 0 || channels as usize > MAX_BUNDLE_CHANNELS {
+            return None;
+        }
 
-        // Check for duplicate
-        let exists = self.edges.iter().any(|e| {
-            e.source == source
-                && e.source_port == source_port
-                && e.target == target
-                && e.target_port == target_port
-        });
+        // Check for duplicate / overlapping channels
+        let exists = self
+            .edges
+            .iter()
+            .any(|e| e.overlaps(source, source_port, target, target_port, channels));
         if exists {
             return None;
         }
 
         let id = EdgeId::new(self.next_edge_id);
         self.next_edge_id += 1;
-        let edge = Edge::new(id, source, source_port, target, target_port);
+        let edge = Edge::new_bundle(id, source, source_port, target, target_port, channels);
         self.edges.push(edge);
         self.dirty = true;
         Some(id)
@@ -149,7 +590,22 @@ impl AudioGraph {
         gain: f32,
         muted: bool,
     ) -> Option<EdgeId> {
-        let id = self.add_edge(source, source_port, target, target_port)?;
+        self.add_bundle_edge_with_params(source, source_port, target, target_port, 1, gain, muted)
+    }
+
+    /// チャンネルバンドルエッジを追加（ゲインとミュート指定）
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_bundle_edge_with_params(
+        &mut self,
+        source: NodeHandle,
+        source_port: PortId,
+        target: NodeHandle,
+        target_port: PortId,
+        channels: u8,
+        gain: f32,
+        muted: bool,
+    ) -> Option<EdgeId> {
+        let id = self.add_bundle_edge(source, source_port, target, target_port, channels)?;
         if let Some(edge) = self.edges.iter_mut().find(|e| e.id == id) {
             edge.set_gain(gain);
             edge.set_muted(muted);
@@ -159,10 +615,30 @@ impl AudioGraph {
 
     /// エッジを削除
     pub fn remove_edge(&mut self, id: EdgeId) -> bool {
-        let len_before = self.edges.len();
-        self.edges.retain(|e| e.id != id);
-        let removed = self.edges.len() < len_before;
+        let removed = self.remove_edges_where(|e| e.id == id) > 0;
         if removed {
+            self.unmetered_edges.remove(&id);
+        }
+        removed
+    }
+
+    /// Remove the edges matching `pred` (order of the rest is kept); returns how many
+    ///
+    /// 外したエッジは切り離し済みにする（再コンパイルまで古いスケジュールが持っていても鳴らない）。
+    fn remove_edges_where(&mut self, mut pred: impl FnMut(&Edge) -> bool) -> usize {
+        let mut removed = 0;
+        let mut i = 0;
+        while i < self.edges.len() {
+            if pred(&self.edges[i]) {
+                let edge = self.edges.remove(i);
+                edge.detach();
+                reclaim::retire(Retired::Edge(edge));
+                removed += 1;
+            } else {
+                i += 1;
+            }
+        }
+        if removed > 0 {
             self.dirty = true;
         }
         removed
@@ -174,7 +650,10 @@ impl AudioGraph {
     }
 
     /// エッジを取得（可変）
+    ///
+    /// ポートを書き換えられてもスケジュールに反映されるよう、再コンパイル対象にする。
     pub fn get_edge_mut(&mut self, id: EdgeId) -> Option<&mut Edge> {
+        self.dirty = true;
         self.edges.iter_mut().find(|e| e.id == id)
     }
 
@@ -218,6 +697,15 @@ impl AudioGraph {
         }
     }
 
+    /// バンドルエッジのチャンネルトリムを更新（&self でOK / Atomic）
+    pub fn set_edge_channel_trim(&self, id: EdgeId, channel: usize, trim: f32) -> bool {
+        self.edges
+            .iter()
+            .find(|e| e.id == id)
+            .map(|e| e.set_channel_trim(channel, trim))
+            .unwrap_or(false)
+    }
+
     /// エッジのミュートを更新（&self でOK / Atomic）
     pub fn set_edge_muted_atomic(&self, id: EdgeId, muted: bool) -> bool {
         if let Some(edge) = self.edges.iter().find(|e| e.id == id) {
@@ -228,6 +716,73 @@ impl AudioGraph {
         }
     }
 
+    /// ダイレクトモニターできるエッジなら、その入出力デバイス ID を返す
+    ///
+    /// 入力デバイスのソースから同じデバイスのシンクへ直接つながるエッジのみ対象。
+    pub fn direct_monitor_device(&self, id: EdgeId) -> Option<u32> {
+        let edge = self.get_edge(id)?;
+        let source = self
+            .get_node(edge.source)?
+            .as_any()
+            .downcast_ref::<SourceNode>()?;
+        let sink = self
+            .get_node(edge.target)?
+            .as_any()
+            .downcast_ref::<SinkNode>()?;
+        match source.source_id() {
+            SourceId::InputDevice { device_id, .. } if *device_id == sink.device_id() => {
+                Some(*device_id)
+            }
+            _ => None,
+        }
+    }
+
+    /// エッジの取り出し位置を設定
+    ///
+    /// プリプラグインはバスから出るエッジのみ。取り出し位置でソース側のレイテンシが
+    /// 変わるので補正を再コンパイルの対象にする。
+    pub fn set_edge_tap_point(&mut self, id: EdgeId, tap: TapPoint) -> Result<(), String> {
+        let edge = self
+            .get_edge(id)
+            .ok_or_else(|| format!("Edge {} not found", id.raw()))?;
+        if tap == TapPoint::PrePlugin
+            && !self
+                .nodes
+                .get(&edge.source)
+                .is_some_and(|n| n.as_any().is::<BusNode>())
+        {
+            return Err(format!(
+                "Edge {} does not come from a bus; only bus sends can tap pre-plugin",
+                id.raw()
+            ));
+        }
+        edge.set_tap_point(tap);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// エッジのモニター経路を切り替え、対象デバイス ID を返す（&self でOK / Atomic）
+    pub fn set_edge_monitor_mode(&self, id: EdgeId, mode: MonitorMode) -> Result<u32, String> {
+        let edge = self
+            .get_edge(id)
+            .ok_or_else(|| format!("Edge {} not found", id.raw()))?;
+        let device_id = self.direct_monitor_device(id).ok_or_else(|| {
+            format!(
+                "Edge {} is not an input-device -> sink path on the same device",
+                id.raw()
+            )
+        })?;
+        edge.set_monitor_mode(mode);
+        Ok(device_id)
+    }
+
+    /// デバイスにダイレクトモニターのエッジがあるか
+    pub fn has_direct_monitor(&self, device_id: u32) -> bool {
+        self.edges
+            .iter()
+            .any(|e| e.is_direct() && self.direct_monitor_device(e.id) == Some(device_id))
+    }
+
     /// ターゲットノードへのエッジを取得
     pub fn edges_to(&self, target: NodeHandle) -> impl Iterator<Item = &Edge> {
         self.edges.iter().filter(move |e| e.target == target)
@@ -238,6 +793,23 @@ impl AudioGraph {
         self.edges.iter().filter(move |e| e.source == source)
     }
 
+    /// `from` から `to` へエッジをたどって到達できるか（`from == to` も true）
+    ///
+    /// `to -> from` のエッジを足すとループになるかの判定に使う。
+    pub fn has_path(&self, from: NodeHandle, to: NodeHandle) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(handle) = stack.pop() {
+            if handle == to {
+                return true;
+            }
+            if visited.insert(handle) {
+                stack.extend(self.edges_from(handle).map(|e| e.target));
+            }
+        }
+        false
+    }
+
     /// 処理順序を取得
     pub fn processing_order(&self) -> &[NodeHandle] {
         &self.processing_order
@@ -250,97 +822,269 @@ impl AudioGraph {
         }
     }
 
-    /// 処理順序を再計算
+    /// 処理順序・スケジュール・レイテンシ補正・ソロをこのスレッドで再計算（制御スレッド）
     pub fn rebuild_order(&mut self) {
-        self.processing_order = self.topological_sort();
-        self.dirty = false;
+        let mut topology = Topology::default();
+        self.capture_topology(&mut topology);
+        self.install(topology.compile());
     }
 
-    /// トポロジカルソート (Kahn's algorithm)
-    fn topological_sort(&self) -> Vec<NodeHandle> {
-        let mut in_degree: HashMap<NodeHandle, usize> = HashMap::new();
-        let mut adjacency: HashMap<NodeHandle, Vec<NodeHandle>> = HashMap::new();
+    /// Mark the graph for recompilation (plugin changes can change latency)
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
 
-        // Initialize
-        for &handle in self.nodes.keys() {
-            in_degree.insert(handle, 0);
-            adjacency.insert(handle, Vec::new());
-        }
+    /// Copy the structure the schedule is compiled from into `topology`
+    ///
+    /// オーディオスレッドから呼ばれる: `topology` の予約済みの容量に詰めるだけで、
+    /// 容量が足りる限り確保しない（レイテンシはキャッシュを読む）。
+    pub fn capture_topology(&mut self, topology: &mut Topology) {
+        topology.clear();
+        self.fill_topology(topology);
+        self.dirty = false;
+    }
 
-        // Build adjacency and in-degree
-        for edge in &self.edges {
-            if let Some(adj) = adjacency.get_mut(&edge.source) {
-                if !adj.contains(&edge.target) {
-                    adj.push(edge.target);
-                }
-            }
-            if let Some(deg) = in_degree.get_mut(&edge.target) {
-                // Only count if not already counted (avoid double counting for multi-port edges)
-                let already_counted = self
-                    .edges
-                    .iter()
-                    .any(|e| e.source == edge.source && e.target == edge.target && e.id != edge.id);
-                if !already_counted {
-                    *deg += 1;
+    fn fill_topology(&self, topology: &mut Topology) {
+        topology
+            .nodes
+            .extend(self.nodes.iter().map(|(&handle, node)| {
+                TopologyNode {
+                    handle,
+                    node_type: node.node_type(),
+                    latency: node.latency_samples(),
+                    device_latency: node
+                        .as_any()
+                        .downcast_ref::<SinkNode>()
+                        .map(|sink| sink.device_latency()),
                 }
+            }));
+        topology.edges.extend(self.edges.iter().cloned());
+        topology
+            .delays
+            .extend(self.edges.iter().map(|e| e.delay_state()));
+        topology.cue_sink = self.cue_sink;
+        topology.align_output_latency = self.align_output_latency;
+        topology.solo_mode = self.solo.mode;
+        topology.solo_monitor = self.solo.monitor;
+        topology.solo_nodes.extend(self.solo.nodes.iter().copied());
+        for (&handle, node) in &self.nodes {
+            if let Some(bus) = node.as_any().downcast_ref::<BusNode>() {
+                topology.dry_delays.extend(
+                    bus.dry_delay_changes()
+                        .map(|(slot, wanted)| (handle, slot, wanted)),
+                );
             }
         }
+    }
 
-        // Recalculate in_degree properly (count unique source->target pairs)
-        for (&handle, deg) in in_degree.iter_mut() {
-            let sources: HashSet<_> = self
-                .edges
-                .iter()
-                .filter(|e| e.target == handle)
-                .map(|e| e.source)
-                .collect();
-            *deg = sources.len();
+    /// Swap in a compiled order, schedule, delay lines (edges and bus dry paths) and solo flags
+    ///
+    /// オーディオスレッドでは入れ替えとフラグの書き込みだけを行い、外れた古いものは
+    /// [`reclaim`](super::reclaim) スレッドで drop する。
+    pub fn install(&mut self, mut compiled: CompiledGraph) {
+        std::mem::swap(&mut self.processing_order, &mut compiled.order);
+        std::mem::swap(&mut self.schedule, &mut compiled.schedule);
+        for (edge, lines) in compiled.delays.iter_mut() {
+            edge.swap_delay_lines(lines);
+        }
+        for (edge, flags) in &compiled.solo {
+            edge.set_solo_flags(flags.muted, flags.unity);
         }
+        for (handle, slot, lines) in compiled.dry_delays.iter_mut() {
+            if let Some(bus) = self
+                .nodes
+                .get_mut(handle)
+                .and_then(|n| n.as_any_mut().downcast_mut::<BusNode>())
+            {
+                bus.swap_dry_delays(*slot, lines);
+            }
+        }
+        reclaim::retire(Retired::Compiled(compiled));
+    }
 
-        // Start with nodes that have no incoming edges
-        let queue: VecDeque<NodeHandle> = in_degree
+    /// コンパイル済みの処理スケジュール
+    ///
+    /// 変更後は `install` まで古いスケジュールのまま。オーディオスレッドは
+    /// ブロックの間これを保持する（差し替えられても処理中のブロックには影響しない）。
+    pub fn schedule(&self) -> Arc<RenderSchedule> {
+        Arc::clone(&self.schedule)
+    }
+
+    /// Set the processing state aside for an offline render and start from a settled one
+    ///
+    /// エッジのゲインランプは目標値に揃え、補正ディレイは同じ遅延の空のラインに入れ替える。
+    /// ノードの状態は [`AudioNode::take_live_state`]。確保を伴うので制御スレッドから呼ぶ。
+    pub fn take_live_state(&mut self) -> LiveState {
+        let edges = self
+            .edges
             .iter()
-            .filter(|(_, &deg)| deg == 0)
-            .map(|(&handle, _)| handle)
+            .map(|edge| {
+                let gain = edge.applied_gain();
+                edge.set_applied_gain(edge.target_gain());
+                let (delay, count) = edge.delay_state();
+                let mut lines = (0..count).map(|_| DelayLine::with_delay(delay)).collect();
+                edge.swap_delay_lines(&mut lines);
+                (edge.clone(), gain, lines)
+            })
             .collect();
+        let nodes = self
+            .nodes
+            .iter_mut()
+            .filter_map(|(&handle, node)| Some((handle, node.take_live_state()?)))
+            .collect();
+        LiveState {
+            schedule: self.schedule(),
+            edges,
+            nodes,
+        }
+    }
 
-        // Sort queue by node type (Source first, then Bus, then Sink)
-        let mut queue: Vec<_> = queue.into_iter().collect();
-        queue.sort_by_key(|h| match self.nodes.get(h).map(|n| n.node_type()) {
-            Some(NodeType::Source) => 0,
-            Some(NodeType::Bus) => 1,
-            Some(NodeType::Sink) => 2,
-            None => 3,
-        });
-        let mut queue: VecDeque<_> = queue.into_iter().collect();
-
-        let mut result = Vec::new();
-
-        while let Some(handle) = queue.pop_front() {
-            result.push(handle);
-
-            if let Some(neighbors) = adjacency.get(&handle) {
-                for &neighbor in neighbors {
-                    if let Some(deg) = in_degree.get_mut(&neighbor) {
-                        *deg = deg.saturating_sub(1);
-                        if *deg == 0 {
-                            queue.push_back(neighbor);
-                        }
-                    }
-                }
+    /// Put back the state set aside by [`Self::take_live_state`] (the offline state is dropped)
+    ///
+    /// レンダリング中に削除されたものは飛ばし、再コンパイルで遅延が変わったエッジは
+    /// 新しいディレイラインのままにする。
+    pub fn restore_live_state(&mut self, state: LiveState) {
+        for (edge, gain, mut lines) in state.edges {
+            edge.set_applied_gain(gain);
+            let live = (lines.first().map_or(0, |l| l.delay()), lines.len());
+            if live == edge.delay_state() {
+                edge.swap_delay_lines(&mut lines);
+            }
+        }
+        for (handle, node_state) in state.nodes {
+            if let Some(node) = self.nodes.get_mut(&handle) {
+                node.restore_live_state(node_state);
             }
         }
+    }
 
-        // Check for cycles (if result doesn't contain all nodes)
-        if result.len() != self.nodes.len() {
-            eprintln!(
-                "[AudioGraph] Warning: Cycle detected! Processed {} of {} nodes",
-                result.len(),
-                self.nodes.len()
-            );
+    /// Structure of the graph as it is now (control thread; allocates)
+    fn topology(&self) -> Topology {
+        let mut topology = Topology::default();
+        self.fill_topology(&mut topology);
+        topology
+    }
+
+    // =========================================================================
+    // Solo
+    // =========================================================================
+
+    // ソロの評価（各エッジのソロミュート）は再コンパイル時に制御スレッドで行い、
+    // install でエッジの Atomic フラグに書き込む。オーディオスレッドはフラグを読むだけ。
+
+    /// エッジのソロを設定（ソロミュートは再コンパイル後に反映）
+    pub fn set_edge_solo(&mut self, id: EdgeId, soloed: bool) -> bool {
+        let Some(edge) = self.get_edge(id) else {
+            return false;
+        };
+        edge.set_soloed(soloed);
+        self.dirty = true;
+        true
+    }
+
+    /// ノードのソロを設定
+    pub fn set_node_solo(&mut self, handle: NodeHandle, soloed: bool) -> bool {
+        if !self.nodes.contains_key(&handle) {
+            return false;
+        }
+        if soloed {
+            self.solo.nodes.insert(handle);
+        } else {
+            self.solo.nodes.remove(&handle);
         }
+        self.dirty = true;
+        true
+    }
 
-        result
+    /// ソロモードとモニター先シンクを設定
+    ///
+    /// AFL/PFL で `monitor` が None の場合は Solo-in-place と同じ挙動になる。
+    pub fn set_solo_mode(&mut self, mode: SoloMode, monitor: Option<NodeHandle>) -> bool {
+        if let Some(monitor) = monitor {
+            if !self.nodes.contains_key(&monitor) {
+                return false;
+            }
+        }
+        self.solo.mode = mode;
+        self.solo.monitor = monitor;
+        self.dirty = true;
+        true
+    }
+
+    /// ソロをすべて解除
+    pub fn clear_solo(&mut self) {
+        self.solo.nodes.clear();
+        for edge in &self.edges {
+            edge.set_soloed(false);
+        }
+        self.dirty = true;
+    }
+
+    /// 現在のソロ状態
+    pub fn solo_state(&self) -> &SoloState {
+        &self.solo
+    }
+
+    /// ソロ中か（ノードまたはエッジ）
+    pub fn is_solo_active(&self) -> bool {
+        !self.solo.nodes.is_empty() || self.edges.iter().any(|e| e.soloed())
+    }
+
+    // =========================================================================
+    // Cue
+    // =========================================================================
+
+    /// Designate the cue sink (None = cue off); must be a sink node
+    pub fn set_cue_sink(&mut self, handle: Option<NodeHandle>) -> bool {
+        if let Some(handle) = handle {
+            if self.nodes.get(&handle).map(|n| n.node_type()) != Some(NodeType::Sink) {
+                return false;
+            }
+        }
+        self.cue_sink = handle;
+        self.dirty = true;
+        true
+    }
+
+    /// Current cue sink
+    pub fn cue_sink(&self) -> Option<NodeHandle> {
+        self.cue_sink
+    }
+
+    // レイテンシ補正（各ノードの入力到達レイテンシを「最も遅い入力パス」に揃え、
+    // それより早く到達するエッジに差分のディレイを挿入する）も再コンパイル時に計算する。
+    // プラグインの追加/削除/バイパス後は `mark_dirty` で再コンパイルの対象にすること。
+
+    /// Whether sinks are delayed to match the slowest output device
+    pub fn align_output_latency(&self) -> bool {
+        self.align_output_latency
+    }
+
+    /// Delay faster sinks so every output (path + device latency) lines up
+    ///
+    /// Bluetooth / AirPlay と有線出力を同時に使うときに大まかに揃えるためのもの。
+    /// 補正はシンクへのエッジのディレイに加算される（上限は
+    /// [`super::edge::MAX_COMPENSATION_SAMPLES`]）。キューシンクは対象外。
+    pub fn set_align_output_latency(&mut self, enabled: bool) {
+        self.align_output_latency = enabled;
+        self.dirty = true;
+    }
+
+    /// Alignment delay currently applied to a sink (samples)
+    pub fn output_alignment_delay(&self, handle: NodeHandle) -> u32 {
+        let topology = self.topology();
+        let (_, output) = topology.path_latencies(&self.processing_order);
+        topology
+            .output_alignment_delays(&output)
+            .get(&handle)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// ノードの入力到達レイテンシ（サンプル数）
+    pub fn path_latency_to(&self, handle: NodeHandle) -> u32 {
+        let (arrival, _) = self.topology().path_latencies(&self.processing_order);
+        arrival.get(&handle).copied().unwrap_or(0)
     }
 
     /// ソースノードを取得
@@ -367,6 +1111,14 @@ impl AudioGraph {
             .map(|(&h, _)| h)
     }
 
+    /// 録音ノードを取得
+    pub fn record_nodes(&self) -> impl Iterator<Item = NodeHandle> + '_ {
+        self.nodes
+            .iter()
+            .filter(|(_, n)| n.node_type() == NodeType::Record)
+            .map(|(&h, _)| h)
+    }
+
     /// Check if graph is dirty (needs rebuild)
     pub fn is_dirty(&self) -> bool {
         self.dirty
@@ -382,7 +1134,7 @@ impl Default for AudioGraph {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::audio::source::SourceNode;
+    use crate::audio::sink::SinkId;
 
     #[test]
     fn test_add_remove_node() {
@@ -398,6 +1150,187 @@ mod tests {
         assert_eq!(graph.node_count(), 0);
     }
 
+    #[test]
+    fn test_add_node_at_keeps_handle() {
+        let mut graph = AudioGraph::new();
+
+        let kept = graph.add_node_at(NodeHandle::new(7), Box::new(SourceNode::new_prism(0, "A")));
+        assert_eq!(kept, NodeHandle::new(7));
+
+        // 使用中・0 は新しいハンドル（復元したハンドルより後）
+        let taken = graph.add_node_at(NodeHandle::new(7), Box::new(SourceNode::new_prism(2, "B")));
+        assert_eq!(taken, NodeHandle::new(8));
+        let zero = graph.add_node_at(NodeHandle::new(0), Box::new(SourceNode::new_prism(4, "C")));
+        assert_eq!(zero, NodeHandle::new(9));
+
+        // 小さいハンドルへの復元は自動割り当てを巻き戻さない
+        graph.remove_node(kept);
+        assert_eq!(
+            graph.add_node_at(NodeHandle::new(3), Box::new(SourceNode::new_prism(6, "D"))),
+            NodeHandle::new(3)
+        );
+        assert_eq!(
+            graph.add_node(Box::new(SourceNode::new_prism(8, "E"))),
+            NodeHandle::new(10)
+        );
+    }
+
+    #[test]
+    fn test_node_color() {
+        let mut graph = AudioGraph::new();
+        let handle = graph.add_node(Box::new(SourceNode::new_prism(0, "Test")));
+
+        assert!(graph.set_node_color(handle, Some("#ff8800".to_string())));
+        assert_eq!(graph.node_color(handle), Some("#ff8800"));
+        assert!(graph.set_node_color(handle, None));
+        assert_eq!(graph.node_color(handle), None);
+
+        graph.set_node_color(handle, Some("#ff8800".to_string()));
+        graph.remove_node(handle);
+        assert!(!graph.set_node_color(handle, Some("#000000".to_string())));
+        assert_eq!(graph.node_color(handle), None);
+    }
+
+    #[test]
+    fn test_node_metadata() {
+        let mut graph = AudioGraph::new();
+        let handle = graph.add_node(Box::new(SourceNode::new_prism(0, "Test")));
+        assert_eq!(graph.node_metadata(handle), None);
+
+        assert!(graph.set_node_metadata(handle, "note".into(), Some("vocals".into())));
+        assert!(graph.set_node_metadata(handle, "order".into(), Some("2".into())));
+        let metadata = graph.node_metadata(handle).unwrap();
+        assert_eq!(metadata.get("note").map(String::as_str), Some("vocals"));
+        assert_eq!(metadata.len(), 2);
+
+        graph.set_node_metadata(handle, "note".into(), None);
+        graph.set_node_metadata(handle, "order".into(), None);
+        assert_eq!(graph.node_metadata(handle), None);
+
+        graph.replace_node_metadata(handle, BTreeMap::from([("a".into(), "1".into())]));
+        graph.remove_node(handle);
+        assert_eq!(graph.node_metadata(handle), None);
+        assert!(!graph.set_node_metadata(handle, "a".into(), Some("1".into())));
+    }
+
+    #[test]
+    fn test_metering_flags() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(BusNode::new_stereo("b", "Bus")));
+        let edge = graph
+            .add_edge(src, PortId::new(0), bus, PortId::new(0))
+            .unwrap();
+        assert!(graph.is_node_metered(src));
+        assert!(graph.is_edge_metered(edge));
+
+        assert!(graph.set_node_metered(src, false));
+        assert!(graph.set_edge_metered(edge, false));
+        assert_eq!(graph.unmetered_nodes(), vec![src]);
+        assert_eq!(graph.unmetered_edges(), vec![edge]);
+        assert!(graph.set_node_metered(src, true));
+        assert!(graph.is_node_metered(src));
+
+        // 消えたノード/エッジは一覧から外れる
+        graph.remove_edge(edge);
+        assert!(graph.unmetered_edges().is_empty());
+        assert!(!graph.set_edge_metered(edge, false));
+        graph.set_node_metered(bus, false);
+        graph.remove_node(bus);
+        assert!(graph.unmetered_nodes().is_empty());
+    }
+
+    #[test]
+    fn test_resize_bus() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(BusNode::new("b", "Bus", 4)));
+        let sink = graph.add_node(Box::new(SinkNode::new_stereo(1, "Out")));
+
+        let wide = graph
+            .add_bundle_edge(src, PortId::new(0), bus, PortId::new(0), 4)
+            .unwrap();
+        let upper = graph
+            .add_bundle_edge(bus, PortId::new(2), sink, PortId::new(0), 2)
+            .unwrap();
+        let lower = graph
+            .add_bundle_edge(bus, PortId::new(0), sink, PortId::new(0), 1)
+            .unwrap();
+
+        assert_eq!(graph.resize_bus(src, 1), None);
+        let report = graph.resize_bus(bus, 2).unwrap();
+        // 入力側のバンドルは幅を保ったまま畳み込まれる
+        assert_eq!(report.folded, vec![wide]);
+        assert!(report.narrowed.is_empty());
+        assert_eq!(report.removed, vec![upper]);
+        assert!(report.rewired.is_empty());
+        assert_eq!(graph.get_edge(wide).unwrap().channels, 4);
+        assert!(graph.get_edge(lower).is_some());
+        assert_eq!(graph.get_node(bus).unwrap().input_port_count(), 2);
+
+        // 折り返し先が空いていればつなぎ替える
+        graph.remove_edge(lower);
+        graph.resize_bus(bus, 4);
+        let high = graph
+            .add_bundle_edge(bus, PortId::new(3), sink, PortId::new(1), 1)
+            .unwrap();
+        let report = graph.resize_bus(bus, 2).unwrap();
+        assert_eq!(report.rewired, vec![high]);
+        assert_eq!(graph.get_edge(high).unwrap().source_port, PortId::new(1));
+    }
+
+    #[test]
+    fn test_cue_sink_is_processed_last() {
+        let mut graph = AudioGraph::new();
+        let cue = graph.add_node(Box::new(crate::audio::sink::SinkNode::new_stereo(2, "Cue")));
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(crate::audio::bus::BusNode::new_stereo("b", "Bus")));
+        graph.add_edge(src, PortId::new(0), bus, PortId::new(0));
+
+        assert!(!graph.set_cue_sink(Some(bus)));
+        assert!(graph.set_cue_sink(Some(cue)));
+        graph.rebuild_order_if_needed();
+        assert_eq!(graph.processing_order().last(), Some(&cue));
+
+        graph.remove_node(cue);
+        assert_eq!(graph.cue_sink(), None);
+    }
+
+    #[test]
+    fn test_removed_edge_is_silent_until_install() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(crate::audio::bus::BusNode::new_stereo("b", "Bus")));
+        let sink = graph.add_node(Box::new(crate::audio::sink::SinkNode::new_stereo(1, "Out")));
+        graph.add_edge(src, PortId::new(0), bus, PortId::new(0));
+        let out = graph
+            .add_edge(bus, PortId::new(0), sink, PortId::new(0))
+            .unwrap();
+        graph.rebuild_order();
+        assert!(graph.get_edge(out).unwrap().is_audible());
+
+        // 削除してから差し替えまでは古いスケジュールがエッジを持つが、鳴らない
+        assert!(graph.remove_edge(out));
+        assert!(graph.is_dirty());
+        let old = graph.schedule();
+        let removed = old.edges().iter().find(|e| e.id == out).unwrap();
+        assert!(removed.is_detached());
+        assert!(!removed.is_audible());
+
+        let mut topology = Topology::default();
+        topology.reserve();
+        graph.capture_topology(&mut topology);
+        assert!(!graph.is_dirty());
+        assert_eq!(topology.edge_ids().len(), 1);
+        let compiled = topology.compile();
+        assert!(Arc::ptr_eq(&graph.schedule(), &old));
+
+        graph.install(compiled);
+        assert!(!Arc::ptr_eq(&graph.schedule(), &old));
+        assert!(graph.schedule().edges().iter().all(|e| e.id != out));
+        assert_eq!(graph.processing_order().len(), 3);
+    }
+
     #[test]
     fn test_topological_sort() {
         let mut graph = AudioGraph::new();
@@ -422,4 +1355,404 @@ mod tests {
         assert!(src_pos < bus_pos);
         assert!(bus_pos < sink_pos);
     }
+
+    #[test]
+    fn test_bundle_edge_overlap() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(crate::audio::bus::BusNode::new_stereo("b", "Bus")));
+
+        let stereo = graph
+            .add_bundle_edge(src, PortId::new(0), bus, PortId::new(0), 2)
+            .unwrap();
+        assert_eq!(graph.get_edge(stereo).unwrap().channels, 2);
+
+        // Right channel is already carried by the bundle
+        assert!(graph
+            .add_edge(src, PortId::new(1), bus, PortId::new(1))
+            .is_none());
+        // Cross routing L -> R is a different port pair
+        assert!(graph
+            .add_edge(src, PortId::new(0), bus, PortId::new(1))
+            .is_some());
+
+        let edge = graph.get_edge(stereo).unwrap();
+        assert_eq!(edge.target_port_for(1), PortId::new(1));
+        assert!(edge.set_channel_trim(1, 0.5));
+        assert!(!edge.set_channel_trim(2, 0.5));
+        assert_eq!(edge.channel_trims(), vec![1.0, 0.5]);
+    }
+
+    /// 固定レイテンシを持つテスト用ノード
+    struct LatentNode {
+        latency: u32,
+        inputs: Vec<crate::audio::AudioBuffer>,
+        outputs: Vec<crate::audio::AudioBuffer>,
+    }
+
+    impl LatentNode {
+        fn new(latency: u32) -> Self {
+            Self {
+                latency,
+                inputs: vec![crate::audio::AudioBuffer::new()],
+                outputs: vec![crate::audio::AudioBuffer::new()],
+            }
+        }
+    }
+
+    impl AudioNode for LatentNode {
+        fn node_type(&self) -> NodeType {
+            NodeType::Bus
+        }
+        fn label(&self) -> &str {
+            "latent"
+        }
+        fn input_port_count(&self) -> usize {
+            1
+        }
+        fn output_port_count(&self) -> usize {
+            1
+        }
+        fn input_buffer(&self, port: PortId) -> Option<&crate::audio::AudioBuffer> {
+            self.inputs.get(port.index())
+        }
+        fn input_buffer_mut(&mut self, port: PortId) -> Option<&mut crate::audio::AudioBuffer> {
+            self.inputs.get_mut(port.index())
+        }
+        fn output_buffer(&self, port: PortId) -> Option<&crate::audio::AudioBuffer> {
+            self.outputs.get(port.index())
+        }
+        fn output_buffer_mut(&mut self, port: PortId) -> Option<&mut crate::audio::AudioBuffer> {
+            self.outputs.get_mut(port.index())
+        }
+        fn process(&mut self, _frames: usize) {}
+        fn clear_buffers(&mut self, _frames: usize) {}
+        fn latency_samples(&self) -> u32 {
+            self.latency
+        }
+        fn input_peak_levels(&self) -> Vec<f32> {
+            Vec::new()
+        }
+        fn output_peak_levels(&self) -> Vec<f32> {
+            Vec::new()
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
+            self
+        }
+    }
+
+    #[test]
+    fn test_latency_compensation_parallel_paths() {
+        let mut graph = AudioGraph::new();
+
+        // Src -> Latent(128) -> Sink
+        // Src ---------------> Sink (dry path must be delayed by 128)
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let fx = graph.add_node(Box::new(LatentNode::new(128)));
+        let sink = graph.add_node(Box::new(crate::audio::sink::SinkNode::new_stereo(1, "Out")));
+
+        let wet_in = graph
+            .add_edge(src, PortId::new(0), fx, PortId::new(0))
+            .unwrap();
+        let wet_out = graph
+            .add_edge(fx, PortId::new(0), sink, PortId::new(0))
+            .unwrap();
+        let dry = graph
+            .add_edge(src, PortId::new(0), sink, PortId::new(0))
+            .unwrap();
+
+        graph.rebuild_order();
+
+        assert_eq!(graph.get_edge(wet_in).unwrap().compensation_samples(), 0);
+        assert_eq!(graph.get_edge(wet_out).unwrap().compensation_samples(), 0);
+        assert_eq!(graph.get_edge(dry).unwrap().compensation_samples(), 128);
+        assert_eq!(graph.path_latency_to(sink), 128);
+    }
+
+    #[test]
+    fn test_offline_render_sets_live_state_aside() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let fx = graph.add_node(Box::new(LatentNode::new(128)));
+        let sink = graph.add_node(Box::new(crate::audio::sink::SinkNode::new_stereo(1, "Out")));
+        graph.add_edge(src, PortId::new(0), fx, PortId::new(0));
+        graph.add_edge(fx, PortId::new(0), sink, PortId::new(0));
+        let dry = graph
+            .add_edge(src, PortId::new(0), sink, PortId::new(0))
+            .unwrap();
+        graph.rebuild_order();
+
+        let edge = graph.get_edge(dry).unwrap().clone();
+        let mix = |input: f32| {
+            let mut source = crate::audio::AudioBuffer::new();
+            source.clear(64);
+            source.samples_mut()[0] = input;
+            let mut target = crate::audio::AudioBuffer::new();
+            target.clear(64);
+            edge.mix_into(0, &source, &mut target, 1.0, 1.0);
+            target.samples()[0]
+        };
+
+        // ライブ: フェードの途中で、補正ディレイにインパルスが入っている
+        edge.set_gain(0.5);
+        edge.set_applied_gain(0.25);
+        assert_eq!(mix(1.0), 0.0);
+
+        // オフラインは落ち着いた状態から始まり、ライブのインパルスは出てこない
+        let state = graph.take_live_state();
+        assert_eq!(state.schedule().edges().len(), 3);
+        assert_eq!(edge.applied_gain(), 0.5);
+        assert_eq!(edge.compensation_samples(), 128);
+        assert_eq!([mix(0.0), mix(0.0), mix(0.0)], [0.0; 3]);
+
+        // 戻すとライブのランプとディレイが続きから進む
+        graph.restore_live_state(state);
+        assert_eq!(edge.applied_gain(), 0.25);
+        assert_eq!([mix(0.0), mix(0.0)], [0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_pre_plugin_tap_only_from_bus() {
+        let mut graph = AudioGraph::new();
+
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(crate::audio::bus::BusNode::new_stereo("b", "Bus")));
+        let sink = graph.add_node(Box::new(SinkNode::new_stereo(1, "Out")));
+        let send = graph
+            .add_edge(src, PortId::new(0), bus, PortId::new(0))
+            .unwrap();
+        let monitor = graph
+            .add_edge(bus, PortId::new(0), sink, PortId::new(0))
+            .unwrap();
+        graph.rebuild_order();
+
+        assert!(graph.set_edge_tap_point(send, TapPoint::PrePlugin).is_err());
+        assert!(graph.set_edge_tap_point(send, TapPoint::PostPlugin).is_ok());
+        assert!(graph
+            .set_edge_tap_point(monitor, TapPoint::PrePlugin)
+            .is_ok());
+        assert_eq!(
+            graph.get_edge(monitor).unwrap().tap_point(),
+            TapPoint::PrePlugin
+        );
+    }
+
+    #[test]
+    fn test_rebind_device() {
+        let mut graph = AudioGraph::new();
+
+        let prism = graph.add_node(Box::new(SourceNode::new_prism(0, "Prism")));
+        let mic = graph.add_node(Box::new(SourceNode::new_device(7, 0, "Mic")));
+        let out = graph.add_node(Box::new(SinkNode::new_stereo(7, "Out")));
+        let other = graph.add_node(Box::new(SinkNode::new_stereo(8, "Other")));
+
+        assert_eq!(graph.rebind_device(7, 42), 2);
+        assert_eq!(graph.rebind_device(7, 42), 0);
+
+        let sink_device = |graph: &mut AudioGraph, h| {
+            graph
+                .get_node_mut(h)
+                .and_then(|n| n.as_any_mut().downcast_mut::<SinkNode>())
+                .map(|s| s.device_id())
+        };
+        assert_eq!(sink_device(&mut graph, out), Some(42));
+        assert_eq!(sink_device(&mut graph, other), Some(8));
+
+        let source_id = |graph: &mut AudioGraph, h| {
+            graph
+                .get_node_mut(h)
+                .and_then(|n| n.as_any_mut().downcast_mut::<SourceNode>())
+                .map(|s| s.source_id().clone())
+        };
+        assert!(matches!(
+            source_id(&mut graph, mic),
+            Some(SourceId::InputDevice { device_id: 42, .. })
+        ));
+        assert!(matches!(
+            source_id(&mut graph, prism),
+            Some(SourceId::PrismChannel { .. })
+        ));
+    }
+
+    #[test]
+    fn test_retarget_default_sinks() {
+        let mut graph = AudioGraph::new();
+
+        let mut follow = SinkNode::new(SinkId::with_uid(7, 0, 2, None), "Default");
+        follow.set_follow_default(true);
+        let follow = graph.add_node(Box::new(follow));
+        let fixed = graph.add_node(Box::new(SinkNode::new(
+            SinkId::with_uid(7, 0, 2, None),
+            "Fixed",
+        )));
+
+        assert_eq!(
+            graph.retarget_default_sinks(9, Some("uid-9")),
+            vec![(follow, 7)]
+        );
+        assert!(graph.retarget_default_sinks(9, Some("uid-9")).is_empty());
+
+        let sink_id = |graph: &mut AudioGraph, h| {
+            graph
+                .get_node_mut(h)
+                .and_then(|n| n.as_any_mut().downcast_mut::<SinkNode>())
+                .map(|s| s.sink_id().clone())
+        };
+        let moved = sink_id(&mut graph, follow).unwrap();
+        assert_eq!(moved.device_id, 9);
+        assert_eq!(moved.device_uid.as_deref(), Some("uid-9"));
+        assert_eq!(sink_id(&mut graph, fixed).unwrap().device_id, 7);
+    }
+
+    #[test]
+    fn test_retarget_device_sinks() {
+        let mut graph = AudioGraph::new();
+
+        let main = graph.add_node(Box::new(SinkNode::new(
+            SinkId::with_uid(7, 0, 2, None),
+            "Main",
+        )));
+        let other = graph.add_node(Box::new(SinkNode::new(
+            SinkId::with_uid(8, 0, 2, None),
+            "Other",
+        )));
+
+        assert_eq!(graph.retarget_device_sinks(7, 9, Some("uid-9")), vec![main]);
+        assert!(graph.retarget_device_sinks(7, 9, Some("uid-9")).is_empty());
+
+        let device = |graph: &mut AudioGraph, h| {
+            graph
+                .get_node_mut(h)
+                .and_then(|n| n.as_any_mut().downcast_mut::<SinkNode>())
+                .map(|s| s.device_id())
+        };
+        assert_eq!(device(&mut graph, main), Some(9));
+        assert_eq!(device(&mut graph, other), Some(8));
+    }
+
+    #[test]
+    fn test_output_latency_alignment() {
+        let mut graph = AudioGraph::new();
+
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Prism")));
+        let mut wired = SinkNode::new_stereo(1, "Wired");
+        wired.set_device_latency(100);
+        let mut bluetooth = SinkNode::new_stereo(2, "Bluetooth");
+        bluetooth.set_device_latency(6_000);
+        let idle = SinkNode::new_stereo(3, "Idle");
+        let wired = graph.add_node(Box::new(wired));
+        let bluetooth = graph.add_node(Box::new(bluetooth));
+        let idle = graph.add_node(Box::new(idle));
+
+        let to_wired = graph
+            .add_edge(src, PortId::new(0), wired, PortId::new(0))
+            .unwrap();
+        let to_bt = graph
+            .add_edge(src, PortId::new(0), bluetooth, PortId::new(0))
+            .unwrap();
+        graph.rebuild_order_if_needed();
+        assert_eq!(graph.get_edge(to_wired).unwrap().compensation_samples(), 0);
+
+        graph.set_align_output_latency(true);
+        graph.rebuild_order_if_needed();
+        assert_eq!(graph.output_alignment_delay(wired), 5_900);
+        assert_eq!(graph.output_alignment_delay(bluetooth), 0);
+        // Sinks without input are not aligned
+        assert_eq!(graph.output_alignment_delay(idle), 0);
+        assert_eq!(
+            graph.get_edge(to_wired).unwrap().compensation_samples(),
+            5_900
+        );
+        assert_eq!(graph.get_edge(to_bt).unwrap().compensation_samples(), 0);
+
+        graph.set_align_output_latency(false);
+        graph.rebuild_order_if_needed();
+        assert_eq!(graph.get_edge(to_wired).unwrap().compensation_samples(), 0);
+    }
+
+    #[test]
+    fn test_resize_device_sources() {
+        let mut graph = AudioGraph::new();
+
+        let mic = graph.add_node(Box::new(SourceNode::new_device_with_channels(
+            7, 0, "Mic", 4,
+        )));
+        let other = graph.add_node(Box::new(SourceNode::new_device(8, 0, "Other")));
+        let out = graph.add_node(Box::new(SinkNode::new_stereo(9, "Out")));
+        let send = graph
+            .add_edge(mic, PortId::new(3), out, PortId::new(0))
+            .unwrap();
+
+        assert_eq!(graph.resize_device_sources(7, 2), vec![mic]);
+        assert_eq!(graph.get_node(mic).unwrap().output_port_count(), 2);
+        assert_eq!(graph.get_node(other).unwrap().output_port_count(), 2);
+        // The edge from the vanished port stays (silent until the channel returns)
+        assert!(graph.get_edge(send).is_some());
+
+        assert!(graph.resize_device_sources(7, 2).is_empty());
+        assert_eq!(graph.resize_device_sources(7, 8), vec![mic]);
+        assert_eq!(graph.get_node(mic).unwrap().output_port_count(), 4);
+    }
+
+    #[test]
+    fn test_direct_monitor_requires_same_device() {
+        let mut graph = AudioGraph::new();
+
+        let mic = graph.add_node(Box::new(SourceNode::new_device(7, 0, "Mic")));
+        let prism = graph.add_node(Box::new(SourceNode::new_prism(0, "Prism")));
+        let out = graph.add_node(Box::new(SinkNode::new_stereo(7, "Out")));
+        let other = graph.add_node(Box::new(SinkNode::new_stereo(8, "Other")));
+
+        let direct = graph
+            .add_bundle_edge(mic, PortId::new(0), out, PortId::new(0), 2)
+            .unwrap();
+        let cross = graph
+            .add_edge(mic, PortId::new(0), other, PortId::new(0))
+            .unwrap();
+        let virtual_in = graph
+            .add_edge(prism, PortId::new(0), out, PortId::new(0))
+            .unwrap();
+
+        assert_eq!(
+            graph.set_edge_monitor_mode(direct, MonitorMode::Direct),
+            Ok(7)
+        );
+        assert!(graph.get_edge(direct).unwrap().is_direct());
+        assert!(graph.has_direct_monitor(7));
+        assert!(!graph.has_direct_monitor(8));
+
+        assert!(graph
+            .set_edge_monitor_mode(cross, MonitorMode::Direct)
+            .is_err());
+        assert!(graph
+            .set_edge_monitor_mode(virtual_in, MonitorMode::Direct)
+            .is_err());
+
+        graph
+            .set_edge_monitor_mode(direct, MonitorMode::Buffered)
+            .unwrap();
+        assert_eq!(
+            graph.get_edge(direct).unwrap().monitor_mode(),
+            MonitorMode::Buffered
+        );
+        assert!(!graph.has_direct_monitor(7));
+    }
+
+    #[test]
+    fn test_has_path() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let bus = graph.add_node(Box::new(crate::audio::bus::BusNode::new_stereo("b", "Bus")));
+        let sink = graph.add_node(Box::new(crate::audio::sink::SinkNode::new_stereo(1, "Out")));
+
+        graph.add_edge(src, PortId::new(0), bus, PortId::new(0));
+        graph.add_edge(bus, PortId::new(0), sink, PortId::new(0));
+
+        assert!(graph.has_path(src, sink));
+        assert!(graph.has_path(bus, bus));
+        assert!(!graph.has_path(sink, src));
+    }
 }
@@ -0,0 +1,343 @@
+//! Loopback Nodes - Virtual sink -> source pair
+//!
+//! `LoopbackSinkNode` に入った音声が対応する `LoopbackSourceNode` から出てくる。
+//! 処理済みの音声を別のブランチへ戻したり、Prism 向けシンクへ送って
+//! 録音アプリに渡したりを、外部ケーブルなしで行える。
+//!
+//! 2 つのノードは内部リングバッファを共有する。両者の間にエッジはないため、
+//! 処理順でシンクが先なら同じブロック、ソースが先なら 1 ブロック遅れで出てくる
+//! （フィードバック経路でもサイクルにならない）。
+
+use super::buffer::AudioBuffer;
+use super::node::{AudioNode, NodeType, PortId};
+use super::MAX_FRAMES;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::sync::Arc;
+
+/// リングバッファ長（フレーム数）。1 ブロック遅れ + 1 ブロック分の余裕
+const RING_FRAMES: usize = MAX_FRAMES * 2;
+
+/// Ring buffer shared by a loopback pair
+///
+/// 書き込み/読み出しはどちらもグラフ処理中（オーディオスレッド）なので
+/// ロックは競合しない。取れなければ無音にする。
+pub struct LoopbackRing {
+    state: Mutex<RingState>,
+}
+
+struct RingState {
+    /// チャンネルごとのリング
+    channels: Vec<Box<[f32]>>,
+    read_pos: usize,
+    fill: usize,
+}
+
+impl LoopbackRing {
+    pub fn new(channels: usize) -> Self {
+        Self {
+            state: Mutex::new(RingState {
+                channels: (0..channels.max(1))
+                    .map(|_| vec![0.0f32; RING_FRAMES].into_boxed_slice())
+                    .collect(),
+                read_pos: 0,
+                fill: 0,
+            }),
+        }
+    }
+
+    /// Push one block (extra frames beyond capacity are dropped)
+    fn write(&self, inputs: &[AudioBuffer], frames: usize) {
+        let Some(mut state) = self.state.try_lock() else {
+            return;
+        };
+        let frames = frames.min(RING_FRAMES - state.fill);
+        let write_pos = (state.read_pos + state.fill) % RING_FRAMES;
+        for (ring, input) in state.channels.iter_mut().zip(inputs) {
+            let samples = input.samples();
+            for i in 0..frames {
+                ring[(write_pos + i) % RING_FRAMES] = samples.get(i).copied().unwrap_or(0.0);
+            }
+        }
+        state.fill += frames;
+    }
+
+    /// Pop one block; outputs stay silent until a full block is buffered
+    fn read(&self, outputs: &mut [AudioBuffer], frames: usize) {
+        let Some(mut state) = self.state.try_lock() else {
+            return;
+        };
+        if state.fill < frames {
+            return;
+        }
+        let read_pos = state.read_pos;
+        for (ring, output) in state.channels.iter().zip(outputs.iter_mut()) {
+            let samples = output.samples_mut();
+            let n = frames.min(samples.len());
+            for (i, s) in samples[..n].iter_mut().enumerate() {
+                *s = ring[(read_pos + i) % RING_FRAMES];
+            }
+        }
+        state.read_pos = (read_pos + frames) % RING_FRAMES;
+        state.fill -= frames;
+    }
+
+    /// Frames currently buffered
+    pub fn fill(&self) -> usize {
+        self.state.lock().fill
+    }
+}
+
+/// Create a connected sink/source pair sharing one ring
+pub fn loopback_pair(
+    loopback_id: impl Into<String>,
+    label: impl Into<String>,
+    port_count: usize,
+) -> (LoopbackSinkNode, LoopbackSourceNode) {
+    let loopback_id = loopback_id.into();
+    let label = label.into();
+    let port_count = port_count.max(1);
+    let ring = Arc::new(LoopbackRing::new(port_count));
+    (
+        LoopbackSinkNode {
+            loopback_id: loopback_id.clone(),
+            label: label.clone(),
+            input_buffers: (0..port_count).map(|_| AudioBuffer::new()).collect(),
+            ring: ring.clone(),
+        },
+        LoopbackSourceNode {
+            loopback_id,
+            label,
+            output_buffers: (0..port_count).map(|_| AudioBuffer::new()).collect(),
+            ring,
+        },
+    )
+}
+
+/// Loopback の入口（シンク側）
+pub struct LoopbackSinkNode {
+    loopback_id: String,
+    label: String,
+    input_buffers: Vec<AudioBuffer>,
+    ring: Arc<LoopbackRing>,
+}
+
+/// Loopback の出口（ソース側）
+pub struct LoopbackSourceNode {
+    loopback_id: String,
+    label: String,
+    output_buffers: Vec<AudioBuffer>,
+    ring: Arc<LoopbackRing>,
+}
+
+impl LoopbackSinkNode {
+    /// ID shared with the paired source
+    pub fn loopback_id(&self) -> &str {
+        &self.loopback_id
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+}
+
+impl LoopbackSourceNode {
+    /// ID shared with the paired sink
+    pub fn loopback_id(&self) -> &str {
+        &self.loopback_id
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+}
+
+impl AudioNode for LoopbackSinkNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Sink
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        self.input_buffers.len()
+    }
+
+    fn output_port_count(&self) -> usize {
+        0
+    }
+
+    fn input_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.input_buffers.get(port.index())
+    }
+
+    fn input_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.input_buffers.get_mut(port.index())
+    }
+
+    fn output_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn output_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn process(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.set_valid_frames(frames);
+            buf.update_meters();
+        }
+        self.ring.write(&self.input_buffers, frames);
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn is_live_io(&self) -> bool {
+        true
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        self.input_buffers.iter().map(|b| b.cached_peak()).collect()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl AudioNode for LoopbackSourceNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Source
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        0
+    }
+
+    fn output_port_count(&self) -> usize {
+        self.output_buffers.len()
+    }
+
+    fn input_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn input_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn output_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.output_buffers.get(port.index())
+    }
+
+    fn output_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.output_buffers.get_mut(port.index())
+    }
+
+    fn process(&mut self, frames: usize) {
+        for buf in &mut self.output_buffers {
+            buf.set_valid_frames(frames);
+        }
+        self.ring.read(&mut self.output_buffers, frames);
+        for buf in &mut self.output_buffers {
+            buf.update_meters();
+        }
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.output_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn is_live_io(&self) -> bool {
+        true
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        self.output_buffers
+            .iter()
+            .map(|b| b.cached_peak())
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_input(sink: &mut LoopbackSinkNode, value: f32, frames: usize) {
+        sink.clear_buffers(frames);
+        for port in 0..sink.input_port_count() {
+            let buf = sink.input_buffer_mut(PortId::new(port as u8)).unwrap();
+            buf.samples_mut()[..frames].fill(value + port as f32);
+        }
+    }
+
+    fn output(source: &LoopbackSourceNode, port: u8) -> f32 {
+        source.output_buffer(PortId::new(port)).unwrap().samples()[0]
+    }
+
+    #[test]
+    fn test_loopback_sink_before_source_is_same_block() {
+        let (mut sink, mut source) = loopback_pair("lb", "Loop", 2);
+
+        fill_input(&mut sink, 0.25, 64);
+        sink.process(64);
+        source.clear_buffers(64);
+        source.process(64);
+
+        assert_eq!(output(&source, 0), 0.25);
+        assert_eq!(output(&source, 1), 1.25);
+        assert_eq!(sink.ring.fill(), 0);
+    }
+
+    #[test]
+    fn test_loopback_source_before_sink_is_one_block_late() {
+        let (mut sink, mut source) = loopback_pair("lb", "Loop", 1);
+
+        for (block, value) in [0.1f32, 0.2, 0.3].into_iter().enumerate() {
+            source.clear_buffers(64);
+            source.process(64);
+            let expected = if block == 0 { 0.0 } else { value - 0.1 };
+            assert!((output(&source, 0) - expected).abs() < 1e-6);
+
+            fill_input(&mut sink, value, 64);
+            sink.process(64);
+        }
+        assert_eq!(sink.ring.fill(), 64);
+    }
+}
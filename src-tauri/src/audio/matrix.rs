@@ -0,0 +1,330 @@
+//! Matrix Node - N×M gain matrix (downmix / upmix)
+//!
+//! 各出力ポートは全入力ポートの重み付き和。5.1 → ステレオのダウンミックスや
+//! モノラル → ステレオの分配を 1 ノードで行える（モノラルエッジを大量に張らずに済む）。
+//!
+//! 係数は Atomic で保持し、制御スレッドからロックなしで変更できる。
+//! 処理は入力をスクラッチに詰めて vDSP の行列積 1 回で行う。
+
+use super::buffer::AudioBuffer;
+use super::node::{AudioNode, NodeType, PortId};
+use super::MAX_FRAMES;
+use crate::vdsp::VDsp;
+use std::any::Any;
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Maximum inputs/outputs of a matrix node
+pub const MAX_MATRIX_CHANNELS: usize = 16;
+
+/// Maximum coefficient magnitude (linear, ~ +12 dB)
+const MAX_COEFFICIENT: f32 = 4.0;
+
+/// 係数のプリセット
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatrixPreset {
+    /// Input i to output i
+    #[default]
+    Identity,
+    /// Every input to every output at unity (mono → stereo split)
+    Split,
+    /// ITU-R BS.775 5.1 (L R C LFE Ls Rs) → stereo, LFE dropped
+    Downmix51,
+}
+
+impl MatrixPreset {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "identity" => Some(Self::Identity),
+            "split" | "mono_to_stereo" => Some(Self::Split),
+            "downmix_5_1" | "5.1_to_stereo" => Some(Self::Downmix51),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Identity => "identity",
+            Self::Split => "split",
+            Self::Downmix51 => "downmix_5_1",
+        }
+    }
+
+    /// (inputs, outputs) a preset is meant for; None = any size
+    pub fn shape(self) -> Option<(usize, usize)> {
+        match self {
+            Self::Identity | Self::Split => None,
+            Self::Downmix51 => Some((6, 2)),
+        }
+    }
+
+    /// Coefficients (`outputs` rows of `inputs`)
+    pub fn coefficients(self, inputs: usize, outputs: usize) -> Vec<Vec<f32>> {
+        (0..outputs)
+            .map(|out| {
+                (0..inputs)
+                    .map(|input| match self {
+                        Self::Identity => (input == out) as u8 as f32,
+                        Self::Split => 1.0,
+                        Self::Downmix51 => match (out, input) {
+                            (0, 0) | (1, 1) => 1.0,
+                            (_, 2) => FRAC_1_SQRT_2,
+                            (0, 4) | (1, 5) => FRAC_1_SQRT_2,
+                            _ => 0.0,
+                        },
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// ダウンミックス/アップミックス用の行列ノード
+pub struct MatrixNode {
+    /// 行列の識別子
+    matrix_id: String,
+    /// 表示ラベル
+    label: String,
+    input_buffers: Vec<AudioBuffer>,
+    output_buffers: Vec<AudioBuffer>,
+    /// 係数（出力ごとの行、row-major: out * inputs + in）
+    coefficient_bits: Vec<AtomicU32>,
+    /// 以下はオーディオスレッドのみ（構築時に確保）
+    coefficients: Vec<f32>,
+    /// 入力を詰めた行列（inputs × frames）
+    packed_in: Vec<f32>,
+    /// 出力行列（outputs × frames）
+    packed_out: Vec<f32>,
+}
+
+impl MatrixNode {
+    /// Create a matrix node (ports are clamped to 1..=MAX_MATRIX_CHANNELS)
+    pub fn new(
+        matrix_id: impl Into<String>,
+        label: impl Into<String>,
+        inputs: usize,
+        outputs: usize,
+        preset: MatrixPreset,
+    ) -> Self {
+        let inputs = inputs.clamp(1, MAX_MATRIX_CHANNELS);
+        let outputs = outputs.clamp(1, MAX_MATRIX_CHANNELS);
+        let node = Self {
+            matrix_id: matrix_id.into(),
+            label: label.into(),
+            input_buffers: (0..inputs).map(|_| AudioBuffer::new()).collect(),
+            output_buffers: (0..outputs).map(|_| AudioBuffer::new()).collect(),
+            coefficient_bits: (0..inputs * outputs)
+                .map(|_| AtomicU32::new(0.0f32.to_bits()))
+                .collect(),
+            coefficients: vec![0.0; inputs * outputs],
+            packed_in: vec![0.0; inputs * MAX_FRAMES],
+            packed_out: vec![0.0; outputs * MAX_FRAMES],
+        };
+        // Sizes always match here
+        let _ = node.set_coefficients(&preset.coefficients(inputs, outputs));
+        node
+    }
+
+    /// Get the matrix ID
+    pub fn matrix_id(&self) -> &str {
+        &self.matrix_id
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Current coefficients (`outputs` rows of `inputs`)
+    pub fn coefficients(&self) -> Vec<Vec<f32>> {
+        let inputs = self.input_buffers.len();
+        self.coefficient_bits
+            .chunks(inputs)
+            .map(|row| {
+                row.iter()
+                    .map(|c| f32::from_bits(c.load(Ordering::Relaxed)))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Replace every coefficient (`outputs` rows of `inputs`, linear)
+    ///
+    /// Non-finite values become 0; magnitudes are clamped to ±4.0.
+    pub fn set_coefficients(&self, rows: &[Vec<f32>]) -> Result<(), String> {
+        let inputs = self.input_buffers.len();
+        let outputs = self.output_buffers.len();
+        if rows.len() != outputs || rows.iter().any(|r| r.len() != inputs) {
+            return Err(format!(
+                "Matrix is {} outputs × {} inputs; got {} rows of {:?}",
+                outputs,
+                inputs,
+                rows.len(),
+                rows.iter().map(|r| r.len()).collect::<Vec<_>>()
+            ));
+        }
+        for (slot, &c) in self.coefficient_bits.iter().zip(rows.iter().flatten()) {
+            let c = if c.is_finite() { c } else { 0.0 };
+            slot.store(
+                c.clamp(-MAX_COEFFICIENT, MAX_COEFFICIENT).to_bits(),
+                Ordering::Relaxed,
+            );
+        }
+        Ok(())
+    }
+}
+
+impl AudioNode for MatrixNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Bus
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        self.input_buffers.len()
+    }
+
+    fn output_port_count(&self) -> usize {
+        self.output_buffers.len()
+    }
+
+    fn input_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.input_buffers.get(port.index())
+    }
+
+    fn input_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.input_buffers.get_mut(port.index())
+    }
+
+    fn output_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.output_buffers.get(port.index())
+    }
+
+    fn output_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.output_buffers.get_mut(port.index())
+    }
+
+    fn process(&mut self, frames: usize) {
+        let frames = frames.min(MAX_FRAMES);
+        let inputs = self.input_buffers.len();
+        let outputs = self.output_buffers.len();
+
+        for (c, slot) in self.coefficients.iter_mut().zip(&self.coefficient_bits) {
+            *c = f32::from_bits(slot.load(Ordering::Relaxed));
+        }
+        for (i, buf) in self.input_buffers.iter_mut().enumerate() {
+            buf.update_peak();
+            let row = &mut self.packed_in[i * frames..(i + 1) * frames];
+            let valid = buf.valid_frames().min(frames);
+            row[..valid].copy_from_slice(&buf.samples()[..valid]);
+            VDsp::clear(&mut row[valid..]);
+        }
+
+        // (outputs × inputs) · (inputs × frames) = (outputs × frames)
+        VDsp::matrix_multiply(
+            &self.coefficients,
+            &self.packed_in[..inputs * frames],
+            &mut self.packed_out[..outputs * frames],
+            outputs,
+            frames,
+            inputs,
+        );
+
+        for (o, buf) in self.output_buffers.iter_mut().enumerate() {
+            buf.write_samples(&self.packed_out[o * frames..(o + 1) * frames]);
+            buf.update_meters();
+        }
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.clear(frames);
+        }
+        for buf in &mut self.output_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        self.input_buffers.iter().map(|b| b.cached_peak()).collect()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        self.output_buffers
+            .iter()
+            .map(|b| b.cached_peak())
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(node: &mut MatrixNode, inputs: &[f32]) -> Vec<f32> {
+        node.clear_buffers(1);
+        for (i, &v) in inputs.iter().enumerate() {
+            node.input_buffer_mut(PortId::new(i as u8))
+                .unwrap()
+                .samples_mut()[0] = v;
+        }
+        node.process(1);
+        (0..node.output_port_count())
+            .map(|o| node.output_buffer(PortId::new(o as u8)).unwrap().samples()[0])
+            .collect()
+    }
+
+    #[test]
+    fn test_downmix_5_1() {
+        let mut node = MatrixNode::new("m", "Downmix", 6, 2, MatrixPreset::Downmix51);
+        // L R C LFE Ls Rs
+        let out = run(&mut node, &[0.1, 0.2, 0.4, 1.0, 0.3, 0.0]);
+        assert!((out[0] - (0.1 + (0.4 + 0.3) * FRAC_1_SQRT_2)).abs() < 1e-6);
+        assert!((out[1] - (0.2 + 0.4 * FRAC_1_SQRT_2)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_split_and_custom_coefficients() {
+        let mut node = MatrixNode::new("m", "Split", 1, 2, MatrixPreset::Split);
+        assert_eq!(run(&mut node, &[0.5]), vec![0.5, 0.5]);
+
+        node.set_coefficients(&[vec![1.0], vec![-0.5]]).unwrap();
+        assert_eq!(run(&mut node, &[0.5]), vec![0.5, -0.25]);
+        assert_eq!(node.coefficients(), vec![vec![1.0], vec![-0.5]]);
+    }
+
+    #[test]
+    fn test_coefficient_shape_is_checked() {
+        let node = MatrixNode::new("m", "M", 2, 2, MatrixPreset::Identity);
+        assert_eq!(node.coefficients(), vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert!(node.set_coefficients(&[vec![1.0, 0.0]]).is_err());
+        assert!(node.set_coefficients(&[vec![1.0], vec![1.0]]).is_err());
+
+        node.set_coefficients(&[vec![f32::NAN, 9.0], vec![0.0, 1.0]])
+            .unwrap();
+        assert_eq!(node.coefficients()[0], vec![0.0, 4.0]);
+    }
+
+    #[test]
+    fn test_preset_parse_round_trip() {
+        for preset in [
+            MatrixPreset::Identity,
+            MatrixPreset::Split,
+            MatrixPreset::Downmix51,
+        ] {
+            assert_eq!(MatrixPreset::parse(preset.as_str()), Some(preset));
+        }
+        assert_eq!(MatrixPreset::parse("bogus"), None);
+    }
+}
@@ -0,0 +1,188 @@
+//! Meter history - short per-node / per-edge peak history
+//!
+//! オーディオスレッドのメーター更新（ブロックごと）を 60 Hz に間引いて 2 秒分保持する。
+//! 間引きは区間内の最大値を取るので、UI のポーリングが遅くてもトランジェントを取りこぼさない。
+//! 値はバリスティクス適用前の生ピーク。
+
+use super::edge::EdgeId;
+use super::meters::GraphMeters;
+use super::node::NodeHandle;
+use std::collections::HashMap;
+
+/// 履歴のフレームレート (Hz)
+pub const HISTORY_RATE_HZ: f32 = 60.0;
+
+/// 保持するフレーム数（2 秒分）
+pub const HISTORY_LEN: usize = 120;
+
+/// Which meter a history belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HistoryKey {
+    /// Loudest output port (input ports for nodes without outputs)
+    Node(NodeHandle),
+    /// Post-gain level
+    Edge(EdgeId),
+}
+
+struct HistoryRing {
+    values: Box<[f32]>,
+    pos: usize,
+    len: usize,
+    /// 現在の区間の最大値
+    pending: f32,
+    /// 最後に値が来たフレーム
+    last_seen: u64,
+}
+
+impl HistoryRing {
+    fn new() -> Self {
+        Self {
+            values: vec![0.0; HISTORY_LEN].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            pending: 0.0,
+            last_seen: 0,
+        }
+    }
+
+    fn commit(&mut self) {
+        self.values[self.pos] = self.pending;
+        self.pos = (self.pos + 1) % HISTORY_LEN;
+        self.len = (self.len + 1).min(HISTORY_LEN);
+        self.pending = 0.0;
+    }
+
+    /// Newest `max` values, oldest first
+    fn snapshot(&self, max: usize) -> Vec<f32> {
+        let n = self.len.min(max);
+        let start = (self.pos + HISTORY_LEN - n) % HISTORY_LEN;
+        (0..n)
+            .map(|i| self.values[(start + i) % HISTORY_LEN])
+            .collect()
+    }
+}
+
+/// Peak history of every node and edge
+pub struct MeterHistory {
+    rings: HashMap<HistoryKey, HistoryRing>,
+    /// 現在の区間の経過時間 (秒)
+    elapsed: f32,
+    /// 確定したフレーム数
+    frame_index: u64,
+}
+
+impl Default for MeterHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MeterHistory {
+    pub fn new() -> Self {
+        Self {
+            rings: HashMap::new(),
+            elapsed: 0.0,
+            frame_index: 0,
+        }
+    }
+
+    /// Number of committed frames so far (for aligning successive reads)
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Accumulate one block of raw meters (audio thread); `dt` is the block length in seconds
+    pub fn record(&mut self, meters: &GraphMeters, dt: f32) {
+        let frame = self.frame_index;
+        for node in &meters.nodes {
+            let ports = if node.outputs.is_empty() {
+                &node.inputs
+            } else {
+                &node.outputs
+            };
+            let peak = ports.iter().fold(0.0f32, |a, p| a.max(p.peak));
+            self.accumulate(HistoryKey::Node(node.handle), peak, frame);
+        }
+        for edge in &meters.edges {
+            self.accumulate(HistoryKey::Edge(edge.edge_id), edge.post_gain.peak, frame);
+        }
+
+        self.elapsed += dt;
+        let period = 1.0 / HISTORY_RATE_HZ;
+        if self.elapsed >= period {
+            // 長いブロックでも 1 フレームずつしか進めない（値は区間最大なので欠けない）
+            self.elapsed = (self.elapsed - period).min(period);
+            for ring in self.rings.values_mut() {
+                ring.commit();
+            }
+            self.frame_index += 1;
+            // 1 フレーム以上値が来ていないノード/エッジは削除済み
+            self.rings.retain(|_, r| r.last_seen >= frame);
+        }
+    }
+
+    fn accumulate(&mut self, key: HistoryKey, peak: f32, frame: u64) {
+        let ring = self.rings.entry(key).or_insert_with(HistoryRing::new);
+        ring.pending = ring.pending.max(peak);
+        ring.last_seen = frame;
+    }
+
+    /// Newest `max_frames` peaks of a meter, oldest first
+    pub fn get(&self, key: HistoryKey, max_frames: usize) -> Option<Vec<f32>> {
+        self.rings.get(&key).map(|r| r.snapshot(max_frames))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::meters::{EdgeMeter, NodeMeter, PortMeter};
+
+    fn meters(node_peak: f32, edge_peak: f32) -> GraphMeters {
+        let mut meters = GraphMeters::new();
+        let mut node = NodeMeter::new(NodeHandle::from_raw(3));
+        node.outputs.push(PortMeter::new(node_peak * 0.5));
+        node.outputs.push(PortMeter::new(node_peak));
+        meters.nodes.push(node);
+        let mut edge = EdgeMeter::new(EdgeId::new(7));
+        edge.post_gain = PortMeter::new(edge_peak);
+        meters.edges.push(edge);
+        meters
+    }
+
+    #[test]
+    fn test_history_keeps_transients_between_frames() {
+        let mut history = MeterHistory::new();
+        // 5 ms blocks: a single loud block inside the first 1/60 s frame
+        for i in 0..4 {
+            let peak = if i == 1 { 0.9 } else { 0.1 };
+            history.record(&meters(peak, 0.2), 0.005);
+        }
+        assert_eq!(history.frame_index(), 1);
+        let node = history.get(HistoryKey::Node(NodeHandle::from_raw(3)), 10);
+        assert_eq!(node, Some(vec![0.9]));
+        let edge = history.get(HistoryKey::Edge(EdgeId::new(7)), 10);
+        assert_eq!(edge, Some(vec![0.2]));
+    }
+
+    #[test]
+    fn test_history_ring_wraps_and_drops_removed_meters() {
+        let mut history = MeterHistory::new();
+        for i in 0..(HISTORY_LEN + 30) {
+            history.record(&meters(i as f32, 0.0), 1.0 / HISTORY_RATE_HZ);
+        }
+        let node = history
+            .get(HistoryKey::Node(NodeHandle::from_raw(3)), usize::MAX)
+            .unwrap();
+        assert_eq!(node.len(), HISTORY_LEN);
+        assert_eq!(node[0], 30.0);
+        assert_eq!(node[HISTORY_LEN - 1], (HISTORY_LEN + 29) as f32);
+
+        // The edge disappears from the graph
+        let mut without_edge = meters(0.0, 0.0);
+        without_edge.edges.clear();
+        history.record(&without_edge, 1.0 / HISTORY_RATE_HZ);
+        history.record(&without_edge, 1.0 / HISTORY_RATE_HZ);
+        assert!(history.get(HistoryKey::Edge(EdgeId::new(7)), 1).is_none());
+    }
+}
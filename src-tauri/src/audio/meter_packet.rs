@@ -0,0 +1,170 @@
+//! Meter packet - Compact binary encoding of GraphMeters
+//!
+//! JSON の `GraphMetersDto` はノード数が多いとポーリングのたびに大きな文字列になるので、
+//! フロントエンド向けに固定長レコードを詰めたバイナリ表現を用意する。
+//! すべてリトルエンディアン・4 バイト境界で、JS 側は `DataView` / `Float32Array` で読める。
+//!
+//! ```text
+//! header   magic "SPMT" | version u16 | reserved u16 | timestamp u64 | nodes u32 | edges u32
+//! node     handle u32 | inputs u16 | outputs u16 | port * (inputs + outputs)
+//! edge     edge_id u32 | port
+//! port     peak f32 | rms f32 | hold f32 | clip f32   (rms / hold は無効なら NaN, clip は 0 / 1)
+//! ```
+
+use super::edge::EdgeId;
+use super::meters::{GraphMeters, PortMeter};
+use super::node::NodeHandle;
+use std::collections::HashSet;
+
+/// Packet magic ("SPMT")
+pub const METER_PACKET_MAGIC: [u8; 4] = *b"SPMT";
+
+/// Format version (bump when the layout changes)
+pub const METER_PACKET_VERSION: u16 = 1;
+
+/// Header size in bytes
+pub const METER_HEADER_BYTES: usize = 24;
+
+/// Bytes per port record
+pub const METER_PORT_BYTES: usize = 16;
+
+/// Which meters go into a packet (None = all)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MeterFilter {
+    pub nodes: Option<HashSet<NodeHandle>>,
+    pub edges: Option<HashSet<EdgeId>>,
+}
+
+impl MeterFilter {
+    /// Everything
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    pub fn new(nodes: Option<&[u32]>, edges: Option<&[u32]>) -> Self {
+        Self {
+            nodes: nodes.map(|h| h.iter().map(|&h| NodeHandle::from_raw(h)).collect()),
+            edges: edges.map(|e| e.iter().map(|&e| EdgeId::from(e)).collect()),
+        }
+    }
+
+    fn wants_node(&self, handle: NodeHandle) -> bool {
+        self.nodes.as_ref().is_none_or(|n| n.contains(&handle))
+    }
+
+    fn wants_edge(&self, edge: EdgeId) -> bool {
+        self.edges.as_ref().is_none_or(|e| e.contains(&edge))
+    }
+}
+
+fn put_port(out: &mut Vec<u8>, meter: &PortMeter) {
+    out.extend_from_slice(&meter.peak.to_le_bytes());
+    out.extend_from_slice(&meter.rms.unwrap_or(f32::NAN).to_le_bytes());
+    out.extend_from_slice(&meter.hold.unwrap_or(f32::NAN).to_le_bytes());
+    out.extend_from_slice(&(if meter.clip { 1.0f32 } else { 0.0 }).to_le_bytes());
+}
+
+/// Encode the meters that pass `filter` into `out` (cleared first; reuse it to avoid allocating)
+pub fn encode(meters: &GraphMeters, filter: &MeterFilter, out: &mut Vec<u8>) {
+    out.clear();
+    let nodes: Vec<_> = meters
+        .nodes
+        .iter()
+        .filter(|n| filter.wants_node(n.handle))
+        .collect();
+    let edges: Vec<_> = meters
+        .edges
+        .iter()
+        .filter(|e| filter.wants_edge(e.edge_id))
+        .collect();
+
+    out.extend_from_slice(&METER_PACKET_MAGIC);
+    out.extend_from_slice(&METER_PACKET_VERSION.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&meters.timestamp.to_le_bytes());
+    out.extend_from_slice(&(nodes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(edges.len() as u32).to_le_bytes());
+
+    for node in nodes {
+        out.extend_from_slice(&node.handle.raw().to_le_bytes());
+        out.extend_from_slice(&(node.inputs.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(node.outputs.len() as u16).to_le_bytes());
+        for port in node.inputs.iter().chain(&node.outputs) {
+            put_port(out, port);
+        }
+    }
+    for edge in edges {
+        out.extend_from_slice(&edge.edge_id.raw().to_le_bytes());
+        put_port(out, &edge.post_gain);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::meters::{EdgeMeter, NodeMeter};
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn f32_at(bytes: &[u8], at: usize) -> f32 {
+        f32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    fn meters() -> GraphMeters {
+        let mut meters = GraphMeters::new();
+        meters.timestamp = 42;
+        for raw in [1, 2] {
+            let mut node = NodeMeter::new(NodeHandle::from_raw(raw));
+            node.inputs.push(PortMeter::new(0.25));
+            node.outputs.push(PortMeter::with_rms(0.5, 0.1));
+            meters.nodes.push(node);
+        }
+        let mut edge = EdgeMeter::new(EdgeId::from(7));
+        edge.post_gain.clip = true;
+        meters.edges.push(edge);
+        meters
+    }
+
+    #[test]
+    fn test_encode_layout() {
+        let mut out = Vec::new();
+        encode(&meters(), &MeterFilter::all(), &mut out);
+
+        assert_eq!(&out[..4], b"SPMT");
+        assert_eq!(u64::from_le_bytes(out[8..16].try_into().unwrap()), 42);
+        assert_eq!(u32_at(&out, 16), 2);
+        assert_eq!(u32_at(&out, 20), 1);
+        let node_bytes = 8 + 2 * METER_PORT_BYTES;
+        assert_eq!(
+            out.len(),
+            METER_HEADER_BYTES + 2 * node_bytes + 4 + METER_PORT_BYTES
+        );
+
+        let node = METER_HEADER_BYTES;
+        assert_eq!(u32_at(&out, node), 1);
+        assert_eq!(f32_at(&out, node + 8), 0.25);
+        assert!(f32_at(&out, node + 12).is_nan());
+        // 出力ポートは入力の後
+        assert_eq!(f32_at(&out, node + 8 + METER_PORT_BYTES), 0.5);
+        assert_eq!(f32_at(&out, node + 12 + METER_PORT_BYTES), 0.1);
+
+        let edge = METER_HEADER_BYTES + 2 * node_bytes;
+        assert_eq!(u32_at(&out, edge), 7);
+        assert_eq!(f32_at(&out, edge + 16), 1.0);
+    }
+
+    #[test]
+    fn test_filter_selects_nodes_and_edges() {
+        let mut out = Vec::new();
+        encode(
+            &meters(),
+            &MeterFilter::new(Some(&[2]), Some(&[])),
+            &mut out,
+        );
+        assert_eq!(u32_at(&out, 16), 1);
+        assert_eq!(u32_at(&out, 20), 0);
+        assert_eq!(u32_at(&out, METER_HEADER_BYTES), 2);
+    }
+}
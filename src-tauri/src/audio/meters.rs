@@ -1,25 +1,234 @@
 //! Metering types
+//!
+//! メーター値はオーディオスレッドで [`GraphMeters::apply_ballistics`] を通してから
+//! 公開される。既定（`instant`）は従来どおりブロックごとの瞬時値。
 
 use super::edge::EdgeId;
 use super::node::NodeHandle;
+use std::collections::HashMap;
+
+/// クリップ判定のしきい値 (0 dBFS)
+pub const CLIP_LEVEL: f32 = 1.0;
 
 /// Port meter (single channel)
 #[derive(Debug, Clone, Default)]
 pub struct PortMeter {
     pub peak: f32,
     pub rms: Option<f32>,
+    /// Held peak (None when peak hold is off)
+    pub hold: Option<f32>,
+    /// Clip indicator
+    pub clip: bool,
 }
 
 impl PortMeter {
     pub fn new(peak: f32) -> Self {
-        Self { peak, rms: None }
+        Self {
+            peak,
+            ..Default::default()
+        }
     }
 
     pub fn with_rms(peak: f32, rms: f32) -> Self {
         Self {
             peak,
             rms: Some(rms),
+            ..Default::default()
+        }
+    }
+}
+
+/// Meter ballistics (shared by every meter in the app)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeterBallistics {
+    /// Rise time constant (0 = instant)
+    pub attack_ms: f32,
+    /// Fall time constant (0 = instant)
+    pub release_ms: f32,
+    /// Peak hold duration (0 = no hold value)
+    pub peak_hold_ms: f32,
+    /// Keep the clip indicator until cleared (otherwise it follows the peak hold)
+    pub clip_hold: bool,
+}
+
+impl Default for MeterBallistics {
+    fn default() -> Self {
+        Self::INSTANT
+    }
+}
+
+impl MeterBallistics {
+    /// Raw per-block values
+    pub const INSTANT: Self = Self {
+        attack_ms: 0.0,
+        release_ms: 0.0,
+        peak_hold_ms: 0.0,
+        clip_hold: false,
+    };
+
+    /// Sample peak meter with a slow fall (DAW-style)
+    pub const DIGITAL: Self = Self {
+        attack_ms: 0.0,
+        release_ms: 650.0,
+        peak_hold_ms: 1500.0,
+        clip_hold: true,
+    };
+
+    /// Quasi-peak programme meter (IEC 60268-10 type I: 5 ms integration, 20 dB fall in 1.7 s)
+    pub const PPM: Self = Self {
+        attack_ms: 5.0,
+        release_ms: 740.0,
+        peak_hold_ms: 0.0,
+        clip_hold: false,
+    };
+
+    /// VU meter (reaches 99% in ~300 ms both ways)
+    pub const VU: Self = Self {
+        attack_ms: 65.0,
+        release_ms: 65.0,
+        peak_hold_ms: 0.0,
+        clip_hold: false,
+    };
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "instant" | "raw" => Some(Self::INSTANT),
+            "digital" | "peak" => Some(Self::DIGITAL),
+            "ppm" => Some(Self::PPM),
+            "vu" => Some(Self::VU),
+            _ => None,
+        }
+    }
+
+    pub fn clamped(self) -> Self {
+        let ms = |v: f32, max: f32| {
+            if v.is_finite() {
+                v.clamp(0.0, max)
+            } else {
+                0.0
+            }
+        };
+        Self {
+            attack_ms: ms(self.attack_ms, 1000.0),
+            release_ms: ms(self.release_ms, 10_000.0),
+            peak_hold_ms: ms(self.peak_hold_ms, 60_000.0),
+            clip_hold: self.clip_hold,
+        }
+    }
+}
+
+/// 平滑化係数（dt 秒進んだときの前回値の重み）
+fn smoothing_coeff(ms: f32, dt: f32) -> f32 {
+    if ms <= 0.0 {
+        0.0
+    } else {
+        (-dt / (ms * 0.001)).exp()
+    }
+}
+
+/// Which meter a ballistics state belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeterKey {
+    Input(NodeHandle, u8),
+    Output(NodeHandle, u8),
+    Edge(EdgeId),
+}
+
+/// 1 メーター分の状態
+#[derive(Debug, Clone, Default)]
+struct BallisticsChannel {
+    peak: f32,
+    rms: f32,
+    hold: f32,
+    /// ホールド残り時間 (秒)
+    hold_left: f32,
+    clip: bool,
+    clip_left: f32,
+    /// 最後に更新された世代
+    generation: u64,
+}
+
+impl BallisticsChannel {
+    fn apply(&mut self, meter: &mut PortMeter, config: &MeterBallistics, dt: f32) {
+        let raw = meter.peak;
+        let follow = |current: f32, target: f32| {
+            let ms = if target > current {
+                config.attack_ms
+            } else {
+                config.release_ms
+            };
+            let c = smoothing_coeff(ms, dt);
+            target + c * (current - target)
+        };
+
+        self.peak = follow(self.peak, raw);
+        meter.peak = self.peak;
+        if let Some(rms) = meter.rms {
+            self.rms = follow(self.rms, rms);
+            meter.rms = Some(self.rms);
+        }
+
+        let hold_secs = config.peak_hold_ms * 0.001;
+        if hold_secs > 0.0 {
+            if raw >= self.hold || self.hold_left <= 0.0 {
+                self.hold = raw.max(self.peak);
+                self.hold_left = hold_secs;
+            } else {
+                self.hold_left -= dt;
+            }
+            meter.hold = Some(self.hold);
+        }
+
+        if raw >= CLIP_LEVEL {
+            self.clip = true;
+            // ホールドなしでも一瞬で消えないよう最低 1 秒は表示する
+            self.clip_left = hold_secs.max(1.0);
+        } else if self.clip && !config.clip_hold {
+            self.clip_left -= dt;
+            if self.clip_left <= 0.0 {
+                self.clip = false;
+            }
+        }
+        meter.clip = self.clip;
+    }
+}
+
+/// Ballistics state of every meter (audio thread only)
+#[derive(Debug, Default)]
+pub struct MeterBallisticsState {
+    channels: HashMap<MeterKey, BallisticsChannel>,
+    /// 更新ごとに進める世代（消えたノード/エッジの状態を捨てるため）
+    generation: u64,
+    /// 今回の更新で触れたメーター数
+    visited: usize,
+}
+
+impl MeterBallisticsState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clear every latched clip indicator
+    pub fn reset_clips(&mut self) {
+        for channel in self.channels.values_mut() {
+            channel.clip = false;
+            channel.clip_left = 0.0;
+        }
+    }
+
+    fn apply(&mut self, key: MeterKey, meter: &mut PortMeter, config: &MeterBallistics, dt: f32) {
+        let channel = self.channels.entry(key).or_default();
+        channel.generation = self.generation;
+        channel.apply(meter, config, dt);
+        self.visited += 1;
+    }
+
+    fn collect_garbage(&mut self) {
+        if self.visited != self.channels.len() {
+            let generation = self.generation;
+            self.channels.retain(|_, c| c.generation == generation);
         }
+        self.visited = 0;
     }
 }
 
@@ -69,4 +278,99 @@ impl GraphMeters {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Apply ballistics to raw block values; `dt` is the block length in seconds
+    pub fn apply_ballistics(
+        &mut self,
+        state: &mut MeterBallisticsState,
+        config: &MeterBallistics,
+        dt: f32,
+    ) {
+        state.generation = state.generation.wrapping_add(1);
+        for node in &mut self.nodes {
+            for (i, meter) in node.inputs.iter_mut().enumerate() {
+                state.apply(MeterKey::Input(node.handle, i as u8), meter, config, dt);
+            }
+            for (i, meter) in node.outputs.iter_mut().enumerate() {
+                state.apply(MeterKey::Output(node.handle, i as u8), meter, config, dt);
+            }
+        }
+        for edge in &mut self.edges {
+            state.apply(
+                MeterKey::Edge(edge.edge_id),
+                &mut edge.post_gain,
+                config,
+                dt,
+            );
+        }
+        state.collect_garbage();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meters_with_peak(peak: f32) -> GraphMeters {
+        let mut meters = GraphMeters::new();
+        let mut node = NodeMeter::new(NodeHandle::from_raw(1));
+        node.outputs.push(PortMeter::new(peak));
+        meters.nodes.push(node);
+        meters
+    }
+
+    #[test]
+    fn test_instant_ballistics_is_passthrough() {
+        let mut state = MeterBallisticsState::new();
+        let mut meters = meters_with_peak(0.5);
+        meters.apply_ballistics(&mut state, &MeterBallistics::INSTANT, 0.01);
+        let out = &meters.nodes[0].outputs[0];
+        assert_eq!(out.peak, 0.5);
+        assert_eq!(out.hold, None);
+        assert!(!out.clip);
+    }
+
+    #[test]
+    fn test_release_hold_and_clip_latch() {
+        let config = MeterBallistics {
+            attack_ms: 0.0,
+            release_ms: 100.0,
+            peak_hold_ms: 500.0,
+            clip_hold: true,
+        };
+        let mut state = MeterBallisticsState::new();
+        let mut meters = meters_with_peak(1.2);
+        meters.apply_ballistics(&mut state, &config, 0.01);
+        assert_eq!(meters.nodes[0].outputs[0].peak, 1.2);
+        assert!(meters.nodes[0].outputs[0].clip);
+
+        // 100 ms of silence: one time constant of release, hold still up
+        let mut last = PortMeter::default();
+        for _ in 0..10 {
+            let mut meters = meters_with_peak(0.0);
+            meters.apply_ballistics(&mut state, &config, 0.01);
+            last = meters.nodes[0].outputs[0].clone();
+        }
+        assert!(
+            (last.peak - 1.2 * (-1.0f32).exp()).abs() < 0.01,
+            "{}",
+            last.peak
+        );
+        assert_eq!(last.hold, Some(1.2));
+        assert!(last.clip);
+
+        // After the hold time the hold value drops to the meter
+        for _ in 0..50 {
+            let mut meters = meters_with_peak(0.0);
+            meters.apply_ballistics(&mut state, &config, 0.01);
+            last = meters.nodes[0].outputs[0].clone();
+        }
+        assert!(last.hold.unwrap() < 0.1);
+        assert!(last.clip, "clip stays latched");
+
+        state.reset_clips();
+        let mut meters = meters_with_peak(0.0);
+        meters.apply_ballistics(&mut state, &config, 0.01);
+        assert!(!meters.nodes[0].outputs[0].clip);
+    }
 }
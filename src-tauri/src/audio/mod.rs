@@ -9,21 +9,53 @@ mod graph;
 mod meters;
 mod node;
 
+pub mod analyzer;
+pub mod automation;
+pub mod bounce;
 pub mod bus;
+pub mod command_queue;
+pub mod crossfade;
+pub mod drift;
+pub mod dsp;
+pub mod dsp_load;
+pub mod ducking;
+pub mod edge_link;
+pub mod freeze;
+pub mod generator;
+pub mod loopback;
+pub mod matrix;
+pub mod meter_history;
+pub mod meter_packet;
+pub mod net;
 pub mod output;
+pub mod param_mailbox;
+pub mod plugin_source;
 pub mod processor;
+pub mod reclaim;
+pub mod record;
+pub mod sample_rate;
+pub mod schedule;
+pub mod session_stats;
 pub mod sink;
+pub mod solo;
 pub mod source;
+pub mod taper;
+pub mod topology;
+pub mod watchdog;
+pub mod xrun;
 
 pub use buffer::AudioBuffer;
-pub use edge::{Edge, EdgeId};
-pub use graph::AudioGraph;
-pub use meters::{EdgeMeter, GraphMeters, NodeMeter, PortMeter};
+pub use edge::{CueTap, Edge, EdgeId, MonitorMode, PanLaw, SumLaw, TapPoint, MAX_BUNDLE_CHANNELS};
+pub use graph::{AudioGraph, BusResize};
+pub use meters::{
+    EdgeMeter, GraphMeters, MeterBallistics, MeterBallisticsState, NodeMeter, PortMeter,
+};
 pub use node::{AudioNode, NodeHandle, NodeType, PortId};
 pub use processor::{get_graph_processor, GraphProcessor};
+pub use sample_rate::engine_sample_rate;
 
 /// Maximum frames per audio callback
 pub const MAX_FRAMES: usize = 4096;
 
-/// Default sample rate
+/// Default sample rate (実行時の値は `engine_sample_rate()`)
 pub const SAMPLE_RATE: f64 = 48000.0;
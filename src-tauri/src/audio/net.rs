@@ -0,0 +1,990 @@
+//! Network Audio Nodes - RTP/UDP send and receive (AES67-lite)
+//!
+//! `NetSendSinkNode` は入力をインターリーブして SPSC リングへ送り、送信スレッドが
+//! パケット時間ごとに RTP (L24 big-endian, PT 96) のデータグラムにして UDP で送る。
+//! `NetReceiveSourceNode` は受信スレッドが RTP を検証してリングへ積み、オーディオスレッドの
+//! ジッターバッファが目標レイテンシ分たまってから再生する。
+//!
+//! AES67 のうち PTP 同期と SAP/SDP は扱わない（"lite"）。両端は同じサンプルレートで
+//! 動かす前提で、クロックのずれはジッターバッファの再バッファ / 読み飛ばしで吸収する。
+
+use super::buffer::AudioBuffer;
+use super::node::{AudioNode, NodeType, PortId};
+use super::MAX_FRAMES;
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::any::Any;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// RTP dynamic payload type used for L24
+pub const RTP_PAYLOAD_TYPE: u8 = 96;
+
+/// Fixed RTP header length (no CSRCs / extension)
+const RTP_HEADER_BYTES: usize = 12;
+
+/// L24 sample size
+const BYTES_PER_SAMPLE: usize = 3;
+
+/// UDP payload that fits one Ethernet frame without fragmentation
+const MAX_DATAGRAM_BYTES: usize = 1460;
+
+/// Maximum channels per stream
+pub const MAX_NET_CHANNELS: usize = 8;
+
+/// AES67 の既定パケット時間 (1ms)
+pub const DEFAULT_PACKET_TIME_US: u32 = 1000;
+pub const MIN_PACKET_TIME_US: u32 = 125;
+pub const MAX_PACKET_TIME_US: u32 = 4000;
+
+/// Default receive latency
+pub const DEFAULT_JITTER_MS: u32 = 20;
+pub const MIN_JITTER_MS: u32 = 1;
+pub const MAX_JITTER_MS: u32 = 500;
+
+/// スレッドとオーディオスレッドの間のリング長（フレーム数）。~0.68s at 48kHz
+const NET_RING_FRAMES: usize = 32768;
+
+/// これより大きい欠落は送信側の再起動とみなして無音で埋めない
+const MAX_GAP_PACKETS: u16 = 64;
+
+/// Receive socket timeout (stop flag polling interval)
+const RECV_TIMEOUT: Duration = Duration::from_millis(100);
+
+// =============================================================================
+// RTP / L24
+// =============================================================================
+
+/// RTP header fields used by the nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtpHeader {
+    pub payload_type: u8,
+    pub sequence: u16,
+    pub timestamp: u32,
+    pub ssrc: u32,
+}
+
+/// Frames per packet for a packet time
+pub fn packet_frames(sample_rate: f64, packet_time_us: u32) -> usize {
+    ((sample_rate * packet_time_us as f64 / 1_000_000.0).round() as usize).max(1)
+}
+
+/// Write a complete RTP packet (header + L24 payload of interleaved samples)
+pub fn write_packet(packet: &mut Vec<u8>, header: RtpHeader, samples: &[f32]) {
+    packet.clear();
+    packet.push(0x80); // V=2, P=0, X=0, CC=0
+    packet.push(header.payload_type & 0x7f);
+    packet.extend_from_slice(&header.sequence.to_be_bytes());
+    packet.extend_from_slice(&header.timestamp.to_be_bytes());
+    packet.extend_from_slice(&header.ssrc.to_be_bytes());
+    for s in samples {
+        let v = (s.clamp(-1.0, 1.0) * 8_388_607.0) as i32;
+        packet.extend_from_slice(&v.to_be_bytes()[1..]);
+    }
+}
+
+/// Parse an RTP packet, returning the header and the payload
+///
+/// CSRC・拡張ヘッダー・パディングは読み飛ばす。
+pub fn parse_packet(packet: &[u8]) -> Option<(RtpHeader, &[u8])> {
+    if packet.len() < RTP_HEADER_BYTES || packet[0] >> 6 != 2 {
+        return None;
+    }
+    let csrc_count = (packet[0] & 0x0f) as usize;
+    let mut start = RTP_HEADER_BYTES + csrc_count * 4;
+    if packet[0] & 0x10 != 0 {
+        let ext = packet.get(start..start + 4)?;
+        start += 4 + u16::from_be_bytes([ext[2], ext[3]]) as usize * 4;
+    }
+    let mut end = packet.len();
+    if packet[0] & 0x20 != 0 {
+        end = end.checked_sub(*packet.last()? as usize)?;
+    }
+    if start > end {
+        return None;
+    }
+    let header = RtpHeader {
+        payload_type: packet[1] & 0x7f,
+        sequence: u16::from_be_bytes([packet[2], packet[3]]),
+        timestamp: u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]),
+        ssrc: u32::from_be_bytes([packet[8], packet[9], packet[10], packet[11]]),
+    };
+    Some((header, &packet[start..end]))
+}
+
+/// Decode an L24 payload into samples; returns the number of samples written
+pub fn decode_l24(payload: &[u8], out: &mut [f32]) -> usize {
+    let mut count = 0;
+    for (chunk, s) in payload.chunks_exact(BYTES_PER_SAMPLE).zip(out.iter_mut()) {
+        let v = i32::from_be_bytes([chunk[0], chunk[1], chunk[2], 0]) >> 8;
+        *s = v as f32 / 8_388_608.0;
+        count += 1;
+    }
+    count
+}
+
+/// Result of checking a packet's sequence number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SeqCheck {
+    /// 期待どおり
+    InOrder,
+    /// 手前の n パケットが欠落
+    Lost(u16),
+    /// 既に再生位置を過ぎたパケット（重複・並べ替え）
+    Late,
+}
+
+/// Tracks RTP sequence numbers of one stream
+#[derive(Debug, Default)]
+struct SequenceTracker {
+    expected: Option<u16>,
+}
+
+impl SequenceTracker {
+    fn check(&mut self, sequence: u16) -> SeqCheck {
+        let Some(expected) = self.expected else {
+            self.expected = Some(sequence.wrapping_add(1));
+            return SeqCheck::InOrder;
+        };
+        let delta = sequence.wrapping_sub(expected);
+        if delta >= 0x8000 {
+            return SeqCheck::Late;
+        }
+        self.expected = Some(sequence.wrapping_add(1));
+        if delta == 0 {
+            SeqCheck::InOrder
+        } else {
+            SeqCheck::Lost(delta)
+        }
+    }
+
+    fn reset(&mut self) {
+        self.expected = None;
+    }
+}
+
+// =============================================================================
+// Shared state
+// =============================================================================
+
+/// ネットワークノードの統計（API 用スナップショット）
+#[derive(Debug, Clone, Default)]
+pub struct NetStatus {
+    pub packets: u64,
+    pub lost_packets: u64,
+    pub late_packets: u64,
+    pub bad_packets: u64,
+    pub dropped_frames: u64,
+    pub underruns: u64,
+    pub buffered_frames: usize,
+    pub error: Option<String>,
+}
+
+/// オーディオスレッドとネットワークスレッドで共有する状態
+#[derive(Default)]
+struct NetShared {
+    stop: AtomicBool,
+    packets: AtomicU64,
+    lost_packets: AtomicU64,
+    late_packets: AtomicU64,
+    bad_packets: AtomicU64,
+    dropped_frames: AtomicU64,
+    underruns: AtomicU64,
+    error: parking_lot::Mutex<Option<String>>,
+}
+
+impl NetShared {
+    fn snapshot(&self, buffered_frames: usize) -> NetStatus {
+        NetStatus {
+            packets: self.packets.load(Ordering::Relaxed),
+            lost_packets: self.lost_packets.load(Ordering::Relaxed),
+            late_packets: self.late_packets.load(Ordering::Relaxed),
+            bad_packets: self.bad_packets.load(Ordering::Relaxed),
+            dropped_frames: self.dropped_frames.load(Ordering::Relaxed),
+            underruns: self.underruns.load(Ordering::Relaxed),
+            buffered_frames,
+            error: self.error.lock().clone(),
+        }
+    }
+
+    /// Record a socket error (logged once per distinct message)
+    fn set_error(&self, context: &str, message: String) {
+        let mut error = self.error.lock();
+        if error.as_deref() != Some(message.as_str()) {
+            log_warn!("[NetAudio] {}: {}", context, message);
+            *error = Some(message);
+        }
+    }
+}
+
+/// Resolve "host" + port (IPv4 preferred)
+fn resolve(address: &str, port: u16) -> Result<SocketAddr, String> {
+    let addrs: Vec<SocketAddr> = (address, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", address, e))?
+        .collect();
+    addrs
+        .iter()
+        .find(|a| a.is_ipv4())
+        .or_else(|| addrs.first())
+        .copied()
+        .ok_or_else(|| format!("No address for {}", address))
+}
+
+fn spawn_thread(name: String, f: impl FnOnce() + Send + 'static) -> Result<JoinHandle<()>, String> {
+    std::thread::Builder::new()
+        .name(name)
+        .spawn(f)
+        .map_err(|e| format!("Failed to spawn network thread: {}", e))
+}
+
+fn stop_thread(shared: &NetShared, thread: &mut Option<JoinHandle<()>>) {
+    shared.stop.store(true, Ordering::Release);
+    if let Some(thread) = thread.take() {
+        let _ = thread.join();
+    }
+}
+
+// =============================================================================
+// Send
+// =============================================================================
+
+/// ネットワーク送信ノード（シンク）
+///
+/// 入力ポートをそのままストリームのチャンネルにする。宛先はユニキャストでも
+/// マルチキャスト（239.x.x.x など）でもよい。
+pub struct NetSendSinkNode {
+    net_id: String,
+    label: String,
+    input_buffers: Vec<AudioBuffer>,
+    address: String,
+    port: u16,
+    packet_time_us: u32,
+    producer: HeapProd<f32>,
+    /// インターリーブ用スクラッチ（事前確保）
+    scratch: Vec<f32>,
+    shared: Arc<NetShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+// SAFETY: producer は &mut self 経由（オーディオスレッドの process）でのみ触る。
+// &NetSendSinkNode からは共有参照で到達できないため、Sync にしても競合しない。
+unsafe impl Sync for NetSendSinkNode {}
+
+impl NetSendSinkNode {
+    /// Open the socket and start the sender thread
+    pub fn new(
+        net_id: impl Into<String>,
+        label: impl Into<String>,
+        port_count: usize,
+        address: &str,
+        port: u16,
+        packet_time_us: u32,
+        sample_rate: f64,
+    ) -> Result<Self, String> {
+        let net_id = net_id.into();
+        let channels = port_count.clamp(1, MAX_NET_CHANNELS);
+        if !(MIN_PACKET_TIME_US..=MAX_PACKET_TIME_US).contains(&packet_time_us) {
+            return Err(format!(
+                "Packet time must be {}..={} us",
+                MIN_PACKET_TIME_US, MAX_PACKET_TIME_US
+            ));
+        }
+        let frames_per_packet = packet_frames(sample_rate, packet_time_us);
+        let payload = frames_per_packet * channels * BYTES_PER_SAMPLE;
+        if RTP_HEADER_BYTES + payload > MAX_DATAGRAM_BYTES {
+            return Err(format!(
+                "{} ch x {} frames does not fit one packet; use a shorter packet time",
+                channels, frames_per_packet
+            ));
+        }
+
+        let destination = resolve(address, port)?;
+        let local: SocketAddr = if destination.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local).map_err(|e| format!("Failed to open socket: {}", e))?;
+        if destination.ip().is_multicast() && destination.is_ipv4() {
+            // AES67 はルーターを越えない前提だが、サブネット内の L3 スイッチ分は許す
+            let _ = socket.set_multicast_ttl_v4(16);
+        }
+        socket
+            .connect(destination)
+            .map_err(|e| format!("Failed to connect to {}: {}", destination, e))?;
+
+        let (producer, consumer) = HeapRb::<f32>::new(NET_RING_FRAMES * channels).split();
+        let shared = Arc::new(NetShared::default());
+        let thread_shared = shared.clone();
+        let thread = spawn_thread(format!("net-send-{}", net_id), move || {
+            sender_thread(socket, consumer, thread_shared, channels, frames_per_packet)
+        })?;
+
+        log_info!(
+            "[NetAudio] Sending {} ({} ch, {} us packets) -> {}",
+            net_id,
+            channels,
+            packet_time_us,
+            destination
+        );
+
+        Ok(Self {
+            net_id,
+            label: label.into(),
+            input_buffers: (0..channels).map(|_| AudioBuffer::new()).collect(),
+            address: address.to_string(),
+            port,
+            packet_time_us,
+            producer,
+            scratch: vec![0.0; MAX_FRAMES * channels],
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Get the network node ID
+    pub fn net_id(&self) -> &str {
+        &self.net_id
+    }
+
+    /// Destination host (as configured)
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Destination UDP port
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Packet time in microseconds
+    pub fn packet_time_us(&self) -> u32 {
+        self.packet_time_us
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Stream statistics
+    pub fn status(&self) -> NetStatus {
+        self.shared
+            .snapshot(self.producer.occupied_len() / self.input_buffers.len())
+    }
+}
+
+impl Drop for NetSendSinkNode {
+    fn drop(&mut self) {
+        stop_thread(&self.shared, &mut self.thread);
+    }
+}
+
+fn sender_thread(
+    socket: UdpSocket,
+    mut consumer: HeapCons<f32>,
+    shared: Arc<NetShared>,
+    channels: usize,
+    frames_per_packet: usize,
+) {
+    let mut samples = vec![0.0f32; frames_per_packet * channels];
+    let mut packet = Vec::with_capacity(RTP_HEADER_BYTES + samples.len() * BYTES_PER_SAMPLE);
+    // SSRC・シーケンス・タイムスタンプの初期値はランダムにする（RFC 3550）
+    let seed = uuid::Uuid::new_v4().as_u128();
+    let mut header = RtpHeader {
+        payload_type: RTP_PAYLOAD_TYPE,
+        sequence: seed as u16,
+        timestamp: (seed >> 32) as u32,
+        ssrc: (seed >> 64) as u32,
+    };
+
+    while !shared.stop.load(Ordering::Acquire) {
+        if consumer.occupied_len() < samples.len() {
+            // オーディオスレッドはブロック単位で積むので、パケット 1 つ分たまるまで待つ
+            std::thread::sleep(Duration::from_micros(250));
+            continue;
+        }
+        consumer.pop_slice(&mut samples);
+        write_packet(&mut packet, header, &samples);
+        match socket.send(&packet) {
+            Ok(_) => {
+                shared.packets.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                shared
+                    .dropped_frames
+                    .fetch_add(frames_per_packet as u64, Ordering::Relaxed);
+                shared.set_error("Send failed", e.to_string());
+            }
+        }
+        header.sequence = header.sequence.wrapping_add(1);
+        header.timestamp = header.timestamp.wrapping_add(frames_per_packet as u32);
+    }
+}
+
+// =============================================================================
+// Receive
+// =============================================================================
+
+/// Jitter buffer read by the audio thread
+struct JitterBuffer {
+    consumer: HeapCons<f32>,
+    channels: usize,
+    /// 再生開始に必要なフレーム数（= 受信レイテンシ）
+    target_frames: usize,
+    playing: bool,
+    /// デインターリーブ用スクラッチ（事前確保）
+    scratch: Vec<f32>,
+}
+
+impl JitterBuffer {
+    fn new(consumer: HeapCons<f32>, channels: usize, target_frames: usize) -> Self {
+        Self {
+            consumer,
+            channels,
+            target_frames: target_frames.clamp(1, NET_RING_FRAMES / 2),
+            playing: false,
+            scratch: vec![0.0; MAX_FRAMES * channels],
+        }
+    }
+
+    fn buffered_frames(&self) -> usize {
+        self.consumer.occupied_len() / self.channels
+    }
+
+    /// Fill one block of outputs (silent while buffering)
+    fn read(&mut self, outputs: &mut [AudioBuffer], frames: usize, shared: &NetShared) {
+        let frames = frames.min(MAX_FRAMES);
+        let available = self.buffered_frames();
+        if !self.playing {
+            if available < self.target_frames.max(frames) {
+                return;
+            }
+            self.playing = true;
+        }
+        if available < frames {
+            // 枯渇したら目標量までためなおす
+            self.playing = false;
+            shared.underruns.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if available > self.target_frames * 2 + frames {
+            // 送信側のクロックが速い分は目標量まで読み飛ばす
+            let excess = available - self.target_frames - frames;
+            self.consumer.skip(excess * self.channels);
+            shared
+                .dropped_frames
+                .fetch_add(excess as u64, Ordering::Relaxed);
+        }
+
+        let needed = frames * self.channels;
+        self.consumer.pop_slice(&mut self.scratch[..needed]);
+        for (ch, output) in outputs.iter_mut().enumerate() {
+            let samples = output.samples_mut();
+            let n = frames.min(samples.len());
+            for (i, s) in samples[..n].iter_mut().enumerate() {
+                *s = self.scratch[i * self.channels + ch];
+            }
+        }
+    }
+}
+
+/// ネットワーク受信ノード（ソース）
+///
+/// UDP ポートで待ち受け、`address` がマルチキャストならそのグループに参加する。
+/// 最初に届いたストリーム（SSRC）を再生し、送信元が変わったらそちらに切り替える。
+pub struct NetReceiveSourceNode {
+    net_id: String,
+    label: String,
+    output_buffers: Vec<AudioBuffer>,
+    address: String,
+    port: u16,
+    jitter_ms: u32,
+    jitter: JitterBuffer,
+    shared: Arc<NetShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+// SAFETY: jitter（consumer）は &mut self 経由（オーディオスレッドの process）でのみ
+// 消費する。&self からは occupied_len の読み取りだけで、これは SPSC で安全。
+unsafe impl Sync for NetReceiveSourceNode {}
+
+impl NetReceiveSourceNode {
+    /// Bind the port and start the receiver thread
+    ///
+    /// `address` は待ち受けるローカルアドレスかマルチキャストグループ（空なら全インターフェイス）。
+    pub fn new(
+        net_id: impl Into<String>,
+        label: impl Into<String>,
+        port_count: usize,
+        address: &str,
+        port: u16,
+        jitter_ms: u32,
+        sample_rate: f64,
+    ) -> Result<Self, String> {
+        let net_id = net_id.into();
+        let channels = port_count.clamp(1, MAX_NET_CHANNELS);
+        if !(MIN_JITTER_MS..=MAX_JITTER_MS).contains(&jitter_ms) {
+            return Err(format!(
+                "Jitter buffer must be {}..={} ms",
+                MIN_JITTER_MS, MAX_JITTER_MS
+            ));
+        }
+
+        let ip: IpAddr = if address.trim().is_empty() {
+            Ipv4Addr::UNSPECIFIED.into()
+        } else {
+            address
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid listen address: {}", address))?
+        };
+        let socket = match ip {
+            IpAddr::V4(group) if group.is_multicast() => {
+                let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port))
+                    .map_err(|e| format!("Failed to bind port {}: {}", port, e))?;
+                socket
+                    .join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)
+                    .map_err(|e| format!("Failed to join {}: {}", group, e))?;
+                socket
+            }
+            IpAddr::V6(group) if group.is_multicast() => {
+                let socket = UdpSocket::bind((Ipv6Addr::UNSPECIFIED, port))
+                    .map_err(|e| format!("Failed to bind port {}: {}", port, e))?;
+                socket
+                    .join_multicast_v6(&group, 0)
+                    .map_err(|e| format!("Failed to join {}: {}", group, e))?;
+                socket
+            }
+            ip => UdpSocket::bind((ip, port))
+                .map_err(|e| format!("Failed to bind {}:{}: {}", ip, port, e))?,
+        };
+        socket
+            .set_read_timeout(Some(RECV_TIMEOUT))
+            .map_err(|e| format!("Failed to configure socket: {}", e))?;
+
+        let (producer, consumer) = HeapRb::<f32>::new(NET_RING_FRAMES * channels).split();
+        let shared = Arc::new(NetShared::default());
+        let thread_shared = shared.clone();
+        let thread = spawn_thread(format!("net-recv-{}", net_id), move || {
+            receiver_thread(socket, producer, thread_shared, channels)
+        })?;
+
+        let target_frames = (sample_rate * jitter_ms as f64 / 1000.0).round() as usize;
+        log_info!(
+            "[NetAudio] Receiving {} ({} ch, {} ms buffer) on {}:{}",
+            net_id,
+            channels,
+            jitter_ms,
+            ip,
+            port
+        );
+
+        Ok(Self {
+            net_id,
+            label: label.into(),
+            output_buffers: (0..channels).map(|_| AudioBuffer::new()).collect(),
+            address: address.trim().to_string(),
+            port,
+            jitter_ms,
+            jitter: JitterBuffer::new(consumer, channels, target_frames),
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// Get the network node ID
+    pub fn net_id(&self) -> &str {
+        &self.net_id
+    }
+
+    /// Listen address or multicast group (empty = any)
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Listen UDP port
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Jitter buffer target in milliseconds
+    pub fn jitter_ms(&self) -> u32 {
+        self.jitter_ms
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Stream statistics
+    pub fn status(&self) -> NetStatus {
+        self.shared.snapshot(self.jitter.buffered_frames())
+    }
+}
+
+impl Drop for NetReceiveSourceNode {
+    fn drop(&mut self) {
+        stop_thread(&self.shared, &mut self.thread);
+    }
+}
+
+fn receiver_thread(
+    socket: UdpSocket,
+    mut producer: HeapProd<f32>,
+    shared: Arc<NetShared>,
+    channels: usize,
+) {
+    let mut datagram = [0u8; 2048];
+    let mut samples = vec![0.0f32; datagram.len() / BYTES_PER_SAMPLE];
+    let silence = vec![0.0f32; samples.len()];
+    let mut tracker = SequenceTracker::default();
+    let mut current_ssrc: Option<u32> = None;
+
+    while !shared.stop.load(Ordering::Acquire) {
+        let len = match socket.recv(&mut datagram) {
+            Ok(len) => len,
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(e) => {
+                shared.set_error("Receive failed", e.to_string());
+                std::thread::sleep(RECV_TIMEOUT);
+                continue;
+            }
+        };
+
+        let Some((header, payload)) = parse_packet(&datagram[..len]) else {
+            shared.bad_packets.fetch_add(1, Ordering::Relaxed);
+            continue;
+        };
+        let frame_bytes = channels * BYTES_PER_SAMPLE;
+        if payload.is_empty() || payload.len() % frame_bytes != 0 {
+            // チャンネル数が合わないストリーム
+            shared.bad_packets.fetch_add(1, Ordering::Relaxed);
+            continue;
+        }
+        if current_ssrc != Some(header.ssrc) {
+            if current_ssrc.is_some() {
+                log_info!("[NetAudio] Stream source changed to {:08x}", header.ssrc);
+            }
+            current_ssrc = Some(header.ssrc);
+            tracker.reset();
+        }
+
+        let frames = payload.len() / frame_bytes;
+        match tracker.check(header.sequence) {
+            SeqCheck::InOrder => {}
+            SeqCheck::Late => {
+                shared.late_packets.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            SeqCheck::Lost(count) => {
+                shared
+                    .lost_packets
+                    .fetch_add(count as u64, Ordering::Relaxed);
+                if count <= MAX_GAP_PACKETS {
+                    // 欠落分を無音で埋めてタイミングを保つ
+                    let mut remaining = count as usize * frames * channels;
+                    while remaining > 0 && producer.vacant_len() > 0 {
+                        let n = remaining.min(silence.len()).min(producer.vacant_len());
+                        producer.push_slice(&silence[..n]);
+                        remaining -= n;
+                    }
+                }
+            }
+        }
+
+        let count = decode_l24(payload, &mut samples);
+        if producer.vacant_len() < count {
+            shared
+                .dropped_frames
+                .fetch_add(frames as u64, Ordering::Relaxed);
+            continue;
+        }
+        producer.push_slice(&samples[..count]);
+        shared.packets.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// =============================================================================
+// AudioNode
+// =============================================================================
+
+impl AudioNode for NetSendSinkNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Sink
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        self.input_buffers.len()
+    }
+
+    fn output_port_count(&self) -> usize {
+        0
+    }
+
+    fn input_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.input_buffers.get(port.index())
+    }
+
+    fn input_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.input_buffers.get_mut(port.index())
+    }
+
+    fn output_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn output_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn process(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.set_valid_frames(frames);
+            buf.update_meters();
+        }
+
+        // インターリーブして送信スレッドへ（アロケーションなし）
+        let channels = self.input_buffers.len();
+        let frames = frames.min(MAX_FRAMES);
+        let needed = frames * channels;
+        if self.producer.vacant_len() < needed {
+            self.shared
+                .dropped_frames
+                .fetch_add(frames as u64, Ordering::Relaxed);
+            return;
+        }
+        for (ch, buf) in self.input_buffers.iter().enumerate() {
+            let samples = buf.samples();
+            for i in 0..frames {
+                self.scratch[i * channels + ch] = samples.get(i).copied().unwrap_or(0.0);
+            }
+        }
+        self.producer.push_slice(&self.scratch[..needed]);
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn is_live_io(&self) -> bool {
+        true
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        self.input_buffers.iter().map(|b| b.cached_peak()).collect()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl AudioNode for NetReceiveSourceNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Source
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        0
+    }
+
+    fn output_port_count(&self) -> usize {
+        self.output_buffers.len()
+    }
+
+    fn input_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn input_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn output_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.output_buffers.get(port.index())
+    }
+
+    fn output_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.output_buffers.get_mut(port.index())
+    }
+
+    fn process(&mut self, frames: usize) {
+        for buf in &mut self.output_buffers {
+            buf.set_valid_frames(frames);
+        }
+        self.jitter
+            .read(&mut self.output_buffers, frames, &self.shared);
+        for buf in &mut self.output_buffers {
+            buf.update_meters();
+        }
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.output_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn is_live_io(&self) -> bool {
+        true
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        self.output_buffers
+            .iter()
+            .map(|b| b.cached_peak())
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtp_round_trip() {
+        let header = RtpHeader {
+            payload_type: RTP_PAYLOAD_TYPE,
+            sequence: 0xfffe,
+            timestamp: 123_456,
+            ssrc: 0xdead_beef,
+        };
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 0.25];
+        let mut packet = Vec::new();
+        write_packet(&mut packet, header, &samples);
+        assert_eq!(packet.len(), RTP_HEADER_BYTES + samples.len() * 3);
+
+        let (parsed, payload) = parse_packet(&packet).unwrap();
+        assert_eq!(parsed, header);
+        let mut decoded = [0.0f32; 6];
+        assert_eq!(decode_l24(payload, &mut decoded), 6);
+        for (a, b) in samples.iter().zip(decoded) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_non_rtp() {
+        assert!(parse_packet(&[0u8; 4]).is_none());
+        assert!(parse_packet(&[0u8; 16]).is_none()); // version 0
+    }
+
+    #[test]
+    fn test_sequence_tracker() {
+        let mut tracker = SequenceTracker::default();
+        assert_eq!(tracker.check(65534), SeqCheck::InOrder);
+        assert_eq!(tracker.check(65535), SeqCheck::InOrder);
+        // 0xffff -> 2 で 0, 1 が欠落（ラップアラウンド）
+        assert_eq!(tracker.check(2), SeqCheck::Lost(2));
+        assert_eq!(tracker.check(1), SeqCheck::Late);
+        assert_eq!(tracker.check(3), SeqCheck::InOrder);
+    }
+
+    #[test]
+    fn test_jitter_buffer_waits_for_target_and_rebuffers() {
+        let (mut producer, consumer) = HeapRb::<f32>::new(1024).split();
+        let shared = NetShared::default();
+        let mut jitter = JitterBuffer::new(consumer, 1, 96);
+        let mut outputs = vec![AudioBuffer::new()];
+
+        // 目標量に届くまでは無音で、消費もしない
+        producer.push_slice(&[0.5; 64]);
+        outputs[0].clear(64);
+        jitter.read(&mut outputs, 64, &shared);
+        assert_eq!(outputs[0].samples()[0], 0.0);
+        assert_eq!(jitter.buffered_frames(), 64);
+
+        producer.push_slice(&[0.5; 64]);
+        jitter.read(&mut outputs, 64, &shared);
+        assert_eq!(outputs[0].samples()[0], 0.5);
+        assert_eq!(jitter.buffered_frames(), 64);
+
+        jitter.read(&mut outputs, 64, &shared);
+        assert_eq!(jitter.buffered_frames(), 0);
+
+        // 枯渇 -> アンダーランを数えて再バッファ
+        outputs[0].clear(64);
+        jitter.read(&mut outputs, 64, &shared);
+        assert_eq!(outputs[0].samples()[0], 0.0);
+        assert_eq!(shared.underruns.load(Ordering::Relaxed), 1);
+        assert!(!jitter.playing);
+    }
+
+    #[test]
+    fn test_send_rejects_oversized_packets() {
+        // 8ch x 192 frames (4ms @ 48kHz) は 1 パケットに収まらない
+        let result = NetSendSinkNode::new("n", "Net", 8, "127.0.0.1", 5004, 4000, 48000.0);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_send_to_receive_over_loopback() {
+        // 空いているポートを借りてから受信ノードに bind させる
+        let port = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let mut rx =
+            NetReceiveSourceNode::new("rx", "Rx", 2, "127.0.0.1", port, 1, 48000.0).unwrap();
+        let mut tx = NetSendSinkNode::new("tx", "Tx", 2, "127.0.0.1", port, 1000, 48000.0).unwrap();
+
+        for _ in 0..4 {
+            tx.clear_buffers(96);
+            tx.input_buffer_mut(PortId::new(0))
+                .unwrap()
+                .write_samples(&[0.25; 96]);
+            tx.process(96);
+        }
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while rx.status().packets < 8 && std::time::Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(tx.status().packets, 8);
+        assert_eq!(rx.status().packets, 8);
+        assert_eq!(rx.status().buffered_frames, 384);
+
+        rx.clear_buffers(96);
+        rx.process(96);
+        let out = rx.output_buffer(PortId::new(0)).unwrap().samples()[0];
+        assert!((out - 0.25).abs() < 1e-6);
+        assert_eq!(rx.output_buffer(PortId::new(1)).unwrap().samples()[0], 0.0);
+    }
+}
@@ -72,11 +72,12 @@ pub enum NodeType {
     Source,
     Bus,
     Sink,
+    Record,
 }
 
 /// オーディオノードの統一インターフェース
 ///
-/// すべてのノード種類（Source, Bus, Sink）がこのトレイトを実装する。
+/// すべてのノード種類（Source, Bus, Sink, Record）がこのトレイトを実装する。
 /// これにより、グラフは具体的なノード種類を知らずに処理できる。
 pub trait AudioNode: Send + Sync {
     /// ノードの種類を返す
@@ -108,11 +109,41 @@ pub trait AudioNode: Send + Sync {
     /// - Source: 入力デバイスから読み込み → 出力バッファへ
     /// - Bus: 入力バッファ → プラグイン処理 → 出力バッファ
     /// - Sink: 入力バッファ → 出力デバイスへ書き込み
+    /// - Record: 入力バッファ → ライタースレッド経由でファイルへ
     fn process(&mut self, frames: usize);
 
     /// バッファをクリア
     fn clear_buffers(&mut self, frames: usize);
 
+    /// ノード内部の処理レイテンシ（サンプル数）
+    ///
+    /// レイテンシ補正の計算に使用する。グラフのロック中（オーディオスレッドを含む）に
+    /// 呼ばれるので、プラグインへの問い合わせはせずキャッシュした値を返すこと。
+    fn latency_samples(&self) -> u32 {
+        0
+    }
+
+    /// Whether processing reads or writes live I/O outside the graph
+    /// (files, network, loopback rings)
+    ///
+    /// オフラインレンダリング（バウンス・フリーズ）ではこのノードを処理しない（無音のまま）。
+    fn is_live_io(&self) -> bool {
+        false
+    }
+
+    /// Swap the processing state (filters, envelopes, delay lines) for a fresh one and
+    /// return the live state (None if the node keeps none)
+    ///
+    /// オフラインレンダリングの開始時に呼ぶ（制御スレッド。確保してよい）。設定はそのまま。
+    fn take_live_state(&mut self) -> Option<Box<dyn Any + Send>> {
+        None
+    }
+
+    /// Put back the state returned by [`Self::take_live_state`] (the offline state is dropped)
+    ///
+    /// レンダリング中に変わった設定は戻したライブの状態に引き継ぐ。
+    fn restore_live_state(&mut self, _state: Box<dyn Any + Send>) {}
+
     /// 入力ピークレベルを取得（メータリング用）
     fn input_peak_levels(&self) -> Vec<f32>;
 
@@ -6,13 +6,34 @@
 //! - GraphProcessor.process() でグラフ全体を処理
 //! - SinkNode の入力バッファから出力デバイスに書き込み
 //! - 各デバイスに対して1つの AudioUnit コールバック
+//!
+//! ## 複数デバイス
+//! 出力ランタイムは device_id ごとに持つ。グラフを処理するのはクロックマスターの
+//! コールバックだけで、他のデバイス向けのシンク出力は [`DriftFifo`] に書き込み、
+//! 各デバイスのコールバックがそこから読む（クロックずれは FIFO 側で吸収）。
+//!
+//! ## ダイレクトモニター
+//! 同じデバイスの入力 → シンクのエッジが `MonitorMode::Direct` の場合、出力ユニットの
+//! 入力側も有効にし（入出力兼用の HAL ユニット）、同じ I/O サイクルで取得した入力を
+//! レンダーコールバックで直接ミックスする。キャプチャのリングバッファを経由しない。
+//!
+//! ## 出力デバイスの切り替え
+//! [`switch_output_device`] は新しいデバイスを先に起動し、クロックマスターのコールバックで
+//! 旧デバイスのシンクを両方のデバイスに [`HANDOVER_MS`] かけてクロスフェードで書き込む。
+//! フェードが終わってからシンクを新しいデバイスに付け替え、旧デバイスを止める。
 
+use crate::audio::crossfade::CrossfadeCurve;
+use crate::audio::drift::{DriftFifo, DriftStats};
+use crate::audio::edge::gain_ramp_step;
 use crate::audio::processor::get_graph_processor;
 use crate::audio::sink::SinkNode;
-use crate::audio::source::SourceId;
+use crate::audio::source::{SourceId, SourceNode};
+use crate::audio::watchdog::{self, Component};
+use crate::audio::AudioGraph;
 use crate::vdsp::VDsp;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use coreaudio::audio_unit::audio_format::LinearPcmFlags;
-use coreaudio::audio_unit::macos_helpers::{get_device_name, set_device_sample_rate};
+use coreaudio::audio_unit::macos_helpers::get_device_name;
 use coreaudio::audio_unit::render_callback::{self, data};
 use coreaudio::audio_unit::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
 use coreaudio::sys::{
@@ -20,26 +41,110 @@ use coreaudio::sys::{
     kAudioObjectPropertyElementMaster, AudioBufferList, AudioObjectGetPropertyData,
     AudioObjectGetPropertyDataSize, AudioObjectPropertyAddress,
 };
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
 use std::ptr;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{mpsc, Arc, LazyLock};
-use std::time::Duration;
-
-/// Sample rate for audio output
-const SAMPLE_RATE: f64 = 48000.0;
+use std::time::{Duration, Instant};
 
 /// Maximum frames per callback
 const MAX_FRAMES: usize = crate::audio::MAX_FRAMES;
 
-/// Active output state (AudioUnit is managed in thread, not stored here)
-struct ActiveOutput {
-    device_id: u32,
+/// Secondary FIFO capacity (frames)
+const FIFO_FRAMES: usize = MAX_FRAMES * 4;
+
+/// Crossfade length of an output device switch
+pub const HANDOVER_MS: u32 = 100;
+
+/// Longest wait for a handover crossfade (the master callback may have stopped)
+const HANDOVER_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One running output device (AudioUnit is managed in thread, not stored here)
+struct OutputRuntime {
     running: Arc<AtomicBool>,
 }
 
-/// Global active output (single device at a time)
-static ACTIVE_OUTPUT: LazyLock<RwLock<Option<ActiveOutput>>> = LazyLock::new(|| RwLock::new(None));
+/// Sink mix handed from the clock master to one device
+struct OutputFeed {
+    device_id: u32,
+    fifo: Arc<DriftFifo>,
+    /// Master-side staging buffer (only the master callback locks it)
+    stage: Mutex<Box<[f32]>>,
+}
+
+/// Input block of a combined I/O unit, handed from its input callback to its
+/// render callback (both run on the device's I/O thread)
+struct DirectInput {
+    channels: usize,
+    /// Frames of the latest block (0 = consumed / none)
+    frames: usize,
+    data: Box<[f32]>,
+}
+
+/// Sinks of one device being crossfaded onto another (output device switch)
+struct Handover {
+    from: u32,
+    to: u32,
+    /// Crossfade length in frames
+    length: u32,
+    /// Frames rendered so far (advanced by the clock master callback)
+    position: AtomicU32,
+}
+
+/// Gains of one block of a handover (interpolated across the block)
+#[derive(Clone, Copy)]
+struct HandoverFade {
+    from: u32,
+    to: u32,
+    /// Old device's gain at block start / end
+    out: (f32, f32),
+    /// New device's gain at block start / end
+    into: (f32, f32),
+}
+
+impl Handover {
+    /// Advance by one block and return its gains
+    fn advance(&self, frames: usize) -> HandoverFade {
+        let start = self.position.fetch_add(frames as u32, Ordering::Relaxed);
+        let end = start.saturating_add(frames as u32);
+        let progress = |p: u32| p.min(self.length) as f32 / self.length.max(1) as f32;
+        let (out_start, in_start) = CrossfadeCurve::EqualPower.factors(progress(start));
+        let (out_end, in_end) = CrossfadeCurve::EqualPower.factors(progress(end));
+        HandoverFade {
+            from: self.from,
+            to: self.to,
+            out: (out_start, out_end),
+            into: (in_start, in_end),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.position.load(Ordering::Relaxed) >= self.length
+    }
+}
+
+/// Running outputs: device_id -> runtime
+static OUTPUTS: LazyLock<RwLock<HashMap<u32, OutputRuntime>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Device whose callback processes the graph (0 = none)
+static CLOCK_MASTER: AtomicU32 = AtomicU32::new(0);
+
+/// Feeds of all running devices, read lock-free by the master callback
+static OUTPUT_FEEDS: LazyLock<ArcSwap<Vec<Arc<OutputFeed>>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+/// Output device switch in progress (read lock-free by the master callback)
+static HANDOVER: LazyLock<ArcSwapOption<Handover>> = LazyLock::new(ArcSwapOption::empty);
+
+/// Per-device runtime state for status queries
+#[derive(Debug, Clone)]
+pub struct OutputRuntimeInfo {
+    pub device_id: u32,
+    pub is_clock_master: bool,
+    pub drift: DriftStats,
+}
 
 /// Get output channel count for a device
 fn get_device_output_channels(device_id: u32) -> u32 {
@@ -92,20 +197,39 @@ fn get_device_output_channels(device_id: u32) -> u32 {
 }
 
 /// Start audio output for a device (v2 architecture)
+///
+/// Switches output: every other running device is stopped and this one becomes
+/// the clock master. Use [`start_output_device`] to add a device alongside.
 pub fn start_output_v2(device_id: u32) -> Result<(), String> {
-    // Check if already running with same device
+    // Check if already running alone with same device
     {
-        let active = ACTIVE_OUTPUT.read();
-        if let Some(ref output) = *active {
-            if output.device_id == device_id && output.running.load(Ordering::Relaxed) {
-                return Ok(()); // Already running
-            }
+        let outputs = OUTPUTS.read();
+        if outputs.len() == 1 && is_running(&outputs, device_id) {
+            return Ok(()); // Already running
         }
     }
 
     // Stop any existing output
     stop_output_v2();
 
+    start_output_device(device_id)
+}
+
+fn is_running(outputs: &HashMap<u32, OutputRuntime>, device_id: u32) -> bool {
+    outputs
+        .get(&device_id)
+        .map_or(false, |o| o.running.load(Ordering::Relaxed))
+}
+
+/// Start an additional output device
+///
+/// The first running device becomes the clock master; the others render the
+/// sinks bound to them from a drift-compensated FIFO fed by the master.
+pub fn start_output_device(device_id: u32) -> Result<(), String> {
+    if is_running(&OUTPUTS.read(), device_id) {
+        return Ok(()); // Already running
+    }
+
     let output_channels = get_device_output_channels(device_id);
     if output_channels == 0 {
         return Err(format!("Device {} has no output channels", device_id));
@@ -113,41 +237,396 @@ pub fn start_output_v2(device_id: u32) -> Result<(), String> {
 
     let device_name =
         get_device_name(device_id).unwrap_or_else(|_| format!("Device {}", device_id));
-    println!(
+    log_info!(
         "[AudioOutput v2] Starting output to {} (ID: {}, {} channels)",
-        device_name, device_id, output_channels
+        device_name,
+        device_id,
+        output_channels
     );
 
-    // Set sample rate
-    if let Err(e) = set_device_sample_rate(device_id, SAMPLE_RATE) {
-        eprintln!(
-            "[AudioOutput v2] Warning: Could not set sample rate: {:?}",
-            e
-        );
+    // Set device rate per its policy (engine / follow / fixed).
+    // ストリームフォーマットは常にエンジンレートで、差があれば AUHAL がレート変換する
+    let sample_rate = crate::audio::engine_sample_rate();
+    if crate::device::apply_output_rate_policy(device_id, sample_rate).is_none() {
+        log_info!("[AudioOutput v2] Following device rate (no rate change)");
     }
 
+    // I/O buffer size (global or this device's override in settings.json)
+    crate::capture::apply_device_buffer_size(device_id);
+
     let running = Arc::new(AtomicBool::new(true));
     let running_clone = running.clone();
+    let fifo = Arc::new(DriftFifo::new(output_channels as usize, FIFO_FRAMES));
+    let heartbeat = watchdog::arm(Component::Output(device_id));
 
-    // Store as active output
+    // Store as running output; the first device drives the graph
     {
-        let mut active = ACTIVE_OUTPUT.write();
-        *active = Some(ActiveOutput {
+        let mut outputs = OUTPUTS.write();
+        outputs.insert(
             device_id,
-            running: running.clone(),
-        });
+            OutputRuntime {
+                running: running.clone(),
+            },
+        );
+        let _ = CLOCK_MASTER.compare_exchange(0, device_id, Ordering::SeqCst, Ordering::SeqCst);
     }
+    add_feed(OutputFeed {
+        device_id,
+        fifo: fifo.clone(),
+        stage: Mutex::new(vec![0.0f32; MAX_FRAMES * output_channels as usize].into_boxed_slice()),
+    });
 
     // Start output thread, and wait until AudioUnit actually starts (or fails).
     let (started_tx, started_rx) = mpsc::channel::<Result<(), String>>();
     std::thread::spawn(move || {
-        output_thread_v2(device_id, output_channels, running_clone, Some(started_tx));
+        output_thread_v2(
+            device_id,
+            output_channels,
+            sample_rate,
+            running_clone,
+            fifo,
+            heartbeat,
+            Some(started_tx),
+        );
     });
 
-    match started_rx.recv_timeout(Duration::from_secs(2)) {
+    let result = match started_rx.recv_timeout(Duration::from_secs(2)) {
         Ok(Ok(())) => Ok(()),
         Ok(Err(e)) => Err(e),
         Err(_) => Err("Timed out while starting audio output".to_string()),
+    };
+    if result.is_err() {
+        stop_output_device(device_id);
+    } else {
+        // セーフティオフセットはレートやバッファで変わるので開始ごとに取り直す
+        refresh_sink_latencies();
+    }
+    result
+}
+
+/// Re-read the hardware output latency of every sink's device
+///
+/// CoreAudio への問い合わせはグラフのロック外で行い、結果だけを書き込む。
+pub fn refresh_sink_latencies() {
+    let processor = get_graph_processor();
+    let sinks: Vec<(crate::audio::NodeHandle, u32, u8)> = processor.with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter_map(|handle| {
+                let sink = graph
+                    .get_node(handle)?
+                    .as_any()
+                    .downcast_ref::<SinkNode>()?;
+                Some((handle, sink.device_id(), sink.channel_offset()))
+            })
+            .collect()
+    });
+    if sinks.is_empty() {
+        return;
+    }
+    let latencies: Vec<(crate::audio::NodeHandle, u32)> = sinks
+        .into_iter()
+        .map(|(handle, device_id, offset)| {
+            (
+                handle,
+                crate::device::get_sink_output_latency(device_id, offset),
+            )
+        })
+        .collect();
+    processor.with_graph_mut(|graph| {
+        for (handle, frames) in latencies {
+            if let Some(sink) = graph
+                .get_node_mut(handle)
+                .and_then(|n| n.as_any_mut().downcast_mut::<SinkNode>())
+            {
+                sink.set_device_latency(frames);
+            }
+        }
+    });
+}
+
+/// Move the "follow system default" sinks to a new default output device
+///
+/// 旧デバイスで出力中だったら、新しいデバイスを先に追加してから、シンクが残っていない
+/// 旧デバイスを止める（クロックマスターは残ったデバイスに引き継がれ、グラフの処理は途切れない）。
+/// Returns the handles of the sinks that moved.
+pub fn retarget_default_sinks(device_id: u32) -> Vec<crate::audio::NodeHandle> {
+    if device_id == 0 {
+        return Vec::new();
+    }
+    let processor = get_graph_processor();
+    let uid = crate::device::get_device_uid(device_id);
+    let moved =
+        processor.with_graph_mut(|graph| graph.retarget_default_sinks(device_id, uid.as_deref()));
+    if moved.is_empty() {
+        return Vec::new();
+    }
+
+    let mut previous: Vec<u32> = moved.iter().map(|&(_, old)| old).collect();
+    previous.sort_unstable();
+    previous.dedup();
+    let was_playing = previous.iter().any(|&old| is_output_device_running(old));
+
+    if was_playing {
+        if let Err(e) = start_output_device(device_id) {
+            log_error!(
+                "[AudioOutput v2] Failed to start default output {}: {}",
+                device_id,
+                e
+            );
+        }
+    }
+
+    // 他のシンクがまだ使っているデバイスは止めない
+    let still_bound: Vec<u32> = processor.with_graph(|graph| {
+        graph
+            .node_handles()
+            .filter_map(|handle| {
+                let sink = graph
+                    .get_node(handle)?
+                    .as_any()
+                    .downcast_ref::<SinkNode>()?;
+                Some(sink.device_id())
+            })
+            .collect()
+    });
+    for old in previous {
+        if !still_bound.contains(&old) && is_output_device_running(old) {
+            stop_output_device(old);
+        }
+    }
+    refresh_sink_latencies();
+
+    log_info!(
+        "[AudioOutput v2] Default output is now device {}; {} sink(s) followed",
+        device_id,
+        moved.len()
+    );
+    moved.into_iter().map(|(handle, _)| handle).collect()
+}
+
+/// Move every sink of output device `from` to device `to` without a gap
+///
+/// 新しいデバイスを追加で起動し、旧デバイスのシンクを両方へ書きながら [`HANDOVER_MS`] で
+/// クロスフェードする。終わったらシンクを付け替えて旧デバイスを止める（クロックマスターは
+/// 残ったデバイスに引き継がれる）。旧デバイスが止まっていればシンクを付け替えるだけ。
+/// Returns the handles of the sinks that moved.
+pub fn switch_output_device(from: u32, to: u32) -> Result<Vec<crate::audio::NodeHandle>, String> {
+    if from == to {
+        return Ok(Vec::new());
+    }
+    let processor = get_graph_processor();
+    let uid = crate::device::get_device_uid(to);
+
+    if is_output_device_running(from) {
+        start_output_device(to)?;
+        let sample_rate = crate::audio::engine_sample_rate();
+        let handover = Arc::new(Handover {
+            from,
+            to,
+            length: (sample_rate * HANDOVER_MS as f64 / 1000.0) as u32,
+            position: AtomicU32::new(0),
+        });
+        HANDOVER.store(Some(handover.clone()));
+
+        let started = Instant::now();
+        while !handover.is_finished() && started.elapsed() < HANDOVER_TIMEOUT {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        if !handover.is_finished() {
+            log_error!(
+                "[AudioOutput v2] Handover {} -> {} did not finish; switching anyway",
+                from,
+                to
+            );
+        }
+    }
+
+    // 付け替えてからフェードを外す（同じブロックで両方が鳴ったり、両方が消えたりしない）
+    let moved =
+        processor.with_graph_mut(|graph| graph.retarget_device_sinks(from, to, uid.as_deref()));
+    HANDOVER.store(None);
+
+    if is_output_device_running(from) {
+        stop_output_device(from);
+    }
+    refresh_sink_latencies();
+
+    log_info!(
+        "[AudioOutput v2] Switched {} sink(s) from device {} to {}",
+        moved.len(),
+        from,
+        to
+    );
+    Ok(moved)
+}
+
+fn add_feed(feed: OutputFeed) {
+    let feed = Arc::new(feed);
+    OUTPUT_FEEDS.rcu(|feeds| {
+        let mut next: Vec<Arc<OutputFeed>> = feeds
+            .iter()
+            .filter(|f| f.device_id != feed.device_id)
+            .cloned()
+            .collect();
+        next.push(feed.clone());
+        next
+    });
+}
+
+fn remove_feed(device_id: u32) {
+    OUTPUT_FEEDS.rcu(|feeds| {
+        feeds
+            .iter()
+            .filter(|f| f.device_id != device_id)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+}
+
+/// Mix the sinks that play on `device_id` into an interleaved buffer
+///
+/// 切り替え中は旧デバイスのシンクを旧デバイスでフェードアウト、新デバイスでフェードインする。
+fn mix_device_sinks(
+    graph: &AudioGraph,
+    device_id: u32,
+    buffer: &mut [f32],
+    out_ch: usize,
+    frames: usize,
+    handover: Option<HandoverFade>,
+) {
+    match handover {
+        Some(h) if h.from == device_id => {
+            mix_sinks(graph, device_id, buffer, out_ch, frames, h.out);
+        }
+        Some(h) if h.to == device_id => {
+            mix_sinks(graph, device_id, buffer, out_ch, frames, (1.0, 1.0));
+            mix_sinks(graph, h.from, buffer, out_ch, frames, h.into);
+        }
+        _ => mix_sinks(graph, device_id, buffer, out_ch, frames, (1.0, 1.0)),
+    }
+}
+
+/// Mix the sinks bound to `device_id`, ramping their level from `fade.0` to `fade.1`
+fn mix_sinks(
+    graph: &AudioGraph,
+    device_id: u32,
+    buffer: &mut [f32],
+    out_ch: usize,
+    frames: usize,
+    fade: (f32, f32),
+) {
+    let (fade_start, fade_end) = fade;
+    if fade_start == 0.0 && fade_end == 0.0 {
+        return;
+    }
+    let fade_step = (fade_end - fade_start) / frames.max(1) as f32;
+    for handle in graph.sink_nodes() {
+        if let Some(node) = graph.get_node(handle) {
+            if let Some(sink) = node.as_any().downcast_ref::<SinkNode>() {
+                // Check if this sink is for our device
+                if sink.device_id() != device_id {
+                    continue;
+                }
+
+                // Copy each port to its device channel(s) (channel map or offset + port)
+                sink.for_each_route(|port, target_ch| {
+                    if target_ch >= out_ch {
+                        return;
+                    }
+
+                    if let Some(samples) = sink.get_output_samples(port) {
+                        let valid = samples.len().min(frames);
+                        let sink_gain = sink.output_gain_for_port(port);
+                        for i in 0..valid {
+                            let out_idx = i * out_ch + target_ch;
+                            if out_idx < buffer.len() {
+                                let fade = fade_start + fade_step * (i + 1) as f32;
+                                buffer[out_idx] += samples[i] * sink_gain * fade;
+                            }
+                        }
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Mix the direct-monitor edges of `device_id` from the unit's own input block
+#[allow(clippy::too_many_arguments)]
+fn mix_direct_monitor(
+    graph: &AudioGraph,
+    device_id: u32,
+    input: &DirectInput,
+    buffer: &mut [f32],
+    out_ch: usize,
+    frames: usize,
+    max_step: f32,
+) {
+    let in_ch = input.channels;
+    let frames = frames.min(input.frames).min(buffer.len() / out_ch.max(1));
+    if frames == 0 {
+        return;
+    }
+
+    for edge in graph
+        .edges()
+        .iter()
+        .filter(|e| e.is_direct() && e.is_audible())
+    {
+        let Some(source) = graph
+            .get_node(edge.source)
+            .and_then(|n| n.as_any().downcast_ref::<SourceNode>())
+        else {
+            continue;
+        };
+        let Some(sink) = graph
+            .get_node(edge.target)
+            .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+        else {
+            continue;
+        };
+        let SourceId::InputDevice {
+            device_id: input_device,
+            channel,
+        } = source.source_id()
+        else {
+            continue;
+        };
+        if *input_device != device_id || sink.device_id() != device_id {
+            continue;
+        }
+
+        // Direct edges are skipped by the graph, so the ramp is advanced only here
+        let (start, end) = edge.advance_gain(frames, max_step);
+        let step = (end - start) / frames as f32;
+
+        let sink_ports = sink.sink_id().channel_count as usize;
+        for ch in 0..edge.channels as usize {
+            let src_port = edge.source_port_for(ch).index();
+            let src_ch = *channel as usize + src_port;
+            let Some((port, fold)) = edge.fold_target(ch, sink_ports) else {
+                continue;
+            };
+            let port = port.index();
+            if src_ch >= in_ch {
+                continue;
+            }
+
+            let gain = source.signed_trim(src_port)
+                * edge.channel_gain(ch)
+                * fold
+                * sink.output_gain_for_port(port);
+            sink.for_each_route(|route_port, dst_ch| {
+                if route_port != port || dst_ch >= out_ch {
+                    return;
+                }
+                for i in 0..frames {
+                    let ramp = start + step * (i + 1) as f32;
+                    buffer[i * out_ch + dst_ch] += input.data[i * in_ch + src_ch] * ramp * gain;
+                }
+            });
+        }
     }
 }
 
@@ -155,14 +634,17 @@ pub fn start_output_v2(device_id: u32) -> Result<(), String> {
 fn output_thread_v2(
     device_id: u32,
     output_channels: u32,
+    sample_rate: f64,
     running: Arc<AtomicBool>,
+    fifo: Arc<DriftFifo>,
+    heartbeat: Arc<watchdog::Heartbeat>,
     started_tx: Option<mpsc::Sender<Result<(), String>>>,
 ) {
     // Create audio unit for output
     let mut audio_unit = match AudioUnit::new(coreaudio::audio_unit::IOType::HalOutput) {
         Ok(au) => au,
         Err(e) => {
-            eprintln!("[AudioOutput v2] Failed to create audio unit: {:?}", e);
+            log_error!("[AudioOutput v2] Failed to create audio unit: {:?}", e);
             if let Some(tx) = started_tx {
                 let _ = tx.send(Err(format!("Failed to create audio unit: {:?}", e)));
             }
@@ -171,6 +653,26 @@ fn output_thread_v2(
         }
     };
 
+    // Direct monitor: enable the input side too (must precede CurrentDevice)
+    let mut direct_channels = 0usize;
+    if get_graph_processor().with_graph(|graph| graph.has_direct_monitor(device_id)) {
+        let input_channels = crate::capture::get_device_input_channels(device_id);
+        let enable_input: u32 = 1;
+        match audio_unit.set_property(
+            coreaudio::sys::kAudioOutputUnitProperty_EnableIO,
+            Scope::Input,
+            Element::Input,
+            Some(&enable_input),
+        ) {
+            Ok(()) if input_channels > 0 => direct_channels = input_channels as usize,
+            Ok(()) => {}
+            Err(e) => log_error!(
+                "[AudioOutput v2] Direct monitor unavailable (enable input failed: {:?})",
+                e
+            ),
+        }
+    }
+
     // Set output device
     if let Err(e) = audio_unit.set_property(
         coreaudio::sys::kAudioOutputUnitProperty_CurrentDevice,
@@ -178,7 +680,7 @@ fn output_thread_v2(
         Element::Output,
         Some(&device_id),
     ) {
-        eprintln!("[AudioOutput v2] Failed to set device: {:?}", e);
+        log_error!("[AudioOutput v2] Failed to set device: {:?}", e);
         if let Some(tx) = started_tx {
             let _ = tx.send(Err(format!("Failed to set device: {:?}", e)));
         }
@@ -188,7 +690,7 @@ fn output_thread_v2(
 
     // Set stream format
     let stream_format = StreamFormat {
-        sample_rate: SAMPLE_RATE,
+        sample_rate,
         sample_format: SampleFormat::F32,
         flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
         channels: output_channels,
@@ -200,7 +702,7 @@ fn output_thread_v2(
         Element::Output,
         Some(&stream_format.to_asbd()),
     ) {
-        eprintln!("[AudioOutput v2] Failed to set stream format: {:?}", e);
+        log_error!("[AudioOutput v2] Failed to set stream format: {:?}", e);
         if let Some(tx) = started_tx {
             let _ = tx.send(Err(format!("Failed to set stream format: {:?}", e)));
         }
@@ -208,6 +710,27 @@ fn output_thread_v2(
         return;
     }
 
+    type Args = render_callback::Args<data::Interleaved<f32>>;
+
+    // Direct monitor input: copy each block for the render callback of the same cycle
+    let direct_input = if direct_channels > 0 {
+        match enable_direct_input(&mut audio_unit, direct_channels, sample_rate) {
+            Ok(input) => {
+                log_info!(
+                    "[AudioOutput v2] Direct monitor enabled ({} input channels)",
+                    direct_channels
+                );
+                Some(input)
+            }
+            Err(e) => {
+                log_error!("[AudioOutput v2] Direct monitor unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     #[derive(Clone, Copy, PartialEq, Eq)]
     enum CapturePairKey {
         PrismAny { pair_idx: usize },
@@ -230,14 +753,17 @@ fn output_thread_v2(
 
     let running_callback = running.clone();
     let out_ch = output_channels as usize;
+    let load_meter = crate::audio::dsp_load::device_load_meter(device_id);
+    let session = crate::audio::session_stats::device_session_stats(device_id);
+    let xruns = crate::audio::xrun::xrun_counter(device_id);
 
     // Set render callback
-    type Args = render_callback::Args<data::Interleaved<f32>>;
-
     if let Err(e) = audio_unit.set_render_callback(move |args: Args| {
         if !running_callback.load(Ordering::Relaxed) {
             return Ok(());
         }
+        crate::logging::register_realtime_thread();
+        heartbeat.beat();
 
         let Args {
             data, num_frames, ..
@@ -249,12 +775,45 @@ fn output_thread_v2(
             return Ok(());
         }
 
+        let callback_started = Instant::now();
+
         // Clear output buffer
         VDsp::clear(buffer);
 
         // Get graph processor
         let processor = get_graph_processor();
 
+        // An offline bounce owns the graph: stay silent until it finishes
+        if processor.is_rendering_offline() {
+            return Ok(());
+        }
+
+        // Direct monitor: this cycle's input, straight from our own input callback
+        let mix_direct = |buffer: &mut [f32]| {
+            let Some(mut input) = direct_input.as_ref().and_then(|d| d.try_lock()) else {
+                return;
+            };
+            if input.frames > 0 {
+                let max_step = gain_ramp_step(processor.gain_ramp_ms(), sample_rate);
+                processor.try_with_graph(|graph| {
+                    mix_direct_monitor(graph, device_id, &input, buffer, out_ch, frames, max_step);
+                });
+            }
+            input.frames = 0;
+        };
+
+        // Secondary device: play what the clock master mixed for us
+        if CLOCK_MASTER.load(Ordering::Relaxed) != device_id {
+            let len = (frames * out_ch).min(buffer.len());
+            fifo.pop(&mut buffer[..len]);
+            mix_direct(buffer);
+            VDsp::clip(buffer, -1.0, 1.0);
+            let elapsed = callback_started.elapsed();
+            load_meter.record(elapsed, frames, sample_rate);
+            session.record(frames, elapsed, sample_rate);
+            return Ok(());
+        }
+
         // Reset per-callback cache state (keep allocations for RT safety)
         CAPTURE_PAIR_CACHE.with(|cache| {
             let mut cache = cache.borrow_mut();
@@ -378,48 +937,48 @@ fn output_thread_v2(
         // Process the audio graph
         processor.process(frames, &read_source);
 
-        // Read from SinkNodes that match this device
-        processor.with_graph(|graph| {
-            for handle in graph.sink_nodes() {
-                if let Some(node) = graph.get_node(handle) {
-                    if let Some(sink) = node.as_any().downcast_ref::<SinkNode>() {
-                        // Check if this sink is for our device
-                        if sink.device_id() != device_id {
-                            continue;
-                        }
+        // 出力デバイスの切り替え中ならこのブロックのフェード量
+        let handover = HANDOVER.load().as_ref().map(|h| h.advance(frames));
 
-                        let channel_offset = sink.channel_offset() as usize;
-                        let port_count = node.input_port_count();
+        // Read from SinkNodes that match this device, and feed the other devices
+        // （グラフがロック中ならこのブロックは無音のまま。コールバックでは待たない）
+        processor.try_with_graph(|graph| {
+            mix_device_sinks(graph, device_id, buffer, out_ch, frames, handover);
 
-                        // Copy each port to corresponding channel
-                        for port in 0..port_count {
-                            let target_ch = channel_offset + port;
-                            if target_ch >= out_ch {
-                                continue;
-                            }
-
-                            if let Some(samples) = sink.get_output_samples(port) {
-                                let valid = samples.len().min(frames);
-                                let sink_gain = sink.output_gain_for_port(port);
-                                for i in 0..valid {
-                                    let out_idx = i * out_ch + target_ch;
-                                    if out_idx < buffer.len() {
-                                        buffer[out_idx] += samples[i] * sink_gain;
-                                    }
-                                }
-                            }
-                        }
-                    }
+            for feed in OUTPUT_FEEDS.load().iter() {
+                if feed.device_id == device_id {
+                    continue;
+                }
+                let Some(mut stage) = feed.stage.try_lock() else {
+                    continue;
+                };
+                let ch = feed.fifo.channels();
+                let len = frames * ch;
+                if len > stage.len() {
+                    continue;
                 }
+                let stage = &mut stage[..len];
+                VDsp::clear(stage);
+                mix_device_sinks(graph, feed.device_id, stage, ch, frames, handover);
+                feed.fifo.push(stage);
             }
         });
 
+        mix_direct(buffer);
+
         // Clip protection
         VDsp::clip(buffer, -1.0, 1.0);
 
+        let elapsed = callback_started.elapsed();
+        load_meter.record(elapsed, frames, sample_rate);
+        session.record(frames, elapsed, sample_rate);
+        if elapsed.as_secs_f64() * sample_rate > frames as f64 {
+            xruns.record_late_callback();
+        }
+
         Ok(())
     }) {
-        eprintln!("[AudioOutput v2] Failed to set render callback: {:?}", e);
+        log_error!("[AudioOutput v2] Failed to set render callback: {:?}", e);
         if let Some(tx) = started_tx {
             let _ = tx.send(Err(format!("Failed to set render callback: {:?}", e)));
         }
@@ -429,7 +988,7 @@ fn output_thread_v2(
 
     // Initialize and start
     if let Err(e) = audio_unit.initialize() {
-        eprintln!("[AudioOutput v2] Failed to initialize AudioUnit: {:?}", e);
+        log_error!("[AudioOutput v2] Failed to initialize AudioUnit: {:?}", e);
         if let Some(tx) = started_tx {
             let _ = tx.send(Err(format!("Failed to initialize AudioUnit: {:?}", e)));
         }
@@ -438,7 +997,7 @@ fn output_thread_v2(
     }
 
     if let Err(e) = audio_unit.start() {
-        eprintln!("[AudioOutput v2] Failed to start AudioUnit: {:?}", e);
+        log_error!("[AudioOutput v2] Failed to start AudioUnit: {:?}", e);
         if let Some(tx) = started_tx {
             let _ = tx.send(Err(format!("Failed to start AudioUnit: {:?}", e)));
         }
@@ -450,7 +1009,7 @@ fn output_thread_v2(
         let _ = tx.send(Ok(()));
     }
 
-    println!("[AudioOutput v2] Started successfully, waiting for stop signal...");
+    log_debug!("[AudioOutput v2] Started successfully, waiting for stop signal...");
 
     // Wait until running is set to false
     while running.load(Ordering::Relaxed) {
@@ -459,28 +1018,152 @@ fn output_thread_v2(
 
     // Stop and cleanup
     let _ = audio_unit.stop();
-    println!("[AudioOutput v2] Stopped");
+    crate::audio::dsp_load::remove_device_load_meter(device_id);
+    log_info!("[AudioOutput v2] Stopped");
 }
 
-/// Stop audio output
+/// Enable the input element of a combined I/O unit and capture into a shared block
+fn enable_direct_input(
+    audio_unit: &mut AudioUnit,
+    channels: usize,
+    sample_rate: f64,
+) -> Result<Arc<Mutex<DirectInput>>, String> {
+    let stream_format = StreamFormat {
+        sample_rate,
+        sample_format: SampleFormat::F32,
+        flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
+        channels: channels as u32,
+    };
+    audio_unit
+        .set_property(
+            coreaudio::sys::kAudioUnitProperty_StreamFormat,
+            Scope::Output,
+            Element::Input,
+            Some(&stream_format.to_asbd()),
+        )
+        .map_err(|e| format!("Failed to set input stream format: {:?}", e))?;
+
+    let input = Arc::new(Mutex::new(DirectInput {
+        channels,
+        frames: 0,
+        data: vec![0.0f32; MAX_FRAMES * channels].into_boxed_slice(),
+    }));
+    let input_callback = input.clone();
+
+    type Args = render_callback::Args<data::Interleaved<f32>>;
+    audio_unit
+        .set_input_callback(move |args: Args| {
+            let Args {
+                data, num_frames, ..
+            } = args;
+            let frames = num_frames as usize;
+            if let Some(mut input) = input_callback.try_lock() {
+                let len = frames * input.channels;
+                if frames <= MAX_FRAMES && data.buffer.len() >= len {
+                    input.data[..len].copy_from_slice(&data.buffer[..len]);
+                    input.frames = frames;
+                } else {
+                    input.frames = 0;
+                }
+            }
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to set input callback: {:?}", e))?;
+
+    Ok(input)
+}
+
+/// Stop one output device
+///
+/// 止めたのがクロックマスターなら、残りのデバイスから新しいマスターを選ぶ。
+pub fn stop_output_device(device_id: u32) {
+    let mut outputs = OUTPUTS.write();
+    if let Some(output) = outputs.remove(&device_id) {
+        log_info!("[AudioOutput v2] Stopping device {}", device_id);
+        output.running.store(false, Ordering::SeqCst);
+    }
+    remove_feed(device_id);
+    watchdog::disarm(Component::Output(device_id));
+
+    if CLOCK_MASTER.load(Ordering::SeqCst) == device_id {
+        let next = outputs.keys().min().copied().unwrap_or(0);
+        CLOCK_MASTER.store(next, Ordering::SeqCst);
+        if next != 0 {
+            log_info!("[AudioOutput v2] Device {} is now the clock master", next);
+        }
+    }
+}
+
+/// Restart a started output device, keeping its clock-master role
+///
+/// ダイレクトモニターの有無など、ユニット構成が変わったときに使う。
+/// 出力スレッドがエラーで止まっているデバイス（停止操作はされていない）も対象。
+pub fn restart_output_device(device_id: u32) -> Result<(), String> {
+    if !OUTPUTS.read().contains_key(&device_id) {
+        return Ok(());
+    }
+    let was_master = CLOCK_MASTER.load(Ordering::SeqCst) == device_id;
+    stop_output_device(device_id);
+    if was_master {
+        CLOCK_MASTER.store(device_id, Ordering::SeqCst);
+    }
+    // 旧スレッドがユニットを止めるまで待つ（停止ポーリング間隔 100ms）
+    std::thread::sleep(Duration::from_millis(150));
+    start_output_device(device_id)
+}
+
+/// Stop all audio output
 pub fn stop_output_v2() {
-    let mut active = ACTIVE_OUTPUT.write();
-    if let Some(output) = active.take() {
-        println!("[AudioOutput v2] Stopping device {}", output.device_id);
+    let mut outputs = OUTPUTS.write();
+    for (device_id, output) in outputs.drain() {
+        log_info!("[AudioOutput v2] Stopping device {}", device_id);
         output.running.store(false, Ordering::SeqCst);
+        watchdog::disarm(Component::Output(device_id));
     }
+    OUTPUT_FEEDS.store(Arc::new(Vec::new()));
+    CLOCK_MASTER.store(0, Ordering::SeqCst);
 }
 
-/// Check if output is running
+/// Check if any output is running
 pub fn is_output_running_v2() -> bool {
-    let active = ACTIVE_OUTPUT.read();
-    active
-        .as_ref()
-        .map_or(false, |o| o.running.load(Ordering::Relaxed))
+    OUTPUTS
+        .read()
+        .values()
+        .any(|o| o.running.load(Ordering::Relaxed))
+}
+
+/// Check if a specific device is running as an output
+pub fn is_output_device_running(device_id: u32) -> bool {
+    is_running(&OUTPUTS.read(), device_id)
 }
 
-/// Get the currently configured active output device, if any.
+/// Get the clock-master output device, if any.
 pub fn get_active_output_device() -> Option<u32> {
-    let active = ACTIVE_OUTPUT.read();
-    active.as_ref().map(|o| o.device_id)
+    match CLOCK_MASTER.load(Ordering::SeqCst) {
+        0 => None,
+        id => Some(id),
+    }
+}
+
+/// All running output devices (sorted, clock master included)
+pub fn get_active_output_devices() -> Vec<u32> {
+    let mut ids: Vec<u32> = OUTPUTS.read().keys().copied().collect();
+    ids.sort_unstable();
+    ids
+}
+
+/// Runtime status of every running output
+pub fn output_runtime_info() -> Vec<OutputRuntimeInfo> {
+    let master = CLOCK_MASTER.load(Ordering::SeqCst);
+    let mut infos: Vec<OutputRuntimeInfo> = OUTPUT_FEEDS
+        .load()
+        .iter()
+        .map(|feed| OutputRuntimeInfo {
+            device_id: feed.device_id,
+            is_clock_master: feed.device_id == master,
+            drift: feed.fifo.stats(),
+        })
+        .collect();
+    infos.sort_by_key(|i| i.device_id);
+    infos
 }
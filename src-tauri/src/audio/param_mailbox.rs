@@ -0,0 +1,228 @@
+//! Edge parameter mailbox - coalesced hot path updates
+//!
+//! フェーダーを動かすと `set_edge_gain` が数百回届く。そのたびにグラフのロックを取ると
+//! オーディオスレッドの `try_write` が失敗してブロックを落とすので、エッジごとに
+//! 最新値だけを持つスロットに書き込み、オーディオスレッドがブロックの頭で 1 回だけ反映する。
+//!
+//! スロットはエッジ ID をキーにしたオープンアドレス表（Atomic のみ、ロックなし）。
+//! 書き込み側は値を書いてから pending ビットを立て、読み出し側はビットを swap で落としてから
+//! 値を読む。間に新しい値が書かれても、次のブロックで同じ値が再適用されるだけで壊れない。
+//!
+//! スロットは `clear` まで解放しない（エッジ ID は使い回されない）。表が埋まったら
+//! 呼び出し側はロックを取る従来の経路で反映する。
+
+use super::edge::EdgeId;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU8, Ordering};
+
+/// Number of slots (edges with a pending or past update)
+pub const MAILBOX_SLOTS: usize = 1024;
+
+/// Empty slot key (edge ids start at 1)
+const EMPTY: u32 = 0;
+
+const GAIN: u8 = 1 << 0;
+const MUTE: u8 = 1 << 1;
+const PAN: u8 = 1 << 2;
+
+/// Values to set on one edge (None = unchanged)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EdgeParamUpdate {
+    pub gain: Option<f32>,
+    pub muted: Option<bool>,
+    pub pan: Option<f32>,
+}
+
+impl EdgeParamUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.gain.is_none() && self.muted.is_none() && self.pan.is_none()
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    key: AtomicU32,
+    pending: AtomicU8,
+    gain_bits: AtomicU32,
+    muted: AtomicBool,
+    pan_bits: AtomicU32,
+}
+
+/// Latest pending parameters per edge
+pub struct ParamMailbox {
+    slots: Box<[Slot]>,
+    /// Set after any post (lets `drain` skip the scan on idle blocks)
+    any_pending: AtomicBool,
+}
+
+impl Default for ParamMailbox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParamMailbox {
+    pub fn new() -> Self {
+        Self {
+            slots: (0..MAILBOX_SLOTS).map(|_| Slot::default()).collect(),
+            any_pending: AtomicBool::new(false),
+        }
+    }
+
+    /// Slot of `edge_id`, claiming a free one if needed (None = full)
+    fn slot(&self, edge_id: u32) -> Option<&Slot> {
+        let start = edge_id as usize % self.slots.len();
+        for i in 0..self.slots.len() {
+            let slot = &self.slots[(start + i) % self.slots.len()];
+            let key = slot.key.load(Ordering::Acquire);
+            if key == edge_id {
+                return Some(slot);
+            }
+            if key == EMPTY {
+                match slot
+                    .key
+                    .compare_exchange(EMPTY, edge_id, Ordering::AcqRel, Ordering::Acquire)
+                {
+                    Ok(_) => return Some(slot),
+                    // 他のスレッドが同じスロットを取った（同じエッジならそのまま使う）
+                    Err(key) if key == edge_id => return Some(slot),
+                    Err(_) => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// Store the latest values for an edge; false when the mailbox is full
+    pub fn post(&self, edge_id: EdgeId, update: EdgeParamUpdate) -> bool {
+        let edge_id = edge_id.raw();
+        if edge_id == EMPTY {
+            return false;
+        }
+        let Some(slot) = self.slot(edge_id) else {
+            return false;
+        };
+        let mut bits = 0;
+        if let Some(gain) = update.gain {
+            slot.gain_bits.store(gain.to_bits(), Ordering::Relaxed);
+            bits |= GAIN;
+        }
+        if let Some(muted) = update.muted {
+            slot.muted.store(muted, Ordering::Relaxed);
+            bits |= MUTE;
+        }
+        if let Some(pan) = update.pan {
+            slot.pan_bits.store(pan.to_bits(), Ordering::Relaxed);
+            bits |= PAN;
+        }
+        slot.pending.fetch_or(bits, Ordering::Release);
+        self.any_pending.store(true, Ordering::Release);
+        true
+    }
+
+    /// Take every pending update (audio thread, once per block)
+    pub fn drain(&self, mut apply: impl FnMut(EdgeId, EdgeParamUpdate)) {
+        if !self.any_pending.swap(false, Ordering::Acquire) {
+            return;
+        }
+        for slot in self.slots.iter() {
+            if slot.pending.load(Ordering::Relaxed) == 0 {
+                continue;
+            }
+            let bits = slot.pending.swap(0, Ordering::Acquire);
+            let key = slot.key.load(Ordering::Relaxed);
+            if bits == 0 || key == EMPTY {
+                continue;
+            }
+            let update = EdgeParamUpdate {
+                gain: (bits & GAIN != 0)
+                    .then(|| f32::from_bits(slot.gain_bits.load(Ordering::Relaxed))),
+                muted: (bits & MUTE != 0).then(|| slot.muted.load(Ordering::Relaxed)),
+                pan: (bits & PAN != 0)
+                    .then(|| f32::from_bits(slot.pan_bits.load(Ordering::Relaxed))),
+            };
+            apply(EdgeId::new(key), update);
+        }
+    }
+
+    /// Whether any update is waiting
+    pub fn has_pending(&self) -> bool {
+        self.any_pending.load(Ordering::Relaxed)
+    }
+
+    /// Forget all slots (call when the whole graph is replaced)
+    pub fn clear(&self) {
+        self.any_pending.store(false, Ordering::Relaxed);
+        for slot in self.slots.iter() {
+            slot.pending.store(0, Ordering::Relaxed);
+            slot.key.store(EMPTY, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drained(mailbox: &ParamMailbox) -> Vec<(u32, EdgeParamUpdate)> {
+        let mut out = Vec::new();
+        mailbox.drain(|id, update| out.push((id.raw(), update)));
+        out.sort_by_key(|(id, _)| *id);
+        out
+    }
+
+    #[test]
+    fn test_latest_value_wins() {
+        let mailbox = ParamMailbox::new();
+        for i in 0..100 {
+            assert!(mailbox.post(
+                EdgeId::new(7),
+                EdgeParamUpdate {
+                    gain: Some(i as f32 / 100.0),
+                    ..Default::default()
+                }
+            ));
+        }
+        mailbox.post(
+            EdgeId::new(7),
+            EdgeParamUpdate {
+                muted: Some(true),
+                ..Default::default()
+            },
+        );
+        assert!(mailbox.has_pending());
+
+        let updates = drained(&mailbox);
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].1,
+            EdgeParamUpdate {
+                gain: Some(0.99),
+                muted: Some(true),
+                pan: None,
+            }
+        );
+        // Drained once
+        assert!(!mailbox.has_pending());
+        assert!(drained(&mailbox).is_empty());
+    }
+
+    #[test]
+    fn test_full_mailbox_and_clear() {
+        let mailbox = ParamMailbox::new();
+        let pan = EdgeParamUpdate {
+            pan: Some(-0.5),
+            ..Default::default()
+        };
+        for id in 1..=MAILBOX_SLOTS as u32 {
+            assert!(mailbox.post(EdgeId::new(id), pan));
+        }
+        assert!(!mailbox.post(EdgeId::new(MAILBOX_SLOTS as u32 + 1), pan));
+        // Known edges still fit
+        assert!(mailbox.post(EdgeId::new(3), pan));
+        assert_eq!(drained(&mailbox).len(), MAILBOX_SLOTS);
+
+        mailbox.clear();
+        assert!(mailbox.post(EdgeId::new(MAILBOX_SLOTS as u32 + 1), pan));
+        assert_eq!(drained(&mailbox), vec![(MAILBOX_SLOTS as u32 + 1, pan)]);
+    }
+}
@@ -0,0 +1,262 @@
+//! Plugin Source Node - Generator / instrument AudioUnits as graph sources
+//!
+//! ジェネレーター ('augn') とインストゥルメント ('aumu') の AU をバスのエフェクトではなく
+//! ソースとして使う。毎コールバック無音を入力として AU のレンダーブロックを呼び、
+//! その出力をグラフへ流す。
+//!
+//! インスタンスはバスのプラグインと同じく AudioUnitManager が持つので、パラメータ・UI・
+//! fullState の保存/復元・クラッシュ時の再起動はすべて instance_id で共通に扱える。
+//! AU はステレオで設定するため、出力は常に 2 ポート。
+
+use super::buffer::AudioBuffer;
+use super::node::{AudioNode, NodeType, PortId};
+use crate::audio_unit::{get_au_manager, AudioUnitInstance};
+use crate::logging::Level;
+use std::any::Any;
+use std::sync::Arc;
+
+/// Output ports of a plugin source (the AU renders stereo)
+pub const PLUGIN_SOURCE_PORTS: usize = 2;
+
+/// AudioUnit ソースノード
+pub struct PluginSourceNode {
+    /// ノードの識別子
+    source_id: String,
+    /// 表示ラベル
+    label: String,
+    /// 出力バッファ（L/R）
+    output_buffers: Vec<AudioBuffer>,
+    instance_id: String,
+    plugin_id: String,
+    name: String,
+    manufacturer: String,
+    /// false の間は無音を出力する（AU は呼ばない）
+    enabled: bool,
+    /// Cached AudioUnit instance for lock-free audio processing
+    au_instance: Option<Arc<AudioUnitInstance>>,
+}
+
+impl PluginSourceNode {
+    /// Create a source for an instance that already exists in the AU manager
+    pub fn new(
+        source_id: impl Into<String>,
+        label: impl Into<String>,
+        instance_id: impl Into<String>,
+        plugin_id: impl Into<String>,
+        name: impl Into<String>,
+        manufacturer: impl Into<String>,
+    ) -> Self {
+        let instance_id = instance_id.into();
+        Self {
+            source_id: source_id.into(),
+            label: label.into(),
+            output_buffers: (0..PLUGIN_SOURCE_PORTS)
+                .map(|_| AudioBuffer::new())
+                .collect(),
+            au_instance: get_au_manager().get_instance(&instance_id),
+            instance_id,
+            plugin_id: plugin_id.into(),
+            name: name.into(),
+            manufacturer: manufacturer.into(),
+            enabled: true,
+        }
+    }
+
+    /// Get the source ID
+    pub fn source_id(&self) -> &str {
+        &self.source_id
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// AudioUnit instance ID (for parameters, UI and state)
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn plugin_id(&self) -> &str {
+        &self.plugin_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn manufacturer(&self) -> &str {
+        &self.manufacturer
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Mute the plugin (outputs silence without rendering)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Whether the AudioUnit's process died (the source is silent)
+    pub fn is_crashed(&self) -> bool {
+        self.au_instance
+            .as_ref()
+            .map(|au| au.is_crashed())
+            .unwrap_or(false)
+    }
+
+    /// Whether the AudioUnit kept overrunning its render budget (the source is silent)
+    pub fn is_overloaded(&self) -> bool {
+        self.au_instance
+            .as_ref()
+            .map(|au| au.is_overloaded())
+            .unwrap_or(false)
+    }
+
+    /// Whether the AudioUnit runs out-of-process
+    pub fn is_out_of_process(&self) -> bool {
+        self.au_instance
+            .as_ref()
+            .map(|au| au.is_out_of_process())
+            .unwrap_or(false)
+    }
+
+    /// Re-fetch the AudioUnit after `instance_id` was restarted (false if it is not ours)
+    pub fn refresh_plugin(&mut self, instance_id: &str) -> bool {
+        if self.instance_id != instance_id {
+            return false;
+        }
+        self.au_instance = get_au_manager().get_instance(&self.instance_id);
+        true
+    }
+}
+
+impl AudioNode for PluginSourceNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Source
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        0
+    }
+
+    fn output_port_count(&self) -> usize {
+        self.output_buffers.len()
+    }
+
+    fn input_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn input_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn output_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.output_buffers.get(port.index())
+    }
+
+    fn output_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.output_buffers.get_mut(port.index())
+    }
+
+    fn process(&mut self, frames: usize) {
+        // 入力は無音（ジェネレーター/インストゥルメントは読まない）
+        for buf in &mut self.output_buffers {
+            buf.clear(frames);
+        }
+
+        if self.enabled {
+            if let Some(ref au) = self.au_instance {
+                let [left, right] = &mut self.output_buffers[..] else {
+                    return;
+                };
+                if au
+                    .process(left.samples_mut(), right.samples_mut(), 0.0)
+                    .is_err()
+                {
+                    // 中途半端な出力は流さない (audio thread: no formatting here)
+                    rt_log!(Level::Error, "[PluginSource] Plugin process error");
+                    left.clear(frames);
+                    right.clear(frames);
+                }
+            }
+        }
+
+        for buf in &mut self.output_buffers {
+            buf.update_meters();
+        }
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.output_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn latency_samples(&self) -> u32 {
+        match self.au_instance {
+            Some(ref au) if self.enabled => au.latency_samples(),
+            _ => 0,
+        }
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        Vec::new()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        self.output_buffers
+            .iter()
+            .map(|b| b.cached_peak())
+            .collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_source_without_instance_is_silent_stereo() {
+        let mut node = PluginSourceNode::new(
+            "psrc",
+            "Synth",
+            "au_missing",
+            "aumu:test:test",
+            "Test Synth",
+            "Test",
+        );
+        assert_eq!(node.output_port_count(), PLUGIN_SOURCE_PORTS);
+        assert_eq!(node.input_port_count(), 0);
+
+        node.process(256);
+        for port in 0..PLUGIN_SOURCE_PORTS as u8 {
+            let buf = node.output_buffer(PortId::new(port)).unwrap();
+            assert_eq!(buf.valid_frames(), 256);
+            assert!(buf.samples().iter().all(|&s| s == 0.0));
+        }
+        assert_eq!(node.output_peak_levels(), vec![0.0; PLUGIN_SOURCE_PORTS]);
+        assert!(!node.is_crashed());
+    }
+
+    #[test]
+    fn test_plugin_source_refreshes_only_its_own_instance() {
+        let mut node = PluginSourceNode::new("psrc", "Synth", "au_1", "id", "n", "m");
+        assert!(!node.refresh_plugin("au_2"));
+        assert!(node.refresh_plugin("au_1"));
+    }
+}
@@ -1,18 +1,52 @@
 //! Graph Processor - Audio processing engine
 
-use super::edge::EdgeId;
-use super::graph::AudioGraph;
-use super::meters::{EdgeMeter, GraphMeters, NodeMeter, PortMeter};
+use super::automation::{AutomationCycle, GainSegment, MAX_BLOCK_SEGMENTS};
+use super::buffer::{self, AudioBuffer};
+use super::command_queue::CommandQueue;
+use super::edge::{
+    gain_ramp_step, CueTap, Edge, EdgeId, PanLaw, SumLaw, TapPoint, DEFAULT_GAIN_RAMP_MS,
+};
+use super::graph::{AudioGraph, LiveState};
+use super::meter_history::{HistoryKey, MeterHistory};
+use super::meters::{
+    EdgeMeter, GraphMeters, MeterBallistics, MeterBallisticsState, NodeMeter, PortMeter,
+};
 use super::node::{AudioNode, NodeHandle, NodeType, PortId};
-use super::source::SourceId;
+use super::param_mailbox::{EdgeParamUpdate, ParamMailbox};
+use super::schedule::{RenderOp, RenderSchedule};
+use super::source::{SourceId, SourceNode};
+use super::topology::Topology;
+use crate::vdsp::VDsp;
 use arc_swap::ArcSwap;
-use parking_lot::RwLock;
-use std::sync::atomic::{AtomicU64, Ordering};
+use parking_lot::{Mutex, RwLock};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Largest metering rate divisor (meters every N blocks)
+pub const MAX_METER_RATE_DIVISOR: u32 = 64;
+
+/// How long a queued graph command waits for the audio thread before the caller runs it
+const COMMAND_WAIT: Duration = Duration::from_millis(50);
+
+/// The audio thread counts as running if it processed a block within this window
+const RENDER_ALIVE_WINDOW: Duration = Duration::from_millis(100);
 
 /// グラフプロセッサ
 ///
 /// オーディオコールバックから呼び出され、グラフ全体を処理
+///
+/// グラフの変更（ノード・エッジの追加/削除、`with_graph_mut`）はオーディオスレッドが
+/// 動いている間はコマンドキューに積まれ、ブロックの頭で適用される。API スレッドが
+/// 書き込みロックを取らないので、変更のたびにブロックを落とすことがない。
+///
+/// オーディオスレッドで走るのは差し替えだけにする: ノードの構築・ファイルのオープン・
+/// ライタースレッドの起動は呼び出し側で済ませてから渡し、プラグインのレイテンシは
+/// キャッシュを読む。トポロジーを変えた変更は構造を写すだけにし、処理順・スケジュール・
+/// 補正のディレイラインは呼び出し側でコンパイルして次のブロックで差し替える
+/// （[`topology`](super::topology)）。削除したノードと外れた古いスケジュールは
+/// [`reclaim`](super::reclaim) スレッド、差し替えたグラフは呼び出し側で drop する。
 pub struct GraphProcessor {
     /// The audio graph (RwLock for synchronized access)
     /// For realtime-safe processing, we use ArcSwap for reads
@@ -25,11 +59,41 @@ pub struct GraphProcessor {
     timestamp: AtomicU64,
     /// Edge meters (accumulated during processing)
     edge_meters: Arc<ArcSwap<Vec<(EdgeId, f32)>>>,
+    /// Edge gain ramp time in ms (f32 bits)
+    gain_ramp_ms_bits: AtomicU32,
+    /// Meter ballistics (applied on the audio thread)
+    meter_ballistics: ArcSwap<MeterBallistics>,
+    ballistics_state: Mutex<MeterBallisticsState>,
+    /// Clear latched clip indicators on the next update
+    reset_clips: AtomicBool,
+    /// Short peak history (audio thread writes with try_lock)
+    meter_history: Mutex<MeterHistory>,
+    /// Offline render (bounce) in progress: live processing and sink output pause
+    offline: AtomicBool,
+    /// Live processing state set aside for the offline render
+    offline_state: Mutex<Option<LiveState>>,
+    /// メーターを計算するブロック間隔（1 = 毎ブロック、0 = 計算しない）
+    meter_rate_divisor: AtomicU32,
+    /// Blocks since the last meter update
+    meter_blocks: AtomicU32,
+    /// Coalesced gain/mute/pan updates, applied at the start of each block
+    params: ParamMailbox,
+    /// Sorted ids of the edges in the graph (lets `post_edge_params` validate without the lock)
+    live_edges: ArcSwap<Vec<u32>>,
+    /// Graph mutations applied by the audio thread at the start of each block
+    commands: CommandQueue<AudioGraph>,
+    /// Structure captured by topology changes (the lock also serializes them)
+    topology: Mutex<Topology>,
+    /// Reference point of `last_block_ns`
+    started: Instant,
+    /// Nanoseconds since `started` at the last processed block (0 = never)
+    last_block_ns: AtomicU64,
 }
 
 impl GraphProcessor {
     /// Create a new graph processor
     pub fn new() -> Self {
+        super::reclaim::init();
         let graph = AudioGraph::new();
         Self {
             graph: Arc::new(RwLock::new(AudioGraph::new())),
@@ -37,9 +101,103 @@ impl GraphProcessor {
             meters: Arc::new(ArcSwap::from_pointee(GraphMeters::new())),
             timestamp: AtomicU64::new(0),
             edge_meters: Arc::new(ArcSwap::from_pointee(Vec::new())),
+            gain_ramp_ms_bits: AtomicU32::new(DEFAULT_GAIN_RAMP_MS.to_bits()),
+            meter_ballistics: ArcSwap::from_pointee(MeterBallistics::default()),
+            ballistics_state: Mutex::new(MeterBallisticsState::new()),
+            reset_clips: AtomicBool::new(false),
+            meter_history: Mutex::new(MeterHistory::new()),
+            offline: AtomicBool::new(false),
+            offline_state: Mutex::new(None),
+            meter_rate_divisor: AtomicU32::new(1),
+            meter_blocks: AtomicU32::new(0),
+            params: ParamMailbox::new(),
+            live_edges: ArcSwap::from_pointee(Vec::new()),
+            commands: CommandQueue::new(),
+            topology: Mutex::new(Topology::default()),
+            started: Instant::now(),
+            last_block_ns: AtomicU64::new(0),
+        }
+    }
+
+    /// Edge gain ramp time in milliseconds
+    pub fn gain_ramp_ms(&self) -> f32 {
+        f32::from_bits(self.gain_ramp_ms_bits.load(Ordering::Relaxed))
+    }
+
+    /// Set edge gain ramp time in milliseconds (0 = instant)
+    pub fn set_gain_ramp_ms(&self, ms: f32) {
+        let ms = if ms.is_finite() {
+            ms.clamp(0.0, 1000.0)
+        } else {
+            DEFAULT_GAIN_RAMP_MS
+        };
+        self.gain_ramp_ms_bits
+            .store(ms.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Current meter ballistics
+    pub fn meter_ballistics(&self) -> MeterBallistics {
+        **self.meter_ballistics.load()
+    }
+
+    /// Set meter ballistics (returns the applied values)
+    pub fn set_meter_ballistics(&self, ballistics: MeterBallistics) -> MeterBallistics {
+        let ballistics = ballistics.clamped();
+        self.meter_ballistics.store(Arc::new(ballistics));
+        ballistics
+    }
+
+    /// Meters are computed every this many blocks (0 = metering off)
+    pub fn meter_rate_divisor(&self) -> u32 {
+        self.meter_rate_divisor.load(Ordering::Relaxed)
+    }
+
+    /// Set the metering rate divisor (clamped to 0..=MAX; returns the applied value)
+    pub fn set_meter_rate_divisor(&self, divisor: u32) -> u32 {
+        let divisor = divisor.min(MAX_METER_RATE_DIVISOR);
+        self.meter_rate_divisor.store(divisor, Ordering::Relaxed);
+        divisor
+    }
+
+    /// Count this block and decide whether it computes meters
+    ///
+    /// Returns the number of blocks the meter update covers (None = skip metering).
+    fn next_meter_block(&self) -> Option<u32> {
+        match self.meter_rate_divisor() {
+            0 => {
+                self.meter_blocks.store(0, Ordering::Relaxed);
+                None
+            }
+            divisor => {
+                let blocks = self.meter_blocks.fetch_add(1, Ordering::Relaxed) + 1;
+                if blocks < divisor {
+                    return None;
+                }
+                self.meter_blocks.store(0, Ordering::Relaxed);
+                Some(blocks)
+            }
         }
     }
 
+    /// Clear latched clip indicators
+    pub fn reset_meter_clips(&self) {
+        self.reset_clips.store(true, Ordering::Relaxed);
+    }
+
+    /// Peak history of the given meters (oldest first) and the current frame index
+    pub fn meter_history(
+        &self,
+        keys: &[HistoryKey],
+        max_frames: usize,
+    ) -> (u64, Vec<(HistoryKey, Vec<f32>)>) {
+        let history = self.meter_history.lock();
+        let entries = keys
+            .iter()
+            .filter_map(|&key| history.get(key, max_frames).map(|v| (key, v)))
+            .collect();
+        (history.frame_index(), entries)
+    }
+
     /// Get a reference to the graph snapshot (for non-realtime operations)
     pub fn graph(&self) -> Arc<AudioGraph> {
         self.graph_snapshot.load_full()
@@ -47,22 +205,41 @@ impl GraphProcessor {
 
     /// Add a node to the graph
     pub fn add_node(&self, node: Box<dyn AudioNode>) -> NodeHandle {
-        let mut graph = self.graph.write();
-        let handle = graph.add_node(node);
-        graph.rebuild_order_if_needed();
-        self.update_snapshot(&graph);
-        handle
+        self.mutate_structure(|graph| graph.add_node(node))
+    }
+
+    /// Add a node under a given handle (falls back to a new handle when it is taken)
+    pub fn add_node_at(&self, handle: NodeHandle, node: Box<dyn AudioNode>) -> NodeHandle {
+        self.mutate_structure(|graph| graph.add_node_at(handle, node))
     }
 
     /// Remove a node from the graph
     pub fn remove_node(&self, handle: NodeHandle) -> bool {
-        let mut graph = self.graph.write();
-        let result = graph.remove_node(handle);
-        if result {
-            graph.rebuild_order_if_needed();
-            self.update_snapshot(&graph);
-        }
-        result
+        self.mutate_structure(|graph| graph.remove_node(handle))
+    }
+
+    /// Set or clear the display color of a node (no rebuild)
+    pub fn set_node_color(&self, handle: NodeHandle, color: Option<String>) -> bool {
+        self.mutate(|graph| graph.set_node_color(handle, color))
+    }
+
+    /// Set (Some) or remove (None) one metadata entry of a node (no rebuild)
+    pub fn set_node_metadata(
+        &self,
+        handle: NodeHandle,
+        key: String,
+        value: Option<String>,
+    ) -> bool {
+        self.mutate(|graph| graph.set_node_metadata(handle, key, value))
+    }
+
+    /// Replace all metadata of a node (no rebuild)
+    pub fn replace_node_metadata(
+        &self,
+        handle: NodeHandle,
+        metadata: BTreeMap<String, String>,
+    ) -> bool {
+        self.mutate(|graph| graph.replace_node_metadata(handle, metadata))
     }
 
     /// Add an edge to the graph
@@ -75,24 +252,37 @@ impl GraphProcessor {
         gain: f32,
         muted: bool,
     ) -> Option<EdgeId> {
-        let mut graph = self.graph.write();
-        let edge_id =
-            graph.add_edge_with_params(source, source_port, target, target_port, gain, muted);
-        if edge_id.is_some() {
-            graph.rebuild_order_if_needed();
-            self.update_snapshot(&graph);
-        }
-        edge_id
+        self.add_bundle_edge(source, source_port, target, target_port, 1, gain, muted)
+    }
+
+    /// Add a channel bundle edge (`channels` adjacent ports with one gain/mute)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_bundle_edge(
+        &self,
+        source: NodeHandle,
+        source_port: PortId,
+        target: NodeHandle,
+        target_port: PortId,
+        channels: u8,
+        gain: f32,
+        muted: bool,
+    ) -> Option<EdgeId> {
+        self.mutate_structure(|graph| {
+            graph.add_bundle_edge_with_params(
+                source,
+                source_port,
+                target,
+                target_port,
+                channels,
+                gain,
+                muted,
+            )
+        })
     }
 
     /// Remove an edge from the graph
     pub fn remove_edge(&self, edge_id: EdgeId) -> bool {
-        let mut graph = self.graph.write();
-        let result = graph.remove_edge(edge_id);
-        if result {
-            self.update_snapshot(&graph);
-        }
-        result
+        self.mutate_structure(|graph| graph.remove_edge(edge_id))
     }
 
     /// Set edge gain (hot path - uses RwLock for now, optimize later)
@@ -107,6 +297,62 @@ impl GraphProcessor {
         graph.set_edge_muted_atomic(edge_id, muted)
     }
 
+    /// Set edge pan (and pan law if given)
+    pub fn set_edge_pan(&self, edge_id: EdgeId, pan: f32, law: Option<PanLaw>) -> bool {
+        let graph = self.graph.read();
+        let Some(edge) = graph.get_edge(edge_id) else {
+            return false;
+        };
+        if let Some(law) = law {
+            edge.set_pan_law(law);
+        }
+        edge.set_pan(pan);
+        true
+    }
+
+    /// Set the sum law used when a bundle folds into a narrower target
+    pub fn set_edge_sum_law(&self, edge_id: EdgeId, law: SumLaw) -> bool {
+        let graph = self.graph.read();
+        let Some(edge) = graph.get_edge(edge_id) else {
+            return false;
+        };
+        edge.set_sum_law(law);
+        true
+    }
+
+    /// Set per-channel trim on a bundle edge
+    pub fn set_edge_channel_trim(&self, edge_id: EdgeId, channel: usize, trim: f32) -> bool {
+        let graph = self.graph.read();
+        graph.set_edge_channel_trim(edge_id, channel, trim)
+    }
+
+    /// Set gain / mute / pan of an edge through the mailbox (false if the edge does not exist)
+    ///
+    /// オーディオスレッドが動いていれば次のブロックの頭でまとめて反映する（ロックを取らない）。
+    /// 止まっているか mailbox が埋まっていれば、その場でロックを取って反映する。
+    pub fn post_edge_params(&self, edge_id: EdgeId, update: EdgeParamUpdate) -> bool {
+        if self
+            .live_edges
+            .load()
+            .binary_search(&edge_id.raw())
+            .is_err()
+        {
+            return false;
+        }
+        // バウンス中もライブ処理の再開まで mailbox に溜める（レンダリングには混ぜない）
+        let queued = self.render_thread_alive() || self.is_rendering_offline();
+        if queued && self.params.post(edge_id, update) {
+            return true;
+        }
+        let graph = self.graph.read();
+        apply_edge_params(&graph, edge_id, update)
+    }
+
+    /// Drop queued edge updates (the whole graph is being replaced)
+    pub fn clear_pending_edge_params(&self) {
+        self.params.clear();
+    }
+
     /// Batch update edge gains
     pub fn set_edge_gains_batch(&self, updates: &[(EdgeId, f32)]) -> usize {
         let graph = self.graph.read();
@@ -119,34 +365,79 @@ impl GraphProcessor {
         count
     }
 
-    /// Update the snapshot for audio thread
-    fn update_snapshot(&self, graph: &AudioGraph) {
-        // Note: This creates a new AudioGraph which is not ideal
-        // For now, we store the edges and recreate - proper solution needs Clone for AudioGraph
-        // This is a temporary workaround
-        let snapshot = self.create_snapshot(graph);
-        self.graph_snapshot.store(Arc::new(snapshot));
+    /// Publish the edge ids checked by `post_edge_params` (control thread)
+    fn publish_live_edges(&self, mut edge_ids: Vec<u32>) {
+        edge_ids.sort_unstable();
+        self.live_edges.store(Arc::new(edge_ids));
     }
 
-    /// Create a snapshot of the graph (temporary workaround)
-    fn create_snapshot(&self, _graph: &AudioGraph) -> AudioGraph {
-        // For now, return a new empty graph
-        // TODO: Implement proper graph cloning
-        // The issue is that Box<dyn AudioNode> is not Clone
-        // Solutions:
-        // 1. Store node state separately from processing buffers
-        // 2. Use Arc<dyn AudioNode> instead of Box
-        // 3. Implement a custom clone mechanism
-        AudioGraph::new()
+    /// Replace the entire graph
+    ///
+    /// スケジュールのコンパイルとレイテンシ補正は差し替え前にここで済ませ、オーディオ
+    /// スレッドではポインタの入れ替えだけを行う。古いグラフは呼び出し側のスレッドで drop する。
+    pub fn set_graph(&self, mut graph: AudioGraph) {
+        let mut topology = self.topology.lock();
+        graph.capture_topology(&mut topology);
+        graph.install(topology.compile());
+        let edge_ids = topology.edge_ids();
+        topology.clear();
+
+        let old = self.mutate(move |current| {
+            self.params.clear();
+            std::mem::replace(current, graph)
+        });
+        self.publish_live_edges(edge_ids);
+        drop(old);
     }
 
-    /// Replace the entire graph
-    pub fn set_graph(&self, graph: AudioGraph) {
-        let mut current = self.graph.write();
-        *current = graph;
-        current.rebuild_order_if_needed();
-        // For now, just create an empty snapshot (temporary)
-        self.graph_snapshot.store(Arc::new(AudioGraph::new()));
+    /// Whether the audio thread processed a block recently (and will pick up commands)
+    pub fn render_thread_alive(&self) -> bool {
+        // バウンス中は誰もキューを処理しないので、呼び出し側がロックを取って適用する
+        if self.is_rendering_offline() {
+            return false;
+        }
+        let last = self.last_block_ns.load(Ordering::Acquire);
+        let now = self.started.elapsed().as_nanos() as u64;
+        last != 0 && now.saturating_sub(last) < RENDER_ALIVE_WINDOW.as_nanos() as u64
+    }
+
+    /// Apply a mutation at the next block boundary and return its result
+    ///
+    /// オーディオスレッドが止まっている（出力なし）か、待ち時間内に拾われなければ、
+    /// 呼び出し側がロックを取ってその場で実行する。どちらの経路でも 1 回だけ実行される。
+    fn mutate<R: Send>(&self, f: impl FnOnce(&mut AudioGraph) -> R + Send) -> R {
+        if !self.render_thread_alive() {
+            let mut graph = self.graph.write();
+            return f(&mut graph);
+        }
+        self.commands
+            .run(f, COMMAND_WAIT, |mut task| task(&mut self.graph.write()))
+    }
+
+    /// [`mutate`](Self::mutate) for topology changes: recompiles the order, schedule and
+    /// latency compensation here and swaps them in at the next block boundary
+    ///
+    /// 変更を適用したブロックでは構造を予約済みのバッファに写すだけにし、コンパイルと
+    /// エッジ id の公開は呼び出し側で行う。戻るのは差し替えが済んでから。
+    fn mutate_structure<R: Send>(&self, f: impl FnOnce(&mut AudioGraph) -> R + Send) -> R {
+        let mut topology = self.topology.lock();
+        topology.reserve();
+        let captured: &mut Topology = &mut topology;
+        let (result, changed) = self.mutate(|graph| {
+            let result = f(graph);
+            let changed = graph.is_dirty();
+            if changed {
+                graph.capture_topology(captured);
+            }
+            (result, changed)
+        });
+        if changed {
+            let compiled = topology.compile();
+            self.mutate(move |graph| graph.install(compiled));
+            self.publish_live_edges(topology.edge_ids());
+            topology.clear();
+        }
+        result
     }
 
     /// Get current meters (lock-free read)
@@ -163,15 +454,97 @@ impl GraphProcessor {
         f(&graph)
     }
 
-    /// Execute with write access to the graph
+    /// Execute with read access unless the graph is locked (for audio callbacks)
+    pub fn try_with_graph<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&AudioGraph) -> R,
+    {
+        let graph = self.graph.try_read()?;
+        Some(f(&graph))
+    }
+
+    /// Whether the graph lock can be taken within `timeout` (watchdog deadlock probe)
+    pub fn is_graph_responsive(&self, timeout: Duration) -> bool {
+        self.graph.try_read_for(timeout).is_some()
+    }
+
+    /// Execute with write access to the graph (at the next block boundary)
     pub fn with_graph_mut<F, R>(&self, f: F) -> R
     where
-        F: FnOnce(&mut AudioGraph) -> R,
+        F: FnOnce(&mut AudioGraph) -> R + Send,
+        R: Send,
     {
+        self.mutate_structure(|graph| {
+            let result = f(graph);
+            // プラグイン構成の変更でレイテンシが変わりうるため補正も再コンパイルする
+            graph.mark_dirty();
+            result
+        })
+    }
+
+    /// Recompile latency compensation after a plugin reported a new latency
+    ///
+    /// レイテンシはキャッシュから読むので、構造を写し直してコンパイルし直すだけ。
+    pub fn refresh_latency_compensation(&self) {
+        self.with_graph_mut(|_| ());
+    }
+
+    /// Whether an offline render (bounce) owns the graph
+    pub fn is_rendering_offline(&self) -> bool {
+        self.offline.load(Ordering::Acquire)
+    }
+
+    /// オフラインレンダリングを開始（既にレンダリング中なら false）
+    ///
+    /// 終了するまでオーディオコールバックはグラフを処理せず無音を出力する。ライブの
+    /// 処理状態（ゲインランプ・補正ディレイ・ノードの DSP 状態）は脇に置き、オフラインは
+    /// 落ち着いた新しい状態から始める（[`AudioGraph::take_live_state`]）。
+    pub fn begin_offline_render(&self) -> bool {
+        if self
+            .offline
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return false;
+        }
+        let state = self.graph.write().take_live_state();
+        *self.offline_state.lock() = Some(state);
+        true
+    }
+
+    /// オフラインレンダリングを終了し、脇に置いた状態を戻してライブ処理に戻す
+    pub fn end_offline_render(&self) {
+        let state = self.offline_state.lock().take();
+        if let Some(state) = state {
+            self.graph.write().restore_live_state(state);
+        }
+        self.offline.store(false, Ordering::Release);
+    }
+
+    /// オフラインで 1 ブロック処理し、ロックを保持したまま `read` に結果を渡す
+    ///
+    /// ロックはブロックごとに取り直すので、レンダリング中も UI や watchdog は止まらない。
+    /// ライブの状態には触れない: 開始時のスケジュールで処理し、コマンドキューと mailbox は
+    /// ライブ処理の再開まで残す。ライブ I/O のノード（[`AudioNode::is_live_io`]）は処理せず、
+    /// ダッキングとレンダリング位置（パンチ/マーカーの時間軸）も進めない。
+    pub fn render_offline_block<R>(
+        &self,
+        frames: usize,
+        read_source_fn: impl Fn(&SourceId, &mut [f32]),
+        read: impl FnOnce(&AudioGraph) -> R,
+    ) -> R {
+        let state = self.offline_state.lock();
         let mut graph = self.graph.write();
-        let result = f(&mut graph);
-        self.update_snapshot(&graph);
-        result
+        match state.as_ref() {
+            Some(state) => {
+                Self::process_graph(&mut graph, state.schedule(), frames, read_source_fn);
+            }
+            None => {
+                let schedule = graph.schedule();
+                Self::process_graph(&mut graph, &schedule, frames, read_source_fn);
+            }
+        }
+        read(&graph)
     }
 
     /// オーディオ処理を実行
@@ -179,96 +552,174 @@ impl GraphProcessor {
     /// Called from audio callback. Uses write lock for mutable access.
     /// In realtime-critical scenarios, consider double-buffering.
     pub fn process(&self, frames: usize, read_source_fn: &dyn Fn(&SourceId, &mut [f32])) {
+        // バウンス中はオフラインドライバーがグラフを進める
+        if self.is_rendering_offline() {
+            return;
+        }
+
         // Get write access for processing
         let Some(mut graph) = self.graph.try_write() else {
             return; // Skip if locked
         };
+        self.last_block_ns.store(
+            (self.started.elapsed().as_nanos() as u64).max(1),
+            Ordering::Release,
+        );
+
+        // API から届いたグラフの変更を積まれた順に適用（コンパイル済みの差し替えを含む）
+        self.commands.run_pending(&mut graph);
+
+        // API から届いたゲイン/ミュート/パンの最新値をまとめて反映
+        self.params.drain(|edge_id, update| {
+            apply_edge_params(&graph, edge_id, update);
+        });
+
+        let schedule = graph.schedule();
+        let edges = schedule.edges();
 
-        graph.rebuild_order_if_needed();
+        // メーターを計算するブロックか（分周・停止中はピーク/RMS の計算ごと省く）
+        let meter_blocks = self.next_meter_block();
+        let metering = meter_blocks.is_some();
 
         // 1. すべてのノードのバッファをクリア
-        for handle in graph.processing_order().to_vec() {
+        for &handle in schedule.nodes() {
             if let Some(node) = graph.get_node_mut(handle) {
                 node.clear_buffers(frames);
             }
         }
 
-        // 2. ソースノードの読み込み
-        use super::source::SourceNode;
-        for handle in graph.source_nodes().collect::<Vec<_>>() {
-            if let Some(node) = graph.get_node_mut(handle) {
-                // Downcast to get source_id
-                if let Some(source) = node.as_any_mut().downcast_mut::<SourceNode>() {
-                    let base_source_id = source.source_id().clone();
-                    // Read each output port
-                    for port_idx in 0..source.output_port_count() {
-                        if let Some(buf) = source.output_buffer_mut(PortId::new(port_idx as u8)) {
-                            let samples = buf.samples_mut();
-                            // SourceNode はステレオ(複数ポート)を持つが、source_id はベース(左ch)のみを保持している。
-                            // 各ポートで channel を port_idx 分オフセットして読み分ける。
-                            let source_id = match &base_source_id {
-                                SourceId::PrismChannel { channel } => SourceId::PrismChannel {
-                                    channel: channel.saturating_add(port_idx as u8),
-                                },
-                                SourceId::InputDevice { device_id, channel } => {
-                                    SourceId::InputDevice {
-                                        device_id: *device_id,
-                                        channel: channel.saturating_add(port_idx as u8),
-                                    }
-                                }
-                            };
-                            read_source_fn(&source_id, samples);
-                            buf.set_valid_frames(frames);
-                            buf.update_meters();
-                        }
-                    }
-                }
-            }
-        }
-
-        // 3. トポロジカル順でノードを処理
-        let processing_order = graph.processing_order().to_vec();
-        let edges = graph.edges().to_vec();
-
-        // Collect edge meters during processing
-        let mut edge_meter_data: Vec<(EdgeId, f32)> = Vec::new();
+        // Collect edge meters during processing (only on metering blocks)
+        let mut edge_meter_data: Vec<(EdgeId, f32)> = if metering {
+            Vec::with_capacity(edges.len())
+        } else {
+            Vec::new()
+        };
 
-        for &handle in &processing_order {
-            // 3a. このノードへの入力を集約（エッジからミックス）
-            for edge in edges.iter().filter(|e| e.target == handle && e.is_active()) {
-                let Some((source_node, target_node)) =
-                    graph.get_two_nodes_mut(edge.source, edge.target)
-                else {
-                    continue;
-                };
+        // ゲインランプの 1 サンプルあたりの最大変化量
+        let sample_rate = super::engine_sample_rate();
+        let max_step = gain_ramp_step(self.gain_ramp_ms(), sample_rate);
 
-                let Some(source_buf) = source_node.output_buffer(edge.source_port) else {
-                    continue;
-                };
+        // スペクトラム解析タップ（なければ何もしない）
+        let taps = super::analyzer::active_taps();
 
-                let gain = edge.gain();
+        // 実行中のクロスフェードのゲインを書き込む（ブロック内はゲインランプで補間）
+        super::crossfade::process(&graph, frames, sample_rate);
 
-                // Calculate post-gain peak for metering
-                let post_gain_peak = source_buf.cached_peak() * gain.abs();
-                edge_meter_data.push((edge.id, post_gain_peak));
+        // エッジオートメーション（トランスポート停止中は None）
+        let automation = AutomationCycle::begin(frames, sample_rate);
+        let mut segments = [GainSegment::default(); MAX_BLOCK_SEGMENTS];
 
-                // Mix into target input buffer with gain applied (no allocations)
-                if let Some(tgt_buf) = target_node.input_buffer_mut(edge.target_port) {
-                    tgt_buf.mix_from(source_buf, gain);
+        // 2. スケジュールを順に実行（ソース読み込み → トポロジカル順のミックスと処理）
+        for &op in schedule.ops() {
+            match op {
+                RenderOp::ReadSource(handle) => {
+                    buffer::set_metering(metering && graph.is_node_metered(handle));
+                    if let Some(node) = graph.get_node_mut(handle) {
+                        read_source_node(node, frames, read_source_fn);
+                    }
+                }
+                RenderOp::MixEdge(index) => {
+                    // ゲイン/ミュート/ダイレクトモニターは Atomic なので実行時に見る
+                    let edge = &edges[index];
+                    if !edge.is_audible() || edge.is_direct() {
+                        continue;
+                    }
+                    let Some((source_node, target_node)) =
+                        graph.get_two_nodes_mut(edge.source, edge.target)
+                    else {
+                        continue;
+                    };
+
+                    // Automation writes the fader value; it is applied sample-accurately once the
+                    // running gain has caught up with the curve (otherwise the ramp chases it)
+                    let segment_count = match &automation {
+                        Some(cycle) => cycle.segments(edge.id, &mut segments),
+                        None => 0,
+                    };
+                    let automated = &segments[..segment_count];
+                    let follows = match (automated.first(), automated.last()) {
+                        (Some(first), Some(last)) => {
+                            edge.set_gain(last.end);
+                            edge.follow_automation(first.start, last.end, max_step * frames as f32)
+                        }
+                        _ => false,
+                    };
+
+                    // Advance the gain ramp toward the target (smooths fader moves and mutes)
+                    let (start_gain, end_gain) = if follows {
+                        (automated[0].start, automated[segment_count - 1].end)
+                    } else {
+                        edge.advance_gain(frames, max_step)
+                    };
+
+                    // Bundle edges carry several adjacent ports with one gain
+                    // (folded with the sum law when the target has fewer ports)
+                    let target_ports = target_node.input_port_count();
+                    let mut post_gain_peak = 0.0f32;
+                    for ch in 0..edge.channels as usize {
+                        let Some(source_buf) = tapped_buffer(source_node, edge, ch) else {
+                            continue;
+                        };
+                        let Some((port, fold)) = edge.fold_target(ch, target_ports) else {
+                            continue;
+                        };
+
+                        // Calculate post-gain peak for metering (0 when the source is not metered)
+                        post_gain_peak = post_gain_peak.max(
+                            source_buf.cached_peak()
+                                * end_gain.abs()
+                                * edge.channel_gain(ch)
+                                * fold,
+                        );
+
+                        // Mix into target input buffer with gain applied (no allocations)
+                        // Latency compensation delay is applied inside the edge
+                        if let Some(tgt_buf) = target_node.input_buffer_mut(port) {
+                            if follows {
+                                edge.mix_segments_into(ch, source_buf, tgt_buf, automated, fold);
+                            } else {
+                                let (start, end) = (start_gain * fold, end_gain * fold);
+                                edge.mix_into(ch, source_buf, tgt_buf, start, end);
+                            }
+                        }
+                    }
+                    if metering && graph.is_edge_metered(edge.id) {
+                        edge_meter_data.push((edge.id, post_gain_peak));
+                    }
+                }
+                // キューシンク: キューが有効なエッジの送り元を追加でミックス（処理順の最後）
+                RenderOp::MixCue(handle) => mix_cue_taps(&mut graph, edges, handle),
+                RenderOp::Process(handle) => {
+                    // ノードの処理を実行（処理時間を計測）
+                    buffer::set_metering(metering && graph.is_node_metered(handle));
+                    if let Some(node) = graph.get_node_mut(handle) {
+                        let started = Instant::now();
+                        node.process(frames);
+                        let elapsed = started.elapsed();
+                        if !taps.is_empty() {
+                            super::analyzer::feed_taps(&taps, handle, &*node);
+                        }
+                        if let Some(load) = graph.node_load(handle) {
+                            load.record(elapsed, frames, sample_rate);
+                        }
+                    }
                 }
-            }
-
-            // 3b. ノードの処理を実行
-            if let Some(node) = graph.get_node_mut(handle) {
-                node.process(frames);
             }
         }
 
-        // Store edge meters
-        self.edge_meters.store(Arc::new(edge_meter_data));
+        // ダッキング: このブロックのキーレベルから次のブロックの減衰量を決める
+        super::ducking::process(&graph, frames, sample_rate);
+        // パンチ/マーカーの時間軸はライブ出力だけで進める（オフラインレンダリングは含めない）
+        super::record::advance_render_frame(frames);
+
+        buffer::set_metering(true);
 
-        // 4. メーターを更新
-        self.update_meters_internal(&graph);
+        // 3. メーターを更新（間引いたブロックの分だけ時間を進める）
+        if let Some(blocks) = meter_blocks {
+            self.edge_meters.store(Arc::new(edge_meter_data));
+            let dt = (frames as u32 * blocks) as f32 / sample_rate as f32;
+            self.update_meters_internal(&graph, dt);
+        }
     }
 
     /// 簡易処理（グラフ直接操作版）
@@ -277,87 +728,88 @@ impl GraphProcessor {
     /// Use this when you have exclusive access to the graph.
     pub fn process_graph(
         graph: &mut AudioGraph,
+        schedule: &RenderSchedule,
         frames: usize,
         read_source_fn: impl Fn(&SourceId, &mut [f32]),
     ) -> Vec<(EdgeId, f32)> {
-        graph.rebuild_order_if_needed();
+        let edges = schedule.edges();
 
         // 1. すべてのノードのバッファをクリア
-        for handle in graph.processing_order().to_vec() {
+        for &handle in schedule.nodes() {
             if let Some(node) = graph.get_node_mut(handle) {
                 node.clear_buffers(frames);
             }
         }
 
-        // 2. ソースノードの読み込み
-        use super::source::SourceNode;
-        for handle in graph.source_nodes().collect::<Vec<_>>() {
-            if let Some(node) = graph.get_node_mut(handle) {
-                if let Some(source) = node.as_any_mut().downcast_mut::<SourceNode>() {
-                    let base_source_id = source.source_id().clone();
-                    for port_idx in 0..source.output_port_count() {
-                        if let Some(buf) = source.output_buffer_mut(PortId::new(port_idx as u8)) {
-                            let samples = buf.samples_mut();
-                            let source_id = match &base_source_id {
-                                SourceId::PrismChannel { channel } => SourceId::PrismChannel {
-                                    channel: channel.saturating_add(port_idx as u8),
-                                },
-                                SourceId::InputDevice { device_id, channel } => {
-                                    SourceId::InputDevice {
-                                        device_id: *device_id,
-                                        channel: channel.saturating_add(port_idx as u8),
-                                    }
-                                }
-                            };
-                            read_source_fn(&source_id, samples);
-                            buf.set_valid_frames(frames);
-                            buf.update_meters();
+        let mut edge_meter_data: Vec<(EdgeId, f32)> = Vec::with_capacity(edges.len());
+        let sample_rate = super::engine_sample_rate();
+        let max_step = gain_ramp_step(DEFAULT_GAIN_RAMP_MS, sample_rate);
+
+        // 2. スケジュールを順に実行（キューはオフラインでは鳴らさない）
+        for &op in schedule.ops() {
+            match op {
+                RenderOp::ReadSource(handle) => {
+                    if let Some(node) = graph.get_node_mut(handle) {
+                        read_source_node(node, frames, &read_source_fn);
+                    }
+                }
+                RenderOp::MixEdge(index) => {
+                    let edge = &edges[index];
+                    if !edge.is_audible() || edge.is_direct() {
+                        continue;
+                    }
+                    let Some((source_node, target_node)) =
+                        graph.get_two_nodes_mut(edge.source, edge.target)
+                    else {
+                        continue;
+                    };
+
+                    let (start_gain, end_gain) = edge.advance_gain(frames, max_step);
+                    let target_ports = target_node.input_port_count();
+                    let mut post_gain_peak = 0.0f32;
+                    for ch in 0..edge.channels as usize {
+                        let Some(source_buf) = tapped_buffer(source_node, edge, ch) else {
+                            continue;
+                        };
+                        let Some((port, fold)) = edge.fold_target(ch, target_ports) else {
+                            continue;
+                        };
+                        post_gain_peak = post_gain_peak.max(
+                            source_buf.cached_peak()
+                                * end_gain.abs()
+                                * edge.channel_gain(ch)
+                                * fold,
+                        );
+                        if let Some(tgt_buf) = target_node.input_buffer_mut(port) {
+                            let (start, end) = (start_gain * fold, end_gain * fold);
+                            edge.mix_into(ch, source_buf, tgt_buf, start, end);
                         }
                     }
+                    edge_meter_data.push((edge.id, post_gain_peak));
                 }
-            }
-        }
-
-        // 3. トポロジカル順でノードを処理
-        let processing_order = graph.processing_order().to_vec();
-        let edges = graph.edges().to_vec();
-        let mut edge_meter_data: Vec<(EdgeId, f32)> = Vec::new();
-
-        for &handle in &processing_order {
-            for edge in edges.iter().filter(|e| e.target == handle && e.is_active()) {
-                let Some((source_node, target_node)) =
-                    graph.get_two_nodes_mut(edge.source, edge.target)
-                else {
-                    continue;
-                };
-
-                let Some(source_buf) = source_node.output_buffer(edge.source_port) else {
-                    continue;
-                };
-
-                let gain = edge.gain();
-                let post_gain_peak = source_buf.cached_peak() * gain.abs();
-                edge_meter_data.push((edge.id, post_gain_peak));
-
-                if let Some(tgt_buf) = target_node.input_buffer_mut(edge.target_port) {
-                    tgt_buf.mix_from(source_buf, gain);
+                RenderOp::MixCue(_) => {}
+                RenderOp::Process(handle) => {
+                    // 録音・ネットワーク・ループバックはライブの入出力なので触らない
+                    if let Some(node) = graph.get_node_mut(handle).filter(|n| !n.is_live_io()) {
+                        node.process(frames);
+                    }
                 }
             }
-
-            if let Some(node) = graph.get_node_mut(handle) {
-                node.process(frames);
-            }
         }
 
+        // ダッキングの追従もしない（開始時の減衰量のまま。ライブの状態を動かさない）
         edge_meter_data
     }
 
-    fn update_meters_internal(&self, graph: &AudioGraph) {
+    fn update_meters_internal(&self, graph: &AudioGraph, dt: f32) {
         let mut meters = GraphMeters::new();
         meters.timestamp = self.timestamp.fetch_add(1, Ordering::Relaxed);
 
         // Collect node meters
         for handle in graph.processing_order() {
+            if !graph.is_node_metered(*handle) {
+                continue;
+            }
             if let Some(node) = graph.get_node(*handle) {
                 let mut node_meter = NodeMeter::new(*handle);
 
@@ -381,6 +833,20 @@ impl GraphProcessor {
             meters.edges.push(meter);
         }
 
+        // 履歴は生ピークで残す（バリスティクス適用前）
+        if let Some(mut history) = self.meter_history.try_lock() {
+            history.record(&meters, dt);
+        }
+
+        // バリスティクス（状態はオーディオスレッドのみが触る）
+        if let Some(mut state) = self.ballistics_state.try_lock() {
+            if self.reset_clips.swap(false, Ordering::Relaxed) {
+                state.reset_clips();
+            }
+            let ballistics = self.meter_ballistics.load();
+            meters.apply_ballistics(&mut state, &ballistics, dt);
+        }
+
         self.meters.store(Arc::new(meters));
     }
 
@@ -413,6 +879,110 @@ impl GraphProcessor {
     }
 }
 
+/// Fill a capture source's output ports from the input (generators and plugin sources are skipped)
+///
+/// SourceNode はステレオ(複数ポート)を持つが、source_id はベース(左ch)のみを保持している。
+/// 各ポートで channel を port_idx 分オフセットして読み分ける。
+fn read_source_node(
+    node: &mut dyn AudioNode,
+    frames: usize,
+    read_source_fn: &dyn Fn(&SourceId, &mut [f32]),
+) {
+    let Some(source) = node.as_any_mut().downcast_mut::<SourceNode>() else {
+        return;
+    };
+    let base_source_id = source.source_id().clone();
+    for port_idx in 0..source.output_port_count() {
+        let trim = source.signed_trim(port_idx);
+        let Some(buf) = source.output_buffer_mut(PortId::new(port_idx as u8)) else {
+            continue;
+        };
+        let samples = buf.samples_mut();
+        let source_id = match &base_source_id {
+            SourceId::PrismChannel { channel } => SourceId::PrismChannel {
+                channel: channel.saturating_add(port_idx as u8),
+            },
+            SourceId::InputDevice { device_id, channel } => SourceId::InputDevice {
+                device_id: *device_id,
+                channel: channel.saturating_add(port_idx as u8),
+            },
+        };
+        read_source_fn(&source_id, samples);
+        // 入力トリム/極性反転（エッジに分配する前）
+        if trim != 1.0 {
+            VDsp::apply_gain(samples, trim);
+        }
+        buf.set_valid_frames(frames);
+        buf.update_meters();
+    }
+}
+
+/// The source buffer an edge reads (a pre-plugin bus send reads the bus input)
+#[inline]
+fn tapped_buffer<'a>(source: &'a dyn AudioNode, edge: &Edge, ch: usize) -> Option<&'a AudioBuffer> {
+    let port = edge.source_port_for(ch);
+    match edge.tap_point() {
+        TapPoint::PrePlugin if source.node_type() == NodeType::Bus => source.input_buffer(port),
+        _ => source.output_buffer(port),
+    }
+}
+
+/// Mix every cued edge's source into the cue sink
+///
+/// プリはエッジのゲイン/ミュート/パンの前（トリムのみ）、ポストは適用中のゲインとパン込み。
+/// モノラルのエッジはキューシンクの L/R 両方に送る。
+fn mix_cue_taps(graph: &mut AudioGraph, edges: &[Edge], cue: NodeHandle) {
+    for edge in edges.iter().filter(|e| e.source != cue) {
+        let tap = edge.cue();
+        if tap == CueTap::Off || edge.is_detached() {
+            continue;
+        }
+        let Some((source_node, cue_node)) = graph.get_two_nodes_mut(edge.source, cue) else {
+            continue;
+        };
+        let cue_ports = cue_node.input_port_count();
+        if cue_ports == 0 {
+            return;
+        }
+        for ch in 0..edge.channels as usize {
+            let Some(source_buf) = tapped_buffer(source_node, edge, ch) else {
+                continue;
+            };
+            let gain = match tap {
+                CueTap::PostGain => edge.applied_gain() * edge.channel_gain(ch),
+                _ => edge.channel_trim(ch),
+            };
+            let ports = if edge.channels == 1 {
+                0..cue_ports.min(2)
+            } else {
+                ch % cue_ports..ch % cue_ports + 1
+            };
+            for port in ports {
+                if let Some(cue_buf) = cue_node.input_buffer_mut(PortId::new(port as u8)) {
+                    cue_buf.mix_from_slice(source_buf.samples(), gain);
+                }
+            }
+        }
+    }
+}
+
+/// Apply an edge parameter update (false if the edge does not exist)
+fn apply_edge_params(graph: &AudioGraph, edge_id: EdgeId, update: EdgeParamUpdate) -> bool {
+    let Some(edge) = graph.get_edge(edge_id) else {
+        return false;
+    };
+    if let Some(gain) = update.gain {
+        edge.set_gain(gain);
+    }
+    if let Some(muted) = update.muted {
+        edge.set_muted(muted);
+    }
+    if let Some(pan) = update.pan {
+        edge.set_pan(pan);
+    }
+    true
+}
+
 impl Default for GraphProcessor {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,65 @@
+//! Reclaim - drop removed graph objects off the audio thread
+//!
+//! グラフの変更はオーディオスレッドで適用されるので、削除したノードをその場で drop すると
+//! AudioUnit の解放（メインスレッドへの同期ディスパッチ）やファイルのクローズが
+//! コールバック内で起きてしまう。削除したものはこのスレッドに送り、ここで drop する。
+//!
+//! 送り口は容量固定の `sync_channel`（送信で確保しない）。起動前や満杯のときだけその場で
+//! drop する。
+
+use super::bus::PluginInstance;
+use super::edge::Edge;
+use super::node::AudioNode;
+use super::topology::CompiledGraph;
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::OnceLock;
+
+/// Objects that can wait for the reclaim thread at once
+const RECLAIM_CAPACITY: usize = 256;
+
+/// Something removed from the graph that must not be dropped on the audio thread
+///
+/// 大きいバリアントも Box にしない（送るのはオーディオスレッドなので確保できない）。
+#[allow(clippy::large_enum_variant)]
+pub enum Retired {
+    Node(Box<dyn AudioNode>),
+    /// An edge removed from the graph (its delay lines may be the last reference)
+    Edge(Edge),
+    /// A plugin slot removed from a bus (its AudioUnit instance may be the last reference)
+    Plugin(PluginInstance),
+    /// Processing order, schedule and delay lines replaced by `AudioGraph::install`
+    Compiled(CompiledGraph),
+}
+
+static SENDER: OnceLock<SyncSender<Retired>> = OnceLock::new();
+
+/// Start the reclaim thread (idempotent; call from a control thread)
+pub fn init() {
+    SENDER.get_or_init(|| {
+        let (tx, rx) = mpsc::sync_channel::<Retired>(RECLAIM_CAPACITY);
+        let spawned = std::thread::Builder::new()
+            .name("spectrum-reclaim".to_string())
+            .spawn(move || {
+                // ここで drop される
+                for retired in rx {
+                    drop(retired);
+                }
+            });
+        if let Err(e) = spawned {
+            log_error!("[Reclaim] Failed to spawn thread: {}", e);
+        }
+        tx
+    });
+}
+
+/// Hand a removed object to the reclaim thread (dropped in place if it cannot take it)
+pub fn retire(retired: Retired) {
+    let retired = match SENDER.get() {
+        Some(tx) => match tx.try_send(retired) {
+            Ok(()) => return,
+            Err(TrySendError::Full(r) | TrySendError::Disconnected(r)) => r,
+        },
+        None => retired,
+    };
+    drop(retired);
+}
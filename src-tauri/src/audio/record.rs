@@ -0,0 +1,696 @@
+//! Record Node - Multitrack capture to disk
+//!
+//! 入力ポートをインターリーブしてロックフリー SPSC リングバッファへ送り、
+//! ライタースレッドが WAV / CAF (32-bit float) ファイルへ書き出す。
+//!
+//! マルチトラック収録用に、各ノードはアーム状態を持ち、録音の開始/終了を
+//! レンダリングのフレーム位置（[`render_frame`]）で指定できる。同じ開始フレームで
+//! 始めた録音ノードのファイルはサンプル単位で頭が揃う（パンチイン/アウト）。
+
+use super::buffer::AudioBuffer;
+use super::node::{AudioNode, NodeType, PortId};
+use super::MAX_FRAMES;
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::any::Any;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// ライタースレッドへのリングバッファ長（フレーム数）
+/// 65536 frames at 48kHz = ~1.4s のディスク遅延を吸収
+const RECORD_RING_FRAMES: usize = 65536;
+
+/// Engine frame at the start of the block being rendered
+static RENDER_FRAME: AtomicU64 = AtomicU64::new(0);
+
+/// Render position in frames (advances by every live output block, not by bounces)
+pub fn render_frame() -> u64 {
+    RENDER_FRAME.load(Ordering::Acquire)
+}
+
+/// Advance the render position after a live block (audio thread)
+pub(super) fn advance_render_frame(frames: usize) {
+    RENDER_FRAME.fetch_add(frames as u64, Ordering::Release);
+}
+
+/// 録音ファイル形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// RIFF WAVE (IEEE float)
+    Wav,
+    /// Core Audio Format (little-endian float LPCM)
+    Caf,
+}
+
+impl RecordFormat {
+    /// Parse from a string ("wav" / "caf")
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "wav" | "wave" => Some(Self::Wav),
+            "caf" => Some(Self::Caf),
+            _ => None,
+        }
+    }
+
+    /// File extension (without dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Wav => "wav",
+            Self::Caf => "caf",
+        }
+    }
+}
+
+/// 録音状態（API 用スナップショット）
+#[derive(Debug, Clone, Default)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    pub path: Option<PathBuf>,
+    pub frames_written: u64,
+    pub dropped_frames: u64,
+    /// Render frame of the first recorded sample (None until the recording starts)
+    pub start_frame: Option<u64>,
+    /// Scheduled punch-out frame
+    pub stop_frame: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// オーディオスレッドとライタースレッドで共有する状態
+struct RecordShared {
+    path: PathBuf,
+    stop: AtomicBool,
+    frames_written: AtomicU64,
+    dropped_frames: AtomicU64,
+    /// 開始フレーム（0 = 次のブロックから）。開始後は実際に書き始めたフレーム
+    start_frame: AtomicU64,
+    started: AtomicBool,
+    /// 終了フレーム（u64::MAX = 停止するまで）
+    stop_frame: AtomicU64,
+    error: parking_lot::Mutex<Option<String>>,
+}
+
+impl RecordShared {
+    fn status(&self, recording: bool) -> RecordingStatus {
+        let stop_frame = self.stop_frame.load(Ordering::Relaxed);
+        RecordingStatus {
+            recording,
+            path: Some(self.path.clone()),
+            frames_written: self.frames_written.load(Ordering::Relaxed),
+            dropped_frames: self.dropped_frames.load(Ordering::Relaxed),
+            start_frame: self
+                .started
+                .load(Ordering::Acquire)
+                .then(|| self.start_frame.load(Ordering::Relaxed)),
+            stop_frame: (stop_frame != u64::MAX).then_some(stop_frame),
+            error: self.error.lock().clone(),
+        }
+    }
+
+    /// Part of the block starting at `block_start` that belongs to the recording
+    fn window(&self, block_start: u64, frames: usize) -> Option<(usize, usize)> {
+        let mut start = self.start_frame.load(Ordering::Relaxed);
+        if !self.started.load(Ordering::Relaxed) {
+            if start >= block_start + frames as u64 {
+                return None;
+            }
+            // 過ぎた開始フレームはこのブロックの頭に揃える（同じ操作で始めたノードは同じになる）
+            start = start.max(block_start);
+            self.start_frame.store(start, Ordering::Relaxed);
+            self.started.store(true, Ordering::Release);
+        }
+        let stop = self.stop_frame.load(Ordering::Relaxed);
+        let from = start.saturating_sub(block_start).min(frames as u64) as usize;
+        let to = stop.saturating_sub(block_start).min(frames as u64) as usize;
+        (from < to).then_some((from, to))
+    }
+}
+
+/// 録音セッション（ファイルとライタースレッド）
+///
+/// 制御スレッドで [`open`](Self::open) してから [`RecordNode::attach`] でノードに渡す
+/// （グラフの変更はオーディオスレッドで適用されるため、そこではファイルを開かない）。
+/// drop は停止を合図するだけで、ライタースレッドの join は [`finish_recording`] で行う。
+pub struct RecordSession {
+    producer: HeapProd<f32>,
+    /// インターリーブ用スクラッチ（事前確保）
+    scratch: Vec<f32>,
+    channels: usize,
+    shared: Arc<RecordShared>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RecordSession {
+    /// ファイルを作成してヘッダーを書き込み、ライタースレッドを起動する（制御スレッド）
+    ///
+    /// `start_frame` は録音を始めるレンダリングフレーム（0 = 次のブロックから）。
+    pub fn open(
+        name: &str,
+        channels: usize,
+        path: &Path,
+        format: RecordFormat,
+        sample_rate: f64,
+        start_frame: u64,
+    ) -> Result<Self, String> {
+        let channels = channels.max(1);
+        let file = File::create(path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        write_header(&mut writer, format, sample_rate, channels as u16, 0)
+            .map_err(|e| format!("Failed to write header: {}", e))?;
+
+        let (producer, consumer) = HeapRb::<f32>::new(RECORD_RING_FRAMES * channels).split();
+        let shared = Arc::new(RecordShared {
+            path: path.to_path_buf(),
+            stop: AtomicBool::new(false),
+            frames_written: AtomicU64::new(0),
+            dropped_frames: AtomicU64::new(0),
+            start_frame: AtomicU64::new(start_frame),
+            started: AtomicBool::new(false),
+            stop_frame: AtomicU64::new(u64::MAX),
+            error: parking_lot::Mutex::new(None),
+        });
+
+        let thread_shared = shared.clone();
+        let thread = std::thread::Builder::new()
+            .name(format!("record-{}", name))
+            .spawn(move || {
+                writer_thread(
+                    writer,
+                    consumer,
+                    thread_shared,
+                    format,
+                    sample_rate,
+                    channels,
+                )
+            })
+            .map_err(|e| format!("Failed to spawn writer thread: {}", e))?;
+
+        log_info!(
+            "[RecordNode] Recording {} ({} ch, {:?}) -> {}",
+            name,
+            channels,
+            format,
+            path.display()
+        );
+
+        Ok(Self {
+            producer,
+            scratch: vec![0.0; MAX_FRAMES * channels],
+            channels,
+            shared,
+            thread: Some(thread),
+        })
+    }
+
+    /// File this session writes to
+    pub fn path(&self) -> &Path {
+        &self.shared.path
+    }
+
+    /// Move the start to another render frame (before the session is attached)
+    pub fn set_start_frame(&self, frame: u64) {
+        self.shared.start_frame.store(frame, Ordering::Relaxed);
+    }
+}
+
+impl Drop for RecordSession {
+    fn drop(&mut self) {
+        // 書きかけのファイルはライタースレッドが自分で確定させる（ここでは待たない）
+        self.shared.stop.store(true, Ordering::Release);
+    }
+}
+
+// SAFETY: producer は &mut self 経由（オーディオスレッドの process）でのみ触る。
+// &RecordNode からは共有参照で到達できないため、Sync にしても競合しない。
+unsafe impl Sync for RecordSession {}
+
+/// 録音ノード
+///
+/// シンクと同様に入力のみを持つ。任意の地点（バスの出力など）を
+/// エッジで接続してステムを録音できる。
+pub struct RecordNode {
+    /// 録音ノードの識別子
+    record_id: String,
+    /// 表示ラベル
+    label: String,
+    /// 入力バッファ（チャンネル数分）
+    input_buffers: Vec<AudioBuffer>,
+    /// 録音中のセッション
+    session: Option<RecordSession>,
+    /// 最後のセッションの共有状態（停止後もステータス参照用に保持）
+    last_shared: Option<Arc<RecordShared>>,
+    /// Armed for the next punch-in
+    armed: bool,
+}
+
+impl RecordNode {
+    /// Create a new record node
+    pub fn new(record_id: impl Into<String>, label: impl Into<String>, port_count: usize) -> Self {
+        let port_count = port_count.max(1);
+        Self {
+            record_id: record_id.into(),
+            label: label.into(),
+            input_buffers: (0..port_count).map(|_| AudioBuffer::new()).collect(),
+            session: None,
+            last_shared: None,
+            armed: false,
+        }
+    }
+
+    /// Get the record node ID
+    pub fn record_id(&self) -> &str {
+        &self.record_id
+    }
+
+    /// Set the label
+    pub fn set_label(&mut self, label: impl Into<String>) {
+        self.label = label.into();
+    }
+
+    /// Whether a recording session is active
+    pub fn is_recording(&self) -> bool {
+        self.session.is_some()
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// Arm (or disarm) the node for punch-in
+    pub fn set_armed(&mut self, armed: bool) {
+        self.armed = armed;
+    }
+
+    /// 録音を開始（制御スレッドから呼ぶ）
+    ///
+    /// ファイルを作成してヘッダーを書き込み、ライタースレッドを起動する。
+    /// グラフの変更の中では [`RecordSession::open`] + [`attach`](Self::attach) を使うこと。
+    pub fn start(
+        &mut self,
+        path: &Path,
+        format: RecordFormat,
+        sample_rate: f64,
+    ) -> Result<(), String> {
+        if self.session.is_some() {
+            return Err(self.already_recording());
+        }
+        let session = RecordSession::open(
+            &self.record_id,
+            self.input_buffers.len(),
+            path,
+            format,
+            sample_rate,
+            0,
+        )?;
+        self.attach(session).map_err(|_| self.already_recording())
+    }
+
+    /// Attach a session opened with [`RecordSession::open`] (cheap; safe on the audio thread)
+    ///
+    /// 録音中か、チャンネル数がノードと合わなければセッションをそのまま返す。
+    pub fn attach(&mut self, session: RecordSession) -> Result<(), RecordSession> {
+        if self.session.is_some() || session.channels != self.input_buffers.len() {
+            return Err(session);
+        }
+        self.last_shared = Some(session.shared.clone());
+        self.session = Some(session);
+        Ok(())
+    }
+
+    fn already_recording(&self) -> String {
+        format!("Record node {} is already recording", self.record_id)
+    }
+
+    /// Stop writing at `frame` (punch-out); false if not recording
+    ///
+    /// The session stays attached until [`detach_session`](Self::detach_session).
+    pub fn punch_out_at(&mut self, frame: u64) -> bool {
+        let Some(session) = &self.session else {
+            return false;
+        };
+        session.shared.stop_frame.store(frame, Ordering::Relaxed);
+        true
+    }
+
+    /// セッションを取り外す（グラフのロック下で呼ぶ）
+    ///
+    /// セッションごと呼び出し側に返すので、解放とライタースレッドの join はロック外で
+    /// [`finish_recording`] を使うこと。
+    pub fn detach_session(&mut self) -> Option<RecordingHandle> {
+        self.session
+            .take()
+            .map(|session| RecordingHandle { session })
+    }
+
+    /// 現在（または直前）の録音状態
+    pub fn status(&self) -> RecordingStatus {
+        match &self.last_shared {
+            Some(shared) => shared.status(self.session.is_some()),
+            None => RecordingStatus::default(),
+        }
+    }
+}
+
+/// 取り外した録音セッション（停止処理用）
+pub struct RecordingHandle {
+    session: RecordSession,
+}
+
+impl From<RecordSession> for RecordingHandle {
+    fn from(session: RecordSession) -> Self {
+        Self { session }
+    }
+}
+
+impl RecordingHandle {
+    /// File the session writes to
+    pub fn path(&self) -> &Path {
+        self.session.path()
+    }
+}
+
+/// ライタースレッドを停止してファイルを確定する（ブロッキング。制御スレッドで呼ぶ）
+pub fn finish_recording(mut handle: RecordingHandle) -> RecordingStatus {
+    let session = &mut handle.session;
+    session.shared.stop.store(true, Ordering::Release);
+    if let Some(thread) = session.thread.take() {
+        let _ = thread.join();
+    }
+    session.shared.status(false)
+}
+
+fn writer_thread(
+    mut writer: BufWriter<File>,
+    mut consumer: HeapCons<f32>,
+    shared: Arc<RecordShared>,
+    format: RecordFormat,
+    sample_rate: f64,
+    channels: usize,
+) {
+    let mut chunk = vec![0.0f32; MAX_FRAMES * channels];
+    let mut bytes = Vec::with_capacity(chunk.len() * 4);
+    let mut total_frames: u64 = 0;
+
+    let result: std::io::Result<()> = (|| {
+        loop {
+            let stopping = shared.stop.load(Ordering::Acquire);
+            let available = consumer.occupied_len();
+            if available == 0 {
+                if stopping {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+                continue;
+            }
+
+            let n = consumer.pop_slice(&mut chunk);
+            // フレーム境界で揃っている前提（producer はフレーム単位で push する）
+            bytes.clear();
+            for s in &chunk[..n] {
+                bytes.extend_from_slice(&s.to_le_bytes());
+            }
+            writer.write_all(&bytes)?;
+
+            total_frames += (n / channels) as u64;
+            shared.frames_written.store(total_frames, Ordering::Relaxed);
+        }
+
+        writer.flush()?;
+        writer.seek(SeekFrom::Start(0))?;
+        write_header(
+            &mut writer,
+            format,
+            sample_rate,
+            channels as u16,
+            total_frames,
+        )?;
+        writer.flush()
+    })();
+
+    if let Err(e) = result {
+        log_error!(
+            "[RecordNode] Writer error for {}: {}",
+            shared.path.display(),
+            e
+        );
+        *shared.error.lock() = Some(e.to_string());
+    } else {
+        log_info!(
+            "[RecordNode] Finished {} ({} frames)",
+            shared.path.display(),
+            total_frames
+        );
+    }
+}
+
+/// ファイルヘッダーを書き込む（frames = 0 の場合はプレースホルダ）
+pub(super) fn write_header(
+    w: &mut impl Write,
+    format: RecordFormat,
+    sample_rate: f64,
+    channels: u16,
+    frames: u64,
+) -> std::io::Result<()> {
+    let data_bytes = frames * channels as u64 * 4;
+    match format {
+        RecordFormat::Wav => {
+            // WAV は 4GB 上限（超えた場合はサイズをクランプ）
+            let data_bytes = data_bytes.min(u32::MAX as u64 - 36) as u32;
+            w.write_all(b"RIFF")?;
+            w.write_all(&(36 + data_bytes).to_le_bytes())?;
+            w.write_all(b"WAVE")?;
+            w.write_all(b"fmt ")?;
+            w.write_all(&16u32.to_le_bytes())?;
+            w.write_all(&3u16.to_le_bytes())?; // WAVE_FORMAT_IEEE_FLOAT
+            w.write_all(&channels.to_le_bytes())?;
+            w.write_all(&(sample_rate as u32).to_le_bytes())?;
+            w.write_all(&(sample_rate as u32 * channels as u32 * 4).to_le_bytes())?;
+            w.write_all(&(channels * 4).to_le_bytes())?;
+            w.write_all(&32u16.to_le_bytes())?;
+            w.write_all(b"data")?;
+            w.write_all(&data_bytes.to_le_bytes())?;
+        }
+        RecordFormat::Caf => {
+            // CAF のヘッダーはビッグエンディアン
+            w.write_all(b"caff")?;
+            w.write_all(&1u16.to_be_bytes())?; // version
+            w.write_all(&0u16.to_be_bytes())?; // flags
+            w.write_all(b"desc")?;
+            w.write_all(&32i64.to_be_bytes())?;
+            w.write_all(&sample_rate.to_be_bytes())?;
+            w.write_all(b"lpcm")?;
+            // kCAFLinearPCMFormatFlagIsFloat | kCAFLinearPCMFormatFlagIsLittleEndian
+            w.write_all(&3u32.to_be_bytes())?;
+            w.write_all(&(channels as u32 * 4).to_be_bytes())?; // bytes per packet
+            w.write_all(&1u32.to_be_bytes())?; // frames per packet
+            w.write_all(&(channels as u32).to_be_bytes())?;
+            w.write_all(&32u32.to_be_bytes())?;
+            w.write_all(b"data")?;
+            // data chunk size includes the 4-byte edit count
+            w.write_all(&((data_bytes + 4) as i64).to_be_bytes())?;
+            w.write_all(&0u32.to_be_bytes())?; // edit count
+        }
+    }
+    Ok(())
+}
+
+impl AudioNode for RecordNode {
+    fn node_type(&self) -> NodeType {
+        NodeType::Record
+    }
+
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn input_port_count(&self) -> usize {
+        self.input_buffers.len()
+    }
+
+    fn output_port_count(&self) -> usize {
+        0 // 録音ノードは出力なし
+    }
+
+    fn input_buffer(&self, port: PortId) -> Option<&AudioBuffer> {
+        self.input_buffers.get(port.index())
+    }
+
+    fn input_buffer_mut(&mut self, port: PortId) -> Option<&mut AudioBuffer> {
+        self.input_buffers.get_mut(port.index())
+    }
+
+    fn output_buffer(&self, _port: PortId) -> Option<&AudioBuffer> {
+        None
+    }
+
+    fn output_buffer_mut(&mut self, _port: PortId) -> Option<&mut AudioBuffer> {
+        None
+    }
+
+    fn process(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.set_valid_frames(frames);
+            buf.update_peak();
+        }
+
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+
+        // 開始/終了フレームの範囲だけ書く（パンチイン/アウトはブロックの途中でも揃う）
+        let frames = frames.min(MAX_FRAMES);
+        let Some((from, to)) = session.shared.window(render_frame(), frames) else {
+            return;
+        };
+
+        // インターリーブしてライタースレッドへ（アロケーションなし）
+        let channels = self.input_buffers.len();
+        let needed = (to - from) * channels;
+        if session.producer.vacant_len() < needed {
+            // ディスクが追いつかない場合はブロック単位で破棄
+            session
+                .shared
+                .dropped_frames
+                .fetch_add((to - from) as u64, Ordering::Relaxed);
+            return;
+        }
+
+        for (ch, buf) in self.input_buffers.iter().enumerate() {
+            let samples = buf.samples();
+            for i in from..to {
+                session.scratch[(i - from) * channels + ch] =
+                    samples.get(i).copied().unwrap_or(0.0);
+            }
+        }
+        session.producer.push_slice(&session.scratch[..needed]);
+    }
+
+    fn clear_buffers(&mut self, frames: usize) {
+        for buf in &mut self.input_buffers {
+            buf.clear(frames);
+        }
+    }
+
+    fn is_live_io(&self) -> bool {
+        true
+    }
+
+    fn input_peak_levels(&self) -> Vec<f32> {
+        self.input_buffers.iter().map(|b| b.cached_peak()).collect()
+    }
+
+    fn output_peak_levels(&self) -> Vec<f32> {
+        Vec::new() // 録音ノードは出力なし
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_header_size() {
+        let mut header = Vec::new();
+        write_header(&mut header, RecordFormat::Wav, 48000.0, 2, 100).unwrap();
+        assert_eq!(header.len(), 44);
+        assert_eq!(&header[0..4], b"RIFF");
+        // data chunk size = frames * channels * 4
+        assert_eq!(u32::from_le_bytes(header[40..44].try_into().unwrap()), 800);
+    }
+
+    #[test]
+    fn test_record_to_wav_file() {
+        let path =
+            std::env::temp_dir().join(format!("spectrum_record_test_{}.wav", std::process::id()));
+        let mut node = RecordNode::new("rec_test", "Rec", 2);
+        node.start(&path, RecordFormat::Wav, 48000.0).unwrap();
+
+        node.clear_buffers(256);
+        node.input_buffer_mut(PortId::new(0))
+            .unwrap()
+            .write_samples(&[0.5; 256]);
+        node.process(256);
+
+        let status = finish_recording(node.detach_session().unwrap());
+        assert_eq!(status.frames_written, 256);
+        assert_eq!(status.dropped_frames, 0);
+
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data.len(), 44 + 256 * 2 * 4);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_dropped_node_finalizes_file_without_join() {
+        let path =
+            std::env::temp_dir().join(format!("spectrum_record_drop_{}.wav", std::process::id()));
+        let session =
+            RecordSession::open("rec_drop", 1, &path, RecordFormat::Wav, 48000.0, 0).unwrap();
+
+        // Channel count must match the node
+        let mut stereo = RecordNode::new("rec_stereo", "Rec", 2);
+        let session = stereo.attach(session).unwrap_err();
+        assert!(!stereo.is_recording());
+
+        let mut node = RecordNode::new("rec_drop", "Rec", 1);
+        assert!(node.attach(session).is_ok());
+        node.clear_buffers(128);
+        node.input_buffer_mut(PortId::new(0))
+            .unwrap()
+            .write_samples(&[0.25; 128]);
+        node.process(128);
+        drop(node);
+
+        // The writer thread finishes on its own and rewrites the header
+        let finished = |data: &[u8]| data.len() == 44 + 512 && data[40..44] == 512u32.to_le_bytes();
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while !std::fs::read(&path).is_ok_and(|data| finished(&data)) {
+            assert!(std::time::Instant::now() < deadline);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn shared(start_frame: u64) -> RecordShared {
+        RecordShared {
+            path: PathBuf::new(),
+            stop: AtomicBool::new(false),
+            frames_written: AtomicU64::new(0),
+            dropped_frames: AtomicU64::new(0),
+            start_frame: AtomicU64::new(start_frame),
+            started: AtomicBool::new(false),
+            stop_frame: AtomicU64::new(u64::MAX),
+            error: parking_lot::Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn test_punch_window_is_sample_aligned() {
+        let punch = shared(1000);
+        punch.stop_frame.store(1300, Ordering::Relaxed);
+        assert_eq!(punch.window(744, 256), None);
+        assert_eq!(punch.status(true).start_frame, None);
+        // Starts inside the block, ends inside the next one
+        assert_eq!(punch.window(900, 256), Some((100, 256)));
+        assert_eq!(punch.window(1156, 256), Some((0, 144)));
+        assert_eq!(punch.window(1412, 256), None);
+        assert_eq!(punch.status(true).start_frame, Some(1000));
+
+        // A start frame that already passed snaps to the first block
+        let late = shared(10);
+        assert_eq!(late.window(500, 128), Some((0, 128)));
+        assert_eq!(late.status(true).start_frame, Some(500));
+    }
+}
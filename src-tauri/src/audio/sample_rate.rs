@@ -0,0 +1,157 @@
+//! Engine sample rate and sample-rate conversion
+//!
+//! エンジン全体で共有するサンプルレート。キャプチャ/出力/AudioUnit は
+//! 起動時にここから値を読む。デバイスがエンジンのレートに合わせられない場合は
+//! [`LinearResampler`] で変換する。
+
+use super::SAMPLE_RATE;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// サポートするサンプルレート
+pub const SUPPORTED_SAMPLE_RATES: [f64; 6] =
+    [44100.0, 48000.0, 88200.0, 96000.0, 176400.0, 192000.0];
+
+/// 現在のエンジンサンプルレート（f64 bits）
+static ENGINE_SAMPLE_RATE: AtomicU64 = AtomicU64::new(0);
+
+/// Get the current engine sample rate
+#[inline]
+pub fn engine_sample_rate() -> f64 {
+    let bits = ENGINE_SAMPLE_RATE.load(Ordering::Relaxed);
+    if bits == 0 {
+        SAMPLE_RATE
+    } else {
+        f64::from_bits(bits)
+    }
+}
+
+/// Set the engine sample rate (does not restart the engine)
+///
+/// Returns false if the rate is not supported.
+pub(crate) fn store_engine_sample_rate(rate: f64) -> bool {
+    if !is_supported_sample_rate(rate) {
+        return false;
+    }
+    ENGINE_SAMPLE_RATE.store(rate.to_bits(), Ordering::Relaxed);
+    true
+}
+
+/// Check if a sample rate is supported by the engine
+pub fn is_supported_sample_rate(rate: f64) -> bool {
+    SUPPORTED_SAMPLE_RATES.iter().any(|&r| rates_match(r, rate))
+}
+
+/// Whether two rates should be treated as equal (no SRC needed)
+#[inline]
+pub fn rates_match(a: f64, b: f64) -> bool {
+    (a - b).abs() < 0.5
+}
+
+/// 線形補間サンプルレートコンバーター（モノラル、ストリーミング）
+///
+/// 入力 1 ブロックごとに可変長の出力を生成する。ブロック間で位相を保持するため
+/// 継ぎ目のノイズは出ない。オーディオスレッドではアロケーションしない。
+#[derive(Debug, Clone)]
+pub struct LinearResampler {
+    /// 入力サンプル / 出力サンプル
+    step: f64,
+    /// 次の出力の入力上の位置（-1.0 = 直前ブロックの末尾サンプル）
+    pos: f64,
+    /// 直前ブロックの最後のサンプル
+    last: f32,
+}
+
+impl LinearResampler {
+    /// Create a converter from `from_rate` to `to_rate`
+    pub fn new(from_rate: f64, to_rate: f64) -> Self {
+        Self {
+            step: from_rate / to_rate,
+            pos: -1.0,
+            last: 0.0,
+        }
+    }
+
+    /// Output frames produced for `input_frames` at most (for sizing scratch buffers)
+    pub fn max_output_frames(&self, input_frames: usize) -> usize {
+        (input_frames as f64 / self.step).ceil() as usize + 1
+    }
+
+    /// Reset internal state
+    pub fn reset(&mut self) {
+        self.pos = -1.0;
+        self.last = 0.0;
+    }
+
+    /// Convert one block. Returns the number of samples written to `output`.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) -> usize {
+        if input.is_empty() {
+            return 0;
+        }
+
+        let len = input.len() as f64;
+        let mut written = 0;
+
+        // pos は [-1, len-1) の範囲で補間（-1 は前ブロックの last）
+        while self.pos < len - 1.0 && written < output.len() {
+            let base = self.pos.floor();
+            let frac = (self.pos - base) as f32;
+            let i = base as isize;
+            let a = if i < 0 { self.last } else { input[i as usize] };
+            let b = input[(i + 1) as usize];
+            output[written] = a + (b - a) * frac;
+            written += 1;
+            self.pos += self.step;
+        }
+
+        self.last = input[input.len() - 1];
+        self.pos -= len;
+        written
+    }
+}
+
+/// リングバッファのサイズをサンプルレートに応じてスケール
+///
+/// 48kHz で `base` フレーム分の時間を確保する。
+pub fn scaled_buffer_frames(base: usize, sample_rate: f64) -> usize {
+    let factor = (sample_rate / SAMPLE_RATE).ceil().max(1.0) as usize;
+    base * factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_rates() {
+        assert!(is_supported_sample_rate(44100.0));
+        assert!(is_supported_sample_rate(96000.0));
+        assert!(!is_supported_sample_rate(12345.0));
+    }
+
+    #[test]
+    fn test_resampler_ratio_over_blocks() {
+        // 44.1k -> 48k: 441 input frames ~= 480 output frames
+        let mut src = LinearResampler::new(44100.0, 48000.0);
+        let input = vec![0.25f32; 441];
+        let mut output = vec![0.0f32; src.max_output_frames(441)];
+
+        let mut total = 0;
+        for _ in 0..100 {
+            total += src.process(&input, &mut output);
+        }
+        assert!((total as i64 - 48000).abs() <= 2, "total = {}", total);
+        // DC is preserved after the first block
+        assert!((output[10] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_resampler_identity() {
+        let mut src = LinearResampler::new(48000.0, 48000.0);
+        let input: Vec<f32> = (0..8).map(|v| v as f32).collect();
+        let mut output = vec![0.0f32; 16];
+        let n = src.process(&input, &mut output);
+        // First output interpolates from the previous block's last sample (0.0)
+        assert_eq!(n, 8);
+        assert_eq!(&output[1..8], &input[..7]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Render Schedule - Flat per-block op list compiled from the graph
+//!
+//! オーディオスレッドが毎ブロック HashMap をたどってエッジを探さなくて済むよう、
+//! トポロジー変更時に制御スレッドでグラフの構造（[`Topology`]）を平らな命令列へ変換しておく。
+//! 命令列は `Arc` ごと差し替えるので、処理中のブロックは古いスケジュールのまま最後まで走る。
+//!
+//! エッジのゲイン・ミュート・ソロ・ダイレクトモニターは共有パラメータ（Atomic）なので
+//! 再コンパイルは不要。実行時に `is_audible` / `is_direct` を確認する。
+
+use super::edge::Edge;
+use super::node::{NodeHandle, NodeType};
+use super::topology::Topology;
+
+/// One step of a block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderOp {
+    /// Read capture input into a source node's output ports
+    ReadSource(NodeHandle),
+    /// Mix an edge into its target's inputs (index into [`RenderSchedule::edges`])
+    MixEdge(usize),
+    /// Mix the cued edges into the cue sink
+    MixCue(NodeHandle),
+    /// Run a node (bus plugins, sink output, recorder, ...)
+    Process(NodeHandle),
+}
+
+/// Compiled processing order of a graph
+#[derive(Debug, Default)]
+pub struct RenderSchedule {
+    ops: Vec<RenderOp>,
+    /// Edges in mixing order (parameters and delay lines are shared with the graph)
+    edges: Vec<Edge>,
+    /// Every node in processing order (buffers are cleared in this order)
+    nodes: Vec<NodeHandle>,
+}
+
+impl RenderSchedule {
+    /// Compile a graph structure in the given processing order
+    ///
+    /// ソースの読み込みを先にまとめ、その後ノードごとに「入力エッジのミックス →
+    /// （キューシンクなら）キューのミックス → 処理」を並べる。
+    pub fn compile(topology: &Topology, order: &[NodeHandle]) -> Self {
+        let nodes = order.to_vec();
+        let mut ops = Vec::with_capacity(nodes.len() * 2 + topology.edges.len());
+        let mut edges = Vec::with_capacity(topology.edges.len());
+
+        ops.extend(
+            nodes
+                .iter()
+                .filter(|&&h| topology.node_type(h) == Some(NodeType::Source))
+                .map(|&h| RenderOp::ReadSource(h)),
+        );
+        for &handle in &nodes {
+            for edge in topology.edges.iter().filter(|e| e.target == handle) {
+                ops.push(RenderOp::MixEdge(edges.len()));
+                edges.push(edge.clone());
+            }
+            if topology.cue_sink == Some(handle) {
+                ops.push(RenderOp::MixCue(handle));
+            }
+            ops.push(RenderOp::Process(handle));
+        }
+
+        Self { ops, edges, nodes }
+    }
+
+    pub fn ops(&self) -> &[RenderOp] {
+        &self.ops
+    }
+
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    pub fn nodes(&self) -> &[NodeHandle] {
+        &self.nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::bus::BusNode;
+    use crate::audio::graph::AudioGraph;
+    use crate::audio::node::PortId;
+    use crate::audio::sink::SinkNode;
+    use crate::audio::source::SourceNode;
+
+    #[test]
+    fn test_compile_orders_mixes_before_processing() {
+        let mut graph = AudioGraph::new();
+        let sink = graph.add_node(Box::new(SinkNode::new_stereo(1, "Out")));
+        let bus = graph.add_node(Box::new(BusNode::new_stereo("b", "Bus")));
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        let to_bus = graph
+            .add_edge(src, PortId::new(0), bus, PortId::new(0))
+            .unwrap();
+        let to_sink = graph
+            .add_edge(bus, PortId::new(0), sink, PortId::new(0))
+            .unwrap();
+        graph.rebuild_order_if_needed();
+
+        let schedule = graph.schedule();
+        assert_eq!(schedule.nodes(), &[src, bus, sink]);
+        assert_eq!(
+            schedule.ops(),
+            &[
+                RenderOp::ReadSource(src),
+                RenderOp::Process(src),
+                RenderOp::MixEdge(0),
+                RenderOp::Process(bus),
+                RenderOp::MixEdge(1),
+                RenderOp::Process(sink),
+            ]
+        );
+        assert_eq!(schedule.edges()[0].id, to_bus);
+        assert_eq!(schedule.edges()[1].id, to_sink);
+    }
+
+    #[test]
+    fn test_schedule_is_recompiled_on_change() {
+        let mut graph = AudioGraph::new();
+        let src = graph.add_node(Box::new(SourceNode::new_prism(0, "Src")));
+        graph.rebuild_order_if_needed();
+        let before = graph.schedule();
+
+        let cue = graph.add_node(Box::new(SinkNode::new_stereo(2, "Cue")));
+        graph.add_edge(src, PortId::new(0), cue, PortId::new(0));
+        assert!(graph.set_cue_sink(Some(cue)));
+        graph.rebuild_order_if_needed();
+
+        // 古いスケジュールはそのまま残る
+        assert_eq!(before.nodes(), &[src]);
+        let ops = graph.schedule().ops().to_vec();
+        assert_eq!(
+            &ops[ops.len() - 3..],
+            &[
+                RenderOp::MixEdge(0),
+                RenderOp::MixCue(cue),
+                RenderOp::Process(cue)
+            ]
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Session statistics - uptime, rendered frames, dropouts, peak load
+//!
+//! 長時間動かす配信環境で「いつから・どれだけ安定して動いているか」を見るための累計値。
+//! [`xrun`](super::xrun) や [`dsp_load`](super::dsp_load) の値は出力の停止や個別の
+//! リセットで消えるが、こちらは [`reset`] するまで残る（止めたデバイスの累計も残す）。
+//!
+//! 記録は Atomic のみ。出力コールバックは開始時に取得した `Arc<DeviceSessionStats>` を通して
+//! 書く（ロックなし）。
+
+use parking_lot::{Mutex, RwLock};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Rendered frames of one output device
+#[derive(Debug, Default)]
+pub struct DeviceSessionStats {
+    frames: AtomicU64,
+    callbacks: AtomicU64,
+    /// 最大のコールバック負荷（処理時間 / バッファ周期、f32 bits）
+    peak_load_bits: AtomicU32,
+}
+
+/// [`DeviceSessionStats`] の読み出し値
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DeviceSessionSnapshot {
+    pub frames: u64,
+    pub callbacks: u64,
+    pub peak_load: f32,
+}
+
+impl DeviceSessionStats {
+    /// 1 コールバック分を記録（オーディオスレッド）
+    #[inline]
+    pub fn record(&self, frames: usize, elapsed: Duration, sample_rate: f64) {
+        self.frames.fetch_add(frames as u64, Ordering::Relaxed);
+        self.callbacks.fetch_add(1, Ordering::Relaxed);
+        if frames == 0 || sample_rate <= 0.0 {
+            return;
+        }
+        let load = (elapsed.as_secs_f64() * sample_rate / frames as f64) as f32;
+        if load > f32::from_bits(self.peak_load_bits.load(Ordering::Relaxed)) {
+            self.peak_load_bits.store(load.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn snapshot(&self) -> DeviceSessionSnapshot {
+        DeviceSessionSnapshot {
+            frames: self.frames.load(Ordering::Relaxed),
+            callbacks: self.callbacks.load(Ordering::Relaxed),
+            peak_load: f32::from_bits(self.peak_load_bits.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn reset(&self) {
+        self.frames.store(0, Ordering::Relaxed);
+        self.callbacks.store(0, Ordering::Relaxed);
+        self.peak_load_bits
+            .store(0.0f32.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Start of the session (both clocks, so uptime does not jump with the wall clock)
+#[derive(Debug, Clone, Copy)]
+struct SessionStart {
+    instant: Instant,
+    unix_ms: u64,
+}
+
+impl SessionStart {
+    fn now() -> Self {
+        Self {
+            instant: Instant::now(),
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+        }
+    }
+}
+
+static START: LazyLock<Mutex<SessionStart>> = LazyLock::new(|| Mutex::new(SessionStart::now()));
+
+/// [`super::xrun::total_xruns`] at the session start
+static XRUN_BASELINE: AtomicU64 = AtomicU64::new(0);
+
+static PLUGIN_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// device_id -> stats
+static DEVICES: LazyLock<RwLock<HashMap<u32, Arc<DeviceSessionStats>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Whole-session values
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSnapshot {
+    /// Unix time of the session start (ms)
+    pub started_at_ms: u64,
+    pub uptime: Duration,
+    /// Dropouts of every device (see [`super::xrun`])
+    pub dropouts: u64,
+    pub plugin_failures: u64,
+    /// Output devices that rendered in this session (sorted by device id)
+    pub devices: Vec<(u32, DeviceSessionSnapshot)>,
+}
+
+/// Start the session clock (call once at startup; uptime counts from here)
+pub fn init() {
+    LazyLock::force(&START);
+}
+
+/// Get (or create) the stats of an output device
+///
+/// Call from the control thread and move the `Arc` into the render callback.
+pub fn device_session_stats(device_id: u32) -> Arc<DeviceSessionStats> {
+    DEVICES
+        .write()
+        .entry(device_id)
+        .or_insert_with(|| Arc::new(DeviceSessionStats::default()))
+        .clone()
+}
+
+/// Count a plugin that failed to instantiate or configure
+pub fn record_plugin_failure() {
+    PLUGIN_FAILURES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn snapshot() -> SessionSnapshot {
+    let start = *START.lock();
+    let mut devices: Vec<(u32, DeviceSessionSnapshot)> = DEVICES
+        .read()
+        .iter()
+        .map(|(&id, stats)| (id, stats.snapshot()))
+        .filter(|(_, s)| s.callbacks > 0)
+        .collect();
+    devices.sort_by_key(|(id, _)| *id);
+    let dropouts = super::xrun::total_xruns().saturating_sub(XRUN_BASELINE.load(Ordering::Relaxed));
+    SessionSnapshot {
+        started_at_ms: start.unix_ms,
+        uptime: start.instant.elapsed(),
+        dropouts,
+        plugin_failures: PLUGIN_FAILURES.load(Ordering::Relaxed),
+        devices,
+    }
+}
+
+/// Start a new session: uptime and all counters from zero
+pub fn reset() {
+    *START.lock() = SessionStart::now();
+    XRUN_BASELINE.store(super::xrun::total_xruns(), Ordering::Relaxed);
+    PLUGIN_FAILURES.store(0, Ordering::Relaxed);
+    for stats in DEVICES.read().values() {
+        stats.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_stats_accumulate_and_reset() {
+        let stats = device_session_stats(0xFFFF_0101);
+        stats.record(512, Duration::from_micros(2_000), 48_000.0);
+        stats.record(512, Duration::from_micros(8_000), 48_000.0);
+        stats.record(256, Duration::from_micros(1_000), 48_000.0);
+
+        let s = stats.snapshot();
+        assert_eq!((s.frames, s.callbacks), (1280, 3));
+        // 8 ms of a 10.67 ms period
+        assert!((s.peak_load - 0.75).abs() < 1e-3, "{}", s.peak_load);
+        assert!(snapshot()
+            .devices
+            .iter()
+            .any(|(id, d)| *id == 0xFFFF_0101 && d.frames == 1280));
+        // Same device returns the same stats
+        assert!(Arc::ptr_eq(&stats, &device_session_stats(0xFFFF_0101)));
+
+        record_plugin_failure();
+        assert!(snapshot().plugin_failures >= 1);
+
+        reset();
+        let after = snapshot();
+        assert_eq!(stats.snapshot(), DeviceSessionSnapshot::default());
+        assert!(after.uptime < Duration::from_secs(1));
+        assert!(!after.devices.iter().any(|(id, _)| *id == 0xFFFF_0101));
+    }
+}
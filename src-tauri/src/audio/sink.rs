@@ -1,6 +1,7 @@
 //! Sink Node - Output destinations
 
 use super::buffer::AudioBuffer;
+use super::dsp::{LimiterSettings, LookaheadLimiter, OutputProtection, ProtectionSettings};
 use super::node::{AudioNode, NodeType, PortId};
 use serde::{Deserialize, Serialize};
 use std::any::Any;
@@ -24,6 +25,23 @@ pub struct SinkId {
     /// 集約デバイスのサブデバイスの場合、サブデバイスの UID を保持
     #[serde(skip_serializing_if = "Option::is_none")]
     pub device_uid: Option<String>,
+    /// ポート → デバイスチャンネルの対応（None なら channel_offset から連続）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub channel_map: Option<Vec<ChannelRoute>>,
+    /// システムの既定出力デバイスに追従する（`device_id` は現在の既定デバイス）
+    #[serde(default)]
+    pub follow_default: bool,
+}
+
+/// チャンネルマップの 1 エントリ: グラフのポートをデバイスの物理チャンネルへ送る
+///
+/// `channel` はデバイスの絶対チャンネル番号（channel_offset は加算しない）。
+/// マップに無いポートは出力されず、同じポートを複数のチャンネルへ送ったり、
+/// 複数のポートを同じチャンネルに重ねたりできる（重なったポートは加算）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelRoute {
+    pub port: u8,
+    pub channel: u16,
 }
 
 impl SinkId {
@@ -34,6 +52,8 @@ impl SinkId {
             channel_offset: 0,
             channel_count,
             device_uid: crate::device::get_device_uid(device_id),
+            channel_map: None,
+            follow_default: false,
         }
     }
 
@@ -44,6 +64,8 @@ impl SinkId {
             channel_offset,
             channel_count,
             device_uid: crate::device::get_device_uid(device_id),
+            channel_map: None,
+            follow_default: false,
         }
     }
 
@@ -59,6 +81,8 @@ impl SinkId {
             channel_offset,
             channel_count,
             device_uid,
+            channel_map: None,
+            follow_default: false,
         }
     }
 }
@@ -77,6 +101,14 @@ pub struct SinkNode {
     output_gain_bits_by_port: Vec<AtomicU32>,
     /// 入力バッファ（チャンネル数分）
     input_buffers: Vec<AudioBuffer>,
+    /// 出力保護用リミッター（既定は無効）
+    limiter: LookaheadLimiter,
+    /// 持続的な過大レベルで出力を下げる保護（既定は無効）
+    protection: OutputProtection,
+    /// リミッター検出用のポートゲイン（オーディオスレッドのみ）
+    gain_scratch: Vec<f32>,
+    /// 出力先デバイスのハードウェアレイテンシ（フレーム、レイテンシ + セーフティオフセット）
+    device_latency: u32,
 }
 
 impl SinkNode {
@@ -90,6 +122,10 @@ impl SinkNode {
                 .map(|_| AtomicU32::new(1.0_f32.to_bits()))
                 .collect(),
             input_buffers: (0..channel_count).map(|_| AudioBuffer::new()).collect(),
+            limiter: LookaheadLimiter::new(channel_count, LimiterSettings::default()),
+            protection: OutputProtection::new(ProtectionSettings::default()),
+            gain_scratch: vec![1.0; channel_count],
+            device_latency: 0,
         }
     }
 
@@ -113,6 +149,56 @@ impl SinkNode {
         self.sink_id.channel_offset
     }
 
+    /// Custom port → device channel map (None = contiguous from the channel offset)
+    pub fn channel_map(&self) -> Option<&[ChannelRoute]> {
+        self.sink_id.channel_map.as_deref()
+    }
+
+    /// Replace the channel map (None or empty = contiguous from the channel offset)
+    ///
+    /// Routes from ports the sink does not have are rejected.
+    pub fn set_channel_map(&mut self, map: Option<Vec<ChannelRoute>>) -> Result<(), String> {
+        let map = map.filter(|m| !m.is_empty());
+        if let Some(route) = map
+            .iter()
+            .flatten()
+            .find(|r| r.port >= self.sink_id.channel_count)
+        {
+            return Err(format!(
+                "Port {} is out of range for a {}-channel sink",
+                route.port, self.sink_id.channel_count
+            ));
+        }
+        self.sink_id.channel_map = map;
+        Ok(())
+    }
+
+    /// Highest device channel written + 1
+    pub fn device_channels_used(&self) -> usize {
+        let mut used = 0;
+        self.for_each_route(|_, channel| used = used.max(channel + 1));
+        used
+    }
+
+    /// Call `f(port, device_channel)` for every output route (no allocation)
+    #[inline]
+    pub fn for_each_route(&self, mut f: impl FnMut(usize, usize)) {
+        let ports = self.input_buffers.len();
+        match &self.sink_id.channel_map {
+            Some(map) => {
+                for route in map.iter().filter(|r| (r.port as usize) < ports) {
+                    f(route.port as usize, route.channel as usize);
+                }
+            }
+            None => {
+                let offset = self.sink_id.channel_offset as usize;
+                for port in 0..ports {
+                    f(port, offset + port);
+                }
+            }
+        }
+    }
+
     /// Get output gain for a given port (linear).
     pub fn output_gain_for_port(&self, port: usize) -> f32 {
         self.output_gain_bits_by_port
@@ -141,11 +227,55 @@ impl SinkNode {
         slot.store(g.to_bits(), Ordering::Relaxed);
     }
 
+    /// Output limiter (settings can be changed through a shared reference)
+    pub fn limiter(&self) -> &LookaheadLimiter {
+        &self.limiter
+    }
+
+    /// Output protection (settings can be changed through a shared reference)
+    pub fn protection(&self) -> &OutputProtection {
+        &self.protection
+    }
+
     /// Set the label
     pub fn set_label(&mut self, label: impl Into<String>) {
         self.label = label.into();
     }
 
+    /// Hardware output latency of the target device (frames)
+    pub fn device_latency(&self) -> u32 {
+        self.device_latency
+    }
+
+    /// Set the hardware output latency (used to align sinks, see
+    /// [`crate::audio::AudioGraph::set_align_output_latency`])
+    pub fn set_device_latency(&mut self, frames: u32) {
+        self.device_latency = frames;
+    }
+
+    /// Rebind to another CoreAudio device ID (same device re-enumerated after hot-plug)
+    pub fn set_device_id(&mut self, device_id: u32) {
+        self.sink_id.device_id = device_id;
+    }
+
+    /// Whether the sink follows the system default output device
+    pub fn follows_default(&self) -> bool {
+        self.sink_id.follow_default
+    }
+
+    /// Enable or disable following the system default output device
+    pub fn set_follow_default(&mut self, follow: bool) {
+        self.sink_id.follow_default = follow;
+    }
+
+    /// Point the sink at a different device (default output changed)
+    ///
+    /// `set_device_id` と違い別のデバイスへの付け替えなので UID も差し替える。
+    pub fn retarget(&mut self, device_id: u32, device_uid: Option<String>) {
+        self.sink_id.device_id = device_id;
+        self.sink_id.device_uid = device_uid;
+    }
+
     /// Get input buffer samples for output (used by output callback)
     pub fn get_output_samples(&self, port: usize) -> Option<&[f32]> {
         self.input_buffers.get(port).map(|b| b.samples())
@@ -187,13 +317,52 @@ impl AudioNode for SinkNode {
 
     fn process(&mut self, frames: usize) {
         // シンクの処理は output callback で行う
-        // ここでは入力バッファのピークを更新するのみ
+        // ここでは出力保護・リミッターを掛けて入力バッファのピークを更新するのみ
         for buf in &mut self.input_buffers {
             buf.set_valid_frames(frames);
+        }
+        for (port, gain) in self.gain_scratch.iter_mut().enumerate() {
+            *gain = f32::from_bits(self.output_gain_bits_by_port[port].load(Ordering::Relaxed));
+        }
+        // 暴走の検出はリミッターの前（リミッターが掛かっていても気付けるように）
+        self.protection
+            .process(&mut self.input_buffers, &self.gain_scratch, frames);
+        self.limiter
+            .process(&mut self.input_buffers, &self.gain_scratch, frames);
+        for buf in &mut self.input_buffers {
             buf.update_peak();
         }
     }
 
+    fn latency_samples(&self) -> u32 {
+        self.limiter.latency_samples()
+    }
+
+    fn take_live_state(&mut self) -> Option<Box<dyn Any + Send>> {
+        let limiter = LookaheadLimiter::new(self.input_buffers.len(), self.limiter.settings());
+        let protection = OutputProtection::new(self.protection.settings());
+        Some(Box::new((
+            std::mem::replace(&mut self.limiter, limiter),
+            std::mem::replace(&mut self.protection, protection),
+        )))
+    }
+
+    fn restore_live_state(&mut self, state: Box<dyn Any + Send>) {
+        let Ok(state) = state.downcast::<(LookaheadLimiter, OutputProtection)>() else {
+            return;
+        };
+        let (limiter, protection) = *state;
+        // 設定を変えると保護が再アームされるので、変わったときだけ引き継ぐ
+        if limiter.settings() != self.limiter.settings() {
+            limiter.set_settings(self.limiter.settings());
+        }
+        if protection.settings() != self.protection.settings() {
+            protection.set_settings(self.protection.settings());
+        }
+        self.limiter = limiter;
+        self.protection = protection;
+    }
+
     fn clear_buffers(&mut self, frames: usize) {
         for buf in &mut self.input_buffers {
             buf.clear(frames);
@@ -216,3 +385,76 @@ impl AudioNode for SinkNode {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes(sink: &SinkNode) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        sink.for_each_route(|port, channel| out.push((port, channel)));
+        out
+    }
+
+    #[test]
+    fn test_default_routes_follow_offset() {
+        let sink = SinkNode::new(SinkId::with_uid(1, 4, 2, None), "Out");
+        assert_eq!(routes(&sink), vec![(0, 4), (1, 5)]);
+        assert_eq!(sink.device_channels_used(), 6);
+    }
+
+    #[test]
+    fn test_channel_map_skips_and_duplicates() {
+        let mut sink = SinkNode::new(SinkId::with_uid(1, 0, 6, None), "5.1");
+        // L/R to 0/1, C duplicated to 2 and 3, LFE skipped, surrounds to 6/7
+        let map = vec![
+            ChannelRoute {
+                port: 0,
+                channel: 0,
+            },
+            ChannelRoute {
+                port: 1,
+                channel: 1,
+            },
+            ChannelRoute {
+                port: 2,
+                channel: 2,
+            },
+            ChannelRoute {
+                port: 2,
+                channel: 3,
+            },
+            ChannelRoute {
+                port: 4,
+                channel: 6,
+            },
+            ChannelRoute {
+                port: 5,
+                channel: 7,
+            },
+        ];
+        sink.set_channel_map(Some(map.clone())).unwrap();
+        assert_eq!(
+            routes(&sink),
+            vec![(0, 0), (1, 1), (2, 2), (2, 3), (4, 6), (5, 7)]
+        );
+        assert_eq!(sink.device_channels_used(), 8);
+        assert_eq!(sink.channel_map(), Some(map.as_slice()));
+
+        // Empty map restores the contiguous layout
+        sink.set_channel_map(Some(Vec::new())).unwrap();
+        assert_eq!(sink.channel_map(), None);
+        assert_eq!(routes(&sink).len(), 6);
+    }
+
+    #[test]
+    fn test_channel_map_rejects_missing_port() {
+        let mut sink = SinkNode::new(SinkId::with_uid(1, 0, 2, None), "Out");
+        let map = vec![ChannelRoute {
+            port: 2,
+            channel: 0,
+        }];
+        assert!(sink.set_channel_map(Some(map)).is_err());
+        assert_eq!(sink.channel_map(), None);
+    }
+}
@@ -0,0 +1,193 @@
+//! Solo - solo-in-place and PFL/AFL monitoring
+//!
+//! ソロ状態（ノード/エッジ）から各エッジの「ソロミュート」を制御スレッドで計算し、
+//! Edge の Atomic フラグに書き込む。オーディオスレッドはフラグを読むだけ（RT-safe）。
+//!
+//! - SoloInPlace: ソロ対象を通らないパスをすべてミュート
+//! - Afl / Pfl: モニターシンクに流れ込むパスだけを対象にする（メインミックスはそのまま）。
+//!   Pfl ではソロしたエッジを送りレベル（フェーダー）に関係なくユニティで聴く。
+
+use super::edge::{Edge, EdgeId};
+use super::node::NodeHandle;
+use std::collections::{HashMap, HashSet};
+
+/// ソロモード
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SoloMode {
+    /// Solo in place (destructive: mutes everything else on all outputs)
+    #[default]
+    InPlace,
+    /// After-fader listen on the monitor sink
+    Afl,
+    /// Pre-fader listen on the monitor sink
+    Pfl,
+}
+
+impl SoloMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "sip" | "in_place" | "inplace" => Some(Self::InPlace),
+            "afl" => Some(Self::Afl),
+            "pfl" => Some(Self::Pfl),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InPlace => "sip",
+            Self::Afl => "afl",
+            Self::Pfl => "pfl",
+        }
+    }
+}
+
+/// グラフのソロ状態
+#[derive(Debug, Clone, Default)]
+pub struct SoloState {
+    pub mode: SoloMode,
+    /// AFL/PFL の試聴先シンク
+    pub monitor: Option<NodeHandle>,
+    /// ソロ中のノード
+    pub nodes: HashSet<NodeHandle>,
+}
+
+/// エッジごとのソロ評価結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SoloFlags {
+    /// ソロによりミュートされる
+    pub muted: bool,
+    /// PFL: ゲイン/ミュートを無視してユニティで通す
+    pub unity: bool,
+}
+
+/// 全エッジのソロフラグを計算
+///
+/// ソロ対象がない場合はすべて `SoloFlags::default()`。
+pub fn evaluate(edges: &[Edge], state: &SoloState) -> HashMap<EdgeId, SoloFlags> {
+    let soloed_edges: Vec<&Edge> = edges.iter().filter(|e| e.soloed()).collect();
+    if state.nodes.is_empty() && soloed_edges.is_empty() {
+        return edges.iter().map(|e| (e.id, SoloFlags::default())).collect();
+    }
+
+    // 上流側の起点: ソロノード + ソロエッジの送り元
+    let up_roots: Vec<NodeHandle> = state
+        .nodes
+        .iter()
+        .copied()
+        .chain(soloed_edges.iter().map(|e| e.source))
+        .collect();
+    // 下流側の起点: ソロノード + ソロエッジの送り先
+    let down_roots: Vec<NodeHandle> = state
+        .nodes
+        .iter()
+        .copied()
+        .chain(soloed_edges.iter().map(|e| e.target))
+        .collect();
+
+    let upstream = reachable(edges, &up_roots, |e| (e.target, e.source));
+    let downstream = reachable(edges, &down_roots, |e| (e.source, e.target));
+
+    // AFL/PFL ではモニターシンクに流れ込むエッジだけが対象
+    let monitored: Option<HashSet<NodeHandle>> = match (state.mode, state.monitor) {
+        (SoloMode::InPlace, _) | (_, None) => None,
+        (_, Some(monitor)) => Some(reachable(edges, &[monitor], |e| (e.target, e.source))),
+    };
+
+    edges
+        .iter()
+        .map(|e| {
+            let in_scope = monitored
+                .as_ref()
+                .map(|m| m.contains(&e.source) && m.contains(&e.target))
+                .unwrap_or(true);
+            if !in_scope {
+                return (e.id, SoloFlags::default());
+            }
+
+            let keep = e.soloed()
+                || (upstream.contains(&e.source) && upstream.contains(&e.target))
+                || (downstream.contains(&e.source) && downstream.contains(&e.target));
+
+            let unity = state.mode == SoloMode::Pfl && monitored.is_some() && e.soloed();
+            (
+                e.id,
+                SoloFlags {
+                    muted: !keep,
+                    unity,
+                },
+            )
+        })
+        .collect()
+}
+
+/// `roots` から `step` の向きに辿れるノード集合（roots を含む）
+fn reachable(
+    edges: &[Edge],
+    roots: &[NodeHandle],
+    step: impl Fn(&Edge) -> (NodeHandle, NodeHandle),
+) -> HashSet<NodeHandle> {
+    let mut visited: HashSet<NodeHandle> = roots.iter().copied().collect();
+    let mut stack: Vec<NodeHandle> = roots.to_vec();
+
+    while let Some(handle) = stack.pop() {
+        for edge in edges {
+            let (from, to) = step(edge);
+            if from == handle && visited.insert(to) {
+                stack.push(to);
+            }
+        }
+    }
+
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::node::PortId;
+
+    fn edge(id: u32, source: u32, target: u32) -> Edge {
+        Edge::new(
+            EdgeId::new(id),
+            NodeHandle::new(source),
+            PortId::new(0),
+            NodeHandle::new(target),
+            PortId::new(0),
+        )
+    }
+
+    #[test]
+    fn test_solo_in_place_node() {
+        // src1 -> bus(3) -> out(4), src2 -> bus(3), src2 -> out(4)
+        let edges = vec![edge(1, 1, 3), edge(2, 3, 4), edge(3, 2, 3), edge(4, 2, 4)];
+        let mut state = SoloState::default();
+        state.nodes.insert(NodeHandle::new(1));
+
+        let flags = evaluate(&edges, &state);
+        assert!(!flags[&EdgeId::new(1)].muted);
+        assert!(!flags[&EdgeId::new(2)].muted);
+        assert!(flags[&EdgeId::new(3)].muted);
+        assert!(flags[&EdgeId::new(4)].muted);
+    }
+
+    #[test]
+    fn test_pfl_only_affects_monitor() {
+        // src1 -> main(3), src2 -> main(3), src1 -> phones(4), src2 -> phones(4)
+        let edges = vec![edge(1, 1, 3), edge(2, 2, 3), edge(3, 1, 4), edge(4, 2, 4)];
+        edges[2].set_soloed(true);
+        let state = SoloState {
+            mode: SoloMode::Pfl,
+            monitor: Some(NodeHandle::new(4)),
+            nodes: HashSet::new(),
+        };
+
+        let flags = evaluate(&edges, &state);
+        // Main mix untouched
+        assert_eq!(flags[&EdgeId::new(1)], SoloFlags::default());
+        assert_eq!(flags[&EdgeId::new(2)], SoloFlags::default());
+        // Phones: only the soloed edge, at unity
+        assert!(flags[&EdgeId::new(3)].unity && !flags[&EdgeId::new(3)].muted);
+        assert!(flags[&EdgeId::new(4)].muted);
+    }
+}
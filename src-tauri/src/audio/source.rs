@@ -4,9 +4,10 @@ use super::buffer::AudioBuffer;
 use super::node::{AudioNode, NodeType, PortId};
 use serde::{Deserialize, Serialize};
 use std::any::Any;
+use std::sync::atomic::{AtomicU32, Ordering};
 
 /// ソースの識別
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum SourceId {
     /// Prism 仮想デバイスのチャンネル
@@ -17,6 +18,9 @@ pub enum SourceId {
     InputDevice { device_id: u32, channel: u8 },
 }
 
+/// トリムの上限（リニア、約 +24 dB）
+const MAX_TRIM: f32 = 16.0;
+
 /// 入力ソースノード
 ///
 /// Prism チャンネルまたは外部入力デバイスから音声を取得
@@ -27,6 +31,12 @@ pub struct SourceNode {
     label: String,
     /// 出力バッファ（モノラル = 1ポート）
     output_buffers: Vec<AudioBuffer>,
+    /// ポートごとの入力トリム（linear、極性反転は負の値）。
+    ///
+    /// f32 bits を AtomicU32 に格納して RT-safe に読む。エッジに分配する前に適用する。
+    trim_bits_by_port: Vec<AtomicU32>,
+    /// 作成時のポート数（デバイスのチャンネルが減って縮めた後、戻ったときの上限）
+    configured_ports: usize,
 }
 
 impl SourceNode {
@@ -37,6 +47,8 @@ impl SourceNode {
             label: label.into(),
             // Prism channels are stereo pairs
             output_buffers: vec![AudioBuffer::new(), AudioBuffer::new()],
+            trim_bits_by_port: unity_trims(2),
+            configured_ports: 2,
         }
     }
 
@@ -47,6 +59,8 @@ impl SourceNode {
             label: label.into(),
             // Default to stereo for input devices
             output_buffers: vec![AudioBuffer::new(), AudioBuffer::new()],
+            trim_bits_by_port: unity_trims(2),
+            configured_ports: 2,
         }
     }
 
@@ -61,6 +75,8 @@ impl SourceNode {
             source_id: SourceId::InputDevice { device_id, channel },
             label: label.into(),
             output_buffers: (0..channel_count).map(|_| AudioBuffer::new()).collect(),
+            trim_bits_by_port: unity_trims(channel_count),
+            configured_ports: channel_count,
         }
     }
 
@@ -73,6 +89,87 @@ impl SourceNode {
     pub fn set_label(&mut self, label: impl Into<String>) {
         self.label = label.into();
     }
+
+    /// Input trim for a port (linear, always positive)
+    pub fn trim_for_port(&self, port: usize) -> f32 {
+        self.signed_trim(port).abs()
+    }
+
+    /// Whether a port's polarity is inverted
+    pub fn phase_inverted(&self, port: usize) -> bool {
+        self.signed_trim(port).is_sign_negative()
+    }
+
+    /// Set input trim (linear) for one port, keeping its polarity
+    pub fn set_trim_for_port(&self, port: usize, trim: f32) {
+        let Some(slot) = self.trim_bits_by_port.get(port) else {
+            return;
+        };
+        let t = if trim.is_finite() { trim } else { 1.0 };
+        let t = t.clamp(0.0, MAX_TRIM);
+        let t = if self.phase_inverted(port) { -t } else { t };
+        slot.store(t.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Invert (or restore) the polarity of one port
+    pub fn set_phase_inverted(&self, port: usize, inverted: bool) {
+        let Some(slot) = self.trim_bits_by_port.get(port) else {
+            return;
+        };
+        let t = self.trim_for_port(port);
+        let t = if inverted { -t } else { t };
+        slot.store(t.to_bits(), Ordering::Relaxed);
+    }
+
+    /// トリムと極性を適用したゲイン（オーディオスレッド用）
+    #[inline]
+    pub fn signed_trim(&self, port: usize) -> f32 {
+        self.trim_bits_by_port
+            .get(port)
+            .map(|t| f32::from_bits(t.load(Ordering::Relaxed)))
+            .unwrap_or(1.0)
+    }
+
+    /// Rebind an input-device source to another CoreAudio device ID
+    ///
+    /// Returns false for Prism sources.
+    pub fn set_device_id(&mut self, new_device_id: u32) -> bool {
+        match &mut self.source_id {
+            SourceId::InputDevice { device_id, .. } => {
+                *device_id = new_device_id;
+                true
+            }
+            SourceId::PrismChannel { .. } => false,
+        }
+    }
+
+    /// Fit the port count to a device that now has `input_channels` channels
+    ///
+    /// 先頭チャンネル以降に残っているチャンネル数まで縮め（最低 1 ポート）、
+    /// チャンネルが戻れば作成時のポート数まで戻す。残るポートのトリムは保持する。
+    /// Returns true when the port count changed (Prism sources never change).
+    pub fn fit_to_device_channels(&mut self, input_channels: usize) -> bool {
+        let SourceId::InputDevice { channel, .. } = self.source_id else {
+            return false;
+        };
+        let ports = input_channels
+            .saturating_sub(channel as usize)
+            .min(self.configured_ports)
+            .max(1);
+        if ports == self.output_buffers.len() {
+            return false;
+        }
+        self.output_buffers.resize_with(ports, AudioBuffer::new);
+        self.trim_bits_by_port
+            .resize_with(ports, || AtomicU32::new(1.0_f32.to_bits()));
+        true
+    }
+}
+
+fn unity_trims(ports: usize) -> Vec<AtomicU32> {
+    (0..ports)
+        .map(|_| AtomicU32::new(1.0_f32.to_bits()))
+        .collect()
 }
 
 impl AudioNode for SourceNode {
@@ -141,3 +238,54 @@ impl AudioNode for SourceNode {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_and_phase_are_independent() {
+        let source = SourceNode::new_device(1, 0, "Mic");
+
+        source.set_trim_for_port(0, 0.5);
+        source.set_phase_inverted(0, true);
+        assert_eq!(source.signed_trim(0), -0.5);
+
+        // Changing trim keeps the polarity, and vice versa
+        source.set_trim_for_port(0, 2.0);
+        assert_eq!(source.signed_trim(0), -2.0);
+        source.set_phase_inverted(0, false);
+        assert_eq!(source.signed_trim(0), 2.0);
+
+        // Other ports and out-of-range ports stay at unity
+        assert_eq!(source.signed_trim(1), 1.0);
+        assert_eq!(source.signed_trim(7), 1.0);
+        source.set_trim_for_port(1, f32::NAN);
+        assert_eq!(source.trim_for_port(1), 1.0);
+    }
+
+    #[test]
+    fn test_fit_to_device_channels() {
+        let mut source = SourceNode::new_device_with_channels(1, 2, "Mic 3-6", 4);
+        source.set_trim_for_port(0, 0.5);
+
+        // Device dropped to 4 channels: only channels 3-4 remain
+        assert!(source.fit_to_device_channels(4));
+        assert_eq!(source.output_port_count(), 2);
+        assert_eq!(source.trim_for_port(0), 0.5);
+        assert!(!source.fit_to_device_channels(4));
+
+        // Channel range gone entirely: keep one port
+        assert!(source.fit_to_device_channels(2));
+        assert_eq!(source.output_port_count(), 1);
+
+        // Back to a large device: grow only to the configured size
+        assert!(source.fit_to_device_channels(32));
+        assert_eq!(source.output_port_count(), 4);
+        assert_eq!(source.trim_for_port(0), 0.5);
+        assert_eq!(source.trim_for_port(3), 1.0);
+
+        let mut prism = SourceNode::new_prism(0, "Prism");
+        assert!(!prism.fit_to_device_channels(1));
+    }
+}
@@ -0,0 +1,182 @@
+//! Gain conversion - dBFS <-> linear and fader taper
+//!
+//! フロントエンドとオートメーションが同じ変換を使えるよう、dB↔リニア変換と
+//! フェーダー位置 (0.0 ~ 1.0) ↔ dB のテーパーテーブルをバックエンドで一元管理する。
+//! -inf dB はリニア 0.0 / `None` で表す（JSON では null）。
+
+use arc_swap::ArcSwap;
+use std::sync::{Arc, OnceLock};
+
+/// これ以下の dB は -inf（無音）として扱う
+pub const MIN_DB: f32 = -144.0;
+
+/// エッジ/出力ゲインの上限（+12 dB）
+pub const MAX_DB: f32 = 12.0;
+
+/// dB → リニア（`None` または MIN_DB 以下で 0.0）
+#[inline]
+pub fn db_to_linear(db: Option<f32>) -> f32 {
+    match db {
+        Some(db) if db.is_finite() && db > MIN_DB => 10f32.powf(db.min(MAX_DB) / 20.0),
+        Some(db) if db == f32::INFINITY => 10f32.powf(MAX_DB / 20.0),
+        _ => 0.0,
+    }
+}
+
+/// リニア → dB（0 以下は `None` = -inf）
+#[inline]
+pub fn linear_to_db(gain: f32) -> Option<f32> {
+    if gain <= 0.0 || !gain.is_finite() {
+        return None;
+    }
+    let db = 20.0 * gain.log10();
+    if db <= MIN_DB {
+        None
+    } else {
+        Some(db)
+    }
+}
+
+/// フェーダーテーパー
+///
+/// (位置, dB) の折れ線。位置 0.0 は常に -inf。点は位置・dB ともに単調増加。
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaderTaper {
+    points: Vec<(f32, f32)>,
+}
+
+impl Default for FaderTaper {
+    /// 一般的なコンソール風のテーパー（0 dB が 3/4 の位置）
+    fn default() -> Self {
+        Self {
+            points: vec![
+                (0.0, -90.0),
+                (0.05, -60.0),
+                (0.25, -30.0),
+                (0.5, -12.0),
+                (0.75, 0.0),
+                (1.0, 6.0),
+            ],
+        }
+    }
+}
+
+impl FaderTaper {
+    /// Build a taper from (position, dB) points
+    ///
+    /// Requires at least two points, positions starting at 0.0 and ending at 1.0,
+    /// and both coordinates strictly increasing.
+    pub fn new(points: Vec<(f32, f32)>) -> Result<Self, String> {
+        if points.len() < 2 {
+            return Err("Taper needs at least two points".to_string());
+        }
+        if points
+            .iter()
+            .any(|(p, db)| !p.is_finite() || !db.is_finite())
+        {
+            return Err("Taper points must be finite".to_string());
+        }
+        if points[0].0 != 0.0 || points[points.len() - 1].0 != 1.0 {
+            return Err("Taper positions must span 0.0 to 1.0".to_string());
+        }
+        if points
+            .windows(2)
+            .any(|w| w[1].0 <= w[0].0 || w[1].1 <= w[0].1)
+        {
+            return Err("Taper points must be strictly increasing".to_string());
+        }
+        if points[points.len() - 1].1 > MAX_DB {
+            return Err(format!("Taper top must be <= {} dB", MAX_DB));
+        }
+        Ok(Self { points })
+    }
+
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// フェーダー位置 → dB（位置 0 は `None` = -inf）
+    pub fn position_to_db(&self, position: f32) -> Option<f32> {
+        let position = position.clamp(0.0, 1.0);
+        if position <= 0.0 {
+            return None;
+        }
+        let i = self
+            .points
+            .windows(2)
+            .position(|w| position <= w[1].0)
+            .unwrap_or(self.points.len() - 2);
+        let (p0, d0) = self.points[i];
+        let (p1, d1) = self.points[i + 1];
+        Some(d0 + (d1 - d0) * (position - p0) / (p1 - p0))
+    }
+
+    /// dB → フェーダー位置（`None` / テーブル下限以下は 0.0）
+    pub fn db_to_position(&self, db: Option<f32>) -> f32 {
+        let Some(db) = db else {
+            return 0.0;
+        };
+        let (_, bottom) = self.points[0];
+        let (_, top) = self.points[self.points.len() - 1];
+        if db <= bottom {
+            return 0.0;
+        }
+        if db >= top {
+            return 1.0;
+        }
+        let i = self
+            .points
+            .windows(2)
+            .position(|w| db <= w[1].1)
+            .unwrap_or(self.points.len() - 2);
+        let (p0, d0) = self.points[i];
+        let (p1, d1) = self.points[i + 1];
+        p0 + (p1 - p0) * (db - d0) / (d1 - d0)
+    }
+}
+
+static FADER_TAPER: OnceLock<ArcSwap<FaderTaper>> = OnceLock::new();
+
+fn taper_cell() -> &'static ArcSwap<FaderTaper> {
+    FADER_TAPER.get_or_init(|| ArcSwap::from_pointee(FaderTaper::default()))
+}
+
+/// Get the current fader taper
+pub fn fader_taper() -> Arc<FaderTaper> {
+    taper_cell().load_full()
+}
+
+/// Replace the fader taper
+pub fn set_fader_taper(taper: FaderTaper) {
+    taper_cell().store(Arc::new(taper));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_db_linear_round_trip() {
+        assert_eq!(db_to_linear(Some(0.0)), 1.0);
+        assert_eq!(db_to_linear(None), 0.0);
+        assert_eq!(db_to_linear(Some(f32::NEG_INFINITY)), 0.0);
+        assert!((db_to_linear(Some(-6.0206)) - 0.5).abs() < 1e-4);
+        assert_eq!(linear_to_db(0.0), None);
+        assert!((linear_to_db(2.0).unwrap() - 6.0206).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_taper_inverse() {
+        let taper = FaderTaper::default();
+        assert_eq!(taper.position_to_db(0.0), None);
+        assert_eq!(taper.position_to_db(0.75), Some(0.0));
+        assert_eq!(taper.db_to_position(None), 0.0);
+
+        for pos in [0.1f32, 0.3, 0.6, 0.9] {
+            let db = taper.position_to_db(pos);
+            assert!((taper.db_to_position(db) - pos).abs() < 1e-5);
+        }
+
+        assert!(FaderTaper::new(vec![(0.0, 0.0), (0.5, -1.0), (1.0, 6.0)]).is_err());
+    }
+}
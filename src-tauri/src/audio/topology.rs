@@ -0,0 +1,309 @@
+//! Topology - graph structure compiled off the audio thread
+//!
+//! グラフの変更はオーディオスレッドで適用されるが、処理順（トポロジカルソート）・
+//! スケジュール・レイテンシ補正のディレイライン・ソロフラグの計算は確保を伴う。
+//! 変更を適用したブロックでは構造を [`Topology`] に写すだけにし（予約済みの容量に詰める）、
+//! 呼び出し側のスレッドで [`Topology::compile`] した [`CompiledGraph`] を次のブロックの頭で
+//! [`AudioGraph::install`](super::AudioGraph::install) する。外れた古い処理順・スケジュール・
+//! ディレイラインは [`reclaim`](super::reclaim) スレッドで drop する。
+//!
+//! 差し替えまでの間（1 ブロック程度）は古いスケジュールのまま処理する。削除したノードは
+//! グラフに無いので飛ばされ、削除したエッジは切り離し済みなので鳴らない。追加したものは
+//! 差し替え後から鳴る。
+
+use super::edge::{DelayLine, Edge, TapPoint};
+use super::node::{NodeHandle, NodeType};
+use super::schedule::RenderSchedule;
+use super::solo::{self, SoloFlags, SoloMode, SoloState};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// Room reserved beyond the last captured size (a capture within it does not allocate)
+const TOPOLOGY_SLACK: usize = 64;
+
+/// What the compile step needs to know about one node
+#[derive(Debug, Clone, Copy)]
+pub struct TopologyNode {
+    pub handle: NodeHandle,
+    pub node_type: NodeType,
+    /// Processing latency in samples (cached plugin latency for buses)
+    pub latency: u32,
+    /// Output device latency of a sink (None for other nodes)
+    pub device_latency: Option<u32>,
+}
+
+/// Structure of an audio graph (filled by `AudioGraph::capture_topology`)
+#[derive(Debug, Default)]
+pub struct Topology {
+    pub(super) nodes: Vec<TopologyNode>,
+    /// Edges (clones share parameters and delay lines with the graph)
+    pub(super) edges: Vec<Edge>,
+    /// Current compensation delay and delay line count of each edge (same order as `edges`)
+    pub(super) delays: Vec<(usize, usize)>,
+    pub(super) cue_sink: Option<NodeHandle>,
+    pub(super) align_output_latency: bool,
+    pub(super) solo_mode: SoloMode,
+    pub(super) solo_monitor: Option<NodeHandle>,
+    pub(super) solo_nodes: Vec<NodeHandle>,
+    /// Bus plugin slots whose dry delay must change: (bus, slot, (delay, line count))
+    pub(super) dry_delays: Vec<(NodeHandle, usize, (usize, usize))>,
+    /// Sizes of the last capture (`reserve` keeps room beyond them)
+    captured: (usize, usize),
+}
+
+/// Processing order, schedule, compensation delay lines and solo flags ready to install
+#[derive(Debug, Default)]
+pub struct CompiledGraph {
+    pub(super) order: Vec<NodeHandle>,
+    pub(super) schedule: Arc<RenderSchedule>,
+    /// Edges whose delay lines change, with the replacement lines (allocated here)
+    pub(super) delays: Vec<(Edge, Vec<DelayLine>)>,
+    /// Solo flags of every edge
+    pub(super) solo: Vec<(Edge, SoloFlags)>,
+    /// Bus plugin slots with their new dry delay lines (empty to remove them)
+    pub(super) dry_delays: Vec<(NodeHandle, usize, Vec<DelayLine>)>,
+}
+
+impl Topology {
+    /// Make room for the last captured graph plus a margin (call before a capture)
+    pub fn reserve(&mut self) {
+        let (nodes, edges) = self.captured;
+        self.nodes.reserve(nodes + TOPOLOGY_SLACK);
+        self.edges.reserve(edges + TOPOLOGY_SLACK);
+        self.delays.reserve(edges + TOPOLOGY_SLACK);
+        self.solo_nodes.reserve(nodes + TOPOLOGY_SLACK);
+        self.dry_delays.reserve(TOPOLOGY_SLACK);
+    }
+
+    /// Empty the buffers (drops the edge clones; capacity is kept)
+    pub fn clear(&mut self) {
+        self.captured = (self.nodes.len(), self.edges.len());
+        self.nodes.clear();
+        self.edges.clear();
+        self.delays.clear();
+        self.solo_nodes.clear();
+        self.dry_delays.clear();
+    }
+
+    /// Ids of the captured edges
+    pub fn edge_ids(&self) -> Vec<u32> {
+        self.edges.iter().map(|e| e.id.raw()).collect()
+    }
+
+    /// Compile the captured structure (control thread)
+    pub fn compile(&self) -> CompiledGraph {
+        let order = self.processing_order();
+        let schedule = Arc::new(RenderSchedule::compile(self, &order));
+
+        let (arrival, output) = self.path_latencies(&order);
+        let alignment = self.output_alignment_delays(&output);
+        let delays = self
+            .edges
+            .iter()
+            .zip(&self.delays)
+            .filter_map(|(edge, &current)| {
+                let source_latency = edge_source_latency(edge, &arrival, &output);
+                let target_arrival = arrival.get(&edge.target).copied().unwrap_or(0);
+                let compensation = target_arrival.saturating_sub(source_latency)
+                    + alignment.get(&edge.target).copied().unwrap_or(0);
+                let wanted = (
+                    DelayLine::clamp_delay(compensation as usize),
+                    edge.channels as usize,
+                );
+                (wanted != current).then(|| {
+                    let lines = (0..wanted.1)
+                        .map(|_| DelayLine::with_delay(wanted.0))
+                        .collect();
+                    (edge.clone(), lines)
+                })
+            })
+            .collect();
+
+        let state = SoloState {
+            mode: self.solo_mode,
+            monitor: self.solo_monitor,
+            nodes: self.solo_nodes.iter().copied().collect(),
+        };
+        let flags = solo::evaluate(&self.edges, &state);
+        let solo = self
+            .edges
+            .iter()
+            .map(|e| (e.clone(), flags.get(&e.id).copied().unwrap_or_default()))
+            .collect();
+
+        let dry_delays = self
+            .dry_delays
+            .iter()
+            .map(|&(bus, slot, (delay, count))| {
+                let lines = (0..count).map(|_| DelayLine::with_delay(delay)).collect();
+                (bus, slot, lines)
+            })
+            .collect();
+
+        CompiledGraph {
+            order,
+            schedule,
+            delays,
+            solo,
+            dry_delays,
+        }
+    }
+
+    fn node(&self, handle: NodeHandle) -> Option<&TopologyNode> {
+        self.nodes.iter().find(|n| n.handle == handle)
+    }
+
+    pub(super) fn node_type(&self, handle: NodeHandle) -> Option<NodeType> {
+        self.node(handle).map(|n| n.node_type)
+    }
+
+    fn edges_to(&self, target: NodeHandle) -> impl Iterator<Item = &Edge> {
+        self.edges.iter().filter(move |e| e.target == target)
+    }
+
+    /// 処理順序（キューシンクはすべての送り元の後）
+    fn processing_order(&self) -> Vec<NodeHandle> {
+        let mut order = self.topological_sort();
+        // キューシンクは出力が無いので最後に回しても順序は崩れない
+        if let Some(cue) = self.cue_sink {
+            if let Some(pos) = order.iter().position(|&h| h == cue) {
+                let cue = order.remove(pos);
+                order.push(cue);
+            }
+        }
+        order
+    }
+
+    /// トポロジカルソート (Kahn's algorithm)
+    fn topological_sort(&self) -> Vec<NodeHandle> {
+        let mut in_degree: HashMap<NodeHandle, usize> = HashMap::new();
+        let mut adjacency: HashMap<NodeHandle, Vec<NodeHandle>> = HashMap::new();
+
+        // Initialize
+        for node in &self.nodes {
+            in_degree.insert(node.handle, 0);
+            adjacency.insert(node.handle, Vec::new());
+        }
+
+        // Build adjacency
+        for edge in &self.edges {
+            if let Some(adj) = adjacency.get_mut(&edge.source) {
+                if !adj.contains(&edge.target) {
+                    adj.push(edge.target);
+                }
+            }
+        }
+
+        // In-degree counts unique source->target pairs (multi-port edges count once)
+        for (&handle, deg) in in_degree.iter_mut() {
+            let sources: HashSet<_> = self.edges_to(handle).map(|e| e.source).collect();
+            *deg = sources.len();
+        }
+
+        // Start with nodes that have no incoming edges, sorted by node type
+        // (Source first, then Bus, then Sink)
+        let mut queue: Vec<_> = in_degree
+            .iter()
+            .filter(|(_, &deg)| deg == 0)
+            .map(|(&handle, _)| handle)
+            .collect();
+        queue.sort_by_key(|&h| match self.node_type(h) {
+            Some(NodeType::Source) => 0,
+            Some(NodeType::Bus) => 1,
+            Some(NodeType::Sink) | Some(NodeType::Record) => 2,
+            None => 3,
+        });
+        let mut queue: VecDeque<_> = queue.into_iter().collect();
+
+        let mut result = Vec::new();
+
+        while let Some(handle) = queue.pop_front() {
+            result.push(handle);
+
+            if let Some(neighbors) = adjacency.get(&handle) {
+                for &neighbor in neighbors {
+                    if let Some(deg) = in_degree.get_mut(&neighbor) {
+                        *deg = deg.saturating_sub(1);
+                        if *deg == 0 {
+                            queue.push_back(neighbor);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Check for cycles (if result doesn't contain all nodes)
+        if result.len() != self.nodes.len() {
+            log_warn!(
+                "[AudioGraph] Warning: Cycle detected! Processed {} of {} nodes",
+                result.len(),
+                self.nodes.len()
+            );
+        }
+
+        result
+    }
+
+    /// ノードごとのパスレイテンシ
+    ///
+    /// 戻り値: (入力到達レイテンシ, 出力レイテンシ)
+    pub(super) fn path_latencies(
+        &self,
+        order: &[NodeHandle],
+    ) -> (HashMap<NodeHandle, u32>, HashMap<NodeHandle, u32>) {
+        let mut arrival: HashMap<NodeHandle, u32> = HashMap::new();
+        let mut output: HashMap<NodeHandle, u32> = HashMap::new();
+
+        for &handle in order {
+            let node_arrival = self
+                .edges_to(handle)
+                .map(|e| edge_source_latency(e, &arrival, &output))
+                .max()
+                .unwrap_or(0);
+            let node_latency = self.node(handle).map_or(0, |n| n.latency);
+            arrival.insert(handle, node_arrival);
+            output.insert(handle, node_arrival.saturating_add(node_latency));
+        }
+
+        (arrival, output)
+    }
+
+    /// Extra delay per sink for output alignment (empty when disabled)
+    ///
+    /// 入力のあるシンクだけを揃える（未接続のシンクの遅いデバイスに引きずられないように）。
+    pub(super) fn output_alignment_delays(
+        &self,
+        output: &HashMap<NodeHandle, u32>,
+    ) -> HashMap<NodeHandle, u32> {
+        if !self.align_output_latency {
+            return HashMap::new();
+        }
+        let totals: Vec<(NodeHandle, u32)> = self
+            .nodes
+            .iter()
+            .filter(|n| self.cue_sink != Some(n.handle) && self.edges_to(n.handle).next().is_some())
+            .filter_map(|n| {
+                let path = output.get(&n.handle).copied().unwrap_or(0);
+                Some((n.handle, path.saturating_add(n.device_latency?)))
+            })
+            .collect();
+        let slowest = totals.iter().map(|&(_, total)| total).max().unwrap_or(0);
+        totals
+            .into_iter()
+            .map(|(handle, total)| (handle, slowest - total))
+            .collect()
+    }
+}
+
+/// エッジが取り出す時点のソース側レイテンシ（プリプラグインはバスのプラグイン分を含まない）
+fn edge_source_latency(
+    edge: &Edge,
+    arrival: &HashMap<NodeHandle, u32>,
+    output: &HashMap<NodeHandle, u32>,
+) -> u32 {
+    let latencies = match edge.tap_point() {
+        TapPoint::PrePlugin => arrival,
+        TapPoint::PostPlugin => output,
+    };
+    latencies.get(&edge.source).copied().unwrap_or(0)
+}
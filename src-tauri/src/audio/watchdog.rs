@@ -0,0 +1,341 @@
+//! Engine watchdog - heartbeats of the realtime runtimes
+//!
+//! 出力コールバック・キャプチャコールバックは呼ばれるたびにハートビートを
+//! 進める（Atomic のみ）。監視スレッドは一定間隔でカウンタを読み、動作中のはずの
+//! ランタイムが `stall_ms` 以上進んでいなければ停止とみなして再起動する。
+//!
+//! 意図的に止めたランタイムは [`disarm`] で監視対象から外す。スレッドがエラーで
+//! 抜けた場合は外されないので、ハートビートが止まったまま検出される。
+//!
+//! プラグイン単位では [`RenderGuard`] がレンダー時間を監視し、予算超過が続いた
+//! プラグインをバイパスさせる（1 つの重いプラグインで全デバイスが途切れないように）。
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+/// Default time without a callback before a runtime counts as stalled
+pub const DEFAULT_STALL_MS: u64 = 2_000;
+
+/// Restarts allowed per runtime within [`RESTART_WINDOW`] before giving up
+pub const MAX_RESTARTS: usize = 3;
+
+/// Window for [`MAX_RESTARTS`]
+pub const RESTART_WINDOW: Duration = Duration::from_secs(60);
+
+/// Share of the block period one plugin render may take
+pub const RENDER_BUDGET: f64 = 0.5;
+
+/// Score added per over-budget render (one is taken off per render within budget)
+const OVERLOAD_PENALTY: u32 = 4;
+
+/// Score at which a plugin is bypassed (16 slow renders in a row, or ~1 in 5 sustained)
+const OVERLOAD_LIMIT: u32 = 64;
+
+/// A watched runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Component {
+    /// Output render callback of a device
+    Output(u32),
+    /// Input callback of a capture device
+    Capture(u32),
+}
+
+impl Component {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Output(_) => "output",
+            Self::Capture(_) => "capture",
+        }
+    }
+
+    pub fn device_id(&self) -> u32 {
+        match self {
+            Self::Output(id) | Self::Capture(id) => *id,
+        }
+    }
+}
+
+/// Callback counter of one runtime
+#[derive(Debug, Default)]
+pub struct Heartbeat {
+    beats: AtomicU64,
+}
+
+impl Heartbeat {
+    /// Record one callback (audio thread)
+    #[inline]
+    pub fn beat(&self) {
+        self.beats.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.beats.load(Ordering::Relaxed)
+    }
+}
+
+/// Armed runtimes: component -> heartbeat
+static HEARTBEATS: LazyLock<RwLock<HashMap<Component, Arc<Heartbeat>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Arm watching for a runtime and get its heartbeat
+///
+/// Call from the control thread when the runtime starts and keep the `Arc` for
+/// the callback. Re-arming replaces the counter (a restart starts from zero).
+pub fn arm(component: Component) -> Arc<Heartbeat> {
+    let heartbeat = Arc::new(Heartbeat::default());
+    HEARTBEATS.write().insert(component, heartbeat.clone());
+    heartbeat
+}
+
+/// Stop watching a runtime (intentional stop)
+pub fn disarm(component: Component) {
+    HEARTBEATS.write().remove(&component);
+}
+
+/// Whether a runtime is armed
+pub fn is_armed(component: Component) -> bool {
+    HEARTBEATS.read().contains_key(&component)
+}
+
+/// Current beat counts of every armed runtime (sorted)
+pub fn beats() -> Vec<(Component, u64)> {
+    let mut beats: Vec<(Component, u64)> = HEARTBEATS
+        .read()
+        .iter()
+        .map(|(&c, h)| (c, h.count()))
+        .collect();
+    beats.sort_by_key(|(c, _)| *c);
+    beats
+}
+
+/// Render time guard of one plugin (leaky bucket, audio thread)
+///
+/// 予算（ブロック長 × [`RENDER_BUDGET`]）を超えたレンダーで点数が増え、予算内なら
+/// 減る。[`OVERLOAD_LIMIT`] に達したら過負荷として止める（解除はプラグインの再起動時）。
+/// 戻ってこないレンダー自体は打ち切れないので、遅いレンダーの繰り返しを防ぐもの。
+#[derive(Debug, Default)]
+pub struct RenderGuard {
+    score: AtomicU32,
+    overloaded: AtomicBool,
+    /// Duration of the last over-budget render (µs)
+    slow_render_us: AtomicU32,
+}
+
+impl RenderGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Score one render of `frames`; returns true when this render tripped the guard
+    #[inline]
+    pub fn record(&self, elapsed: Duration, frames: usize, sample_rate: f64) -> bool {
+        if sample_rate <= 0.0 || self.is_overloaded() {
+            return false;
+        }
+        let budget = frames as f64 / sample_rate * RENDER_BUDGET;
+        let score = self.score.load(Ordering::Relaxed);
+        if elapsed.as_secs_f64() <= budget {
+            self.score.store(score.saturating_sub(1), Ordering::Relaxed);
+            return false;
+        }
+
+        let micros = elapsed.as_micros().min(u32::MAX as u128) as u32;
+        self.slow_render_us.store(micros, Ordering::Relaxed);
+        let score = score + OVERLOAD_PENALTY;
+        self.score.store(score, Ordering::Relaxed);
+        score >= OVERLOAD_LIMIT && !self.overloaded.swap(true, Ordering::AcqRel)
+    }
+
+    /// Trip the guard from outside (e.g. a sibling instance overloaded)
+    pub fn trip(&self) -> bool {
+        !self.overloaded.swap(true, Ordering::AcqRel)
+    }
+
+    /// Clear the score and the overload (plugin restarted)
+    pub fn reset(&self) {
+        self.score.store(0, Ordering::Relaxed);
+        self.overloaded.store(false, Ordering::Release);
+    }
+
+    /// Whether the plugin has been bypassed for overload
+    pub fn is_overloaded(&self) -> bool {
+        self.overloaded.load(Ordering::Acquire)
+    }
+
+    /// Duration of the last over-budget render (ms)
+    pub fn slow_render_ms(&self) -> f32 {
+        self.slow_render_us.load(Ordering::Relaxed) as f32 / 1000.0
+    }
+}
+
+/// Stall detection state (watchdog thread only)
+#[derive(Debug, Default)]
+pub struct StallDetector {
+    /// component -> (last count, when it last changed)
+    last: HashMap<Component, (u64, Instant)>,
+    /// Restart times per component (within the window)
+    restarts: HashMap<Component, Vec<Instant>>,
+}
+
+impl StallDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the current counts; returns runtimes stalled for at least `stall`
+    /// and how long they have been silent
+    ///
+    /// 新しく監視対象になったものはこの時点から計る。報告済みのものは
+    /// [`Self::reset`] されるまで続けて返す。
+    pub fn observe(
+        &mut self,
+        now: Instant,
+        beats: &[(Component, u64)],
+        stall: Duration,
+    ) -> Vec<(Component, Duration)> {
+        self.last
+            .retain(|c, _| beats.iter().any(|(armed, _)| armed == c));
+
+        let mut stalled = Vec::new();
+        for &(component, count) in beats {
+            let entry = self.last.entry(component).or_insert((count, now));
+            if entry.0 != count {
+                *entry = (count, now);
+                continue;
+            }
+            let silent = now.saturating_duration_since(entry.1);
+            if silent >= stall {
+                stalled.push((component, silent));
+            }
+        }
+        stalled
+    }
+
+    /// Forget the timing of a runtime (after restarting it)
+    pub fn reset(&mut self, component: Component, now: Instant) {
+        if let Some(entry) = self.last.get_mut(&component) {
+            entry.1 = now;
+        }
+    }
+
+    /// Record a restart attempt; false when the runtime has used up its restarts
+    pub fn allow_restart(&mut self, component: Component, now: Instant) -> bool {
+        let attempts = self.restarts.entry(component).or_default();
+        attempts.retain(|&t| now.saturating_duration_since(t) < RESTART_WINDOW);
+        if attempts.len() >= MAX_RESTARTS {
+            return false;
+        }
+        attempts.push(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STALL: Duration = Duration::from_millis(500);
+
+    #[test]
+    fn test_stall_is_detected_after_timeout() {
+        let mut detector = StallDetector::new();
+        let out = Component::Output(1);
+        let t0 = Instant::now();
+
+        assert!(detector.observe(t0, &[(out, 10)], STALL).is_empty());
+        // Still beating
+        assert!(detector
+            .observe(t0 + Duration::from_millis(400), &[(out, 20)], STALL)
+            .is_empty());
+        // Silent, but not long enough
+        assert!(detector
+            .observe(t0 + Duration::from_millis(800), &[(out, 20)], STALL)
+            .is_empty());
+        let stalled = detector.observe(t0 + Duration::from_millis(1000), &[(out, 20)], STALL);
+        assert_eq!(stalled, vec![(out, Duration::from_millis(600))]);
+
+        // Restart resets the timer
+        detector.reset(out, t0 + Duration::from_millis(1000));
+        assert!(detector
+            .observe(t0 + Duration::from_millis(1200), &[(out, 20)], STALL)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_disarmed_runtime_is_forgotten() {
+        let mut detector = StallDetector::new();
+        let cap = Component::Capture(7);
+        let t0 = Instant::now();
+
+        detector.observe(t0, &[(cap, 0)], STALL);
+        assert!(detector.observe(t0 + STALL * 4, &[], STALL).is_empty());
+        // Armed again: timing starts over
+        assert!(detector
+            .observe(t0 + STALL * 5, &[(cap, 0)], STALL)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_restart_budget() {
+        let mut detector = StallDetector::new();
+        let out = Component::Output(1);
+        let t0 = Instant::now();
+
+        for _ in 0..MAX_RESTARTS {
+            assert!(detector.allow_restart(out, t0));
+        }
+        assert!(!detector.allow_restart(out, t0 + Duration::from_secs(1)));
+        // Other runtimes have their own budget
+        assert!(detector.allow_restart(Component::Capture(1), t0));
+        // The window slides
+        assert!(detector.allow_restart(out, t0 + RESTART_WINDOW));
+    }
+
+    #[test]
+    fn test_render_guard_trips_on_repeated_slow_renders() {
+        // 480 frames @ 48 kHz = 10 ms block, 5 ms budget
+        let guard = RenderGuard::new();
+        let slow = Duration::from_millis(6);
+        for _ in 0..15 {
+            assert!(!guard.record(slow, 480, 48_000.0));
+        }
+        assert!(!guard.is_overloaded());
+        assert!(guard.record(slow, 480, 48_000.0));
+        assert!(guard.is_overloaded());
+        assert!((guard.slow_render_ms() - 6.0).abs() < 1e-3);
+        // Reported once
+        assert!(!guard.record(slow, 480, 48_000.0));
+        assert!(!guard.trip());
+    }
+
+    #[test]
+    fn test_render_guard_tolerates_occasional_spikes() {
+        let guard = RenderGuard::new();
+        for _ in 0..100 {
+            guard.record(Duration::from_millis(8), 480, 48_000.0);
+            for _ in 0..9 {
+                guard.record(Duration::from_millis(1), 480, 48_000.0);
+            }
+        }
+        assert!(!guard.is_overloaded());
+    }
+
+    #[test]
+    fn test_arm_and_disarm() {
+        let component = Component::Output(0xFFFF_0002);
+        let heartbeat = arm(component);
+        heartbeat.beat();
+        heartbeat.beat();
+        assert!(beats().contains(&(component, 2)));
+
+        // Re-arming starts from zero
+        let heartbeat = arm(component);
+        assert_eq!(heartbeat.count(), 0);
+
+        disarm(component);
+        assert!(!is_armed(component));
+    }
+}
@@ -0,0 +1,140 @@
+//! Xrun (dropout) detection
+//!
+//! デバイスごとにドロップアウトを数える。
+//! - underrun: キャプチャのリングバッファから要求フレーム数を読めなかった
+//! - overrun: 読み出しが遅れ、未読データが上書きされる寸前だった
+//! - late callback: 出力コールバックがバッファ周期内に終わらなかった
+//!
+//! カウンタは Atomic のみ。オーディオスレッドからはデバイス登録時に取得した
+//! `Arc<XrunCounter>` を通して記録する（ロックなし）。
+
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+/// デバイスごとのドロップアウトカウンタ
+#[derive(Debug, Default)]
+pub struct XrunCounter {
+    underruns: AtomicU64,
+    overruns: AtomicU64,
+    late_callbacks: AtomicU64,
+}
+
+/// [`XrunCounter`] の読み出し値
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct XrunSnapshot {
+    pub underruns: u64,
+    pub overruns: u64,
+    pub late_callbacks: u64,
+}
+
+impl XrunSnapshot {
+    pub fn total(&self) -> u64 {
+        self.underruns + self.overruns + self.late_callbacks
+    }
+}
+
+impl XrunCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn record_underrun(&self) {
+        self.underruns.fetch_add(1, Ordering::Relaxed);
+        TOTAL_XRUNS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn record_overrun(&self) {
+        self.overruns.fetch_add(1, Ordering::Relaxed);
+        TOTAL_XRUNS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn record_late_callback(&self) {
+        self.late_callbacks.fetch_add(1, Ordering::Relaxed);
+        TOTAL_XRUNS.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> XrunSnapshot {
+        XrunSnapshot {
+            underruns: self.underruns.load(Ordering::Relaxed),
+            overruns: self.overruns.load(Ordering::Relaxed),
+            late_callbacks: self.late_callbacks.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.underruns.store(0, Ordering::Relaxed);
+        self.overruns.store(0, Ordering::Relaxed);
+        self.late_callbacks.store(0, Ordering::Relaxed);
+    }
+}
+
+/// 全デバイス合計（イベント通知の変化検出用、リセットしない）
+static TOTAL_XRUNS: AtomicU64 = AtomicU64::new(0);
+
+/// device_id -> counter
+static XRUN_COUNTERS: LazyLock<RwLock<HashMap<u32, Arc<XrunCounter>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Get (or create) the counter for a device
+///
+/// Call from the control thread and keep the `Arc` for the audio thread.
+pub fn xrun_counter(device_id: u32) -> Arc<XrunCounter> {
+    XRUN_COUNTERS
+        .write()
+        .entry(device_id)
+        .or_insert_with(|| Arc::new(XrunCounter::new()))
+        .clone()
+}
+
+/// Snapshot all device counters (sorted by device id)
+pub fn xrun_stats() -> Vec<(u32, XrunSnapshot)> {
+    let mut stats: Vec<(u32, XrunSnapshot)> = XRUN_COUNTERS
+        .read()
+        .iter()
+        .map(|(&id, c)| (id, c.snapshot()))
+        .collect();
+    stats.sort_by_key(|(id, _)| *id);
+    stats
+}
+
+/// Reset all device counters
+pub fn reset_xrun_stats() {
+    for counter in XRUN_COUNTERS.read().values() {
+        counter.reset();
+    }
+}
+
+/// Monotonic total of xruns since startup
+pub fn total_xruns() -> u64 {
+    TOTAL_XRUNS.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xrun_counter() {
+        let counter = xrun_counter(0xFFFF_0001);
+        let before = total_xruns();
+
+        counter.record_underrun();
+        counter.record_underrun();
+        counter.record_late_callback();
+
+        let s = counter.snapshot();
+        assert_eq!((s.underruns, s.overruns, s.late_callbacks), (2, 0, 1));
+        assert_eq!(s.total(), 3);
+        assert!(total_xruns() >= before + 3);
+
+        counter.reset();
+        assert_eq!(counter.snapshot().total(), 0);
+        // Same device returns the same counter
+        assert!(Arc::ptr_eq(&counter, &xrun_counter(0xFFFF_0001)));
+    }
+}
@@ -7,6 +7,11 @@
 //! - Multiple consumers (output callbacks) can read the SAME data independently
 //! - Each output device has its own read position via triple buffering
 
+use crate::audio::sample_rate::{rates_match, LinearResampler};
+use crate::audio::watchdog::{self, Component, Heartbeat};
+use crate::audio::xrun::{xrun_counter, XrunCounter};
+use crate::audio::{engine_sample_rate, MAX_FRAMES};
+use crate::permission::PermissionStatus;
 use crate::vdsp::VDsp;
 
 /// Number of Prism channels (64 mono = 32 stereo pairs)
@@ -23,9 +28,9 @@ use coreaudio::audio_unit::macos_helpers::{
 };
 use coreaudio::sys::{
     kAudioDevicePropertyBufferFrameSize, kAudioDevicePropertyScopeInput,
-    kAudioDevicePropertyStreamConfiguration, kAudioObjectPropertyElementMaster, AudioBuffer,
-    AudioBufferList, AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize,
-    AudioObjectPropertyAddress, AudioObjectSetPropertyData,
+    kAudioDevicePropertyStreamConfiguration, kAudioObjectPropertyElementMaster,
+    kAudioObjectPropertyScopeGlobal, AudioBuffer, AudioBufferList, AudioObjectGetPropertyData,
+    AudioObjectGetPropertyDataSize, AudioObjectPropertyAddress, AudioObjectSetPropertyData,
 };
 use parking_lot::RwLock;
 use std::collections::HashMap;
@@ -37,9 +42,6 @@ use std::thread;
 /// Maximum channels per device
 const MAX_CHANNELS_PER_DEVICE: usize = 64;
 
-/// Sample rate for audio capture
-const SAMPLE_RATE: f64 = 48000.0;
-
 /// Number of stereo pairs (legacy Prism)
 const STEREO_PAIRS: usize = PRISM_CHANNELS / 2;
 
@@ -54,6 +56,12 @@ const DEFAULT_IO_BUFFER_SIZE: usize = 256;
 /// Current CoreAudio I/O buffer size (can be changed at runtime)
 static IO_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_IO_BUFFER_SIZE);
 
+/// Convert frames to milliseconds at the given sample rate (for logging)
+#[inline]
+fn frames_to_ms(frames: f64, sample_rate: f64) -> f64 {
+    frames * 1000.0 / sample_rate
+}
+
 /// Shared level data - updated from audio thread (legacy Prism support)
 static LEVEL_DATA: RwLock<[ChannelLevels; STEREO_PAIRS]> = RwLock::new(
     [ChannelLevels {
@@ -76,19 +84,68 @@ static PRISM_DEVICE_ID: AtomicU32 = AtomicU32::new(0);
 struct OutputReadPositions {
     /// Read position per channel (atomic for lock-free access)
     positions: Vec<AtomicUsize>,
+    /// Diagnostics of this reader (see [`buffer_diagnostics`])
+    stats: ReaderStats,
+}
+
+/// Per-reader health counters (written by the reading callback)
+#[derive(Debug)]
+struct ReaderStats {
+    /// Frames available at the last read
+    last_fill: AtomicUsize,
+    /// Lowest fill seen since the last reset
+    min_fill: AtomicUsize,
+    underruns: AtomicU64,
+    overruns: AtomicU64,
+}
+
+impl Default for ReaderStats {
+    fn default() -> Self {
+        Self {
+            last_fill: AtomicUsize::new(0),
+            min_fill: AtomicUsize::new(usize::MAX),
+            underruns: AtomicU64::new(0),
+            overruns: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ReaderStats {
+    #[inline]
+    fn record(&self, fill: usize, underrun: bool, overrun: bool) {
+        self.last_fill.store(fill, Ordering::Relaxed);
+        self.min_fill.fetch_min(fill, Ordering::Relaxed);
+        if underrun {
+            self.underruns.fetch_add(1, Ordering::Relaxed);
+        } else if overrun {
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn reset(&self) {
+        self.min_fill.store(usize::MAX, Ordering::Relaxed);
+        self.underruns.store(0, Ordering::Relaxed);
+        self.overruns.store(0, Ordering::Relaxed);
+    }
 }
 
 impl OutputReadPositions {
     fn new(num_channels: usize) -> Self {
         let positions = (0..num_channels).map(|_| AtomicUsize::new(0)).collect();
-        Self { positions }
+        Self {
+            positions,
+            stats: ReaderStats::default(),
+        }
     }
 
     fn new_at_position(num_channels: usize, write_positions: &[usize]) -> Self {
         let positions = (0..num_channels)
             .map(|i| AtomicUsize::new(write_positions.get(i).copied().unwrap_or(0)))
             .collect();
-        Self { positions }
+        Self {
+            positions,
+            stats: ReaderStats::default(),
+        }
     }
 
     #[inline]
@@ -117,6 +174,8 @@ struct InputDeviceState {
     running: Arc<AtomicBool>,
     buffers: Arc<RwLock<DeviceBuffers>>,
     levels: Arc<RwLock<Vec<ChannelLevels>>>,
+    /// Dropout counter for this device
+    xruns: Arc<XrunCounter>,
     /// Read positions per output device: output_device_id -> positions (RwLock only for adding new devices)
     read_positions: Arc<RwLock<HashMap<u32, Arc<OutputReadPositions>>>>,
 }
@@ -137,6 +196,7 @@ impl InputDeviceState {
                 level_slots.max(1)
             ])),
             read_positions: Arc::new(RwLock::new(HashMap::new())),
+            xruns: xrun_counter(device_id),
         }
     }
 
@@ -170,8 +230,11 @@ struct DeviceBuffers {
 
 impl DeviceBuffers {
     fn new(num_channels: usize) -> Self {
+        // 高いサンプルレートでも同じ時間分のバッファを確保
+        let size =
+            crate::audio::sample_rate::scaled_buffer_frames(RING_BUFFER_SIZE, engine_sample_rate());
         let channels = (0..num_channels)
-            .map(|_| ChannelBuffer::new(RING_BUFFER_SIZE))
+            .map(|_| ChannelBuffer::new(size))
             .collect();
         Self {
             channels,
@@ -207,25 +270,66 @@ impl ChannelBuffer {
 
     /// Write samples to the buffer (called from input callback)
     fn write(&self, samples: &[f32]) {
+        let frames = samples.len().min(self.data.len());
+        self.write_with(frames, |dst, offset| {
+            dst.copy_from_slice(&samples[offset..offset + dst.len()]);
+        });
+    }
+
+    /// Deinterleave one channel straight into the ring (called from input callback)
+    ///
+    /// vDSP のストライドコピーでリングに直接書き込む（中間バッファなし）。
+    fn write_deinterleaved(
+        &self,
+        interleaved: &[f32],
+        channel: usize,
+        num_channels: usize,
+        frames: usize,
+    ) {
+        let frames = frames
+            .min(interleaved.len() / num_channels.max(1))
+            .min(self.data.len());
+        self.write_with(frames, |dst, offset| {
+            VDsp::deinterleave(
+                &interleaved[offset * num_channels..],
+                channel,
+                num_channels,
+                dst,
+            );
+        });
+    }
+
+    /// Hand `fill` the (at most two) contiguous ring segments for `frames` samples
+    /// and advance the write position. `fill` gets the segment and its offset
+    /// within the written block.
+    #[inline]
+    fn write_with(&self, frames: usize, mut fill: impl FnMut(&mut [f32], usize)) {
         let len = self.data.len();
-        let mut pos = self.write_pos.load(Ordering::Acquire);
+        if frames == 0 || len == 0 {
+            return;
+        }
+        let pos = self.write_pos.load(Ordering::Acquire) % len;
+        let first = frames.min(len - pos);
 
         // Safety: single writer (input callback), multiple readers
         let data_ptr = self.data.as_ptr() as *mut f32;
-
-        for &sample in samples {
-            unsafe {
-                *data_ptr.add(pos) = sample;
+        unsafe {
+            fill(std::slice::from_raw_parts_mut(data_ptr.add(pos), first), 0);
+            if first < frames {
+                fill(
+                    std::slice::from_raw_parts_mut(data_ptr, frames - first),
+                    first,
+                );
             }
-            pos = (pos + 1) % len;
         }
 
-        self.write_pos.store(pos, Ordering::Release);
+        self.write_pos
+            .store((pos + frames) % len, Ordering::Release);
     }
 
     /// Read samples from the buffer starting at a specific position
-    /// Returns the new read position
-    fn read(&self, read_pos: usize, out: &mut [f32]) -> usize {
+    /// Returns the new read position and whether the read under/overran
+    fn read(&self, read_pos: usize, out: &mut [f32]) -> ChannelRead {
         let len = self.data.len();
         let write_pos = self.write_pos.load(Ordering::Acquire);
 
@@ -252,28 +356,110 @@ impl ChannelBuffer {
             out[i] = 0.0;
         }
 
-        pos
+        ChannelRead {
+            pos,
+            available,
+            underrun: to_read < out.len(),
+            // 読み出しが遅れすぎて、次の書き込みで未読データが上書きされる
+            overrun: available + MAX_FRAMES > len,
+        }
     }
 
     fn get_write_pos(&self) -> usize {
         self.write_pos.load(Ordering::Acquire)
     }
+
+    /// Move `read_pos` forward so at most `max_lag` frames are unread
+    ///
+    /// Returns the new read position, or None if it is already within the limit.
+    fn trimmed_read_pos(&self, read_pos: usize, max_lag: usize) -> Option<usize> {
+        let len = self.data.len();
+        let write_pos = self.get_write_pos();
+        let lag = (write_pos + len - read_pos % len) % len;
+        (lag > max_lag).then(|| (write_pos + len - max_lag.min(len - 1)) % len)
+    }
+}
+
+/// Preallocated per-capture scratch (owned by the input callback)
+///
+/// SRC が不要な場合は `write_deinterleaved` でリングへ直接書くため、ここは空のまま。
+/// SRC 時のみ 1 チャンネル分の deinterleave 領域と変換出力を確保しておく。
+struct CaptureScratch {
+    /// Per-channel sample rate converters (empty when device rate == engine rate)
+    resamplers: Vec<LinearResampler>,
+    deinterleaved: Box<[f32]>,
+    resampled: Box<[f32]>,
+}
+
+impl CaptureScratch {
+    fn new(channel_count: usize, needs_src: bool, device_rate: f64, engine_rate: f64) -> Self {
+        if !needs_src {
+            return Self {
+                resamplers: Vec::new(),
+                deinterleaved: Box::default(),
+                resampled: Box::default(),
+            };
+        }
+        let resamplers: Vec<LinearResampler> = (0..channel_count)
+            .map(|_| LinearResampler::new(device_rate, engine_rate))
+            .collect();
+        let max_out = LinearResampler::new(device_rate, engine_rate).max_output_frames(MAX_FRAMES);
+        Self {
+            resamplers,
+            deinterleaved: vec![0.0f32; MAX_FRAMES].into_boxed_slice(),
+            resampled: vec![0.0f32; max_out].into_boxed_slice(),
+        }
+    }
+}
+
+/// Result of one [`ChannelBuffer::read`]
+struct ChannelRead {
+    /// New read position
+    pos: usize,
+    /// Frames that were available before the read
+    available: usize,
+    /// Fewer samples were available than requested
+    underrun: bool,
+    /// Reader is lagging far enough that unread samples are being overwritten
+    overrun: bool,
+}
+
+/// Count at most one xrun per stereo read (device counter and the reader's own)
+#[inline]
+fn record_xruns(
+    xruns: &XrunCounter,
+    reader: &OutputReadPositions,
+    left: &ChannelRead,
+    right: Option<&ChannelRead>,
+) {
+    let underrun = left.underrun || right.is_some_and(|r| r.underrun);
+    let overrun = left.overrun || right.is_some_and(|r| r.overrun);
+    if underrun {
+        xruns.record_underrun();
+    } else if overrun {
+        xruns.record_overrun();
+    }
+    let fill = right.map_or(left.available, |r| r.available.min(left.available));
+    reader.stats.record(fill, underrun, overrun);
 }
 
 /// Legacy: Global audio buffers for Prism channels (backward compatibility)
 struct AudioBuffers {
     channels: Vec<ChannelBuffer>,
     buffer_size: usize,
+    /// Dropout counter (registered under the current Prism device id)
+    xruns: Arc<XrunCounter>,
 }
 
 impl AudioBuffers {
-    fn new(num_channels: usize, buffer_size: usize) -> Self {
+    fn new(num_channels: usize, buffer_size: usize, xruns: Arc<XrunCounter>) -> Self {
         let channels = (0..num_channels)
             .map(|_| ChannelBuffer::new(buffer_size))
             .collect();
         Self {
             channels,
             buffer_size,
+            xruns,
         }
     }
 }
@@ -290,14 +476,19 @@ static WRITE_FRAME_COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// Legacy: Initialize audio buffers
 fn init_audio_buffers() {
+    let xruns = xrun_counter(PRISM_DEVICE_ID.load(Ordering::SeqCst));
     let mut buffers = AUDIO_BUFFERS.write();
-    if buffers.is_none() {
-        *buffers = Some(AudioBuffers::new(PRISM_CHANNELS, RING_BUFFER_SIZE));
-        println!(
-            "[AudioCapture] Ring buffers initialized: {} channels x {} samples ({:.1}ms at 48kHz)",
+    if let Some(existing) = buffers.as_mut() {
+        // Prism デバイスが変わった場合もカウンタをそのデバイスに紐付け直す
+        existing.xruns = xruns;
+    } else {
+        *buffers = Some(AudioBuffers::new(PRISM_CHANNELS, RING_BUFFER_SIZE, xruns));
+        log_info!(
+            "[AudioCapture] Ring buffers initialized: {} channels x {} samples ({:.1}ms at {}Hz)",
             PRISM_CHANNELS,
             RING_BUFFER_SIZE,
-            RING_BUFFER_SIZE as f64 / 48.0
+            frames_to_ms(RING_BUFFER_SIZE as f64, engine_sample_rate()),
+            engine_sample_rate()
         );
     }
 }
@@ -306,7 +497,8 @@ fn init_audio_buffers() {
 fn set_device_buffer_size(device_id: u32, buffer_size: u32) -> Result<(), String> {
     let address = AudioObjectPropertyAddress {
         mSelector: kAudioDevicePropertyBufferFrameSize,
-        mScope: kAudioDevicePropertyScopeInput,
+        // デバイス全体の値（出力専用デバイスにも使う）
+        mScope: kAudioObjectPropertyScopeGlobal,
         mElement: kAudioObjectPropertyElementMaster,
     };
 
@@ -325,11 +517,11 @@ fn set_device_buffer_size(device_id: u32, buffer_size: u32) -> Result<(), String
         return Err(format!("Failed to set buffer size: OSStatus {}", status));
     }
 
-    println!(
+    log_info!(
         "[AudioCapture] Device {} I/O buffer size set to {} samples ({:.1}ms)",
         device_id,
         buffer_size,
-        buffer_size as f64 / 48.0
+        frames_to_ms(buffer_size as f64, engine_sample_rate())
     );
     Ok(())
 }
@@ -338,7 +530,7 @@ fn set_device_buffer_size(device_id: u32, buffer_size: u32) -> Result<(), String
 fn get_device_buffer_size(device_id: u32) -> Option<u32> {
     let address = AudioObjectPropertyAddress {
         mSelector: kAudioDevicePropertyBufferFrameSize,
-        mScope: kAudioDevicePropertyScopeInput,
+        mScope: kAudioObjectPropertyScopeGlobal,
         mElement: kAudioObjectPropertyElementMaster,
     };
 
@@ -434,25 +626,26 @@ pub fn get_device_input_channels(device_id: u32) -> u32 {
 }
 
 /// Audio capture thread function
-fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
+fn capture_thread(device_id: u32, running: Arc<AtomicBool>, heartbeat: Arc<Heartbeat>) {
     use coreaudio::audio_unit::audio_format::LinearPcmFlags;
     use coreaudio::audio_unit::render_callback::{self, data};
     use coreaudio::audio_unit::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
 
-    println!(
+    log_info!(
         "[AudioCapture] Starting capture thread for device {}",
         device_id
     );
 
     // Set sample rate
-    if let Err(e) = set_device_sample_rate(device_id, SAMPLE_RATE) {
-        println!("[AudioCapture] Warning: Could not set sample rate: {:?}", e);
+    let sample_rate = engine_sample_rate();
+    if let Err(e) = set_device_sample_rate(device_id, sample_rate) {
+        log_warn!("[AudioCapture] Warning: Could not set sample rate: {:?}", e);
     }
 
     // Set I/O buffer size (affects latency)
-    let io_buffer_size = IO_BUFFER_SIZE.load(Ordering::SeqCst) as u32;
+    let io_buffer_size = device_io_buffer_size(device_id);
     if let Err(e) = set_device_buffer_size(device_id, io_buffer_size) {
-        println!(
+        log_warn!(
             "[AudioCapture] Warning: Could not set I/O buffer size: {}",
             e
         );
@@ -460,10 +653,10 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
 
     // Report actual buffer size
     if let Some(actual_size) = get_device_buffer_size(device_id) {
-        println!(
+        log_info!(
             "[AudioCapture] Actual device buffer size: {} samples ({:.1}ms)",
             actual_size,
-            actual_size as f64 / 48.0
+            frames_to_ms(actual_size as f64, engine_sample_rate())
         );
     }
 
@@ -472,7 +665,7 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
     let mut audio_unit = match audio_unit_result {
         Ok(au) => au,
         Err(e) => {
-            eprintln!("[AudioCapture] Failed to create audio unit: {:?}", e);
+            log_error!("[AudioCapture] Failed to create audio unit: {:?}", e);
             running.store(false, Ordering::SeqCst);
             return;
         }
@@ -485,7 +678,7 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
         Element::Input,
         Some(&1u32),
     ) {
-        eprintln!("[AudioCapture] Failed to enable input: {:?}", e);
+        log_error!("[AudioCapture] Failed to enable input: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
@@ -497,7 +690,7 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
         Element::Output,
         Some(&0u32),
     ) {
-        eprintln!("[AudioCapture] Failed to disable output: {:?}", e);
+        log_error!("[AudioCapture] Failed to disable output: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
@@ -509,7 +702,7 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
         Element::Output,
         Some(&device_id),
     ) {
-        eprintln!("[AudioCapture] Failed to set device: {:?}", e);
+        log_error!("[AudioCapture] Failed to set device: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
@@ -518,11 +711,11 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
     let input_channels = get_device_input_channels(device_id);
     let channels = input_channels.min(PRISM_CHANNELS as u32);
 
-    println!("[AudioCapture] Using {} channels", channels);
+    log_info!("[AudioCapture] Using {} channels", channels);
 
     // Set stream format
     let stream_format = StreamFormat {
-        sample_rate: SAMPLE_RATE,
+        sample_rate,
         sample_format: SampleFormat::F32,
         flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
         channels,
@@ -534,7 +727,7 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
         Element::Input,
         Some(&stream_format.to_asbd()),
     ) {
-        eprintln!("[AudioCapture] Failed to set stream format: {:?}", e);
+        log_error!("[AudioCapture] Failed to set stream format: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
@@ -542,9 +735,6 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
     let running_callback = running.clone();
     let channel_count = channels as usize;
 
-    // Pre-allocated deinterleave buffer (per-channel)
-    const MAX_FRAMES: usize = 4096;
-
     // Set input callback
     type Args = render_callback::Args<data::Interleaved<f32>>;
 
@@ -552,6 +742,8 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
         if !running_callback.load(Ordering::Relaxed) {
             return Ok(());
         }
+        crate::logging::register_realtime_thread();
+        heartbeat.beat();
 
         let Args {
             data, num_frames, ..
@@ -566,16 +758,12 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
             return Ok(());
         }
 
-        // Stack-allocated deinterleave buffer
-        let mut deinterleaved = [0.0f32; MAX_FRAMES];
-
         // Write to broadcast buffers
         if let Some(audio_buffers) = AUDIO_BUFFERS.try_read() {
             if let Some(ref buffers) = *audio_buffers {
-                // Deinterleave and write each channel using vDSP
+                // Deinterleave each channel directly into its ring using vDSP
                 for ch in 0..num_channels.min(buffers.channels.len()) {
-                    VDsp::deinterleave(buffer, ch, num_channels, &mut deinterleaved[..frames]);
-                    buffers.channels[ch].write(&deinterleaved[..frames]);
+                    buffers.channels[ch].write_deinterleaved(buffer, ch, num_channels, frames);
                 }
 
                 // Update frame counter
@@ -604,25 +792,25 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
 
         Ok(())
     }) {
-        eprintln!("[AudioCapture] Failed to set input callback: {:?}", e);
+        log_error!("[AudioCapture] Failed to set input callback: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
 
     // Initialize and start
     if let Err(e) = audio_unit.initialize() {
-        eprintln!("[AudioCapture] Failed to initialize audio unit: {:?}", e);
+        log_error!("[AudioCapture] Failed to initialize audio unit: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
 
     if let Err(e) = audio_unit.start() {
-        eprintln!("[AudioCapture] Failed to start audio unit: {:?}", e);
+        log_error!("[AudioCapture] Failed to start audio unit: {:?}", e);
         running.store(false, Ordering::SeqCst);
         return;
     }
 
-    println!("[AudioCapture] Capture started successfully");
+    log_info!("[AudioCapture] Capture started successfully");
 
     // Keep thread alive while running
     while running.load(Ordering::SeqCst) {
@@ -631,7 +819,39 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
 
     // Stop audio unit
     let _ = audio_unit.stop();
-    println!("[AudioCapture] Capture thread stopped");
+    log_info!("[AudioCapture] Capture thread stopped");
+}
+
+/// Trim the unread backlog of every output reader to `max_lag` frames
+///
+/// 読み出し側の位置を進めるだけ（書き込みとリングはそのまま）。出力コールバックと
+/// 同時に書き換わった場合はコールバック側の値が残り、その読み手は次回に持ち越す。
+fn trim_read_latency(max_lag: usize) -> usize {
+    let trim = |channels: &[ChannelBuffer], positions: &OutputReadPositions| -> usize {
+        let mut trimmed = 0;
+        for (ch, buffer) in channels.iter().enumerate() {
+            if let Some(pos) = buffer.trimmed_read_pos(positions.get(ch), max_lag) {
+                positions.set(ch, pos);
+                trimmed = 1;
+            }
+        }
+        trimmed
+    };
+
+    let mut trimmed = 0;
+    let states: Vec<Arc<InputDeviceState>> = INPUT_DEVICES.read().values().cloned().collect();
+    for state in states {
+        let buffers = state.buffers.read();
+        for positions in state.read_positions.read().values() {
+            trimmed += trim(&buffers.channels, positions);
+        }
+    }
+    if let Some(buffers) = AUDIO_BUFFERS.read().as_ref() {
+        for positions in DEVICE_READ_POSITIONS.read().values() {
+            trimmed += trim(&buffers.channels, positions);
+        }
+    }
+    trimmed
 }
 
 // --- Public API ---
@@ -642,10 +862,11 @@ fn capture_thread(device_id: u32, running: Arc<AtomicBool>) {
 pub fn set_io_buffer_size(size: usize) {
     let size = size.max(32).min(2048);
     IO_BUFFER_SIZE.store(size, Ordering::SeqCst);
-    println!(
-        "[AudioCapture] I/O buffer size set to {} samples ({:.1}ms at 48kHz)",
+    log_info!(
+        "[AudioCapture] I/O buffer size set to {} samples ({:.1}ms at {}Hz)",
         size,
-        size as f64 / 48.0
+        frames_to_ms(size as f64, engine_sample_rate()),
+        engine_sample_rate()
     );
 }
 
@@ -654,6 +875,125 @@ pub fn get_io_buffer_size() -> usize {
     IO_BUFFER_SIZE.load(Ordering::SeqCst)
 }
 
+/// One device's result of a live buffer size change
+#[derive(Debug, Clone)]
+pub struct BufferSizeResult {
+    pub device_id: u32,
+    /// Size the device reports after the change
+    pub actual: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// I/O buffer size for a device (its override in settings.json, else the global size)
+pub fn device_io_buffer_size(device_id: u32) -> u32 {
+    crate::device::get_device_uid(device_id)
+        .and_then(|uid| crate::settings::device_buffer_size(&uid))
+        .unwrap_or(IO_BUFFER_SIZE.load(Ordering::SeqCst) as u32)
+}
+
+/// Set a device's buffer size per [`device_io_buffer_size`] and read back the result
+pub fn apply_device_buffer_size(device_id: u32) -> BufferSizeResult {
+    let error = set_device_buffer_size(device_id, device_io_buffer_size(device_id)).err();
+    if let Some(e) = &error {
+        log_warn!("[AudioCapture] Device {}: {}", device_id, e);
+    }
+    BufferSizeResult {
+        device_id,
+        actual: get_device_buffer_size(device_id),
+        error,
+    }
+}
+
+/// Running capture and output devices (sorted, deduplicated)
+fn running_devices() -> Vec<u32> {
+    let mut devices: Vec<u32> = INPUT_DEVICES
+        .read()
+        .values()
+        .filter(|s| s.running.load(Ordering::SeqCst))
+        .map(|s| s.device_id)
+        .collect();
+    if CAPTURE_RUNNING.load(Ordering::SeqCst) {
+        devices.push(PRISM_DEVICE_ID.load(Ordering::SeqCst));
+    }
+    devices.extend(crate::audio::output::get_active_output_devices());
+    devices.retain(|&id| id != 0);
+    devices.sort_unstable();
+    devices.dedup();
+    devices
+}
+
+/// Wait for the new sizes to take effect, then trim reader backlog
+///
+/// 最大のバッファサイズ 2 つ分は残す（それより小さいデバイスの読み手も同じ上限）。
+fn settle_and_trim(results: &[BufferSizeResult]) -> usize {
+    let largest = results
+        .iter()
+        .filter_map(|r| r.actual)
+        .chain(std::iter::once(IO_BUFFER_SIZE.load(Ordering::SeqCst) as u32))
+        .max()
+        .unwrap_or(0) as usize;
+    let settle_ms = frames_to_ms(largest as f64 * 2.0, engine_sample_rate()).ceil() as u64;
+    thread::sleep(std::time::Duration::from_millis(settle_ms.max(10)));
+    trim_read_latency(largest * 2)
+}
+
+/// Change the I/O buffer size of every running device without restarting it
+///
+/// 動作中のデバイス（キャプチャと出力）の `kAudioDevicePropertyBufferFrameSize` を
+/// その場で変更する。AUHAL はユニットを止めずに新しいサイズに追従するので、
+/// グラフもキャプチャのリングも作り直さない。変更後 2 周期待ってから、前のサイズで
+/// 溜まった読み出し遅延を 2 バッファ分まで詰める（小さくしたときに遅延が残らないように）。
+/// デバイスごとの上書き設定があるデバイスはその値のまま。
+pub fn apply_io_buffer_size(size: usize) -> Vec<BufferSizeResult> {
+    set_io_buffer_size(size);
+
+    let results: Vec<BufferSizeResult> = running_devices()
+        .into_iter()
+        .map(apply_device_buffer_size)
+        .collect();
+    let trimmed = settle_and_trim(&results);
+    log_info!(
+        "[AudioCapture] Live buffer size change to {} applied to {} device(s), {} reader(s) trimmed",
+        get_io_buffer_size(),
+        results.len(),
+        trimmed
+    );
+    results
+}
+
+/// Set (Some) or clear (None) a device's buffer size override
+///
+/// settings.json に UID で保存し、デバイスが動作中ならその場で適用する。
+pub fn set_device_buffer_override(
+    device_id: u32,
+    frames: Option<u32>,
+) -> Result<BufferSizeResult, String> {
+    let uid = crate::device::get_device_uid(device_id)
+        .ok_or_else(|| format!("Device {} has no UID; cannot store an override", device_id))?;
+    crate::settings::set_device_buffer_size(&uid, frames)?;
+
+    if !running_devices().contains(&device_id) {
+        return Ok(BufferSizeResult {
+            device_id,
+            actual: get_device_buffer_size(device_id),
+            error: None,
+        });
+    }
+    let result = apply_device_buffer_size(device_id);
+    settle_and_trim(std::slice::from_ref(&result));
+    log_info!(
+        "[AudioCapture] Device {} buffer size {} ({})",
+        device_id,
+        device_io_buffer_size(device_id),
+        if frames.is_some() {
+            "override"
+        } else {
+            "global"
+        }
+    );
+    Ok(result)
+}
+
 /// Initialize and start audio capture
 pub fn start_capture() -> Result<bool, String> {
     if CAPTURE_RUNNING.load(Ordering::SeqCst) {
@@ -663,7 +1003,7 @@ pub fn start_capture() -> Result<bool, String> {
     let device_id = match find_prism_device() {
         Some(id) => id,
         None => {
-            println!("[AudioCapture] Prism device not found");
+            log_info!("[AudioCapture] Prism device not found");
             return Ok(false);
         }
     };
@@ -677,8 +1017,9 @@ pub fn start_capture() -> Result<bool, String> {
     CAPTURE_RUNNING.store(true, Ordering::SeqCst);
 
     let running_clone = running.clone();
+    let heartbeat = watchdog::arm(Component::Capture(device_id));
     thread::spawn(move || {
-        capture_thread(device_id, running_clone);
+        capture_thread(device_id, running_clone, heartbeat);
         CAPTURE_RUNNING.store(false, Ordering::SeqCst);
     });
 
@@ -688,13 +1029,14 @@ pub fn start_capture() -> Result<bool, String> {
 /// Stop audio capture
 pub fn stop_capture() {
     CAPTURE_RUNNING.store(false, Ordering::SeqCst);
+    watchdog::disarm(Component::Capture(PRISM_DEVICE_ID.load(Ordering::SeqCst)));
     // Give some time for threads to stop
     std::thread::sleep(std::time::Duration::from_millis(150));
 }
 
 /// Restart audio capture with new settings
 pub fn restart_capture() -> Result<bool, String> {
-    println!("[AudioCapture] Restarting capture...");
+    log_info!("[AudioCapture] Restarting capture...");
 
     // Stop current capture
     if CAPTURE_RUNNING.load(Ordering::SeqCst) {
@@ -705,7 +1047,7 @@ pub fn restart_capture() -> Result<bool, String> {
     {
         let mut positions = DEVICE_READ_POSITIONS.write();
         positions.clear();
-        println!("[AudioCapture] Cleared all device read positions");
+        log_info!("[AudioCapture] Cleared all device read positions");
     }
 
     // Clear existing buffers
@@ -763,7 +1105,7 @@ pub fn register_output_device(device_id: u32) {
                     &write_positions,
                 )),
             );
-            println!(
+            log_info!(
                 "[AudioCapture] Registered output device {} for reading at write_pos[0]={}",
                 device_id,
                 write_positions.get(0).unwrap_or(&0)
@@ -776,7 +1118,7 @@ pub fn register_output_device(device_id: u32) {
 pub fn unregister_output_device(device_id: u32) {
     let mut positions = DEVICE_READ_POSITIONS.write();
     if positions.remove(&device_id).is_some() {
-        println!("[AudioCapture] Unregistered output device {}", device_id);
+        log_info!("[AudioCapture] Unregistered output device {}", device_id);
     }
 }
 
@@ -832,13 +1174,15 @@ pub fn read_channel_audio(
 
     // Read from left channel - fully lock-free!
     let left_read_pos = read_pos.get(left_ch);
-    let new_left_pos = audio_buffers.channels[left_ch].read(left_read_pos, left_out);
-    read_pos.set(left_ch, new_left_pos);
+    let left = audio_buffers.channels[left_ch].read(left_read_pos, left_out);
+    read_pos.set(left_ch, left.pos);
 
     // Read from right channel - fully lock-free!
     let right_read_pos = read_pos.get(right_ch);
-    let new_right_pos = audio_buffers.channels[right_ch].read(right_read_pos, right_out);
-    read_pos.set(right_ch, new_right_pos);
+    let right = audio_buffers.channels[right_ch].read(right_read_pos, right_out);
+    read_pos.set(right_ch, right.pos);
+
+    record_xruns(&audio_buffers.xruns, &read_pos, &left, Some(&right));
 
     num_frames
 }
@@ -851,8 +1195,7 @@ pub fn read_channel_audio_any(
     left_out: &mut [f32],
     right_out: &mut [f32],
 ) -> usize {
-    // Use device_id u32::MAX as the processing thread's ID
-    read_channel_audio(u32::MAX, left_ch, right_ch, left_out, right_out)
+    read_channel_audio(PROCESSOR_READER_ID, left_ch, right_ch, left_out, right_out)
 }
 
 /// Legacy API - deprecated, use read_channel_audio instead
@@ -889,26 +1232,41 @@ pub fn get_input_devices() -> Vec<(u32, String, u32, bool, Option<String>)> {
 }
 
 /// Generic capture thread for any input device
-fn generic_capture_thread(state: Arc<InputDeviceState>) {
+fn generic_capture_thread(state: Arc<InputDeviceState>, heartbeat: Arc<Heartbeat>) {
     use coreaudio::audio_unit::audio_format::LinearPcmFlags;
     use coreaudio::audio_unit::render_callback::{self, data};
     use coreaudio::audio_unit::{AudioUnit, Element, SampleFormat, Scope, StreamFormat};
 
     let device_id = state.device_id;
-    println!(
+    log_info!(
         "[AudioCapture] Starting capture thread for device {} ({})",
-        device_id, state.device_name
+        device_id,
+        state.device_name
     );
 
     // Set sample rate
-    if let Err(e) = set_device_sample_rate(device_id, SAMPLE_RATE) {
-        println!("[AudioCapture] Warning: Could not set sample rate: {:?}", e);
+    // デバイスがエンジンのレートに切り替えられない場合はデバイスのレートで
+    // キャプチャし、コールバック内でエンジンのレートへ変換する
+    let engine_rate = engine_sample_rate();
+    if let Err(e) = set_device_sample_rate(device_id, engine_rate) {
+        log_warn!("[AudioCapture] Warning: Could not set sample rate: {:?}", e);
+    }
+    let device_rate =
+        crate::device::get_device_nominal_sample_rate(device_id).unwrap_or(engine_rate);
+    let needs_src = !rates_match(device_rate, engine_rate);
+    if needs_src {
+        log_info!(
+            "[AudioCapture] Device {} runs at {}Hz, resampling to {}Hz",
+            device_id,
+            device_rate,
+            engine_rate
+        );
     }
 
     // Set I/O buffer size
-    let io_buffer_size = IO_BUFFER_SIZE.load(Ordering::SeqCst) as u32;
+    let io_buffer_size = device_io_buffer_size(device_id);
     if let Err(e) = set_device_buffer_size(device_id, io_buffer_size) {
-        println!(
+        log_warn!(
             "[AudioCapture] Warning: Could not set I/O buffer size: {}",
             e
         );
@@ -916,10 +1274,10 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
 
     // Report actual buffer size
     if let Some(actual_size) = get_device_buffer_size(device_id) {
-        println!(
+        log_info!(
             "[AudioCapture] Actual device buffer size: {} samples ({:.1}ms)",
             actual_size,
-            actual_size as f64 / 48.0
+            frames_to_ms(actual_size as f64, device_rate)
         );
     }
 
@@ -928,7 +1286,7 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
     let mut audio_unit = match audio_unit_result {
         Ok(au) => au,
         Err(e) => {
-            eprintln!("[AudioCapture] Failed to create audio unit: {:?}", e);
+            log_error!("[AudioCapture] Failed to create audio unit: {:?}", e);
             state.running.store(false, Ordering::SeqCst);
             return;
         }
@@ -941,7 +1299,7 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
         Element::Input,
         Some(&1u32),
     ) {
-        eprintln!("[AudioCapture] Failed to enable input: {:?}", e);
+        log_error!("[AudioCapture] Failed to enable input: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
@@ -953,7 +1311,7 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
         Element::Output,
         Some(&0u32),
     ) {
-        eprintln!("[AudioCapture] Failed to disable output: {:?}", e);
+        log_error!("[AudioCapture] Failed to disable output: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
@@ -965,18 +1323,18 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
         Element::Output,
         Some(&device_id),
     ) {
-        eprintln!("[AudioCapture] Failed to set device: {:?}", e);
+        log_error!("[AudioCapture] Failed to set device: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
 
     // Get input channel count
     let channels = state.channel_count.min(MAX_CHANNELS_PER_DEVICE) as u32;
-    println!("[AudioCapture] Using {} channels", channels);
+    log_info!("[AudioCapture] Using {} channels", channels);
 
-    // Set stream format
+    // Set stream format (input element cannot convert rates, so use the device rate)
     let stream_format = StreamFormat {
-        sample_rate: SAMPLE_RATE,
+        sample_rate: device_rate,
         sample_format: SampleFormat::F32,
         flags: LinearPcmFlags::IS_FLOAT | LinearPcmFlags::IS_PACKED,
         channels,
@@ -988,7 +1346,7 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
         Element::Input,
         Some(&stream_format.to_asbd()),
     ) {
-        eprintln!("[AudioCapture] Failed to set stream format: {:?}", e);
+        log_error!("[AudioCapture] Failed to set stream format: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
@@ -999,8 +1357,8 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
     let channel_count = channels as usize;
     let is_prism = state.is_prism;
 
-    // Pre-allocated deinterleave buffer
-    const MAX_FRAMES: usize = 4096;
+    // Scratch for the SRC path, allocated here so the callback never allocates
+    let mut scratch = CaptureScratch::new(channel_count, needs_src, device_rate, engine_rate);
 
     // Set input callback
     type Args = render_callback::Args<data::Interleaved<f32>>;
@@ -1009,6 +1367,8 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
         if !running_callback.load(Ordering::Relaxed) {
             return Ok(());
         }
+        crate::logging::register_realtime_thread();
+        heartbeat.beat();
 
         let Args {
             data, num_frames, ..
@@ -1024,14 +1384,19 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
             return Ok(());
         }
 
-        // Stack-allocated deinterleave buffer
-        let mut deinterleaved = [0.0f32; MAX_FRAMES];
-
         // Write to broadcast buffers using vDSP deinterleave
         if let Some(device_buffers) = buffers.try_read() {
             for ch in 0..num_channels.min(device_buffers.channels.len()) {
-                VDsp::deinterleave(buffer, ch, num_channels, &mut deinterleaved[..frames]);
-                device_buffers.channels[ch].write(&deinterleaved[..frames]);
+                let ring = &device_buffers.channels[ch];
+                match scratch.resamplers.get_mut(ch) {
+                    Some(src) => {
+                        let input = &mut scratch.deinterleaved[..frames];
+                        VDsp::deinterleave(buffer, ch, num_channels, input);
+                        let n = src.process(input, &mut scratch.resampled);
+                        ring.write(&scratch.resampled[..n]);
+                    }
+                    None => ring.write_deinterleaved(buffer, ch, num_channels, frames),
+                }
             }
         }
 
@@ -1075,25 +1440,25 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
 
         Ok(())
     }) {
-        eprintln!("[AudioCapture] Failed to set input callback: {:?}", e);
+        log_error!("[AudioCapture] Failed to set input callback: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
 
     // Initialize and start
     if let Err(e) = audio_unit.initialize() {
-        eprintln!("[AudioCapture] Failed to initialize audio unit: {:?}", e);
+        log_error!("[AudioCapture] Failed to initialize audio unit: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
 
     if let Err(e) = audio_unit.start() {
-        eprintln!("[AudioCapture] Failed to start audio unit: {:?}", e);
+        log_error!("[AudioCapture] Failed to start audio unit: {:?}", e);
         state.running.store(false, Ordering::SeqCst);
         return;
     }
 
-    println!(
+    log_info!(
         "[AudioCapture] Capture started successfully for device {}",
         device_id
     );
@@ -1105,14 +1470,53 @@ fn generic_capture_thread(state: Arc<InputDeviceState>) {
 
     // Stop audio unit
     let _ = audio_unit.stop();
-    println!(
+    log_info!(
         "[AudioCapture] Capture thread stopped for device {}",
         device_id
     );
 }
 
+/// Why an input capture could not start
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureError {
+    /// Microphone access is denied or restricted (the capture would only return silence)
+    PermissionDenied {
+        device_id: u32,
+        status: PermissionStatus,
+    },
+    Other(String),
+}
+
+impl std::fmt::Display for CaptureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PermissionDenied { device_id, status } => write!(
+                f,
+                "Microphone access is {} (device {})",
+                status.as_str(),
+                device_id
+            ),
+            Self::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<String> for CaptureError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<CaptureError> for String {
+    fn from(error: CaptureError) -> Self {
+        error.to_string()
+    }
+}
+
 /// Start capture from a specific input device
-pub fn start_input_capture(device_id: u32) -> Result<bool, String> {
+///
+/// マイクの許可が拒否されていれば [`CaptureError::PermissionDenied`] を返す。
+pub fn start_input_capture(device_id: u32) -> Result<bool, CaptureError> {
     // Check if already capturing
     {
         let devices = INPUT_DEVICES.read();
@@ -1123,13 +1527,23 @@ pub fn start_input_capture(device_id: u32) -> Result<bool, String> {
         }
     }
 
+    let status = crate::permission::audio_permission_status();
+    if !status.allows_capture() {
+        log_warn!(
+            "[AudioCapture] Not capturing device {}: microphone access is {}",
+            device_id,
+            status.as_str()
+        );
+        return Err(CaptureError::PermissionDenied { device_id, status });
+    }
+
     // Get device info
     let device_name =
         get_device_name(device_id).map_err(|e| format!("Failed to get device name: {:?}", e))?;
     let channel_count = get_device_input_channels(device_id) as usize;
 
     if channel_count == 0 {
-        return Err("Device has no input channels".to_string());
+        return Err("Device has no input channels".to_string().into());
     }
 
     let is_prism = device_name.to_lowercase().contains("prism");
@@ -1160,13 +1574,18 @@ pub fn start_input_capture(device_id: u32) -> Result<bool, String> {
 
     // Start capture thread
     let state_clone = state.clone();
+    let heartbeat = watchdog::arm(Component::Capture(device_id));
     thread::spawn(move || {
-        generic_capture_thread(state_clone);
+        generic_capture_thread(state_clone, heartbeat);
     });
+    // チャンネル数が変わったらリングバッファを作り直す
+    crate::device::watch_stream_configuration(device_id);
 
-    println!(
+    log_info!(
         "[AudioCapture] Started capture for {} (ID: {}, {} channels)",
-        device_name, device_id, channel_count
+        device_name,
+        device_id,
+        channel_count
     );
 
     Ok(true)
@@ -1181,15 +1600,18 @@ pub fn stop_input_capture(device_id: u32) {
 
     if let Some(state) = state {
         state.running.store(false, Ordering::SeqCst);
+        watchdog::disarm(Component::Capture(device_id));
+        crate::device::unwatch_stream_configuration(device_id);
 
         // Update legacy Prism flag if this is Prism
         if state.is_prism {
             CAPTURE_RUNNING.store(false, Ordering::SeqCst);
         }
 
-        println!(
+        log_info!(
             "[AudioCapture] Stopping capture for device {} ({})",
-            device_id, state.device_name
+            device_id,
+            state.device_name
         );
     }
 
@@ -1213,6 +1635,47 @@ pub fn stop_all_captures() {
     }
 }
 
+/// Restart all running input captures (e.g. after a sample rate change)
+///
+/// Ring buffers are recreated so read positions from the old rate are dropped.
+pub fn restart_input_captures() -> Result<(), String> {
+    let device_ids: Vec<u32> = get_active_captures().into_iter().map(|c| c.0).collect();
+
+    for &device_id in &device_ids {
+        stop_input_capture(device_id);
+    }
+
+    let mut errors = Vec::new();
+    for device_id in device_ids {
+        if let Err(e) = start_input_capture(device_id) {
+            errors.push(format!("device {}: {}", device_id, e));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Failed to restart captures: {}", errors.join(", ")))
+    }
+}
+
+/// Restart the capture of one device (watchdog recovery)
+///
+/// 入力デバイスのキャプチャはそのデバイスだけ、旧来の Prism キャプチャは
+/// [`restart_capture`] で作り直す。
+pub fn restart_device_capture(device_id: u32) -> Result<(), String> {
+    if INPUT_DEVICES.read().contains_key(&device_id) {
+        stop_input_capture(device_id);
+        start_input_capture(device_id)
+            .map(|_| ())
+            .map_err(String::from)
+    } else if PRISM_DEVICE_ID.load(Ordering::SeqCst) == device_id {
+        restart_capture().map(|_| ())
+    } else {
+        Ok(())
+    }
+}
+
 /// Get list of active input captures
 pub fn get_active_captures() -> Vec<(u32, String, usize, bool)> {
     let devices = INPUT_DEVICES.read();
@@ -1255,9 +1718,10 @@ pub fn register_output_for_input(input_device_id: u32, output_device_id: u32) {
     let devices = INPUT_DEVICES.read();
     if let Some(state) = devices.get(&input_device_id) {
         state.register_output(output_device_id);
-        println!(
+        log_info!(
             "[AudioCapture] Registered output {} for input {}",
-            output_device_id, input_device_id
+            output_device_id,
+            input_device_id
         );
     }
 }
@@ -1268,9 +1732,10 @@ pub fn unregister_output_for_input(input_device_id: u32, output_device_id: u32)
     if let Some(state) = devices.get(&input_device_id) {
         let mut positions = state.read_positions.write();
         if positions.remove(&output_device_id).is_some() {
-            println!(
+            log_info!(
                 "[AudioCapture] Unregistered output {} from input {}",
-                output_device_id, input_device_id
+                output_device_id,
+                input_device_id
             );
         }
     }
@@ -1326,22 +1791,115 @@ pub fn read_input_audio(
 
     // Read from left channel - fully lock-free!
     let left_read_pos = read_pos.get(left_ch);
-    let new_left_pos = buffers.channels[left_ch].read(left_read_pos, left_out);
-    read_pos.set(left_ch, new_left_pos);
+    let left = buffers.channels[left_ch].read(left_read_pos, left_out);
+    read_pos.set(left_ch, left.pos);
 
     // Read from right channel, or copy left to right if device is mono
     if right_ch < buffers.channels.len() {
         let right_read_pos = read_pos.get(right_ch);
-        let new_right_pos = buffers.channels[right_ch].read(right_read_pos, right_out);
-        read_pos.set(right_ch, new_right_pos);
+        let right = buffers.channels[right_ch].read(right_read_pos, right_out);
+        read_pos.set(right_ch, right.pos);
+        record_xruns(&state.xruns, &read_pos, &left, Some(&right));
     } else {
         // Mono device: copy left channel to right
         right_out[..num_frames].copy_from_slice(&left_out[..num_frames]);
+        record_xruns(&state.xruns, &read_pos, &left, None);
     }
 
     num_frames
 }
 
+/// Reader id used by the graph processor (`read_channel_audio_any`)
+pub const PROCESSOR_READER_ID: u32 = u32::MAX;
+
+/// Health of one output reader of a capture ring
+#[derive(Debug, Clone)]
+pub struct ReaderDiagnostics {
+    /// Output device reading the ring ([`PROCESSOR_READER_ID`] = graph processor)
+    pub reader_id: u32,
+    /// Frames available at the last read
+    pub fill: usize,
+    /// Lowest fill since the last reset (None = not read yet)
+    pub min_fill: Option<usize>,
+    pub underruns: u64,
+    pub overruns: u64,
+}
+
+/// Health of one capture ring
+#[derive(Debug, Clone)]
+pub struct RingDiagnostics {
+    pub device_id: u32,
+    pub device_name: String,
+    /// Legacy Prism ring (`start_capture`) rather than a per-device capture
+    pub legacy: bool,
+    pub running: bool,
+    pub capacity: usize,
+    pub readers: Vec<ReaderDiagnostics>,
+}
+
+fn reader_diagnostics(
+    positions: &HashMap<u32, Arc<OutputReadPositions>>,
+    reset: bool,
+) -> Vec<ReaderDiagnostics> {
+    let mut readers: Vec<ReaderDiagnostics> = positions
+        .iter()
+        .map(|(&reader_id, p)| {
+            let stats = &p.stats;
+            let min_fill = stats.min_fill.load(Ordering::Relaxed);
+            let diag = ReaderDiagnostics {
+                reader_id,
+                fill: stats.last_fill.load(Ordering::Relaxed),
+                min_fill: (min_fill != usize::MAX).then_some(min_fill),
+                underruns: stats.underruns.load(Ordering::Relaxed),
+                overruns: stats.overruns.load(Ordering::Relaxed),
+            };
+            if reset {
+                stats.reset();
+            }
+            diag
+        })
+        .collect();
+    readers.sort_by_key(|r| r.reader_id);
+    readers
+}
+
+/// Fill level and xruns of every capture ring and its readers
+///
+/// `reset` clears the per-reader counters and low-water marks after reading.
+pub fn buffer_diagnostics(reset: bool) -> Vec<RingDiagnostics> {
+    let mut rings: Vec<RingDiagnostics> = INPUT_DEVICES
+        .read()
+        .values()
+        .map(|state| RingDiagnostics {
+            device_id: state.device_id,
+            device_name: state.device_name.clone(),
+            legacy: false,
+            running: state.running.load(Ordering::SeqCst),
+            capacity: state
+                .buffers
+                .read()
+                .channels
+                .first()
+                .map_or(0, |c| c.data.len()),
+            readers: reader_diagnostics(&state.read_positions.read(), reset),
+        })
+        .collect();
+    rings.sort_by_key(|r| r.device_id);
+
+    if let Some(buffers) = AUDIO_BUFFERS.read().as_ref() {
+        let device_id = PRISM_DEVICE_ID.load(Ordering::SeqCst);
+        rings.push(RingDiagnostics {
+            device_id,
+            device_name: get_device_name(device_id).unwrap_or_else(|_| "Prism".to_string()),
+            legacy: true,
+            running: CAPTURE_RUNNING.load(Ordering::SeqCst),
+            capacity: buffers.buffer_size,
+            readers: reader_diagnostics(&DEVICE_READ_POSITIONS.read(), reset),
+        });
+    }
+    rings
+}
+
 /// Get device info for a specific device
 pub fn get_device_info(device_id: u32) -> Option<(String, u32, bool)> {
     let name = get_device_name(device_id).ok()?;
@@ -3,16 +3,19 @@
 //! This module handles AudioUnit discovery, instantiation, and processing.
 //! Uses CoreAudio's AudioComponent API to enumerate and manage AudioUnits.
 
+use crate::audio::bus::PluginLayout;
+use crate::audio::watchdog::RenderGuard;
 use block2::RcBlock;
 use objc2::runtime::AnyObject;
-use objc2::{class, msg_send, Encode, Encoding, RefEncode};
+use objc2::{class, msg_send, sel, Encode, Encoding, RefEncode};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ffi::{c_void, CStr};
 use std::ptr;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
 
 // CoreAudio bindings
 #[allow(non_upper_case_globals)]
@@ -29,6 +32,13 @@ mod bindings {
 
     pub const noErr: OSStatus = 0;
 
+    // The out-of-process AU instance is gone (extension process crashed)
+    pub const kAudioComponentErr_InstanceInvalidated: OSStatus = -66749;
+
+    // AudioComponentInstantiationOptions
+    pub const kAudioComponentInstantiation_LoadOutOfProcess: u32 = 1;
+    pub const kAudioComponentInstantiation_LoadInProcess: u32 = 2;
+
     // AudioComponent types
     pub const kAudioUnitType_Effect: u32 = 0x61756678; // 'aufx'
     pub const kAudioUnitType_MusicEffect: u32 = 0x61756D66; // 'aumf'
@@ -44,8 +54,39 @@ mod bindings {
     pub const kAudioComponentFlag_SandboxSafe: u32 = 1 << 1;
 
     // AudioUnit properties
+    pub const kAudioUnitProperty_ParameterList: u32 = 3;
+    pub const kAudioUnitProperty_ParameterInfo: u32 = 4;
+    pub const kAudioUnitProperty_ParameterValueStrings: u32 = 16;
     pub const kAudioUnitProperty_CocoaUI: u32 = 4013;
 
+    // AudioUnitParameterInfo (kAudioUnitProperty_ParameterInfo)
+    // AUv3 の AUParameter の unit / flags も同じ値を使う
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy)]
+    pub struct AudioUnitParameterInfo {
+        pub name: [c_char; 52],
+        pub unitName: CFStringRef,
+        pub clumpID: u32,
+        pub cfNameString: CFStringRef,
+        pub unit: u32,
+        pub minValue: f32,
+        pub maxValue: f32,
+        pub defaultValue: f32,
+        pub flags: u32,
+    }
+
+    // AudioUnitParameterOptions
+    pub const kAudioUnitParameterFlag_ValuesHaveStrings: u32 = 1 << 21;
+    pub const kAudioUnitParameterFlag_DisplayLogarithmic: u32 = 1 << 22;
+    pub const kAudioUnitParameterFlag_CanRamp: u32 = 1 << 25;
+    pub const kAudioUnitParameterFlag_IsReadable: u32 = 1 << 30;
+    pub const kAudioUnitParameterFlag_IsWritable: u32 = 1 << 31;
+
+    // AudioUnitParameterUnit
+    pub const kAudioUnitParameterUnit_Indexed: u32 = 1;
+    pub const kAudioUnitParameterUnit_Boolean: u32 = 2;
+    pub const kAudioUnitParameterUnit_CustomUnit: u32 = 26;
+
     // AudioUnit scopes
     pub const kAudioUnitScope_Global: u32 = 0;
     pub const kAudioUnitScope_Input: u32 = 1;
@@ -139,6 +180,23 @@ mod bindings {
             inDataSize: u32,
         ) -> OSStatus;
 
+        pub fn AudioUnitGetParameter(
+            inUnit: AudioUnit,
+            inID: u32,
+            inScope: u32,
+            inElement: u32,
+            outValue: *mut f32,
+        ) -> OSStatus;
+
+        pub fn AudioUnitSetParameter(
+            inUnit: AudioUnit,
+            inID: u32,
+            inScope: u32,
+            inElement: u32,
+            inValue: f32,
+            inBufferOffsetInFrames: u32,
+        ) -> OSStatus;
+
         pub fn AudioUnitRender(
             inUnit: AudioUnit,
             ioActionFlags: *mut u32,
@@ -318,6 +376,81 @@ pub struct AudioUnitInfo {
     pub sandbox_safe: bool,
 }
 
+/// AudioUnitParameterUnit to a short name
+fn parameter_unit_to_string(unit: u32) -> &'static str {
+    const UNITS: [&str; 27] = [
+        "generic",
+        "indexed",
+        "boolean",
+        "percent",
+        "seconds",
+        "sample_frames",
+        "phase",
+        "rate",
+        "hertz",
+        "cents",
+        "relative_semitones",
+        "midi_note",
+        "midi_controller",
+        "decibels",
+        "linear_gain",
+        "degrees",
+        "equal_power_crossfade",
+        "mixer_fader_curve",
+        "pan",
+        "meters",
+        "absolute_cents",
+        "octaves",
+        "bpm",
+        "beats",
+        "milliseconds",
+        "ratio",
+        "custom",
+    ];
+    UNITS.get(unit as usize).copied().unwrap_or("generic")
+}
+
+/// NSString to String (None for nil)
+unsafe fn nsstring_to_string(ns_string: *mut AnyObject) -> Option<String> {
+    if ns_string.is_null() {
+        return None;
+    }
+    let utf8: *const i8 = msg_send![ns_string, UTF8String];
+    if utf8.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(utf8).to_string_lossy().to_string())
+}
+
+/// One automatable parameter of a plugin instance
+///
+/// AUv3 の parameterTree から取得する（AUv2 もブリッジ経由で同じ形になる）。
+/// `unit` / `flags` は AudioUnitParameterInfo と同じ定義。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioUnitParameter {
+    /// Parameter address (AudioUnitParameterID for AUv2 plugins)
+    pub address: u64,
+    /// Stable identifier string (may be empty for AUv2 plugins)
+    pub identifier: String,
+    pub name: String,
+    /// Display names of the enclosing parameter groups, joined with " / "
+    pub group: Option<String>,
+    /// Unit name (decibels, hertz, ...)
+    pub unit: String,
+    /// Plugin-defined unit label (custom units only)
+    pub unit_label: Option<String>,
+    pub min_value: f32,
+    pub max_value: f32,
+    pub value: f32,
+    /// Current value as text formatted by the plugin
+    pub display_value: Option<String>,
+    /// Names of indexed values (empty unless the parameter is a list)
+    pub value_strings: Vec<String>,
+    pub writable: bool,
+    pub can_ramp: bool,
+    pub logarithmic: bool,
+}
+
 /// AudioUnit plugin category
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AudioUnitCategory {
@@ -355,8 +488,6 @@ impl AudioUnitCategory {
 
 /// Get all AudioUnits of a specific category
 pub fn get_audio_units(category: AudioUnitCategory) -> Vec<AudioUnitInfo> {
-    let mut result = Vec::new();
-
     let desc = AudioComponentDescription {
         componentType: category.component_type(),
         componentSubType: 0,
@@ -364,12 +495,39 @@ pub fn get_audio_units(category: AudioUnitCategory) -> Vec<AudioUnitInfo> {
         componentFlags: 0,
         componentFlagsMask: 0,
     };
+    let mut result = find_components(&desc, category.as_str());
+
+    // Sort by manufacturer, then by name
+    result.sort_by(|a, b| {
+        a.manufacturer
+            .cmp(&b.manufacturer)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    result
+}
+
+/// Look up one component again by its codes (None if it is no longer installed)
+pub fn find_audio_unit(info: &AudioUnitInfo) -> Option<AudioUnitInfo> {
+    let desc = AudioComponentDescription {
+        componentType: info.type_code,
+        componentSubType: info.subtype_code,
+        componentManufacturer: info.manufacturer_code,
+        componentFlags: 0,
+        componentFlagsMask: 0,
+    };
+    find_components(&desc, &info.plugin_type).into_iter().next()
+}
+
+/// Enumerate components matching `desc` (0 fields are wildcards)
+fn find_components(desc: &AudioComponentDescription, plugin_type: &str) -> Vec<AudioUnitInfo> {
+    let mut result = Vec::new();
 
     unsafe {
         let mut component: AudioComponent = ptr::null_mut();
 
         loop {
-            component = AudioComponentFindNext(component, &desc);
+            component = AudioComponentFindNext(component, desc);
             if component.is_null() {
                 break;
             }
@@ -411,7 +569,7 @@ pub fn get_audio_units(category: AudioUnitCategory) -> Vec<AudioUnitInfo> {
                 id,
                 name: plugin_name,
                 manufacturer: manufacturer_name,
-                plugin_type: category.as_str().to_string(),
+                plugin_type: plugin_type.to_string(),
                 type_code: out_desc.componentType,
                 subtype_code: out_desc.componentSubType,
                 manufacturer_code: out_desc.componentManufacturer,
@@ -420,13 +578,6 @@ pub fn get_audio_units(category: AudioUnitCategory) -> Vec<AudioUnitInfo> {
         }
     }
 
-    // Sort by manufacturer, then by name
-    result.sort_by(|a, b| {
-        a.manufacturer
-            .cmp(&b.manufacturer)
-            .then_with(|| a.name.cmp(&b.name))
-    });
-
     result
 }
 
@@ -537,6 +688,83 @@ fn resolve_dispatch_symbols() -> Option<(
     }
 }
 
+/// Run `f` on the main thread and wait for its result
+///
+/// Objective-C のプラグイン API はメインスレッドから呼ぶ。既にメインスレッドなら直接実行する。
+fn run_on_main_thread<R, F>(f: F) -> R
+where
+    F: Fn() -> R + 'static,
+    R: 'static,
+{
+    let is_main_thread: bool = unsafe { msg_send![class!(NSThread), isMainThread] };
+    if is_main_thread {
+        return f();
+    }
+    let Some((dispatch_get_main_queue_fn, dispatch_sync_fn)) = resolve_dispatch_symbols() else {
+        // Best-effort fallback on current thread.
+        return f();
+    };
+
+    let f = Arc::new(f);
+    let f_for_block = Arc::clone(&f);
+    let result: Arc<Mutex<Option<R>>> = Arc::new(Mutex::new(None));
+    let result_for_block = Arc::clone(&result);
+    let block = RcBlock::new(move || {
+        *result_for_block.lock().unwrap() = Some(f_for_block());
+    });
+    unsafe {
+        let q = dispatch_get_main_queue_fn();
+        dispatch_sync_fn(q, &*block as *const _ as *mut c_void);
+    }
+    let value = result.lock().unwrap().take();
+    value.unwrap_or_else(|| f())
+}
+
+/// Instantiate new plugins out-of-process (crash isolation)
+static OUT_OF_PROCESS: AtomicBool = AtomicBool::new(false);
+
+/// Number of plugin instances that died so far (watchers compare against this)
+static PLUGIN_CRASHES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of plugin instances bypassed for slow renders so far
+static PLUGIN_OVERLOADS: AtomicU64 = AtomicU64::new(0);
+
+/// Number of times a plugin reported a new latency (compensation follows this)
+static PLUGIN_LATENCY_CHANGES: AtomicU64 = AtomicU64::new(0);
+
+/// Whether new plugin instances are loaded out-of-process
+pub fn out_of_process_hosting() -> bool {
+    OUT_OF_PROCESS.load(Ordering::Relaxed)
+}
+
+/// Load new plugin instances out-of-process (existing instances are unaffected)
+///
+/// AUv3 拡張は別プロセスで動くので、プラグインがクラッシュしてもアプリと
+/// オーディオエンジンは止まらない（処理が 1 ブロック分の IPC 往復ぶん重くなる）。
+/// AUv2 はシステムが対応していれば同様に分離される。
+pub fn set_out_of_process_hosting(enabled: bool) {
+    OUT_OF_PROCESS.store(enabled, Ordering::Relaxed);
+    log_info!(
+        "[AudioUnit] Out-of-process hosting {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+}
+
+/// Total number of crashed plugin instances since launch
+pub fn plugin_crash_count() -> u64 {
+    PLUGIN_CRASHES.load(Ordering::Relaxed)
+}
+
+/// Total number of overloaded plugin instances since launch
+pub fn plugin_overload_count() -> u64 {
+    PLUGIN_OVERLOADS.load(Ordering::Relaxed)
+}
+
+/// Total number of plugin latency changes since launch
+pub fn plugin_latency_change_count() -> u64 {
+    PLUGIN_LATENCY_CHANGES.load(Ordering::Relaxed)
+}
+
 /// Wrapper for raw pointers to make them Send + Sync
 #[derive(Clone, Copy)]
 pub struct SendSyncPtr(pub *mut AnyObject);
@@ -546,7 +774,7 @@ unsafe impl Sync for SendSyncPtr {}
 /// Maximum buffer size for AU processing
 const AU_MAX_BUFFER_SIZE: usize = 8192;
 
-/// Stereo AudioBufferList structure (fixed 2 channels)
+/// Stereo AudioBufferList structure (up to 2 channels; mono uses the first buffer)
 /// This is heap-allocated and its address never changes
 #[repr(C)]
 struct StereoAudioBufferList {
@@ -574,8 +802,9 @@ impl StereoAudioBufferList {
     }
 
     /// Set buffer pointers and size
-    fn set_buffers(&mut self, left: *mut f32, right: *mut f32, frames: u32) {
+    fn set_buffers(&mut self, left: *mut f32, right: *mut f32, frames: u32, channels: u32) {
         let byte_size = frames * 4; // sizeof(float)
+        self.mNumberBuffers = channels.clamp(1, 2);
         self.mBuffers[0].mData = left as *mut c_void;
         self.mBuffers[0].mDataByteSize = byte_size;
         self.mBuffers[1].mData = right as *mut c_void;
@@ -681,6 +910,22 @@ pub struct AudioUnitInstance {
     pub instance_id: String,
     /// Whether render resources have been allocated (atomic for lock-free check)
     render_resources_allocated: AtomicBool,
+    /// Sample rate passed to the last configure() (f64 bits, 0 = unconfigured)
+    sample_rate_bits: AtomicU64,
+    /// Reported latency in samples, read on load / configure / state and parameter changes
+    latency_samples: AtomicU32,
+    /// Channel count of the bus formats (1 or 2)
+    channels: AtomicU32,
+    /// Loaded in a separate extension process
+    out_of_process: bool,
+    /// The extension process died; processing is bypassed until restarted
+    crashed: AtomicBool,
+    /// Render time watchdog; an overloaded instance is bypassed until restarted
+    render_guard: RenderGuard,
+    /// Last fullState read successfully (restored when restarting a crashed instance)
+    last_state: Mutex<Option<Vec<u8>>>,
+    /// A fullState read is queued on the main thread and has not returned yet
+    state_capture_pending: AtomicBool,
     /// Processing state - wrapped in UnsafeCell for lock-free audio thread access
     /// SAFETY: Only accessed from audio thread during process(), never concurrently
     processing_state: std::cell::UnsafeCell<ProcessingState>,
@@ -721,9 +966,26 @@ impl AudioUnitInstance {
         instance_id: String,
         au_audio_unit: *mut AnyObject,
     ) -> Result<Self, String> {
-        println!(
-            "[AudioUnit] Created AUAudioUnit instance {:?} for {}",
-            au_audio_unit, info.name
+        // macOS 10.15+: 実際にどちらで読み込まれたかを AU 自身に聞く
+        let out_of_process = unsafe {
+            let responds: bool =
+                msg_send![au_audio_unit, respondsToSelector: sel!(isLoadedInProcess)];
+            if responds {
+                let in_process: bool = msg_send![au_audio_unit, isLoadedInProcess];
+                !in_process
+            } else {
+                false
+            }
+        };
+        log_info!(
+            "[AudioUnit] Created AUAudioUnit instance {:?} for {} ({})",
+            au_audio_unit,
+            info.name,
+            if out_of_process {
+                "out-of-process"
+            } else {
+                "in-process"
+            }
         );
 
         Ok(Self {
@@ -733,6 +995,14 @@ impl AudioUnitInstance {
             enabled: AtomicBool::new(true),
             instance_id,
             render_resources_allocated: AtomicBool::new(false),
+            sample_rate_bits: AtomicU64::new(0),
+            latency_samples: AtomicU32::new(0),
+            channels: AtomicU32::new(2),
+            out_of_process,
+            crashed: AtomicBool::new(false),
+            render_guard: RenderGuard::new(),
+            last_state: Mutex::new(None),
+            state_capture_pending: AtomicBool::new(false),
             processing_state: std::cell::UnsafeCell::new(ProcessingState {
                 input_buffer_list: StereoAudioBufferList::new(),
                 output_buffer_list: StereoAudioBufferList::new(),
@@ -750,7 +1020,7 @@ impl AudioUnitInstance {
     {
         let info = info.clone();
 
-        println!(
+        log_info!(
             "[AudioUnit] Starting async instantiation for {} on main thread (non-blocking)",
             info.name
         );
@@ -786,7 +1056,7 @@ impl AudioUnitInstance {
             }
 
             // Not on main thread - dispatch synchronously to main thread
-            println!("[AudioUnit] Synchronously dispatching to main thread (may block UI)...");
+            log_info!("[AudioUnit] Synchronously dispatching to main thread (may block UI)...");
 
             // Use a semaphore to wait for completion
             let semaphore = dispatch_semaphore_create(0);
@@ -862,29 +1132,35 @@ impl AudioUnitInstance {
             // Create the completion handler block
             let block = RcBlock::new(
                 move |au_audio_unit: *mut AnyObject, error: *mut AnyObject| {
-                    println!("[AudioUnit] Completion handler called!");
+                    log_info!("[AudioUnit] Completion handler called!");
                     if !error.is_null() {
-                        println!("[AudioUnit] AUAudioUnit instantiation error");
+                        log_warn!("[AudioUnit] AUAudioUnit instantiation error");
                     }
 
                     if !au_audio_unit.is_null() {
                         // Retain the AUAudioUnit to prevent deallocation
                         let _: () = msg_send![au_audio_unit, retain];
                         *result_clone.lock().unwrap() = Some(au_audio_unit);
-                        println!("[AudioUnit] AUAudioUnit retained");
+                        log_info!("[AudioUnit] AUAudioUnit retained");
                     }
 
                     // Signal that completion is done
                     dispatch_semaphore_signal(semaphore);
-                    println!("[AudioUnit] Semaphore signaled");
+                    log_info!("[AudioUnit] Semaphore signaled");
                 },
             );
 
+            let options = if out_of_process_hosting() {
+                kAudioComponentInstantiation_LoadOutOfProcess
+            } else {
+                0
+            };
+
             // Call instantiateWithComponentDescription:options:completionHandler:
             let _: () = msg_send![
                 au_audio_unit_class,
                 instantiateWithComponentDescription: desc
-                options: 0u32
+                options: options
                 completionHandler: &*block
             ];
 
@@ -903,7 +1179,7 @@ impl AudioUnitInstance {
 
             match au_audio_unit {
                 Some(au) => {
-                    println!(
+                    log_info!(
                         "[AudioUnit] Successfully instantiated AUAudioUnit: {:?}",
                         au
                     );
@@ -914,11 +1190,290 @@ impl AudioUnitInstance {
         }
     }
 
+    /// Whether the instance runs in a separate extension process
+    pub fn is_out_of_process(&self) -> bool {
+        self.out_of_process
+    }
+
+    /// Whether the plugin process died (processing is bypassed)
+    pub fn is_crashed(&self) -> bool {
+        self.crashed.load(Ordering::Acquire)
+    }
+
+    /// Whether renders kept exceeding the time budget (processing is bypassed)
+    pub fn is_overloaded(&self) -> bool {
+        self.render_guard.is_overloaded()
+    }
+
+    /// Duration of the last render that exceeded the budget (ms)
+    pub fn slow_render_ms(&self) -> f32 {
+        self.render_guard.slow_render_ms()
+    }
+
+    /// Last fullState read successfully
+    pub fn last_known_state(&self) -> Option<Vec<u8>> {
+        self.last_state.lock().unwrap().clone()
+    }
+
+    /// Channel count the instance is configured for (1 or 2)
+    pub fn channels(&self) -> u32 {
+        self.channels.load(Ordering::Acquire)
+    }
+
+    /// Whether `channelCapabilities` allows `channels` in and out
+    ///
+    /// nil は「入出力が同じなら何chでも可」。負の値は -1/-2 が任意、-N は N ch まで。
+    /// 実際に受け付けるかは configure() の setFormat で最終確認する。
+    /// NOTE: Calls into Objective-C - do not use from the audio thread.
+    pub fn supports_channels(&self, channels: u32) -> bool {
+        let au = match self.au_audio_unit {
+            Some(SendSyncPtr(au)) if !au.is_null() => au,
+            _ => return false,
+        };
+        let accepts = |n: isize| {
+            n == channels as isize || n == -1 || n == -2 || (n < -2 && channels as isize <= -n)
+        };
+
+        unsafe {
+            let capabilities: *mut AnyObject = msg_send![au, channelCapabilities];
+            if capabilities.is_null() {
+                return true;
+            }
+            let count: usize = msg_send![capabilities, count];
+            (0..count / 2).any(|i| {
+                let input: *mut AnyObject =
+                    msg_send![capabilities, objectAtIndexedSubscript: i * 2];
+                let output: *mut AnyObject =
+                    msg_send![capabilities, objectAtIndexedSubscript: i * 2 + 1];
+                let input: isize = msg_send![input, integerValue];
+                let output: isize = msg_send![output, integerValue];
+                accepts(input) && accepts(output)
+            })
+        }
+    }
+
     /// Get the AUAudioUnit instance (for AUv3 UI)
     pub fn get_au_audio_unit(&self) -> Option<*mut AnyObject> {
         self.au_audio_unit.map(|p| p.0)
     }
 
+    /// Processing latency reported by the plugin, in samples at the configured rate
+    ///
+    /// Cached value (no Objective-C call), so the graph can read it under its lock.
+    /// Updated by [`Self::refresh_latency`]. Returns 0 when unconfigured.
+    pub fn latency_samples(&self) -> u32 {
+        self.latency_samples.load(Ordering::Acquire)
+    }
+
+    /// Re-read the plugin's latency into the cache; returns whether it changed
+    ///
+    /// Reads AUAudioUnit's `latency` (seconds; the AUv3 counterpart of
+    /// `kAudioUnitProperty_Latency`). configure / fullState / パラメーター変更の後と、
+    /// プラグイン自身の UI での変更を拾う定期確認（[`AudioUnitManager::refresh_latencies`]）で呼ばれる。
+    /// 変わったら [`plugin_latency_change_count`] を進める（補正の再計算はそれを見て行う）。
+    /// NOTE: Calls into Objective-C - do not use from the audio thread.
+    pub fn refresh_latency(&self) -> bool {
+        let latency = self.query_latency();
+        let changed = self.latency_samples.swap(latency, Ordering::AcqRel) != latency;
+        if changed {
+            PLUGIN_LATENCY_CHANGES.fetch_add(1, Ordering::Relaxed);
+        }
+        changed
+    }
+
+    fn query_latency(&self) -> u32 {
+        let au = match self.au_audio_unit {
+            Some(SendSyncPtr(au)) if !au.is_null() => au,
+            _ => return 0,
+        };
+        if !self.render_resources_allocated.load(Ordering::Acquire) {
+            return 0;
+        }
+
+        let sample_rate = f64::from_bits(self.sample_rate_bits.load(Ordering::Acquire));
+        let latency: f64 = unsafe { msg_send![au, latency] };
+        if !latency.is_finite() || latency <= 0.0 || sample_rate <= 0.0 {
+            return 0;
+        }
+        (latency * sample_rate).round() as u32
+    }
+
+    /// Clear the plugin's processing state (reverb / delay tails)
+    ///
+    /// NOTE: Calls into Objective-C - not while the instance is being processed.
+    pub fn reset(&self) {
+        let au = match self.au_audio_unit {
+            Some(SendSyncPtr(au)) if !au.is_null() => au,
+            _ => return,
+        };
+        if self.render_resources_allocated.load(Ordering::Acquire) {
+            unsafe {
+                let _: () = msg_send![au, reset];
+            }
+        }
+    }
+
+    /// All parameters from the AUAudioUnit parameter tree
+    /// NOTE: Calls into Objective-C - call on the main thread (see `AudioUnitManager::get_parameters`)
+    pub fn parameters(&self) -> Vec<AudioUnitParameter> {
+        let au = match self.au_audio_unit {
+            Some(SendSyncPtr(au)) if !au.is_null() => au,
+            _ => return Vec::new(),
+        };
+
+        unsafe {
+            let tree: *mut AnyObject = msg_send![au, parameterTree];
+            if tree.is_null() {
+                return Vec::new();
+            }
+            let mut params = Vec::new();
+            Self::collect_parameters(tree, &mut Vec::new(), &mut params);
+            if !params.is_empty() {
+                return params;
+            }
+
+            // グループを辿れないツリーはフラットな一覧から
+            let all: *mut AnyObject = msg_send![tree, allParameters];
+            if all.is_null() {
+                return Vec::new();
+            }
+            let count: usize = msg_send![all, count];
+            (0..count)
+                .filter_map(|i| {
+                    let param: *mut AnyObject = msg_send![all, objectAtIndex: i];
+                    Self::read_parameter(param, None)
+                })
+                .collect()
+        }
+    }
+
+    /// Walk a parameter group in tree order, recording the group path of each parameter
+    unsafe fn collect_parameters(
+        group: *mut AnyObject,
+        path: &mut Vec<String>,
+        out: &mut Vec<AudioUnitParameter>,
+    ) {
+        let children: *mut AnyObject = msg_send![group, children];
+        if children.is_null() {
+            return;
+        }
+        let count: usize = msg_send![children, count];
+        for i in 0..count {
+            let node: *mut AnyObject = msg_send![children, objectAtIndex: i];
+            if node.is_null() {
+                continue;
+            }
+            let is_group: bool = msg_send![node, isKindOfClass: class!(AUParameterGroup)];
+            if is_group {
+                let name: *mut AnyObject = msg_send![node, displayName];
+                path.push(nsstring_to_string(name).unwrap_or_default());
+                Self::collect_parameters(node, path, out);
+                path.pop();
+            } else {
+                let names: Vec<&str> = path
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|n| !n.is_empty())
+                    .collect();
+                let group = (!names.is_empty()).then(|| names.join(" / "));
+                out.extend(Self::read_parameter(node, group));
+            }
+        }
+    }
+
+    unsafe fn read_parameter(
+        param: *mut AnyObject,
+        group: Option<String>,
+    ) -> Option<AudioUnitParameter> {
+        if param.is_null() {
+            return None;
+        }
+        let address: u64 = msg_send![param, address];
+        let identifier: *mut AnyObject = msg_send![param, identifier];
+        let display_name: *mut AnyObject = msg_send![param, displayName];
+        let unit: u32 = msg_send![param, unit];
+        let unit_name: *mut AnyObject = msg_send![param, unitName];
+        let flags: u32 = msg_send![param, flags];
+        let min_value: f32 = msg_send![param, minValue];
+        let max_value: f32 = msg_send![param, maxValue];
+        let value: f32 = msg_send![param, value];
+        let display_value: *mut AnyObject = msg_send![param, stringFromValue: ptr::null::<f32>()];
+
+        let mut value_strings = Vec::new();
+        let strings: *mut AnyObject = msg_send![param, valueStrings];
+        if !strings.is_null() {
+            let count: usize = msg_send![strings, count];
+            for i in 0..count {
+                let s: *mut AnyObject = msg_send![strings, objectAtIndex: i];
+                value_strings.push(nsstring_to_string(s).unwrap_or_default());
+            }
+        }
+
+        let name = nsstring_to_string(display_name)
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| format!("Parameter {}", address));
+        Some(AudioUnitParameter {
+            address,
+            identifier: nsstring_to_string(identifier).unwrap_or_default(),
+            name,
+            group,
+            unit: parameter_unit_to_string(unit).to_string(),
+            unit_label: if unit == kAudioUnitParameterUnit_CustomUnit {
+                nsstring_to_string(unit_name)
+            } else {
+                None
+            },
+            min_value,
+            max_value,
+            value,
+            display_value: nsstring_to_string(display_value),
+            value_strings,
+            writable: flags & kAudioUnitParameterFlag_IsWritable != 0,
+            can_ramp: flags & kAudioUnitParameterFlag_CanRamp != 0,
+            logarithmic: flags & kAudioUnitParameterFlag_DisplayLogarithmic != 0,
+        })
+    }
+
+    /// Set a parameter value (clamped to its range); returns the parameter after the change
+    /// NOTE: Calls into Objective-C - call on the main thread (see `AudioUnitManager::set_parameter`)
+    pub fn set_parameter(&self, address: u64, value: f32) -> Result<AudioUnitParameter, String> {
+        let au = match self.au_audio_unit {
+            Some(SendSyncPtr(au)) if !au.is_null() => au,
+            _ => return Err("AudioUnit is not instantiated".to_string()),
+        };
+        if !value.is_finite() {
+            return Err("Parameter value must be finite".to_string());
+        }
+
+        unsafe {
+            let tree: *mut AnyObject = msg_send![au, parameterTree];
+            if tree.is_null() {
+                return Err(format!("{} has no parameters", self.info.name));
+            }
+            let param: *mut AnyObject = msg_send![tree, parameterWithAddress: address];
+            if param.is_null() {
+                return Err(format!("Parameter {} not found", address));
+            }
+            let flags: u32 = msg_send![param, flags];
+            if flags & kAudioUnitParameterFlag_IsWritable == 0 {
+                return Err(format!("Parameter {} is read-only", address));
+            }
+            let min_value: f32 = msg_send![param, minValue];
+            let max_value: f32 = msg_send![param, maxValue];
+            let mut value = value.clamp(min_value.min(max_value), max_value.max(min_value));
+            let unit: u32 = msg_send![param, unit];
+            if unit == kAudioUnitParameterUnit_Indexed || unit == kAudioUnitParameterUnit_Boolean {
+                value = value.round();
+            }
+            let _: () = msg_send![param, setValue: value];
+            // ルックアヘッドなどレイテンシが変わるパラメーターがある
+            self.refresh_latency();
+            // 1 パラメーターだけ読み直すのでグループは付かない（一覧側で保持している）
+            Self::read_parameter(param, None)
+                .ok_or_else(|| format!("Parameter {} not found", address))
+        }
+    }
+
     /// Get the plugin's full state (all parameters and data) as a plist data
     /// Returns None if no AUAudioUnit or if state couldn't be retrieved
     pub fn get_full_state(&self) -> Option<Vec<u8>> {
@@ -931,7 +1486,7 @@ impl AudioUnitInstance {
             // Get fullState property (NSDictionary*)
             let full_state: *mut AnyObject = msg_send![au, fullState];
             if full_state.is_null() {
-                println!("[AudioUnit] fullState is nil for {}", self.info.name);
+                log_info!("[AudioUnit] fullState is nil for {}", self.info.name);
                 return None;
             }
 
@@ -950,7 +1505,7 @@ impl AudioUnitInstance {
             ];
 
             if plist_data.is_null() {
-                println!(
+                log_warn!(
                     "[AudioUnit] Failed to serialize fullState for {}",
                     self.info.name
                 );
@@ -962,12 +1517,13 @@ impl AudioUnitInstance {
             let bytes: *const u8 = msg_send![plist_data, bytes];
 
             if bytes.is_null() || length == 0 {
-                println!("[AudioUnit] Empty plist data for {}", self.info.name);
+                log_info!("[AudioUnit] Empty plist data for {}", self.info.name);
                 return None;
             }
 
             let data = std::slice::from_raw_parts(bytes, length).to_vec();
-            println!(
+            *self.last_state.lock().unwrap() = Some(data.clone());
+            log_info!(
                 "[AudioUnit] Got fullState ({} bytes) for {}",
                 data.len(),
                 self.info.name
@@ -998,7 +1554,7 @@ impl AudioUnitInstance {
             ];
 
             if ns_data.is_null() {
-                println!("[AudioUnit] Failed to create NSData for {}", self.info.name);
+                log_warn!("[AudioUnit] Failed to create NSData for {}", self.info.name);
                 return false;
             }
 
@@ -1018,7 +1574,7 @@ impl AudioUnitInstance {
             ];
 
             if full_state.is_null() {
-                println!(
+                log_warn!(
                     "[AudioUnit] Failed to parse plist data for {}",
                     self.info.name
                 );
@@ -1027,7 +1583,8 @@ impl AudioUnitInstance {
 
             // Set fullState property
             let _: () = msg_send![au, setFullState: full_state];
-            println!(
+            self.refresh_latency();
+            log_info!(
                 "[AudioUnit] Set fullState ({} bytes) for {}",
                 data.len(),
                 self.info.name
@@ -1039,17 +1596,19 @@ impl AudioUnitInstance {
     /// Configure the AudioUnit for processing using AUv3 API
     /// This uses AUAudioUnit's allocateRenderResources and internalRenderBlock
     /// Must be called before process() with the current sample rate and max frames
+    /// `channels` is 1 (mono) or 2; a plugin that rejects a mono format is an error
     /// NOTE: Must be called from main thread only, never concurrently with process()
     pub fn configure(
         &mut self,
         sample_rate: f64,
         max_frames: u32,
-        _channels: u32,
+        channels: u32,
     ) -> Result<(), String> {
         let au = match self.au_audio_unit {
             Some(SendSyncPtr(au)) if !au.is_null() => au,
             _ => return Err("No AUAudioUnit instance".to_string()),
         };
+        let channels = channels.clamp(1, 2);
 
         unsafe {
             // Deallocate existing resources if any
@@ -1057,6 +1616,7 @@ impl AudioUnitInstance {
                 let _: () = msg_send![au, deallocateRenderResources];
                 self.render_resources_allocated
                     .store(false, Ordering::Release);
+                self.latency_samples.store(0, Ordering::Release);
                 *self.render_block.get() = None;
             }
 
@@ -1067,20 +1627,23 @@ impl AudioUnitInstance {
             let input_busses: *mut AnyObject = msg_send![au, inputBusses];
             let output_busses: *mut AnyObject = msg_send![au, outputBusses];
 
-            // Create AVAudioFormat for stereo non-interleaved float
+            // Create AVAudioFormat for mono/stereo non-interleaved float
             let av_audio_format_class = class!(AVAudioFormat);
             let format: *mut AnyObject = msg_send![av_audio_format_class, alloc];
             // initStandardFormatWithSampleRate:channels: creates non-interleaved float format
             let format: *mut AnyObject = msg_send![
                 format,
                 initStandardFormatWithSampleRate: sample_rate
-                channels: 2u32
+                channels: channels
             ];
 
             if format.is_null() {
                 return Err("Failed to create AVAudioFormat".to_string());
             }
 
+            // ステレオは従来どおり失敗しても続行する。モノラルは拒否されたら呼び出し側で戻す
+            let mut format_rejected = false;
+
             // Set format on input bus 0 and ENABLE it
             let input_bus_count: usize = msg_send![input_busses, count];
             if input_bus_count > 0 {
@@ -1094,12 +1657,13 @@ impl AudioUnitInstance {
                     let success: bool =
                         msg_send![input_bus, setFormat: format error: &mut error as *mut _];
                     if !success {
-                        println!(
+                        format_rejected = true;
+                        log_warn!(
                             "[AudioUnit] Warning: Failed to set input format for {}",
                             self.info.name
                         );
                     } else {
-                        println!(
+                        log_info!(
                             "[AudioUnit] Input bus 0 enabled and format set for {}",
                             self.info.name
                         );
@@ -1117,7 +1681,8 @@ impl AudioUnitInstance {
                     let success: bool =
                         msg_send![output_bus, setFormat: format error: &mut error as *mut _];
                     if !success {
-                        println!(
+                        format_rejected = true;
+                        log_warn!(
                             "[AudioUnit] Warning: Failed to set output format for {}",
                             self.info.name
                         );
@@ -1128,6 +1693,13 @@ impl AudioUnitInstance {
             // Release format
             let _: () = msg_send![format, release];
 
+            if format_rejected && channels != 2 {
+                return Err(format!(
+                    "{} does not accept a {}-channel format",
+                    self.info.name, channels
+                ));
+            }
+
             // Allocate render resources
             let mut error: *mut AnyObject = std::ptr::null_mut();
             let success: bool =
@@ -1169,10 +1741,18 @@ impl AudioUnitInstance {
             *self.render_block.get() = Some(render_block);
             self.render_resources_allocated
                 .store(true, Ordering::Release);
-
-            println!(
-                "[AudioUnit] Configured {} @ {}Hz, {} frames (AUv3 API, renderBlock={:?})",
-                self.info.name, sample_rate, max_frames, render_block
+            self.sample_rate_bits
+                .store(sample_rate.to_bits(), Ordering::Release);
+            self.channels.store(channels, Ordering::Release);
+            self.refresh_latency();
+
+            log_info!(
+                "[AudioUnit] Configured {} @ {}Hz, {} frames, {}ch (AUv3 API, renderBlock={:?})",
+                self.info.name,
+                sample_rate,
+                max_frames,
+                channels,
+                render_block
             );
             Ok(())
         }
@@ -1189,7 +1769,10 @@ impl AudioUnitInstance {
         right: &mut [f32],
         _sample_time: f64,
     ) -> Result<(), String> {
-        if !self.enabled.load(Ordering::Relaxed) {
+        if !self.enabled.load(Ordering::Relaxed)
+            || self.crashed.load(Ordering::Relaxed)
+            || self.render_guard.is_overloaded()
+        {
             return Ok(());
         }
 
@@ -1216,17 +1799,22 @@ impl AudioUnitInstance {
             state.input_copy.left[..frames_usize].copy_from_slice(&left[..frames_usize]);
             state.input_copy.right[..frames_usize].copy_from_slice(&right[..frames_usize]);
 
-            // Set up input buffer list pointing to our copy
+            // Set up input buffer list pointing to our copy (mono: left only)
+            let channels = self.channels.load(Ordering::Relaxed);
             state.input_buffer_list.set_buffers(
                 state.input_copy.left.as_mut_ptr(),
                 state.input_copy.right.as_mut_ptr(),
                 frames,
+                channels,
             );
 
             // Set up output buffer list pointing directly to caller's buffers (zero-copy output)
-            state
-                .output_buffer_list
-                .set_buffers(left.as_mut_ptr(), right.as_mut_ptr(), frames);
+            state.output_buffer_list.set_buffers(
+                left.as_mut_ptr(),
+                right.as_mut_ptr(),
+                frames,
+                channels,
+            );
 
             // Minimal timestamp - only sample time is needed
             let timestamp = AudioTimeStamp {
@@ -1361,6 +1949,7 @@ impl AudioUnitInstance {
 
             let output_buffer_list_ptr = state.output_buffer_list.as_audio_buffer_list();
 
+            let render_started = Instant::now();
             let status = ((*render_block_ptr).invoke)(
                 render_block_ptr,
                 &mut action_flags,
@@ -1373,6 +1962,22 @@ impl AudioUnitInstance {
 
             state.sample_position += frames as i64;
 
+            // 予算超過が続いたら以降はバイパス（デバイス全体を止めないため）
+            if self.render_guard.record(
+                render_started.elapsed(),
+                frames as usize,
+                f64::from_bits(self.sample_rate_bits.load(Ordering::Relaxed)),
+            ) {
+                PLUGIN_OVERLOADS.fetch_add(1, Ordering::Relaxed);
+            }
+
+            if status == kAudioComponentErr_InstanceInvalidated {
+                // 拡張プロセスが落ちた: 以降はバイパスし、再起動を待つ
+                if !self.crashed.swap(true, Ordering::AcqRel) {
+                    PLUGIN_CRASHES.fetch_add(1, Ordering::Relaxed);
+                }
+                return Err("plugin process crashed".to_string());
+            }
             if status != 0 {
                 return Err(format!("render failed: {}", status));
             }
@@ -1494,11 +2099,11 @@ impl Drop for AudioUnitInstance {
                         if render_resources_allocated {
                             let _: () = msg_send![au, deallocateRenderResources];
                         }
-                        println!("[AudioUnit] Releasing AUAudioUnit: {:?}", au);
+                        log_info!("[AudioUnit] Releasing AUAudioUnit: {:?}", au);
                         let _: () = msg_send![au, release];
                     } else {
                         // Not on main thread - dispatch synchronously to main thread using semaphore
-                        println!(
+                        log_info!(
                             "[AudioUnit] Synchronously releasing AUAudioUnit on main thread: {:?}",
                             au
                         );
@@ -1515,7 +2120,7 @@ impl Drop for AudioUnitInstance {
                             if render_resources_allocated {
                                 let _: () = msg_send![au, deallocateRenderResources];
                             }
-                            println!("[AudioUnit] Releasing AUAudioUnit on main thread: {:?}", au);
+                            log_info!("[AudioUnit] Releasing AUAudioUnit on main thread: {:?}", au);
                             let _: () = msg_send![au, release];
 
                             // Signal completion
@@ -1530,7 +2135,7 @@ impl Drop for AudioUnitInstance {
                         let timeout = dispatch_time(DISPATCH_TIME_NOW, 10_000_000_000); // 10 seconds
                         let result = dispatch_semaphore_wait(semaphore, timeout);
                         if result != 0 {
-                            eprintln!("[AudioUnit] WARNING: Timed out waiting for AudioUnit cleanup on main thread");
+                            log_warn!("[AudioUnit] WARNING: Timed out waiting for AudioUnit cleanup on main thread");
                         }
                         dispatch_release(semaphore);
                     }
@@ -1552,6 +2157,9 @@ pub struct AudioUnitManager {
     /// Inner Arc<AudioUnitInstance> has no locks, process() takes &self
     /// Wrapped in Arc to allow cloning in async operations
     instances: Arc<RwLock<HashMap<String, Arc<AudioUnitInstance>>>>,
+    /// Extra stereo instances for buses wider than 2 ports, by primary instance ID
+    /// (ports 2/3, 4/5, ...). パラメーター・有効状態・fullState はプライマリに追従する
+    pairs: RwLock<HashMap<String, Vec<Arc<AudioUnitInstance>>>>,
     /// Counter for unique instance IDs
     counter: std::sync::atomic::AtomicU64,
 }
@@ -1560,23 +2168,27 @@ impl AudioUnitManager {
     pub fn new() -> Self {
         Self {
             instances: Arc::new(RwLock::new(HashMap::new())),
+            pairs: RwLock::new(HashMap::new()),
             counter: std::sync::atomic::AtomicU64::new(1),
         }
     }
 
     /// Create a new AudioUnit instance
     /// NOTE: Called from main thread only, never from audio thread
-    /// Automatically configures the instance for 48kHz stereo processing
+    /// Automatically configures the instance for stereo processing at the engine sample rate
     pub fn create_instance(&self, info: &AudioUnitInfo) -> Result<String, String> {
         let id = self
             .counter
             .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         let instance_id = format!("au_{}", id);
 
-        let mut instance = AudioUnitInstance::new(info, instance_id.clone())?;
+        let mut instance = AudioUnitInstance::new(info, instance_id.clone())
+            .inspect_err(|_| crate::audio::session_stats::record_plugin_failure())?;
 
         // Pre-configure the instance for audio processing. This is a critical step.
-        instance.configure(48000.0, 1024, 2)?;
+        instance
+            .configure(crate::audio::engine_sample_rate(), 1024, 2)
+            .inspect_err(|_| crate::audio::session_stats::record_plugin_failure())?;
 
         self.instances
             .write()
@@ -1584,9 +2196,10 @@ impl AudioUnitManager {
 
         // Debug log: instance created and current count
         let count = self.instances.read().len();
-        println!(
+        log_info!(
             "[AudioUnit] create_instance -> {} (total={})",
-            instance_id, count
+            instance_id,
+            count
         );
 
         Ok(instance_id)
@@ -1594,7 +2207,7 @@ impl AudioUnitManager {
 
     /// Create a new AudioUnit instance asynchronously (non-blocking, better UI responsiveness)
     /// NOTE: Called from main thread only, never from audio thread
-    /// Automatically configures the instance for 48kHz stereo processing
+    /// Automatically configures the instance for stereo processing at the engine sample rate
     /// The callback will be called with the result once instantiation completes
     pub fn create_instance_async<F>(&self, info: &AudioUnitInfo, callback: F)
     where
@@ -1622,34 +2235,38 @@ impl AudioUnitManager {
                     ) {
                         Ok(mut instance) => {
                             // Pre-configure the instance
-                            match instance.configure(48000.0, 1024, 2) {
+                            match instance.configure(crate::audio::engine_sample_rate(), 1024, 2) {
                                 Ok(()) => {
                                     instances
                                         .write()
                                         .insert(instance_id_clone.clone(), Arc::new(instance));
 
                                     let count = instances.read().len();
-                                    println!(
+                                    log_info!(
                                         "[AudioUnit] create_instance_async -> {} (total={})",
-                                        instance_id_clone, count
+                                        instance_id_clone,
+                                        count
                                     );
 
                                     callback(Ok(instance_id_clone));
                                 }
                                 Err(e) => {
-                                    println!("[AudioUnit] Failed to configure instance: {}", e);
+                                    log_warn!("[AudioUnit] Failed to configure instance: {}", e);
+                                    crate::audio::session_stats::record_plugin_failure();
                                     callback(Err(e));
                                 }
                             }
                         }
                         Err(e) => {
-                            println!("[AudioUnit] Failed to create instance: {}", e);
+                            log_warn!("[AudioUnit] Failed to create instance: {}", e);
+                            crate::audio::session_stats::record_plugin_failure();
                             callback(Err(e));
                         }
                     }
                 }
                 Err(e) => {
-                    println!("[AudioUnit] Failed to instantiate AU: {}", e);
+                    log_warn!("[AudioUnit] Failed to instantiate AU: {}", e);
+                    crate::audio::session_stats::record_plugin_failure();
                     callback(Err(e));
                 }
             }
@@ -1666,15 +2283,17 @@ impl AudioUnitManager {
     pub fn remove_instance(&self, id: &str) -> bool {
         // Clean up cached view controller before removing the instance
         crate::audio_unit_ui::cleanup_cached_view_controller(id);
+        self.pairs.write().remove(id);
         let removed = self.instances.write().remove(id).is_some();
         if removed {
             let count = self.instances.read().len();
-            println!(
+            log_info!(
                 "[AudioUnit] remove_instance -> {} (remaining={})",
-                id, count
+                id,
+                count
             );
         } else {
-            println!("[AudioUnit] remove_instance -> {} (not found)", id);
+            log_info!("[AudioUnit] remove_instance -> {} (not found)", id);
         }
         removed
     }
@@ -1686,7 +2305,7 @@ impl AudioUnitManager {
         unsafe {
             let is_main_thread: bool = msg_send![class!(NSThread), isMainThread];
             if !is_main_thread {
-                eprintln!("[AudioUnit] WARNING: remove_all_instances called from non-main thread!");
+                log_warn!("[AudioUnit] WARNING: remove_all_instances called from non-main thread!");
             }
         }
 
@@ -1700,8 +2319,9 @@ impl AudioUnitManager {
             // Clean up any cached UI controller
             crate::audio_unit_ui::cleanup_cached_view_controller(&id);
             // Remove instance (drop will release AU resources on main thread)
+            self.pairs.write().remove(&id);
             self.instances.write().remove(&id);
-            println!("[AudioUnit] Removed instance {} during shutdown", id);
+            log_info!("[AudioUnit] Removed instance {} during shutdown", id);
         }
     }
 
@@ -1709,6 +2329,9 @@ impl AudioUnitManager {
     pub fn set_enabled(&self, id: &str, enabled: bool) -> bool {
         if let Some(instance) = self.instances.read().get(id) {
             instance.enabled.store(enabled, Ordering::Release);
+            for pair in self.pair_instances(id) {
+                pair.enabled.store(enabled, Ordering::Release);
+            }
             true
         } else {
             false
@@ -1730,20 +2353,130 @@ impl AudioUnitManager {
             .collect()
     }
 
+    /// Clear the processing state of every instance (after an offline render)
+    /// NOTE: Never called while the graph is being processed
+    pub fn reset_all(&self) {
+        let instances = self.instances.read();
+        let pairs = self.pairs.read();
+        for instance in instances.values().chain(pairs.values().flatten()) {
+            instance.reset();
+        }
+    }
+
     /// Configure all instances for processing
+    /// Each instance keeps the channel count negotiated for its bus
     /// NOTE: Called from main thread only, never from audio thread
-    pub fn configure_all(&self, sample_rate: f64, max_frames: u32, channels: u32) {
+    pub fn configure_all(&self, sample_rate: f64, max_frames: u32) {
         let instances = self.instances.read();
-        for (id, instance) in instances.iter() {
+        let pairs = self.pairs.read();
+        let all = instances.iter().chain(
+            pairs
+                .iter()
+                .flat_map(|(id, p)| p.iter().map(move |inst| (id, inst))),
+        );
+        for (id, instance) in all {
             // SAFETY: configure is only called from main thread, never concurrently with process
             // We need mutable access, so we use unsafe here
             let inst_ptr = Arc::as_ptr(instance) as *mut AudioUnitInstance;
             unsafe {
+                let channels = (*inst_ptr).channels();
                 if let Err(e) = (*inst_ptr).configure(sample_rate, max_frames, channels) {
-                    println!("[AudioUnit] Failed to configure {}: {}", id, e);
+                    log_warn!("[AudioUnit] Failed to configure {}: {}", id, e);
+                }
+            }
+        }
+    }
+
+    /// Extra stereo instances of a plugin on a bus wider than 2 ports
+    pub fn pair_instances(&self, id: &str) -> Vec<Arc<AudioUnitInstance>> {
+        self.pairs.read().get(id).cloned().unwrap_or_default()
+    }
+
+    /// Fit an instance to a `bus_channels`-wide bus
+    ///
+    /// - 1ch: `channelCapabilities` が許せばモノラルで設定し直す。だめならステレオに戻してバイパス
+    /// - 2ch: ステレオ
+    /// - 3ch 以上: ステレオのまま、残りのペア用にインスタンスを追加し fullState を複製する
+    ///   （作れなければバイパス）
+    ///
+    /// The caller must keep the instance out of the audio thread until the returned
+    /// layout is applied to the bus (configure() reallocates render resources).
+    /// NOTE: Called from main thread only, never from audio thread
+    pub fn negotiate_layout(&self, instance_id: &str, bus_channels: usize) -> PluginLayout {
+        let Some(instance) = self.get_instance(instance_id) else {
+            return PluginLayout::Bypassed;
+        };
+        let sample_rate = crate::audio::engine_sample_rate();
+        let extra_pairs = (bus_channels / 2).saturating_sub(1);
+        let existing = self.pair_instances(instance_id);
+        let id = instance_id.to_string();
+
+        let (layout, pairs) = run_on_main_thread(move || {
+            // SAFETY: main thread; the bus does not process this instance meanwhile
+            let inst = unsafe { &mut *(Arc::as_ptr(&instance) as *mut AudioUnitInstance) };
+            let mut reconfigure = |channels: u32| {
+                if inst.channels() == channels
+                    && inst.render_resources_allocated.load(Ordering::Acquire)
+                {
+                    return Ok(());
+                }
+                inst.configure(sample_rate, 1024, channels)
+            };
+
+            if bus_channels <= 1 {
+                if inst.supports_channels(1) && reconfigure(1).is_ok() {
+                    return (PluginLayout::Native, Vec::new());
+                }
+                if let Err(e) = reconfigure(2) {
+                    log_error!("[AudioUnit] {}: failed to restore stereo: {}", id, e);
+                }
+                return (PluginLayout::Bypassed, Vec::new());
+            }
+            if let Err(e) = reconfigure(2) {
+                log_error!("[AudioUnit] {}: failed to configure stereo: {}", id, e);
+                return (PluginLayout::Bypassed, Vec::new());
+            }
+            if extra_pairs == 0 {
+                return (PluginLayout::Native, Vec::new());
+            }
+
+            // 既存のペアは使い回し、足りない分だけ作る
+            let state = inst.get_full_state();
+            let mut pairs: Vec<_> = existing.iter().take(extra_pairs).cloned().collect();
+            while pairs.len() < extra_pairs {
+                let pair_id = format!("{}#{}", id, pairs.len() + 1);
+                let created = AudioUnitInstance::new(&inst.info, pair_id).and_then(|mut pair| {
+                    pair.configure(sample_rate, 1024, 2)?;
+                    if let Some(data) = &state {
+                        pair.set_full_state(data);
+                    }
+                    pair.enabled
+                        .store(inst.enabled.load(Ordering::Relaxed), Ordering::Release);
+                    Ok(pair)
+                });
+                match created {
+                    Ok(pair) => pairs.push(Arc::new(pair)),
+                    Err(e) => {
+                        log_error!("[AudioUnit] {}: failed to create pair instance: {}", id, e);
+                        return (PluginLayout::Bypassed, Vec::new());
+                    }
                 }
             }
+            (PluginLayout::StereoPairs, pairs)
+        });
+
+        if pairs.is_empty() {
+            self.pairs.write().remove(instance_id);
+        } else {
+            self.pairs.write().insert(instance_id.to_string(), pairs);
         }
+        log_info!(
+            "[AudioUnit] negotiate_layout -> {} on {}ch bus: {}",
+            instance_id,
+            bus_channels,
+            layout.as_str()
+        );
+        layout
     }
 
     /// Process audio through a chain of plugins (by instance IDs)
@@ -1796,54 +2529,140 @@ impl AudioUnitManager {
 
     /// Collect current fullState data for all instances
     /// Returns a map from instance_id -> Option<Vec<u8>> (None if no state)
-    pub fn collect_all_instance_states(
+    ///
+    /// 取得は専用スレッド（`capture_states`）に任せ、1 プラグインごとに
+    /// `STATE_CAPTURE_TIMEOUT` まで待つ。間に合わなかったプラグインは最後に取得できた
+    /// 状態を使う。`instances` のロックは一覧のコピーにしか使わない
+    /// （getter の間ロックを持つと、書き込み待ちを挟んでオーディオスレッドの read が詰まる）。
+    pub fn collect_all_instance_states(&self) -> HashMap<String, Option<Vec<u8>>> {
+        let instances: Vec<Arc<AudioUnitInstance>> =
+            self.instances.read().values().cloned().collect();
+        capture_states(instances)
+    }
+
+    /// Parameters of an instance (runs on the main thread)
+    pub fn get_parameters(&self, instance_id: &str) -> Option<Vec<AudioUnitParameter>> {
+        let instance = self.get_instance(instance_id)?;
+        Some(run_on_main_thread(move || instance.parameters()))
+    }
+
+    /// Set one parameter of an instance (runs on the main thread)
+    pub fn set_parameter(
         &self,
-    ) -> std::collections::HashMap<String, Option<Vec<u8>>> {
-        use std::sync::{Arc, Mutex};
+        instance_id: &str,
+        address: u64,
+        value: f32,
+    ) -> Result<AudioUnitParameter, String> {
+        let instance = self
+            .get_instance(instance_id)
+            .ok_or_else(|| format!("Plugin instance not found: {}", instance_id))?;
+        let pairs = self.pair_instances(instance_id);
+        run_on_main_thread(move || {
+            for pair in &pairs {
+                let _ = pair.set_parameter(address, value);
+            }
+            instance.set_parameter(address, value)
+        })
+    }
 
-        // Result container shared between threads
-        let result: Arc<Mutex<std::collections::HashMap<String, Option<Vec<u8>>>>> =
-            Arc::new(Mutex::new(std::collections::HashMap::new()));
-        let result_for_block = Arc::clone(&result);
-        let result_for_fallback = Arc::clone(&result);
+    /// Re-read the latency of every live instance (on the main thread)
+    ///
+    /// プラグイン自身の UI で変わったレイテンシ（ルックアヘッドの切り替えなど）は
+    /// 通知されないので、監視スレッドから定期的に呼ぶ。Returns whether any changed.
+    pub fn refresh_latencies(&self) -> bool {
+        let instances: Vec<Arc<AudioUnitInstance>> = self
+            .instances
+            .read()
+            .values()
+            .filter(|inst| !inst.is_crashed())
+            .cloned()
+            .collect();
+        if instances.is_empty() {
+            return false;
+        }
+        run_on_main_thread(move || {
+            instances
+                .iter()
+                .fold(false, |changed, inst| inst.refresh_latency() | changed)
+        })
+    }
 
-        // We need to call Objective-C APIs (`-fullState` / NSPropertyListSerialization)
-        // on the main thread. Use dispatch_sync to synchronously run a block on the
-        // main queue and populate the result map there.
+    /// IDs of instances whose plugin process died
+    pub fn crashed_instances(&self) -> Vec<String> {
+        self.instances
+            .read()
+            .iter()
+            .filter(|(_, inst)| inst.is_crashed())
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
 
-        let mgr_ptr = self as *const AudioUnitManager as *mut AudioUnitManager;
-        let block = RcBlock::new(move || {
-            let mgr = unsafe { &*mgr_ptr };
-            let instances = mgr.instances.read();
-            for (id, inst) in instances.iter() {
-                let state = inst.get_full_state();
-                result_for_block.lock().unwrap().insert(id.clone(), state);
-            }
-        });
+    /// IDs of instances bypassed for slow renders (the instance or any of its pairs)
+    pub fn overloaded_instances(&self) -> Vec<String> {
+        let pairs = self.pairs.read();
+        self.instances
+            .read()
+            .iter()
+            .filter(|(id, inst)| {
+                inst.is_overloaded()
+                    || pairs
+                        .get(id.as_str())
+                        .is_some_and(|p| p.iter().any(|i| i.is_overloaded()))
+            })
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
 
-        // Try to resolve dispatch symbols dynamically and call dispatch_sync on main queue.
-        if let Some((dispatch_get_main_queue_fn, dispatch_sync_fn)) = resolve_dispatch_symbols() {
-            unsafe {
-                let q = dispatch_get_main_queue_fn();
-                dispatch_sync_fn(q, &*block as *const _ as *mut c_void);
-            }
-        } else {
-            // Fallback: attempt to run the block on the current run loop briefly
-            // This is less reliable but better than nothing; populate from current thread.
-            let mgr = unsafe { &*mgr_ptr };
-            let instances = mgr.instances.read();
-            for (id, inst) in instances.iter() {
-                let state = inst.get_full_state();
-                result_for_fallback
-                    .lock()
-                    .unwrap()
-                    .insert(id.clone(), state);
-            }
+    /// Bypass every pair of an overloaded instance so the channels stay in step
+    ///
+    /// ペアの一部だけが止まるとチャンネル間で音が食い違うため、全体をまとめてバイパスする。
+    pub fn bypass_overloaded(&self, instance_id: &str) {
+        if let Some(instance) = self.get_instance(instance_id) {
+            instance.render_guard.trip();
+        }
+        for pair in self.pair_instances(instance_id) {
+            pair.render_guard.trip();
+        }
+    }
+
+    /// Replace a (crashed or overloaded) instance with a fresh one under the same ID
+    ///
+    /// 最後に取得できた fullState と有効/無効状態を引き継ぐ。バスは同じ instance_id で
+    /// 参照しているので、呼び出し側で `refresh_au_instance` すれば処理が再開する。
+    /// ペアのインスタンスは作り直さず、過負荷の判定だけを解除する。
+    /// NOTE: Called from main thread only, never from audio thread
+    pub fn restart_instance(&self, instance_id: &str) -> Result<(), String> {
+        let old = self
+            .get_instance(instance_id)
+            .ok_or_else(|| format!("Plugin instance not found: {}", instance_id))?;
+        let state = old.last_known_state();
+
+        let mut instance = AudioUnitInstance::new(&old.info, instance_id.to_string())?;
+        instance.configure(crate::audio::engine_sample_rate(), 1024, old.channels())?;
+        instance
+            .enabled
+            .store(old.enabled.load(Ordering::Relaxed), Ordering::Release);
+
+        // 古い AU を参照している UI は閉じる
+        crate::audio_unit_ui::cleanup_cached_view_controller(instance_id);
+        self.instances
+            .write()
+            .insert(instance_id.to_string(), Arc::new(instance));
+        drop(old);
+        for pair in self.pair_instances(instance_id) {
+            pair.render_guard.reset();
         }
 
-        // Return cloned map
-        let map = result.lock().unwrap().clone();
-        map
+        if let Some(data) = state {
+            if !self.set_instance_full_state(instance_id, &data) {
+                log_error!(
+                    "[AudioUnit] restart_instance -> {}: failed to restore state",
+                    instance_id
+                );
+            }
+        }
+        log_info!("[AudioUnit] restart_instance -> {}", instance_id);
+        Ok(())
     }
 
     /// Set fullState data for a specific instance.
@@ -1887,10 +2706,137 @@ impl AudioUnitManager {
         }
 
         let ok = *result.lock().unwrap();
+        if ok {
+            // ペアのインスタンスも同じ状態にそろえる
+            for pair in self.pair_instances(instance_id) {
+                let bytes = Arc::clone(&bytes);
+                run_on_main_thread(move || {
+                    // SAFETY: main thread, same contract as above
+                    let pair = Arc::as_ptr(&pair) as *mut AudioUnitInstance;
+                    unsafe { (*pair).set_full_state(bytes.as_slice()) }
+                });
+            }
+        }
         ok
     }
 }
 
+// =============================================================================
+// Plugin state capture
+// =============================================================================
+
+/// How long one plugin's fullState getter may take before its last known state is used
+const STATE_CAPTURE_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// One request to the capture thread
+struct StateCaptureJob {
+    instances: Vec<Arc<AudioUnitInstance>>,
+    reply: std::sync::mpsc::Sender<HashMap<String, Option<Vec<u8>>>>,
+}
+
+/// Capture thread (started on first use); requests run one at a time
+static STATE_CAPTURE: LazyLock<Mutex<std::sync::mpsc::Sender<StateCaptureJob>>> =
+    LazyLock::new(|| {
+        let (tx, rx) = std::sync::mpsc::channel::<StateCaptureJob>();
+        let spawned = std::thread::Builder::new()
+            .name("spectrum-plugin-state".to_string())
+            .spawn(move || {
+                for job in rx {
+                    let states = job
+                        .instances
+                        .iter()
+                        .map(|inst| (inst.instance_id.clone(), capture_state(inst)))
+                        .collect();
+                    let _ = job.reply.send(states);
+                }
+            });
+        if let Err(e) = spawned {
+            log_error!("[AudioUnit] Failed to start state capture thread: {}", e);
+        }
+        Mutex::new(tx)
+    });
+
+/// fullState of every instance, read on the main thread via the capture thread
+///
+/// メインスレッドから呼ばれた場合は（キューに積むと自分を待つことになるので）その場で読む。
+fn capture_states(instances: Vec<Arc<AudioUnitInstance>>) -> HashMap<String, Option<Vec<u8>>> {
+    let is_main_thread: bool = unsafe { msg_send![class!(NSThread), isMainThread] };
+    if is_main_thread {
+        return instances
+            .iter()
+            .map(|inst| {
+                let state = if inst.is_crashed() {
+                    None
+                } else {
+                    inst.get_full_state()
+                };
+                (
+                    inst.instance_id.clone(),
+                    state.or_else(|| inst.last_known_state()),
+                )
+            })
+            .collect();
+    }
+
+    let cached = |instances: &[Arc<AudioUnitInstance>]| -> HashMap<String, Option<Vec<u8>>> {
+        instances
+            .iter()
+            .map(|inst| (inst.instance_id.clone(), inst.last_known_state()))
+            .collect()
+    };
+    let (reply, rx) = std::sync::mpsc::channel();
+    let job = StateCaptureJob {
+        instances: instances.clone(),
+        reply,
+    };
+    if STATE_CAPTURE.lock().unwrap().send(job).is_err() {
+        log_warn!("[AudioUnit] State capture thread is not running, using cached states");
+        return cached(&instances);
+    }
+    // 各プラグインがタイムアウトしても返るので、ここで待つのは最悪 n × タイムアウト
+    let deadline = STATE_CAPTURE_TIMEOUT * (instances.len() as u32 + 1);
+    rx.recv_timeout(deadline).unwrap_or_else(|_| {
+        log_warn!("[AudioUnit] State capture timed out, using cached states");
+        cached(&instances)
+    })
+}
+
+/// Read one instance's fullState on the main thread, waiting at most [`STATE_CAPTURE_TIMEOUT`]
+///
+/// 遅い getter は待たずに最後に取得できた状態を返す。前回の読み出しがまだ返っていない
+/// インスタンスには積み増さない（返ってきた時点で `last_state` が更新される）。
+fn capture_state(instance: &Arc<AudioUnitInstance>) -> Option<Vec<u8>> {
+    if instance.is_crashed() || instance.state_capture_pending.swap(true, Ordering::AcqRel) {
+        return instance.last_known_state();
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let inst = Arc::clone(instance);
+    let block = RcBlock::new(move || {
+        let state = inst.get_full_state();
+        inst.state_capture_pending.store(false, Ordering::Release);
+        let _ = tx.send(state);
+    });
+    unsafe {
+        let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
+        let _: () = msg_send![main_queue, addOperationWithBlock: &*block];
+    }
+
+    let started = Instant::now();
+    match rx.recv_timeout(STATE_CAPTURE_TIMEOUT) {
+        Ok(state) => state.or_else(|| instance.last_known_state()),
+        Err(_) => {
+            log_warn!(
+                "[AudioUnit] fullState of {} ({}) took over {} ms, using last known state",
+                instance.info.name,
+                instance.instance_id,
+                started.elapsed().as_millis()
+            );
+            instance.last_known_state()
+        }
+    }
+}
+
 // Global AudioUnit manager
 lazy_static::lazy_static! {
     pub static ref AU_MANAGER: AudioUnitManager = AudioUnitManager::new();
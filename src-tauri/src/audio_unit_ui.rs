@@ -3,6 +3,12 @@
 //! This module creates native Cocoa windows to display AudioUnit custom views.
 //! Supports both AUv2 (CocoaUI) and AUv3 (requestViewController) plugins.
 //!
+//! Views can also be docked into the main window instead (see [`open_audio_unit_ui_embedded`]).
+//!
+//! Keyboard focus: clicks make the plugin window key (or the docked view first responder)
+//! before AppKit dispatches them, and auxiliary windows opened by plugins (file dialogs,
+//! alerts) are kept above the floating plugin windows until they close.
+//!
 //! Note: NSWindow is not thread-safe, so we store window numbers (i64) for global
 //! bookkeeping, and keep any strong NSWindow references only on the main thread.
 //! We can retrieve the window using [NSApp windowWithWindowNumber:].
@@ -16,7 +22,7 @@ use objc2_foundation::{MainThreadMarker, NSPoint, NSRect, NSSize, NSString};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::ffi::c_void;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, RwLock};
 
 // CoreFoundation RunLoop functions
@@ -106,8 +112,39 @@ lazy_static::lazy_static! {
 // NSWindow is not Send/Sync; we keep a strong reference on the main thread only.
 thread_local! {
     static OPEN_PLUGIN_WINDOWS: RefCell<HashMap<String, Retained<NSWindow>>> = RefCell::new(HashMap::new());
+    // Container views docked in the main window (owned, released on close)
+    static EMBEDDED_PLUGIN_VIEWS: RefCell<HashMap<String, SendSyncPtr>> = RefCell::new(HashMap::new());
+    static FOCUS_STATE: RefCell<FocusState> = RefCell::new(FocusState::default());
+}
+
+/// Keyboard focus bookkeeping (main thread)
+#[derive(Default)]
+struct FocusState {
+    /// Instance whose window was key most recently (gets the keyboard back after a dialog)
+    last_key_plugin: Option<String>,
+    /// Window numbers of open auxiliary windows (dialogs opened from plugin UIs)
+    aux_windows: Vec<isize>,
 }
 
+/// Main (webview) window, to tell it apart from auxiliary windows
+static HOST_WINDOW: AtomicUsize = AtomicUsize::new(0);
+static FOCUS_TRACKING_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// `identifier` of our plugin panels
+const PLUGIN_WINDOW_IDENTIFIER: &str = "spectrum.plugin-window";
+
+// NSAutoresizingMaskOptions
+const NS_VIEW_WIDTH_SIZABLE: u64 = 1 << 1;
+const NS_VIEW_MIN_Y_MARGIN: u64 = 1 << 3;
+const NS_VIEW_HEIGHT_SIZABLE: u64 = 1 << 4;
+// NSWindowOrderingMode
+const NS_WINDOW_ABOVE: isize = 1;
+// NSWindowLevel
+const NS_NORMAL_WINDOW_LEVEL: isize = 0;
+const NS_FLOATING_WINDOW_LEVEL: isize = 3;
+// NSEventMask: left / right / other mouse down
+const MOUSE_DOWN_EVENT_MASK: u64 = (1 << 1) | (1 << 3) | (1 << 25);
+
 fn activate_app_and_focus_plugin_window(window: &NSWindow, mtm: MainThreadMarker, reason: &str) {
     unsafe {
         let app = NSApplication::sharedApplication(mtm);
@@ -132,9 +169,13 @@ fn activate_app_and_focus_plugin_window(window: &NSWindow, mtm: MainThreadMarker
         let _: () = msg_send![window, setHidesOnDeactivate: false];
 
         // User-requested: keep plugin windows floating.
-        // NOTE: NSFloatingWindowLevel is 3.
-        let level: isize = 3; // NSFloatingWindowLevel
-        window.setLevel(level);
+        // (Except while a plugin's dialog is open; see on_window_became_key.)
+        let aux_open = FOCUS_STATE.with(|state| !state.borrow().aux_windows.is_empty());
+        window.setLevel(if aux_open {
+            NS_NORMAL_WINDOW_LEVEL
+        } else {
+            NS_FLOATING_WINDOW_LEVEL
+        });
 
         // Bring to front and make key/main.
         window.makeKeyAndOrderFront(None);
@@ -155,7 +196,7 @@ fn activate_app_and_focus_plugin_window(window: &NSWindow, mtm: MainThreadMarker
         let is_main: bool = msg_send![window, isMainWindow];
         let is_visible: bool = msg_send![window, isVisible];
         let win_level: isize = msg_send![window, level];
-        println!(
+        log_info!(
             "[AudioUnitUI] focus_window reason={} app_active={} key_window_ptr={:p} this_window_ptr={:p} key={} main={} visible={} level={}",
             reason,
             app_is_active,
@@ -554,7 +595,7 @@ fn defer_release_view_controller(instance_id: &str, vc: *mut AnyObject) {
     unsafe {
         let main_queue: *mut AnyObject = msg_send![class!(NSOperationQueue), mainQueue];
         let block = RcBlock::new(move || {
-            println!(
+            log_info!(
                 "[AudioUnit] Releasing cached view controller for {}",
                 instance_id
             );
@@ -570,17 +611,27 @@ fn defer_release_view_controller(instance_id: &str, vc: *mut AnyObject) {
 /// 1. Uses the existing AUAudioUnit instance to get the view controller
 /// 2. Creates an NSWindow
 /// 3. Embeds the AudioUnit's custom view in the window
+///
+/// Returns false (without opening a window) when the plugin has no custom view;
+/// the frontend then shows a generic parameter UI instead.
 pub fn open_audio_unit_ui(
     instance_id: &str,
     au_audio_unit: *mut AnyObject,
     plugin_name: &str,
-) -> Result<(), String> {
+) -> Result<bool, String> {
     // Must run on main thread for UI operations
     let mtm = match MainThreadMarker::new() {
         Some(m) => m,
         None => return Err("Must be called from main thread".to_string()),
     };
 
+    install_focus_tracking();
+
+    // ドッキング中なら外してから別ウィンドウで開く（ビューの親は 1 つだけ）
+    if is_plugin_ui_embedded(instance_id) {
+        close_audio_unit_ui(instance_id);
+    }
+
     // Check if window is already open
     // 1) まず read ロックで現在のウィンドウ番号だけ読み取り、すぐにロックを解放する
     // 2) 既存ウィンドウが存在しない場合だけ write ロックを取ってマップを更新する
@@ -593,7 +644,7 @@ pub fn open_audio_unit_ui(
         // Try to bring existing window to front
         if let Some(window) = get_window_by_number(window_number, mtm) {
             activate_app_and_focus_plugin_window(&window, mtm, "reuse");
-            return Ok(());
+            return Ok(true);
         } else {
             // Window was closed externally, remove from tracking
             let mut map = PLUGIN_WINDOW_NUMBERS.write().unwrap();
@@ -620,62 +671,64 @@ pub fn open_audio_unit_ui(
     // Get AudioUnit's view using existing AUAudioUnit instance
     let view = get_audio_unit_view(instance_id, au_audio_unit)?;
 
-    // Determine window size based on the view's size
-    let (window_width, window_height) = if let Some(au_view) = view {
-        unsafe {
-            let mut width = 0.0;
-            let mut height = 0.0;
+    // カスタム UI がなければウィンドウは開かず、汎用 UI（パラメーター一覧）に任せる
+    let Some(au_view) = view else {
+        log_info!("[AudioUnitUI] {} has no custom view", instance_id);
+        return Ok(false);
+    };
 
-            if let Some(SendSyncPtr(vc)) = CACHED_VIEW_CONTROLLERS
-                .read()
-                .unwrap()
-                .get(instance_id)
-                .copied()
-            {
-                if !vc.is_null() {
-                    let preferred: NSSize = msg_send![vc, preferredContentSize];
-                    if preferred.width > 10.0 && preferred.height > 10.0 {
-                        width = preferred.width;
-                        height = preferred.height;
-                    }
-                }
-            }
+    // Determine window size based on the view's size
+    let (window_width, window_height) = unsafe {
+        let mut width = 0.0;
+        let mut height = 0.0;
 
-            if width <= 10.0 || height <= 10.0 {
-                if let Some((iw, ih)) = view_intrinsic_size(au_view) {
-                    width = iw;
-                    height = ih;
+        if let Some(SendSyncPtr(vc)) = CACHED_VIEW_CONTROLLERS
+            .read()
+            .unwrap()
+            .get(instance_id)
+            .copied()
+        {
+            if !vc.is_null() {
+                let preferred: NSSize = msg_send![vc, preferredContentSize];
+                if preferred.width > 10.0 && preferred.height > 10.0 {
+                    width = preferred.width;
+                    height = preferred.height;
                 }
             }
+        }
 
-            if width <= 10.0 || height <= 10.0 {
-                let fitting: NSSize = msg_send![au_view, fittingSize];
-                if fitting.width > 10.0 && fitting.height > 10.0 {
-                    width = fitting.width;
-                    height = fitting.height;
-                }
+        if width <= 10.0 || height <= 10.0 {
+            if let Some((iw, ih)) = view_intrinsic_size(au_view) {
+                width = iw;
+                height = ih;
             }
+        }
 
-            if width <= 10.0 || height <= 10.0 {
-                let frame: NSRect = msg_send![au_view, frame];
-                if frame.size.width > 10.0 && frame.size.height > 10.0 {
-                    width = frame.size.width;
-                    height = frame.size.height;
-                }
+        if width <= 10.0 || height <= 10.0 {
+            let fitting: NSSize = msg_send![au_view, fittingSize];
+            if fitting.width > 10.0 && fitting.height > 10.0 {
+                width = fitting.width;
+                height = fitting.height;
             }
+        }
 
-            if width <= 10.0 {
-                width = 600.0;
-            }
-            if height <= 10.0 {
-                height = 400.0;
+        if width <= 10.0 || height <= 10.0 {
+            let frame: NSRect = msg_send![au_view, frame];
+            if frame.size.width > 10.0 && frame.size.height > 10.0 {
+                width = frame.size.width;
+                height = frame.size.height;
             }
+        }
 
-            // Clamp to reasonable sizes (min 200x100, max 2000x1500)
-            (width.max(200.0).min(2000.0), height.max(100.0).min(1500.0))
+        if width <= 10.0 {
+            width = 600.0;
         }
-    } else {
-        (600.0, 400.0) // Default size for placeholder
+        if height <= 10.0 {
+            height = 400.0;
+        }
+
+        // Clamp to reasonable sizes (min 200x100, max 2000x1500)
+        (width.max(200.0).min(2000.0), height.max(100.0).min(1500.0))
     };
 
     // Create window with the determined size
@@ -715,6 +768,10 @@ pub fn open_audio_unit_ui(
         // is dropped.
         let _: () = msg_send![&*window, setReleasedWhenClosed: false];
 
+        // 補助ウィンドウと区別するための印（install_focus_tracking）
+        let identifier = NSString::from_str(PLUGIN_WINDOW_IDENTIFIER);
+        let _: () = msg_send![&*window, setIdentifier: &*identifier];
+
         // =============================
         // 【重要修正 2】透過計算の無効化
         // =============================
@@ -751,104 +808,90 @@ pub fn open_audio_unit_ui(
         // =============================
         // 【重要修正 3 & 4】GPU描画対応とAuto Layout
         // =============================
-        if let Some(au_view) = view {
-            // コンテナ(ContentView)を取得してGPUレイヤーを有効化
-            let container_view: *mut AnyObject = msg_send![&*window, contentView];
-            let _: () = msg_send![container_view, setWantsLayer: true];
-
-            // プラグインViewもGPUレイヤーを有効化
-            let _: () = msg_send![au_view, setWantsLayer: true];
-
-            // Auto Layout用に古いリサイズ設定をOFF
-            let _: () = msg_send![au_view, setTranslatesAutoresizingMaskIntoConstraints: false];
-
-            // コンテナに追加（setContentViewで上書きしない！）
-            let _: () = msg_send![container_view, addSubview: au_view];
-
-            // --- 制約(Anchor)で上下左右を貼り付け ---
-            // Debug logging for diagnosis (e.g. ProQ4)
-            let mask_dbg: u64 = msg_send![au_view, autoresizingMask];
-            let intrinsic_dbg: NSSize = msg_send![au_view, intrinsicContentSize];
-            let fit_dbg: NSSize = msg_send![au_view, fittingSize];
-            let frame_dbg: NSRect = msg_send![au_view, frame];
-            println!(
-                "[AudioUnitUI] {} resizable={} mask=0x{:x} intrinsic=({:.1},{:.1}) fitting=({:.1},{:.1}) frame=({:.1},{:.1})",
-                instance_id,
-                false,
-                mask_dbg,
-                intrinsic_dbg.width,
-                intrinsic_dbg.height,
-                fit_dbg.width,
-                fit_dbg.height,
-                frame_dbg.size.width,
-                frame_dbg.size.height
-            );
-
-            let leading_anchor: *mut AnyObject = msg_send![au_view, leadingAnchor];
-            let trailing_anchor: *mut AnyObject = msg_send![au_view, trailingAnchor];
-            let top_anchor: *mut AnyObject = msg_send![au_view, topAnchor];
-            let bottom_anchor: *mut AnyObject = msg_send![au_view, bottomAnchor];
+        // コンテナ(ContentView)を取得してGPUレイヤーを有効化
+        let container_view: *mut AnyObject = msg_send![&*window, contentView];
+        let _: () = msg_send![container_view, setWantsLayer: true];
+
+        // プラグインViewもGPUレイヤーを有効化
+        let _: () = msg_send![au_view, setWantsLayer: true];
+
+        // Auto Layout用に古いリサイズ設定をOFF
+        let _: () = msg_send![au_view, setTranslatesAutoresizingMaskIntoConstraints: false];
+
+        // コンテナに追加（setContentViewで上書きしない！）
+        let _: () = msg_send![container_view, addSubview: au_view];
+
+        // --- 制約(Anchor)で上下左右を貼り付け ---
+        // Debug logging for diagnosis (e.g. ProQ4)
+        let mask_dbg: u64 = msg_send![au_view, autoresizingMask];
+        let intrinsic_dbg: NSSize = msg_send![au_view, intrinsicContentSize];
+        let fit_dbg: NSSize = msg_send![au_view, fittingSize];
+        let frame_dbg: NSRect = msg_send![au_view, frame];
+        log_info!(
+            "[AudioUnitUI] {} resizable={} mask=0x{:x} intrinsic=({:.1},{:.1}) fitting=({:.1},{:.1}) frame=({:.1},{:.1})",
+            instance_id,
+            false,
+            mask_dbg,
+            intrinsic_dbg.width,
+            intrinsic_dbg.height,
+            fit_dbg.width,
+            fit_dbg.height,
+            frame_dbg.size.width,
+            frame_dbg.size.height
+        );
 
-            let c_leading: *mut AnyObject = msg_send![container_view, leadingAnchor];
-            let c_trailing: *mut AnyObject = msg_send![container_view, trailingAnchor];
-            let c_top: *mut AnyObject = msg_send![container_view, topAnchor];
-            let c_bottom: *mut AnyObject = msg_send![container_view, bottomAnchor];
+        let leading_anchor: *mut AnyObject = msg_send![au_view, leadingAnchor];
+        let trailing_anchor: *mut AnyObject = msg_send![au_view, trailingAnchor];
+        let top_anchor: *mut AnyObject = msg_send![au_view, topAnchor];
+        let bottom_anchor: *mut AnyObject = msg_send![au_view, bottomAnchor];
 
-            // 左上はガチガチに固定、右下は同様に貼るが
-            // プラグインの内部抵抗力は is_resizable によって変える
-            let c1: *mut AnyObject = msg_send![leading_anchor, constraintEqualToAnchor: c_leading];
-            let _: () = msg_send![c1, setActive: true];
+        let c_leading: *mut AnyObject = msg_send![container_view, leadingAnchor];
+        let c_trailing: *mut AnyObject = msg_send![container_view, trailingAnchor];
+        let c_top: *mut AnyObject = msg_send![container_view, topAnchor];
+        let c_bottom: *mut AnyObject = msg_send![container_view, bottomAnchor];
 
-            let c2: *mut AnyObject =
-                msg_send![trailing_anchor, constraintEqualToAnchor: c_trailing];
-            let _: () = msg_send![c2, setActive: true];
+        // 左上はガチガチに固定、右下は同様に貼るが
+        // プラグインの内部抵抗力は is_resizable によって変える
+        let c1: *mut AnyObject = msg_send![leading_anchor, constraintEqualToAnchor: c_leading];
+        let _: () = msg_send![c1, setActive: true];
 
-            let c3: *mut AnyObject = msg_send![top_anchor, constraintEqualToAnchor: c_top];
-            let _: () = msg_send![c3, setActive: true];
+        let c2: *mut AnyObject = msg_send![trailing_anchor, constraintEqualToAnchor: c_trailing];
+        let _: () = msg_send![c2, setActive: true];
 
-            let c4: *mut AnyObject = msg_send![bottom_anchor, constraintEqualToAnchor: c_bottom];
-            let _: () = msg_send![c4, setActive: true];
+        let c3: *mut AnyObject = msg_send![top_anchor, constraintEqualToAnchor: c_top];
+        let _: () = msg_send![c3, setActive: true];
 
-            // Always use strong resistance; window is fixed to plugin size.
-            let priority: f32 = 1000.0;
-            let orient_h: isize = 0; // Horizontal
-            let orient_v: isize = 1; // Vertical
+        let c4: *mut AnyObject = msg_send![bottom_anchor, constraintEqualToAnchor: c_bottom];
+        let _: () = msg_send![c4, setActive: true];
 
-            let _: () =
-                msg_send![au_view, setContentHuggingPriority: priority, forOrientation: orient_h];
-            let _: () =
-                msg_send![au_view, setContentHuggingPriority: priority, forOrientation: orient_v];
+        // Always use strong resistance; window is fixed to plugin size.
+        let priority: f32 = 1000.0;
+        let orient_h: isize = 0; // Horizontal
+        let orient_v: isize = 1; // Vertical
 
-            let _: () = msg_send![au_view, setContentCompressionResistancePriority: priority, forOrientation: orient_h];
-            let _: () = msg_send![au_view, setContentCompressionResistancePriority: priority, forOrientation: orient_v];
+        let _: () =
+            msg_send![au_view, setContentHuggingPriority: priority, forOrientation: orient_h];
+        let _: () =
+            msg_send![au_view, setContentHuggingPriority: priority, forOrientation: orient_v];
 
-            // Layer設定の再確保
-            let _: () = msg_send![au_view, setWantsLayer: true];
+        let _: () = msg_send![au_view, setContentCompressionResistancePriority: priority, forOrientation: orient_h];
+        let _: () = msg_send![au_view, setContentCompressionResistancePriority: priority, forOrientation: orient_v];
 
-            // Best-effort initial layout after constraints.
-            let _: () = msg_send![container_view, layoutSubtreeIfNeeded];
+        // Layer設定の再確保
+        let _: () = msg_send![au_view, setWantsLayer: true];
 
-            // Ensure responder chain starts at the plugin view.
-            let _: bool = msg_send![&*window, makeFirstResponder: au_view];
+        // Best-effort initial layout after constraints.
+        let _: () = msg_send![container_view, layoutSubtreeIfNeeded];
 
-            // Lock window to plugin preferred size and keep following it.
-            sync_fixed_window_to_view(&window, instance_id, au_view);
-            install_view_size_observer(instance_id, window.windowNumber(), au_view);
+        // Ensure responder chain starts at the plugin view.
+        let _: bool = msg_send![&*window, makeFirstResponder: au_view];
 
-            // Now show the window after setup.
-            activate_app_and_focus_plugin_window(&window, mtm, "open_with_view");
-        } else {
-            // カスタムUIがない場合はプレースホルダー
-            let label_text = format!("No custom UI available for {}", plugin_name);
-            create_placeholder_view(&window, &label_text, mtm);
-
-            // Keep placeholder non-resizable too.
-            set_window_resizable(&window, false);
-            set_window_fixed_content_size(&window, window_width, window_height);
+        // Lock window to plugin preferred size and keep following it.
+        sync_fixed_window_to_view(&window, instance_id, au_view);
+        install_view_size_observer(instance_id, window.windowNumber(), au_view);
 
-            // Show window (placeholder)
-            activate_app_and_focus_plugin_window(&window, mtm, "open_placeholder");
-        }
+        // Now show the window after setup.
+        activate_app_and_focus_plugin_window(&window, mtm, "open_with_view");
     }
 
     // Store window number (not the window itself, as it's not Sync)
@@ -869,18 +912,380 @@ pub fn open_audio_unit_ui(
             .insert(instance_id.to_string(), retained);
     });
 
-    Ok(())
+    Ok(true)
+}
+
+/// Embed an AudioUnit's custom view into the main window
+///
+/// The view is placed in a container above the webview, at `x`/`y`/`width`/`height`
+/// points from the top-left of the window's content view (CSS pixels at 100% zoom).
+/// Calling again for a docked instance only moves/resizes it; a floating window of
+/// the instance is closed first. Close with [`close_audio_unit_ui`].
+///
+/// Returns false (nothing docked) when the plugin has no custom view.
+/// Must be called from main thread.
+pub fn open_audio_unit_ui_embedded(
+    instance_id: &str,
+    au_audio_unit: *mut AnyObject,
+    ns_window: *mut AnyObject,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<bool, String> {
+    if MainThreadMarker::new().is_none() {
+        return Err("Must be called from main thread".to_string());
+    }
+    install_focus_tracking();
+    if ns_window.is_null() {
+        return Err("Main window is not available".to_string());
+    }
+    if !(width > 0.0 && height > 0.0 && x.is_finite() && y.is_finite()) {
+        return Err(format!(
+            "Invalid embed frame: {}x{} at ({}, {})",
+            width, height, x, y
+        ));
+    }
+
+    let content_view: *mut AnyObject = unsafe { msg_send![ns_window, contentView] };
+    if content_view.is_null() {
+        return Err("Main window has no content view".to_string());
+    }
+    let (frame, flipped) = unsafe { embedded_frame(content_view, x, y, width, height) };
+
+    // 既にドッキング済みなら位置とサイズだけ更新
+    let existing = EMBEDDED_PLUGIN_VIEWS.with(|views| views.borrow().get(instance_id).copied());
+    if let Some(SendSyncPtr(container)) = existing {
+        unsafe {
+            let _: () = msg_send![container, setFrame: frame];
+        }
+        return Ok(true);
+    }
+
+    // 別ウィンドウで開いていれば閉じる（ビューの親は 1 つだけ）
+    if PLUGIN_WINDOW_NUMBERS
+        .read()
+        .unwrap()
+        .contains_key(instance_id)
+    {
+        close_audio_unit_ui(instance_id);
+    }
+
+    let Some(au_view) = get_audio_unit_view(instance_id, au_audio_unit)? else {
+        log_info!("[AudioUnitUI] {} has no custom view", instance_id);
+        return Ok(false);
+    };
+
+    let container = unsafe {
+        let container: *mut AnyObject = msg_send![class!(NSView), alloc];
+        let container: *mut AnyObject = msg_send![container, initWithFrame: frame];
+        let _: () = msg_send![container, setWantsLayer: true];
+        // ドッキング枠の外に描画がはみ出さないように
+        let layer: *mut AnyObject = msg_send![container, layer];
+        if !layer.is_null() {
+            let _: () = msg_send![layer, setMasksToBounds: true];
+        }
+        // ウィンドウのリサイズで上端からの位置を保つ（非 flipped のときは下マージンが伸びる）
+        let container_mask: u64 = if flipped { 0 } else { NS_VIEW_MIN_Y_MARGIN };
+        let _: () = msg_send![container, setAutoresizingMask: container_mask];
+
+        // プラグインビューはコンテナいっぱいに（サイズはドッキング側が決める）
+        let bounds: NSRect = msg_send![container, bounds];
+        let _: () = msg_send![au_view, setTranslatesAutoresizingMaskIntoConstraints: true];
+        let _: () = msg_send![au_view, setFrame: bounds];
+        let _: () = msg_send![
+            au_view,
+            setAutoresizingMask: NS_VIEW_WIDTH_SIZABLE | NS_VIEW_HEIGHT_SIZABLE
+        ];
+        let _: () = msg_send![au_view, setWantsLayer: true];
+        let _: () = msg_send![container, addSubview: au_view];
+
+        // Webview より手前に重ねる
+        let _: () = msg_send![
+            content_view,
+            addSubview: container,
+            positioned: NS_WINDOW_ABOVE,
+            relativeTo: std::ptr::null_mut::<AnyObject>()
+        ];
+        container
+    };
+
+    log_info!(
+        "[AudioUnitUI] {} embedded at ({:.0},{:.0}) {:.0}x{:.0}",
+        instance_id,
+        x,
+        y,
+        width,
+        height
+    );
+    EMBEDDED_PLUGIN_VIEWS.with(|views| {
+        views
+            .borrow_mut()
+            .insert(instance_id.to_string(), SendSyncPtr(container))
+    });
+    Ok(true)
+}
+
+/// Top-left based frame -> content view coordinates (and whether the view is flipped)
+unsafe fn embedded_frame(
+    content_view: *mut AnyObject,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> (NSRect, bool) {
+    let flipped: bool = msg_send![content_view, isFlipped];
+    let bounds: NSRect = msg_send![content_view, bounds];
+    let origin_y = if flipped {
+        y
+    } else {
+        bounds.size.height - y - height
+    };
+    (
+        NSRect::new(NSPoint::new(x, origin_y), NSSize::new(width, height)),
+        flipped,
+    )
+}
+
+/// Whether the instance's view is docked in the main window (main thread)
+pub fn is_plugin_ui_embedded(instance_id: &str) -> bool {
+    EMBEDDED_PLUGIN_VIEWS.with(|views| views.borrow().contains_key(instance_id))
 }
 
-/// Close an AudioUnit UI window
+/// Detach and release a docked container (main thread); false if none
+fn remove_embedded_view(instance_id: &str) -> bool {
+    let Some(SendSyncPtr(container)) =
+        EMBEDDED_PLUGIN_VIEWS.with(|views| views.borrow_mut().remove(instance_id))
+    else {
+        return false;
+    };
+    unsafe {
+        let _: () = msg_send![container, removeFromSuperview];
+        // Balanced with alloc in open_audio_unit_ui_embedded
+        let _: () = msg_send![container, release];
+    }
+    true
+}
+
+/// Remember the main window (call once from setup)
+///
+/// Windows that become key while plugin windows are open and are neither a plugin
+/// window nor this one are treated as auxiliary windows of a plugin.
+pub fn set_host_window(ns_window: *mut c_void) {
+    HOST_WINDOW.store(ns_window as usize, Ordering::Relaxed);
+}
+
+/// Install the mouse-down monitor and key-window observers (once, main thread)
+fn install_focus_tracking() {
+    if FOCUS_TRACKING_INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    unsafe {
+        // Local monitor: runs before NSApplication dispatches the event
+        let handler = RcBlock::new(|event: *mut AnyObject| -> *mut AnyObject {
+            if !event.is_null() {
+                route_mouse_down(event);
+            }
+            event
+        });
+        let monitor: *mut AnyObject = msg_send![
+            class!(NSEvent),
+            addLocalMonitorForEventsMatchingMask: MOUSE_DOWN_EVENT_MASK,
+            handler: &*handler
+        ];
+        if !monitor.is_null() {
+            // Kept for the lifetime of the app
+            let _: () = msg_send![monitor, retain];
+        }
+
+        // queue: nil -> delivered synchronously on the posting (main) thread
+        let center: *mut AnyObject = msg_send![class!(NSNotificationCenter), defaultCenter];
+        let became_key = RcBlock::new(|note: *mut AnyObject| {
+            let window: *mut AnyObject = msg_send![note, object];
+            on_window_became_key(window);
+        });
+        let will_close = RcBlock::new(|note: *mut AnyObject| {
+            let window: *mut AnyObject = msg_send![note, object];
+            on_window_will_close(window);
+        });
+        let name_became_key = NSString::from_str("NSWindowDidBecomeKeyNotification");
+        let name_will_close = NSString::from_str("NSWindowWillCloseNotification");
+        let _: *mut AnyObject = msg_send![
+            center,
+            addObserverForName: &*name_became_key,
+            object: std::ptr::null_mut::<AnyObject>(),
+            queue: std::ptr::null_mut::<AnyObject>(),
+            usingBlock: &*became_key
+        ];
+        let _: *mut AnyObject = msg_send![
+            center,
+            addObserverForName: &*name_will_close,
+            object: std::ptr::null_mut::<AnyObject>(),
+            queue: std::ptr::null_mut::<AnyObject>(),
+            usingBlock: &*will_close
+        ];
+    }
+    log_info!("[AudioUnitUI] Focus tracking installed");
+}
+
+fn plugin_instance_for_window(window_number: isize) -> Option<String> {
+    PLUGIN_WINDOW_NUMBERS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|(_, &n)| n == window_number)
+        .map(|(id, _)| id.clone())
+}
+
+unsafe fn is_plugin_window(window: *mut AnyObject) -> bool {
+    let identifier: *mut AnyObject = msg_send![window, identifier];
+    if identifier.is_null() {
+        return false;
+    }
+    let ours = NSString::from_str(PLUGIN_WINDOW_IDENTIFIER);
+    msg_send![identifier, isEqualToString: &*ours]
+}
+
+/// Give the keyboard to the clicked plugin UI before the click is dispatched
+///
+/// キーでないパネルへの最初のクリックはウィンドウのアクティブ化に使われ、
+/// 修飾キー付きドラッグやテキスト入力の開始がプラグインに届かないことがある。
+/// ドッキング中のビューは、クリックしてもキー入力が Webview に残らないようにする。
+unsafe fn route_mouse_down(event: *mut AnyObject) {
+    let window: *mut AnyObject = msg_send![event, window];
+    if window.is_null() {
+        return;
+    }
+
+    if is_plugin_window(window) {
+        let is_key: bool = msg_send![window, isKeyWindow];
+        if !is_key {
+            let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+            let _: () = msg_send![app, activateIgnoringOtherApps: true];
+            let _: () = msg_send![window, makeKeyWindow];
+        }
+        return;
+    }
+
+    let containers: Vec<*mut AnyObject> = EMBEDDED_PLUGIN_VIEWS
+        .with(|views| views.borrow().values().map(|SendSyncPtr(c)| *c).collect());
+    let location: NSPoint = msg_send![event, locationInWindow];
+    for container in containers {
+        let container_window: *mut AnyObject = msg_send![container, window];
+        let superview: *mut AnyObject = msg_send![container, superview];
+        if container_window != window || superview.is_null() {
+            continue;
+        }
+        // hitTest: takes a point in the superview's coordinates
+        let point: NSPoint = msg_send![
+            superview,
+            convertPoint: location,
+            fromView: std::ptr::null_mut::<AnyObject>()
+        ];
+        let mut view: *mut AnyObject = msg_send![container, hitTest: point];
+        if view.is_null() {
+            continue;
+        }
+
+        // クリック位置で一番深い、キー入力を受けるビュー
+        while !view.is_null() && view != container {
+            let accepts: bool = msg_send![view, acceptsFirstResponder];
+            if accepts {
+                let _: bool = msg_send![window, makeFirstResponder: view];
+                return;
+            }
+            view = msg_send![view, superview];
+        }
+        // 受けるビューがなくても Webview からは外す（ショートカットの誤爆防止）
+        let _: bool = msg_send![window, makeFirstResponder: std::ptr::null_mut::<AnyObject>()];
+        return;
+    }
+}
+
+/// Track the key plugin window; push plugin windows down under auxiliary windows
+unsafe fn on_window_became_key(window: *mut AnyObject) {
+    if window.is_null() {
+        return;
+    }
+    let number: isize = msg_send![window, windowNumber];
+    if is_plugin_window(window) {
+        if let Some(instance_id) = plugin_instance_for_window(number) {
+            FOCUS_STATE.with(|state| state.borrow_mut().last_key_plugin = Some(instance_id));
+        }
+        return;
+    }
+    if window as usize == HOST_WINDOW.load(Ordering::Relaxed)
+        || PLUGIN_WINDOW_NUMBERS.read().unwrap().is_empty()
+    {
+        return;
+    }
+
+    // プラグインが開いたダイアログ等: 閉じるまでフローティングのプラグインウィンドウを下げる
+    let first = FOCUS_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.aux_windows.contains(&number) {
+            return false;
+        }
+        state.aux_windows.push(number);
+        state.aux_windows.len() == 1
+    });
+    if first {
+        log_info!("[AudioUnitUI] Auxiliary window {} opened", number);
+        set_plugin_window_levels(NS_NORMAL_WINDOW_LEVEL);
+    }
+    let _: () = msg_send![window, orderFrontRegardless];
+}
+
+/// Restore floating plugin windows and the plugin's keyboard focus after the last dialog
+unsafe fn on_window_will_close(window: *mut AnyObject) {
+    if window.is_null() {
+        return;
+    }
+    let number: isize = msg_send![window, windowNumber];
+    let (last_closed, return_to) = FOCUS_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let before = state.aux_windows.len();
+        state.aux_windows.retain(|&n| n != number);
+        (
+            before != state.aux_windows.len() && state.aux_windows.is_empty(),
+            state.last_key_plugin.clone(),
+        )
+    });
+    if !last_closed {
+        return;
+    }
+
+    set_plugin_window_levels(NS_FLOATING_WINDOW_LEVEL);
+    // ファーストレスポンダーはウィンドウが覚えているので、キーに戻せば入力中のフィールドに戻る
+    let Some(mtm) = MainThreadMarker::new() else {
+        return;
+    };
+    let window_number =
+        return_to.and_then(|id| PLUGIN_WINDOW_NUMBERS.read().unwrap().get(&id).copied());
+    if let Some(plugin_window) = window_number.and_then(|n| get_window_by_number(n, mtm)) {
+        plugin_window.makeKeyAndOrderFront(None);
+    }
+}
+
+fn set_plugin_window_levels(level: isize) {
+    OPEN_PLUGIN_WINDOWS.with(|windows| {
+        for window in windows.borrow().values() {
+            window.setLevel(level);
+        }
+    });
+}
+
+/// Close an AudioUnit UI window (or docked view)
 pub fn close_audio_unit_ui(instance_id: &str) {
     // Remove size observer first (best-effort)
     remove_view_size_observer(instance_id);
 
-    let window_number = match PLUGIN_WINDOW_NUMBERS.write().unwrap().remove(instance_id) {
-        Some(n) => n,
-        None => return,
-    };
+    let embedded = remove_embedded_view(instance_id);
+    let window_number = PLUGIN_WINDOW_NUMBERS.write().unwrap().remove(instance_id);
+    if window_number.is_none() && !embedded {
+        return;
+    }
 
     // Must be on main thread
     let mtm = match MainThreadMarker::new() {
@@ -888,12 +1293,15 @@ pub fn close_audio_unit_ui(instance_id: &str) {
         None => return,
     };
 
-    // Prefer our owned reference if present
-    let owned_window = OPEN_PLUGIN_WINDOWS.with(|windows| windows.borrow_mut().remove(instance_id));
+    if let Some(window_number) = window_number {
+        // Prefer our owned reference if present
+        let owned_window =
+            OPEN_PLUGIN_WINDOWS.with(|windows| windows.borrow_mut().remove(instance_id));
 
-    if let Some(window) = owned_window.or_else(|| get_window_by_number(window_number, mtm)) {
-        window.orderOut(None);
-        window.close();
+        if let Some(window) = owned_window.or_else(|| get_window_by_number(window_number, mtm)) {
+            window.orderOut(None);
+            window.close();
+        }
     }
 
     // We only cache view controllers to keep them alive while the window is open.
@@ -925,13 +1333,14 @@ fn get_audio_unit_view(
     au_audio_unit: *mut AnyObject,
 ) -> Result<Option<*mut AnyObject>, String> {
     if au_audio_unit.is_null() {
-        eprintln!("AUAudioUnit handle is null");
+        log_error!("AUAudioUnit handle is null");
         return Err("AUAudioUnit handle is null".to_string());
     }
 
-    println!(
+    log_info!(
         "Getting AudioUnit view for AUAudioUnit: {:?}, instance: {}",
-        au_audio_unit, instance_id
+        au_audio_unit,
+        instance_id
     );
 
     // Check if we already have a cached view controller for this instance
@@ -942,7 +1351,7 @@ fn get_audio_unit_view(
         .copied()
     {
         if !vc.is_null() {
-            println!("Using cached view controller for {}", instance_id);
+            log_info!("Using cached view controller for {}", instance_id);
             unsafe {
                 let view: *mut AnyObject = msg_send![vc, view];
                 if !view.is_null() {
@@ -959,9 +1368,9 @@ fn get_audio_unit_view(
 
     // Request view controller from existing AUAudioUnit instance
     for attempt in 1..=3 {
-        println!("AUv3 view attempt {}/3", attempt);
+        log_info!("AUv3 view attempt {}/3", attempt);
         if let Some((view, view_controller)) = request_view_controller(au_audio_unit) {
-            println!("Successfully obtained AUv3 view on attempt {}", attempt);
+            log_info!("Successfully obtained AUv3 view on attempt {}", attempt);
             // Cache the view controller to keep it alive
             CACHED_VIEW_CONTROLLERS
                 .write()
@@ -975,7 +1384,7 @@ fn get_audio_unit_view(
         }
     }
 
-    println!("Failed to get AUv3 view after 3 attempts");
+    log_warn!("Failed to get AUv3 view after 3 attempts");
     Ok(None)
 }
 
@@ -985,7 +1394,7 @@ fn request_view_controller(
     au_audio_unit: *mut AnyObject,
 ) -> Option<(*mut AnyObject, *mut AnyObject)> {
     unsafe {
-        println!(
+        log_info!(
             "Requesting view controller from AUAudioUnit: {:?}",
             au_audio_unit
         );
@@ -1015,7 +1424,7 @@ fn request_view_controller(
 
         while !view_done.load(Ordering::SeqCst) {
             if start_time.elapsed() > timeout {
-                println!("Timed out waiting for view controller");
+                log_info!("Timed out waiting for view controller");
                 return None;
             }
             CFRunLoopRunInMode(mode, 0.01, false);
@@ -1024,23 +1433,23 @@ fn request_view_controller(
         let view_controller = view_result.lock().unwrap().take();
 
         if view_controller.is_none() {
-            println!("No view controller available for this AUv3 plugin");
+            log_info!("No view controller available for this AUv3 plugin");
             return None;
         }
 
         let view_controller = view_controller.unwrap();
-        println!("Got AUv3 view controller: {:?}", view_controller);
+        log_info!("Got AUv3 view controller: {:?}", view_controller);
 
         // Get the view from the view controller
         let _: () = msg_send![view_controller, loadViewIfNeeded];
         let view: *mut AnyObject = msg_send![view_controller, view];
 
         if view.is_null() {
-            println!("View controller has no view");
+            log_info!("View controller has no view");
             return None;
         }
 
-        println!("Successfully obtained AUv3 view: {:?}", view);
+        log_info!("Successfully obtained AUv3 view: {:?}", view);
         Some((view, view_controller))
     }
 }
@@ -1089,9 +1498,10 @@ fn try_get_auv2_view(audio_unit: *mut c_void) -> Option<*mut AnyObject> {
             &mut writable,
         );
 
-        println!(
+        log_info!(
             "AUv2 CocoaUI property info: status={}, size={}",
-            status, size
+            status,
+            size
         );
 
         if status != 0 {
@@ -1185,9 +1595,9 @@ fn try_get_generic_view(audio_unit: *mut c_void) -> Option<*mut AnyObject> {
             msg_send![bundle_class, bundleWithPath: &*cak_framework_path];
         if !cak_bundle.is_null() {
             let loaded: bool = msg_send![cak_bundle, load];
-            println!("CoreAudioKit framework load: {}", loaded);
+            log_info!("CoreAudioKit framework load: {}", loaded);
         } else {
-            println!("CoreAudioKit framework bundle not found");
+            log_info!("CoreAudioKit framework bundle not found");
         }
 
         // Get AUGenericView class using objc_getClass
@@ -1199,51 +1609,32 @@ fn try_get_generic_view(audio_unit: *mut c_void) -> Option<*mut AnyObject> {
 
         let au_class = objc_getClass(class_name.as_ptr());
 
-        println!("AUGenericView class lookup: {:?}", au_class);
+        log_info!("AUGenericView class lookup: {:?}", au_class);
 
         if au_class.is_null() {
-            println!("AUGenericView class not found in CoreAudioKit");
+            log_info!("AUGenericView class not found in CoreAudioKit");
             return None;
         }
 
         // Create generic view: [[AUGenericView alloc] initWithAudioUnit:audioUnit]
         let generic_view: *mut AnyObject = msg_send![au_class, alloc];
         if generic_view.is_null() {
-            println!("Failed to alloc AUGenericView");
+            log_warn!("Failed to alloc AUGenericView");
             return None;
         }
 
         let generic_view: *mut AnyObject = msg_send![generic_view, initWithAudioUnit: audio_unit];
 
         if generic_view.is_null() {
-            println!("Failed to init AUGenericView");
+            log_warn!("Failed to init AUGenericView");
             return None;
         }
 
-        println!("Successfully created AUGenericView");
+        log_info!("Successfully created AUGenericView");
         Some(generic_view)
     }
 }
 
-/// Create a placeholder view when no custom UI is available
-fn create_placeholder_view(window: &NSWindow, text: &str, _mtm: MainThreadMarker) {
-    unsafe {
-        // Create a text field as placeholder
-        let text_field_class = class!(NSTextField);
-        let text_field: *mut AnyObject = msg_send![text_field_class, alloc];
-        let text_field: *mut AnyObject = msg_send![text_field, init];
-
-        let ns_string = NSString::from_str(text);
-        let _: () = msg_send![text_field, setStringValue: &*ns_string];
-        let _: () = msg_send![text_field, setEditable: false];
-        let _: () = msg_send![text_field, setBezeled: false];
-        let _: () = msg_send![text_field, setDrawsBackground: false];
-        let _: () = msg_send![text_field, setAlignment: 2u64]; // NSTextAlignmentCenter
-
-        let _: () = msg_send![window, setContentView: text_field];
-    }
-}
-
 /// Check if a plugin window is currently open
 pub fn is_plugin_window_open(instance_id: &str) -> bool {
     if let Some(&window_number) = PLUGIN_WINDOW_NUMBERS.read().unwrap().get(instance_id) {
@@ -1276,6 +1667,11 @@ pub fn close_all_plugin_windows() {
             window.close();
         }
     }
+    let embedded: Vec<String> =
+        EMBEDDED_PLUGIN_VIEWS.with(|views| views.borrow().keys().cloned().collect());
+    for instance_id in embedded {
+        remove_embedded_view(&instance_id);
+    }
 }
 
 /// Clean up cached view controller when AudioUnit instance is removed
@@ -1292,7 +1688,8 @@ pub fn cleanup_cached_view_controller(instance_id: &str) {
 ///
 /// This is a convenience wrapper that looks up the AudioUnit instance
 /// and opens its UI. Must be called from main thread.
-pub fn open_plugin_ui_by_instance_id(instance_id: &str) -> Result<(), String> {
+/// Returns false when the plugin has no custom view (see [`open_audio_unit_ui`]).
+pub fn open_plugin_ui_by_instance_id(instance_id: &str) -> Result<bool, String> {
     // Get the AudioUnit instance from manager
     let au_instance = crate::audio_unit::get_au_manager()
         .get_instance(instance_id)
@@ -1308,3 +1705,24 @@ pub fn open_plugin_ui_by_instance_id(instance_id: &str) -> Result<(), String> {
     // Open the UI
     open_audio_unit_ui(instance_id, au_audio_unit, &plugin_name)
 }
+
+/// Dock plugin UI into the main window by instance_id only
+///
+/// See [`open_audio_unit_ui_embedded`]. Must be called from main thread.
+pub fn open_plugin_ui_embedded_by_instance_id(
+    instance_id: &str,
+    ns_window: *mut AnyObject,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+) -> Result<bool, String> {
+    let au_instance = crate::audio_unit::get_au_manager()
+        .get_instance(instance_id)
+        .ok_or_else(|| format!("Plugin instance not found: {}", instance_id))?;
+    let au_audio_unit = au_instance
+        .get_au_audio_unit()
+        .ok_or_else(|| "AudioUnit not initialized".to_string())?;
+
+    open_audio_unit_ui_embedded(instance_id, au_audio_unit, ns_window, x, y, width, height)
+}
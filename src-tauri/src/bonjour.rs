@@ -0,0 +1,170 @@
+//! Bonjour (DNS-SD) advertisement of the remote control services
+//!
+//! LAN に公開しているコントロールサービスを `_spectrum._tcp` で広告し、タブレットなどの
+//! コントローラーが自動で見つけられるようにする。インスタンス名はコンピューター名
+//! （システムが決める）で、TXT レコードにプロトコル・バージョン・機能を入れる。
+//!
+//! 登録は `DNSServiceRef` が生きている間だけ有効（解放すると取り下げられる）。
+//! 127.0.0.1 だけで待ち受けているサービスは広告しない。
+
+use parking_lot::Mutex;
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+
+/// DNS-SD service type
+pub const SERVICE_TYPE: &str = "_spectrum._tcp";
+
+/// TXT record format version
+const TXT_VERSION: &str = "1";
+
+#[repr(C)]
+struct OpaqueDnsService {
+    _private: [u8; 0],
+}
+
+type DnsServiceRef = *mut OpaqueDnsService;
+
+type DnsServiceRegisterReply = unsafe extern "C" fn(
+    sd_ref: DnsServiceRef,
+    flags: u32,
+    error: i32,
+    name: *const c_char,
+    regtype: *const c_char,
+    domain: *const c_char,
+    context: *mut c_void,
+);
+
+// dns_sd.h (libSystem)
+extern "C" {
+    fn DNSServiceRegister(
+        sd_ref: *mut DnsServiceRef,
+        flags: u32,
+        interface_index: u32,
+        name: *const c_char,
+        regtype: *const c_char,
+        domain: *const c_char,
+        host: *const c_char,
+        port: u16,
+        txt_len: u16,
+        txt_record: *const c_void,
+        callback: Option<DnsServiceRegisterReply>,
+        context: *mut c_void,
+    ) -> i32;
+    fn DNSServiceRefDeallocate(sd_ref: DnsServiceRef);
+}
+
+/// A live registration (withdrawn on drop)
+struct Registration(DnsServiceRef);
+
+// DNSServiceRef は解放するまで他のスレッドから触らない
+unsafe impl Send for Registration {}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        unsafe { DNSServiceRefDeallocate(self.0) };
+    }
+}
+
+/// protocol ("http", ...) -> registration
+static REGISTRATIONS: Mutex<Vec<(&'static str, Registration)>> = Mutex::new(Vec::new());
+
+/// Advertise a control service, replacing an earlier advertisement of the same protocol
+///
+/// `capabilities` go into the `caps` TXT entry (comma separated).
+pub fn advertise(protocol: &'static str, port: u16, capabilities: &[&str]) {
+    withdraw(protocol);
+
+    let caps = capabilities.join(",");
+    let host = host_name();
+    let version = env!("CARGO_PKG_VERSION");
+    let txt = txt_record(&[
+        ("txtvers", TXT_VERSION),
+        ("proto", protocol),
+        ("version", version),
+        ("host", &host),
+        ("caps", &caps),
+        ("auth", "token"),
+    ]);
+    let Ok(regtype) = CString::new(SERVICE_TYPE) else {
+        return;
+    };
+
+    let mut sd_ref: DnsServiceRef = std::ptr::null_mut();
+    let error = unsafe {
+        DNSServiceRegister(
+            &mut sd_ref,
+            0,
+            0,
+            std::ptr::null(), // computer name
+            regtype.as_ptr(),
+            std::ptr::null(),
+            std::ptr::null(),
+            port.to_be(),
+            txt.len() as u16,
+            txt.as_ptr() as *const c_void,
+            None,
+            std::ptr::null_mut(),
+        )
+    };
+    if error != 0 || sd_ref.is_null() {
+        log_warn!(
+            "[Bonjour] Failed to advertise {} on port {} (error {})",
+            protocol,
+            port,
+            error
+        );
+        return;
+    }
+
+    REGISTRATIONS.lock().push((protocol, Registration(sd_ref)));
+    log_info!(
+        "[Bonjour] Advertising {} {} on port {}",
+        SERVICE_TYPE,
+        protocol,
+        port
+    );
+}
+
+/// Stop advertising a protocol (no-op when it is not advertised)
+pub fn withdraw(protocol: &str) {
+    let removed: Vec<_> = {
+        let mut registrations = REGISTRATIONS.lock();
+        let (removed, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut *registrations)
+            .into_iter()
+            .partition(|(p, _)| *p == protocol);
+        *registrations = kept;
+        removed
+    };
+    if !removed.is_empty() {
+        log_info!("[Bonjour] Withdrew {}", protocol);
+    }
+}
+
+/// Whether a protocol is currently advertised
+pub fn is_advertised(protocol: &str) -> bool {
+    REGISTRATIONS.lock().iter().any(|(p, _)| *p == protocol)
+}
+
+/// TXT record data: length-prefixed "key=value" strings (each at most 255 bytes)
+fn txt_record(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut txt = Vec::new();
+    for (key, value) in entries {
+        let entry = format!("{}={}", key, value);
+        let bytes = &entry.as_bytes()[..entry.len().min(255)];
+        txt.push(bytes.len() as u8);
+        txt.extend_from_slice(bytes);
+    }
+    txt
+}
+
+fn host_name() -> String {
+    let mut buf = [0 as c_char; 256];
+    let ok = unsafe { libc::gethostname(buf.as_mut_ptr(), buf.len()) } == 0;
+    if !ok {
+        return String::new();
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+    name.to_string_lossy()
+        .trim_end_matches(".local")
+        .to_string()
+}
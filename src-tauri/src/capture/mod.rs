@@ -9,6 +9,10 @@ pub use ring_buffer::*;
 // Re-export from legacy module for now
 // TODO: Refactor into this module structure
 pub use crate::audio_capture::{
+    apply_device_buffer_size,
+    apply_io_buffer_size,
+    buffer_diagnostics,
+    device_io_buffer_size,
     find_prism_device,
     get_active_captures,
     get_device_info,
@@ -22,6 +26,9 @@ pub use crate::audio_capture::{
     read_input_audio,
     register_output_device,
     register_output_for_input,
+    restart_device_capture,
+    restart_input_captures,
+    set_device_buffer_override,
     set_io_buffer_size,
     start_capture,
     // Generic input capture
@@ -29,4 +36,6 @@ pub use crate::audio_capture::{
     stop_capture,
     stop_input_capture,
     unregister_output_device,
+    CaptureError,
+    PROCESSOR_READER_ID,
 };
@@ -168,13 +168,13 @@ impl AppConfig {
         let path = match get_config_path() {
             Some(p) => p,
             None => {
-                println!("[Config] Could not determine config path, using defaults");
+                log_info!("[Config] Could not determine config path, using defaults");
                 return Self::default();
             }
         };
 
         if !path.exists() {
-            println!("[Config] No config file found, using defaults");
+            log_info!("[Config] No config file found, using defaults");
             return Self::default();
         }
 
@@ -182,17 +182,17 @@ impl AppConfig {
             Ok(content) => {
                 match serde_json::from_str::<AppConfig>(&content) {
                     Ok(config) => {
-                        println!("[Config] Loaded configuration from {:?}", path);
+                        log_info!("[Config] Loaded configuration from {:?}", path);
                         config
                     }
                     Err(e) => {
-                        eprintln!("[Config] Failed to parse config: {}", e);
+                        log_error!("[Config] Failed to parse config: {}", e);
                         Self::default()
                     }
                 }
             }
             Err(e) => {
-                eprintln!("[Config] Failed to read config: {}", e);
+                log_error!("[Config] Failed to read config: {}", e);
                 Self::default()
             }
         }
@@ -219,7 +219,7 @@ impl AppConfig {
         fs::write(&path, content)
             .map_err(|e| format!("Failed to write config: {}", e))?;
 
-        println!("[Config] Saved configuration to {:?}", path);
+        log_info!("[Config] Saved configuration to {:?}", path);
         Ok(())
     }
 }
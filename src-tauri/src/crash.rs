@@ -0,0 +1,364 @@
+//! Crash reports - panic hook and engine faults with a state snapshot
+//!
+//! パニック（リリースビルドは panic = "abort" なのでそのまま終了する）と、オーディオエンジンの
+//! 致命的な停止（グラフのロックが取れない・再起動しても出力/キャプチャが止まり続ける）を
+//! 検出したら、その時点のエンジンの状態をレポートとして保存する。次回の起動後に
+//! `get_last_crash_report` で読み出してバグ報告に添付できる。
+//!
+//! 含めるもの: グラフの概要（種類ごとのノード数・エッジ数。ラベルは含めない）、
+//! 動作中のデバイスとバッファサイズ、直近 200 行のログ。ホームディレクトリのパス・
+//! IPv4 アドレス・デバイス名の持ち主（"Alice's AirPods" など）は伏せる。
+//!
+//! 状態は別スレッドで集めて一定時間で打ち切る（パニックしたスレッドがグラフなどの
+//! ロックを持ったままでも固まらないように）。
+//!
+//! 保存先: `<data_dir>/spectrum/crash_reports/crash-<unix ms>.json`（新しい 10 件を残す）
+
+use crate::api::dto::{
+    CrashDeviceDto, CrashReportDto, EngineSnapshotDto, GraphSummaryDto, LogEntryDto,
+};
+use crate::audio::bus::BusNode;
+use crate::audio::processor::get_graph_processor;
+use crate::logging::Level;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Log lines included in a report
+const LOG_LINES: usize = 200;
+
+/// Reports kept on disk
+const MAX_REPORTS: usize = 10;
+
+/// How long the snapshot may take before the report is written without it
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Attempts to read the graph (the audio thread holds the write lock during a block)
+const GRAPH_READ_ATTEMPTS: usize = 20;
+
+const REPORT_DIR: &str = "crash_reports";
+
+/// Set while a report is written (a panic inside the hook is not reported again)
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// What went wrong
+struct Fault {
+    kind: String,
+    message: String,
+    location: Option<String>,
+    backtrace: Option<String>,
+}
+
+/// Install the panic hook (call once, before anything else runs)
+///
+/// 既存のフック（標準のメッセージ出力）はレポートを書いた後に呼ぶ。
+pub fn install() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        write_report(Fault {
+            kind: "panic".to_string(),
+            message,
+            location: info
+                .location()
+                .map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            backtrace: Some(std::backtrace::Backtrace::force_capture().to_string()),
+        });
+        previous(info);
+    }));
+}
+
+/// Record an engine fault that did not panic (watchdog: "processor_stall", "output_stall", ...)
+pub fn report_fault(kind: &str, message: String) {
+    write_report(Fault {
+        kind: kind.to_string(),
+        message,
+        location: None,
+        backtrace: None,
+    });
+}
+
+fn write_report(fault: Fault) {
+    if REPORTING.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    let thread = std::thread::current().name().map(str::to_string);
+    let headline = format!(
+        "[Crash] {} on thread {}: {}",
+        fault.kind,
+        thread.as_deref().unwrap_or("<unnamed>"),
+        fault.message
+    );
+    let (engine, logs) = collect_snapshot(headline);
+
+    let report = CrashReportDto {
+        timestamp_ms: now_ms(),
+        kind: fault.kind,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        platform: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+        thread,
+        message: redact(&fault.message),
+        location: fault.location,
+        backtrace: fault.backtrace,
+        engine,
+        logs,
+    };
+    // ログ（のロック）を経由しないよう stderr に直接書く
+    match save(&report) {
+        Ok(path) => eprintln!("[Crash] Report written to {}", path.display()),
+        Err(e) => eprintln!("[Crash] Failed to write crash report: {}", e),
+    }
+    REPORTING.store(false, Ordering::Release);
+}
+
+/// Logs and engine state, gathered on a helper thread within [`SNAPSHOT_TIMEOUT`]
+fn collect_snapshot(headline: String) -> (Option<EngineSnapshotDto>, Vec<LogEntryDto>) {
+    let (logs_tx, logs_rx) = mpsc::channel();
+    let (engine_tx, engine_rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("spectrum-crash-report".to_string())
+        .spawn(move || {
+            log_error!("{}", headline);
+            let logs: Vec<LogEntryDto> = crate::logging::recent(LOG_LINES, Level::Trace, None)
+                .into_iter()
+                .map(|entry| {
+                    let mut dto = LogEntryDto::from(entry);
+                    dto.message = redact(&dto.message);
+                    dto
+                })
+                .collect();
+            let _ = logs_tx.send(logs);
+            let _ = engine_tx.send(engine_snapshot());
+        });
+    if spawned.is_err() {
+        return (None, Vec::new());
+    }
+
+    let deadline = Instant::now() + SNAPSHOT_TIMEOUT;
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    let logs = logs_rx.recv_timeout(remaining()).unwrap_or_default();
+    let engine = engine_rx.recv_timeout(remaining()).ok();
+    (engine, logs)
+}
+
+fn engine_snapshot() -> EngineSnapshotDto {
+    let mut graph = None;
+    for _ in 0..GRAPH_READ_ATTEMPTS {
+        graph = get_graph_processor().try_with_graph(graph_summary);
+        if graph.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    let outputs = crate::audio::output::get_active_output_devices()
+        .into_iter()
+        .map(|device_id| {
+            device_snapshot(
+                device_id,
+                crate::device::get_device_output_channels(device_id) as usize,
+            )
+        })
+        .collect();
+    let inputs = crate::capture::get_active_captures()
+        .into_iter()
+        .map(|(device_id, _, channels, _)| device_snapshot(device_id, channels))
+        .collect();
+
+    EngineSnapshotDto {
+        sample_rate: crate::audio::engine_sample_rate(),
+        io_buffer_size: crate::capture::get_io_buffer_size() as u32,
+        graph,
+        outputs,
+        inputs,
+    }
+}
+
+fn graph_summary(graph: &crate::audio::AudioGraph) -> GraphSummaryDto {
+    let mut nodes_by_type = BTreeMap::new();
+    let mut plugin_count = 0;
+    for handle in graph.node_handles() {
+        let Some(node) = graph.get_node(handle) else {
+            continue;
+        };
+        let kind = format!("{:?}", node.node_type()).to_ascii_lowercase();
+        *nodes_by_type.entry(kind).or_insert(0) += 1;
+        if let Some(bus) = node.as_any().downcast_ref::<BusNode>() {
+            plugin_count += bus.plugins().len();
+        }
+    }
+    GraphSummaryDto {
+        node_count: graph.node_count(),
+        edge_count: graph.edge_count(),
+        nodes_by_type,
+        plugin_count,
+    }
+}
+
+fn device_snapshot(device_id: u32, channels: usize) -> CrashDeviceDto {
+    let name = coreaudio::audio_unit::macos_helpers::get_device_name(device_id)
+        .unwrap_or_else(|_| format!("Device {}", device_id));
+    CrashDeviceDto {
+        device_id,
+        name: redact_device_name(&name),
+        channels: channels as u32,
+        buffer_size: crate::capture::device_io_buffer_size(device_id),
+        sample_rate: crate::device::get_device_nominal_sample_rate(device_id),
+    }
+}
+
+// =============================================================================
+// Storage
+// =============================================================================
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn reports_dir() -> Result<PathBuf, String> {
+    let dir = dirs::data_dir()
+        .ok_or("Could not find app data directory")?
+        .join("spectrum")
+        .join(REPORT_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create crash report directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Report files, oldest first
+fn report_files() -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(reports_dir()?)
+        .map_err(|e| format!("Failed to read crash reports: {}", e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("crash-") && n.ends_with(".json"))
+        })
+        .collect();
+    // 名前はゼロ埋めの時刻なので名前順 = 時刻順
+    files.sort();
+    Ok(files)
+}
+
+fn save(report: &CrashReportDto) -> Result<PathBuf, String> {
+    let path = reports_dir()?.join(format!("crash-{:013}.json", report.timestamp_ms));
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| format!("Failed to serialize crash report: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write crash report: {}", e))?;
+
+    let files = report_files()?;
+    for old in files.iter().take(files.len().saturating_sub(MAX_REPORTS)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Newest saved report (None if nothing has crashed yet)
+pub fn last_report() -> Result<Option<CrashReportDto>, String> {
+    let Some(path) = report_files()?.pop() else {
+        return Ok(None);
+    };
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read crash report: {}", e))?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse crash report: {}", e))
+}
+
+// =============================================================================
+// Redaction
+// =============================================================================
+
+/// Hide the home directory and IPv4 addresses in free text
+fn redact(text: &str) -> String {
+    let mut text = text.to_string();
+    if let Some(home) = dirs::home_dir().and_then(|h| h.to_str().map(str::to_string)) {
+        if home.len() > 1 {
+            text = text.replace(&home, "~");
+        }
+    }
+    redact_ipv4(&text)
+}
+
+fn redact_ipv4(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        out.push_str(&rest[..start]);
+        let run = &rest[start..];
+        let len = run
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(run.len());
+        let candidate = run[..len].trim_end_matches('.');
+        let parts: Vec<&str> = candidate.split('.').collect();
+        let is_address = parts.len() == 4
+            && parts
+                .iter()
+                .all(|p| (1..=3).contains(&p.len()) && p.parse::<u16>().is_ok_and(|v| v <= 255));
+        if is_address {
+            out.push_str("x.x.x.x");
+            rest = &run[candidate.len()..];
+        } else {
+            out.push_str(&run[..len]);
+            rest = &run[len..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// "Alice's AirPods" -> "<user>'s AirPods"
+fn redact_device_name(name: &str) -> String {
+    match name.find("'s ").or_else(|| name.find("\u{2019}s ")) {
+        Some(pos) if pos > 0 => format!("<user>{}", &name[pos..]),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_ipv4() {
+        assert_eq!(
+            redact_ipv4("Sending to 192.168.1.20:9000 (ttl 64)"),
+            "Sending to x.x.x.x:9000 (ttl 64)"
+        );
+        assert_eq!(redact_ipv4("peer 10.0.0.1."), "peer x.x.x.x.");
+        // Not addresses
+        assert_eq!(redact_ipv4("48000.0 Hz, 1.2.3"), "48000.0 Hz, 1.2.3");
+        assert_eq!(redact_ipv4("300.1.1.1"), "300.1.1.1");
+    }
+
+    #[test]
+    fn test_redact_home_and_device_names() {
+        if let Some(home) = dirs::home_dir().and_then(|h| h.to_str().map(str::to_string)) {
+            if home.len() > 1 {
+                let text = format!("Failed to read {}/Music/a.wav", home);
+                assert_eq!(redact(&text), "Failed to read ~/Music/a.wav");
+            }
+        }
+        assert_eq!(
+            redact_device_name("Alice\u{2019}s AirPods Pro"),
+            "<user>\u{2019}s AirPods Pro"
+        );
+        assert_eq!(redact_device_name("Bob's iPhone"), "<user>'s iPhone");
+        assert_eq!(
+            redact_device_name("MacBook Pro Speakers"),
+            "MacBook Pro Speakers"
+        );
+    }
+}
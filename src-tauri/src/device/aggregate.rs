@@ -0,0 +1,172 @@
+//! Aggregate device creation / destruction
+//!
+//! Audio MIDI Setup を開かずに複数デバイスをまとめた Aggregate Device を作る。
+//! Spectrum が作ったデバイスは UID に `AGGREGATE_UID_PREFIX` を付けて区別し、
+//! 削除はそれに限る（ユーザーが自分で作ったデバイスは消さない）。
+
+use super::enumerate::{get_device_output_channels, get_device_uid};
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::CFDictionary;
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
+use coreaudio::audio_unit::macos_helpers::get_audio_device_ids;
+use coreaudio::sys::{AudioHardwareCreateAggregateDevice, AudioHardwareDestroyAggregateDevice};
+
+/// UID prefix of aggregate devices created by Spectrum
+pub const AGGREGATE_UID_PREFIX: &str = "com.petitstrawberry.spectrum.aggregate.";
+
+// AudioHardware.h の辞書キー（#define の文字列定数なので bindgen では扱いにくい）
+const KEY_NAME: &str = "name"; // kAudioAggregateDeviceNameKey
+const KEY_UID: &str = "uid"; // kAudioAggregateDeviceUIDKey
+const KEY_SUB_DEVICE_LIST: &str = "subdevices"; // kAudioAggregateDeviceSubDeviceListKey
+const KEY_MAIN_SUB_DEVICE: &str = "master"; // kAudioAggregateDeviceMainSubDeviceKey
+const KEY_IS_PRIVATE: &str = "private"; // kAudioAggregateDeviceIsPrivateKey
+const KEY_IS_STACKED: &str = "stacked"; // kAudioAggregateDeviceIsStackedKey
+const KEY_SUB_DEVICE_UID: &str = "uid"; // kAudioSubDeviceUIDKey
+const KEY_SUB_DEVICE_DRIFT: &str = "drift"; // kAudioSubDeviceDriftCompensationKey
+
+/// A newly created aggregate device
+#[derive(Debug, Clone)]
+pub struct CreatedAggregate {
+    pub device_id: u32,
+    pub uid: String,
+    pub name: String,
+    pub channels: u32,
+}
+
+/// Find a device by UID
+pub fn find_device_by_uid(uid: &str) -> Option<u32> {
+    get_audio_device_ids()
+        .ok()?
+        .into_iter()
+        .find(|&id| get_device_uid(id).as_deref() == Some(uid))
+}
+
+/// Whether a device was created by Spectrum
+pub fn is_spectrum_aggregate(device_id: u32) -> bool {
+    get_device_uid(device_id)
+        .map(|uid| uid.starts_with(AGGREGATE_UID_PREFIX))
+        .unwrap_or(false)
+}
+
+/// Create an aggregate device from sub-device UIDs
+///
+/// 最初のサブデバイスがクロックマスターになる。`drift_correction` が true の場合、
+/// それ以外のサブデバイスにドリフト補正をかける。
+pub fn create_aggregate_device(
+    name: &str,
+    sub_device_uids: &[String],
+    drift_correction: bool,
+) -> Result<CreatedAggregate, String> {
+    if sub_device_uids.is_empty() {
+        return Err("Aggregate device needs at least one sub-device".to_string());
+    }
+    for (i, uid) in sub_device_uids.iter().enumerate() {
+        if sub_device_uids[..i].contains(uid) {
+            return Err(format!("Duplicate sub-device: {}", uid));
+        }
+        if find_device_by_uid(uid).is_none() {
+            return Err(format!("Sub-device not found: {}", uid));
+        }
+    }
+
+    let name = if name.trim().is_empty() {
+        "Spectrum Aggregate"
+    } else {
+        name.trim()
+    };
+    let uid = format!("{}{}", AGGREGATE_UID_PREFIX, uuid::Uuid::new_v4());
+
+    let sub_devices: Vec<CFDictionary<CFString, CFType>> = sub_device_uids
+        .iter()
+        .enumerate()
+        .map(|(i, sub_uid)| {
+            // kAudioSubDeviceDriftCompensationKey は CFNumber (0/1)
+            let drift = drift_correction && i > 0;
+            CFDictionary::from_CFType_pairs(&[
+                (
+                    CFString::new(KEY_SUB_DEVICE_UID),
+                    CFString::new(sub_uid).as_CFType(),
+                ),
+                (
+                    CFString::new(KEY_SUB_DEVICE_DRIFT),
+                    CFNumber::from(drift as i32).as_CFType(),
+                ),
+            ])
+        })
+        .collect();
+    let sub_device_list = CFArray::from_CFTypes(&sub_devices);
+
+    let description: CFDictionary<CFString, CFType> = CFDictionary::from_CFType_pairs(&[
+        (CFString::new(KEY_NAME), CFString::new(name).as_CFType()),
+        (CFString::new(KEY_UID), CFString::new(&uid).as_CFType()),
+        (
+            CFString::new(KEY_SUB_DEVICE_LIST),
+            sub_device_list.as_CFType(),
+        ),
+        (
+            CFString::new(KEY_MAIN_SUB_DEVICE),
+            CFString::new(&sub_device_uids[0]).as_CFType(),
+        ),
+        (
+            CFString::new(KEY_IS_PRIVATE),
+            CFBoolean::false_value().as_CFType(),
+        ),
+        (
+            CFString::new(KEY_IS_STACKED),
+            CFBoolean::false_value().as_CFType(),
+        ),
+    ]);
+
+    let mut device_id: u32 = 0;
+    let status = unsafe {
+        AudioHardwareCreateAggregateDevice(
+            description.as_concrete_TypeRef() as *const _,
+            &mut device_id,
+        )
+    };
+    if status != 0 || device_id == 0 {
+        return Err(format!(
+            "AudioHardwareCreateAggregateDevice failed (status {})",
+            status
+        ));
+    }
+
+    log_info!(
+        "[Device] Created aggregate '{}' (id {}, {} sub-devices, drift correction {})",
+        name,
+        device_id,
+        sub_device_uids.len(),
+        drift_correction
+    );
+
+    Ok(CreatedAggregate {
+        device_id,
+        uid,
+        name: name.to_string(),
+        channels: get_device_output_channels(device_id),
+    })
+}
+
+/// Destroy an aggregate device created by Spectrum
+pub fn destroy_aggregate_device(device_id: u32) -> Result<(), String> {
+    if !is_spectrum_aggregate(device_id) {
+        return Err(format!(
+            "Device {} is not an aggregate device created by Spectrum",
+            device_id
+        ));
+    }
+
+    let status = unsafe { AudioHardwareDestroyAggregateDevice(device_id) };
+    if status != 0 {
+        return Err(format!(
+            "AudioHardwareDestroyAggregateDevice failed (status {})",
+            status
+        ));
+    }
+
+    log_info!("[Device] Destroyed aggregate device {}", device_id);
+    Ok(())
+}
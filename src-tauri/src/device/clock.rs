@@ -0,0 +1,257 @@
+//! Device clock - nominal sample rate policy and clock source selection
+//!
+//! 出力デバイスのサンプルレートをどう扱うかをデバイス（UID）ごとに決める。
+//! - Engine: エンジンのレートに合わせる（従来の挙動）
+//! - Follow: デバイスのレートに触らない（AUHAL がレート変換する）
+//! - Fixed: 指定レートに固定する
+//!
+//! `start_output_v2` は [`apply_output_rate_policy`] を通してレートを設定する。
+
+use super::enumerate::{get_device_available_sample_rates, get_device_uid};
+use coreaudio::audio_unit::macos_helpers::set_device_sample_rate;
+use coreaudio::sys::{
+    kAudioDevicePropertyClockSource, kAudioDevicePropertyClockSourceNameForIDCFString,
+    kAudioDevicePropertyClockSources, kAudioDevicePropertyScopeOutput,
+    kAudioObjectPropertyElementMaster, AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize,
+    AudioObjectPropertyAddress, AudioObjectSetPropertyData, AudioValueTranslation,
+};
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::ptr;
+use std::sync::LazyLock;
+
+/// Output sample-rate policy for one device
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RatePolicy {
+    /// Switch the device to the engine rate
+    #[default]
+    Engine,
+    /// Leave the device at whatever rate it runs at
+    Follow,
+    /// Pin the device to a specific rate
+    Fixed(f64),
+}
+
+impl RatePolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Engine => "engine",
+            Self::Follow => "follow",
+            Self::Fixed(_) => "fixed",
+        }
+    }
+}
+
+/// A device clock source
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockSource {
+    pub id: u32,
+    pub name: String,
+}
+
+/// device UID -> policy（UID がないデバイスは `id:<device_id>` をキーにする）
+static RATE_POLICIES: LazyLock<RwLock<HashMap<String, RatePolicy>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn policy_key(device_id: u32) -> String {
+    get_device_uid(device_id).unwrap_or_else(|| format!("id:{}", device_id))
+}
+
+/// Policy for devices without their own entry (settings.json の `rate_policy`)
+static DEFAULT_RATE_POLICY: RwLock<RatePolicy> = RwLock::new(RatePolicy::Engine);
+
+/// Get the policy used for devices without their own setting
+pub fn default_rate_policy() -> RatePolicy {
+    *DEFAULT_RATE_POLICY.read()
+}
+
+/// Set the policy used for devices without their own setting
+pub fn set_default_rate_policy(policy: RatePolicy) {
+    *DEFAULT_RATE_POLICY.write() = policy;
+}
+
+/// Get the rate policy for a device
+pub fn rate_policy(device_id: u32) -> RatePolicy {
+    RATE_POLICIES
+        .read()
+        .get(&policy_key(device_id))
+        .copied()
+        .unwrap_or_else(default_rate_policy)
+}
+
+/// Set the rate policy for a device
+pub fn set_rate_policy(device_id: u32, policy: RatePolicy) {
+    let key = policy_key(device_id);
+    let mut policies = RATE_POLICIES.write();
+    if policy == default_rate_policy() {
+        policies.remove(&key);
+    } else {
+        policies.insert(key, policy);
+    }
+}
+
+/// Set a device's nominal sample rate and pin it there
+pub fn set_device_nominal_sample_rate(device_id: u32, sample_rate: f64) -> Result<(), String> {
+    let available = get_device_available_sample_rates(device_id);
+    if !available
+        .iter()
+        .any(|&r| crate::audio::sample_rate::rates_match(r, sample_rate))
+    {
+        return Err(format!(
+            "Device {} does not support {} Hz (available: {:?})",
+            device_id, sample_rate, available
+        ));
+    }
+
+    set_device_sample_rate(device_id, sample_rate)
+        .map_err(|e| format!("Failed to set sample rate: {:?}", e))?;
+    set_rate_policy(device_id, RatePolicy::Fixed(sample_rate));
+
+    log_info!("[Device] Device {} pinned to {} Hz", device_id, sample_rate);
+    Ok(())
+}
+
+/// Apply the device's rate policy before starting output
+///
+/// Returns the rate requested from the device, or `None` when following it.
+pub fn apply_output_rate_policy(device_id: u32, engine_rate: f64) -> Option<f64> {
+    let target = match rate_policy(device_id) {
+        RatePolicy::Engine => engine_rate,
+        RatePolicy::Fixed(rate) => rate,
+        RatePolicy::Follow => return None,
+    };
+    // デバイスが対応しない場合は AUHAL がレート変換する
+    if let Err(e) = set_device_sample_rate(device_id, target) {
+        log_warn!(
+            "[Device] Warning: Could not set sample rate {} on device {}: {:?}",
+            target,
+            device_id,
+            e
+        );
+    }
+    Some(target)
+}
+
+fn clock_address(selector: u32) -> AudioObjectPropertyAddress {
+    AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: kAudioDevicePropertyScopeOutput,
+        mElement: kAudioObjectPropertyElementMaster,
+    }
+}
+
+/// Current clock source of a device (None if it doesn't expose one)
+pub fn get_clock_source(device_id: u32) -> Option<u32> {
+    let address = clock_address(kAudioDevicePropertyClockSource);
+    let mut source: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            &mut source as *mut u32 as *mut _,
+        )
+    };
+    (status == 0).then_some(source)
+}
+
+/// Clock sources a device offers
+pub fn get_clock_sources(device_id: u32) -> Vec<ClockSource> {
+    let address = clock_address(kAudioDevicePropertyClockSources);
+    let mut size: u32 = 0;
+    let status =
+        unsafe { AudioObjectGetPropertyDataSize(device_id, &address, 0, ptr::null(), &mut size) };
+    if status != 0 || size == 0 {
+        return Vec::new();
+    }
+
+    let mut ids = vec![0u32; size as usize / std::mem::size_of::<u32>()];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            ids.as_mut_ptr() as *mut _,
+        )
+    };
+    if status != 0 {
+        return Vec::new();
+    }
+
+    ids.into_iter()
+        .map(|id| ClockSource {
+            id,
+            name: clock_source_name(device_id, id).unwrap_or_else(|| format!("Source {}", id)),
+        })
+        .collect()
+}
+
+fn clock_source_name(device_id: u32, source_id: u32) -> Option<String> {
+    use core_foundation::base::TCFType;
+    use core_foundation::string::{CFString, CFStringRef};
+
+    let address = clock_address(kAudioDevicePropertyClockSourceNameForIDCFString);
+    let mut input = source_id;
+    let mut name: CFStringRef = ptr::null();
+    let mut translation = AudioValueTranslation {
+        mInputData: &mut input as *mut u32 as *mut _,
+        mInputDataSize: std::mem::size_of::<u32>() as u32,
+        mOutputData: &mut name as *mut CFStringRef as *mut _,
+        mOutputDataSize: std::mem::size_of::<CFStringRef>() as u32,
+    };
+    let mut size = std::mem::size_of::<AudioValueTranslation>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            &mut translation as *mut AudioValueTranslation as *mut _,
+        )
+    };
+    if status != 0 || name.is_null() {
+        return None;
+    }
+    Some(unsafe { CFString::wrap_under_create_rule(name) }.to_string())
+}
+
+/// Select a device's clock source
+pub fn set_clock_source(device_id: u32, source_id: u32) -> Result<(), String> {
+    if !get_clock_sources(device_id)
+        .iter()
+        .any(|s| s.id == source_id)
+    {
+        return Err(format!(
+            "Device {} has no clock source {}",
+            device_id, source_id
+        ));
+    }
+
+    let address = clock_address(kAudioDevicePropertyClockSource);
+    let status = unsafe {
+        AudioObjectSetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            std::mem::size_of::<u32>() as u32,
+            &source_id as *const u32 as *const _,
+        )
+    };
+    if status != 0 {
+        return Err(format!("Failed to set clock source (status {})", status));
+    }
+
+    log_info!(
+        "[Device] Device {} clock source set to {}",
+        device_id,
+        source_id
+    );
+    Ok(())
+}
@@ -3,11 +3,14 @@
 use crate::api::dto::OutputDeviceDto;
 use coreaudio::audio_unit::macos_helpers::{get_audio_device_ids, get_device_name};
 use coreaudio::sys::{
-    kAudioAggregateDevicePropertyActiveSubDeviceList, kAudioDevicePropertyDeviceUID,
-    kAudioDevicePropertyScopeOutput, kAudioDevicePropertyStreamConfiguration,
-    kAudioHardwarePropertyDefaultOutputDevice, kAudioObjectPropertyElementMaster,
-    kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject, AudioBuffer, AudioBufferList,
-    AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize, AudioObjectPropertyAddress,
+    kAudioAggregateDevicePropertyActiveSubDeviceList,
+    kAudioDevicePropertyAvailableNominalSampleRates, kAudioDevicePropertyDeviceUID,
+    kAudioDevicePropertyLatency, kAudioDevicePropertyNominalSampleRate,
+    kAudioDevicePropertySafetyOffset, kAudioDevicePropertyScopeOutput,
+    kAudioDevicePropertyStreamConfiguration, kAudioHardwarePropertyDefaultOutputDevice,
+    kAudioObjectPropertyElementMaster, kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject,
+    AudioBuffer, AudioBufferList, AudioObjectGetPropertyData, AudioObjectGetPropertyDataSize,
+    AudioObjectPropertyAddress, AudioValueRange,
 };
 use std::ptr;
 
@@ -104,6 +107,144 @@ pub fn get_device_uid(device_id: u32) -> Option<String> {
     Some(cf_string.to_string())
 }
 
+/// Get device nominal sample rate
+pub fn get_device_nominal_sample_rate(device_id: u32) -> Option<f64> {
+    let address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyNominalSampleRate,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+
+    let mut rate: f64 = 0.0;
+    let mut size = std::mem::size_of::<f64>() as u32;
+
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            &mut rate as *mut f64 as *mut _,
+        )
+    };
+
+    if status != 0 || rate <= 0.0 {
+        return None;
+    }
+
+    Some(rate)
+}
+
+/// Get the nominal sample rates a device supports
+///
+/// レンジ（min != max）で返すデバイスもあるため、エンジンの対応レートのうち
+/// レンジに含まれるものを返す。
+pub fn get_device_available_sample_rates(device_id: u32) -> Vec<f64> {
+    let address = AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyAvailableNominalSampleRates,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    };
+
+    let mut size: u32 = 0;
+    let status =
+        unsafe { AudioObjectGetPropertyDataSize(device_id, &address, 0, ptr::null(), &mut size) };
+
+    if status != 0 || size == 0 {
+        return Vec::new();
+    }
+
+    let count = size as usize / std::mem::size_of::<AudioValueRange>();
+    let mut ranges = vec![
+        AudioValueRange {
+            mMinimum: 0.0,
+            mMaximum: 0.0,
+        };
+        count
+    ];
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            device_id,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            ranges.as_mut_ptr() as *mut _,
+        )
+    };
+
+    if status != 0 {
+        return Vec::new();
+    }
+
+    crate::audio::sample_rate::SUPPORTED_SAMPLE_RATES
+        .iter()
+        .copied()
+        .filter(|&rate| {
+            ranges
+                .iter()
+                .any(|r| rate >= r.mMinimum - 0.5 && rate <= r.mMaximum + 0.5)
+        })
+        .collect()
+}
+
+/// Output latency of a device in frames (device latency + safety offset)
+///
+/// Bluetooth / AirPlay では数千フレームになる。取得できない値は 0 として扱う。
+pub fn get_device_output_latency(device_id: u32) -> u32 {
+    let read = |selector: u32| -> u32 {
+        let address = AudioObjectPropertyAddress {
+            mSelector: selector,
+            mScope: kAudioDevicePropertyScopeOutput,
+            mElement: kAudioObjectPropertyElementMaster,
+        };
+        let mut frames: u32 = 0;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            AudioObjectGetPropertyData(
+                device_id,
+                &address,
+                0,
+                ptr::null(),
+                &mut size,
+                &mut frames as *mut u32 as *mut _,
+            )
+        };
+        if status == 0 {
+            frames
+        } else {
+            0
+        }
+    };
+    read(kAudioDevicePropertyLatency).saturating_add(read(kAudioDevicePropertySafetyOffset))
+}
+
+/// Output latency of the device behind a sink (frames)
+///
+/// アグリゲートデバイスでは `channel_offset` を含むサブデバイスのレイテンシを返す
+/// （有線と Bluetooth を束ねた場合はサブデバイスごとに大きく違う）。
+pub fn get_sink_output_latency(device_id: u32, channel_offset: u8) -> u32 {
+    if is_aggregate_device(device_id) {
+        let mut offset = 0u32;
+        for sub in get_aggregate_sub_devices(device_id) {
+            if sub.channels == 0 {
+                continue;
+            }
+            if (channel_offset as u32) < offset + sub.channels {
+                return get_device_output_latency(sub.original_id);
+            }
+            offset += sub.channels;
+        }
+    }
+    get_device_output_latency(device_id)
+}
+
+fn latency_ms(frames: u32, device_id: u32) -> f32 {
+    let rate = get_device_nominal_sample_rate(device_id).unwrap_or(48_000.0);
+    (frames as f64 * 1000.0 / rate) as f32
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TransportType {
     Bluetooth,
@@ -191,7 +332,7 @@ fn get_transport_type(device_id: u32) -> TransportType {
     };
 
     if status != 0 {
-        eprint!("Failed to get transport type for device {}", device_id);
+        log_warn!("Failed to get transport type for device {}", device_id);
         return TransportType::Unknown;
     }
 
@@ -272,6 +413,7 @@ pub fn get_output_devices() -> Vec<OutputDeviceDto> {
                     }
 
                     let transport_type = get_transport_type(sub.original_id);
+                    let latency_frames = get_device_output_latency(sub.original_id);
 
                     // Generate ID with subdevice UID hash to track devices across configuration changes
                     let id = if let Some(ref uid) = sub.uid {
@@ -297,6 +439,8 @@ pub fn get_output_devices() -> Vec<OutputDeviceDto> {
                         transport_type: transport_type.to_string(),
                         icon_hint: get_icon_hint(sub.uid.as_deref().unwrap_or(""), &transport_type),
                         is_aggregate_sub: true,
+                        latency_frames,
+                        latency_ms: latency_ms(latency_frames, device_id),
                     });
 
                     offset += sub.channels;
@@ -304,6 +448,7 @@ pub fn get_output_devices() -> Vec<OutputDeviceDto> {
             }
         } else {
             let device_uid = get_device_uid(device_id);
+            let latency_frames = get_device_output_latency(device_id);
             // Regular device - single entry
             result.push(OutputDeviceDto {
                 id: format!("vout_{}_0", device_id),
@@ -318,6 +463,8 @@ pub fn get_output_devices() -> Vec<OutputDeviceDto> {
                 transport_type: transport_type.to_string(),
                 icon_hint: get_icon_hint(device_uid.as_deref().unwrap_or(""), &transport_type),
                 is_aggregate_sub: false,
+                latency_frames,
+                latency_ms: latency_ms(latency_frames, device_id),
             });
         }
     }
@@ -0,0 +1,507 @@
+//! Hot-plug monitoring - device connect/disconnect and default device changes
+//!
+//! CoreAudio のプロパティリスナーをシステムオブジェクトに登録し、
+//! デバイスの抜き差しを検出して自動で出力/キャプチャを一時停止・再開する。
+//!
+//! キャプチャ中のデバイスにはストリーム構成のリスナーも登録し、入力チャンネル数の
+//! 変化（アグリゲートデバイスの構成変更など）でリングバッファとソースノードを作り直す。
+//! 既定の出力デバイスが変わると、「既定に追従」するシンクを新しいデバイスへ付け替える。
+//!
+//! リスナーは CoreAudio の通知スレッドで呼ばれるため、セレクタをチャンネルに送るだけにして、
+//! 差分計算と再ルーティングは監視スレッド（`spectrum-device-watch`）で行う。
+
+use super::enumerate::{get_device_output_channels, get_device_uid};
+use crate::audio::processor::get_graph_processor;
+use coreaudio::audio_unit::macos_helpers::{get_audio_device_ids, get_device_name};
+use coreaudio::sys::{
+    kAudioDevicePropertyScopeInput, kAudioDevicePropertyStreamConfiguration,
+    kAudioHardwarePropertyDefaultInputDevice, kAudioHardwarePropertyDefaultOutputDevice,
+    kAudioHardwarePropertyDevices, kAudioObjectPropertyElementMaster,
+    kAudioObjectPropertyScopeGlobal, kAudioObjectSystemObject, AudioObjectAddPropertyListener,
+    AudioObjectGetPropertyData, AudioObjectID, AudioObjectPropertyAddress,
+    AudioObjectRemovePropertyListener, OSStatus,
+};
+use crossbeam_channel::{Receiver, Sender};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::ffi::c_void;
+use std::ptr;
+use std::sync::{LazyLock, OnceLock};
+use std::time::Duration;
+
+/// 連続する通知をまとめる待ち時間
+const DEBOUNCE_MS: u64 = 200;
+
+/// Snapshot of one device at enumeration time
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceInfo {
+    pub device_id: u32,
+    pub uid: Option<String>,
+    pub name: String,
+    pub input_channels: u32,
+    pub output_channels: u32,
+}
+
+/// A detected device change
+#[derive(Debug, Clone)]
+pub enum DeviceChange {
+    Connected(DeviceInfo),
+    Disconnected(DeviceInfo),
+    DefaultOutputChanged(u32),
+    DefaultInputChanged(u32),
+    /// Input channel count of a captured device changed (info has the new count)
+    ChannelsChanged(DeviceInfo),
+}
+
+/// What the monitor did in response to a change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RerouteAction {
+    None,
+    /// Output and/or capture bound to the device was stopped
+    Paused,
+    /// Device came back; `rebound` graph nodes were moved to its new ID
+    Resumed {
+        rebound: usize,
+    },
+    /// Capture was rebuilt; `resized` source nodes got a new port count
+    Resized {
+        resized: usize,
+    },
+    /// Default output changed; `retargeted` "follow default" sinks moved to it
+    Retargeted {
+        retargeted: usize,
+    },
+}
+
+/// 抜かれたデバイスで停止したもの（UID で再接続を待つ）
+#[derive(Debug, Clone, Copy)]
+struct Suspended {
+    device_id: u32,
+    output: bool,
+    input: bool,
+}
+
+static SUSPENDED: LazyLock<Mutex<HashMap<String, Suspended>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Listener -> watcher notification channel: (object, selector)
+static NOTIFY_TX: OnceLock<Sender<(AudioObjectID, u32)>> = OnceLock::new();
+
+/// Devices with a stream-configuration listener
+static STREAM_WATCHED: LazyLock<Mutex<HashSet<u32>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// CoreAudio property listener (CoreAudio notification thread)
+unsafe extern "C" fn property_listener(
+    object_id: AudioObjectID,
+    num_addresses: u32,
+    addresses: *const AudioObjectPropertyAddress,
+    _client_data: *mut c_void,
+) -> OSStatus {
+    let Some(tx) = NOTIFY_TX.get() else {
+        return 0;
+    };
+    if addresses.is_null() {
+        return 0;
+    }
+    for i in 0..num_addresses as usize {
+        let _ = tx.try_send((object_id, (*addresses.add(i)).mSelector));
+    }
+    0
+}
+
+/// Start monitoring devices (idempotent)
+///
+/// `on_change` is called from the watcher thread after the monitor has
+/// paused/resumed anything bound to the device.
+pub fn start_device_monitor(
+    on_change: impl Fn(&DeviceChange, RerouteAction) + Send + 'static,
+) -> Result<(), String> {
+    let (tx, rx) = crossbeam_channel::bounded::<(AudioObjectID, u32)>(64);
+    if NOTIFY_TX.set(tx).is_err() {
+        return Ok(()); // Already running
+    }
+
+    for selector in [
+        kAudioHardwarePropertyDevices,
+        kAudioHardwarePropertyDefaultOutputDevice,
+        kAudioHardwarePropertyDefaultInputDevice,
+    ] {
+        let address = system_address(selector);
+        let status = unsafe {
+            AudioObjectAddPropertyListener(
+                kAudioObjectSystemObject,
+                &address,
+                Some(property_listener),
+                ptr::null_mut(),
+            )
+        };
+        if status != 0 {
+            return Err(format!(
+                "AudioObjectAddPropertyListener failed for selector {} (status {})",
+                selector, status
+            ));
+        }
+    }
+
+    std::thread::Builder::new()
+        .name("spectrum-device-watch".to_string())
+        .spawn(move || watch_thread(rx, on_change))
+        .map_err(|e| format!("Failed to start device watcher: {}", e))?;
+
+    log_info!("[Device] Hot-plug monitor started");
+    Ok(())
+}
+
+/// Watch a capture device for input channel-count changes (idempotent)
+///
+/// キャプチャ開始時に呼ぶ。通知はホットプラグ監視スレッドで処理されるので、
+/// [`start_device_monitor`] が動いていなければ何もしない。
+pub fn watch_stream_configuration(device_id: u32) {
+    if NOTIFY_TX.get().is_none() || !STREAM_WATCHED.lock().insert(device_id) {
+        return;
+    }
+    let address = input_stream_address();
+    let status = unsafe {
+        AudioObjectAddPropertyListener(
+            device_id,
+            &address,
+            Some(property_listener),
+            ptr::null_mut(),
+        )
+    };
+    if status != 0 {
+        STREAM_WATCHED.lock().remove(&device_id);
+        log_warn!(
+            "[Device] Failed to watch stream configuration of device {} (status {})",
+            device_id,
+            status
+        );
+    }
+}
+
+/// Stop watching a capture device's stream configuration
+pub fn unwatch_stream_configuration(device_id: u32) {
+    if !STREAM_WATCHED.lock().remove(&device_id) {
+        return;
+    }
+    let address = input_stream_address();
+    // 抜かれたデバイスでは失敗するが、リスナーはデバイスと一緒に消える
+    unsafe {
+        AudioObjectRemovePropertyListener(
+            device_id,
+            &address,
+            Some(property_listener),
+            ptr::null_mut(),
+        );
+    }
+}
+
+fn watch_thread(
+    rx: Receiver<(AudioObjectID, u32)>,
+    on_change: impl Fn(&DeviceChange, RerouteAction),
+) {
+    let mut known = snapshot_devices();
+    let mut default_output = get_default_device(kAudioHardwarePropertyDefaultOutputDevice);
+    let mut default_input = get_default_device(kAudioHardwarePropertyDefaultInputDevice);
+
+    while let Ok(first) = rx.recv() {
+        // 抜き差しでは複数の通知が続けて来るのでまとめて処理する
+        std::thread::sleep(Duration::from_millis(DEBOUNCE_MS));
+        let mut notifications = vec![first];
+        notifications.extend(rx.try_iter());
+        let selectors: Vec<u32> = notifications.iter().map(|&(_, sel)| sel).collect();
+
+        let mut changes = Vec::new();
+
+        // デバイス一覧の更新より先に比べる（同時に来ると新しい一覧では差が見えない）
+        let mut reconfigured: Vec<u32> = notifications
+            .iter()
+            .filter(|&&(_, sel)| sel == kAudioDevicePropertyStreamConfiguration)
+            .map(|&(id, _)| id)
+            .collect();
+        reconfigured.sort_unstable();
+        reconfigured.dedup();
+        for device_id in reconfigured {
+            let Some(info) = known.get_mut(&device_id) else {
+                continue;
+            };
+            let channels = crate::capture::get_device_input_channels(device_id);
+            if channels != info.input_channels {
+                info.input_channels = channels;
+                changes.push(DeviceChange::ChannelsChanged(info.clone()));
+            }
+        }
+
+        if selectors.contains(&kAudioHardwarePropertyDevices) {
+            let current = snapshot_devices();
+            changes.extend(diff_devices(&known, &current));
+            known = current;
+        }
+        if selectors.contains(&kAudioHardwarePropertyDefaultOutputDevice) {
+            let id = get_default_device(kAudioHardwarePropertyDefaultOutputDevice);
+            if id != default_output {
+                default_output = id;
+                changes.push(DeviceChange::DefaultOutputChanged(id.unwrap_or(0)));
+            }
+        }
+        if selectors.contains(&kAudioHardwarePropertyDefaultInputDevice) {
+            let id = get_default_device(kAudioHardwarePropertyDefaultInputDevice);
+            if id != default_input {
+                default_input = id;
+                changes.push(DeviceChange::DefaultInputChanged(id.unwrap_or(0)));
+            }
+        }
+
+        for change in changes {
+            let action = handle_device_change(&change);
+            on_change(&change, action);
+        }
+    }
+}
+
+/// Pause or resume everything bound to a changed device
+pub fn handle_device_change(change: &DeviceChange) -> RerouteAction {
+    match change {
+        DeviceChange::Disconnected(info) => pause_device(info),
+        DeviceChange::Connected(info) => resume_device(info),
+        DeviceChange::ChannelsChanged(info) => reconfigure_device(info),
+        DeviceChange::DefaultOutputChanged(device_id) => follow_default_output(*device_id),
+        DeviceChange::DefaultInputChanged(_) => RerouteAction::None,
+    }
+}
+
+fn follow_default_output(device_id: u32) -> RerouteAction {
+    let retargeted = crate::audio::output::retarget_default_sinks(device_id).len();
+    if retargeted == 0 {
+        return RerouteAction::None;
+    }
+    RerouteAction::Retargeted { retargeted }
+}
+
+fn pause_device(info: &DeviceInfo) -> RerouteAction {
+    let output = crate::audio::output::is_output_device_running(info.device_id);
+    let input = crate::capture::is_device_capturing(info.device_id);
+    if !output && !input {
+        return RerouteAction::None;
+    }
+
+    if output {
+        crate::audio::output::stop_output_device(info.device_id);
+    }
+    if input {
+        crate::capture::stop_input_capture(info.device_id);
+    }
+    log_info!(
+        "[Device] '{}' disconnected; paused{}{}",
+        info.name,
+        if output { " output" } else { "" },
+        if input { " capture" } else { "" }
+    );
+
+    // UID がなければ再接続を判別できないので一時停止のみ
+    if let Some(uid) = &info.uid {
+        SUSPENDED.lock().insert(
+            uid.clone(),
+            Suspended {
+                device_id: info.device_id,
+                output,
+                input,
+            },
+        );
+    }
+    RerouteAction::Paused
+}
+
+fn resume_device(info: &DeviceInfo) -> RerouteAction {
+    let Some(suspended) = info
+        .uid
+        .as_ref()
+        .and_then(|uid| SUSPENDED.lock().remove(uid))
+    else {
+        return RerouteAction::None;
+    };
+
+    // 再接続で AudioObjectID が変わることがあるので、グラフのノードを付け替える
+    let rebound = get_graph_processor()
+        .with_graph_mut(|graph| graph.rebind_device(suspended.device_id, info.device_id));
+
+    if suspended.input {
+        if let Err(e) = crate::capture::start_input_capture(info.device_id) {
+            log_error!(
+                "[Device] Failed to resume capture on '{}': {}",
+                info.name,
+                e
+            );
+        }
+    }
+    if suspended.output {
+        // 他の出力が動いていればそれがクロックマスターのまま、このデバイスを追加する
+        if let Err(e) = crate::audio::output::start_output_device(info.device_id) {
+            log_error!("[Device] Failed to resume output on '{}': {}", info.name, e);
+        }
+    }
+    log_info!(
+        "[Device] '{}' reconnected (ID {} -> {}); resumed, {} node(s) rebound",
+        info.name,
+        suspended.device_id,
+        info.device_id,
+        rebound
+    );
+    RerouteAction::Resumed { rebound }
+}
+
+fn reconfigure_device(info: &DeviceInfo) -> RerouteAction {
+    // 古いチャンネル数のリングバッファを読み続けないよう、キャプチャごと作り直す
+    if crate::capture::is_device_capturing(info.device_id) {
+        if let Err(e) = crate::capture::restart_device_capture(info.device_id) {
+            log_error!(
+                "[Device] Failed to rebuild capture on '{}': {}",
+                info.name,
+                e
+            );
+        }
+    }
+    let resized = get_graph_processor().with_graph_mut(|graph| {
+        graph
+            .resize_device_sources(info.device_id, info.input_channels as usize)
+            .len()
+    });
+    log_info!(
+        "[Device] '{}' now has {} input channel(s); capture rebuilt, {} source(s) resized",
+        info.name,
+        info.input_channels,
+        resized
+    );
+    RerouteAction::Resized { resized }
+}
+
+/// Wait for a device that is not connected yet
+///
+/// 未接続デバイスのノード（プロジェクト読み込み時のプレースホルダー ID）を登録しておくと、
+/// 接続時に `device_id` から新しい ID へ付け替えて出力/キャプチャを開始する。
+pub fn await_device(uid: &str, device_id: u32, output: bool, input: bool) {
+    SUSPENDED.lock().insert(
+        uid.to_string(),
+        Suspended {
+            device_id,
+            output,
+            input,
+        },
+    );
+}
+
+/// Enumerate current devices
+pub fn snapshot_devices() -> HashMap<u32, DeviceInfo> {
+    let Ok(ids) = get_audio_device_ids() else {
+        return HashMap::new();
+    };
+    ids.into_iter()
+        .map(|id| {
+            let info = DeviceInfo {
+                device_id: id,
+                uid: get_device_uid(id),
+                name: get_device_name(id).unwrap_or_else(|_| format!("Device {}", id)),
+                input_channels: crate::capture::get_device_input_channels(id),
+                output_channels: get_device_output_channels(id),
+            };
+            (id, info)
+        })
+        .collect()
+}
+
+/// Compare two enumerations (UID が同じなら ID が変わっても同一デバイス)
+fn diff_devices(
+    before: &HashMap<u32, DeviceInfo>,
+    after: &HashMap<u32, DeviceInfo>,
+) -> Vec<DeviceChange> {
+    let same = |a: &DeviceInfo, b: &DeviceInfo| match (&a.uid, &b.uid) {
+        (Some(x), Some(y)) => x == y,
+        _ => a.device_id == b.device_id,
+    };
+
+    let mut changes: Vec<DeviceChange> = before
+        .values()
+        .filter(|old| !after.values().any(|new| same(old, new)))
+        .cloned()
+        .map(DeviceChange::Disconnected)
+        .collect();
+    changes.extend(
+        after
+            .values()
+            .filter(|new| !before.values().any(|old| same(old, new)))
+            .cloned()
+            .map(DeviceChange::Connected),
+    );
+    changes
+}
+
+fn system_address(selector: u32) -> AudioObjectPropertyAddress {
+    AudioObjectPropertyAddress {
+        mSelector: selector,
+        mScope: kAudioObjectPropertyScopeGlobal,
+        mElement: kAudioObjectPropertyElementMaster,
+    }
+}
+
+fn input_stream_address() -> AudioObjectPropertyAddress {
+    AudioObjectPropertyAddress {
+        mSelector: kAudioDevicePropertyStreamConfiguration,
+        mScope: kAudioDevicePropertyScopeInput,
+        mElement: kAudioObjectPropertyElementMaster,
+    }
+}
+
+/// Current system default output device
+pub fn default_output_device() -> Option<u32> {
+    get_default_device(kAudioHardwarePropertyDefaultOutputDevice)
+}
+
+/// Current system default device for a selector
+fn get_default_device(selector: u32) -> Option<u32> {
+    let address = system_address(selector);
+    let mut device_id: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        AudioObjectGetPropertyData(
+            kAudioObjectSystemObject,
+            &address,
+            0,
+            ptr::null(),
+            &mut size,
+            &mut device_id as *mut u32 as *mut _,
+        )
+    };
+    (status == 0 && device_id != 0).then_some(device_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: u32, uid: &str) -> DeviceInfo {
+        DeviceInfo {
+            device_id: id,
+            uid: Some(uid.to_string()),
+            name: uid.to_string(),
+            input_channels: 0,
+            output_channels: 2,
+        }
+    }
+
+    #[test]
+    fn test_diff_devices_matches_by_uid() {
+        let before: HashMap<u32, DeviceInfo> =
+            [(1, info(1, "builtin")), (2, info(2, "usb"))].into();
+        // "usb" re-enumerated with a new ID, "phones" is new, "builtin" is gone
+        let after: HashMap<u32, DeviceInfo> = [(5, info(5, "usb")), (6, info(6, "phones"))].into();
+
+        let changes = diff_devices(&before, &after);
+        assert_eq!(changes.len(), 2);
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, DeviceChange::Disconnected(d) if d.device_id == 1)));
+        assert!(changes
+            .iter()
+            .any(|c| matches!(c, DeviceChange::Connected(d) if d.device_id == 6)));
+    }
+}
@@ -1,5 +1,11 @@
 //! Device Module - Audio device enumeration and management
 
+mod aggregate;
+mod clock;
 mod enumerate;
+mod hotplug;
 
+pub use aggregate::*;
+pub use clock::*;
 pub use enumerate::*;
+pub use hotplug::*;
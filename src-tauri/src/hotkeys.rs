@@ -0,0 +1,202 @@
+//! Global keyboard shortcuts - mute toggles, gain nudges, scene recall, markers
+//!
+//! バインドは settings.json の `hotkeys` に保存し、起動時と変更時に登録し直す。
+//! ショートカットのハンドラはメインスレッドで呼ばれるので、アクションは非同期ランタイムに
+//! 投げて、UI と同じ API（Atomic なパラメータ更新 + イベント通知）で実行する。
+//!
+//! ノードは stable_id で参照するので、アクションのたびに現在のハンドルを引き直す。
+
+use crate::api::dto::{EdgeInfoDto, GraphDto, HotkeyActionDto, HotkeyBindingDto, PortId};
+use crate::api::SpectrumError;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::sink::SinkNode;
+use crate::audio::taper::{db_to_linear, linear_to_db, MAX_DB};
+use crate::audio::NodeHandle;
+use parking_lot::RwLock;
+use std::sync::OnceLock;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+/// Nudging up from silence starts here; nudging below it mutes (dBFS)
+const NUDGE_FLOOR_DB: f32 = -60.0;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Registered shortcuts and their actions
+static BINDINGS: RwLock<Vec<(Shortcut, HotkeyActionDto)>> = RwLock::new(Vec::new());
+
+/// Install the global shortcut plugin and register the saved bindings (call once from `setup`)
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    app.plugin(
+        tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(|_app, shortcut, event| {
+                if event.state() == ShortcutState::Pressed {
+                    on_pressed(shortcut);
+                }
+            })
+            .build(),
+    )?;
+    let _ = APP.set(app.clone());
+    register(&crate::settings::get().hotkeys);
+    Ok(())
+}
+
+/// Parse an accelerator ("CmdOrCtrl+Shift+M", "Alt+F10", ...)
+pub fn parse_shortcut(accelerator: &str) -> Result<Shortcut, String> {
+    accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut '{}': {}", accelerator, e))
+}
+
+/// Replace the registered shortcuts
+///
+/// Returns the accelerators that could not be registered (usually taken by another app).
+pub fn register(bindings: &[HotkeyBindingDto]) -> Vec<String> {
+    let Some(app) = APP.get() else {
+        return Vec::new();
+    };
+    let shortcuts = app.global_shortcut();
+    if let Err(e) = shortcuts.unregister_all() {
+        log_warn!("[Hotkeys] Failed to unregister shortcuts: {}", e);
+    }
+
+    let mut active: Vec<(Shortcut, HotkeyActionDto)> = Vec::new();
+    let mut failed = Vec::new();
+    for binding in bindings {
+        let registered = parse_shortcut(&binding.shortcut).and_then(|shortcut| {
+            if active.iter().any(|(s, _)| *s == shortcut) {
+                return Err(format!("'{}' is bound twice", binding.shortcut));
+            }
+            shortcuts
+                .register(shortcut)
+                .map(|()| shortcut)
+                .map_err(|e| e.to_string())
+        });
+        match registered {
+            Ok(shortcut) => active.push((shortcut, binding.action.clone())),
+            Err(e) => {
+                log_warn!("[Hotkeys] Skipping {}: {}", binding.shortcut, e);
+                failed.push(binding.shortcut.clone());
+            }
+        }
+    }
+
+    log_info!("[Hotkeys] {} shortcut(s) registered", active.len());
+    *BINDINGS.write() = active;
+    failed
+}
+
+fn on_pressed(shortcut: &Shortcut) {
+    let action = BINDINGS
+        .read()
+        .iter()
+        .find(|(s, _)| s == shortcut)
+        .map(|(_, action)| action.clone());
+    let Some(action) = action else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::api::history::as_actor("hotkey", run_action(&action)).await {
+            log_warn!("[Hotkeys] {:?} failed: {}", action, e);
+        }
+    });
+}
+
+async fn run_action(action: &HotkeyActionDto) -> Result<(), SpectrumError> {
+    match action {
+        HotkeyActionDto::ToggleEdgeMute {
+            source,
+            source_port,
+            target,
+            target_port,
+        } => {
+            let graph = crate::api::get_graph().await?;
+            let edge = find_edge(&graph, source, *source_port, target, *target_port)?;
+            crate::api::set_edge_muted(edge.id, !edge.muted).await
+        }
+        HotkeyActionDto::ToggleNodeMute { node } => {
+            let graph = crate::api::get_graph().await?;
+            let handle = find_node(&graph, node)?;
+            let sends: Vec<&EdgeInfoDto> =
+                graph.edges.iter().filter(|e| e.source == handle).collect();
+            // 一つでも鳴っていれば全部ミュート、全部ミュート済みなら全部解除
+            let mute = sends.iter().any(|e| !e.muted);
+            for edge in sends {
+                crate::api::set_edge_muted(edge.id, mute).await?;
+            }
+            Ok(())
+        }
+        HotkeyActionDto::NudgeEdgeGain {
+            source,
+            source_port,
+            target,
+            target_port,
+            db,
+        } => {
+            let graph = crate::api::get_graph().await?;
+            let edge = find_edge(&graph, source, *source_port, target, *target_port)?;
+            crate::api::set_edge_gain(edge.id, nudge(edge.gain, *db)).await
+        }
+        HotkeyActionDto::NudgeOutputGain { node, db } => {
+            let graph = crate::api::get_graph().await?;
+            let handle = find_node(&graph, node)?;
+            let current = get_graph_processor()
+                .with_graph(|g| {
+                    g.get_node(NodeHandle::from_raw(handle))
+                        .and_then(|n| n.as_any().downcast_ref::<SinkNode>())
+                        .map(|sink| sink.output_gain_for_port(0))
+                })
+                .ok_or(SpectrumError::WrongNodeType {
+                    handle,
+                    expected: "sink",
+                })?;
+            crate::api::set_output_gain(handle, nudge(current, *db)).await
+        }
+        HotkeyActionDto::RecallScene { name, fade_ms } => {
+            crate::api::recall_scene(name.clone(), Some(*fade_ms))
+                .await
+                .map(|_| ())
+        }
+        HotkeyActionDto::AddMarker { label } => {
+            crate::api::add_marker(label.clone()).await.map(|_| ())
+        }
+    }
+}
+
+fn find_node(graph: &GraphDto, stable_id: &str) -> Result<u32, SpectrumError> {
+    graph
+        .node_by_stable_id(stable_id)
+        .map(|n| n.handle())
+        .ok_or_else(|| SpectrumError::InvalidState(format!("{} is not in the graph", stable_id)))
+}
+
+fn find_edge<'a>(
+    graph: &'a GraphDto,
+    source: &str,
+    source_port: PortId,
+    target: &str,
+    target_port: PortId,
+) -> Result<&'a EdgeInfoDto, SpectrumError> {
+    let (source, target) = (find_node(graph, source)?, find_node(graph, target)?);
+    graph
+        .edge_between(source, source_port, target, target_port)
+        .ok_or_else(|| {
+            SpectrumError::InvalidState(format!(
+                "No edge {}:{} -> {}:{}",
+                source, source_port, target, target_port
+            ))
+        })
+}
+
+/// Linear gain after a step of `db`
+fn nudge(gain: f32, db: f32) -> f32 {
+    let current = linear_to_db(gain)
+        .unwrap_or(NUDGE_FLOOR_DB)
+        .max(NUDGE_FLOOR_DB);
+    let next = current + db;
+    if next <= NUDGE_FLOOR_DB {
+        0.0
+    } else {
+        db_to_linear(Some(next.min(MAX_DB)))
+    }
+}
@@ -8,10 +8,19 @@
 // v2 Modules (New Architecture)
 // =============================================================================
 
+#[macro_use]
+mod logging; // log_info!/rt_log! macros, recent logs, log file (declared first for the macros)
 pub mod api; // Tauri commands and DTOs
+mod app_icon; // App icons of Prism clients (PNG)
 pub mod audio; // AudioGraph, AudioNode, Edge, Meters
+mod bonjour; // Bonjour advertisement of the control services
 pub mod capture; // Input audio capture
+mod crash; // Panic hook and crash reports (engine state snapshot)
 pub mod device; // Device enumeration
+mod hotkeys; // Global keyboard shortcuts
+mod permission; // Microphone permission (TCC)
+mod tray; // Menu bar extra (quick mutes / output / engine)
+mod url_scheme; // spectrum:// automation URLs
 
 // =============================================================================
 // Legacy Modules (To be deprecated/refactored)
@@ -20,7 +29,9 @@ pub mod device; // Device enumeration
 mod audio_capture; // Legacy capture (wrapped by capture module)
 mod audio_unit; // AudioUnit plugin management
 mod audio_unit_ui; // AudioUnit UI
+mod plugin_registry; // AudioUnit registry (cached scan)
 pub mod prismd; // Prism daemon communication
+mod settings; // settings.json (engine settings)
 mod vdsp; // vDSP hardware acceleration
 
 use serde::{Deserialize, Serialize};
@@ -67,6 +78,7 @@ pub struct DriverStatus {
     pub connected: bool,
     pub sample_rate: u32,
     pub buffer_size: u32,
+    pub channel_count: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -95,59 +107,266 @@ pub struct UiStateCache(pub Mutex<Option<api::dto::UIStateDto>>);
 // =============================================================================
 
 // Device Commands
+pub use api::create_aggregate_device;
+pub use api::destroy_aggregate_device;
+pub use api::get_audio_permission_status;
 pub use api::get_input_devices;
 pub use api::get_output_devices;
 pub use api::get_prism_status;
+pub use api::request_audio_permission;
+
+// Prism Channel Commands
+pub use api::assign_app_to_channel;
+pub use api::get_channel_map;
+pub use api::release_app_channel;
+pub use api::set_client_gain;
+
+// Prism Driver Commands
+pub use api::get_prism_driver;
+pub use api::set_prism_buffer_size;
+pub use api::set_prism_channel_count;
 
 // Graph Commands
 pub use api::add_bus_node;
 pub use api::add_edge;
 pub use api::add_sink_node;
 pub use api::add_source_node;
+pub use api::apply_graph;
+pub use api::execute_batch;
 pub use api::get_graph;
 pub use api::remove_edge;
 pub use api::remove_node;
+pub use api::set_bus_port_count;
+pub use api::set_node_color;
+pub use api::set_node_label;
+pub use api::set_node_metadata;
 
 // Edge Commands (Hot Path)
+pub use api::crossfade_edges;
+pub use api::get_fader_taper;
+pub use api::get_gain_ramp_time;
+pub use api::set_edge_channel_trim;
 pub use api::set_edge_gain;
+pub use api::set_edge_gain_db;
 pub use api::set_edge_gains_batch;
 pub use api::set_edge_muted;
+pub use api::set_edge_pan;
+pub use api::set_edge_params;
+pub use api::set_edge_pinned;
+pub use api::set_edge_sum_law;
+pub use api::set_edge_tap_point;
+pub use api::set_fader_taper;
+pub use api::set_gain_ramp_time;
+pub use api::set_monitor_mode;
+
+// Automation Commands
+pub use api::get_automation_status;
+pub use api::get_edge_automation;
+pub use api::set_edge_automation;
+pub use api::start_automation;
+pub use api::stop_automation;
+
+// Source Commands
+pub use api::get_source_auto_gate;
+pub use api::set_source_auto_gate;
+pub use api::set_source_phase;
+pub use api::set_source_trim;
+
+// Solo Commands
+pub use api::clear_solo;
+pub use api::get_solo_state;
+pub use api::set_edge_solo;
+pub use api::set_node_solo;
+pub use api::set_solo_mode;
+
+// Cue Commands
+pub use api::get_cue_state;
+pub use api::set_cue_sink;
+pub use api::set_edge_cue;
+
+// Ducking Commands
+pub use api::get_ducking_rules;
+pub use api::remove_ducking_rule;
+pub use api::set_ducking_rule;
+
+// Edge Link Commands
+pub use api::get_edge_link_groups;
+pub use api::link_edges;
+pub use api::unlink_edges;
 
 // Plugin Commands
 pub use api::add_plugin_to_bus;
 pub use api::close_plugin_ui;
 pub use api::get_available_plugins;
+pub use api::get_plugin_out_of_process;
+pub use api::get_plugin_parameters;
 pub use api::open_plugin_ui;
+pub use api::open_plugin_ui_embedded;
 pub use api::remove_plugin_from_bus;
 pub use api::reorder_plugins;
+pub use api::rescan_plugins;
+pub use api::restart_plugin;
 pub use api::set_plugin_enabled;
+pub use api::set_plugin_mix;
+pub use api::set_plugin_out_of_process;
+pub use api::set_plugin_parameter;
+pub use api::set_plugin_ring_out;
+
+// Native DSP Commands
+pub use api::get_native_dsp_params;
+pub use api::set_native_dsp_params;
+
+// Record Commands
+pub use api::add_record_node;
+pub use api::get_recording_status;
+pub use api::punch_in;
+pub use api::punch_out;
+pub use api::set_record_armed;
+pub use api::start_recording;
+pub use api::stop_recording;
+
+// Marker Commands
+pub use api::add_marker;
+pub use api::clear_markers;
+pub use api::export_markers;
+pub use api::get_markers;
+
+// Bounce Commands
+pub use api::bounce_graph;
+pub use api::cancel_bounce;
+
+// Freeze Commands
+pub use api::freeze_bus;
+pub use api::unfreeze_bus;
+
+// Generator Commands
+pub use api::add_generator_node;
+pub use api::set_generator_params;
+
+// Plugin Source Commands
+pub use api::add_plugin_source_node;
+pub use api::get_available_source_plugins;
+pub use api::set_plugin_source_enabled;
+
+// Matrix Commands
+pub use api::add_matrix_node;
+pub use api::set_matrix_coefficients;
+
+// Loopback Commands
+pub use api::add_loopback_pair;
+
+// Network Audio Commands
+pub use api::add_net_receive_node;
+pub use api::add_net_send_node;
+pub use api::get_net_node_status;
+
+// Analyzer Commands
+pub use api::get_spectrum;
+pub use api::remove_spectrum_tap;
+pub use api::set_spectrum_config;
 
 // Meter Commands
+pub use api::configure_metering;
 pub use api::get_edge_meters;
+pub use api::get_meter_history;
+pub use api::get_meter_stream_rate;
+pub use api::get_metering_config;
 pub use api::get_meters;
+pub use api::get_meters_packed;
 pub use api::get_node_meters;
+pub use api::reset_meter_clips;
+pub use api::set_meter_stream_rate;
+pub use api::set_metering_enabled;
+pub use api::subscribe_meters;
+pub use api::unsubscribe_meters;
+
+// Scene Commands
+pub use api::delete_scene;
+pub use api::list_scenes;
+pub use api::recall_scene;
+pub use api::save_scene;
+
+// Channel Template Commands
+pub use api::create_bus_from_template;
+pub use api::delete_channel_template;
+pub use api::list_channel_templates;
+pub use api::save_channel_template;
 
 // State Commands
+pub use api::check_state_integrity;
+pub use api::discard_autosave;
+pub use api::get_autosave_recovery;
+pub use api::get_mutation_history;
+pub use api::list_state_backups;
 pub use api::load_graph_state;
 pub use api::persist_state;
 pub use api::persist_state_background;
+pub use api::recover_autosave;
+pub use api::recover_state_from_backup;
+pub use api::repair_state;
 pub use api::restore_state;
 pub use api::save_graph_state;
 pub use api::set_ui_state_cache;
 
+// Project Commands
+pub use api::export_graph;
+pub use api::import_graph;
+pub use api::list_recent_projects;
+pub use api::open_project;
+pub use api::save_project;
+
+// Settings Commands
+pub use api::get_hotkeys;
+pub use api::get_http_api_status;
+pub use api::get_settings;
+pub use api::set_hotkeys;
+pub use api::set_http_api;
+pub use api::update_settings;
+
+// Logging Commands
+pub use api::clear_log_level;
+pub use api::get_last_crash_report;
+pub use api::get_log_levels;
+pub use api::get_recent_logs;
+pub use api::set_log_level;
+
 // System Commands
 pub use api::get_app_icon_by_pid;
+pub use api::get_app_icons;
+pub use api::get_buffer_diagnostics;
+pub use api::get_device_clock_info;
+pub use api::get_dsp_profile;
+pub use api::get_plugin_performance;
+pub use api::get_sample_rate;
+pub use api::get_session_stats;
 pub use api::get_system_status;
+pub use api::get_xrun_stats;
 pub use api::open_prism_app;
+pub use api::reset_xrun_stats;
 pub use api::set_buffer_size;
+pub use api::set_device_buffer_size;
+pub use api::set_device_clock_source;
+pub use api::set_device_nominal_sample_rate;
+pub use api::set_device_rate_policy;
+pub use api::set_sample_rate;
 pub use api::start_audio;
 pub use api::stop_audio;
 pub use api::stop_output_runtime;
 // Output runtime
 pub use api::get_output_runtime;
+pub use api::get_output_runtimes;
+pub use api::start_output_device;
+pub use api::stop_output_device;
+pub use api::switch_output_device;
 // Output master
+pub use api::reset_output_protection;
 pub use api::set_output_channel_gain;
 pub use api::set_output_gain;
+pub use api::set_output_gain_db;
+pub use api::set_output_protection;
+pub use api::set_sink_channel_map;
+pub use api::set_sink_follow_default;
+pub use api::set_sink_limiter;
 
 // =============================================================================
 // Legacy Commands (For backward compatibility)
@@ -158,6 +377,8 @@ async fn get_prism_clients() -> Result<Vec<ClientInfo>, String> {
     prismd::get_clients().await.map_err(|e| e.to_string())
 }
 
+// Routing commands take absolute channel offsets and never check for conflicts;
+// prefer the v2 assign_app_to_channel / release_app_channel (stereo pair indices).
 #[tauri::command]
 async fn set_routing(pid: i32, offset: u32) -> Result<RoutingUpdate, String> {
     prismd::set_routing(pid, offset)
@@ -181,12 +402,14 @@ async fn set_client_routing(client_id: u32, offset: u32) -> Result<ClientRouting
 
 #[tauri::command]
 async fn get_driver_status() -> Result<DriverStatus, String> {
-    let connected = prismd::is_connected() || capture::is_capture_running();
+    // prismd が答えればドライバーの実際の構成、答えなければ Prism デバイスから読んだ値
+    let driver = api::get_prism_driver().await.map_err(|e| e.to_string())?;
 
     Ok(DriverStatus {
-        connected,
-        sample_rate: 48000, // TODO: Get from config
-        buffer_size: capture::get_io_buffer_size() as u32,
+        connected: driver.connected,
+        sample_rate: driver.sample_rate,
+        buffer_size: driver.buffer_size,
+        channel_count: driver.channel_count,
     })
 }
 
@@ -242,8 +465,8 @@ fn get_input_devices_legacy() -> Vec<InputDeviceInfo> {
 }
 
 #[tauri::command]
-fn start_input_capture(device_id: u32) -> Result<bool, String> {
-    capture::start_input_capture(device_id)
+fn start_input_capture(device_id: u32) -> Result<bool, api::SpectrumError> {
+    Ok(capture::start_input_capture(device_id)?)
 }
 
 #[tauri::command]
@@ -273,7 +496,7 @@ fn is_device_capturing(device_id: u32) -> bool {
 
 #[tauri::command]
 fn set_io_buffer_size(size: u32) -> Result<(), String> {
-    capture::set_io_buffer_size(size as usize);
+    capture::apply_io_buffer_size(size as usize);
     Ok(())
 }
 
@@ -294,7 +517,7 @@ pub struct PluginInfo {
 
 #[tauri::command]
 fn get_plugins() -> Vec<PluginInfo> {
-    audio_unit::get_effect_audio_units()
+    plugin_registry::effects()
         .into_iter()
         .map(|p| PluginInfo {
             id: p.id,
@@ -333,50 +556,101 @@ fn get_processes() -> Vec<PrismProcess> {
 /// Generate Tauri command handlers
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Before anything can panic (setup, engine init)
+    crate::crash::install();
+
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(UiStateCache::default())
-        .setup(|_app| {
+        .setup(|app| {
+            // Log file + realtime log drain before anything else logs
+            crate::logging::init(dirs::data_dir().map(|d| d.join("spectrum").join("logs")));
+
+            // Uptime of get_session_stats counts from here
+            crate::audio::session_stats::init();
+
+            // Push event stream (graph changes / meters)
+            crate::api::events::init(app.handle().clone());
+
+            // settings.json (buffer size / sample rate / meter rate ...) before the engine starts
+            crate::settings::apply_startup();
+
+            // Autosave scheduler (+ keeps a crash autosave for recovery)
+            crate::api::autosave::init();
+
+            // AudioUnit の列挙は重いのでバックグラウンドで（キャッシュがあれば即完了）
+            crate::plugin_registry::start_background_scan();
+
+            // HTTP control API (only when enabled in settings.json)
+            crate::api::http::apply_startup();
+
+            // Global shortcuts from settings.json
+            if let Err(e) = crate::hotkeys::init(app.handle()) {
+                log_warn!(
+                    "[Spectrum] Warning: Failed to install global shortcuts: {}",
+                    e
+                );
+            }
+
+            // Plugin UIs tell the main window apart from dialogs opened by plugins
+            if let Some(ns_window) = app
+                .get_webview_window("main")
+                .and_then(|window| window.ns_window().ok())
+            {
+                crate::audio_unit_ui::set_host_window(ns_window);
+            }
+
+            // Menu bar extra (keeps the engine reachable while the window is closed)
+            if let Err(e) = crate::tray::init(app.handle()) {
+                log_warn!(
+                    "[Spectrum] Warning: Failed to install menu bar extra: {}",
+                    e
+                );
+            }
+
             // IMPORTANT: Do not block `setup` with CoreAudio init.
             // Blocking here delays first paint and results in a white window.
-            println!("[Spectrum] Scheduling audio engine init...");
+            log_info!("[Spectrum] Scheduling audio engine init...");
 
             tauri::async_runtime::spawn_blocking(|| {
-                println!("[Spectrum] Initializing audio engine...");
+                log_info!("[Spectrum] Initializing audio engine...");
 
                 // Start capture first so the initial output can render actual audio.
                 if let Err(e) = crate::capture::start_capture() {
-                    eprintln!(
+                    log_warn!(
                         "[Spectrum] Warning: Failed to start capture on startup: {}",
                         e
                     );
                 }
 
-                // Find preferred output device (aggregate or system default)
-                if let Some(device_id) = crate::device::find_preferred_output_device() {
+                // Output device from settings.json, else aggregate or system default
+                if let Some(device_id) = crate::settings::preferred_output_device()
+                    .or_else(crate::device::find_preferred_output_device)
+                {
                     match crate::audio::output::start_output_v2(device_id) {
                         Ok(_) => {
                             let channels = crate::device::get_device_output_channels(device_id);
-                            println!("[Spectrum] Audio engine initialized successfully");
-                            println!(
+                            log_info!("[Spectrum] Audio engine initialized successfully");
+                            log_info!(
                                 "[Spectrum] Using output device: {} ({} channels)",
-                                device_id, channels
+                                device_id,
+                                channels
                             );
                         }
                         Err(e) => {
-                            eprintln!(
+                            log_warn!(
                                 "[Spectrum] Warning: Failed to initialize audio engine: {}",
                                 e
                             );
-                            eprintln!("[Spectrum] The app will start without audio output.");
+                            log_error!("[Spectrum] The app will start without audio output.");
 
                             // Best-effort cleanup if output fails.
                             crate::capture::stop_capture();
                         }
                     }
                 } else {
-                    eprintln!("[Spectrum] Warning: No suitable output device found");
-                    eprintln!("[Spectrum] The app will start without audio output.");
+                    log_warn!("[Spectrum] Warning: No suitable output device found");
+                    log_error!("[Spectrum] The app will start without audio output.");
                 }
             });
 
@@ -387,30 +661,161 @@ pub fn run() {
             get_input_devices,
             get_output_devices,
             get_prism_status,
+            create_aggregate_device,
+            destroy_aggregate_device,
+            get_audio_permission_status,
+            request_audio_permission,
+            // v2 API - Prism
+            get_channel_map,
+            assign_app_to_channel,
+            release_app_channel,
+            set_client_gain,
+            // v2 API - Prism Driver
+            get_prism_driver,
+            set_prism_buffer_size,
+            set_prism_channel_count,
             // v2 API - Graph
             add_source_node,
             add_bus_node,
+            set_bus_port_count,
             add_sink_node,
             remove_node,
             add_edge,
             remove_edge,
             get_graph,
+            apply_graph,
+            execute_batch,
+            set_node_label,
+            set_node_color,
+            set_node_metadata,
             // v2 API - Edge
             set_edge_gain,
             set_edge_muted,
             set_edge_gains_batch,
+            set_edge_channel_trim,
+            set_edge_pan,
+            set_edge_params,
+            set_edge_sum_law,
+            set_edge_gain_db,
+            get_fader_taper,
+            set_fader_taper,
+            set_gain_ramp_time,
+            get_gain_ramp_time,
+            crossfade_edges,
+            set_monitor_mode,
+            set_edge_tap_point,
+            set_edge_pinned,
+            // v2 API - Automation
+            set_edge_automation,
+            get_edge_automation,
+            start_automation,
+            stop_automation,
+            get_automation_status,
+            // v2 API - Source
+            set_source_trim,
+            set_source_phase,
+            set_source_auto_gate,
+            get_source_auto_gate,
+            // v2 API - Solo
+            set_edge_solo,
+            set_node_solo,
+            set_solo_mode,
+            clear_solo,
+            get_solo_state,
+            // v2 API - Cue
+            set_cue_sink,
+            set_edge_cue,
+            get_cue_state,
+            // v2 API - Ducking
+            set_ducking_rule,
+            remove_ducking_rule,
+            get_ducking_rules,
+            // v2 API - Edge links
+            link_edges,
+            unlink_edges,
+            get_edge_link_groups,
             // v2 API - Plugin
             get_available_plugins,
+            rescan_plugins,
             add_plugin_to_bus,
             remove_plugin_from_bus,
             reorder_plugins,
             set_plugin_enabled,
+            set_plugin_mix,
+            set_plugin_ring_out,
             open_plugin_ui,
+            open_plugin_ui_embedded,
             close_plugin_ui,
+            get_plugin_parameters,
+            set_plugin_parameter,
+            restart_plugin,
+            set_plugin_out_of_process,
+            get_plugin_out_of_process,
+            // v2 API - Native DSP
+            get_native_dsp_params,
+            set_native_dsp_params,
+            // v2 API - Record
+            add_record_node,
+            start_recording,
+            stop_recording,
+            get_recording_status,
+            set_record_armed,
+            punch_in,
+            punch_out,
+            // v2 API - Markers
+            add_marker,
+            get_markers,
+            clear_markers,
+            export_markers,
+            // v2 API - Bounce
+            bounce_graph,
+            cancel_bounce,
+            // v2 API - Freeze
+            freeze_bus,
+            unfreeze_bus,
+            // v2 API - Generator
+            add_generator_node,
+            set_generator_params,
+            // v2 API - Plugin Source
+            add_plugin_source_node,
+            get_available_source_plugins,
+            set_plugin_source_enabled,
+            // v2 API - Matrix
+            add_matrix_node,
+            set_matrix_coefficients,
+            // v2 API - Loopback
+            add_loopback_pair,
+            // v2 API - Network Audio
+            add_net_send_node,
+            add_net_receive_node,
+            get_net_node_status,
+            // v2 API - Analyzer
+            get_spectrum,
+            set_spectrum_config,
+            remove_spectrum_tap,
             // v2 API - Meter
             get_meters,
             get_node_meters,
             get_edge_meters,
+            get_meters_packed,
+            subscribe_meters,
+            unsubscribe_meters,
+            set_meter_stream_rate,
+            get_meter_stream_rate,
+            configure_metering,
+            get_metering_config,
+            set_metering_enabled,
+            reset_meter_clips,
+            get_meter_history,
+            // v2 API - Scene
+            save_scene,
+            recall_scene,
+            list_scenes,
+            delete_scene,
+            save_channel_template,
+            list_channel_templates,
+            delete_channel_template,
+            create_bus_from_template,
             // v2 API - State
             save_graph_state,
             load_graph_state,
@@ -418,6 +823,27 @@ pub fn run() {
             persist_state_background,
             restore_state,
             set_ui_state_cache,
+            list_state_backups,
+            recover_state_from_backup,
+            check_state_integrity,
+            repair_state,
+            get_autosave_recovery,
+            recover_autosave,
+            discard_autosave,
+            get_mutation_history,
+            // v2 API - Project
+            save_project,
+            open_project,
+            list_recent_projects,
+            export_graph,
+            import_graph,
+            // v2 API - Settings
+            get_settings,
+            update_settings,
+            get_hotkeys,
+            set_hotkeys,
+            get_http_api_status,
+            set_http_api,
             // v2 API - System
             start_audio,
             stop_audio,
@@ -425,12 +851,42 @@ pub fn run() {
             get_system_status,
             open_prism_app,
             get_app_icon_by_pid,
+            get_app_icons,
             set_buffer_size,
+            set_device_buffer_size,
+            get_sample_rate,
+            set_sample_rate,
+            get_device_clock_info,
+            set_device_nominal_sample_rate,
+            set_device_rate_policy,
+            set_device_clock_source,
+            get_dsp_profile,
+            get_plugin_performance,
+            get_session_stats,
+            get_buffer_diagnostics,
+            get_xrun_stats,
+            reset_xrun_stats,
+            // v2 API - Logging
+            get_recent_logs,
+            get_last_crash_report,
+            get_log_levels,
+            set_log_level,
+            clear_log_level,
             // v2 API - Output runtime
             get_output_runtime,
+            get_output_runtimes,
+            start_output_device,
+            stop_output_device,
+            switch_output_device,
             // v2 API - Output master
             set_output_gain,
+            set_output_gain_db,
             set_output_channel_gain,
+            set_sink_channel_map,
+            set_sink_follow_default,
+            set_sink_limiter,
+            set_output_protection,
+            reset_output_protection,
             // Legacy commands
             get_prism_clients,
             set_routing,
@@ -451,10 +907,33 @@ pub fn run() {
             get_plugins,
             get_processes,
         ])
+        .on_window_event(|window, event| {
+            // メニューバーから操作できる間はウィンドウを閉じても隠すだけ（エンジンは動かし続ける）
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if crate::tray::is_active() {
+                    api.prevent_close();
+                    let _ = window.hide();
+                }
+            }
+        })
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
     app.run(|app_handle, event| {
+        match &event {
+            // Dock icon clicked while the window is hidden
+            tauri::RunEvent::Reopen { .. } => {
+                crate::tray::show_main_window(app_handle);
+                return;
+            }
+            // spectrum:// URLs (Shortcuts / AppleScript / Stream Deck)
+            tauri::RunEvent::Opened { urls } => {
+                crate::url_scheme::handle_urls(urls);
+                return;
+            }
+            _ => {}
+        }
+
         // Save state only when the app is exiting (Cmd+Q, Quit menu, etc.).
         // Do NOT save on window close.
         let should_flush = matches!(
@@ -475,7 +954,7 @@ pub fn run() {
             Err(_) => None,
         };
 
-        println!(
+        log_info!(
             "[Spectrum] Exit flush: ui_state_cached={}",
             if ui_state.is_some() { "yes" } else { "no" }
         );
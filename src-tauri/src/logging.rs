@@ -0,0 +1,475 @@
+//! Logging - levels, per-module filters, recent-log buffer and a rotating log file
+//!
+//! 制御スレッドからは `log_info!` などのマクロで書く（ターゲットは呼び出し元の
+//! モジュールパス）。有効なログはコンソール・直近ログのバッファ・ログファイルに出る。
+//!
+//! オーディオスレッドからは `rt_log!` を使う。メッセージは `&'static str` と数値 1 つに
+//! 限り、ロックフリーのリングバッファに積むだけ（フォーマットもアロケーションもしない）。
+//! 背景スレッドが定期的に取り出して通常のログとして書く。リングが一杯のときや
+//! 他のオーディオスレッドと競合したときは捨てて、捨てた数だけ後で報告する。
+
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use ringbuf::traits::{Consumer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{LazyLock, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Entries kept for `get_recent_logs`
+const RECENT_CAPACITY: usize = 2000;
+
+/// Realtime events buffered between drains
+const RT_CAPACITY: usize = 1024;
+
+/// How often the realtime ring is drained
+const RT_DRAIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Log file size that triggers rotation
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Rotated files kept (`spectrum.log.1` ..)
+const MAX_ROTATED_FILES: usize = 3;
+
+/// ログレベル（小さいほど重要）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl Level {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "error" => Some(Self::Error),
+            "warn" | "warning" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            1 => Self::Error,
+            2 => Self::Warn,
+            4 => Self::Debug,
+            5 => Self::Trace,
+            _ => Self::Info,
+        }
+    }
+}
+
+/// One log line
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    /// Unix time in milliseconds
+    pub timestamp_ms: u64,
+    pub level: Level,
+    /// Module path without the crate name (e.g. "audio::output")
+    pub target: String,
+    pub message: String,
+}
+
+/// Audio-thread event (no owned data)
+#[derive(Debug, Clone, Copy)]
+struct RtEvent {
+    timestamp_ms: u64,
+    level: Level,
+    target: &'static str,
+    message: &'static str,
+    value: Option<i64>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> std::io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 + 1 > MAX_LOG_BYTES {
+            if let Err(e) = self.rotate() {
+                eprintln!("[Logging] Failed to rotate {}: {}", self.path.display(), e);
+            }
+        }
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+
+    /// spectrum.log → .1 → .2 ... (the oldest is removed)
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let rotated = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        let _ = fs::remove_file(rotated(MAX_ROTATED_FILES));
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated(n);
+            if from.exists() {
+                fs::rename(&from, rotated(n + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+static DEFAULT_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+/// Per-module levels (module path prefix, longest match wins)
+static FILTERS: LazyLock<ArcSwap<Vec<(String, Level)>>> =
+    LazyLock::new(|| ArcSwap::from_pointee(Vec::new()));
+
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+
+static FILE: Mutex<Option<LogFile>> = Mutex::new(None);
+
+/// Audio threads share the producer through `try_lock` (never blocks)
+static RT_PRODUCER: OnceLock<Mutex<HeapProd<RtEvent>>> = OnceLock::new();
+
+static RT_DROPPED: AtomicU64 = AtomicU64::new(0);
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// `spectrum_lib::audio::output` → `audio::output`
+fn short_target(target: &str) -> &str {
+    target.split_once("::").map_or(target, |(_, rest)| rest)
+}
+
+/// Start file output in `dir` and the realtime drain thread
+pub fn init(dir: Option<PathBuf>) {
+    if let Some(dir) = dir {
+        match LogFile::open(dir.join("spectrum.log")) {
+            Ok(file) => *FILE.lock() = Some(file),
+            Err(e) => eprintln!(
+                "[Logging] Failed to open log file in {}: {}",
+                dir.display(),
+                e
+            ),
+        }
+    }
+
+    let (producer, consumer) = HeapRb::<RtEvent>::new(RT_CAPACITY).split();
+    if RT_PRODUCER.set(Mutex::new(producer)).is_err() {
+        return;
+    }
+    if let Err(e) = std::thread::Builder::new()
+        .name("spectrum-log".into())
+        .spawn(move || drain_loop(consumer))
+    {
+        eprintln!("[Logging] Failed to start log drain thread: {}", e);
+    }
+}
+
+fn drain_loop(mut consumer: HeapCons<RtEvent>) {
+    loop {
+        std::thread::sleep(RT_DRAIN_INTERVAL);
+        while let Some(event) = consumer.try_pop() {
+            let message = match event.value {
+                Some(value) => format!("{} ({})", event.message, value),
+                None => event.message.to_string(),
+            };
+            if enabled(event.level, event.target) {
+                record(LogEntry {
+                    timestamp_ms: event.timestamp_ms,
+                    level: event.level,
+                    target: short_target(event.target).to_string(),
+                    message,
+                });
+            }
+        }
+        let dropped = RT_DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            write(
+                Level::Warn,
+                module_path!(),
+                format_args!("[Logging] {} realtime log events dropped", dropped),
+            );
+        }
+    }
+}
+
+/// Effective level for a module path
+pub fn level_for(target: &str) -> Level {
+    let target = short_target(target);
+    FILTERS
+        .load()
+        .iter()
+        .filter(|(prefix, _)| {
+            target == prefix
+                || target
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with("::"))
+        })
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, level)| *level)
+        .unwrap_or_else(|| Level::from_u8(DEFAULT_LEVEL.load(Ordering::Relaxed)))
+}
+
+/// Whether a message at `level` from `target` is written
+#[inline]
+pub fn enabled(level: Level, target: &str) -> bool {
+    level <= level_for(target)
+}
+
+/// Default level (`None`) or the level for a module path prefix such as "audio::output"
+pub fn set_level(target: Option<&str>, level: Level) {
+    match target.map(str::trim).filter(|t| !t.is_empty()) {
+        None => DEFAULT_LEVEL.store(level as u8, Ordering::Relaxed),
+        Some(target) => {
+            let target = short_target_owned(target);
+            FILTERS.rcu(|filters| {
+                let mut filters: Vec<_> = filters
+                    .iter()
+                    .filter(|(prefix, _)| *prefix != target)
+                    .cloned()
+                    .collect();
+                filters.push((target.clone(), level));
+                filters
+            });
+        }
+    }
+}
+
+/// Remove a module filter (it falls back to the default level)
+pub fn clear_level(target: &str) {
+    let target = short_target_owned(target.trim());
+    FILTERS.rcu(|filters| {
+        filters
+            .iter()
+            .filter(|(prefix, _)| *prefix != target)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+}
+
+/// Accept both "audio::output" and "spectrum_lib::audio::output"
+fn short_target_owned(target: &str) -> String {
+    match target.strip_prefix(concat!(env!("CARGO_CRATE_NAME"), "::")) {
+        Some(rest) => rest.to_string(),
+        None => target.to_string(),
+    }
+}
+
+/// Default level and module filters
+pub fn levels() -> (Level, Vec<(String, Level)>) {
+    let mut filters = FILTERS.load().as_ref().clone();
+    filters.sort();
+    (
+        Level::from_u8(DEFAULT_LEVEL.load(Ordering::Relaxed)),
+        filters,
+    )
+}
+
+/// Write a message (control threads; use the macros)
+pub fn write(level: Level, target: &str, args: fmt::Arguments) {
+    record(LogEntry {
+        timestamp_ms: now_ms(),
+        level,
+        target: short_target(target).to_string(),
+        message: args.to_string(),
+    });
+}
+
+thread_local! {
+    /// Set on audio callback threads by [`register_realtime_thread`]
+    static REALTIME_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Mark the calling thread as an audio thread (call at the top of each callback)
+///
+/// 登録したスレッドから通常のログを書くとデバッグビルドでは assert で止まる
+/// （`rt_log!` を使うこと）。
+#[inline]
+pub fn register_realtime_thread() {
+    REALTIME_THREAD.with(|rt| rt.set(true));
+}
+
+fn record(entry: LogEntry) {
+    debug_assert!(
+        !REALTIME_THREAD.with(Cell::get),
+        "log written from a realtime thread (use rt_log!): {}",
+        entry.message
+    );
+    if entry.level <= Level::Warn {
+        eprintln!("{}", entry.message);
+    } else {
+        println!("{}", entry.message);
+    }
+
+    if let Some(file) = FILE.lock().as_mut() {
+        file.write_line(&format!(
+            "{} {:5} {}: {}",
+            entry.timestamp_ms,
+            entry.level.as_str().to_ascii_uppercase(),
+            entry.target,
+            entry.message
+        ));
+    }
+
+    let mut recent = RECENT.lock();
+    if recent.len() >= RECENT_CAPACITY {
+        recent.pop_front();
+    }
+    recent.push_back(entry);
+}
+
+/// Queue an audio-thread event (no allocation, never blocks)
+#[inline]
+pub fn rt(level: Level, target: &'static str, message: &'static str, value: Option<i64>) {
+    let Some(producer) = RT_PRODUCER.get() else {
+        return;
+    };
+    let event = RtEvent {
+        timestamp_ms: now_ms(),
+        level,
+        target,
+        message,
+        value,
+    };
+    let pushed = producer
+        .try_lock()
+        .is_some_and(|mut p| p.try_push(event).is_ok());
+    if !pushed {
+        RT_DROPPED.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Most recent entries (oldest first), optionally filtered by level and module prefix
+pub fn recent(limit: usize, min_level: Level, target: Option<&str>) -> Vec<LogEntry> {
+    let target = target.map(short_target_owned);
+    let recent = RECENT.lock();
+    let mut entries: Vec<LogEntry> = recent
+        .iter()
+        .rev()
+        .filter(|e| e.level <= min_level)
+        .filter(|e| target.as_deref().is_none_or(|t| e.target.starts_with(t)))
+        .take(limit)
+        .cloned()
+        .collect();
+    entries.reverse();
+    entries
+}
+
+/// Path of the current log file
+pub fn log_file_path() -> Option<PathBuf> {
+    FILE.lock().as_ref().map(|f| f.path.clone())
+}
+
+/// Log at a level from the calling module
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($level, module_path!()) {
+            $crate::logging::write($level, module_path!(), format_args!($($arg)+));
+        }
+    };
+}
+
+macro_rules! log_error {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Error, $($arg)+) };
+}
+
+macro_rules! log_warn {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Warn, $($arg)+) };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Info, $($arg)+) };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)+) => { log_at!($crate::logging::Level::Debug, $($arg)+) };
+}
+
+/// Audio-thread log: `rt_log!(Level::Error, "message")` or with one integer value
+macro_rules! rt_log {
+    ($level:expr, $msg:literal) => {
+        $crate::logging::rt($level, module_path!(), $msg, None)
+    };
+    ($level:expr, $msg:literal, $value:expr) => {
+        $crate::logging::rt($level, module_path!(), $msg, Some($value as i64))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "realtime thread")]
+    fn test_record_rejects_realtime_threads() {
+        register_realtime_thread();
+        write(Level::Error, "test", format_args!("from the audio thread"));
+    }
+
+    #[test]
+    fn test_module_filters_use_longest_prefix() {
+        set_level(Some("audio"), Level::Warn);
+        set_level(Some("audio::output"), Level::Debug);
+        assert!(!enabled(Level::Info, "spectrum_lib::audio::graph"));
+        assert!(enabled(Level::Debug, "spectrum_lib::audio::output"));
+        // "audio::outputs" is not inside "audio::output"
+        assert_eq!(level_for("spectrum_lib::audio::outputs"), Level::Warn);
+        clear_level("audio");
+        clear_level("audio::output");
+        assert_eq!(
+            level_for("spectrum_lib::audio::graph"),
+            Level::from_u8(DEFAULT_LEVEL.load(Ordering::Relaxed))
+        );
+    }
+
+    #[test]
+    fn test_level_parse_round_trip() {
+        for level in [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ] {
+            assert_eq!(Level::parse(level.as_str()), Some(level));
+        }
+        assert_eq!(Level::parse("warning"), Some(Level::Warn));
+        assert_eq!(Level::parse("loud"), None);
+    }
+}
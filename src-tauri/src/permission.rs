@@ -0,0 +1,107 @@
+//! Microphone permission (TCC) - AVCaptureDevice authorization
+//!
+//! macOS では入力デバイスのキャプチャにマイクの許可が必要。拒否されていても AUHAL は
+//! エラーにならず無音を返すだけなので、キャプチャ開始前にここで状態を確認する。
+//! 未確認（not determined）の場合は最初のキャプチャでシステムが許可を求める。
+
+use block2::RcBlock;
+use objc2::runtime::Bool;
+use objc2::{class, msg_send};
+use objc2_foundation::NSString;
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {
+    static AVMediaTypeAudio: &'static NSString;
+}
+
+/// Microphone authorization (`AVAuthorizationStatus`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionStatus {
+    /// The user has not been asked yet
+    NotDetermined,
+    /// Blocked by a profile / parental controls; the user cannot change it
+    Restricted,
+    Denied,
+    Authorized,
+}
+
+impl PermissionStatus {
+    fn from_raw(raw: isize) -> Self {
+        match raw {
+            1 => Self::Restricted,
+            2 => Self::Denied,
+            3 => Self::Authorized,
+            _ => Self::NotDetermined,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotDetermined => "not_determined",
+            Self::Restricted => "restricted",
+            Self::Denied => "denied",
+            Self::Authorized => "authorized",
+        }
+    }
+
+    /// Whether input capture may start (not determined: the system asks on first use)
+    pub fn allows_capture(self) -> bool {
+        !matches!(self, Self::Restricted | Self::Denied)
+    }
+
+    /// Whether [`request_audio_permission`] can still show the system prompt
+    pub fn can_request(self) -> bool {
+        self == Self::NotDetermined
+    }
+}
+
+/// Current microphone authorization
+pub fn audio_permission_status() -> PermissionStatus {
+    let raw: isize = unsafe {
+        msg_send![
+            class!(AVCaptureDevice),
+            authorizationStatusForMediaType: AVMediaTypeAudio
+        ]
+    };
+    PermissionStatus::from_raw(raw)
+}
+
+/// Ask for microphone access
+///
+/// システムのダイアログが出るのは未確認のときだけ。拒否済みの場合はそのまま返すので、
+/// UI はシステム設定（プライバシーとセキュリティ > マイク）へ誘導する。
+/// ユーザーが答えるか `timeout` が過ぎるまでブロックする（タイムアウト時は not determined）。
+pub fn request_audio_permission(timeout: Duration) -> PermissionStatus {
+    let status = audio_permission_status();
+    if !status.can_request() {
+        return status;
+    }
+
+    let (tx, rx) = mpsc::channel::<bool>();
+    let block = RcBlock::new(move |granted: Bool| {
+        let _ = tx.send(granted.as_bool());
+    });
+    unsafe {
+        let _: () = msg_send![
+            class!(AVCaptureDevice),
+            requestAccessForMediaType: AVMediaTypeAudio,
+            completionHandler: &*block
+        ];
+    }
+
+    match rx.recv_timeout(timeout) {
+        Ok(granted) => {
+            log_info!(
+                "[Permission] Microphone access {}",
+                if granted { "granted" } else { "denied" }
+            );
+            audio_permission_status()
+        }
+        Err(_) => {
+            log_warn!("[Permission] No answer to the microphone prompt yet");
+            PermissionStatus::NotDetermined
+        }
+    }
+}
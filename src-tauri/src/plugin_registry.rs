@@ -0,0 +1,254 @@
+//! Plugin registry - cached AudioUnit scan
+//!
+//! コンポーネントの列挙（AudioComponentFindNext）はプラグインが多いと数秒かかるので、
+//! 起動時にバックグラウンドで 1 回だけ行い、結果をディスクにキャッシュする。
+//! 次回起動時は Components フォルダのバンドル更新時刻が同じならキャッシュをそのまま使う。
+//!
+//! AUv3（アプリ拡張）の追加・削除は更新時刻では検出できないので、
+//! `rescan_plugins` で明示的に再スキャンする。
+//!
+//! 保存先: `<data_dir>/spectrum/plugin_cache.json`
+
+use crate::audio_unit::{self, AudioUnitInfo};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::{Instant, UNIX_EPOCH};
+
+const CACHE_VERSION: u32 = 1;
+
+/// Folders whose bundles are stamped (AUv2 components)
+const COMPONENT_DIRS: [&str; 2] = [
+    "/Library/Audio/Plug-Ins/Components",
+    "/System/Library/Components",
+];
+
+/// User component folder (relative to home)
+const USER_COMPONENT_DIR: &str = "Library/Audio/Plug-Ins/Components";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct BundleStamp {
+    path: String,
+    /// Modification time (seconds since epoch)
+    modified: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryCache {
+    version: u32,
+    bundles: Vec<BundleStamp>,
+    plugins: Vec<AudioUnitInfo>,
+}
+
+/// Scanned plugins (None until the first scan / cache load)
+static PLUGINS: LazyLock<RwLock<Option<Vec<AudioUnitInfo>>>> = LazyLock::new(|| RwLock::new(None));
+
+/// Last instantiation error per plugin_id
+static FAILURES: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Serializes scans (callers wait for a running scan instead of starting another)
+static SCAN_LOCK: Mutex<()> = Mutex::new(());
+
+/// Start the initial scan in the background (call once at startup)
+pub fn start_background_scan() {
+    if let Err(e) = std::thread::Builder::new()
+        .name("plugin-registry".to_string())
+        .spawn(ensure_loaded)
+    {
+        log_error!("[PluginRegistry] Failed to start scan thread: {}", e);
+    }
+}
+
+/// Effect plugins ('aufx' and 'aumf')
+pub fn effects() -> Vec<AudioUnitInfo> {
+    ensure_loaded();
+    PLUGINS
+        .read()
+        .iter()
+        .flatten()
+        .filter(|p| p.plugin_type == "effect" || p.plugin_type == "music_effect")
+        .cloned()
+        .collect()
+}
+
+/// Source plugins: instruments ('aumu') and generators ('augn')
+pub fn sources() -> Vec<AudioUnitInfo> {
+    ensure_loaded();
+    PLUGINS
+        .read()
+        .iter()
+        .flatten()
+        .filter(|p| p.plugin_type == "instrument" || p.plugin_type == "generator")
+        .cloned()
+        .collect()
+}
+
+/// Every registered plugin (effects, instruments and generators)
+pub fn all() -> Vec<AudioUnitInfo> {
+    ensure_loaded();
+    PLUGINS.read().clone().unwrap_or_default()
+}
+
+/// Last instantiation error of a plugin (None if it loaded fine)
+pub fn failure(plugin_id: &str) -> Option<String> {
+    FAILURES.read().get(plugin_id).cloned()
+}
+
+/// Forget the failure of a plugin that instantiated successfully
+pub fn clear_failure(plugin_id: &str) {
+    if FAILURES.write().remove(plugin_id).is_some() {
+        log_info!("[PluginRegistry] {} loads again", plugin_id);
+    }
+}
+
+/// Record an instantiation failure and re-check that one component
+///
+/// アンインストール済みなら一覧から外し、残っていれば情報を取り直す。
+/// 他のプラグインのキャッシュには触れない。
+pub fn invalidate(plugin_id: &str, error: &str) {
+    FAILURES
+        .write()
+        .insert(plugin_id.to_string(), error.to_string());
+
+    let _guard = SCAN_LOCK.lock();
+    let mut plugins = PLUGINS.write();
+    let Some(list) = plugins.as_mut() else {
+        return;
+    };
+    let Some(pos) = list.iter().position(|p| p.id == plugin_id) else {
+        return;
+    };
+    match audio_unit::find_audio_unit(&list[pos]) {
+        Some(fresh) => list[pos] = fresh,
+        None => {
+            log_info!("[PluginRegistry] {} is no longer installed", plugin_id);
+            list.remove(pos);
+        }
+    }
+    write_cache(&bundle_stamps(), list);
+}
+
+/// Scan all components again and refresh the cache; returns the plugin count
+pub fn rescan() -> usize {
+    let _guard = SCAN_LOCK.lock();
+    FAILURES.write().clear();
+    scan_and_store()
+}
+
+fn ensure_loaded() {
+    if PLUGINS.read().is_some() {
+        return;
+    }
+    let _guard = SCAN_LOCK.lock();
+    // 待っている間に別スレッドが読み込み終えている場合がある
+    if PLUGINS.read().is_some() {
+        return;
+    }
+
+    let stamps = bundle_stamps();
+    if let Some(cache) = read_cache() {
+        if cache.version == CACHE_VERSION && cache.bundles == stamps {
+            log_info!(
+                "[PluginRegistry] Loaded {} plugins from cache",
+                cache.plugins.len()
+            );
+            *PLUGINS.write() = Some(cache.plugins);
+            return;
+        }
+        log_info!("[PluginRegistry] Components changed; rescanning");
+    }
+    scan_and_store();
+}
+
+/// Caller must hold SCAN_LOCK
+fn scan_and_store() -> usize {
+    let started = Instant::now();
+    let stamps = bundle_stamps();
+
+    let mut plugins = audio_unit::get_effect_audio_units();
+    plugins.extend(audio_unit::get_instrument_audio_units());
+    plugins.extend(audio_unit::get_generator_audio_units());
+
+    log_info!(
+        "[PluginRegistry] Scanned {} plugins in {:?}",
+        plugins.len(),
+        started.elapsed()
+    );
+    write_cache(&stamps, &plugins);
+    let count = plugins.len();
+    *PLUGINS.write() = Some(plugins);
+    count
+}
+
+/// Modification times of every component bundle
+fn bundle_stamps() -> Vec<BundleStamp> {
+    let mut dirs: Vec<PathBuf> = COMPONENT_DIRS.iter().map(PathBuf::from).collect();
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(USER_COMPONENT_DIR));
+    }
+
+    let mut stamps = Vec::new();
+    for dir in &dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            stamps.push(BundleStamp {
+                path: entry.path().to_string_lossy().to_string(),
+                modified: modified_secs(&entry.path()),
+            });
+        }
+    }
+    stamps.sort_by(|a, b| a.path.cmp(&b.path));
+    stamps
+}
+
+fn modified_secs(path: &Path) -> u64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn cache_file() -> Option<PathBuf> {
+    let dir = dirs::data_dir()?.join("spectrum");
+    std::fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("plugin_cache.json"))
+}
+
+fn read_cache() -> Option<RegistryCache> {
+    let json = std::fs::read_to_string(cache_file()?).ok()?;
+    match serde_json::from_str(&json) {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            log_error!("[PluginRegistry] Ignoring unreadable cache: {}", e);
+            None
+        }
+    }
+}
+
+fn write_cache(bundles: &[BundleStamp], plugins: &[AudioUnitInfo]) {
+    let Some(path) = cache_file() else {
+        return;
+    };
+    let cache = RegistryCache {
+        version: CACHE_VERSION,
+        bundles: bundles.to_vec(),
+        plugins: plugins.to_vec(),
+    };
+    let result = serde_json::to_string(&cache)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, json).map_err(|e| e.to_string())?;
+            std::fs::rename(&tmp, &path).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        log_error!("[PluginRegistry] Failed to write cache: {}", e);
+    }
+}
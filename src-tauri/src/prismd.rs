@@ -1,12 +1,28 @@
 //! prismd IPC client for communicating with the Prism daemon
+//!
+//! リクエストごとにソケットへ接続する。接続状態とクライアント一覧は
+//! スーパーバイザースレッドが監視し、デーモンの停止/再起動を検出して通知する。
 
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 const PRISMD_SOCKET_PATH: &str = "/tmp/prismd.sock";
 
+/// Client list poll interval while connected
+const SUPERVISOR_POLL_MS: u64 = 1_000;
+
+/// Reconnect backoff while prismd is unreachable (doubles up to the max)
+const RECONNECT_MIN_MS: u64 = 500;
+const RECONNECT_MAX_MS: u64 = 10_000;
+
+/// Largest per-client gain the driver accepts (linear, +12 dB)
+pub const MAX_CLIENT_GAIN: f32 = 4.0;
+
 // --- IPC Types ---
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +32,10 @@ pub enum CommandRequest {
     Set { pid: i32, offset: u32 },
     SetApp { app_name: String, offset: u32 },
     SetClient { client_id: u32, offset: u32 },
+    SetClientGain { client_id: u32, gain: f32 },
+    DriverInfo,
+    SetBufferSize { frames: u32 },
+    SetChannelCount { channels: u32 },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,7 +45,7 @@ pub struct RpcResponse<T> {
     pub data: Option<T>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ClientInfo {
     pub pid: i32,
     pub client_id: u32,
@@ -33,6 +53,13 @@ pub struct ClientInfo {
     pub process_name: Option<String>,
     pub responsible_pid: Option<i32>,
     pub responsible_name: Option<String>,
+    /// Pre-capture gain applied by the driver (linear; older daemons do not report it)
+    #[serde(default = "unity_gain")]
+    pub gain: f32,
+}
+
+fn unity_gain() -> f32 {
+    1.0
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +74,21 @@ pub struct ClientRoutingUpdate {
     pub channel_offset: u32,
 }
 
+/// Virtual driver configuration as reported by prismd
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DriverInfo {
+    pub sample_rate: u32,
+    /// Driver I/O buffer (frames)
+    pub buffer_size: u32,
+    pub channel_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientGainUpdate {
+    pub client_id: u32,
+    pub gain: f32,
+}
+
 // --- Helper Functions ---
 
 fn send_request<T: for<'de> Deserialize<'de>>(
@@ -131,11 +173,125 @@ pub async fn set_client_routing(
     .await?
 }
 
+/// Set the pre-capture gain of a client (linear, 0 ..= [`MAX_CLIENT_GAIN`])
+pub async fn set_client_gain(
+    client_id: u32,
+    gain: f32,
+) -> Result<ClientGainUpdate, Box<dyn Error + Send + Sync>> {
+    tokio::task::spawn_blocking(move || {
+        send_request::<ClientGainUpdate>(&CommandRequest::SetClientGain { client_id, gain })
+    })
+    .await?
+}
+
+/// Query the virtual driver's actual sample rate, buffer size and channel count
+pub async fn get_driver_info() -> Result<DriverInfo, Box<dyn Error + Send + Sync>> {
+    tokio::task::spawn_blocking(|| send_request::<DriverInfo>(&CommandRequest::DriverInfo)).await?
+}
+
+/// Ask the driver for a new I/O buffer size; returns the configuration it settled on
+pub async fn set_buffer_size(frames: u32) -> Result<DriverInfo, Box<dyn Error + Send + Sync>> {
+    tokio::task::spawn_blocking(move || {
+        send_request::<DriverInfo>(&CommandRequest::SetBufferSize { frames })
+    })
+    .await?
+}
+
+/// Ask the driver for a new channel count; returns the configuration it settled on
+///
+/// ドライバーはストリーム構成を作り直すので、キャプチャ側は再起動が必要。
+pub async fn set_channel_count(channels: u32) -> Result<DriverInfo, Box<dyn Error + Send + Sync>> {
+    tokio::task::spawn_blocking(move || {
+        send_request::<DriverInfo>(&CommandRequest::SetChannelCount { channels })
+    })
+    .await?
+}
+
 /// Check if prismd is running
+///
+/// スーパーバイザー稼働中は最後に確認した状態を返す（ソケットに接続しない）。
 pub fn is_connected() -> bool {
+    if SUPERVISED.load(Ordering::Relaxed) {
+        return CONNECTED.load(Ordering::Relaxed);
+    }
     UnixStream::connect(PRISMD_SOCKET_PATH).is_ok()
 }
 
+/// Current Prism clients (cached while the supervisor runs)
+pub fn clients() -> Vec<ClientInfo> {
+    if SUPERVISED.load(Ordering::Relaxed) {
+        return CLIENTS.read().clone();
+    }
+    send_request::<Vec<ClientInfo>>(&CommandRequest::Clients).unwrap_or_default()
+}
+
+// --- Connection supervisor ---
+
+/// Connection state change reported by the supervisor
+#[derive(Debug, Clone)]
+pub enum PrismEvent {
+    /// prismd became reachable (first connect or after a restart)
+    Connected { clients: Vec<ClientInfo> },
+    /// prismd stopped responding
+    Disconnected,
+    /// Clients appeared, left or were re-routed
+    ClientsChanged { clients: Vec<ClientInfo> },
+}
+
+static SUPERVISED: AtomicBool = AtomicBool::new(false);
+static CONNECTED: AtomicBool = AtomicBool::new(false);
+static CLIENTS: RwLock<Vec<ClientInfo>> = RwLock::new(Vec::new());
+
+/// Start watching the prismd connection (idempotent)
+///
+/// `on_event` is called from the supervisor thread.
+pub fn start_supervisor(on_event: impl Fn(&PrismEvent) + Send + 'static) -> Result<(), String> {
+    if SUPERVISED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    std::thread::Builder::new()
+        .name("spectrum-prismd".to_string())
+        .spawn(move || supervisor_thread(on_event))
+        .map(|_| ())
+        .map_err(|e| {
+            SUPERVISED.store(false, Ordering::SeqCst);
+            format!("Failed to start prismd supervisor: {}", e)
+        })
+}
+
+fn supervisor_thread(on_event: impl Fn(&PrismEvent)) {
+    let mut backoff_ms = RECONNECT_MIN_MS;
+
+    loop {
+        match send_request::<Vec<ClientInfo>>(&CommandRequest::Clients) {
+            Ok(clients) => {
+                backoff_ms = RECONNECT_MIN_MS;
+                let was_connected = CONNECTED.swap(true, Ordering::Relaxed);
+                let changed = *CLIENTS.read() != clients;
+                if changed {
+                    *CLIENTS.write() = clients.clone();
+                }
+                if !was_connected {
+                    log_info!("[Prismd] Connected ({} clients)", clients.len());
+                    on_event(&PrismEvent::Connected { clients });
+                } else if changed {
+                    on_event(&PrismEvent::ClientsChanged { clients });
+                }
+                std::thread::sleep(Duration::from_millis(SUPERVISOR_POLL_MS));
+            }
+            Err(e) => {
+                if CONNECTED.swap(false, Ordering::Relaxed) {
+                    log_info!("[Prismd] Disconnected ({}); retrying with backoff", e);
+                    CLIENTS.write().clear();
+                    on_event(&PrismEvent::Disconnected);
+                }
+                std::thread::sleep(Duration::from_millis(backoff_ms));
+                backoff_ms = (backoff_ms * 2).min(RECONNECT_MAX_MS);
+            }
+        }
+    }
+}
+
 /// Process info for UI display
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
@@ -144,20 +300,20 @@ pub struct ProcessInfo {
     pub channel_offset: u32,
 }
 
+impl From<ClientInfo> for ProcessInfo {
+    fn from(c: ClientInfo) -> Self {
+        ProcessInfo {
+            pid: c.pid as u32,
+            name: c
+                .responsible_name
+                .or(c.process_name)
+                .unwrap_or_else(|| format!("PID {}", c.pid)),
+            channel_offset: c.channel_offset,
+        }
+    }
+}
+
 /// Get list of processes from prismd (sync version for UI)
 pub fn get_processes() -> Vec<ProcessInfo> {
-    match send_request::<Vec<ClientInfo>>(&CommandRequest::Clients) {
-        Ok(clients) => clients
-            .into_iter()
-            .map(|c| ProcessInfo {
-                pid: c.pid as u32,
-                name: c
-                    .responsible_name
-                    .or(c.process_name)
-                    .unwrap_or_else(|| format!("PID {}", c.pid)),
-                channel_offset: c.channel_offset,
-            })
-            .collect(),
-        Err(_) => Vec::new(),
-    }
+    clients().into_iter().map(ProcessInfo::from).collect()
 }
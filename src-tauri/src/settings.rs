@@ -0,0 +1,449 @@
+//! App settings - settings.json
+//!
+//! グラフ状態（graph_state.json）とは別に、エンジンの起動設定を保存する。
+//! 値は起動時の `run()` で [`apply_startup`] により適用される。
+//! 出力デバイスとサンプルレートは次回起動時から、それ以外は更新と同時に反映される。
+//!
+//! 保存先: `<data_dir>/spectrum/settings.json`
+
+use crate::api::dto::{HotkeyActionDto, HotkeyBindingDto};
+use crate::device::RatePolicy;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Default startup I/O buffer size (frames)
+const DEFAULT_BUFFER_SIZE: u32 = 256;
+
+/// Allowed I/O buffer size range (frames)
+const MIN_BUFFER_SIZE: u32 = 32;
+const MAX_BUFFER_SIZE: u32 = 2048;
+
+/// State log verbosity (`SPECTRUM_STATE_LOG` overrides it)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Off,
+    Summary,
+    Verbose,
+}
+
+impl LogLevel {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" | "0" => Some(Self::Off),
+            "summary" | "1" => Some(Self::Summary),
+            "verbose" | "2" => Some(Self::Verbose),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Summary => "summary",
+            Self::Verbose => "verbose",
+        }
+    }
+
+    /// Numeric level (0 = off, 1 = summary, 2 = verbose)
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Summary => 1,
+            Self::Verbose => 2,
+        }
+    }
+}
+
+/// What to do when a Prism client lands on a channel pair without a source node
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoSourcePolicy {
+    /// Create the source node automatically
+    Auto,
+    /// Only notify the frontend (`prism-app-detected`)
+    #[default]
+    Ask,
+    Off,
+}
+
+impl AutoSourcePolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "ask" => Some(Self::Ask),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Ask => "ask",
+            Self::Off => "off",
+        }
+    }
+}
+
+/// Persistent engine settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Output device to start on launch (None = aggregate device / system default)
+    pub preferred_output_device_uid: Option<String>,
+    /// I/O buffer size used when the engine starts (frames)
+    pub buffer_size: u32,
+    /// Engine sample rate on launch (None = 48 kHz)
+    pub sample_rate: Option<u32>,
+    /// Rate policy for devices without their own setting ("engine" or "follow")
+    pub rate_policy: String,
+    /// Push meter stream rate in Hz (0 = off)
+    pub meter_rate_hz: u32,
+    /// State log level (None = build default: summary in debug, off in release)
+    pub log_level: Option<LogLevel>,
+    /// Source node creation for new Prism clients
+    pub prism_auto_source: AutoSourcePolicy,
+    /// Per-device I/O buffer size overriding `buffer_size` (device UID -> frames)
+    pub device_buffer_sizes: BTreeMap<String, u32>,
+    /// Delay faster outputs to line up with the slowest (e.g. Bluetooth) device
+    pub align_output_latency: bool,
+    /// Global keyboard shortcuts
+    pub hotkeys: Vec<HotkeyBindingDto>,
+    /// Embedded HTTP control API (`api::http`)
+    pub http_api_enabled: bool,
+    pub http_api_port: u16,
+    /// Listen on every interface instead of 127.0.0.1 only
+    pub http_api_allow_lan: bool,
+    /// Bearer token (generated when the API is first enabled)
+    pub http_api_token: Option<String>,
+    /// Pre-capture gain per Prism app (app name -> linear), applied to its new clients
+    pub prism_client_gains: BTreeMap<String, f32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            preferred_output_device_uid: None,
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            sample_rate: None,
+            rate_policy: RatePolicy::Engine.as_str().to_string(),
+            meter_rate_hz: 0,
+            log_level: None,
+            prism_auto_source: AutoSourcePolicy::default(),
+            device_buffer_sizes: BTreeMap::new(),
+            align_output_latency: false,
+            hotkeys: Vec::new(),
+            http_api_enabled: false,
+            http_api_port: crate::api::http::DEFAULT_PORT,
+            http_api_allow_lan: false,
+            http_api_token: None,
+            prism_client_gains: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// Replace out-of-range / unknown values with something usable
+    pub fn sanitized(mut self) -> Self {
+        self.buffer_size = self.buffer_size.clamp(MIN_BUFFER_SIZE, MAX_BUFFER_SIZE);
+        self.device_buffer_sizes
+            .retain(|uid, _| !uid.trim().is_empty());
+        for frames in self.device_buffer_sizes.values_mut() {
+            *frames = (*frames).clamp(MIN_BUFFER_SIZE, MAX_BUFFER_SIZE);
+        }
+        if let Some(rate) = self.sample_rate {
+            if !crate::audio::sample_rate::is_supported_sample_rate(rate as f64) {
+                self.sample_rate = None;
+            }
+        }
+        self.hotkeys.retain(|binding| {
+            !binding.shortcut.trim().is_empty()
+                && match binding.action {
+                    HotkeyActionDto::NudgeEdgeGain { db, .. }
+                    | HotkeyActionDto::NudgeOutputGain { db, .. } => db.is_finite() && db != 0.0,
+                    _ => true,
+                }
+        });
+        if self.http_api_port == 0 {
+            self.http_api_port = crate::api::http::DEFAULT_PORT;
+        }
+        if self
+            .http_api_token
+            .as_ref()
+            .is_some_and(|t| t.trim().is_empty())
+        {
+            self.http_api_token = None;
+        }
+        self.prism_client_gains
+            .retain(|app, gain| !app.trim().is_empty() && gain.is_finite());
+        for gain in self.prism_client_gains.values_mut() {
+            *gain = gain.clamp(0.0, crate::prismd::MAX_CLIENT_GAIN);
+        }
+        if parse_rate_policy(&self.rate_policy).is_none() {
+            self.rate_policy = RatePolicy::Engine.as_str().to_string();
+        }
+        self.meter_rate_hz = self
+            .meter_rate_hz
+            .min(crate::api::events::MAX_METER_STREAM_RATE);
+        if self
+            .preferred_output_device_uid
+            .as_ref()
+            .is_some_and(|uid| uid.trim().is_empty())
+        {
+            self.preferred_output_device_uid = None;
+        }
+        self
+    }
+}
+
+/// Default rate policy names ("fixed" is per device only)
+pub fn parse_rate_policy(s: &str) -> Option<RatePolicy> {
+    match s {
+        "engine" => Some(RatePolicy::Engine),
+        "follow" => Some(RatePolicy::Follow),
+        _ => None,
+    }
+}
+
+static SETTINGS: RwLock<Option<Settings>> = RwLock::new(None);
+
+/// Current settings (loaded from disk on first use)
+pub fn get() -> Settings {
+    if let Some(settings) = SETTINGS.read().as_ref() {
+        return settings.clone();
+    }
+    let loaded = load();
+    SETTINGS.write().get_or_insert(loaded).clone()
+}
+
+/// Save new settings and apply the ones that take effect immediately
+pub fn update(settings: Settings) -> Result<Settings, String> {
+    let settings = settings.sanitized();
+    save(&settings)?;
+    apply_live(&settings);
+    *SETTINGS.write() = Some(settings.clone());
+    Ok(settings)
+}
+
+/// Apply settings before the engine starts (call once from `run()`)
+pub fn apply_startup() -> Settings {
+    let settings = get();
+    if let Some(rate) = settings.sample_rate {
+        crate::audio::sample_rate::store_engine_sample_rate(rate as f64);
+    }
+    crate::audio_capture::set_io_buffer_size(settings.buffer_size as usize);
+    apply_live(&settings);
+    log_info!(
+        "[Settings] Applied: buffer={} rate={:?} policy={} meters={}Hz",
+        settings.buffer_size,
+        settings.sample_rate,
+        settings.rate_policy,
+        settings.meter_rate_hz
+    );
+    settings
+}
+
+/// Preferred output device, if it is connected
+pub fn preferred_output_device() -> Option<u32> {
+    let uid = get().preferred_output_device_uid?;
+    let device_id = crate::device::find_device_by_uid(&uid);
+    if device_id.is_none() {
+        log_info!(
+            "[Settings] Preferred output device {} is not connected",
+            uid
+        );
+    }
+    device_id
+}
+
+/// State log level from settings (None = build default)
+pub fn log_level() -> Option<LogLevel> {
+    SETTINGS.read().as_ref().and_then(|s| s.log_level)
+}
+
+/// Buffer size override for a device UID (None = use `buffer_size`)
+pub fn device_buffer_size(uid: &str) -> Option<u32> {
+    get().device_buffer_sizes.get(uid).copied()
+}
+
+/// Set (Some) or clear (None) a device's buffer size override and save
+pub fn set_device_buffer_size(uid: &str, frames: Option<u32>) -> Result<Settings, String> {
+    let mut settings = get();
+    match frames {
+        Some(frames) => settings.device_buffer_sizes.insert(uid.to_string(), frames),
+        None => settings.device_buffer_sizes.remove(uid),
+    };
+    update(settings)
+}
+
+/// Saved pre-capture gain of a Prism app
+pub fn prism_client_gain(app: &str) -> Option<f32> {
+    get().prism_client_gains.get(app).copied()
+}
+
+/// Remember a Prism app's gain (unity removes the entry) and save
+pub fn set_prism_client_gain(app: &str, gain: f32) -> Result<Settings, String> {
+    let mut settings = get();
+    if gain == 1.0 {
+        settings.prism_client_gains.remove(app);
+    } else {
+        settings.prism_client_gains.insert(app.to_string(), gain);
+    }
+    update(settings)
+}
+
+/// Auto-source policy for new Prism clients
+pub fn prism_auto_source() -> AutoSourcePolicy {
+    get().prism_auto_source
+}
+
+fn apply_live(settings: &Settings) {
+    if let Some(policy) = parse_rate_policy(&settings.rate_policy) {
+        crate::device::set_default_rate_policy(policy);
+    }
+    crate::api::events::set_meter_stream_rate(settings.meter_rate_hz);
+    set_output_alignment(settings.align_output_latency);
+}
+
+fn set_output_alignment(enabled: bool) {
+    let processor = crate::audio::processor::get_graph_processor();
+    if processor.with_graph(|graph| graph.align_output_latency()) == enabled {
+        return;
+    }
+    if enabled {
+        crate::audio::output::refresh_sink_latencies();
+    }
+    processor.with_graph_mut(|graph| graph.set_align_output_latency(enabled));
+    log_info!(
+        "[Settings] Output latency alignment {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+}
+
+fn settings_file() -> Option<PathBuf> {
+    Some(dirs::data_dir()?.join("spectrum").join("settings.json"))
+}
+
+fn load() -> Settings {
+    let Some(path) = settings_file() else {
+        return Settings::default();
+    };
+    let Ok(json) = std::fs::read_to_string(&path) else {
+        return Settings::default();
+    };
+    match serde_json::from_str::<Settings>(&json) {
+        Ok(settings) => settings.sanitized(),
+        Err(e) => {
+            log_error!("[Settings] Ignoring unreadable {}: {}", path.display(), e);
+            Settings::default()
+        }
+    }
+}
+
+fn save(settings: &Settings) -> Result<(), String> {
+    let path = settings_file().ok_or("Could not find app data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitized_fixes_out_of_range_values() {
+        let settings = Settings {
+            preferred_output_device_uid: Some("  ".to_string()),
+            buffer_size: 8,
+            sample_rate: Some(12345),
+            rate_policy: "sometimes".to_string(),
+            meter_rate_hz: 10_000,
+            log_level: Some(LogLevel::Verbose),
+            prism_auto_source: AutoSourcePolicy::Auto,
+            device_buffer_sizes: BTreeMap::from([
+                ("usb-mic".to_string(), 100_000),
+                (" ".to_string(), 512),
+            ]),
+            align_output_latency: true,
+            hotkeys: vec![
+                HotkeyBindingDto {
+                    shortcut: "CmdOrCtrl+Shift+M".to_string(),
+                    action: HotkeyActionDto::ToggleNodeMute {
+                        node: "source:prism:0".to_string(),
+                    },
+                },
+                HotkeyBindingDto {
+                    shortcut: " ".to_string(),
+                    action: HotkeyActionDto::RecallScene {
+                        name: "Live".to_string(),
+                        fade_ms: 0,
+                    },
+                },
+                HotkeyBindingDto {
+                    shortcut: "Alt+Up".to_string(),
+                    action: HotkeyActionDto::NudgeOutputGain {
+                        node: "sink:1:0:2".to_string(),
+                        db: f32::NAN,
+                    },
+                },
+            ],
+            http_api_enabled: true,
+            http_api_port: 0,
+            http_api_allow_lan: false,
+            http_api_token: Some(String::new()),
+            prism_client_gains: BTreeMap::from([
+                ("Music".to_string(), 10.0),
+                ("Zoom".to_string(), f32::NAN),
+                ("".to_string(), 0.5),
+            ]),
+        }
+        .sanitized();
+
+        assert_eq!(settings.preferred_output_device_uid, None);
+        assert_eq!(settings.buffer_size, MIN_BUFFER_SIZE);
+        assert_eq!(settings.sample_rate, None);
+        assert_eq!(settings.rate_policy, "engine");
+        assert_eq!(
+            settings.meter_rate_hz,
+            crate::api::events::MAX_METER_STREAM_RATE
+        );
+        assert_eq!(settings.log_level, Some(LogLevel::Verbose));
+        assert_eq!(
+            settings.device_buffer_sizes,
+            BTreeMap::from([("usb-mic".to_string(), MAX_BUFFER_SIZE)])
+        );
+        assert_eq!(settings.hotkeys.len(), 1);
+        assert_eq!(settings.hotkeys[0].shortcut, "CmdOrCtrl+Shift+M");
+        assert_eq!(settings.http_api_port, crate::api::http::DEFAULT_PORT);
+        assert_eq!(settings.http_api_token, None);
+        assert_eq!(
+            settings.prism_client_gains,
+            BTreeMap::from([("Music".to_string(), crate::prismd::MAX_CLIENT_GAIN)])
+        );
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let settings: Settings = serde_json::from_str(r#"{ "buffer_size": 512 }"#).unwrap();
+        assert_eq!(settings.buffer_size, 512);
+        assert_eq!(settings.rate_policy, "engine");
+        assert_eq!(settings.log_level, None);
+        assert_eq!(settings.prism_auto_source, AutoSourcePolicy::Ask);
+        assert!(settings.device_buffer_sizes.is_empty());
+        assert!(!settings.align_output_latency);
+        assert!(settings.hotkeys.is_empty());
+        assert!(!settings.http_api_enabled);
+        assert_eq!(settings.http_api_port, crate::api::http::DEFAULT_PORT);
+        assert!(settings.prism_client_gains.is_empty());
+    }
+}
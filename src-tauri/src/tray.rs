@@ -0,0 +1,302 @@
+//! Menu bar extra (tray) - quick controls while the main window is closed
+//!
+//! メニュー: エンジンの開始/停止、ピン留めしたエッジのミュート、出力デバイスの切り替え。
+//! メニューは監視スレッド（`spectrum-tray`）が状態を見て、変わったときだけ作り直す。
+//! メニュー項目の操作は UI スレッドを塞がないよう別スレッドで実行する。
+//!
+//! トレイがある間はウィンドウを閉じても隠すだけにして、アプリを終了させない。
+
+use crate::api::dto::GraphEventDto;
+use crate::api::events::emit_graph_event;
+use crate::audio::processor::get_graph_processor;
+use crate::audio::EdgeId;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Manager, Wry};
+
+const TRAY_ID: &str = "spectrum-tray";
+
+/// Menu refresh interval
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Output devices are re-enumerated every N polls (CoreAudio queries are slow)
+const DEVICE_POLL_EVERY: u32 = 5;
+
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// What the menu shows (rebuilt only when this changes)
+#[derive(Debug, Clone, Default, PartialEq)]
+struct TrayState {
+    engine_running: bool,
+    /// Clock-master output
+    active_output: Option<u32>,
+    /// (edge id, label, muted)
+    pinned_edges: Vec<(u32, String, bool)>,
+    /// (device id, name)
+    output_devices: Vec<(u32, String)>,
+}
+
+/// Whether the menu bar extra is installed
+pub fn is_active() -> bool {
+    ACTIVE.load(Ordering::Relaxed)
+}
+
+/// Install the menu bar extra (call once from `setup`)
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let state = current_state(output_devices()).unwrap_or_default();
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Spectrum")
+        .menu(&build_menu(app, &state)?)
+        .on_menu_event(|app, event| handle_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    ACTIVE.store(true, Ordering::Relaxed);
+
+    let handle = app.clone();
+    std::thread::Builder::new()
+        .name("spectrum-tray".to_string())
+        .spawn(move || watch_thread(handle, state))?;
+
+    log_info!("[Tray] Menu bar extra installed");
+    Ok(())
+}
+
+/// Show and focus the main window (after it was closed to the menu bar)
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn watch_thread(app: AppHandle, mut shown: TrayState) {
+    let mut tick = 0u32;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        tick = tick.wrapping_add(1);
+        let devices = if tick % DEVICE_POLL_EVERY == 0 {
+            output_devices()
+        } else {
+            shown.output_devices.clone()
+        };
+        refresh(&app, &mut shown, devices);
+    }
+}
+
+/// Rebuild the menu if the state changed
+fn refresh(app: &AppHandle, shown: &mut TrayState, output_devices: Vec<(u32, String)>) {
+    let Some(state) = current_state(output_devices) else {
+        return;
+    };
+    if state == *shown {
+        return;
+    }
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    match build_menu(app, &state) {
+        Ok(menu) => {
+            if let Err(e) = tray.set_menu(Some(menu)) {
+                log_error!("[Tray] Failed to update menu: {}", e);
+            }
+        }
+        Err(e) => log_error!("[Tray] Failed to build menu: {}", e),
+    }
+    *shown = state;
+}
+
+/// None while the graph is locked for writing (try again on the next poll)
+fn current_state(output_devices: Vec<(u32, String)>) -> Option<TrayState> {
+    let pinned_edges = get_graph_processor().try_with_graph(|graph| {
+        graph
+            .edges()
+            .iter()
+            .filter(|e| e.pinned())
+            .map(|e| {
+                let label = |h| {
+                    graph
+                        .get_node(h)
+                        .map(|n| n.label().to_string())
+                        .unwrap_or_default()
+                };
+                (
+                    e.id.raw(),
+                    format!("{} → {}", label(e.source), label(e.target)),
+                    e.muted(),
+                )
+            })
+            .collect()
+    })?;
+    Some(TrayState {
+        engine_running: crate::audio::output::is_output_running_v2(),
+        active_output: crate::audio::output::get_active_output_device(),
+        pinned_edges,
+        output_devices,
+    })
+}
+
+/// Output devices for the switcher (aggregate sub-devices collapsed into their aggregate)
+fn output_devices() -> Vec<(u32, String)> {
+    let mut devices: Vec<(u32, String)> = Vec::new();
+    for device in crate::device::get_output_devices() {
+        if devices.iter().any(|(id, _)| *id == device.device_id) {
+            continue;
+        }
+        let name = device.parent_name.unwrap_or(device.name);
+        devices.push((device.device_id, name));
+    }
+    devices
+}
+
+fn build_menu(app: &AppHandle, state: &TrayState) -> tauri::Result<Menu<Wry>> {
+    let menu = Menu::new(app)?;
+
+    let engine_label = if state.engine_running {
+        "Stop Engine"
+    } else {
+        "Start Engine"
+    };
+    menu.append(&MenuItem::with_id(
+        app,
+        "engine",
+        engine_label,
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    if state.pinned_edges.is_empty() {
+        menu.append(&MenuItem::with_id(
+            app,
+            "mute-none",
+            "No pinned sends",
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    for (id, label, muted) in &state.pinned_edges {
+        menu.append(&CheckMenuItem::with_id(
+            app,
+            format!("mute:{}", id),
+            format!("Mute {}", label),
+            true,
+            *muted,
+            None::<&str>,
+        )?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    let outputs = Submenu::with_id(app, "outputs", "Output Device", true)?;
+    for (device_id, name) in &state.output_devices {
+        outputs.append(&CheckMenuItem::with_id(
+            app,
+            format!("output:{}", device_id),
+            name,
+            true,
+            state.active_output == Some(*device_id),
+            None::<&str>,
+        )?)?;
+    }
+    menu.append(&outputs)?;
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    menu.append(&MenuItem::with_id(
+        app,
+        "show",
+        "Show Spectrum",
+        true,
+        None::<&str>,
+    )?)?;
+    menu.append(&MenuItem::with_id(
+        app,
+        "quit",
+        "Quit Spectrum",
+        true,
+        None::<&str>,
+    )?)?;
+    Ok(menu)
+}
+
+fn handle_menu_event(app: &AppHandle, id: &str) {
+    match id {
+        "show" => show_main_window(app),
+        "quit" => app.exit(0),
+        _ => {
+            let id = id.to_string();
+            let app = app.clone();
+            // 出力の開始は数秒かかることがあるのでメインスレッドの外で
+            std::thread::spawn(move || {
+                run_action(&id);
+                let mut shown = TrayState::default();
+                let devices = output_devices();
+                refresh(&app, &mut shown, devices);
+            });
+        }
+    }
+}
+
+fn run_action(id: &str) {
+    if id == "engine" {
+        toggle_engine();
+    } else if let Some(edge_id) = id.strip_prefix("mute:").and_then(|s| s.parse().ok()) {
+        toggle_mute(edge_id);
+    } else if let Some(device_id) = id.strip_prefix("output:").and_then(|s| s.parse().ok()) {
+        switch_output(device_id);
+    }
+}
+
+fn toggle_engine() {
+    if crate::audio::output::is_output_running_v2() {
+        crate::capture::stop_capture();
+        crate::audio::output::stop_output_v2();
+        log_info!("[Tray] Engine stopped");
+        return;
+    }
+
+    if let Err(e) = crate::capture::start_capture() {
+        log_warn!("[Tray] Failed to start capture: {}", e);
+    }
+    let Some(device_id) = crate::settings::preferred_output_device()
+        .or_else(crate::device::find_preferred_output_device)
+    else {
+        log_warn!("[Tray] No output device to start");
+        return;
+    };
+    match crate::audio::output::start_output_v2(device_id) {
+        Ok(()) => log_info!("[Tray] Engine started on device {}", device_id),
+        Err(e) => {
+            log_error!("[Tray] Failed to start output: {}", e);
+            crate::capture::stop_capture();
+        }
+    }
+}
+
+fn toggle_mute(edge_id: u32) {
+    let processor = get_graph_processor();
+    let Some(muted) =
+        processor.with_graph(|graph| graph.get_edge(EdgeId::from(edge_id)).map(|e| !e.muted()))
+    else {
+        return;
+    };
+    if processor.set_edge_muted(EdgeId::from(edge_id), muted) {
+        crate::api::history::with_actor("tray", || {
+            emit_graph_event(GraphEventDto::EdgeChanged {
+                id: edge_id,
+                gain: None,
+                muted: Some(muted),
+            });
+            crate::api::apply_edge_links(EdgeId::from(edge_id), None, Some(muted), &[]);
+        });
+    }
+}
+
+fn switch_output(device_id: u32) {
+    if let Err(e) = crate::audio::output::start_output_v2(device_id) {
+        log_error!("[Tray] Failed to switch output to {}: {}", device_id, e);
+    }
+}
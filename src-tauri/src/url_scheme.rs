@@ -0,0 +1,417 @@
+//! `spectrum://` URL scheme - automation from Shortcuts, AppleScript and Stream Deck
+//!
+//! macOS は Info.plist の CFBundleURLTypes で登録したスキームの URL を
+//! `RunEvent::Opened` で渡す（起動していなければ起動してから）。AppleScript からは
+//! `open location "spectrum://scene/StreamStart"`、ショートカットからは「URL を開く」で呼べる。
+//!
+//! - `spectrum://scene/<name>[?fade=<ms>]` - recall a scene
+//! - `spectrum://edge/<id>/gain?db=<dB>` (or `?gain=<linear>`) - set an edge gain
+//! - `spectrum://edge/<id>/mute[?on=1|0|toggle]` - mute an edge
+//! - `spectrum://node/<stable_id|handle>/mute[?on=1|0|toggle]` - mute every send leaving a node
+//! - `spectrum://output/<device uid|id>` - move the main output's sinks to another device
+//!   (crossfaded like `switch_output_device`; capture and other outputs keep running)
+//!
+//! エッジは id の代わりに `?from=<stable_id>:<port>&to=<stable_id>:<port>` でも指定できる
+//! （`spectrum://edge/gain?from=source:prism:0:0&to=bus:main:0&db=-6`）。
+
+use crate::api::dto::GraphDto;
+use crate::api::SpectrumError;
+use crate::audio::taper::{db_to_linear, MAX_DB};
+use std::collections::HashMap;
+use tauri::Url;
+
+pub const SCHEME: &str = "spectrum";
+
+/// Edge addressed by id or by its two ends
+#[derive(Debug, Clone, PartialEq)]
+enum EdgeRef {
+    Id(u32),
+    Ports {
+        source: String,
+        source_port: u8,
+        target: String,
+        target_port: u8,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MuteOp {
+    On,
+    Off,
+    Toggle,
+}
+
+impl MuteOp {
+    fn parse(s: Option<&str>) -> Result<Self, String> {
+        match s.map(|s| s.to_ascii_lowercase()).as_deref() {
+            None | Some("toggle") => Ok(Self::Toggle),
+            Some("1" | "true" | "on") => Ok(Self::On),
+            Some("0" | "false" | "off") => Ok(Self::Off),
+            Some(other) => Err(format!("Unknown mute value: {}", other)),
+        }
+    }
+
+    fn apply(self, muted: bool) -> bool {
+        match self {
+            Self::On => true,
+            Self::Off => false,
+            Self::Toggle => !muted,
+        }
+    }
+}
+
+/// A parsed `spectrum://` request
+#[derive(Debug, Clone, PartialEq)]
+enum UrlCommand {
+    RecallScene { name: String, fade_ms: u32 },
+    SetEdgeGain { edge: EdgeRef, gain: f32 },
+    MuteEdge { edge: EdgeRef, op: MuteOp },
+    MuteNode { node: String, op: MuteOp },
+    SwitchOutput { device: String },
+}
+
+/// Handle URLs opened by the system (`RunEvent::Opened`)
+pub fn handle_urls(urls: &[Url]) {
+    for url in urls.iter().cloned() {
+        if url.scheme() != SCHEME {
+            continue;
+        }
+        let command = match parse(&url) {
+            Ok(command) => command,
+            Err(e) => {
+                log_warn!("[URL] Ignoring {}: {}", url, e);
+                continue;
+            }
+        };
+        log_info!("[URL] {}", url);
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::api::history::as_actor("url", run(command)).await {
+                log_warn!("[URL] {} failed: {}", url, e);
+            }
+        });
+    }
+}
+
+fn parse(url: &Url) -> Result<UrlCommand, String> {
+    let target = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let segments: Vec<String> = url
+        .path_segments()
+        .map(|s| {
+            s.filter(|s| !s.is_empty())
+                .map(percent_decode)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+    let param = |key: &str| query.get(key).map(String::as_str);
+
+    match (target.as_str(), segments.as_slice()) {
+        ("scene", [name]) => Ok(UrlCommand::RecallScene {
+            name: name.clone(),
+            fade_ms: param("fade")
+                .map(|f| f.parse().map_err(|_| format!("Bad fade: {}", f)))
+                .transpose()?
+                .unwrap_or(0),
+        }),
+        ("edge", [rest @ .., action]) => {
+            let edge = match rest {
+                [id] => EdgeRef::Id(id.parse().map_err(|_| format!("Bad edge id: {}", id))?),
+                [] => {
+                    let (source, source_port) = parse_port(param("from"))?;
+                    let (target, target_port) = parse_port(param("to"))?;
+                    EdgeRef::Ports {
+                        source,
+                        source_port,
+                        target,
+                        target_port,
+                    }
+                }
+                _ => return Err("Too many path segments".to_string()),
+            };
+            match action.as_str() {
+                "gain" => Ok(UrlCommand::SetEdgeGain {
+                    edge,
+                    gain: parse_gain(param("db"), param("gain"))?,
+                }),
+                "mute" => Ok(UrlCommand::MuteEdge {
+                    edge,
+                    op: MuteOp::parse(param("on"))?,
+                }),
+                other => Err(format!("Unknown edge action: {}", other)),
+            }
+        }
+        ("node", [node, action]) if action == "mute" => Ok(UrlCommand::MuteNode {
+            node: node.clone(),
+            op: MuteOp::parse(param("on"))?,
+        }),
+        ("output", [device]) => Ok(UrlCommand::SwitchOutput {
+            device: device.clone(),
+        }),
+        _ => Err("Unknown request".to_string()),
+    }
+}
+
+/// `<stable_id>:<port>` (stable ids contain ':' themselves)
+fn parse_port(value: Option<&str>) -> Result<(String, u8), String> {
+    let value = value.ok_or("Edge needs an id or from/to")?;
+    let (node, port) = value
+        .rsplit_once(':')
+        .ok_or_else(|| format!("Expected <node>:<port>: {}", value))?;
+    let port = port.parse().map_err(|_| format!("Bad port: {}", value))?;
+    Ok((node.to_string(), port))
+}
+
+/// `db` wins over `gain`; "-inf" mutes. Both are capped at [`MAX_DB`] like the UI faders
+fn parse_gain(db: Option<&str>, gain: Option<&str>) -> Result<f32, String> {
+    if let Some(db) = db {
+        if db.eq_ignore_ascii_case("-inf") {
+            return Ok(0.0);
+        }
+        let db: f32 = db.parse().map_err(|_| format!("Bad dB value: {}", db))?;
+        return Ok(db_to_linear(Some(db)));
+    }
+    let gain = gain.ok_or("Missing db or gain")?;
+    match gain.parse::<f32>() {
+        Ok(g) if g.is_finite() && g >= 0.0 => Ok(g.min(db_to_linear(Some(MAX_DB)))),
+        _ => Err(format!("Bad gain: {}", gain)),
+    }
+}
+
+/// Decode `%XX` escapes in a path segment
+pub(crate) fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+async fn run(command: UrlCommand) -> Result<(), SpectrumError> {
+    match command {
+        UrlCommand::RecallScene { name, fade_ms } => {
+            crate::api::recall_scene(name, Some(fade_ms)).await?;
+            Ok(())
+        }
+        UrlCommand::SetEdgeGain { edge, gain } => {
+            let graph = crate::api::get_graph().await?;
+            let (id, _) = resolve_edge(&graph, &edge)?;
+            crate::api::set_edge_gain(id, gain).await
+        }
+        UrlCommand::MuteEdge { edge, op } => {
+            let graph = crate::api::get_graph().await?;
+            let (id, muted) = resolve_edge(&graph, &edge)?;
+            crate::api::set_edge_muted(id, op.apply(muted)).await
+        }
+        UrlCommand::MuteNode { node, op } => {
+            let graph = crate::api::get_graph().await?;
+            let handle = resolve_node(&graph, &node)?;
+            let sends: Vec<_> = graph.edges.iter().filter(|e| e.source == handle).collect();
+            let all_muted = sends.iter().all(|e| e.muted);
+            let mute = op.apply(all_muted);
+            for edge in sends {
+                crate::api::set_edge_muted(edge.id, mute).await?;
+            }
+            Ok(())
+        }
+        UrlCommand::SwitchOutput { device } => {
+            let device_id = match device.parse::<u32>() {
+                Ok(id) => id,
+                Err(_) => crate::device::find_device_by_uid(&device).ok_or_else(|| {
+                    SpectrumError::InvalidArgument(format!("No output device {}", device))
+                })?,
+            };
+            // エンジンを止めずにシンクだけ移す（出力が止まっていれば起動する）
+            match crate::audio::output::get_active_output_device() {
+                Some(current) if current == device_id => Ok(()),
+                Some(current) => crate::api::switch_output_device(current, device_id)
+                    .await
+                    .map(|_| ()),
+                None => crate::api::start_audio(device_id).await,
+            }
+        }
+    }
+}
+
+/// Node by stable_id, or by handle when the value is a number
+fn resolve_node(graph: &GraphDto, node: &str) -> Result<u32, SpectrumError> {
+    if let Ok(handle) = node.parse::<u32>() {
+        return graph
+            .nodes
+            .iter()
+            .find(|n| n.handle() == handle)
+            .map(|n| n.handle())
+            .ok_or(SpectrumError::NodeNotFound(handle));
+    }
+    graph
+        .node_by_stable_id(node)
+        .map(|n| n.handle())
+        .ok_or_else(|| SpectrumError::InvalidState(format!("{} is not in the graph", node)))
+}
+
+/// (edge id, muted)
+fn resolve_edge(graph: &GraphDto, edge: &EdgeRef) -> Result<(u32, bool), SpectrumError> {
+    let found = match edge {
+        EdgeRef::Id(id) => graph.edges.iter().find(|e| e.id == *id),
+        EdgeRef::Ports {
+            source,
+            source_port,
+            target,
+            target_port,
+        } => {
+            let source = resolve_node(graph, source)?;
+            let target = resolve_node(graph, target)?;
+            graph.edge_between(source, *source_port, target, *target_port)
+        }
+    };
+    match (found, edge) {
+        (Some(e), _) => Ok((e.id, e.muted)),
+        (None, EdgeRef::Id(id)) => Err(SpectrumError::EdgeNotFound(*id)),
+        (None, _) => Err(SpectrumError::InvalidState(format!("No edge {:?}", edge))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(url: &str) -> Result<UrlCommand, String> {
+        parse(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn test_parse_scene() {
+        assert_eq!(
+            parse_str("spectrum://scene/StreamStart?fade=500"),
+            Ok(UrlCommand::RecallScene {
+                name: "StreamStart".to_string(),
+                fade_ms: 500
+            })
+        );
+        assert_eq!(
+            parse_str("spectrum://Scene/Live%20Show"),
+            Ok(UrlCommand::RecallScene {
+                name: "Live Show".to_string(),
+                fade_ms: 0
+            })
+        );
+        assert!(parse_str("spectrum://scene/A?fade=soon").is_err());
+        assert!(parse_str("spectrum://scene").is_err());
+        assert!(parse_str("spectrum://scene/A/B").is_err());
+    }
+
+    #[test]
+    fn test_parse_edge() {
+        assert_eq!(
+            parse_str("spectrum://edge/12/gain?db=-6"),
+            Ok(UrlCommand::SetEdgeGain {
+                edge: EdgeRef::Id(12),
+                gain: db_to_linear(Some(-6.0))
+            })
+        );
+        assert_eq!(
+            parse_str("spectrum://edge/12/gain?db=-inf&gain=1"),
+            Ok(UrlCommand::SetEdgeGain {
+                edge: EdgeRef::Id(12),
+                gain: 0.0
+            })
+        );
+        assert_eq!(
+            parse_str("spectrum://edge/3/mute"),
+            Ok(UrlCommand::MuteEdge {
+                edge: EdgeRef::Id(3),
+                op: MuteOp::Toggle
+            })
+        );
+        assert_eq!(
+            parse_str("spectrum://edge/gain?from=source:prism:0:1&to=bus:main:0&gain=0.5"),
+            Ok(UrlCommand::SetEdgeGain {
+                edge: EdgeRef::Ports {
+                    source: "source:prism:0".to_string(),
+                    source_port: 1,
+                    target: "bus:main".to_string(),
+                    target_port: 0,
+                },
+                gain: 0.5
+            })
+        );
+        // 上限（+12 dB）を超える値は丸める
+        for url in [
+            "spectrum://edge/12/gain?gain=1e6",
+            "spectrum://edge/12/gain?db=40",
+        ] {
+            assert_eq!(
+                parse_str(url),
+                Ok(UrlCommand::SetEdgeGain {
+                    edge: EdgeRef::Id(12),
+                    gain: db_to_linear(Some(MAX_DB))
+                }),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_node_and_output() {
+        assert_eq!(
+            parse_str("spectrum://node/bus:main/mute?on=off"),
+            Ok(UrlCommand::MuteNode {
+                node: "bus:main".to_string(),
+                op: MuteOp::Off
+            })
+        );
+        assert_eq!(
+            parse_str("spectrum://output/BuiltInSpeakerDevice"),
+            Ok(UrlCommand::SwitchOutput {
+                device: "BuiltInSpeakerDevice".to_string()
+            })
+        );
+        assert_eq!(
+            parse_str("spectrum://output/73"),
+            Ok(UrlCommand::SwitchOutput {
+                device: "73".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed() {
+        for url in [
+            "spectrum://",
+            "spectrum://unknown/thing",
+            "spectrum://edge/abc/gain?db=-6",
+            "spectrum://edge/1/2/gain?db=-6",
+            "spectrum://edge/1/solo",
+            "spectrum://edge/1/gain",
+            "spectrum://edge/1/gain?db=loud",
+            "spectrum://edge/1/gain?gain=-1",
+            "spectrum://edge/1/gain?gain=NaN",
+            "spectrum://edge/1/mute?on=maybe",
+            "spectrum://edge/gain?db=-6",
+            "spectrum://edge/gain?from=bus&to=bus:main:0&db=-6",
+            "spectrum://edge/gain?from=bus:main:x&to=bus:main:0&db=-6",
+            "spectrum://edge/gain?from=bus:main:300&to=bus:main:0&db=-6",
+            "spectrum://node/1/solo",
+            "spectrum://node/1",
+            "spectrum://output",
+            "spectrum://output/a/b",
+        ] {
+            assert!(parse_str(url).is_err(), "{} should not parse", url);
+        }
+    }
+}
@@ -3,11 +3,25 @@
 
 #![allow(non_camel_case_types)]
 
-use std::os::raw::c_int;
+use std::os::raw::{c_int, c_void};
 
 // vDSP stride type
 pub type vDSP_Stride = c_int;
 pub type vDSP_Length = usize;
+// Opaque biquad setup (coefficients only; the delay line is owned by the caller)
+pub type vDSP_biquad_Setup = *mut c_void;
+// Opaque FFT setup (twiddle factors)
+pub type FFTSetup = *mut c_void;
+
+/// Split complex vector (vDSP)
+#[repr(C)]
+pub struct DSPSplitComplex {
+    pub realp: *mut f32,
+    pub imagp: *mut f32,
+}
+
+const FFT_RADIX2: c_int = 0;
+const FFT_DIRECTION_FORWARD: c_int = 1;
 
 #[link(name = "Accelerate", kind = "framework")]
 extern "C" {
@@ -79,6 +93,17 @@ extern "C" {
         n: vDSP_Length,
     );
 
+    // Ramped multiply-add: O = O + I * (start + i * step), start is updated
+    pub fn vDSP_vrampmuladd(
+        i: *const f32,
+        stride_i: vDSP_Stride,
+        start: *mut f32,
+        step: *const f32,
+        o: *mut f32,
+        stride_o: vDSP_Stride,
+        n: vDSP_Length,
+    );
+
     // Mean of squares (for RMS calculation)
     pub fn vDSP_measqv(a: *const f32, stride: vDSP_Stride, result: *mut f32, n: vDSP_Length);
 
@@ -115,6 +140,210 @@ extern "C" {
         stride_c: vDSP_Stride,
         n: vDSP_Length,
     );
+
+    // Matrix multiply: C (m×n) = A (m×p) · B (p×n), row-major
+    pub fn vDSP_mmul(
+        a: *const f32,
+        stride_a: vDSP_Stride,
+        b: *const f32,
+        stride_b: vDSP_Stride,
+        c: *mut f32,
+        stride_c: vDSP_Stride,
+        m: vDSP_Length,
+        n: vDSP_Length,
+        p: vDSP_Length,
+    );
+
+    // Cascaded biquad: coeffs = [b0, b1, b2, a1, a2] * sections
+    pub fn vDSP_biquad_CreateSetup(coeffs: *const f64, sections: vDSP_Length) -> vDSP_biquad_Setup;
+
+    // Replace coefficients of an existing setup (no allocation)
+    pub fn vDSP_biquad_SetCoefficientsDouble(
+        setup: vDSP_biquad_Setup,
+        coeffs: *const f64,
+        start_section: vDSP_Length,
+        sections: vDSP_Length,
+    );
+
+    pub fn vDSP_biquad_DestroySetup(setup: vDSP_biquad_Setup);
+
+    // Run the cascade; delay holds 2 * sections + 2 floats of state
+    pub fn vDSP_biquad(
+        setup: vDSP_biquad_Setup,
+        delay: *mut f32,
+        x: *const f32,
+        stride_x: vDSP_Stride,
+        y: *mut f32,
+        stride_y: vDSP_Stride,
+        n: vDSP_Length,
+    );
+
+    pub fn vDSP_create_fftsetup(log2n: vDSP_Length, radix: c_int) -> FFTSetup;
+
+    pub fn vDSP_destroy_fftsetup(setup: FFTSetup);
+
+    // In-place real FFT on packed split complex data (output scaled by 2)
+    pub fn vDSP_fft_zrip(
+        setup: FFTSetup,
+        c: *const DSPSplitComplex,
+        stride: vDSP_Stride,
+        log2n: vDSP_Length,
+        direction: c_int,
+    );
+
+    // Squared magnitudes of a split complex vector
+    pub fn vDSP_zvmags(
+        a: *const DSPSplitComplex,
+        stride_a: vDSP_Stride,
+        c: *mut f32,
+        stride_c: vDSP_Stride,
+        n: vDSP_Length,
+    );
+}
+
+/// Real forward FFT (vDSP_fft_zrip)
+///
+/// 作業領域を内部に持つので、`magnitudes` は割り当てなしで繰り返し呼べる。
+pub struct RealFft {
+    setup: FFTSetup,
+    log2n: usize,
+    real: Vec<f32>,
+    imag: Vec<f32>,
+}
+
+// Safety: セットアップは読み取り専用のテーブル。作業領域は &mut self でのみ触る
+unsafe impl Send for RealFft {}
+
+impl RealFft {
+    /// Create an FFT of `size` points (power of two)
+    pub fn new(size: usize) -> Option<Self> {
+        if size < 4 || !size.is_power_of_two() {
+            return None;
+        }
+        let log2n = size.trailing_zeros() as usize;
+        let setup = unsafe { vDSP_create_fftsetup(log2n, FFT_RADIX2) };
+        if setup.is_null() {
+            return None;
+        }
+        Some(Self {
+            setup,
+            log2n,
+            real: vec![0.0; size / 2],
+            imag: vec![0.0; size / 2],
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        1 << self.log2n
+    }
+
+    /// Amplitude spectrum of `input` (`size` samples, already windowed)
+    ///
+    /// `out[k]` (k < size/2) is |X[k]| normalized so a full-scale sine reads 1.0
+    /// with a rectangular window. DC is bin 0; Nyquist is dropped.
+    pub fn magnitudes(&mut self, input: &[f32], out: &mut [f32]) {
+        let half = self.size() / 2;
+        if input.len() < self.size() || out.len() < half {
+            return;
+        }
+        // 偶数サンプルを実部、奇数サンプルを虚部に詰める（vDSP_ctoz 相当）
+        for i in 0..half {
+            self.real[i] = input[2 * i];
+            self.imag[i] = input[2 * i + 1];
+        }
+        let split = DSPSplitComplex {
+            realp: self.real.as_mut_ptr(),
+            imagp: self.imag.as_mut_ptr(),
+        };
+        unsafe {
+            vDSP_fft_zrip(self.setup, &split, 1, self.log2n, FFT_DIRECTION_FORWARD);
+            // imagp[0] は Nyquist 成分なので DC の計算から外す
+            self.imag[0] = 0.0;
+            vDSP_zvmags(&split, 1, out.as_mut_ptr(), 1, half);
+        }
+        // zrip の出力は 2 倍、片側スペクトルなので 2/N を掛けると振幅になる
+        let scale = 1.0 / self.size() as f32;
+        for m in &mut out[..half] {
+            *m = m.sqrt() * scale;
+        }
+    }
+}
+
+impl Drop for RealFft {
+    fn drop(&mut self) {
+        unsafe { vDSP_destroy_fftsetup(self.setup) };
+    }
+}
+
+/// Cascaded biquad filter (vDSP_biquad)
+///
+/// セットアップは係数のみを持つので、同じ係数で複数チャンネルを処理する場合は
+/// チャンネルごとに [`Biquad::delay_len`] 長の遅延バッファを用意する。
+pub struct Biquad {
+    setup: vDSP_biquad_Setup,
+    sections: usize,
+}
+
+// Safety: セットアップは係数の入れ物で、スレッド固有の状態を持たない
+unsafe impl Send for Biquad {}
+unsafe impl Sync for Biquad {}
+
+impl Biquad {
+    /// Create a cascade from `[b0, b1, b2, a1, a2]` per section
+    pub fn new(coeffs: &[f64]) -> Option<Self> {
+        let sections = coeffs.len() / 5;
+        if sections == 0 || coeffs.len() != sections * 5 {
+            return None;
+        }
+        let setup = unsafe { vDSP_biquad_CreateSetup(coeffs.as_ptr(), sections) };
+        if setup.is_null() {
+            return None;
+        }
+        Some(Self { setup, sections })
+    }
+
+    pub fn sections(&self) -> usize {
+        self.sections
+    }
+
+    /// Delay-line length needed per channel
+    pub fn delay_len(&self) -> usize {
+        2 * self.sections + 2
+    }
+
+    /// Replace all coefficients (realtime-safe)
+    pub fn set_coefficients(&mut self, coeffs: &[f64]) {
+        if coeffs.len() != self.sections * 5 {
+            return;
+        }
+        unsafe {
+            vDSP_biquad_SetCoefficientsDouble(self.setup, coeffs.as_ptr(), 0, self.sections);
+        }
+    }
+
+    /// Filter `buf` in-place using the channel's delay line
+    pub fn process(&self, delay: &mut [f32], buf: &mut [f32]) {
+        if buf.is_empty() || delay.len() < self.delay_len() {
+            return;
+        }
+        unsafe {
+            vDSP_biquad(
+                self.setup,
+                delay.as_mut_ptr(),
+                buf.as_ptr(),
+                1,
+                buf.as_mut_ptr(),
+                1,
+                buf.len(),
+            );
+        }
+    }
+}
+
+impl Drop for Biquad {
+    fn drop(&mut self) {
+        unsafe { vDSP_biquad_DestroySetup(self.setup) };
+    }
 }
 
 /// Safe wrapper for vDSP operations
@@ -251,6 +480,29 @@ impl VDsp {
         }
     }
 
+    /// Mix with a linear gain ramp: out = out + input * ramp(start -> end)
+    /// Used for click-free gain changes (ramp ends exactly at `end` on the next block)
+    #[inline]
+    pub fn mix_add_ramp(input: &[f32], start: f32, end: f32, output: &mut [f32]) {
+        let len = input.len().min(output.len());
+        if len == 0 {
+            return;
+        }
+        let mut ramp_start = start;
+        let step = (end - start) / len as f32;
+        unsafe {
+            vDSP_vrampmuladd(
+                input.as_ptr(),
+                1,
+                &mut ramp_start,
+                &step,
+                output.as_mut_ptr(),
+                1,
+                len,
+            );
+        }
+    }
+
     /// Apply gain to a buffer in-place: buf = buf * gain
     #[inline]
     pub fn apply_gain(buf: &mut [f32], gain: f32) {
@@ -331,6 +583,19 @@ impl VDsp {
         }
     }
 
+    /// Row-major matrix product: out (m×n) = a (m×p) · b (p×n)
+    ///
+    /// Does nothing if a slice is shorter than its shape.
+    #[inline]
+    pub fn matrix_multiply(a: &[f32], b: &[f32], out: &mut [f32], m: usize, n: usize, p: usize) {
+        if m == 0 || n == 0 || p == 0 || a.len() < m * p || b.len() < p * n || out.len() < m * n {
+            return;
+        }
+        unsafe {
+            vDSP_mmul(a.as_ptr(), 1, b.as_ptr(), 1, out.as_mut_ptr(), 1, m, n, p);
+        }
+    }
+
     /// Clip buffer values to [low, high] range - hardware accelerated
     /// This is used for clip protection to prevent digital distortion
     #[inline]